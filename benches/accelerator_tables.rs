@@ -0,0 +1,59 @@
+//! Times finding functions by name in libraries with and without DWARF
+//! accelerator tables, built from the same source by
+//! `fixtures/accelerator/build.sh`.
+//!
+//! Run with `cargo bench --bench accelerator_tables`. With a table, only the
+//! functions with the name are read, rather than every function in the
+//! library, so the lookups in the indexed libraries should be several times
+//! faster.
+
+use std::{
+    hint::black_box,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use declaration_site::declaration_by_name_in;
+
+const LIBRARIES: [(&str, &str); 3] = [
+    ("without tables", "libaccelerator_fixture_unindexed.so"),
+    (".gdb_index", "libaccelerator_fixture_gdb_index.so"),
+    (".debug_names", "libaccelerator_fixture.so"),
+];
+
+const NAMES: [&str; 3] = [
+    "accelerator_fixture::first",
+    "accelerator_fixture::m23::f5",
+    "accelerator_fixture::last",
+];
+
+const RUNS: usize = 50;
+
+/// The median time taken to find each of [`NAMES`] in the library at `path`.
+fn median_lookup_time(path: &Path) -> Duration {
+    let mut times = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            for name in NAMES {
+                let site = declaration_by_name_in(path, name).unwrap();
+                assert!(black_box(site).is_some(), "`{name}` in {}", path.display());
+            }
+            start.elapsed() / NAMES.len() as u32
+        })
+        .collect::<Vec<_>>();
+    times.sort();
+    times[RUNS / 2]
+}
+
+fn main() {
+    let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/accelerator");
+    let mut baseline = None;
+    for (description, file) in LIBRARIES {
+        let time = median_lookup_time(&directory.join(file));
+        let baseline = *baseline.get_or_insert(time);
+        println!(
+            "{description:>14}: {time:>10.1?} per lookup ({:.1}x)",
+            baseline.as_secs_f64() / time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,35 @@
+/*
+ * Looks up a function in a binary or debug file using the C bindings.
+ *
+ * Build and run from the repository root with:
+ *   cargo build -p declaration_site_capi
+ *   cc capi/examples/lookup.c -Icapi/include -Ltarget/debug -ldeclaration_site_capi -o target/lookup
+ *   LD_LIBRARY_PATH=target/debug ./target/lookup target/debug/examples/functions functions::function_to_find
+ */
+#include <stdio.h>
+
+#include "declaration_site.h"
+
+int main(int argc, char **argv) {
+    if (argc != 3) {
+        fprintf(stderr, "usage: %s FILE NAME\n", argv[0]);
+        return 2;
+    }
+    DeclSiteC site;
+    int32_t result = decl_site_lookup_in(argv[1], argv[2], &site);
+    switch (result) {
+    case DECL_SITE_OK:
+        printf("%s:%u\n", site.file, site.line);
+        decl_site_free(&site);
+        return 0;
+    case DECL_SITE_NOT_FOUND:
+        fprintf(stderr, "not found\n");
+        return 1;
+    case DECL_SITE_NO_DEBUG_INFO:
+        fprintf(stderr, "no debug info\n");
+        return 1;
+    default:
+        fprintf(stderr, "error %d\n", result);
+        return 2;
+    }
+}
@@ -0,0 +1,33 @@
+/* C bindings for the declaration_site crate. See capi/src/lib.rs for details. */
+#ifndef DECLARATION_SITE_H
+#define DECLARATION_SITE_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DECL_SITE_OK 0
+#define DECL_SITE_NOT_FOUND 1
+#define DECL_SITE_NO_DEBUG_INFO 2
+#define DECL_SITE_INVALID_ARGUMENT 3
+#define DECL_SITE_IO_ERROR 4
+#define DECL_SITE_INVALID_PATH 5
+#define DECL_SITE_PANIC 6
+
+typedef struct DeclSiteC {
+    /* Owned by the library. Release using decl_site_free */
+    char *file;
+    uint32_t line;
+} DeclSiteC;
+
+int32_t decl_site_lookup(const char *name, DeclSiteC *out);
+int32_t decl_site_lookup_in(const char *path, const char *name, DeclSiteC *out);
+void decl_site_free(DeclSiteC *site);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
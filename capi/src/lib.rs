@@ -0,0 +1,197 @@
+//! C bindings for [`declaration_site`], for finding the declaration sites of
+//! Rust functions from non-Rust hosts.
+//!
+//! This is a separate crate so that `declaration_site` itself can continue to
+//! forbid `unsafe` code. The C header is in `include/declaration_site.h`, and
+//! `examples/lookup.c` shows its use.
+//!
+//! All functions return one of the `DECL_SITE_*` status codes. On success,
+//! the [`DeclSiteC`] pointed to by `out` is filled in, and must later be
+//! released using [`decl_site_free`]. Panics don't unwind into the caller,
+//! and are reported as [`DECL_SITE_PANIC`].
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use declaration_site::{
+    declaration_by_name_in, for_functions_in_file, try_declaration_by_name, DeclarationError,
+};
+use declaration_site::{DeclarationSite, IterationControl};
+
+/// The lookup succeeded, and `out` has been filled in.
+pub const DECL_SITE_OK: i32 = 0;
+/// No function with the given name was found.
+pub const DECL_SITE_NOT_FOUND: i32 = 1;
+/// The function was found, but has no source locations, or there was no debug
+/// info to search.
+pub const DECL_SITE_NO_DEBUG_INFO: i32 = 2;
+/// A pointer argument was null, or a string was not valid UTF-8.
+pub const DECL_SITE_INVALID_ARGUMENT: i32 = 3;
+/// The file could not be read or parsed.
+pub const DECL_SITE_IO_ERROR: i32 = 4;
+/// The function was found, but the path of its declaration site contains a
+/// nul byte, so can't be returned as a C string.
+pub const DECL_SITE_INVALID_PATH: i32 = 5;
+/// The lookup panicked. The panic message is printed by the panic hook, as
+/// for any other panic.
+pub const DECL_SITE_PANIC: i32 = 6;
+
+/// A declaration site, as returned to C.
+#[repr(C)]
+pub struct DeclSiteC {
+    /// The source file, as a nul terminated string. This is owned by this
+    /// library, and is released by [`decl_site_free`].
+    pub file: *mut c_char,
+    /// The line number, starting from 1.
+    pub line: u32,
+}
+
+/// Find the declaration site of the currently loaded function with the given
+/// name.
+///
+/// See [`declaration_site::try_declaration_by_name`].
+///
+/// # Safety
+///
+/// `name` must be null or a valid nul terminated string, and `out` must be
+/// null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn decl_site_lookup(name: *const c_char, out: *mut DeclSiteC) -> i32 {
+    catch_panics(|| {
+        // SAFETY: Guaranteed by the caller
+        let name = match unsafe { str_arg(name) } {
+            Some(it) => it,
+            None => return DECL_SITE_INVALID_ARGUMENT,
+        };
+        if out.is_null() {
+            return DECL_SITE_INVALID_ARGUMENT;
+        }
+        match try_declaration_by_name(name) {
+            // SAFETY: `out` is non-null, and valid for writes by the caller's guarantee
+            Ok(site) => unsafe { write_site(site, out) },
+            Err(
+                DeclarationError::MissingLines
+                | DeclarationError::InlinedInto(_)
+                | DeclarationError::NoSearchTargets { .. }
+                | DeclarationError::NoDebugInfo { .. },
+            ) => DECL_SITE_NO_DEBUG_INFO,
+            Err(_) => DECL_SITE_NOT_FOUND,
+        }
+    })
+}
+
+/// Find the declaration site of the function with the given name in the binary
+/// or debug file at `path`.
+///
+/// Names are matched as by [`decl_site_lookup`], so closures, `Type::method`
+/// spellings and `#[no_mangle]` symbols can be found. See
+/// [`declaration_site::declaration_by_name_in`].
+///
+/// # Safety
+///
+/// `path` and `name` must each be null or a valid nul terminated string, and
+/// `out` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn decl_site_lookup_in(
+    path: *const c_char,
+    name: *const c_char,
+    out: *mut DeclSiteC,
+) -> i32 {
+    catch_panics(|| {
+        // SAFETY: Guaranteed by the caller
+        let (path, name) = match unsafe { (str_arg(path), str_arg(name)) } {
+            (Some(path), Some(name)) => (path, name),
+            _ => return DECL_SITE_INVALID_ARGUMENT,
+        };
+        if out.is_null() {
+            return DECL_SITE_INVALID_ARGUMENT;
+        }
+        match declaration_by_name_in(path, name) {
+            // SAFETY: `out` is non-null, and valid for writes by the caller's guarantee
+            Ok(Some(site)) => unsafe { write_site(site, out) },
+            // Only find why nothing was found once the lookup has failed
+            Ok(None) if !has_functions(path) => DECL_SITE_NO_DEBUG_INFO,
+            Ok(None) => DECL_SITE_NOT_FOUND,
+            Err(_) => DECL_SITE_IO_ERROR,
+        }
+    })
+}
+
+/// Whether any function can be read from the binary or debug file at `path`.
+fn has_functions(path: &str) -> bool {
+    let mut any_functions = false;
+    let _ = for_functions_in_file(path, |_| {
+        any_functions = true;
+        IterationControl::Break
+    });
+    any_functions
+}
+
+/// Release the string owned by `site`, which must have been filled in by a
+/// successful lookup. `site.file` is set to null, so calling this twice is
+/// harmless.
+///
+/// # Safety
+///
+/// `site` must be null, or point to a [`DeclSiteC`] which was filled in by this
+/// library, or whose `file` is null.
+#[no_mangle]
+pub unsafe extern "C" fn decl_site_free(site: *mut DeclSiteC) {
+    catch_panics(|| {
+        // SAFETY: Guaranteed by the caller
+        let site = match unsafe { site.as_mut() } {
+            Some(it) => it,
+            None => return DECL_SITE_OK,
+        };
+        let file = std::mem::replace(&mut site.file, ptr::null_mut());
+        if !file.is_null() {
+            // SAFETY: `file` was created by `CString::into_raw` in `write_site`
+            drop(unsafe { CString::from_raw(file) });
+        }
+        DECL_SITE_OK
+    });
+}
+
+/// Run `f`, returning [`DECL_SITE_PANIC`] if it panics, as unwinding out of
+/// an `extern "C"` function aborts the process.
+///
+/// Nothing is shared with the caller other than through the pointers it
+/// passed in, which are only written once a lookup has succeeded, so they are
+/// never left half written.
+fn catch_panics(f: impl FnOnce() -> i32) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(DECL_SITE_PANIC)
+}
+
+/// # Safety
+///
+/// `arg` must be null or a valid nul terminated string, which lives for `'a`
+unsafe fn str_arg<'a>(arg: *const c_char) -> Option<&'a str> {
+    if arg.is_null() {
+        return None;
+    }
+    // SAFETY: Guaranteed by the caller
+    unsafe { CStr::from_ptr(arg) }.to_str().ok()
+}
+
+/// # Safety
+///
+/// `out` must be non-null and valid for writes
+unsafe fn write_site(site: DeclarationSite, out: *mut DeclSiteC) -> i32 {
+    // Paths can't contain nul bytes on any platform we support, but debug info
+    // could still have one, which can't be handed out without truncating it
+    let file = match CString::new(site.file) {
+        Ok(it) => it,
+        Err(_) => return DECL_SITE_INVALID_PATH,
+    };
+    let site = DeclSiteC {
+        file: file.into_raw(),
+        line: site.line,
+    };
+    // SAFETY: Guaranteed by the caller
+    unsafe { out.write(site) };
+    DECL_SITE_OK
+}
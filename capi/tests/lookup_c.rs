@@ -0,0 +1,147 @@
+//! Builds `examples/lookup.c` against the C bindings, and checks that it finds
+//! the functions of this test's executable as the Rust API does.
+
+#![cfg(unix)]
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use declaration_site::declaration_by_name_in;
+use declaration_site_capi::{
+    DECL_SITE_INVALID_ARGUMENT, DECL_SITE_INVALID_PATH, DECL_SITE_IO_ERROR, DECL_SITE_NOT_FOUND,
+    DECL_SITE_NO_DEBUG_INFO, DECL_SITE_OK, DECL_SITE_PANIC,
+};
+
+#[inline(never)]
+fn c_target() -> u32 {
+    std::hint::black_box(1)
+}
+
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn c_target_entry() -> u32 {
+    std::hint::black_box(2)
+}
+
+struct Target(u32);
+
+impl Default for Target {
+    #[inline(never)]
+    fn default() -> Self {
+        Target(std::hint::black_box(3))
+    }
+}
+
+fn capi_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The compiled `lookup.c`, and the directory of the library it links to.
+struct Lookup {
+    executable: PathBuf,
+    library_dir: PathBuf,
+}
+
+impl Lookup {
+    /// Build the bindings, then compile `lookup.c` against them.
+    fn build() -> Self {
+        let target_dir = capi_dir().join("../target/capi-harness");
+        let status = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+            .args(["build", "--quiet", "--lib"])
+            .arg("--manifest-path")
+            .arg(capi_dir().join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .status()
+            .expect("Should have run cargo");
+        assert!(status.success(), "building the bindings failed");
+        let library_dir = target_dir.join("debug");
+        let executable = target_dir.join("lookup");
+        let status = Command::new(env::var_os("CC").unwrap_or_else(|| "cc".into()))
+            .arg(capi_dir().join("examples/lookup.c"))
+            .arg("-I")
+            .arg(capi_dir().join("include"))
+            .arg("-L")
+            .arg(&library_dir)
+            .args(["-ldeclaration_site_capi", "-o"])
+            .arg(&executable)
+            .status()
+            .expect("Should have run the C compiler");
+        assert!(status.success(), "compiling lookup.c failed");
+        Lookup {
+            executable,
+            library_dir,
+        }
+    }
+
+    /// Run `lookup.c` to find `name` in `file`.
+    fn run(&self, file: &Path, name: &str) -> Output {
+        Command::new(&self.executable)
+            .arg(file)
+            .arg(name)
+            .env("LD_LIBRARY_PATH", &self.library_dir)
+            .env("DYLD_LIBRARY_PATH", &self.library_dir)
+            .output()
+            .expect("Should have run lookup")
+    }
+}
+
+#[test]
+fn lookup_c_finds_functions_as_rust_does() {
+    let lookup = Lookup::build();
+    let exe = env::current_exe().unwrap();
+    let names = [
+        format!("{}::c_target", module_path!()),
+        // `#[no_mangle]` functions by their symbol
+        "c_target_entry".to_owned(),
+        // Trait methods spelled `Type::method`
+        format!("{}::Target::default", module_path!()),
+    ];
+    for name in &names {
+        let expected = declaration_by_name_in(&exe, name)
+            .unwrap()
+            .unwrap_or_else(|| panic!("Should have found `{name}`"));
+        let output = lookup.run(&exe, name);
+        let printed = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "for `{name}`: {output:?}");
+        assert_eq!(printed, format!("{expected}\n"), "for `{name}`");
+        assert!(printed.contains("lookup_c.rs:"), "for `{name}`: {printed}");
+    }
+
+    let output = lookup.run(&exe, &format!("{}::missing", module_path!()));
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "not found\n");
+    let output = lookup.run(Path::new("does/not/exist"), "c_target_entry");
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error 4\n");
+
+    c_target();
+    c_target_entry();
+    assert_eq!(Target::default().0, 3);
+}
+
+#[test]
+fn header_has_every_status_code() {
+    let header = std::fs::read_to_string(capi_dir().join("include/declaration_site.h")).unwrap();
+    let codes = [
+        ("DECL_SITE_OK", DECL_SITE_OK),
+        ("DECL_SITE_NOT_FOUND", DECL_SITE_NOT_FOUND),
+        ("DECL_SITE_NO_DEBUG_INFO", DECL_SITE_NO_DEBUG_INFO),
+        ("DECL_SITE_INVALID_ARGUMENT", DECL_SITE_INVALID_ARGUMENT),
+        ("DECL_SITE_IO_ERROR", DECL_SITE_IO_ERROR),
+        ("DECL_SITE_INVALID_PATH", DECL_SITE_INVALID_PATH),
+        ("DECL_SITE_PANIC", DECL_SITE_PANIC),
+    ];
+    let defined = header
+        .lines()
+        .filter_map(|line| line.strip_prefix("#define DECL_SITE_"))
+        .count();
+    assert_eq!(defined, codes.len());
+    for (name, code) in codes {
+        let define = format!("#define {name} {code}\n");
+        assert!(header.contains(&define), "{define}");
+    }
+}
@@ -0,0 +1,156 @@
+//! Summarising how far the declaration site of a chosen function can be
+//! trusted, from `DeclarationChoice::confidence`.
+
+use core::fmt;
+
+use crate::MatchRelaxation;
+
+/// How far the declaration site of a `DeclarationChoice` can be trusted, for
+/// deciding how to present it.
+///
+/// This is the lowest of the limits of the choice's `confidence_factors`, or
+/// [`Exact`](Self::Exact) if there are none. The limit of each factor is:
+///
+/// | Factor | Limit |
+/// |--------|-------|
+/// | [`Spelling`](ConfidenceFactor::Spelling) | [`High`](Self::High) |
+/// | [`DefaultImpl`](ConfidenceFactor::DefaultImpl) | [`High`](Self::High) |
+/// | [`OtherCrate`](ConfidenceFactor::OtherCrate) | [`High`](Self::High) |
+/// | [`LowestLine`](ConfidenceFactor::LowestLine) | [`High`](Self::High) |
+/// | [`BinAndLib`](ConfidenceFactor::BinAndLib) | [`High`](Self::High) |
+/// | [`UnverifiedDebugFile`](ConfidenceFactor::UnverifiedDebugFile) | [`High`](Self::High) |
+/// | [`Relaxed`](ConfidenceFactor::Relaxed)`(Conservative)` | [`Medium`](Self::Medium) |
+/// | [`Inlinee`](ConfidenceFactor::Inlinee) | [`Medium`](Self::Medium) |
+/// | [`Ambiguous`](ConfidenceFactor::Ambiguous) | [`Medium`](Self::Medium) |
+/// | [`OtherVersions`](ConfidenceFactor::OtherVersions) | [`Medium`](Self::Medium) |
+/// | [`Relaxed`](ConfidenceFactor::Relaxed)`(Aggressive)` | [`Low`](Self::Low) |
+/// | [`Heuristic`](ConfidenceFactor::Heuristic) | [`Low`](Self::Low) |
+/// | [`NoLines`](ConfidenceFactor::NoLines) | [`Low`](Self::Low) |
+/// | [`MismatchedDebugFile`](ConfidenceFactor::MismatchedDebugFile) | [`Low`](Self::Low) |
+///
+/// This mapping only changes in a major version. New factors may be added
+/// (so [`ConfidenceFactor`] is `#[non_exhaustive]`), but only for cases which
+/// were previously [`Exact`](Self::Exact).
+///
+/// The variants are ordered from the least to the most confident, so results
+/// can be compared or filtered with `confidence >= Confidence::High`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Confidence {
+    /// The site is a guess, such as from a name which only matched after
+    /// ignoring its leading segments, or from a debug file of another build
+    Low,
+    /// The site is probably right, but another site could have been meant,
+    /// such as for one of several closures in the same function
+    Medium,
+    /// The site is right, but was found in a less direct way, such as from a
+    /// copy of the function compiled into another crate
+    High,
+    /// The function matched the name exactly, and its site is the first line
+    /// of its line table, in a file which was verified (or is the binary)
+    Exact,
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+            Confidence::Exact => "exact",
+        })
+    }
+}
+
+/// Why a `DeclarationChoice` isn't [`Confidence::Exact`], from
+/// `DeclarationChoice::confidence_factors`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ConfidenceFactor {
+    /// The name matched with a wildcard or another spelling, or as the body
+    /// of an `async fn`'s future
+    Spelling,
+    /// The function is the default body of the trait method, rather than an
+    /// implementation for the type
+    DefaultImpl,
+    /// The name only matched with this [`MatchRelaxation`]
+    Relaxed(MatchRelaxation),
+    /// The function was compiled in a unit of another crate
+    OtherCrate,
+    /// The line is the lowest line of the function
+    /// ([`LineProvenance::LineTableMin`](crate::LineProvenance::LineTableMin))
+    LowestLine,
+    /// The line is from an inlined copy of the function
+    /// ([`LineProvenance::Inlinee`](crate::LineProvenance::Inlinee))
+    Inlinee,
+    /// The line is from the crate root, which may not be the macro's
+    /// invocation
+    /// ([`LineProvenance::Heuristic`](crate::LineProvenance::Heuristic))
+    Heuristic,
+    /// The function has no source locations, so has no site
+    NoLines,
+    /// Other functions, declared elsewhere, matched the name as well as the
+    /// chosen function, such as the other closures of the same function. See
+    /// `DeclarationChoice::ambiguous`
+    Ambiguous,
+    /// Other versions of the function's crate are linked, see
+    /// `DeclarationChoice::other_versions`
+    OtherVersions,
+    /// Both the bin and lib targets of the package contain the function, see
+    /// `DeclarationChoice::bin_and_lib`
+    BinAndLib,
+    /// The function was found in a separate debug file whose debug ids
+    /// couldn't be compared with its binary's
+    /// ([`PairingStatus::Unverifiable`](crate::PairingStatus::Unverifiable))
+    UnverifiedDebugFile,
+    /// The function was found in a separate debug file from a different build
+    /// to its binary
+    /// ([`PairingStatus::Mismatched`](crate::PairingStatus::Mismatched))
+    MismatchedDebugFile,
+}
+
+impl ConfidenceFactor {
+    /// The highest [`Confidence`] of a choice with this factor, as described
+    /// in [`Confidence`].
+    pub fn limit(self) -> Confidence {
+        match self {
+            ConfidenceFactor::Spelling
+            | ConfidenceFactor::DefaultImpl
+            | ConfidenceFactor::OtherCrate
+            | ConfidenceFactor::LowestLine
+            | ConfidenceFactor::BinAndLib
+            | ConfidenceFactor::UnverifiedDebugFile => Confidence::High,
+            ConfidenceFactor::Relaxed(MatchRelaxation::None | MatchRelaxation::Conservative)
+            | ConfidenceFactor::Inlinee
+            | ConfidenceFactor::Ambiguous
+            | ConfidenceFactor::OtherVersions => Confidence::Medium,
+            ConfidenceFactor::Relaxed(MatchRelaxation::Aggressive)
+            | ConfidenceFactor::Heuristic
+            | ConfidenceFactor::NoLines
+            | ConfidenceFactor::MismatchedDebugFile => Confidence::Low,
+        }
+    }
+}
+
+impl fmt::Display for ConfidenceFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfidenceFactor::Spelling => f.write_str("matched with another spelling"),
+            ConfidenceFactor::DefaultImpl => f.write_str("the trait's default method"),
+            ConfidenceFactor::Relaxed(relaxation) => {
+                write!(f, "matched with {relaxation:?} relaxation")
+            }
+            ConfidenceFactor::OtherCrate => f.write_str("compiled in another crate"),
+            ConfidenceFactor::LowestLine => f.write_str("the lowest line of the function"),
+            ConfidenceFactor::Inlinee => f.write_str("line from an inlined copy"),
+            ConfidenceFactor::Heuristic => f.write_str("line from the crate root"),
+            ConfidenceFactor::NoLines => f.write_str("no source locations"),
+            ConfidenceFactor::Ambiguous => f.write_str("other functions also matched"),
+            ConfidenceFactor::OtherVersions => f.write_str("several versions of the crate"),
+            ConfidenceFactor::BinAndLib => f.write_str("in both the bin and lib"),
+            ConfidenceFactor::UnverifiedDebugFile => f.write_str("debug file not verified"),
+            ConfidenceFactor::MismatchedDebugFile => f.write_str("debug file from another build"),
+        }
+    }
+}
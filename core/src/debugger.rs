@@ -0,0 +1,148 @@
+//! Creating debugger commands which set breakpoints at declaration sites.
+
+use alloc::{borrow::ToOwned, format, string::String};
+
+use crate::DeclarationSite;
+
+/// A debugger (or debugger interface) to create breakpoint commands for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Debugger {
+    /// The GDB command line, such as `break src/lib.rs:42`
+    Gdb,
+    /// The LLDB command line, such as
+    /// `breakpoint set --file src/lib.rs --line 42`
+    Lldb,
+    /// GDB's [machine interface](https://sourceware.org/gdb/current/onlinedocs/gdb.html/GDB_002fMI.html),
+    /// used by editors and IDEs, such as
+    /// `-break-insert --source src/lib.rs --line 42`
+    GdbMi,
+}
+
+impl DeclarationSite {
+    /// Create a command which sets a breakpoint at this declaration site in
+    /// `debugger`.
+    ///
+    /// The file is quoted if needed, such as if it contains spaces. Relative
+    /// paths are passed through unchanged, so are resolved by the debugger
+    /// (usually against the compilation directory).
+    ///
+    /// If the file isn't available where the debugger is running (for
+    /// example, if the paths were remapped at compile time), use
+    /// [`function_breakpoint_spec`] instead.
+    ///
+    /// ```rust
+    /// # use declaration_site_core::{DeclarationSite, Debugger};
+    /// let site = DeclarationSite { file: "src/foo.rs".into(), line: 42 };
+    /// assert_eq!(site.breakpoint_spec(Debugger::Gdb), "break src/foo.rs:42");
+    /// assert_eq!(
+    ///     site.breakpoint_spec(Debugger::Lldb),
+    ///     "breakpoint set --file src/foo.rs --line 42"
+    /// );
+    /// assert_eq!(
+    ///     site.breakpoint_spec(Debugger::GdbMi),
+    ///     "-break-insert --source src/foo.rs --line 42"
+    /// );
+    ///
+    /// let site = DeclarationSite { file: r"C:\My Projects\game\src\main.rs".into(), line: 7 };
+    /// assert_eq!(
+    ///     site.breakpoint_spec(Debugger::Gdb),
+    ///     r#"break "C:\My Projects\game\src\main.rs":7"#
+    /// );
+    /// assert_eq!(
+    ///     site.breakpoint_spec(Debugger::Lldb),
+    ///     r#"breakpoint set --file "C:\\My Projects\\game\\src\\main.rs" --line 7"#
+    /// );
+    /// assert_eq!(
+    ///     site.breakpoint_spec(Debugger::GdbMi),
+    ///     r#"-break-insert --source "C:\\My Projects\\game\\src\\main.rs" --line 7"#
+    /// );
+    /// ```
+    pub fn breakpoint_spec(&self, debugger: Debugger) -> String {
+        let line = self.line;
+        match debugger {
+            Debugger::Gdb => format!("break {}:{line}", gdb_quote(&self.file)),
+            Debugger::Lldb => format!(
+                "breakpoint set --file {} --line {line}",
+                escaped_quote(&self.file)
+            ),
+            Debugger::GdbMi => format!(
+                "-break-insert --source {} --line {line}",
+                escaped_quote(&self.file)
+            ),
+        }
+    }
+}
+
+/// Create a command which sets a breakpoint on the function with the given
+/// (unmangled) name in `debugger`.
+///
+/// Unlike [`DeclarationSite::breakpoint_spec`], this doesn't need the source
+/// file to be available.
+///
+/// ```rust
+/// # use declaration_site_core::{function_breakpoint_spec, Debugger};
+/// assert_eq!(
+///     function_breakpoint_spec("my_game::update", Debugger::Gdb),
+///     "break my_game::update"
+/// );
+/// assert_eq!(
+///     function_breakpoint_spec("my_game::update", Debugger::Lldb),
+///     "breakpoint set --name my_game::update"
+/// );
+/// assert_eq!(
+///     function_breakpoint_spec("<my_game::Player as my_game::Update>::update", Debugger::Gdb),
+///     "break '<my_game::Player as my_game::Update>::update'"
+/// );
+/// ```
+pub fn function_breakpoint_spec(name: &str, debugger: Debugger) -> String {
+    match debugger {
+        Debugger::Gdb if needs_quoting(name) => format!("break '{name}'"),
+        Debugger::Gdb => format!("break {name}"),
+        Debugger::Lldb => format!("breakpoint set --name {}", escaped_quote(name)),
+        Debugger::GdbMi => format!("-break-insert --function {}", escaped_quote(name)),
+    }
+}
+
+fn needs_quoting(text: &str) -> bool {
+    text.is_empty()
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ',' | '`' | '$'))
+}
+
+/// Quote `path` for a GDB linespec, which doesn't support escapes, but
+/// accepts either single or double quotes.
+fn gdb_quote(path: &str) -> String {
+    // Unquoted Windows drive letters are handled by GDB itself, but a path with
+    // a drive letter and spaces must be quoted for the colon to not be taken as
+    // the line separator
+    if !needs_quoting(path) {
+        path.to_owned()
+    } else if path.contains('"') {
+        format!("'{path}'")
+    } else {
+        format!("\"{path}\"")
+    }
+}
+
+/// Quote `text` in double quotes with backslash escapes, as used by LLDB and
+/// GDB/MI.
+fn escaped_quote(text: &str) -> String {
+    if !needs_quoting(text) {
+        return text.to_owned();
+    }
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
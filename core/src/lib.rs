@@ -0,0 +1,53 @@
+//! The data types of [declaration_site](https://docs.rs/declaration_site),
+//! for crates which pass its results around without finding them, such as
+//! firmware or WASM code which receives declaration sites from a host.
+//!
+//! This crate is `#![no_std]`. With `default-features = false`, only the
+//! enums describing results (such as [`Confidence`]) are available. The
+//! `alloc` feature (enabled by default) adds the types which own their data,
+//! such as [`DeclarationSite`], and the breakpoint commands of [`Debugger`].
+//! The `serde` feature implements `Serialize` and `Deserialize` for every
+//! type.
+//!
+//! `declaration_site` re-exports every item of this crate, so code which uses
+//! both doesn't need to depend on this crate directly. Its `DeclarationChoice`
+//! isn't here, as it holds the compilation units and inlined calls read from
+//! the debug info, but its [`confidence`](Confidence),
+//! [`confidence_factors`](ConfidenceFactor),
+//! [`relaxation`](MatchRelaxation), [`provenance`](LineProvenance) and
+//! [`debug_pairing`](PairingStatus) are.
+//!
+//! ```rust
+//! use declaration_site_core::{Confidence, ConfidenceFactor, DeclarationSite};
+//!
+//! let site = DeclarationSite { file: "src/main.rs".into(), line: 12 };
+//! assert_eq!(site.to_string(), "src/main.rs:12");
+//! assert_eq!(ConfidenceFactor::Inlinee.limit(), Confidence::Medium);
+//! ```
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod confidence;
+#[cfg(feature = "alloc")]
+mod debugger;
+mod matching;
+mod pairing;
+mod provenance;
+mod site;
+#[cfg(feature = "alloc")]
+mod verbatim;
+
+pub use confidence::{Confidence, ConfidenceFactor};
+#[cfg(feature = "alloc")]
+pub use debugger::{function_breakpoint_spec, Debugger};
+pub use matching::MatchRelaxation;
+pub use pairing::PairingStatus;
+pub use provenance::LineProvenance;
+#[cfg(feature = "alloc")]
+pub use site::DeclarationSite;
+pub use site::DeclarationSiteError;
+#[cfg(feature = "alloc")]
+pub use verbatim::{strip_verbatim_prefix, verbatim_path};
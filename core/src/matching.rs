@@ -0,0 +1,23 @@
+//! How far the matching of names may be relaxed.
+
+/// How far matching may be relaxed when no function matches a name, such
+/// as when a newer or older rustc or demangler spells it differently.
+///
+/// Relaxed matches are approximate, so are only used if exactly one
+/// function (ignoring copies of it) matched at the least relaxed level. See
+/// `declaration_site::DeclarationSearch::relaxed`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchRelaxation {
+    /// Names must match as in `declaration_site::names_match`
+    #[default]
+    None,
+    /// Generic arguments are ignored, after
+    /// `declaration_site::canonical_name` (which removes hash suffixes), so `my_crate::Foo<u8>::bar<u16>` matches
+    /// `my_crate::Foo<T>::bar` and `my_crate::Foo::bar`
+    Conservative,
+    /// As [`Conservative`](Self::Conservative), and one name also matches
+    /// the last segments of the other, if it has at least two segments, so
+    /// `inner::Foo::bar` matches `my_crate::inner::Foo::bar`
+    Aggressive,
+}
@@ -0,0 +1,18 @@
+//! Whether a separate debug file was built with its binary.
+
+/// Whether a debug file was built with its binary, from
+/// `declaration_site::PairingReport`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PairingStatus {
+    /// The debug file and the binary have a debug id in common
+    Matched,
+    /// The debug file and the binary both have debug ids, but none are the
+    /// same, so the debug file is probably from a different build (such as a
+    /// stale dSYM or a PDB of an earlier build)
+    Mismatched,
+    /// The debug file or the binary has no debug id which can be compared,
+    /// such as an ELF file without a build id (whose debug id is a hash of
+    /// its code, which its debug file doesn't contain)
+    Unverifiable,
+}
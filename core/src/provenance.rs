@@ -0,0 +1,41 @@
+//! Where the line of a declaration site came from.
+
+use core::fmt;
+
+/// Where the line of a declaration site came from, which says how far it
+/// can be trusted, from `FunctionRecord::declaration_site_with_provenance`
+/// and `DeclarationChoice::provenance` in `declaration_site`.
+///
+/// The declaration line attributes of the debug info (`DW_AT_decl_line`)
+/// aren't exposed by the debug info parser, so every line comes from the
+/// line table, and is the line of the function's first statement rather
+/// than of its signature.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum LineProvenance {
+    /// The first entry of the function's line table in the file with the most
+    /// of its entries, which is usually the function's first line
+    LineTableFirst,
+    /// The lowest line of the function's line table in the file of its first
+    /// entry, used for the body of the future of an `async fn`. This can be
+    /// before the function, if code from elsewhere was inlined into it
+    LineTableMin,
+    /// The first entry of the line table of a copy of the function which was
+    /// inlined into another function
+    Inlinee,
+    /// The first entry of the line table in the crate root, from
+    /// `MacroSite::Invocation`, which may not be the macro's invocation
+    Heuristic,
+}
+
+impl fmt::Display for LineProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LineProvenance::LineTableFirst => "the first line of the function",
+            LineProvenance::LineTableMin => "the lowest line of the function",
+            LineProvenance::Inlinee => "the first line of an inlined copy of the function",
+            LineProvenance::Heuristic => "the first line of the function in the crate root",
+        })
+    }
+}
@@ -0,0 +1,162 @@
+//! The declaration site of a function.
+
+use core::{error::Error, fmt};
+
+#[cfg(feature = "symbolic")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String};
+
+#[cfg(feature = "symbolic")]
+use symbolic_debuginfo::{Function, LineInfo};
+
+/// A source file location, obtained from a `symbolic_debuginfo::Function`,
+/// using [`TryFrom`]/[`TryInto`] or [`from_lines`](Self::from_lines) (with
+/// the `symbolic` feature).
+///
+/// Printing this type into a terminal will often allow it to act as a link into
+/// the source code (if the working directories line up and the terminal
+/// emulator supports this feature).
+///
+/// Sites have no column: although DWARF line records can have one, the
+/// `LineInfo`s of `symbolic_debuginfo` 8 don't include it.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeclarationSite {
+    pub file: String,
+    pub line: u32,
+}
+
+#[cfg(feature = "alloc")]
+impl DeclarationSite {
+    /// The file of the site in the extended-length form of Windows paths
+    /// (`\\?\C:\...` or `\\?\UNC\server\...`), which can be read even
+    /// if it is longer than `MAX_PATH`.
+    ///
+    /// [`file`](Self::file) is never in this form when found by
+    /// `declaration_site`, as the prefix is removed (see
+    /// [`strip_verbatim_prefix`](crate::strip_verbatim_prefix)). Files which
+    /// aren't absolute Windows paths are unchanged.
+    ///
+    /// ```rust
+    /// use declaration_site_core::DeclarationSite;
+    ///
+    /// let site = DeclarationSite { file: r"C:\work\src\lib.rs".into(), line: 3 };
+    /// assert_eq!(site.verbatim_file(), r"\\?\C:\work\src\lib.rs");
+    /// ```
+    pub fn verbatim_file(&self) -> Cow<'_, str> {
+        crate::verbatim_path(&self.file)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for DeclarationSite {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}:{}", self.file, self.line)
+    }
+}
+
+/// An error returned in the [`TryFrom`] impl for `DeclarationSite`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeclarationSiteError {
+    /// The debug info for the function contains no source locations
+    MissingLines,
+}
+
+impl fmt::Display for DeclarationSiteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeclarationSiteError::MissingLines => write!(
+                f,
+                "debug info contains no source locations for this function"
+            ),
+        }
+    }
+}
+
+impl Error for DeclarationSiteError {}
+
+/// Get the site of the function, according to the debug info, as in
+/// [`DeclarationSite::from_lines`] without a primary file.
+///
+/// # Errors
+///
+/// If the function's debug info has no source locations
+#[cfg(feature = "symbolic")]
+impl<'a> TryFrom<&Function<'a>> for DeclarationSite {
+    type Error = DeclarationSiteError;
+
+    fn try_from(value: &Function<'a>) -> Result<Self, Self::Error> {
+        DeclarationSite::from_lines(&value.lines, None)
+    }
+}
+
+#[cfg(feature = "symbolic")]
+impl DeclarationSite {
+    /// The site of a function with the line records `lines`: the first line
+    /// in the file with the most of its lines.
+    ///
+    /// The lines of most functions are all in one file, in which case this is
+    /// the first line. Functions whose code comes from several files, such as
+    /// from `include!` or a macro defined in another file, are declared in
+    /// the file with the most lines, so that the file doesn't change between
+    /// builds when only the order of the lines does. If several files have
+    /// as many lines, `primary_file` (such as the crate root of the
+    /// function's compilation unit) is chosen if it is one of them, and
+    /// otherwise the first of them in `lines`.
+    ///
+    /// # Errors
+    ///
+    /// If `lines` is empty
+    pub fn from_lines(
+        lines: &[LineInfo<'_>],
+        primary_file: Option<&str>,
+    ) -> Result<Self, DeclarationSiteError> {
+        let first = lines.first().ok_or(DeclarationSiteError::MissingLines)?;
+        let mut chosen = first;
+        if lines.iter().any(|line| line.file != first.file) {
+            // The first line of each file, and the number of lines in it
+            let mut files: Vec<(&LineInfo<'_>, usize)> = Vec::new();
+            for line in lines {
+                match files.iter_mut().find(|(it, _)| it.file == line.file) {
+                    Some((_, count)) => *count += 1,
+                    None => files.push((line, 1)),
+                }
+            }
+            let most = files.iter().map(|(_, count)| *count).max().unwrap_or(0);
+            files.retain(|(_, count)| *count == most);
+            chosen = files
+                .iter()
+                .find(|(line, _)| {
+                    primary_file
+                        .is_some_and(|primary| ends_with_path(&line.file.path_str(), primary))
+                })
+                .unwrap_or(&files[0])
+                .0;
+        }
+        let file = chosen.file.path_str();
+        let file = match crate::strip_verbatim_prefix(&file) {
+            Cow::Borrowed(stripped) if stripped.len() == file.len() => file,
+            stripped => stripped.into_owned(),
+        };
+        Ok(DeclarationSite {
+            file,
+            line: chosen.line as u32,
+        })
+    }
+}
+
+/// Whether the path `path` ends with the components of `suffix`, with either
+/// separator.
+#[cfg(feature = "symbolic")]
+fn ends_with_path(path: &str, suffix: &str) -> bool {
+    fn components(path: &str) -> Vec<&str> {
+        path.rsplit(['/', '\\'])
+            .filter(|it| !it.is_empty() && *it != ".")
+            .collect()
+    }
+    let (path, suffix) = (components(path), components(suffix));
+    !suffix.is_empty() && path.len() >= suffix.len() && path[..suffix.len()] == suffix[..]
+}
@@ -0,0 +1,82 @@
+//! Converting between the usual and the extended-length ("verbatim") forms
+//! of Windows paths.
+
+use alloc::{borrow::Cow, format};
+
+/// The prefix of extended-length Windows paths.
+const VERBATIM: &str = r"\\?\";
+/// The prefix of extended-length Windows paths of network shares.
+const VERBATIM_UNC: &str = r"\\?\UNC\";
+
+/// The path `path` without the `\\?\` (or `\\?\UNC\`) prefix of an
+/// extended-length Windows path, which is how the path is usually written.
+///
+/// Debug info and the loader report paths in this form when the files were
+/// in deep directories, but most programs don't expect it, so this crate
+/// removes it from every path it reports (such as the file of a
+/// [`DeclarationSite`](crate::DeclarationSite)). Other paths are unchanged.
+///
+/// ```rust
+/// use declaration_site_core::strip_verbatim_prefix;
+///
+/// assert_eq!(strip_verbatim_prefix(r"\\?\C:\work\src\lib.rs"), r"C:\work\src\lib.rs");
+/// assert_eq!(strip_verbatim_prefix(r"\\?\UNC\server\share\src\lib.rs"), r"\\server\share\src\lib.rs");
+/// assert_eq!(strip_verbatim_prefix("src/lib.rs"), "src/lib.rs");
+/// ```
+pub fn strip_verbatim_prefix(path: &str) -> Cow<'_, str> {
+    if let Some(share) = strip_prefix_ignore_case(path, VERBATIM_UNC) {
+        return Cow::Owned(format!(r"\\{share}"));
+    }
+    match path.strip_prefix(VERBATIM) {
+        // Only drive paths are stripped, as other verbatim paths (such as of
+        // volumes, `\\?\Volume{..}\`) have no other form
+        Some(rest) if has_drive(rest) => Cow::Borrowed(rest),
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// The path `path` in the extended-length form of Windows paths, if it is
+/// an absolute Windows path (with a drive, such as `C:\work`, or of a
+/// network share, such as `\\server\share`).
+///
+/// Extended-length paths can be longer than `MAX_PATH` (260 characters), so
+/// this is the form to read files in deep directories with. Their
+/// separators must be `\`, so `/` is replaced. Other paths (including those
+/// already in this form) are unchanged.
+///
+/// ```rust
+/// use declaration_site_core::verbatim_path;
+///
+/// assert_eq!(verbatim_path(r"C:\work/src\lib.rs"), r"\\?\C:\work\src\lib.rs");
+/// assert_eq!(verbatim_path(r"\\server\share\lib.rs"), r"\\?\UNC\server\share\lib.rs");
+/// assert_eq!(verbatim_path(r"\\?\C:\work\lib.rs"), r"\\?\C:\work\lib.rs");
+/// assert_eq!(verbatim_path("/home/me/lib.rs"), "/home/me/lib.rs");
+/// ```
+pub fn verbatim_path(path: &str) -> Cow<'_, str> {
+    if path.starts_with(VERBATIM) {
+        return Cow::Borrowed(path);
+    }
+    if has_drive(path) && path[2..].starts_with(['\\', '/']) {
+        return Cow::Owned(format!("{VERBATIM}{}", path.replace('/', "\\")));
+    }
+    match path.strip_prefix(r"\\") {
+        // `\\.\` is the prefix of device paths, which have no verbatim form
+        Some(share) if !share.is_empty() && !share.starts_with(['.', '\\']) => {
+            Cow::Owned(format!("{VERBATIM_UNC}{}", share.replace('/', "\\")))
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Whether `path` starts with a drive, such as `C:`.
+fn has_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// `path` without `prefix`, ignoring the case of ASCII letters.
+fn strip_prefix_ignore_case<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = path.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &path[prefix.len()..])
+}
@@ -0,0 +1,51 @@
+//! Detects that a name is ambiguous, as two closures in the same function
+//! have the same `type_name`.
+
+use declaration_site::{
+    declaration_by_name_all, explain_declaration_by_name, explain_declaration_by_name_all,
+};
+
+fn name_of<T>(_: &T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+/// Two closures, which are the only closures in this function.
+fn operations() -> (impl Fn(u32) -> u32, impl Fn(u32) -> u32) {
+    let double = |x: u32| x * 2; // Found by this example
+    let triple = |x: u32| x * 3; // Found by this example
+    (double, triple)
+}
+
+fn main() {
+    let (double, triple) = operations();
+    assert_eq!(
+        std::hint::black_box(&double)(1) + std::hint::black_box(&triple)(1),
+        5
+    );
+
+    let name = name_of(&double);
+    assert_eq!(name, name_of(&triple));
+
+    let choice = explain_declaration_by_name(name).expect("Should have found a closure");
+    println!("{choice}");
+    assert!(choice.ambiguous);
+    assert_eq!(choice.ambiguous_with.len(), 1, "{choice}");
+    assert_ne!(choice.ambiguous_with[0].raw_name, choice.raw_name);
+
+    // Each closure is found, and each is ambiguous with the other
+    let choices = explain_declaration_by_name_all(name);
+    for choice in &choices {
+        println!("{choice}");
+        assert!(choice.ambiguous);
+    }
+    assert_eq!(choices.len(), 2);
+
+    // The sites of both closures, rather than only the first which matched
+    let sites = declaration_by_name_all(name);
+    assert_eq!(sites.len(), 2, "{sites:?}");
+    assert!(sites[0].line < sites[1].line, "{sites:?}");
+
+    // A function's name isn't ambiguous
+    let choice = explain_declaration_by_name(name_of(&operations)).unwrap();
+    assert!(!choice.ambiguous, "{choice}");
+}
@@ -0,0 +1,66 @@
+//! Finds an `async fn`, whose code is in the body of the future generated for
+//! it, rather than the function itself.
+//!
+//! Also run with `CARGO_PROFILE_DEV_OPT_LEVEL=1`, where the function and its
+//! future are only found inlined into `block_on`.
+
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    task::{Context, Poll, Waker},
+};
+
+use declaration_site::{declaration_by_name, explain_declaration_by_name, LineProvenance};
+
+pub async fn fetch_data(x: u64) -> u64 {
+    let a = std::hint::black_box(x);
+    YieldOnce(false).await;
+    let b = std::hint::black_box(a * 2);
+    YieldOnce(false).await;
+    b + 1
+}
+
+fn main() {
+    assert_eq!(block_on(fetch_data(std::hint::black_box(3))), 7);
+    let name = std::any::type_name_of_val(&fetch_data);
+    let site = declaration_by_name(name).expect("Should have found `async fn` by name");
+    println!("Found `{name}` at {site}");
+    assert!(site.file.ends_with("async_fn.rs"));
+    assert_eq!(site.line, 15);
+
+    // The line is that of the function itself, or of its copy inlined into
+    // `block_on` with optimizations
+    let choice = explain_declaration_by_name(name).expect("Should have found `async fn` by name");
+    println!("{choice}");
+    assert!(matches!(
+        choice.provenance,
+        Some(LineProvenance::LineTableFirst | LineProvenance::Inlinee)
+    ));
+}
+
+/// A future which is pending once.
+struct YieldOnce(bool);
+
+impl Future for YieldOnce {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Finds the functions in backtraces pasted from logs, from the text of the
+//! backtrace alone.
+//!
+//! The fixtures in `fixtures/backtraces` are backtraces of a panic in this
+//! example, as printed on Linux and on Windows (where names are spelled as
+//! in PDBs), with and without `RUST_BACKTRACE=full`. The Linux fixtures were
+//! captured from a dev build, so this should be run in the dev profile, where
+//! the closure isn't inlined.
+
+use declaration_site::{declaration_from_backtrace_frame_text, declarations_from_backtrace_text};
+
+mod player {
+    #[inline(never)]
+    pub fn update_player(health: &mut [u32]) -> String {
+        health[0] = health[0].saturating_sub(1);
+        std::backtrace::Backtrace::force_capture().to_string()
+    }
+}
+
+const FIXTURES: [(&str, &str); 4] = [
+    (
+        "linux.txt",
+        include_str!("../fixtures/backtraces/linux.txt"),
+    ),
+    (
+        "linux_full.txt",
+        include_str!("../fixtures/backtraces/linux_full.txt"),
+    ),
+    (
+        "windows.txt",
+        include_str!("../fixtures/backtraces/windows.txt"),
+    ),
+    (
+        "windows_full.txt",
+        include_str!("../fixtures/backtraces/windows_full.txt"),
+    ),
+];
+
+fn main() {
+    let mut health = vec![3];
+    let mut update = || player::update_player(&mut health);
+    let live = update();
+
+    for (name, text) in [("the live backtrace", live.as_str())]
+        .into_iter()
+        .chain(FIXTURES)
+    {
+        let annotated = declarations_from_backtrace_text(text);
+        let mut found = annotated
+            .declarations()
+            .filter(|(_, site)| site.file.ends_with("backtrace_text.rs"))
+            .map(|(name, site)| (name.to_owned(), site.line))
+            .collect::<Vec<_>>();
+        found.dedup();
+        println!("In {name}, found {found:?}");
+        let lines = found.iter().map(|(_, line)| *line).collect::<Vec<_>>();
+        assert_eq!(lines, [14, 41, 39], "in {name}");
+
+        let frame = text
+            .lines()
+            .find(|line| line.contains("update_player"))
+            .expect("Should have a frame of `update_player`");
+        let site = declaration_from_backtrace_frame_text(frame)
+            .expect("Should have found `update_player`");
+        assert_eq!(site.line, 14, "in {name}");
+    }
+}
@@ -0,0 +1,50 @@
+//! Resolves a thousand function names at once, as a profiler resolving a
+//! whole call tree would, and compares the time taken to a single lookup.
+//!
+//! The names share long module prefixes and many share their last
+//! identifier (`fmt`, `next`, `{closure}`), but each scanned function is only
+//! hashed against the queries, so the batch takes a small multiple of the
+//! time of a single full scan, rather than a thousand scans.
+
+use std::{collections::BTreeSet, time::Instant};
+
+use declaration_site::{
+    declaration_by_name, declarations_by_name, for_each_loaded_function, normalize_function_path,
+    NormalizeOptions,
+};
+
+const QUERIES: usize = 1000;
+
+fn main() {
+    let mut names = BTreeSet::new();
+    for_each_loaded_function(|function| {
+        let name =
+            normalize_function_path(function.name(), NormalizeOptions::default()).into_string();
+        if name.starts_with("core::") || name.starts_with("std::") {
+            names.insert(name);
+        }
+    });
+    let names = names.into_iter().take(QUERIES).collect::<Vec<_>>();
+    assert_eq!(names.len(), QUERIES, "Too few functions were loaded");
+    let queries = names.iter().map(String::as_str).collect::<Vec<_>>();
+
+    // A name which isn't found needs a scan of every loaded function
+    let start = Instant::now();
+    let missing = declarations_by_name(&["my_crate::missing_function"]);
+    let single_time = start.elapsed();
+    assert_eq!(missing, [None]);
+
+    let start = Instant::now();
+    let sites = declarations_by_name(&queries);
+    let batch_time = start.elapsed();
+
+    let found = sites.iter().flatten().count();
+    println!("1 query: {single_time:?}");
+    println!("{QUERIES} queries: {batch_time:?}, {found} found");
+
+    // The results are in the same order as the queries
+    assert_eq!(sites.len(), QUERIES);
+    for (name, site) in queries.iter().zip(&sites).step_by(QUERIES / 10) {
+        assert_eq!(site, &declaration_by_name(name), "{name}");
+    }
+}
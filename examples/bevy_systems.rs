@@ -0,0 +1,44 @@
+use bevy_app::{App, Startup, Update};
+use bevy_ecs::{schedule::Schedules, system::IntoSystem};
+use declaration_site::bevy::{system_function_names, DeclarationSiteCache, DeclarationSitePlugin};
+
+fn main() {
+    let mut app = App::new();
+    app.add_plugins(DeclarationSitePlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, (move_player, count.pipe(report)));
+    // Schedules need to have been run before their systems can be listed
+    app.update();
+
+    let world = app.world_mut();
+    world.resource_scope(
+        |world, mut cache: bevy_ecs::world::Mut<DeclarationSiteCache>| {
+            let schedules = world.resource::<Schedules>();
+            cache.resolve_schedules(schedules);
+            for (_, schedule) in schedules.iter() {
+                for (_, system) in schedule.systems().into_iter().flatten() {
+                    let name = system.name().to_string();
+                    let sites = cache.get(&name).unwrap_or_default();
+                    for (function, site) in system_function_names(&name).iter().zip(sites) {
+                        match site {
+                            Some(site) => println!("system `{function}` defined at {site}"),
+                            None => println!("system `{function}` has no known declaration site"),
+                        }
+                    }
+                }
+            }
+        },
+    );
+}
+
+fn setup() {}
+
+fn move_player() {} // Found by this example
+
+fn count() -> usize {
+    1
+}
+
+fn report(count: bevy_ecs::system::In<usize>) {
+    let _ = count;
+}
@@ -0,0 +1,43 @@
+//! Finds functions in a big-endian PowerPC library built by rustc for the
+//! `powerpc-unknown-linux-gnu` target, which can be searched from any host.
+//!
+//! Searching the object it was linked from fails loudly, as the addresses in
+//! the debug info of relocatable ELF objects are only in their relocations.
+//!
+//! Run from the root of the repository. The library and object are built from
+//! `fixtures/big_endian/big_endian_fixture.rs` by `fixtures/big_endian/build.sh`.
+
+use std::io;
+
+use declaration_site::{declaration_by_name_in, declarations_by_name_in, for_functions_in_file};
+use symbolic_common::Arch;
+
+const LIBRARY: &str = "fixtures/big_endian/libbig_endian_fixture.so";
+const OBJECT: &str = "fixtures/big_endian/big_endian_fixture.o";
+
+fn main() {
+    let mut arches = Vec::new();
+    for_functions_in_file(LIBRARY, |function| arches.push(function.object_arch()))
+        .expect("Should have read the library");
+    assert!(!arches.is_empty());
+    assert!(arches.iter().all(|&arch| arch == Arch::Ppc), "{arches:?}");
+
+    let expected = [
+        ("big_endian_fixture::first", 27),
+        ("big_endian_fixture::nested::second", 34),
+    ];
+    let names = expected.map(|(name, _)| name);
+    let sites = declarations_by_name_in(LIBRARY, &names).expect("Should have read the library");
+    for ((name, line), site) in expected.into_iter().zip(sites) {
+        println!("`{name}` is at {site:?}");
+        let site = site.unwrap_or_else(|| panic!("Should have found `{name}`"));
+        assert_eq!(site.line, line, "for `{name}`");
+        assert!(site.file.ends_with("big_endian_fixture.rs"));
+    }
+
+    let error = declaration_by_name_in(OBJECT, "big_endian_fixture::nested::second")
+        .expect_err("The functions of the object can't be told apart");
+    println!("{error}");
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    assert!(error.to_string().contains("ppc"), "{error}");
+}
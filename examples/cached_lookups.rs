@@ -0,0 +1,93 @@
+//! Checks that looking up a function whose declaration site is already
+//! cached doesn't allocate, by counting allocations with a global allocator,
+//! that redaction is applied to cached sites, and that lookups which didn't
+//! search everything aren't cached.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use declaration_site::{
+    cached_declaration, cached_declaration_of, set_redaction, with_low_allocation_scans, Redaction,
+    RedactionRule,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[inline(never)]
+fn draw_overlay() -> u32 {
+    std::hint::black_box(1)
+}
+
+#[inline(never)]
+fn draw_grid() -> u32 {
+    std::hint::black_box(2)
+}
+
+fn main() {
+    draw_overlay();
+    let site = cached_declaration_of(&draw_overlay).expect("Should have found `draw_overlay`");
+    println!("Found {site}");
+    assert!(site.file.ends_with("cached_lookups.rs"), "{site}");
+
+    // Types which weren't found are also cached
+    let missing = cached_declaration::<u32>();
+    assert!(missing.is_none());
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..1000 {
+        let again = cached_declaration_of(&draw_overlay);
+        assert!(std::ptr::eq(again.unwrap(), site));
+        assert!(cached_declaration::<u32>().is_none());
+    }
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    assert_eq!(after - before, 0, "Cached lookups allocated");
+
+    // Redaction set after a site was cached still applies to it, and only the
+    // first lookup with it allocates
+    set_redaction(Some(Redaction::new(vec![RedactionRule::KeepFrom(
+        "examples".into(),
+    )])));
+    let redacted = cached_declaration_of(&draw_overlay).unwrap();
+    assert_eq!(
+        redacted.file.replace('\\', "/"),
+        "examples/cached_lookups.rs"
+    );
+    assert_eq!(redacted.line, site.line);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..1000 {
+        let again = cached_declaration_of(&draw_overlay);
+        assert!(std::ptr::eq(again.unwrap(), redacted));
+    }
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    assert_eq!(after - before, 0, "Cached redacted lookups allocated");
+    set_redaction(None);
+    assert!(std::ptr::eq(
+        cached_declaration_of(&draw_overlay).unwrap(),
+        site
+    ));
+
+    // No loaded library is this small, so nothing is searched, and the
+    // function isn't cached as missing
+    draw_grid();
+    assert!(with_low_allocation_scans(64, || cached_declaration_of(&draw_grid)).is_none());
+    let grid = cached_declaration_of(&draw_grid).expect("Should have found `draw_grid`");
+    assert!(grid.file.ends_with("cached_lookups.rs"), "{grid}");
+}
@@ -0,0 +1,36 @@
+//! Lists the compilers which contributed code to this example, as a build
+//! provenance audit would.
+
+use std::collections::BTreeMap;
+
+use declaration_site::{for_each_compilation_unit, ScanTarget};
+
+fn main() {
+    // The functions of each producer and language
+    let mut producers = BTreeMap::new();
+    let mut own_units = 0;
+    for_each_compilation_unit(ScanTarget::CurrentProcess, |unit| {
+        let producer = unit.producer.unwrap_or("(unknown producer)").to_owned();
+        *producers
+            .entry((producer, format!("{:?}", unit.language)))
+            .or_insert(0) += unit.function_count;
+        if unit.unit.crate_name().as_deref() == Some("compilation_units") {
+            own_units += 1;
+        }
+    })
+    .unwrap();
+
+    for ((producer, language), functions) in &producers {
+        println!("{functions:>7} {language} functions from {producer}");
+    }
+    println!("This example was compiled in {own_units} codegen units");
+    assert!(own_units > 0, "Should have found the units of this example");
+    assert!(
+        producers.iter().any(
+            |((producer, language), functions)| producer.contains("rustc")
+                && language == "Rust"
+                && *functions > 0
+        ),
+        "Should have found Rust functions compiled by rustc"
+    );
+}
@@ -0,0 +1,34 @@
+//! Finds the declaration site of the function calling
+//! `current_function_declaration`, without naming it.
+//!
+//! Also run with `CARGO_PROFILE_DEV_OPT_LEVEL=1`.
+
+use declaration_site::current_function_declaration;
+
+#[inline(never)]
+fn not_inlined() -> u32 {
+    let site = current_function_declaration().expect("Should have found `not_inlined`");
+    println!("`not_inlined` is declared at {site}");
+    assert!(site.file.ends_with("current_function.rs"));
+    assert_eq!(site.line, 9);
+    std::hint::black_box(1)
+}
+
+#[inline(always)]
+fn inlined() -> u32 {
+    let site = current_function_declaration().expect("Should have found `inlined`");
+    println!("`inlined` is declared at {site}");
+    assert!(site.file.ends_with("current_function.rs"));
+    // Found through its inlined copy, whose first line is in its body
+    assert!(matches!(site.line, 18 | 19));
+    std::hint::black_box(2)
+}
+
+fn main() {
+    not_inlined();
+    inlined();
+    let closure = || current_function_declaration().expect("Should have found the closure");
+    let site = closure();
+    println!("The closure is declared at {site}");
+    assert_eq!(site.line, 30);
+}
@@ -0,0 +1,80 @@
+//! A long running process which answers lookups from an [`IndexSession`],
+//! one command per line of stdin, with one line of JSON per command on
+//! stdout:
+//!
+//! - `LOOKUP <name>` prints a [`LookupResult`] for the function `name`.
+//! - `PREFIX <path>` prints an array of the [`LookupResult`]s of the functions
+//!   whose names start with `path`, such as `my_crate::module::`.
+//! - `RELOAD` indexes the file again if it has changed, printing a
+//!   [`RefreshResult`].
+//! - `STATS` prints the number of times the file has been indexed and the
+//!   number of queries answered, with their mean duration.
+//!
+//! Run with the path of the binary or debug file to index, which defaults to
+//! this example:
+//!
+//! ```text
+//! cargo build
+//! cargo run --features serde --example daemon -- target/debug/declaration-site
+//! ```
+
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use declaration_site::{
+    schema::{LookupResult, RefreshResult},
+    IndexSession,
+};
+use serde_json::json;
+
+fn main() -> io::Result<()> {
+    let path = match env::args_os().nth(1) {
+        Some(path) => path.into(),
+        None => env::current_exe()?,
+    };
+    let mut session = IndexSession::open(&path)?;
+    eprintln!(
+        "Indexed {} functions in {}",
+        session.index().len(),
+        path.display()
+    );
+
+    let mut queries = 0_u32;
+    let mut query_time = Duration::ZERO;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let start = Instant::now();
+        let response = match command {
+            "LOOKUP" => json!(LookupResult::new(argument, session.index().get(argument))),
+            "PREFIX" => json!(session
+                .index()
+                .with_prefix(argument)
+                .map(|(name, site)| LookupResult::new(name, Some(site)))
+                .collect::<Vec<_>>()),
+            "RELOAD" => match session.refresh() {
+                Ok(diff) => json!(RefreshResult::new(session.index(), diff.as_ref())),
+                Err(e) => json!({ "error": e.to_string() }),
+            },
+            "STATS" => json!({
+                "scans": session.scans(),
+                "queries": queries,
+                "mean_query_micros": query_time.as_secs_f64() * 1e6 / f64::from(queries.max(1)),
+            }),
+            "" => continue,
+            _ => json!({ "error": format!("unknown command `{command}`") }),
+        };
+        if matches!(command, "LOOKUP" | "PREFIX") {
+            queries += 1;
+            query_time += start.elapsed();
+        }
+        writeln!(stdout, "{response}")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Checks that the split debug files of WASM modules were built with them,
+//! and that a stale debug file is caught.
+//!
+//! Run from the root of the repository. The modules and debug files are built
+//! from `fixtures/debug_pairing/pairing_fixture.rs` by
+//! `fixtures/debug_pairing/build.sh`.
+
+use std::io;
+
+use declaration_site::{
+    declaration_by_name_in, verify_debug_pairing, with_debug_pairing, DebugPairing, PairingStatus,
+};
+
+const MODULE: &str = "fixtures/debug_pairing/module.wasm";
+const DEBUG: &str = "fixtures/debug_pairing/module.debug.wasm";
+const STALE_MODULE: &str = "fixtures/debug_pairing/stale.wasm";
+const STALE_DEBUG: &str = "fixtures/debug_pairing/stale.debug.wasm";
+
+fn main() {
+    let report = verify_debug_pairing(MODULE, DEBUG).expect("Should have read the files");
+    println!("{report}");
+    assert_eq!(report.status, PairingStatus::Matched);
+
+    let report = verify_debug_pairing(MODULE, STALE_DEBUG).expect("Should have read the files");
+    println!("{report}");
+    assert_eq!(report.status, PairingStatus::Mismatched);
+    assert_ne!(report.binary_ids, report.debug_ids);
+
+    // The module is searched using the debug file it names
+    let site = declaration_by_name_in(MODULE, "pairing_fixture::helper")
+        .expect("Should have read the module")
+        .expect("Should have found `helper`");
+    println!("`helper` is at {site:?}");
+    assert_eq!(site.line, 33);
+    assert!(site.file.ends_with("pairing_fixture.rs"));
+
+    // The stale module has no debug info of its own, so its mismatched debug
+    // file is still searched by default
+    let site = declaration_by_name_in(STALE_MODULE, "pairing_fixture::helper")
+        .expect("Should have read the module");
+    assert!(site.is_some());
+
+    let error = with_debug_pairing(DebugPairing::Strict, || {
+        declaration_by_name_in(STALE_MODULE, "pairing_fixture::helper")
+    })
+    .expect_err("The debug file of the stale module is from a different build");
+    println!("{error}");
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.to_string().contains("stale.debug.wasm"), "{error}");
+
+    let site = with_debug_pairing(DebugPairing::Unverified, || {
+        declaration_by_name_in(STALE_MODULE, "pairing_fixture::helper")
+    })
+    .expect("Should have read the module");
+    assert!(site.is_some());
+}
@@ -0,0 +1,59 @@
+//! Finds trait methods which use the trait's default body, rather than
+//! being overridden in the type's impl.
+//!
+//! Legacy demangled names of default bodies don't include the type, so these
+//! are found as the default body of the trait's method. Build with
+//! `RUSTFLAGS="-C symbol-mangling-version=v0"` to check the v0 mangling
+//! scheme, whose names include the type.
+
+use declaration_site::{declaration_by_name, declarations_by_name, explain_declaration_by_name};
+
+pub trait Greet {
+    #[inline(never)]
+    fn greeting(&self) -> &'static str {
+        "hello"
+    }
+}
+
+/// Uses the default `greeting`.
+pub struct Plain;
+
+impl Greet for Plain {}
+
+/// Overrides `greeting`.
+pub struct Custom;
+
+impl Greet for Custom {
+    #[inline(never)]
+    fn greeting(&self) -> &'static str {
+        "hi"
+    }
+}
+
+fn main() {
+    // Ensure that the methods are linked
+    println!("{} {}", Plain.greeting(), Custom.greeting());
+
+    let name = std::any::type_name_of_val(&<Plain as Greet>::greeting);
+    let choice = explain_declaration_by_name(name).expect("Should have found default method");
+    println!("{choice}");
+    let site = choice
+        .site
+        .expect("Should have found site of default method");
+    assert!(site.file.ends_with("default_methods.rs"));
+    assert_eq!(site.line, 13);
+    assert_eq!(declarations_by_name(&[name]), [Some(site)]);
+    // The v0 demangled name includes the type, so matches exactly
+    assert!(choice.via_default_impl || choice.exact);
+
+    let name = std::any::type_name_of_val(&<Custom as Greet>::greeting);
+    let choice = explain_declaration_by_name(name).expect("Should have found overridden method");
+    println!("{choice}");
+    assert!(!choice.via_default_impl);
+    assert_eq!(
+        choice.confidence,
+        declaration_site::Confidence::Exact,
+        "{choice}"
+    );
+    assert_eq!(declaration_by_name(name).map(|site| site.line), Some(28));
+}
@@ -0,0 +1,49 @@
+//! Finds the code which drops values of a type, through its drop glue.
+//!
+//! Build with `RUSTFLAGS="-C symbol-mangling-version=v0"` to check the v0
+//! mangling scheme, where newer rustc versions name drop glue
+//! `core::ptr::drop_glue`, and with `CARGO_PROFILE_DEV_OPT_LEVEL=1`.
+
+use declaration_site::drop_glue_declaration;
+
+pub struct Guard(Vec<u8>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        std::hint::black_box(&self.0);
+    }
+}
+
+pub struct Holder {
+    pub guard: Guard,
+    pub name: String,
+}
+
+pub struct Leaked(pub String);
+
+fn main() {
+    // Ensure that the drop glue is linked
+    drop(std::hint::black_box(Holder {
+        guard: Guard(vec![1]),
+        name: String::from("holder"),
+    }));
+
+    // The `Drop` impl is found through the glue. With optimizations, `drop`
+    // is inlined into the glue, and its first line is that of its body
+    let site = drop_glue_declaration::<Guard>().expect("Should have found `Guard`'s drop");
+    println!("`Guard` is dropped by {site}");
+    assert!(site.file.ends_with("drop_glue.rs"));
+    assert!(matches!(site.line, 12 | 13));
+
+    // Types without a `Drop` impl only have glue, in the standard library
+    let site = drop_glue_declaration::<Holder>().expect("Should have found `Holder`'s drop glue");
+    println!("`Holder` is dropped by glue at {site}");
+    assert!(site
+        .file
+        .replace('\\', "/")
+        .ends_with("core/src/ptr/mod.rs"));
+
+    // Types which are never dropped have no glue
+    std::mem::forget(std::hint::black_box(Leaked(String::from("leaked"))));
+    assert_eq!(drop_glue_declaration::<Leaked>(), None);
+}
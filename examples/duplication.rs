@@ -0,0 +1,56 @@
+//! Finds the generic functions with the most copies in this example.
+
+use declaration_site::{duplication_report, ScanTarget};
+
+#[inline(never)]
+pub fn describe<T: std::fmt::Debug>(value: T) -> String {
+    format!("{value:?}")
+}
+
+fn main() {
+    // Ensure that several copies of `describe` are linked
+    let described = [
+        describe(1u8),
+        describe(2u16),
+        describe("three"),
+        describe([4.0f32]),
+    ];
+    std::hint::black_box(described);
+
+    let report = duplication_report(ScanTarget::CurrentProcess, usize::MAX)
+        .expect("Should have scanned the current process");
+    let object = report
+        .objects
+        .iter()
+        .find(|object| {
+            object
+                .path
+                .as_deref()
+                .is_some_and(|path| path.contains("duplication"))
+        })
+        .expect("Should have found this example's executable");
+    assert!(object.distinct_names <= object.functions);
+    let describe = object
+        .duplicated
+        .iter()
+        .find(|function| function.base_path == "duplication::describe")
+        .expect("Should have found the copies of `describe`");
+    println!(
+        "{} copies of `describe`, {} bytes",
+        describe.copies, describe.total_size
+    );
+    assert_eq!(describe.copies, 4);
+    assert!(describe.total_size > 0);
+    let site = describe
+        .site
+        .as_ref()
+        .expect("Should have found `describe`");
+    assert!(site.file.ends_with("duplication.rs"));
+    assert_eq!(site.line, 6);
+
+    let mut top = report.clone();
+    for object in &mut top.objects {
+        object.duplicated.truncate(3);
+    }
+    println!("{top}");
+}
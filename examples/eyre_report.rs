@@ -0,0 +1,44 @@
+//! Renders an `eyre` report with the declaration sites of the function
+//! attached to it and of the functions in its backtrace.
+
+use declaration_site::{
+    eyre::{install_eyre_hook, WithDeclaration},
+    with_scan_limits, ScanLimits,
+};
+
+#[inline(never)]
+fn load_level() -> eyre::Result<()> {
+    Err(eyre::eyre!("level file missing")).with_declaration_of(&load_level)
+}
+
+fn main() {
+    // Capture backtraces for the reports
+    std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    install_eyre_hook().unwrap();
+    let report = load_level().unwrap_err();
+
+    let (rendered, stats) = with_scan_limits(ScanLimits::default(), || format!("{report:?}"));
+    println!("{rendered}");
+    assert!(rendered.starts_with("level file missing"), "{rendered}");
+    let (_, declarations) = rendered
+        .split_once("\n\nDeclarations:\n")
+        .expect("Should have printed the declarations");
+    assert!(
+        declarations
+            .starts_with("    eyre_report::load_level defined at examples/eyre_report.rs:10\n"),
+        "{declarations}"
+    );
+    let (_, backtrace) = rendered
+        .split_once("\n\nBacktrace declarations:\n")
+        .expect("Should have printed the backtrace's declarations");
+    assert!(
+        backtrace.contains("eyre_report::main defined at examples/eyre_report.rs:14"),
+        "{backtrace}"
+    );
+    assert!(stats.objects > 0);
+
+    // The sites are only looked up the first time the report is printed
+    let (again, stats) = with_scan_limits(ScanLimits::default(), || format!("{report:?}"));
+    assert_eq!(again, rendered);
+    assert_eq!(stats.objects, 0, "{stats:?}");
+}
@@ -0,0 +1,128 @@
+//! Searches a binary in an in-memory filesystem, with every read going
+//! through a custom `FileAccess` which records the paths it was asked for.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use declaration_site::{
+    declaration_by_name_in, with_file_access, DeclarationError, DeclarationIndex,
+    DeclarationSearch, EmbeddedSources, FileAccess, FileMetadata,
+};
+
+/// A filesystem of files in memory, which can't read any other files.
+struct InMemory {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    reads: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl FileAccess for InMemory {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.reads.lock().unwrap().push(path.to_owned());
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or(io::ErrorKind::NotFound.into())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        if let Some(data) = self.files.get(path) {
+            return Ok(FileMetadata {
+                is_dir: false,
+                len: data.len() as u64,
+            });
+        }
+        if self.files.keys().any(|file| file.starts_with(path)) {
+            return Ok(FileMetadata {
+                is_dir: true,
+                len: 0,
+            });
+        }
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self
+            .files
+            .keys()
+            .filter(|file| file.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+}
+
+#[inline(never)]
+fn resolved_in_memory() -> u32 {
+    std::hint::black_box(7)
+}
+
+fn main() {
+    resolved_in_memory();
+    let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    let binary = PathBuf::from("/virtual/bin/app");
+    let source = "/virtual/src/app.rs";
+    let files = BTreeMap::from([
+        (binary.clone(), exe),
+        (source.into(), b"fn app() {}\n".to_vec()),
+    ]);
+    let reads = Arc::new(Mutex::new(Vec::new()));
+    let access = InMemory {
+        files,
+        reads: Arc::clone(&reads),
+    };
+
+    with_file_access(access, || {
+        let name = std::any::type_name_of_val(&resolved_in_memory);
+        let site = declaration_by_name_in(&binary, name)
+            .expect("Should have read the binary from memory")
+            .expect("Should have found `resolved_in_memory`");
+        println!("Found {site}");
+        assert!(site.file.ends_with("file_access.rs"), "{site}");
+
+        let index = DeclarationIndex::from_file(&binary).unwrap();
+        assert_eq!(index.get(name), Some(&site));
+
+        // Files outside of the in-memory filesystem can't be read, even if
+        // they exist
+        let outside = std::env::current_exe().unwrap();
+        let error = declaration_by_name_in(&outside, name).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+        let site = declaration_site::DeclarationSite {
+            file: source.into(),
+            line: 1,
+        };
+        let sources = EmbeddedSources::new();
+        assert_eq!(sources.read_source(&site).as_deref(), Some("fn app() {}\n"));
+    });
+    {
+        let reads = reads.lock().unwrap();
+        println!("Read {reads:?}");
+        assert!(reads.iter().any(|path| path == &binary));
+        assert!(reads.iter().any(|path| path == Path::new(source)));
+    }
+
+    // Outside of `with_file_access`, files are read from disk
+    let error = declaration_by_name_in(&binary, "app::main").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+
+    // A search of the loaded libraries can have its own file access, which
+    // here can't read any of them
+    let name = std::any::type_name_of_val(&resolved_in_memory);
+    let search = DeclarationSearch::new(name);
+    assert!(search.find().is_some());
+    reads.lock().unwrap().clear();
+    let empty = InMemory {
+        files: BTreeMap::new(),
+        reads: Arc::clone(&reads),
+    };
+    let error = search.file_access(empty).try_find().unwrap_err();
+    assert!(
+        matches!(error, DeclarationError::NoSearchTargets { .. }),
+        "{error}"
+    );
+    assert!(!reads.lock().unwrap().is_empty());
+}
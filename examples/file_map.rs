@@ -0,0 +1,50 @@
+//! Groups the functions of this example by the file they are declared in,
+//! both by scanning and from an index, and checks that they agree.
+
+use declaration_site::{file_map, DeclarationIndex, ScanTarget};
+
+#[inline(never)]
+fn first() -> u32 {
+    std::hint::black_box(1)
+}
+
+#[inline(never)]
+fn second() -> u32 {
+    std::hint::black_box(2)
+}
+
+fn main() -> std::io::Result<()> {
+    first();
+    second();
+    let exe = std::env::current_exe()?;
+    let files = file_map(ScanTarget::File(&exe))?;
+    let (_, functions) = files
+        .iter()
+        .find(|(file, _)| file.ends_with("file_map.rs") && file.contains("examples"))
+        .expect("Should have found this file");
+    println!("Found {functions:?}");
+    // Closures (such as those below) are listed after `main`, and differ
+    // between optimization levels, as do the lines found for small functions
+    let mut names = Vec::new();
+    for (name, _) in &functions[..3] {
+        names.push(name.as_str());
+    }
+    assert_eq!(
+        names,
+        ["file_map::first", "file_map::second", "file_map::main"]
+    );
+    assert!(functions.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+    let index = DeclarationIndex::from_file(&exe)?;
+    let index_files = index.file_map();
+    let (_, index_functions) = index_files
+        .iter()
+        .find(|(file, _)| file.ends_with("file_map.rs") && file.contains("examples"))
+        .expect("Should have found this file in the index");
+    // The index only keeps one declaration site for each name, so each
+    // closure is only listed once
+    for (name, line) in index_functions {
+        assert!(functions.contains(&(name.to_string(), *line)));
+    }
+    Ok(())
+}
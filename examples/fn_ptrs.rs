@@ -0,0 +1,33 @@
+//! Finds the declaration sites of `fn` pointers, including closures which
+//! capture nothing, whose pointers are to compiler generated shims.
+
+use declaration_site::declaration_of_fn_ptr;
+
+#[inline(never)]
+fn named_callback(x: u32) -> u32 {
+    x * 2
+}
+
+fn main() {
+    let increment: fn(u32) -> u32 = |x| x + 1;
+    let decrement: fn(u32) -> u32 = |x| x - 1;
+    let named: fn(u32) -> u32 = named_callback;
+    // Ensure that the functions are linked
+    assert_eq!(increment(decrement(named(1))), 2);
+
+    let expected = [
+        ("increment", increment, 12),
+        ("decrement", decrement, 13),
+        ("named", named, 7),
+    ];
+    for (name, callback, line) in expected {
+        let site = declaration_of_fn_ptr(callback as usize);
+        println!("`{name}` is at {site:?}");
+        let site = site.unwrap_or_else(|| panic!("Should have found `{name}`"));
+        assert_eq!(site.line, line, "for `{name}`");
+        assert!(site.file.ends_with("fn_ptrs.rs"), "for `{name}`");
+    }
+
+    // Addresses outside of any loaded library aren't found
+    assert_eq!(declaration_of_fn_ptr(0), None);
+}
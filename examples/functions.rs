@@ -1,11 +1,20 @@
 use std::time::Instant;
 
-use declaration_site::declaration_of;
+use declaration_site::try_declaration_of;
+
+/// A table of this example's functions from `export_compact_table`, embedded
+/// when it is built with `--cfg declaration_site_table` and
+/// `DECLARATION_SITE_TABLE` set to the table's path, so that it still finds
+/// its function when stripped. See `tests/embedded_table.rs`.
+#[cfg(declaration_site_table)]
+static TABLE: &[u8] = include_bytes!(env!("DECLARATION_SITE_TABLE"));
 
 fn main() {
+    #[cfg(declaration_site_table)]
+    declaration_site::register_embedded_table(TABLE).expect("Should have read the table");
     let now = Instant::now();
-    let declaration =
-        declaration_of(&function_to_find).expect("Should have gotten declaration site");
+    let declaration = try_declaration_of(&function_to_find)
+        .unwrap_or_else(|error| panic!("Should have gotten declaration site: {error}"));
     println!(
         "Found {declaration} in {elapsed:?}",
         elapsed = now.elapsed()
@@ -15,4 +24,4 @@ fn main() {
 }
 
 #[inline(never)]
-fn function_to_find() {}
+fn function_to_find() {} // Found by this example
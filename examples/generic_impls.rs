@@ -0,0 +1,65 @@
+//! Finds methods of generic impls, and generic methods, by their
+//! [`type_name`](std::any::type_name), which names the concrete generic
+//! arguments.
+//!
+//! Legacy demangled names print the impl's generic parameters instead, and
+//! leave out the method's generic arguments. Build with
+//! `RUSTFLAGS="-C symbol-mangling-version=v0"` to check the v0 mangling
+//! scheme, whose names print the arguments.
+
+use std::any::type_name_of_val;
+
+use declaration_site::declarations_by_name;
+
+pub struct Wrapper<T>(T);
+
+impl<T: Copy> Wrapper<T> {
+    #[inline(never)]
+    pub fn get(&self) -> T {
+        self.0
+    }
+
+    #[inline(never)]
+    pub fn map<U: From<T>>(&self) -> U {
+        U::from(self.0)
+    }
+}
+
+pub mod inner {
+    pub struct Pair<A, B>(pub A, pub B);
+
+    impl<A: Copy, B> Pair<A, B> {
+        #[inline(never)]
+        pub fn first(&self) -> A {
+            self.0
+        }
+    }
+}
+
+fn main() {
+    // Ensure that the methods are linked
+    let wrapper = Wrapper(3u32);
+    std::hint::black_box(wrapper.get());
+    std::hint::black_box(wrapper.map::<u64>());
+    let pair = inner::Pair(1u8, vec![1u16]);
+    std::hint::black_box(pair.first());
+
+    let expected = [
+        (type_name_of_val(&Wrapper::<u32>::get), 18),
+        (type_name_of_val(&Wrapper::<u32>::map::<u64>), 23),
+        (type_name_of_val(&inner::Pair::<u8, Vec<u16>>::first), 33),
+        // Paths of primitives and std re-exports can be spelled either way
+        ("generic_impls::Wrapper<core::primitive::u32>::get", 18),
+        (
+            "generic_impls::inner::Pair<u8, std::vec::Vec<u16>>::first",
+            33,
+        ),
+    ];
+    let names = expected.map(|(name, _)| name);
+    for ((name, line), site) in expected.into_iter().zip(declarations_by_name(&names)) {
+        let site = site.unwrap_or_else(|| panic!("Should have found `{name}`"));
+        println!("Found `{name}` at {site}");
+        assert!(site.file.ends_with("generic_impls.rs"));
+        assert_eq!(site.line, line, "for `{name}`");
+    }
+}
@@ -0,0 +1,54 @@
+//! Finds functions with raw and non-ASCII identifiers, which are spelled
+//! differently by `type_name` and the demangler, and in files whose names
+//! aren't ASCII.
+//!
+//! Build with `RUSTFLAGS="-C symbol-mangling-version=v0"` to check the
+//! punycode encoded names of the v0 mangling scheme.
+
+use declaration_site::{declaration_by_name, declaration_of, explain_declaration_by_name};
+
+fn main() {
+    let functions: [(&str, fn()); 4] = [
+        (std::any::type_name_of_val(&r#match), r#match),
+        (std::any::type_name_of_val(&grüße), grüße),
+        (std::any::type_name_of_val(&r#mod::straße), r#mod::straße),
+        (std::any::type_name_of_val(&名前::関数), 名前::関数),
+    ];
+    for (name, function) in functions {
+        // Ensure that the function is linked
+        function();
+        let site = declaration_by_name(name).expect("Should have found function by name");
+        println!("Found `{name}` at {site}");
+    }
+    assert!(declaration_by_name("identifiers::r#match").is_some());
+    assert!(declaration_by_name("identifiers::r#mod::straße").is_some());
+    assert!(declaration_by_name("identifiers::xn--gre-6ka8l").is_some());
+    assert_eq!(
+        declaration_of(&grüße),
+        declaration_by_name("identifiers::grüße")
+    );
+
+    let choice = explain_declaration_by_name("identifiers::名前::関数")
+        .expect("Should have found function in file with non-ASCII name");
+    println!("{choice}");
+    let site = choice.site.as_ref().expect("Should have found site");
+    assert!(site.file.ends_with("名前😀.rs"));
+    assert_eq!(site.line, 4);
+    assert!(choice
+        .to_string()
+        .starts_with("`identifiers::名前::関数` at "));
+}
+
+#[inline(never)]
+fn r#match() {} // Found by this example
+
+#[inline(never)]
+fn grüße() {} // Found by this example
+
+#[path = "identifiers/名前😀.rs"]
+mod 名前;
+
+mod r#mod {
+    #[inline(never)]
+    pub fn straße() {} // Found by this example
+}
@@ -0,0 +1,4 @@
+//! A module with a file name which isn't ASCII, for the `identifiers` example.
+
+#[inline(never)]
+pub fn 関数() {} // Found by this example
@@ -0,0 +1,119 @@
+//! Caches the index of this example's functions, checks that caches which are
+//! stale, corrupted or from an incompatible version are rebuilt, and that
+//! incomplete indexes aren't cached.
+
+use std::{env, fs, io};
+
+use declaration_site::{
+    with_cancellation, with_scan_limits, CacheControl, CacheStatus, CancellationToken,
+    DeclarationIndex, ScanLimits,
+};
+
+#[inline(never)]
+fn cached_function() {}
+
+fn main() -> io::Result<()> {
+    cached_function();
+    let exe = env::current_exe()?;
+    let directory = env::temp_dir().join(format!("index_cache_{}", std::process::id()));
+    fs::create_dir_all(&directory)?;
+    let cache = directory.join("index");
+    let load = |control| DeclarationIndex::from_file_cached(&exe, &cache, control);
+
+    let (built, status) = load(CacheControl::Use)?;
+    assert_eq!(status, CacheStatus::Missing);
+    let site = built
+        .get("index_cache::cached_function")
+        .expect("Should have indexed `cached_function`");
+    assert_eq!(site.line, 13);
+    println!("Indexed {} functions", built.len());
+
+    let (loaded, status) = load(CacheControl::Use)?;
+    assert_eq!(status, CacheStatus::Loaded);
+    assert_eq!(loaded, built);
+
+    let (_, status) = load(CacheControl::Rebuild)?;
+    assert_eq!(status, CacheStatus::Forced);
+
+    // A higher minor version, with a field added to the end of the header
+    let original = fs::read(&cache)?;
+    let header_len = u32::from_le_bytes(original[12..16].try_into().unwrap()) as usize;
+    let mut newer = original.clone();
+    newer[10..12].copy_from_slice(&1_u16.to_le_bytes());
+    newer[12..16].copy_from_slice(&(header_len as u32 + 4).to_le_bytes());
+    newer.splice(28 + header_len..28 + header_len, [1, 2, 3, 4]);
+    let checksum = crc32(&newer[28..]);
+    newer[24..28].copy_from_slice(&checksum.to_le_bytes());
+    fs::write(&cache, &newer)?;
+    let (loaded, status) = load(CacheControl::Use)?;
+    assert_eq!(status, CacheStatus::Loaded);
+    assert_eq!(loaded, built);
+
+    // A higher major version
+    let mut incompatible = original.clone();
+    incompatible[8..10].copy_from_slice(&2_u16.to_le_bytes());
+    fs::write(&cache, &incompatible)?;
+    assert_eq!(load(CacheControl::Use)?.1, CacheStatus::Incompatible);
+
+    // A bit flipped in the payload, such as in a line number
+    let mut corrupted = original.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0x10;
+    fs::write(&cache, &corrupted)?;
+    let (rebuilt, status) = load(CacheControl::Use)?;
+    assert_eq!(status, CacheStatus::Corrupted);
+    assert_eq!(rebuilt, built);
+
+    // A truncated file
+    fs::write(&cache, &original[..original.len() / 2])?;
+    assert_eq!(load(CacheControl::Use)?.1, CacheStatus::Corrupted);
+
+    // A cache of a different version of the binary
+    let copy = directory.join("copy");
+    let mut changed = fs::read(&exe)?;
+    changed.push(0);
+    fs::write(&copy, &changed)?;
+    let (_, status) = DeclarationIndex::from_file_cached(&copy, &cache, CacheControl::Use)?;
+    assert_eq!(status, CacheStatus::Stale);
+
+    // Indexes missing functions, as a limit was exceeded or the scan was
+    // cancelled, aren't saved, so they can't be loaded in place of a complete
+    // index
+    fs::remove_file(&cache)?;
+    let limits = ScanLimits {
+        max_functions_per_object: 10,
+        ..ScanLimits::default()
+    };
+    let (result, stats) = with_scan_limits(limits, || load(CacheControl::Use));
+    let (partial, status) = result?;
+    assert_eq!(status, CacheStatus::Missing);
+    assert!(stats.is_truncated() && partial.len() < built.len());
+    assert!(!cache.exists());
+    let token = CancellationToken::new();
+    token.cancel();
+    let error = with_cancellation(&token, || load(CacheControl::Use)).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    assert!(!cache.exists());
+    assert_eq!(load(CacheControl::Use)?, (built, CacheStatus::Missing));
+
+    fs::remove_dir_all(&directory)?;
+    println!("Every cache was rebuilt or loaded as expected");
+    Ok(())
+}
+
+/// The CRC-32 used by the cache format, to write a valid file with a newer
+/// minor version.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,84 @@
+//! Compares the functions of this example with those of an imagined earlier
+//! build, as a hot reloading tool would compare the previously loaded version
+//! of a library with the newly built one.
+
+use declaration_site::{DeclarationIndex, DeclarationSite};
+
+fn unchanged() {}
+fn shifted() {} // Found by this example
+fn relocated() {} // Found by this example
+fn added() {} // Found by this example
+
+fn main() -> std::io::Result<()> {
+    unchanged();
+    shifted();
+    relocated();
+    added();
+    let exe = std::env::current_exe()?;
+    // Nothing differs between a build and itself
+    assert!(DeclarationIndex::diff_files(&exe, &exe)?.is_empty());
+
+    let new = DeclarationIndex::from_file(&exe)?
+        .with_prefix("index_diff::")
+        .map(|(name, site)| (name.to_owned(), site.clone()))
+        .collect::<DeclarationIndex>();
+    // In the earlier build, `shifted` was declared lower in this file,
+    // `relocated` was in another file, `added` didn't exist, and `removed` did
+    let mut old = Vec::new();
+    for (name, site) in new.iter() {
+        let mut site = site.clone();
+        match name {
+            "index_diff::shifted" => site.line += 3,
+            "index_diff::relocated" => site.file = site.file.replace("index_diff", "old_location"),
+            "index_diff::added" => continue,
+            _ => {}
+        }
+        old.push((name.to_owned(), site));
+    }
+    let removed = DeclarationSite {
+        file: "examples/index_diff.rs".into(),
+        line: 40,
+    };
+    old.push(("index_diff::removed".to_owned(), removed.clone()));
+    let old = old.into_iter().collect::<DeclarationIndex>();
+
+    let diff = old.diff(&new);
+    for (name, site) in &diff.added {
+        println!("Added {name} at {site}");
+    }
+    for (name, site) in &diff.removed {
+        println!("Removed {name}, which was at {site}");
+    }
+    for moved in &diff.moved {
+        println!("Moved {} from {} to {}", moved.name, moved.old, moved.new);
+    }
+    let added_site = new.get("index_diff::added").unwrap().clone();
+    assert_eq!(diff.added, [("index_diff::added".to_owned(), added_site)]);
+    assert_eq!(diff.removed, [("index_diff::removed".to_owned(), removed)]);
+    let moved = diff
+        .moved
+        .iter()
+        .map(|it| (it.name.as_str(), it.is_relocation()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        moved,
+        [
+            ("index_diff::relocated", true),
+            ("index_diff::shifted", false)
+        ]
+    );
+
+    // Comparing the other way round swaps the added and removed functions
+    let reversed = new.diff(&old);
+    assert_eq!(reversed.added, diff.removed);
+    assert_eq!(reversed.removed, diff.added);
+
+    // Only the function which moved to another file is a real relocation
+    let relocations = diff.without_line_shifts();
+    assert_eq!(relocations.moved.len(), 1);
+    let relocated = &relocations.moved[0];
+    assert_eq!(relocated.name, "index_diff::relocated");
+    assert!(relocated.old.file.ends_with("old_location.rs"));
+    assert_eq!(&relocated.new, new.get("index_diff::relocated").unwrap());
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! Reports where a function which only exists inlined into other functions
+//! was inlined, as it would be in an optimized build.
+
+use declaration_site::{
+    declaration_by_name_all, explain_declaration_by_name, explain_declaration_by_name_all,
+};
+
+#[inline(always)]
+fn inlined_away(x: u32) -> u32 {
+    std::hint::black_box(x) + 1
+}
+
+#[inline(never)]
+fn first_caller() -> u32 {
+    inlined_away(1)
+}
+
+#[inline(never)]
+fn second_caller() -> u32 {
+    inlined_away(2) * 2
+}
+
+fn main() {
+    assert_eq!(first_caller() + second_caller(), 8);
+
+    let choice = explain_declaration_by_name("inlined_away::inlined_away")
+        .expect("Should have found the inlined copies");
+    println!("{choice}");
+    let site = choice.site.as_ref().unwrap();
+    assert!(site.file.ends_with("inlined_away.rs"));
+    // Found through an inlined copy, whose first line is in its body
+    assert!(matches!(site.line, 9 | 10), "{site}");
+    assert_eq!(choice.confidence, declaration_site::Confidence::Medium);
+    let factor = declaration_site::ConfidenceFactor::Inlinee;
+    assert!(choice.confidence_factors.contains(&factor));
+
+    let calls = choice
+        .inlined_into
+        .iter()
+        .map(|call| {
+            let line = call.call_site.as_ref().map(|it| it.line);
+            (call.caller.as_str(), line)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        calls,
+        [
+            ("inlined_away::first_caller", Some(15)),
+            ("inlined_away::second_caller", Some(20)),
+        ]
+    );
+
+    // The `_all` lookups find the inlined copies too
+    let sites = declaration_by_name_all("inlined_away::inlined_away");
+    assert_eq!(sites, std::slice::from_ref(site));
+    let choices = explain_declaration_by_name_all("inlined_away::inlined_away");
+    assert_eq!(choices.len(), 2);
+    assert!(choices.iter().all(|it| it.inlined_into.len() == 2));
+
+    // Functions which weren't inlined have no calls
+    let choice = explain_declaration_by_name("inlined_away::first_caller").unwrap();
+    assert!(choice.inlined_into.is_empty());
+}
@@ -0,0 +1,87 @@
+//! Measures the memory held by the full index of a binary, and the most memory
+//! used while building it, with and without sharing the file paths of its
+//! sites.
+//!
+//! Pass the path of a binary to index, which defaults to this example.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use declaration_site::{DeclarationIndex, SharedIndex};
+
+/// The system allocator, counting the bytes currently allocated, and the
+/// most allocated at once.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+// SAFETY: Allocation is forwarded to the system allocator
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The memory used by `build`, and its result.
+fn measure<T>(build: impl FnOnce() -> T) -> (Usage, T) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let value = build();
+    let usage = Usage {
+        retained: ALLOCATED.load(Ordering::Relaxed) - before,
+        peak: PEAK.load(Ordering::Relaxed) - before,
+    };
+    (usage, value)
+}
+
+/// The bytes allocated by a function.
+struct Usage {
+    /// Still allocated once it returned
+    retained: usize,
+    /// Allocated at once while it ran
+    peak: usize,
+}
+
+impl std::fmt::Display for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} KiB, at most {} KiB while building",
+            self.retained / 1024,
+            self.peak / 1024
+        )
+    }
+}
+
+fn main() {
+    let path = std::env::args_os()
+        .nth(1)
+        .map_or_else(|| std::env::current_exe().unwrap(), Into::into);
+
+    let (plain_usage, plain) = measure(|| DeclarationIndex::from_file(&path).unwrap());
+    let (shared_usage, shared) = measure(|| SharedIndex::from_file(&path).unwrap());
+    println!("{} functions in {} files", plain.len(), shared.pool().len());
+    println!("DeclarationIndex: {plain_usage}");
+    println!("SharedIndex: {shared_usage}");
+
+    assert_eq!(plain.len(), shared.len());
+    for (name, site) in shared.iter() {
+        assert_eq!(plain.get(name), Some(&site.to_site()));
+    }
+    assert!(shared_usage.retained < plain_usage.retained);
+    // The shared index is built without building a `DeclarationIndex` first
+    assert!(shared_usage.peak < plain_usage.peak);
+}
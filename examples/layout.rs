@@ -0,0 +1,45 @@
+//! Checks that `for_functions_in_address_order` visits the functions of each
+//! object in address order, and finds the same functions as
+//! `for_functions_in_file`.
+
+use declaration_site::{
+    for_functions_in_address_order, for_functions_in_file, FunctionRecord, ScanTarget,
+};
+
+fn key(function: &FunctionRecord<'_>) -> (usize, u64, u64, String) {
+    (
+        function.object_index(),
+        function.address(),
+        function.size(),
+        function.name().to_owned(),
+    )
+}
+
+fn main() {
+    let path = std::env::current_exe().unwrap();
+
+    let mut ordered = Vec::new();
+    for_functions_in_address_order(ScanTarget::File(&path), |function| {
+        assert_eq!(function.end_address(), function.address() + function.size());
+        ordered.push(key(&function));
+    })
+    .unwrap();
+    assert!(!ordered.is_empty());
+    for pair in ordered.windows(2) {
+        let ((a_object, a_address, a_size, _), (b_object, b_address, b_size, _)) =
+            (&pair[0], &pair[1]);
+        if a_object == b_object {
+            assert!((a_address, a_size) <= (b_address, b_size), "{pair:?}");
+        }
+    }
+
+    let mut unordered = Vec::new();
+    for_functions_in_file(&path, |function| unordered.push(key(&function))).unwrap();
+    assert_eq!(ordered.len(), unordered.len());
+    let mut sorted = unordered.clone();
+    sorted.sort();
+    ordered.sort();
+    assert_eq!(ordered, sorted);
+
+    println!("{} functions", ordered.len());
+}
@@ -0,0 +1,49 @@
+//! Finds `#[no_mangle]` functions in a Mach-O object built by rustc for the
+//! `x86_64-apple-darwin` target, whose symbols have an extra leading
+//! underscore.
+//!
+//! Run from the root of the repository. The object is built from
+//! `fixtures/macho/macho_fixture.rs` by `fixtures/macho/build.sh`.
+
+use declaration_site::{declarations_by_name_in, for_functions_in_file};
+
+const OBJECT: &str = "fixtures/macho/macho_fixture.o";
+
+fn main() {
+    let mut names = Vec::new();
+    for_functions_in_file(OBJECT, |function| {
+        println!(
+            "`{}` is named `{}` in the object",
+            function.name(),
+            function.raw_name()
+        );
+        names.push(function.name().to_owned());
+    })
+    .expect("Should have read the object");
+    // Unmangled functions are found with their own names, but the leading
+    // underscores of mangled names aren't removed
+    assert!(names.iter().any(|name| name == "my_ffi_entry"));
+    assert!(names.iter().any(|name| name == "_leading"));
+    assert!(names.iter().any(|name| name == "macho_fixture::mangled"));
+
+    // The `type_name` of each function, with the line it is declared on
+    let expected = [
+        ("macho_fixture::my_ffi_entry", Some(25)),
+        ("macho_fixture::_leading", Some(31)),
+        // Exactly one leading underscore of unmangled names is ignored in
+        // Mach-O files, as it could have been added for the platform
+        ("macho_fixture::leading", Some(31)),
+        ("macho_fixture::__leading", None),
+        // Unmangled functions can't be generic
+        ("macho_fixture::my_ffi_entry::<u32>", None),
+    ];
+    let names = expected.map(|(name, _)| name);
+    let sites = declarations_by_name_in(OBJECT, &names).expect("Should have read the object");
+    for ((name, line), site) in expected.into_iter().zip(sites) {
+        println!("`{name}` is at {site:?}");
+        assert_eq!(site.as_ref().map(|site| site.line), line, "for `{name}`");
+        if let Some(site) = site {
+            assert!(site.file.ends_with("macho_fixture.rs"));
+        }
+    }
+}
@@ -0,0 +1,11 @@
+/// Generates a function named `$name` which runs `$body`.
+#[macro_export]
+macro_rules! generate {
+    ($name:ident, $body:block) => {
+        #[inline(never)]
+        pub fn $name() -> u64 {
+            let value = std::hint::black_box(21);
+            value + $body
+        }
+    };
+}
@@ -0,0 +1,74 @@
+//! Finds the sites of functions generated by a `macro_rules!` macro and a
+//! derive macro, both inside the macro and at its invocation.
+
+mod generate;
+
+use declaration_site::{
+    for_each_loaded_function, names_match, DeclarationSearch, LineProvenance, MacroSite,
+};
+
+generate!(generated, { offset() }); // Found by this example
+
+#[inline(never)]
+fn offset() -> u64 {
+    std::hint::black_box(21)
+}
+
+#[derive(Clone, Debug, PartialEq)] // Found by this example
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+fn main() {
+    let point = Point { x: 1, y: 2 };
+    println!("{} {:?} {}", generated(), point, point == point.clone());
+
+    let name = std::any::type_name_of_val(&generated);
+    let expansion = DeclarationSearch::new(name)
+        .macro_site(MacroSite::Expansion)
+        .find()
+        .expect("Should have found generated function");
+    let invocation = DeclarationSearch::new(name)
+        .macro_site(MacroSite::Invocation)
+        .find()
+        .expect("Should have found generated function");
+    println!("`{name}` is generated at {expansion} and invoked at {invocation}");
+    assert!(expansion.file.ends_with("generate.rs"));
+    assert!(invocation.file.ends_with("main.rs"));
+    assert_eq!(invocation.line, 10);
+
+    // The invocation is found by a heuristic
+    let mut provenances = Vec::new();
+    for_each_loaded_function(|function| {
+        if names_match(function.name(), name) {
+            for macro_site in [MacroSite::Expansion, MacroSite::Invocation] {
+                let provenance = function
+                    .declaration_site_with_provenance(macro_site)
+                    .map(|(_, provenance)| provenance);
+                provenances.push(provenance);
+            }
+        }
+    });
+    assert_eq!(
+        provenances,
+        [
+            Ok(LineProvenance::LineTableFirst),
+            Ok(LineProvenance::Heuristic)
+        ]
+    );
+
+    // Derive macros are already attributed to the invocation
+    let name = "macro_sites::Point::fmt";
+    let expansion = DeclarationSearch::new(name)
+        .macro_site(MacroSite::Expansion)
+        .find()
+        .expect("Should have found derived method");
+    let invocation = DeclarationSearch::new(name)
+        .macro_site(MacroSite::Invocation)
+        .find()
+        .expect("Should have found derived method");
+    println!("`{name}` is derived at {expansion}");
+    assert_eq!(expansion, invocation);
+    assert_eq!(expansion.line, 17);
+}
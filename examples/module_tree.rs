@@ -0,0 +1,102 @@
+//! Arranges the functions of this example into a tree of crates and modules,
+//! checks where its methods, trait methods and closures are placed, and draws
+//! the tree with Graphviz.
+
+use declaration_site::{module_tree, ModuleTree, ScanTarget};
+
+mod player {
+    pub trait Spawn {
+        fn spawn(&self);
+    }
+
+    pub struct Player;
+
+    impl Player {
+        pub fn heal(&self) {} // Found by this example
+    }
+
+    impl Spawn for Player {
+        fn spawn(&self) {} // Found by this example
+    }
+
+    pub fn update(health: u32) -> u32 {
+        let heal = |health: u32| health + 1; // Found by this example
+        heal(health)
+    }
+}
+
+/// The child of `node` named `name`.
+fn child<'a>(node: &'a ModuleTree, name: &str) -> &'a ModuleTree {
+    node.children
+        .iter()
+        .find(|it| it.name == name)
+        .unwrap_or_else(|| panic!("`{}` should have had a child `{name}`", node.name))
+}
+
+/// Print the functions in `node` and below it, with their paths.
+fn print_functions(node: &ModuleTree, path: &str) {
+    let path = if path.is_empty() {
+        node.name.clone()
+    } else {
+        format!("{path}::{}", node.name)
+    };
+    if let Some(site) = node.site.as_ref().filter(|_| node.is_function) {
+        println!("{path} ({} bytes) at {site}", node.size);
+    }
+    for child in &node.children {
+        print_functions(child, &path);
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    use player::Spawn;
+
+    player::Player.heal();
+    player::Player.spawn();
+    player::update(1);
+    let exe = std::env::current_exe()?;
+    let tree = module_tree(ScanTarget::File(&exe))?;
+    assert!(tree.name.is_empty());
+
+    let krate = child(&tree, "module_tree");
+    print_functions(krate, "");
+    let module = child(krate, "player");
+    assert!(!module.is_function);
+
+    // Methods are below their type, and trait methods below the impl, in the
+    // module of the type
+    let player = child(module, "Player");
+    assert!(!player.is_function);
+    let heal = child(player, "heal");
+    assert!(heal.is_function && heal.children.is_empty());
+    let line = |node: &ModuleTree| node.site.as_ref().map(|it| it.line);
+    assert_eq!(line(heal), Some(15));
+    let spawn = child(
+        child(
+            module,
+            "<module_tree::player::Player as module_tree::player::Spawn>",
+        ),
+        "spawn",
+    );
+    assert_eq!(line(spawn), Some(19));
+
+    // Closures are below the function they are declared in
+    let update = child(module, "update");
+    assert!(update.is_function);
+    let closure = child(update, "{closure}");
+    assert_eq!(line(closure), Some(23));
+
+    // The size of each node includes the functions below it
+    assert!(update.size > closure.size);
+    let children = module.children.iter().map(|it| it.size).sum::<u64>();
+    assert_eq!(module.size, children);
+    assert!(tree.size >= krate.size);
+
+    let mut dot = Vec::new();
+    tree.write_dot(&mut dot)?;
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph module_tree {"));
+    let site = heal.site.as_ref().unwrap();
+    assert!(dot.contains(&format!("heal\\n{} bytes\\n{site}", heal.size)));
+    Ok(())
+}
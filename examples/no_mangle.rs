@@ -0,0 +1,47 @@
+//! Finds `#[no_mangle]` functions, whose names in debug info and symbol
+//! tables are only the last segment of their `type_name`, by either their
+//! `type_name` or that symbol.
+//!
+//! See the `macho` example for how the extra leading underscore of Mach-O
+//! symbols is handled.
+
+use declaration_site::{declaration_by_name, lookup_by_name, try_declaration_by_name, Lookup};
+
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn my_ffi_entry(x: u32) -> u32 {
+    std::hint::black_box(x) + 1
+}
+
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn _my_leading_entry(x: u32) -> u32 {
+    std::hint::black_box(x) + 2
+}
+
+fn main() {
+    // Ensure that the functions are linked
+    println!("{}", my_ffi_entry(1) + _my_leading_entry(2));
+
+    let name = std::any::type_name_of_val(&my_ffi_entry);
+    let site = declaration_by_name(name).expect("Should have found `my_ffi_entry`");
+    println!("Found `{name}` at {site}");
+    assert!(site.file.ends_with("no_mangle.rs"));
+    assert_eq!(site.line, 12);
+    assert!(matches!(
+        lookup_by_name(name, true),
+        Some(Lookup::Declaration(found)) if found == site
+    ));
+    // The bare symbol finds the same function
+    assert_eq!(declaration_by_name("my_ffi_entry").as_ref(), Some(&site));
+    assert_eq!(try_declaration_by_name("my_ffi_entry").ok(), Some(site));
+
+    let name = std::any::type_name_of_val(&_my_leading_entry);
+    let site = declaration_by_name(name).expect("Should have found `_my_leading_entry`");
+    println!("Found `{name}` at {site}");
+    assert_eq!(site.line, 18);
+    assert_eq!(declaration_by_name("_my_leading_entry"), Some(site));
+    // Leading underscores are only ignored in Mach-O files
+    let without_underscore = declaration_by_name("no_mangle::my_leading_entry");
+    assert_eq!(without_underscore.is_some(), cfg!(target_vendor = "apple"));
+}
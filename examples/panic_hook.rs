@@ -0,0 +1,34 @@
+//! Installs a panic hook which prints the declaration site of the panicking
+//! function, then panics from a closure and from a function of this example.
+//!
+//! The standard panic message isn't printed, so that the only mention of the
+//! marked lines is from the hook.
+
+use std::panic;
+
+use declaration_site::{augment_panic_hook, with_low_allocation_scans};
+
+#[inline(never)]
+fn update_player(health: u32) -> u32 {
+    health.checked_sub(10).expect("player has no health")
+}
+
+/// Whether spawning panicked, which it always does.
+fn spawn_enemies() -> bool {
+    let spawn = || panic!("no room for enemies"); // Found by this example
+    panic::catch_unwind(spawn).is_err()
+}
+
+fn main() {
+    panic::set_hook(augment_panic_hook(Box::new(|_| {}), &["panic_hook"]));
+
+    // Closures are named `{{closure}}` in backtraces, so this finds the only
+    // closure in `spawn_enemies`
+    assert!(spawn_enemies());
+
+    // A lower file size limit set by the panicking thread is kept, so here
+    // nothing is searched and only the name of the function is printed
+    let result = with_low_allocation_scans(64, || panic::catch_unwind(|| update_player(5)));
+    assert!(result.is_err());
+    assert!(panic::catch_unwind(|| update_player(20)).is_ok());
+}
@@ -0,0 +1,56 @@
+//! Finds functions in a PDB built by rustc for the `x86_64-pc-windows-msvc`
+//! target, whose names are spelled differently to `type_name`.
+//!
+//! Run from the root of the repository. The PDB is built from
+//! `fixtures/pdb/pdb_fixture.rs` by `fixtures/pdb/build.sh`.
+
+use declaration_site::{declarations_by_name_in, for_functions_in_file};
+
+const PDB: &str = "fixtures/pdb/pdb_fixture.pdb";
+
+fn main() {
+    for_functions_in_file(PDB, |function| {
+        println!(
+            "`{}` is named `{}` in the PDB",
+            function.name(),
+            function.raw_name()
+        );
+        // The crate is found from the name of the module's object file
+        let unit = function
+            .compilation_unit()
+            .expect("Should have found module");
+        assert_eq!(unit.crate_name().as_deref(), Some("pdb_fixture"));
+    })
+    .expect("Should have read the PDB");
+
+    // The `type_name` of each function, with the line it is declared on
+    let expected = [
+        ("pdb_fixture::nested::free_function", 78),
+        ("pdb_fixture::Square::side", 55),
+        // Generic arguments are spelled as C++ types in PDBs
+        ("pdb_fixture::generic<[u32; 2]>", 84),
+        ("pdb_fixture::generic<&str>", 84),
+        (
+            "pdb_fixture::generic<(&u32, *const pdb_fixture::Square)>",
+            84,
+        ),
+        // PDBs name trait impl methods after their impl block, such as
+        // `pdb_fixture::impl$4::area`
+        ("<pdb_fixture::Square as pdb_fixture::Shape>::area", 48),
+        ("pdb_fixture::Square::area", 48),
+        (
+            "<pdb_fixture::Wrapper<pdb_fixture::Square> as pdb_fixture::Shape>::area",
+            64,
+        ),
+        // The generic arguments of the impl block are part of the PDB name
+        ("pdb_fixture::Wrapper<pdb_fixture::Square>::inner", 71),
+    ];
+    let names = expected.map(|(name, _)| name);
+    let sites = declarations_by_name_in(PDB, &names).expect("Should have read the PDB");
+    for ((name, line), site) in expected.into_iter().zip(sites) {
+        let site = site.unwrap_or_else(|| panic!("Should have found `{name}`"));
+        println!("Found `{name}` at {site}");
+        assert!(site.file.ends_with("pdb_fixture.rs"));
+        assert_eq!(site.line, line, "for `{name}`");
+    }
+}
@@ -0,0 +1,53 @@
+//! Checks that probes of several shapes are found at their declaration
+//! sites, using the helpers of `declaration_site::test_support`.
+//!
+//! Requires the `test-support` feature. Also run with
+//! `CARGO_PROFILE_DEV_OPT_LEVEL=1` and with
+//! `RUSTFLAGS="-C symbol-mangling-version=v0"`.
+
+use declaration_site::{assert_declaration_matches, probe, test_support::Probe};
+
+#[inline(never)]
+fn free_probe() -> Probe {
+    probe!(free_probe)
+}
+
+#[inline(never)]
+fn generic_probe<T>() -> Probe {
+    probe!(generic_probe::<T>)
+}
+
+struct Widget<T>(T);
+
+impl<T> Widget<T> {
+    #[inline(never)]
+    fn method_probe(&self) -> Probe {
+        probe!(Self::method_probe)
+    }
+}
+
+trait Shape {
+    fn trait_probe(&self) -> Probe;
+}
+
+impl Shape for Widget<u8> {
+    #[inline(never)]
+    fn trait_probe(&self) -> Probe {
+        probe!(Self::trait_probe)
+    }
+}
+
+fn main() {
+    let probes = [
+        free_probe(),
+        generic_probe::<u16>(),
+        Widget(1_u32).method_probe(),
+        Widget(2_u8).trait_probe(),
+    ];
+    for probe in probes {
+        // Found at the line of the signature, which is before the `probe!`
+        let site = assert_declaration_matches!(probe, tolerance = 1);
+        assert_eq!(site.line, probe.line - 1);
+        println!("Found {probe} at {site}");
+    }
+}
@@ -0,0 +1,80 @@
+//! Starts lookups from inside a scan, from a callback and from a panic hook
+//! run by a panic inside a scan, and checks that they return immediately
+//! rather than hanging or recursing.
+//!
+//! The checks run on another thread, so that a deadlock fails the example
+//! rather than hanging it.
+
+use std::{
+    panic,
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use declaration_site::{
+    for_each_loaded_function, try_declaration_by_name, with_low_allocation_scans, DeclarationError,
+    IterationControl,
+};
+
+/// What the lookup in the panic hook returned.
+static FROM_HOOK: Mutex<Option<Result<(), DeclarationError>>> = Mutex::new(None);
+
+#[inline(never)]
+fn looked_up() {}
+
+fn lookup() -> Result<(), DeclarationError> {
+    try_declaration_by_name("reentrancy::looked_up").map(|_| ())
+}
+
+fn main() {
+    looked_up();
+    let (done, finished) = mpsc::channel();
+    thread::spawn(move || {
+        run_checks();
+        done.send(()).unwrap();
+    });
+    finished
+        .recv_timeout(Duration::from_secs(120))
+        .expect("The checks should have finished, rather than hanging");
+}
+
+fn run_checks() {
+    // From a callback of a scan
+    let mut nested = None;
+    for_each_loaded_function(|_| {
+        nested = Some(lookup());
+        IterationControl::Break
+    });
+    let nested = nested.expect("Should have scanned a function");
+    println!("from a callback: {nested:?}");
+    assert!(matches!(nested, Err(DeclarationError::Reentrant)));
+
+    // From a panic hook, for a panic inside a scan
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(|_| {
+        *FROM_HOOK.lock().unwrap() = Some(lookup());
+    }));
+    let result = panic::catch_unwind(|| {
+        for_each_loaded_function(|_| -> IterationControl { panic!("inside a scan") });
+    });
+    panic::set_hook(previous);
+    assert!(result.is_err());
+    let from_hook = FROM_HOOK.lock().unwrap().take();
+    println!("from a panic hook: {from_hook:?}");
+    assert!(matches!(from_hook, Some(Err(DeclarationError::Reentrant))));
+
+    // Once the scans have finished, lookups work again
+    let site = try_declaration_by_name("reentrancy::looked_up");
+    println!("after the scans: {site:?}");
+    assert!(site.is_ok());
+
+    // Files larger than the limit aren't read
+    let skipped = with_low_allocation_scans(64, lookup);
+    assert!(matches!(
+        skipped,
+        Err(DeclarationError::NoSearchTargets { .. })
+    ));
+    let found = with_low_allocation_scans(u64::MAX, lookup);
+    assert!(found.is_ok());
+}
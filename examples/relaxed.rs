@@ -0,0 +1,100 @@
+//! Finds functions whose names don't match exactly, using relaxed matching.
+//!
+//! Legacy demangled names don't include the generic arguments of generic
+//! functions, so `relaxed::parse<alloc::string::String>` matches
+//! `relaxed::parse` without relaxation. Build with
+//! `RUSTFLAGS="-C symbol-mangling-version=v0"` to check the v0 mangling
+//! scheme, where it only matches `relaxed::parse::<u8>` with relaxation.
+
+use declaration_site::{
+    declaration_by_name, Confidence, ConfidenceFactor, DeclarationSearch, MatchRelaxation,
+};
+
+#[inline(never)]
+pub fn parse<T: From<u8>>(x: u8) -> T {
+    T::from(x)
+}
+
+pub mod inner {
+    #[inline(never)]
+    pub fn helper() -> u8 {
+        std::hint::black_box(1)
+    }
+}
+
+pub mod first {
+    pub mod shared {
+        #[inline(never)]
+        pub fn run() -> u8 {
+            std::hint::black_box(2)
+        }
+    }
+}
+
+pub mod second {
+    pub mod shared {
+        #[inline(never)]
+        pub fn run() -> u8 {
+            std::hint::black_box(3)
+        }
+    }
+}
+
+fn main() {
+    // Ensure that the functions are linked
+    std::hint::black_box(parse::<u8>(4));
+    inner::helper();
+    first::shared::run();
+    second::shared::run();
+
+    // Only `parse::<u8>` was instantiated
+    let name = "relaxed::parse<alloc::string::String>";
+    let choice = DeclarationSearch::new(name)
+        .relaxed(MatchRelaxation::Conservative)
+        .explain()
+        .expect("Should have found `parse` with different generic arguments");
+    println!("{choice}");
+    assert_eq!(choice.site.as_ref().map(|site| site.line).ok(), Some(14));
+    assert!(matches!(
+        choice.relaxation,
+        MatchRelaxation::None | MatchRelaxation::Conservative
+    ));
+
+    // Names without their crate only match with aggressive relaxation
+    let name = "inner::helper";
+    assert_eq!(declaration_by_name(name), None);
+    let search = DeclarationSearch::new(name);
+    assert_eq!(
+        search.clone().relaxed(MatchRelaxation::Conservative).find(),
+        None
+    );
+    let choice = search
+        .relaxed(MatchRelaxation::Aggressive)
+        .explain()
+        .expect("Should have found `helper` by the end of its path");
+    println!("{choice}");
+    assert_eq!(choice.relaxation, MatchRelaxation::Aggressive);
+    assert_eq!(choice.site.as_ref().map(|site| site.line).ok(), Some(20));
+    assert_eq!(choice.confidence, Confidence::Low);
+    assert!(choice
+        .confidence_factors
+        .contains(&ConfidenceFactor::Relaxed(MatchRelaxation::Aggressive)));
+
+    // Ambiguous relaxed matches aren't chosen, but are all explained
+    let name = "shared::run";
+    let search = DeclarationSearch::new(name).relaxed(MatchRelaxation::Aggressive);
+    assert_eq!(search.find(), None);
+    let choices = search.explain_all();
+    for choice in &choices {
+        println!("{choice}");
+    }
+    let lines = choices
+        .iter()
+        .filter_map(|choice| Some(choice.site.as_ref().ok()?.line))
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [28, 37]);
+    // Each of them is less likely to be the one meant
+    assert!(choices.iter().all(|choice| choice
+        .confidence_factors
+        .contains(&ConfidenceFactor::Ambiguous)));
+}
@@ -0,0 +1,51 @@
+//! Finds the functions declared in this file, and the function containing a
+//! line, comparing paths as on Windows and as on Linux.
+
+use declaration_site::{DeclarationIndex, PathComparison};
+
+#[inline(never)]
+fn first() -> u32 {
+    std::hint::black_box(1)
+}
+
+#[inline(never)]
+fn second() -> u32 {
+    let value = std::hint::black_box(2);
+    value + 1
+}
+
+fn main() -> std::io::Result<()> {
+    first();
+    second();
+    let index = DeclarationIndex::from_file(std::env::current_exe()?)?;
+
+    let functions =
+        index.functions_declared_in("examples/reverse_lookup.rs", PathComparison::host());
+    let mut names = Vec::new();
+    for (name, _) in &functions {
+        names.push(*name);
+    }
+    println!("Found {names:?}");
+    assert_eq!(
+        names,
+        [
+            "reverse_lookup::first",
+            "reverse_lookup::second",
+            "reverse_lookup::main",
+        ]
+    );
+
+    let (name, site) = index
+        .function_at("examples/reverse_lookup.rs", 14, PathComparison::host())
+        .expect("Should have found the function containing line 14");
+    assert_eq!(name, "reverse_lookup::second");
+    assert_eq!(site.line, 12);
+
+    // The spelling of a path on Windows only matches if case is ignored
+    let windows_path = r"EXAMPLES\Reverse_Lookup.rs";
+    let insensitive = index.functions_declared_in(windows_path, PathComparison::CaseInsensitive);
+    assert_eq!(insensitive, functions);
+    let sensitive = index.functions_declared_in(windows_path, PathComparison::CaseSensitive);
+    assert!(sensitive.is_empty());
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Checks that functions of several shapes can be found by their `type_name`
+//! with the current toolchain, both in this crate's `test_fixtures` and in
+//! this example's own crate.
+//!
+//! Requires the `test-fixtures` feature. Also run with
+//! `RUSTFLAGS="-C symbol-mangling-version=v0"` and with
+//! `CARGO_PROFILE_DEV_OPT_LEVEL=1`.
+
+use declaration_site::{test_fixtures::verify_fixtures, verify_matching_roundtrip_of, MatchDrift};
+
+#[inline(never)]
+fn local_function() -> u8 {
+    std::hint::black_box(1)
+}
+
+struct Local;
+
+impl Local {
+    #[inline(never)]
+    fn local_method(&self) -> u8 {
+        std::hint::black_box(2)
+    }
+}
+
+fn main() -> Result<(), MatchDrift> {
+    verify_fixtures()?;
+    println!("Found every fixture of `declaration_site::test_fixtures`");
+
+    local_function();
+    Local.local_method();
+    verify_matching_roundtrip_of(&local_function)?;
+    verify_matching_roundtrip_of(&Local::local_method)?;
+    println!("Found every function of this example");
+    Ok(())
+}
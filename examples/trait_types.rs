@@ -0,0 +1,72 @@
+//! Finds functions whose names include trait objects (`dyn Trait`) and opaque
+//! types (`impl Trait`), which are printed differently by `type_name`, the
+//! demanglers and different rustc versions.
+//!
+//! Build with `RUSTFLAGS="-C symbol-mangling-version=v0"` to check the v0
+//! mangling scheme, which prints the auto traits of trait objects in a
+//! different order to `type_name`.
+
+use std::{any::type_name_of_val, error::Error, fmt::Debug};
+
+use declaration_site::{declarations_by_name, explain_declaration_by_name_all};
+
+pub struct Wrapper<T: ?Sized>(Box<T>);
+
+impl<T: ?Sized + Debug> Wrapper<T> {
+    #[inline(never)]
+    pub fn show(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[inline(never)]
+pub fn make() -> impl Fn(u8) -> u8 {
+    |x| x + 1
+}
+
+#[inline(never)]
+pub fn apply<F: Fn(u8) -> u8>(f: F) -> u8 {
+    f(1)
+}
+
+fn main() {
+    // Ensure that the functions are linked
+    let error: Box<dyn Error + Send + Sync> = "error".into();
+    std::hint::black_box(Wrapper(error).show());
+    let debug: Box<dyn Debug> = Box::new(3u8);
+    std::hint::black_box(Wrapper(debug).show());
+    let apply = apply;
+    std::hint::black_box(apply(make()));
+
+    let expected = [
+        (
+            type_name_of_val(&Wrapper::<dyn Error + Send + Sync>::show),
+            17,
+        ),
+        (type_name_of_val(&Wrapper::<dyn Debug>::show), 17),
+        (type_name_of_val(&apply), 28),
+        // Spellings of older rustc versions
+        (
+            "trait_types::Wrapper<dyn std::error::Error + 'static>::show",
+            17,
+        ),
+        (
+            "trait_types::apply<impl core::ops::function::Fn(u8) -> u8>",
+            28,
+        ),
+    ];
+    let names = expected.map(|(name, _)| name);
+    for ((name, line), site) in expected.into_iter().zip(declarations_by_name(&names)) {
+        let site = site.unwrap_or_else(|| panic!("Should have found `{name}`"));
+        println!("Found `{name}` at {site}");
+        assert!(site.file.ends_with("trait_types.rs"));
+        assert_eq!(site.line, line, "for `{name}`");
+    }
+
+    // Trait objects with and without auto traits can't be told apart, so the
+    // functions for both are found
+    let name = "trait_types::Wrapper<dyn core::error::Error>::show";
+    for choice in explain_declaration_by_name_all(name) {
+        println!("`{name}` matched `{}`", choice.function_name);
+    }
+}
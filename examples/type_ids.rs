@@ -0,0 +1,42 @@
+//! Finds the declaration sites of functions from their `TypeId`s, as a
+//! reflection system which only stores `TypeId`s would.
+
+use std::any::{Any, TypeId};
+
+use declaration_site::{declaration_by_type_id, register_type_of};
+
+#[inline(never)]
+fn on_spawn() {} // Found by this example
+
+#[inline(never)]
+fn on_despawn() {} // Found by this example
+
+/// The handlers of a reflection system, which only keeps their `TypeId`s
+/// after they are registered.
+#[derive(Default)]
+struct Handlers {
+    handlers: Vec<(TypeId, Box<dyn Fn()>)>,
+}
+
+impl Handlers {
+    fn register<F: Fn() + 'static>(&mut self, handler: F) {
+        register_type_of(&handler);
+        self.handlers.push((handler.type_id(), Box::new(handler)));
+    }
+}
+
+fn main() {
+    let mut handlers = Handlers::default();
+    handlers.register(on_spawn);
+    handlers.register(on_despawn);
+
+    for (id, handler) in &handlers.handlers {
+        handler();
+        let site = declaration_by_type_id(*id).expect("Should have found the handler");
+        println!("Found a handler at {site}");
+        assert!(site.file.ends_with("type_ids.rs"), "{site}");
+    }
+
+    // Types which weren't registered aren't looked up
+    assert_eq!(declaration_by_type_id(TypeId::of::<u32>()), None);
+}
@@ -0,0 +1,44 @@
+//! Finds functions passed to `declaration_of` in references, smart pointers
+//! and `Option`s, whose type names are those of the wrappers.
+
+use std::{rc::Rc, sync::Arc};
+
+use declaration_site::{declaration_of, try_declaration_of, DeclarationError};
+
+#[inline(never)]
+fn function_to_find() {}
+
+fn main() {
+    function_to_find();
+    let expected = declaration_of(&function_to_find).expect("Should have found the function");
+    assert_eq!(expected.line, 9);
+
+    let wrapped = [
+        ("&", declaration_of(&&function_to_find)),
+        ("&mut", declaration_of(&&mut function_to_find)),
+        ("Box", declaration_of(&Box::new(function_to_find))),
+        ("Rc", declaration_of(&Rc::new(function_to_find))),
+        ("Arc", declaration_of(&Arc::new(function_to_find))),
+        ("Option", declaration_of(&Some(function_to_find))),
+        (
+            "nested",
+            declaration_of(&Some(&Box::new(Rc::new(function_to_find)))),
+        ),
+    ];
+    for (wrapper, site) in wrapped {
+        println!("{wrapper}: {site:?}");
+        assert_eq!(site.as_ref(), Some(&expected), "for {wrapper}");
+    }
+
+    // Trait objects don't name the function
+    let function: &dyn Fn() = &function_to_find;
+    assert!(matches!(
+        try_declaration_of(&function),
+        Err(DeclarationError::ProbablyNotAFunction)
+    ));
+    // Only 8 wrappers are removed
+    assert!(matches!(
+        try_declaration_of(&&&&&&&&&&function_to_find),
+        Err(DeclarationError::ProbablyNotAFunction)
+    ));
+}
@@ -0,0 +1,102 @@
+//! The source of `libaccelerator_fixture.so`, an x86_64 library whose DWARF 5
+//! debug info has a `.debug_names` accelerator table, and of
+//! `libaccelerator_fixture_gdb_index.so` (DWARF 4 with a `.gdb_index`) and
+//! `libaccelerator_fixture_unindexed.so` (without either table), used to
+//! check that functions are found in the same places with and without the
+//! tables. See `build.sh`.
+//!
+//! Most of its functions are generated, so that reading every function is
+//! noticeably slower than looking one up in a table.
+
+#![no_std]
+#![crate_type = "lib"]
+
+/// A function with a mangled name.
+#[inline(never)]
+pub fn first(x: u32) -> u32 {
+    x
+}
+
+pub mod nested {
+    /// A function in a nested module, declared on a later line.
+    #[inline(never)]
+    pub fn second(x: u32) -> u32 {
+        x.wrapping_add(1)
+    }
+}
+
+/// A type with methods.
+pub struct Counter(pub u32);
+
+impl Counter {
+    /// A method of an inherent impl.
+    #[inline(never)]
+    pub fn bump(&mut self) -> u32 {
+        self.0 = self.0.wrapping_add(1);
+        self.0
+    }
+}
+
+impl Default for Counter {
+    /// A method of a trait impl.
+    #[inline(never)]
+    fn default() -> Self {
+        Counter(7)
+    }
+}
+
+/// A function which isn't mangled.
+#[no_mangle]
+pub extern "C" fn accelerator_fixture_entry(x: u32) -> u32 {
+    first(x).wrapping_add(always_inlined(x))
+}
+
+/// A function which is inlined into its callers, even without optimisations.
+#[inline(always)]
+fn always_inlined(x: u32) -> u32 {
+    x.wrapping_mul(3)
+}
+
+/// A function which calls an inlined function.
+#[inline(never)]
+pub fn with_inlinee(x: u32) -> u32 {
+    always_inlined(x).wrapping_sub(1)
+}
+
+macro_rules! generated {
+    ($($module:ident)*) => {
+        $(
+            /// A module of generated functions.
+            pub mod $module {
+                #[inline(never)]
+                pub fn f0(x: u32) -> u32 { x.wrapping_add(0) }
+                #[inline(never)]
+                pub fn f1(x: u32) -> u32 { x.wrapping_add(1) }
+                #[inline(never)]
+                pub fn f2(x: u32) -> u32 { x.wrapping_add(2) }
+                #[inline(never)]
+                pub fn f3(x: u32) -> u32 { x.wrapping_add(3) }
+                #[inline(never)]
+                pub fn f4(x: u32) -> u32 { x.wrapping_add(4) }
+                #[inline(never)]
+                pub fn f5(x: u32) -> u32 { x.wrapping_add(5) }
+                #[inline(never)]
+                pub fn f6(x: u32) -> u32 { x.wrapping_add(6) }
+                #[inline(never)]
+                pub fn f7(x: u32) -> u32 { x.wrapping_add(7) }
+            }
+        )*
+    };
+}
+
+generated! {
+    m00 m01 m02 m03 m04 m05 m06 m07 m08 m09 m10 m11 m12 m13 m14 m15
+    m16 m17 m18 m19 m20 m21 m22 m23 m24 m25 m26 m27 m28 m29 m30 m31
+    m32 m33 m34 m35 m36 m37 m38 m39 m40 m41 m42 m43 m44 m45 m46 m47
+}
+
+/// A function declared after the generated functions.
+#[inline(never)]
+pub fn last(x: u32) -> u32 {
+    x.wrapping_sub(1)
+}
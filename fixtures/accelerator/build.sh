@@ -0,0 +1,25 @@
+#!/bin/sh
+# Rebuild the libraries in this directory from `accelerator_fixture.rs`, using
+# the `rust-lld` shipped with the toolchain, and `ld.gold` (from binutils) for
+# the `.gdb_index`, which lld only builds from `.debug_gnu_pubnames`.
+set -e
+# Build from the root of the repository, so that source paths are relative to it
+cd "$(dirname "$0")/../.."
+out=fixtures/accelerator
+for version in 4 5; do
+    rustc --target x86_64-unknown-linux-gnu -g -C opt-level=0 \
+        -C overflow-checks=off -C panic=abort -C codegen-units=1 \
+        -C dwarf-version=$version --remap-path-prefix="$PWD=." \
+        --emit=obj -o "$out/accelerator_fixture_v$version.o" \
+        "$out/accelerator_fixture.rs"
+done
+host=$(rustc -vV | sed -n 's/^host: //p')
+rust_lld="$(rustc --print sysroot)/lib/rustlib/$host/bin/rust-lld"
+# rustc writes a `.debug_names` for DWARF 5, which lld merges
+"$rust_lld" -flavor gnu -shared --debug-names -o "$out/libaccelerator_fixture.so" \
+    "$out/accelerator_fixture_v5.o"
+ld.gold -shared --gdb-index -o "$out/libaccelerator_fixture_gdb_index.so" \
+    "$out/accelerator_fixture_v4.o"
+"$rust_lld" -flavor gnu -shared -o "$out/libaccelerator_fixture_unindexed.so" \
+    "$out/accelerator_fixture_v4.o"
+rm "$out/accelerator_fixture_v4.o" "$out/accelerator_fixture_v5.o"
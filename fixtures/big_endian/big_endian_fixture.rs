@@ -0,0 +1,37 @@
+//! The source of `big_endian_fixture.o`, a big-endian ELF object built by
+//! rustc for the `powerpc-unknown-linux-gnu` target, used by the
+//! `big_endian` example, and of `libbig_endian_fixture.so`, which it is linked
+//! into.
+//!
+//! This is `no_core`, so that it can be built without the standard library
+//! of that target. See `build.sh`.
+
+#![feature(no_core, lang_items)]
+#![allow(internal_features)]
+#![no_core]
+#![crate_type = "lib"]
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+#[lang = "copy"]
+pub trait Copy {}
+
+impl Copy for u32 {}
+
+/// A function with a mangled name.
+#[inline(never)]
+pub fn first(x: u32) -> u32 {
+    x
+}
+
+pub mod nested {
+    /// A function in a nested module, declared on a later line.
+    #[inline(never)]
+    pub fn second(x: u32) -> u32 {
+        x
+    }
+}
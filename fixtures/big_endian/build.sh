@@ -0,0 +1,16 @@
+#!/bin/sh
+# Rebuild `big_endian_fixture.o` from `big_endian_fixture.rs`, using a nightly
+# toolchain, and link it into `libbig_endian_fixture.so` with the `rust-lld`
+# shipped with that toolchain. No PowerPC toolchain is needed.
+set -e
+# Build from the root of the repository, so that source paths are relative to it
+cd "$(dirname "$0")/../.."
+rustc +nightly --target powerpc-unknown-linux-gnu -g -C opt-level=0 \
+    -C overflow-checks=off -C panic=abort \
+    --remap-path-prefix="$PWD=." \
+    --emit=obj -o fixtures/big_endian/big_endian_fixture.o \
+    fixtures/big_endian/big_endian_fixture.rs
+host=$(rustc +nightly -vV | sed -n 's/^host: //p')
+rust_lld="$(rustc +nightly --print sysroot)/lib/rustlib/$host/bin/rust-lld"
+"$rust_lld" -flavor gnu -shared -o fixtures/big_endian/libbig_endian_fixture.so \
+    fixtures/big_endian/big_endian_fixture.o
@@ -0,0 +1,5 @@
+/// The `run` of the lib target.
+#[inline(never)]
+pub fn run(x: u32) -> u32 {
+    std::hint::black_box(x) + 1
+}
@@ -0,0 +1,32 @@
+use declaration_site::{
+    declaration_by_name, explain_declaration_by_name, explain_declaration_by_name_all,
+    object::CrateTarget,
+};
+
+/// The `run` of the bin target, which has the same name as the lib's.
+#[inline(never)]
+fn run(x: u32) -> u32 {
+    std::hint::black_box(x) * 2
+}
+
+fn main() {
+    let x = std::hint::black_box(20);
+    println!("{}", run(x) + bin_and_lib::run(x));
+
+    // Both functions have the same `type_name`
+    let name = std::any::type_name_of_val(&run);
+    assert_eq!(name, std::any::type_name_of_val(&bin_and_lib::run));
+
+    let choice = explain_declaration_by_name(name).expect("Should have found `run`");
+    println!("{choice}");
+    assert!(choice.bin_and_lib, "Expected both targets to match");
+    assert_eq!(choice.target, Some(CrateTarget::Lib));
+    let site = declaration_by_name(name).expect("Should have found `run`");
+    assert!(site.file.ends_with("lib.rs"), "{site}");
+
+    let all = explain_declaration_by_name_all(name);
+    let mut targets = all.iter().filter_map(|it| it.target).collect::<Vec<_>>();
+    targets.sort_by_key(|target| *target == CrateTarget::Lib);
+    assert_eq!(targets, [CrateTarget::Bin, CrateTarget::Lib]);
+    assert!(all.iter().all(|it| it.bin_and_lib));
+}
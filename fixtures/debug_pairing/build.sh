@@ -0,0 +1,39 @@
+#!/bin/sh
+# Rebuild the modules and debug files from `pairing_fixture.rs`, using a
+# nightly toolchain with the `wasm32-unknown-unknown` target and
+# `llvm-objcopy`.
+set -e
+# Build from the root of the repository, so that source paths are relative to it
+cd "$(dirname "$0")/../.."
+dir=fixtures/debug_pairing
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+rustc +nightly --target wasm32-unknown-unknown -g -C opt-level=0 -C panic=abort \
+    --remap-path-prefix="$PWD=." \
+    -C link-arg=--build-id=0x0123456789abcdef0123456789abcdef \
+    -o "$tmp/full.wasm" "$dir/pairing_fixture.rs"
+
+# The contents of an `external_debug_info` section naming the file `$1`
+external_debug_info() {
+    printf "\\$(printf '%03o' "${#1}")%s" "$1" > "$tmp/$1.section"
+    echo "$tmp/$1.section"
+}
+# The contents of a `build_id` section holding the 16 byte id in hex `$1`
+build_id() {
+    printf '\020' > "$tmp/$1.section"
+    printf '%s' "$1" | sed 's/../\\x&/g' | xargs -0 printf >> "$tmp/$1.section"
+    echo "$tmp/$1.section"
+}
+
+llvm-objcopy --strip-debug \
+    --add-section external_debug_info="$(external_debug_info module.debug.wasm)" \
+    "$tmp/full.wasm" "$dir/module.wasm"
+llvm-objcopy --strip-debug \
+    --add-section external_debug_info="$(external_debug_info stale.debug.wasm)" \
+    "$tmp/full.wasm" "$dir/stale.wasm"
+# `--only-keep-debug` drops the `build_id` section, so it is added back
+llvm-objcopy --only-keep-debug "$tmp/full.wasm" "$tmp/debug.wasm"
+llvm-objcopy --add-section build_id="$(build_id 0123456789abcdef0123456789abcdef)" \
+    "$tmp/debug.wasm" "$dir/module.debug.wasm"
+llvm-objcopy --add-section build_id="$(build_id fedcba9876543210fedcba9876543210)" \
+    "$tmp/debug.wasm" "$dir/stale.debug.wasm"
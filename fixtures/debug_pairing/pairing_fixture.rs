@@ -0,0 +1,35 @@
+//! The source of the WASM modules and split debug files used by the
+//! `debug_pairing` example. `module.wasm` names `module.debug.wasm` as its
+//! external debug file, which was built with it, while `stale.wasm` names
+//! `stale.debug.wasm`, whose build id is that of a different build.
+//!
+//! This is `no_core`, so that it can be built without the standard library
+//! of that target. See `build.sh`.
+
+#![feature(no_core, lang_items)]
+#![allow(internal_features)]
+#![no_core]
+#![crate_type = "cdylib"]
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+#[lang = "copy"]
+pub trait Copy {}
+
+impl Copy for u32 {}
+
+/// The entry point of the module.
+#[no_mangle]
+pub extern "C" fn entry(x: u32) -> u32 {
+    helper(x)
+}
+
+/// A function with a mangled name.
+#[inline(never)]
+pub fn helper(x: u32) -> u32 {
+    x
+}
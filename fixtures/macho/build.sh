@@ -0,0 +1,10 @@
+#!/bin/sh
+# Rebuild `macho_fixture.o` from `macho_fixture.rs`, using a nightly toolchain.
+# The object isn't linked, so no macOS SDK is needed.
+set -e
+# Build from the root of the repository, so that source paths are relative to it
+cd "$(dirname "$0")/../.."
+rustc +nightly --target x86_64-apple-darwin -g -C opt-level=0 \
+    -C overflow-checks=off -C panic=abort -C symbol-mangling-version=v0 \
+    --remap-path-prefix="$PWD=." \
+    --emit=obj -o fixtures/macho/macho_fixture.o fixtures/macho/macho_fixture.rs
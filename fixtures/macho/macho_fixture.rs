@@ -0,0 +1,39 @@
+//! The source of `macho_fixture.o`, a Mach-O object built by rustc for the
+//! `x86_64-apple-darwin` target, used by the `macho` example.
+//!
+//! This is `no_core`, so that it can be built without the standard library
+//! of that target. See `build.sh`.
+
+#![feature(no_core, lang_items)]
+#![allow(internal_features)]
+#![no_core]
+#![crate_type = "lib"]
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+#[lang = "copy"]
+pub trait Copy {}
+
+impl Copy for u32 {}
+
+/// The symbol of this is `_my_ffi_entry`.
+#[no_mangle]
+pub extern "C" fn my_ffi_entry(x: u32) -> u32 {
+    x
+}
+
+/// The symbol of this is `__leading`.
+#[no_mangle]
+pub extern "C" fn _leading(x: u32) -> u32 {
+    x
+}
+
+/// The symbol of this is mangled, starting with `__R`.
+#[inline(never)]
+pub fn mangled(x: u32) -> u32 {
+    x
+}
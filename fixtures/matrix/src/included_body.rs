@@ -0,0 +1,10 @@
+// The body of `mostly_included` in main.rs, so most of its lines are here
+{
+    record(type_name_of_val(&mostly_included), file!(), line!());
+    let mut total = 0_u32;
+    for value in std::hint::black_box([1_u32, 2, 3]) {
+        total = total.wrapping_mul(31).wrapping_add(value);
+    }
+    let total = std::hint::black_box(total) ^ 0x5a5a;
+    std::hint::black_box(total)
+}
@@ -0,0 +1,2 @@
+// A value included by `mostly_own` in main.rs, so few of its lines are here
+std::hint::black_box(0x5a5a_u32)
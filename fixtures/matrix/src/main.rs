@@ -0,0 +1,90 @@
+//! Each function records its name and the site of its first line, printed
+//! as `name<TAB>file<TAB>line` when this program is run.
+//!
+//! The name is the function's `type_name`, and the line is that of the
+//! `record` call, which is the first line of the function's body. Copy this
+//! to check functions of the shapes you rely on with your toolchain.
+
+use std::any::type_name_of_val;
+
+/// Print the name and site of a function.
+#[inline(never)]
+fn record(name: &str, file: &str, line: u32) {
+    println!("{name}\t{file}\t{line}");
+}
+
+#[inline(never)]
+fn free_function() {
+    record(type_name_of_val(&free_function), file!(), line!());
+}
+
+mod nested {
+    use std::any::type_name_of_val;
+
+    #[inline(never)]
+    pub fn nested_function() {
+        super::record(type_name_of_val(&nested_function), file!(), line!());
+    }
+}
+
+struct Widget;
+
+impl Widget {
+    #[inline(never)]
+    fn method(&self) {
+        record(type_name_of_val(&Self::method), file!(), line!());
+    }
+}
+
+trait Greet {
+    fn greet(&self);
+}
+
+impl Greet for Widget {
+    #[inline(never)]
+    fn greet(&self) {
+        record(type_name_of_val(&<Self as Greet>::greet), file!(), line!());
+    }
+}
+
+#[inline(never)]
+fn generic<T>(value: T) -> T {
+    record(type_name_of_val(&generic::<T>), file!(), line!());
+    value
+}
+
+// Functions whose lines are in two files, which are declared in the file
+// with the most of their lines
+#[inline(never)]
+fn mostly_own() -> u32 {
+    record(type_name_of_val(&mostly_own), file!(), line!());
+    let mut total = 0_u32;
+    for value in std::hint::black_box([4_u32, 5, 6]) {
+        total = total.wrapping_mul(31).wrapping_add(value);
+    }
+    let total = std::hint::black_box(total) ^ include!("included_value.rs");
+    std::hint::black_box(total)
+}
+
+#[inline(never)]
+fn mostly_included() -> u32 {
+    include!("included_body.rs")
+}
+
+fn main() {
+    free_function();
+    nested::nested_function();
+    Widget.method();
+    Widget.greet();
+    std::hint::black_box(generic(1_u32));
+    std::hint::black_box(mostly_own());
+    std::hint::black_box(mostly_included());
+
+    let closure = |x: u32| {
+        let line = line!();
+        std::hint::black_box(x);
+        line
+    };
+    let line = std::hint::black_box(closure)(1);
+    record(type_name_of_val(&closure), file!(), line);
+}
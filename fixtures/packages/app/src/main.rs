@@ -0,0 +1,58 @@
+use std::ffi::{CStr, CString};
+
+use declaration_site::{declaration_by_name, explain_declaration_by_name_all, DeclarationSearch};
+
+/// Load the plugin `library`, and call its function `symbol`.
+fn call_plugin(library: &str, symbol: &CStr, x: u32) -> u32 {
+    let path = format!("{}/../target/debug/{library}", env!("CARGO_MANIFEST_DIR"));
+    let path = CString::new(path).unwrap();
+    let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW) };
+    assert!(!handle.is_null(), "Build the plugins first");
+    let symbol = unsafe { libc::dlsym(handle, symbol.as_ptr()) };
+    assert!(!symbol.is_null());
+    let work: extern "C" fn(u32) -> u32 = unsafe { std::mem::transmute(symbol) };
+    work(x)
+}
+
+fn main() {
+    let x = std::hint::black_box(42);
+    println!("{}", call_plugin("libgame_plugin.so", c"game_work", x));
+    println!("{}", call_plugin("libengine_plugin.so", c"engine_work", x));
+
+    // Both plugins contain `utils::work`, from different packages
+    let name = "utils::work";
+    let all = explain_declaration_by_name_all(name);
+    for choice in &all {
+        println!("{choice}");
+    }
+    assert_eq!(all.len(), 2);
+    let mut hints = all
+        .iter()
+        .map(|choice| choice.package_hint.clone().expect("Should have a package"))
+        .collect::<Vec<_>>();
+    hints.sort();
+    assert!(hints[0].ends_with("/packages/engine_utils"), "{hints:?}");
+    assert!(hints[1].ends_with("/packages/game_utils"), "{hints:?}");
+
+    // Without a package, either may be chosen
+    assert!(declaration_by_name(name).is_some());
+
+    for package in ["game_utils", "packages/game_utils", hints[1].as_str()] {
+        let site = DeclarationSearch::new(name)
+            .package_hint(package)
+            .find()
+            .expect("Should have found the game's `work`");
+        assert!(site.file.ends_with("game_utils/src/lib.rs"), "{site}");
+    }
+    let site = DeclarationSearch::new(name)
+        .package_hint("engine_utils")
+        .find()
+        .expect("Should have found the engine's `work`");
+    assert!(site.file.ends_with("engine_utils/src/lib.rs"), "{site}");
+    for package in ["utils", "other_utils"] {
+        assert_eq!(
+            DeclarationSearch::new(name).package_hint(package).find(),
+            None
+        );
+    }
+}
@@ -0,0 +1,4 @@
+#[inline(never)]
+pub fn work(x: u32) -> u32 {
+    x.wrapping_add(7)
+}
@@ -0,0 +1,4 @@
+#[no_mangle]
+pub extern "C" fn game_work(x: u32) -> u32 {
+    utils::work(x)
+}
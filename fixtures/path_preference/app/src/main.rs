@@ -0,0 +1,51 @@
+use std::ffi::CString;
+
+use declaration_site::{
+    declaration_by_name, explain_declaration_by_name, explain_declaration_by_name_all,
+    DeclarationSearch, PathPreference,
+};
+
+fn main() {
+    let x = std::hint::black_box(42);
+    println!("{}", shared::work(x));
+
+    // Load the plugin, which contains `shared::work` from the workspace
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../target/debug/libpath_preference_plugin.so"
+    );
+    let path = CString::new(path).unwrap();
+    let handle = unsafe { libc::dlopen(path.as_ptr(), libc::RTLD_NOW) };
+    assert!(!handle.is_null(), "Build the plugin first");
+    let symbol = unsafe { libc::dlsym(handle, c"plugin_work".as_ptr()) };
+    assert!(!symbol.is_null());
+    let plugin_work: extern "C" fn(u32) -> u32 = unsafe { std::mem::transmute(symbol) };
+    println!("{}", plugin_work(x));
+
+    let name = std::any::type_name_of_val(&shared::work);
+    assert_eq!(name, "shared::work");
+
+    // The copy in the workspace is chosen, though this program is searched first
+    let choice = explain_declaration_by_name(name).expect("Should have found `work`");
+    println!("{choice}");
+    let site = choice.site.expect("Should have found the site");
+    assert!(
+        site.file.ends_with("shared/src/lib.rs") && !site.file.contains("registry"),
+        "{site}"
+    );
+    assert_eq!(choice.path_score, 1);
+    assert_eq!(choice.outscored.len(), 1);
+    assert!(choice.outscored[0].file.contains("shared-0.1.0"));
+    assert_eq!(declaration_by_name(name), Some(site));
+
+    // The other copy is still found by the `_all` lookups
+    let all = explain_declaration_by_name_all(name);
+    assert_eq!(all.len(), 2);
+
+    let first = DeclarationSearch::new(name)
+        .path_preference(PathPreference::FirstFound)
+        .find()
+        .expect("Should have found `work`");
+    println!("First found at {first}");
+    assert!(first.file.contains("shared-0.1.0"), "{first}");
+}
@@ -0,0 +1,4 @@
+#[no_mangle]
+pub extern "C" fn plugin_work(x: u32) -> u32 {
+    shared::work(x)
+}
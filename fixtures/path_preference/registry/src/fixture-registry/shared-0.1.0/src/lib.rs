@@ -0,0 +1,6 @@
+// A copy of `shared`, as vendored into a dependency
+
+#[inline(never)]
+pub fn work(x: u32) -> u32 {
+    x.wrapping_mul(3)
+}
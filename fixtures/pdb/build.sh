@@ -0,0 +1,16 @@
+#!/bin/sh
+# Rebuild `pdb_fixture.pdb` from `pdb_fixture.rs`, using a nightly toolchain and
+# the `rust-lld` linker shipped with it. No Windows SDK is needed.
+set -e
+# Build from the root of the repository, so that source paths are relative to it
+cd "$(dirname "$0")/../.."
+rustc +nightly --target x86_64-pc-windows-msvc -g -C opt-level=0 \
+    -C overflow-checks=off -C panic=abort \
+    --remap-path-prefix="$PWD=." \
+    --emit=obj -o fixtures/pdb/pdb_fixture.obj fixtures/pdb/pdb_fixture.rs
+lld="$(rustc +nightly --print sysroot)/lib/rustlib/$(rustc +nightly -vV | sed -n 's/^host: //p')/bin/rust-lld"
+# Paths in the PDB are made absolute as if the repository was at `C:\declaration_site`
+"$lld" -flavor link /dll /noentry /nodefaultlib /debug /export:entry \
+    '/pdbsourcepath:C:\declaration_site' /pdbaltpath:pdb_fixture.pdb \
+    /out:fixtures/pdb/pdb_fixture.dll /pdb:fixtures/pdb/pdb_fixture.pdb fixtures/pdb/pdb_fixture.obj
+rm fixtures/pdb/pdb_fixture.obj fixtures/pdb/pdb_fixture.dll fixtures/pdb/pdb_fixture.lib
@@ -0,0 +1,99 @@
+//! The source of `pdb_fixture.pdb`, a PDB built by rustc for the
+//! `x86_64-pc-windows-msvc` target, used by the `pdb` example.
+//!
+//! This is `no_core`, so that it can be built without the standard library
+//! of that target. See `build.sh`.
+
+#![feature(no_core, lang_items, auto_traits)]
+#![allow(internal_features, unused_variables)]
+#![no_core]
+#![crate_type = "cdylib"]
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+#[lang = "copy"]
+pub trait Copy {}
+#[lang = "freeze"]
+pub unsafe auto trait Freeze {}
+#[lang = "legacy_receiver"]
+pub trait LegacyReceiver {}
+#[lang = "add"]
+pub trait Add<Rhs = Self> {
+    type Output;
+    fn add(self, rhs: Rhs) -> Self::Output;
+}
+
+impl Copy for u32 {}
+impl<T: PointeeSized> Copy for &T {}
+impl<T: PointeeSized> LegacyReceiver for &T {}
+impl Add for u32 {
+    type Output = u32;
+    fn add(self, rhs: u32) -> u32 {
+        self + rhs
+    }
+}
+
+pub trait Shape {
+    fn area(&self) -> u32;
+}
+
+pub struct Square(u32);
+
+impl Shape for Square {
+    #[inline(never)]
+    fn area(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Square {
+    #[inline(never)]
+    pub fn side(&self) -> u32 {
+        self.0
+    }
+}
+
+pub struct Wrapper<T>(T);
+
+impl<T: Shape> Shape for Wrapper<T> {
+    #[inline(never)]
+    fn area(&self) -> u32 {
+        self.0.area()
+    }
+}
+
+impl<T> Wrapper<T> {
+    #[inline(never)]
+    pub fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+pub mod nested {
+    #[inline(never)]
+    pub fn free_function(x: u32) -> u32 {
+        x
+    }
+}
+
+#[inline(never)]
+pub fn generic<T>(value: &T, pair: (u32, &str)) -> u32 {
+    pair.0
+}
+
+#[no_mangle]
+pub extern "C" fn entry(x: u32) -> u32 {
+    let square = Square(x);
+    let wrapper = Wrapper(Square(x));
+    nested::free_function(square.side())
+        + square.area()
+        + wrapper.area()
+        + wrapper.inner().side()
+        + generic::<[u32; 2]>(&[x, x], (x, "a"))
+        + generic::<(&u32, *const Square)>(&(&x, &wrapper.0), (x, "b"))
+        + generic::<&str>(&"c", (x, "c"))
+}
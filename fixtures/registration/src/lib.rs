@@ -0,0 +1,44 @@
+//! A registry of systems, which wraps each system in a closure of its own
+//! before storing it, as frameworks often do to adapt the signatures of the
+//! functions registered with them.
+
+use declaration_site::{declaration_of, DeclarationSite, RegistrationInfo};
+
+/// The systems registered so far.
+#[derive(Default)]
+pub struct Registry {
+    systems: Vec<(Box<dyn Fn() -> u32>, RegistrationInfo)>,
+}
+
+impl Registry {
+    /// Register `system`, recording where it was registered and declared.
+    #[track_caller]
+    pub fn register<F: Fn() -> u32 + 'static>(&mut self, system: F) -> &RegistrationInfo {
+        let wrapped = move || system() + 1;
+        let info = RegistrationInfo::of(&wrapped);
+        self.systems.push((Box::new(wrapped), info));
+        &self.systems.last().unwrap().1
+    }
+
+    /// Register `system` without resolving the function it wraps, finding
+    /// the declaration site of the wrapper instead.
+    pub fn register_naively<F: Fn() -> u32 + 'static>(
+        &mut self,
+        system: F,
+    ) -> Option<DeclarationSite> {
+        let wrapped = move || system() + 1;
+        let site = declaration_of(&wrapped);
+        self.register(wrapped);
+        site
+    }
+
+    /// The registration of each system, in the order they were registered.
+    pub fn registrations(&self) -> impl Iterator<Item = &RegistrationInfo> {
+        self.systems.iter().map(|(_, info)| info)
+    }
+
+    /// Run every system, returning the sum of their results.
+    pub fn run(&self) -> u32 {
+        self.systems.iter().map(|(system, _)| system()).sum()
+    }
+}
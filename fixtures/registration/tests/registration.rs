@@ -0,0 +1,93 @@
+//! Registers functions from this crate with the registry in
+//! `registration_fixture`, which wraps them in closures of its own.
+
+use declaration_site::{declaration_of_registered, register_with_site};
+use registration_fixture::Registry;
+
+#[inline(never)]
+fn spawn_enemies() -> u32 {
+    std::hint::black_box(1)
+}
+
+mod physics {
+    #[inline(never)]
+    pub fn step() -> u32 {
+        std::hint::black_box(2)
+    }
+}
+
+/// Assert that `site` is in this file, on `line`.
+#[track_caller]
+fn assert_site(site: Option<&declaration_site::DeclarationSite>, line: u32) {
+    let site = site.expect("Should have found the declaration site");
+    assert!(site.file.ends_with("registration.rs"), "{site}");
+    assert_eq!(site.line, line, "{site}");
+}
+
+#[test]
+fn resolves_functions_through_wrappers() {
+    let mut registry = Registry::default();
+    let info = registry.register(spawn_enemies).clone();
+    assert_eq!(info.name, "registration::spawn_enemies");
+    assert_eq!(info.registered_at.line(), line!() - 2);
+    assert_site(info.declaration.as_ref(), 8);
+
+    let info = registry.register(physics::step);
+    assert_eq!(info.name, "registration::physics::step");
+    assert_site(info.declaration.as_ref(), 14);
+    assert_eq!(registry.run(), 5);
+}
+
+#[test]
+fn naive_lookup_finds_the_wrapper() {
+    let mut registry = Registry::default();
+    // The wrapper is either not found, or found in the registry
+    if let Some(naive) = registry.register_naively(spawn_enemies) {
+        assert!(naive.file.ends_with("lib.rs"), "{naive}");
+    }
+    // Resolved through both of the registry's closures
+    let info = registry.registrations().next().unwrap();
+    assert_eq!(info.name, "registration::spawn_enemies");
+    assert_site(info.declaration.as_ref(), 8);
+    assert_eq!(registry.run(), 3);
+}
+
+#[test]
+fn tells_closures_apart_by_registration_site() {
+    let mut registry = Registry::default();
+    registry.register(|| std::hint::black_box(10));
+    let declared_before = || std::hint::black_box(20);
+    registry.register(declared_before);
+    // The closure isn't on the line of the registration
+    registry.register(
+        || std::hint::black_box(30), // Keep on its own line
+    );
+    let lines = registry
+        .registrations()
+        .map(|info| info.declaration.as_ref().map(|it| it.line))
+        .collect::<Vec<_>>();
+    let first = line!() - 11;
+    assert_eq!(
+        lines,
+        [Some(first), Some(first + 1), Some(first + 5)],
+        "{:#?}",
+        registry.registrations().collect::<Vec<_>>()
+    );
+    assert_eq!(registry.run(), 63);
+}
+
+#[test]
+fn register_with_site_captures_both_sites() {
+    let mut registry = Registry::default();
+    let info = register_with_site!(registry, spawn_enemies);
+    assert_eq!(info.registered_at.line(), line!() - 1);
+    assert!(info.registered_at.file().ends_with("registration.rs"));
+    assert_site(info.declaration.as_ref(), 8);
+    println!("{info}");
+}
+
+#[test]
+fn declaration_of_registered_without_wrappers() {
+    assert_site(declaration_of_registered(&physics::step).as_ref(), 14);
+    assert_site(declaration_of_registered(&&Some(spawn_enemies)).as_ref(), 8);
+}
@@ -0,0 +1,31 @@
+use declaration_site::explain_declaration_by_name;
+
+mod first {
+    #[inline(never)]
+    pub fn run(x: u64) -> u64 {
+        thinlto_dep::shared(x) + thinlto_dep::shared(x * 3)
+    }
+}
+
+mod second {
+    #[inline(never)]
+    pub fn run(x: u64) -> u64 {
+        thinlto_dep::shared(x ^ 5) + thinlto_dep::shared(x * 7)
+    }
+}
+
+fn main() {
+    let x = std::hint::black_box(11);
+    println!("{}", first::run(x) + second::run(x) + thinlto_dep::own_use(x));
+
+    let choice = explain_declaration_by_name("thinlto_dep::shared")
+        .expect("Should have found `thinlto_dep::shared`");
+    println!("{choice:#?}");
+    assert!(
+        choice.candidates > 1,
+        "Expected copies of `thinlto_dep::shared` in several codegen units"
+    );
+    assert_eq!(choice.in_own_crate, Some(true));
+    let site = choice.site.expect("Should have found source locations");
+    assert!(site.file.ends_with("lib.rs"), "{site}");
+}
@@ -0,0 +1,15 @@
+/// Recursive, so that it isn't inlined, and generic, so that it is compiled
+/// into the codegen units of every crate which uses it.
+#[inline]
+pub fn shared<T: Into<u64>>(x: T) -> u64 {
+    let x = x.into();
+    if std::hint::black_box(x) < 3 {
+        return x;
+    }
+    shared(x / 3) + shared(x / 5)
+}
+
+#[inline(never)]
+pub fn own_use(x: u64) -> u64 {
+    shared(x + 1)
+}
@@ -0,0 +1,55 @@
+use declaration_site::{
+    declaration_by_name_with_metadata, explain_declaration_by_name,
+    explain_declaration_by_name_all, DeclarationSearch,
+};
+
+fn main() {
+    let x = std::hint::black_box(42);
+    println!(
+        "{}",
+        util::helpers::clamp(x) + versions_middle::clamp_with_old_util(x)
+    );
+
+    // Both versions have the same `type_name`
+    let name = std::any::type_name_of_val(&util::helpers::clamp);
+    assert_eq!(name, "util::helpers::clamp");
+
+    let choice = explain_declaration_by_name(name).expect("Should have found `clamp`");
+    println!("{choice:#?}");
+    let version = choice.version.expect("Should have found the version");
+    let expected = if version == "0.1.0" {
+        ["0.2.0"]
+    } else {
+        ["0.1.0"]
+    };
+    assert_eq!(
+        choice.other_versions, expected,
+        "Expected both versions to match"
+    );
+
+    let all = explain_declaration_by_name_all(name);
+    let mut versions = all
+        .iter()
+        .filter_map(|it| it.version.as_deref())
+        .collect::<Vec<_>>();
+    versions.dedup();
+    assert_eq!(versions, ["0.1.0", "0.2.0"]);
+
+    for version in ["0.1.0", "0.2.0"] {
+        let site = DeclarationSearch::new(name)
+            .version(version)
+            .find()
+            .expect("Should have found `clamp`");
+        println!("`{name}` from util {version} is at {site}");
+        assert!(site.file.contains(&format!("util-{version}")), "{site}");
+    }
+
+    // This package depends on util 0.2 directly
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .exec()
+        .expect("Should have run `cargo metadata`");
+    let site =
+        declaration_by_name_with_metadata(name, &metadata).expect("Should have found `clamp`");
+    assert!(site.file.contains("util-0.2.0"), "{site}");
+}
@@ -0,0 +1,4 @@
+#[inline(never)]
+pub fn clamp_with_old_util(x: i64) -> i64 {
+    util::helpers::clamp(x)
+}
@@ -0,0 +1,6 @@
+pub mod helpers {
+    #[inline(never)]
+    pub fn clamp(x: i64) -> i64 {
+        x.clamp(0, 10)
+    }
+}
@@ -0,0 +1,9 @@
+pub mod helpers {
+    /// Unlike in 0.1, the upper bound is configurable.
+    pub const MAX: i64 = 100;
+
+    #[inline(never)]
+    pub fn clamp(x: i64) -> i64 {
+        x.clamp(0, MAX)
+    }
+}
@@ -0,0 +1,25 @@
+//! Searches arbitrary bytes as an object file, which must never panic.
+//!
+//! Run with `cargo +nightly fuzz run parse_bytes` from the repository root.
+
+#![no_main]
+
+use declaration_site::{for_functions_in_bytes, with_scan_limits, DeclarationIndex, ScanLimits};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Small limits, so that the limits themselves are also exercised
+    let limits = ScanLimits {
+        max_lines_per_function: 64,
+        max_functions_per_object: 256,
+    };
+    let _ = with_scan_limits(limits, || {
+        let _ = for_functions_in_bytes(data, |function| {
+            let _ = function.declaration_site(Default::default());
+            let _ = function.lines().count();
+            let _ = function.inlinees().count();
+            let _ = function.compilation_unit();
+        });
+        let _ = DeclarationIndex::from_bytes(data);
+    });
+});
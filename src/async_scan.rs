@@ -0,0 +1,286 @@
+//! Running scans on tokio's blocking threads, so that they don't block the
+//! async runtime.
+
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    io, panic,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{
+    cancellation, declaration_by_name, file_access, limits, low_allocation, pairing,
+    with_cancellation, with_debug_pairing, with_low_allocation_scans, with_scan_limits,
+    with_scan_progress, CancellationToken, DebugPairing, DeclarationIndex, DeclarationSite,
+    FileAccess, ScanLimits, ScanProgress,
+};
+
+/// A scan running on a blocking thread, started by [`spawn_scan`], which
+/// resolves to its result, or to [`ScanCancelled`] if it was stopped by
+/// [`cancel`](Self::cancel) before it finished.
+///
+/// Dropping this (such as when it is the losing branch of
+/// `tokio::select!`, or exceeds `tokio::time::timeout`) cancels the scan,
+/// which stops at its next checkpoint (see [`with_cancellation`]), so the
+/// blocking thread is freed soon after.
+#[must_use = "the scan is cancelled when this is dropped"]
+pub struct ScanTask<T> {
+    /// The result of the scan, and whether it stopped as it was cancelled
+    handle: JoinHandle<(T, bool)>,
+    token: CancellationToken,
+    progress: Option<mpsc::UnboundedReceiver<ScanProgress>>,
+}
+
+impl<T> ScanTask<T> {
+    /// The progress of the scan, as a [`Stream`] which ends when the scan
+    /// does.
+    ///
+    /// The stream can only be taken once, so later calls return an empty
+    /// stream. Progress which isn't read is kept until the stream is dropped.
+    pub fn progress(&mut self) -> ScanProgressStream {
+        ScanProgressStream(self.progress.take())
+    }
+
+    /// Stop the scan at its next checkpoint, without waiting for it.
+    ///
+    /// What the scan found before stopping is incomplete, so this then
+    /// resolves to [`ScanCancelled`]. If the scan had already finished, this
+    /// still resolves to its result.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+}
+
+impl<T> Future for ScanTask<T> {
+    type Output = Result<T, ScanCancelled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.handle)
+            .poll(cx)
+            .map(|result| match result {
+                Ok((_, true)) => Err(ScanCancelled),
+                Ok((it, false)) => Ok(it),
+                // The scan panicked, or the runtime is shutting down
+                Err(e) => match e.try_into_panic() {
+                    Ok(payload) => panic::resume_unwind(payload),
+                    Err(e) => panic!("the scan was stopped by the runtime: {e}"),
+                },
+            })
+    }
+}
+
+impl<T> Drop for ScanTask<T> {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// The error a [`ScanTask`] resolves to if its scan was stopped by
+/// [`ScanTask::cancel`] before it finished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanCancelled;
+
+impl fmt::Display for ScanCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the scan was cancelled")
+    }
+}
+
+impl Error for ScanCancelled {}
+
+/// The progress of a [`ScanTask`], from [`ScanTask::progress`].
+pub struct ScanProgressStream(Option<mpsc::UnboundedReceiver<ScanProgress>>);
+
+impl ScanProgressStream {
+    /// The next progress of the scan, or `None` once the scan has ended.
+    ///
+    /// This is the same as [`StreamExt::next`](https://docs.rs/futures/latest/futures/stream/trait.StreamExt.html#method.next),
+    /// without depending on `futures`.
+    pub async fn next(&mut self) -> Option<ScanProgress> {
+        match &mut self.0 {
+            Some(receiver) => receiver.recv().await,
+            None => None,
+        }
+    }
+}
+
+impl Stream for ScanProgressStream {
+    type Item = ScanProgress;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ScanProgress>> {
+        match &mut self.0 {
+            Some(receiver) => receiver.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+/// Run `scan` on one of tokio's blocking threads, with its progress reported
+/// and cancellation checked as by [`with_scan_progress`] and
+/// [`with_cancellation`].
+///
+/// The settings made for the current thread when this is called, by
+/// [`with_file_access`](crate::with_file_access), [`with_debug_pairing`],
+/// [`with_scan_limits`] and [`with_low_allocation_scans`], apply to `scan`
+/// on the blocking thread too. What the scan processed isn't counted in the
+/// statistics of the current `with_scan_limits`, which may have returned
+/// before the scan runs, so call `with_scan_limits` inside `scan` to get
+/// them, as below.
+///
+/// # Panics
+///
+/// If called outside of a tokio runtime. If `scan` panics, the task panics
+/// with the same payload when awaited
+///
+/// ```rust,no_run
+/// use declaration_site::{spawn_scan, with_scan_limits, DeclarationIndex, ScanLimits};
+///
+/// # async fn run() {
+/// let limits = ScanLimits {
+///     max_functions_per_object: 1000,
+///     ..ScanLimits::default()
+/// };
+/// let (index, stats) = spawn_scan(move || {
+///     with_scan_limits(limits, || DeclarationIndex::from_file("target/debug/my_app"))
+/// })
+/// .await
+/// .unwrap();
+/// # }
+/// ```
+pub fn spawn_scan<T: Send + 'static>(scan: impl FnOnce() -> T + Send + 'static) -> ScanTask<T> {
+    let token = CancellationToken::new();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let scan_token = token.clone();
+    let settings = ThreadSettings::current();
+    let handle = tokio::task::spawn_blocking(move || {
+        settings.apply(|| {
+            with_cancellation(&scan_token, || {
+                with_scan_progress(
+                    move |progress| {
+                        // The progress stream may have been dropped
+                        let _ = sender.send(progress);
+                    },
+                    || cancellation::track_interruption(scan),
+                )
+            })
+        })
+    });
+    ScanTask {
+        handle,
+        token,
+        progress: Some(receiver),
+    }
+}
+
+/// The settings of scans made for the current thread, to be applied to the
+/// scans started on other threads on its behalf.
+struct ThreadSettings {
+    file_access: Option<Arc<dyn FileAccess>>,
+    debug_pairing: DebugPairing,
+    limits: ScanLimits,
+    max_file_len: Option<u64>,
+}
+
+impl ThreadSettings {
+    /// The settings of the current thread.
+    fn current() -> Self {
+        ThreadSettings {
+            file_access: file_access::current_override(),
+            debug_pairing: pairing::current(),
+            limits: limits::current(),
+            max_file_len: low_allocation::max_file_len(),
+        }
+    }
+
+    /// Run `f` with these settings applied to the current thread.
+    fn apply<R>(self, f: impl FnOnce() -> R) -> R {
+        let ThreadSettings {
+            file_access,
+            debug_pairing,
+            limits,
+            max_file_len,
+        } = self;
+        let f = move || with_debug_pairing(debug_pairing, || with_scan_limits(limits, f).0);
+        let f = move || match max_file_len {
+            Some(max_file_len) => with_low_allocation_scans(max_file_len, f),
+            None => f(),
+        };
+        match file_access {
+            Some(access) => file_access::with_shared_file_access(access, f),
+            None => f(),
+        }
+    }
+}
+
+/// Find the declaration site of the function `name` as
+/// [`declaration_by_name`], on one of tokio's blocking threads.
+///
+/// The lookup is cancelled if the task is dropped before it resolves, such as
+/// on a timeout.
+///
+/// # Panics
+///
+/// If called outside of a tokio runtime
+///
+/// ```rust
+/// use declaration_site::{declaration_by_name_async, ScanCancelled};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// fn my_function() {}
+///
+/// let name = std::any::type_name_of_val(&my_function);
+/// match tokio::time::timeout(Duration::from_secs(10), declaration_by_name_async(name)).await {
+///     Ok(Ok(Some(site))) => println!("{site}"),
+///     Ok(Ok(None)) => println!("{name} wasn't found"),
+///     // Nothing calls `ScanTask::cancel` here
+///     Ok(Err(ScanCancelled)) => unreachable!(),
+///     // The lookup was cancelled when its task was dropped
+///     Err(_) => println!("{name} took too long to find"),
+/// }
+/// # }
+/// ```
+pub fn declaration_by_name_async(name: &str) -> ScanTask<Option<DeclarationSite>> {
+    let name = name.to_owned();
+    spawn_scan(move || declaration_by_name(&name))
+}
+
+impl DeclarationIndex {
+    /// Index the functions in the binary or debug file at `path` as
+    /// [`from_file`](Self::from_file), on one of tokio's blocking threads.
+    ///
+    /// Building the index is cancelled if the task is dropped before it
+    /// resolves.
+    ///
+    /// # Panics
+    ///
+    /// If called outside of a tokio runtime
+    ///
+    /// ```rust
+    /// use declaration_site::DeclarationIndex;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut task = DeclarationIndex::build_async(std::env::current_exe().unwrap());
+    /// let mut progress = task.progress();
+    /// tokio::spawn(async move {
+    ///     while let Some(progress) = progress.next().await {
+    ///         println!("{progress:?}");
+    ///     }
+    /// });
+    /// let index = task.await.unwrap().unwrap();
+    /// # }
+    /// ```
+    pub fn build_async(path: impl Into<PathBuf>) -> ScanTask<io::Result<Self>> {
+        let path = path.into();
+        spawn_scan(move || DeclarationIndex::from_file(path))
+    }
+}
@@ -0,0 +1,207 @@
+//! Finding which crate a declaration site is in.
+
+#[cfg(feature = "cargo-metadata")]
+use std::path::Path;
+
+#[cfg(feature = "cargo-metadata")]
+use crate::DeclarationSite;
+#[cfg(all(feature = "cargo-metadata", feature = "process"))]
+use crate::{declaration_by_name, names, DeclarationSearch};
+
+/// The crate a declaration site is in, from [`attribute_dependency_path`] or
+/// `attribute_site`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CrateAttribution {
+    /// The name of the crate's package. For git dependencies found without
+    /// metadata, this is the name of the repository instead
+    pub name: String,
+    /// The version of the crate's package, if known
+    pub version: Option<String>,
+    /// The path of the declaration site's file within the crate's package,
+    /// using `/` as the separator
+    pub path: String,
+    pub source: CrateSource,
+}
+
+/// Where a crate's source code came from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CrateSource {
+    /// A registry, such as crates.io
+    Registry,
+    /// A git repository
+    Git,
+    /// A local path, including the crates in the current workspace
+    Path,
+    /// The standard library (`std`, `core`, `alloc`, etc.)
+    Std,
+}
+
+/// Find which crate the file at `path` is in, from the layout of cargo's
+/// download directories, without needing any metadata.
+///
+/// This recognises:
+/// - Registry dependencies, in `registry/src/<registry>/<name>-<version>/`
+/// - Git dependencies, in `git/checkouts/<repository>-<hash>/<revision>/`
+/// - The standard library, in `/rustc/<commit>/library/<crate>/`
+///
+/// Other paths (such as those of the crates in your workspace) return `None`.
+///
+/// ```rust
+/// # use declaration_site::{attribute_dependency_path, CrateSource};
+/// let attribution = attribute_dependency_path(
+///     "/home/me/.cargo/registry/src/index.crates.io-6f17d22bba15001f/tokio-1.35.1/src/runtime/mod.rs",
+/// )
+/// .unwrap();
+/// assert_eq!(attribution.name, "tokio");
+/// assert_eq!(attribution.version.as_deref(), Some("1.35.1"));
+/// assert_eq!(attribution.path, "src/runtime/mod.rs");
+/// assert_eq!(attribution.source, CrateSource::Registry);
+/// ```
+pub fn attribute_dependency_path(path: &str) -> Option<CrateAttribution> {
+    let components = path
+        .split(['/', '\\'])
+        .filter(|it| !it.is_empty())
+        .collect::<Vec<_>>();
+    for (index, window) in components.windows(2).enumerate() {
+        let rest = &components[index + 2..];
+        match window {
+            ["registry", "src"] if rest.len() > 2 => {
+                let (name, version) = split_name_version(rest[1])?;
+                return Some(CrateAttribution {
+                    name: name.to_owned(),
+                    version: Some(version.to_owned()),
+                    path: rest[2..].join("/"),
+                    source: CrateSource::Registry,
+                });
+            }
+            ["git", "checkouts"] if rest.len() > 2 => {
+                // The checkout directory is the repository name, followed by a
+                // hash of its url
+                let name = rest[0].rsplit_once('-').map_or(rest[0], |(name, _)| name);
+                return Some(CrateAttribution {
+                    name: name.to_owned(),
+                    version: None,
+                    path: rest[2..].join("/"),
+                    source: CrateSource::Git,
+                });
+            }
+            ["rustc", _] if index == 0 && rest.len() > 2 && rest[0] == "library" => {
+                return Some(CrateAttribution {
+                    name: rest[1].to_owned(),
+                    version: None,
+                    path: rest[2..].join("/"),
+                    source: CrateSource::Std,
+                });
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split a registry directory name such as `serde-json-1.0.0-rc.1` into the
+/// package name and version.
+fn split_name_version(directory: &str) -> Option<(&str, &str)> {
+    directory
+        .match_indices('-')
+        .map(|(index, _)| (&directory[..index], &directory[index + 1..]))
+        .find(|(_, version)| {
+            let mut parts = version.splitn(3, '.');
+            let is_number = |part: Option<&str>| {
+                part.map(|it| it.split(|c: char| !c.is_ascii_digit()).next().unwrap_or(""))
+                    .is_some_and(|it| !it.is_empty())
+            };
+            is_number(parts.next()) && is_number(parts.next()) && is_number(parts.next())
+        })
+}
+
+/// Find which package in `metadata` contains `site`, enabled by the
+/// `cargo-metadata` feature.
+///
+/// Relative paths (as used in the debug info for crates in the current
+/// workspace) are resolved against the workspace root. If no package contains
+/// the site, this falls back to [`attribute_dependency_path`].
+#[cfg(feature = "cargo-metadata")]
+pub fn attribute_site(
+    site: &DeclarationSite,
+    metadata: &cargo_metadata::Metadata,
+) -> Option<CrateAttribution> {
+    let file = Path::new(&site.file);
+    let file = if file.is_relative() {
+        metadata.workspace_root.as_std_path().join(file)
+    } else {
+        file.to_owned()
+    };
+    metadata
+        .packages
+        .iter()
+        .filter_map(|package| {
+            let root = package.manifest_path.parent()?.as_std_path();
+            Some((package, root, file.strip_prefix(root).ok()?))
+        })
+        // Packages can be nested inside other packages, so use the innermost
+        .max_by_key(|(_, root, _)| root.components().count())
+        .map(|(package, _, path)| CrateAttribution {
+            name: package.name.to_string(),
+            version: Some(package.version.to_string()),
+            path: path_string(path),
+            source: match &package.source {
+                None => CrateSource::Path,
+                Some(source) if source.repr.starts_with("git+") => CrateSource::Git,
+                Some(_) => CrateSource::Registry,
+            },
+        })
+        .or_else(|| attribute_dependency_path(&site.file))
+}
+
+/// Attempt to get the declaration site of a currently loaded function with
+/// the given (unmangled) name, preferring the version of its crate which the
+/// package described by `metadata` depends on, enabled by the
+/// `cargo-metadata` and `process` features.
+///
+/// This is [`DeclarationSearch::version`], using the version of the
+/// function's crate which the root package of `metadata` (or else, the first
+/// workspace member which has one) depends on directly. This is useful when
+/// several versions of the crate are linked through different dependency
+/// paths. If no such dependency is found, this is [`declaration_by_name`].
+///
+/// See also "Caveats" in the [module level documentation](crate).
+#[cfg(all(feature = "cargo-metadata", feature = "process"))]
+pub fn declaration_by_name_with_metadata(
+    name: &str,
+    metadata: &cargo_metadata::Metadata,
+) -> Option<DeclarationSite> {
+    let canonical = names::canonical_name(name);
+    match names::crate_name(&canonical).and_then(|krate| dependency_version(metadata, krate)) {
+        Some(version) => DeclarationSearch::new(name).version(&version).find(),
+        None => declaration_by_name(name),
+    }
+}
+
+/// The version of the crate `krate` which the root package of `metadata`, or
+/// else the first workspace member which has one, depends on directly.
+#[cfg(all(feature = "cargo-metadata", feature = "process"))]
+fn dependency_version(metadata: &cargo_metadata::Metadata, krate: &str) -> Option<String> {
+    let resolve = metadata.resolve.as_ref()?;
+    let root = resolve.root.iter();
+    root.chain(&metadata.workspace_members).find_map(|member| {
+        let node = resolve.nodes.iter().find(|node| node.id == *member)?;
+        // Dependencies can be renamed, so compare the name of their library
+        node.deps.iter().find_map(|dependency| {
+            let package = metadata
+                .packages
+                .iter()
+                .find(|it| it.id == dependency.pkg)?;
+            let library = package.targets.iter().find(|target| target.is_lib())?;
+            (library.name.replace('-', "_") == krate).then(|| package.version.to_string())
+        })
+    })
+}
+
+#[cfg(feature = "cargo-metadata")]
+fn path_string(path: &Path) -> String {
+    path.components()
+        .map(|it| it.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
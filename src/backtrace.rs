@@ -0,0 +1,237 @@
+//! Adding declaration sites to [`Backtrace`]s.
+
+use std::{backtrace::Backtrace, borrow::Cow, fmt};
+
+use crate::{
+    declaration_by_name, declarations_by_name, names, pdb_names, redaction, DeclarationSite,
+};
+
+/// Get the name of the function from a line of a formatted [`Backtrace`], if
+/// the line is the start of a frame.
+///
+/// Frames are formatted as the index of the frame, followed by the function's
+/// name, such as `  12: my_crate::my_function`. Full backtraces (with
+/// `RUST_BACKTRACE=full`) also print the address of the frame, and the legacy
+/// mangling hash of the function, such as
+/// `  12:     0x55d1c2a3b4c5 - my_crate::my_function::h0123456789abcdef`.
+pub(crate) fn frame_function_name(line: &str) -> Option<&str> {
+    let (index, name) = line.trim_start().split_once(": ")?;
+    if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut name = name.trim();
+    if let Some((address, rest)) = name.split_once(" - ") {
+        let is_address = address
+            .strip_prefix("0x")
+            .is_some_and(|hex| !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()));
+        if is_address {
+            name = rest.trim_start();
+        }
+    }
+    let name = names::strip_hash_suffix(name);
+    (!name.is_empty() && name != "<unknown>").then_some(name)
+}
+
+/// The name to look up for the function named `name` in a frame.
+///
+/// Backtraces on Windows spell generic arguments and closures as in PDBs,
+/// such as `my_crate::main::closure$0`.
+fn frame_query(name: &str) -> Cow<'_, str> {
+    if name.contains('$') {
+        pdb_names::normalize_msvc_name(name)
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Attempt to get the declaration site of the function in a line of a
+/// formatted backtrace, such as from a log.
+///
+/// The line should be the start of a frame as printed by [`Backtrace`] and the
+/// default panic hook, which is the index of the frame followed by the name of
+/// its function. The address and legacy mangling hash printed in full
+/// backtraces (with `RUST_BACKTRACE=full`), and the spelling of generic
+/// functions and closures in backtraces on Windows, are handled. Other lines
+/// (such as the `at` lines giving the location in each frame) return `None`.
+///
+/// The function is looked up in the current process with
+/// [`declaration_by_name`], so the backtrace should be from the same build of
+/// the same program.
+///
+/// ```rust,no_run
+/// use declaration_site::declaration_from_backtrace_frame_text;
+///
+/// let line = "  12:     0x55d1c2a3b4c5 - my_game::player::update_player::h0123456789abcdef";
+/// if let Some(site) = declaration_from_backtrace_frame_text(line) {
+///     println!("`update_player` is declared at {site}");
+/// }
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declaration_from_backtrace_frame_text(line: &str) -> Option<DeclarationSite> {
+    declaration_by_name(&frame_query(frame_function_name(line)?))
+}
+
+/// Attempt to get the declaration site of the function which calls this, without
+/// naming it.
+///
+/// The function is found by capturing a [`Backtrace`], and looking up the
+/// function in the frame which called this with [`declaration_by_name`]. This
+/// is as slow as capturing a backtrace and scanning for a function, so the
+/// result should be cached if it is needed often, such as in a macro which
+/// registers the function it was invoked in.
+///
+/// Backtraces report functions which were inlined as frames of their own when
+/// the debug info records the inlining, in which case the inlined function is
+/// still found (see [`LineProvenance::Inlinee`](crate::LineProvenance::Inlinee)).
+/// Otherwise, the nearest function which wasn't inlined is reported. Mark the
+/// calling function `#[inline(never)]` for exact results.
+///
+/// ```rust,no_run
+/// use declaration_site::current_function_declaration;
+///
+/// #[inline(never)]
+/// fn register_system() {
+///     let site = current_function_declaration().unwrap();
+///     println!("`register_system` is declared at {site}");
+/// }
+/// # register_system();
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+#[inline(never)]
+pub fn current_function_declaration() -> Option<DeclarationSite> {
+    let backtrace = Backtrace::force_capture().to_string();
+    declaration_by_name(caller_name(&backtrace)?)
+}
+
+/// The name of the function in the frame after that of
+/// [`current_function_declaration`] in the formatted backtrace `text`.
+fn caller_name(text: &str) -> Option<&str> {
+    let mut frames = text.lines().filter_map(frame_function_name);
+    frames.find(|name| {
+        names::crate_name(name) == Some("declaration_site")
+            && names::last_ident(name) == "current_function_declaration"
+    })?;
+    frames.next()
+}
+
+/// Get the declaration sites of the functions in each frame of `backtrace`.
+///
+/// All of the functions are found in a single scan. Frames whose functions
+/// cannot be found (such as those from libraries without debug info, or which
+/// have been inlined) are still included, but have no declaration site.
+///
+/// As [`Backtrace`] doesn't provide access to its frames on stable Rust, the
+/// function names are extracted from its [`Display`](fmt::Display)
+/// implementation.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn annotate_backtrace(backtrace: &Backtrace) -> AnnotatedBacktrace {
+    declarations_from_backtrace_text(&backtrace.to_string())
+}
+
+/// Get the declaration sites of the functions in each frame of a formatted
+/// backtrace, such as one pasted from a log, as in [`annotate_backtrace`].
+///
+/// Each line which starts a frame is handled as in
+/// [`declaration_from_backtrace_frame_text`], and the other lines are kept.
+/// All of the functions are found in a single scan of the current process,
+/// so the backtrace should be from the same build of the same program.
+///
+/// ```rust,no_run
+/// use declaration_site::declarations_from_backtrace_text;
+///
+/// let text = "\
+/// stack backtrace:
+///    0: my_game::player::update_player
+///              at ./src/player.rs:40:13
+///    1: my_game::main
+///              at ./src/main.rs:12:5";
+/// let annotated = declarations_from_backtrace_text(text);
+/// for (name, site) in annotated.declarations() {
+///     println!("`{name}` is declared at {site}");
+/// }
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declarations_from_backtrace_text(text: &str) -> AnnotatedBacktrace {
+    let lines = text.lines().map(redact_location).collect::<Vec<_>>();
+    let queries = lines
+        .iter()
+        .filter_map(|line| frame_function_name(line))
+        .map(frame_query)
+        .collect::<Vec<_>>();
+    let names = queries.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+    let mut sites = declarations_by_name(&names).into_iter();
+    let frames = lines
+        .iter()
+        .map(|line| {
+            frame_function_name(line)
+                .is_some()
+                .then(|| sites.next().flatten())
+                .flatten()
+        })
+        .collect();
+    AnnotatedBacktrace { lines, frames }
+}
+
+/// The line `line` of a backtrace, with the path of its location redacted if
+/// it is an `at` line.
+fn redact_location(line: &str) -> String {
+    let location = line.trim_start();
+    match location.strip_prefix("at ") {
+        Some(path) => {
+            let indent = &line[..line.len() - location.len()];
+            format!("{indent}at {}", redaction::path(path))
+        }
+        None => line.to_owned(),
+    }
+}
+
+/// A [`Backtrace`] with the declaration sites of the functions in its frames,
+/// created using [`annotate_backtrace`] or
+/// [`declarations_from_backtrace_text`].
+///
+/// The [`Display`](fmt::Display) implementation prints the backtrace, with
+/// the declaration site of each function which was found after its frame.
+#[derive(Clone, Debug)]
+pub struct AnnotatedBacktrace {
+    lines: Vec<String>,
+    /// The declaration site found for the frame started on the line at the
+    /// same index in `lines`, if any
+    frames: Vec<Option<DeclarationSite>>,
+}
+
+impl AnnotatedBacktrace {
+    /// The declaration sites which were found, and the names of the functions
+    /// they are for, in the order of the frames of the backtrace.
+    pub fn declarations(&self) -> impl Iterator<Item = (&str, &DeclarationSite)> {
+        self.lines
+            .iter()
+            .zip(&self.frames)
+            .filter_map(|(line, site)| Some((frame_function_name(line)?, site.as_ref()?)))
+    }
+}
+
+impl fmt::Display for AnnotatedBacktrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut pending = None;
+        for (line, site) in self.lines.iter().zip(&self.frames) {
+            // The declaration goes after the `at` lines of the previous frame
+            if frame_function_name(line).is_some() || !line.starts_with(char::is_whitespace) {
+                if let Some(site) = pending.take() {
+                    writeln!(f, "             declared at {site}")?;
+                }
+            }
+            writeln!(f, "{line}")?;
+            if site.is_some() {
+                pending = site.as_ref();
+            }
+        }
+        if let Some(site) = pending {
+            writeln!(f, "             declared at {site}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! Helpers for finding the declaration sites of [bevy](https://bevyengine.org/)
+//! systems, enabled by the `bevy` feature.
+//!
+//! The names of systems in bevy are (by default) the [type
+//! names](core::any::type_name) of the functions they are created from, which
+//! can be looked up using [`declaration_by_name`](crate::declaration_by_name).
+//! However, systems combined from other systems (such as with `pipe` or run
+//! condition combinators) have names built from the names of their parts, such
+//! as `Pipe(my_game::a, my_game::b)`. [`system_function_names`] extracts the
+//! names of the functions from these.
+//!
+//! Note that system names are only available if bevy's `debug` feature is
+//! enabled.
+
+use std::collections::HashMap;
+
+use bevy_app::{App, Plugin};
+use bevy_ecs::{prelude::Resource, schedule::Schedules};
+
+use crate::{declarations_by_name, DeclarationSite};
+
+/// Get the names of the functions a system with name `system_name` was created
+/// from.
+///
+/// For systems created directly from a function or closure, this is just the
+/// name of the system. Piped systems (`Pipe(a, b)`) and combined run
+/// conditions (such as `a && b`, or `!(a || b)`) return the names of each of
+/// their parts.
+pub fn system_function_names(system_name: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    collect_function_names(system_name, &mut names);
+    names
+}
+
+fn collect_function_names<'a>(name: &'a str, names: &mut Vec<&'a str>) {
+    let name = name.trim();
+    if name.is_empty() {
+        return;
+    }
+    if let Some(inner) = enclosed(name, "Pipe(") {
+        for part in split_top_level(inner, &[", "]) {
+            collect_function_names(part, names);
+        }
+        return;
+    }
+    if let Some(inner) = enclosed(name, "!(").or_else(|| enclosed(name, "(")) {
+        collect_function_names(inner, names);
+        return;
+    }
+    let parts = split_top_level(name, &[" && ", " || ", " & ", " | ", " ^ "]);
+    if parts.len() > 1 {
+        for part in parts {
+            collect_function_names(part, names);
+        }
+        return;
+    }
+    names.push(name);
+}
+
+/// If `name` is `prefix`, followed by some text, followed by the `)` which
+/// closes the `(` at the end of `prefix`, get that text.
+fn enclosed<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    let inner = name.strip_prefix(prefix)?.strip_suffix(')')?;
+    let mut depth = 0usize;
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            // The opening bracket was closed before the end of `name`
+            ')' => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    Some(inner)
+}
+
+/// Split `input` at each of `separators` which are not nested inside brackets.
+fn split_top_level<'a>(input: &'a str, separators: &[&str]) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut index = 0;
+    'outer: while index < input.len() {
+        let rest = &input[index..];
+        if depth == 0 {
+            for separator in separators {
+                if rest.starts_with(separator) {
+                    parts.push(&input[start..index]);
+                    index += separator.len();
+                    start = index;
+                    continue 'outer;
+                }
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        match c {
+            '(' | '<' | '[' => depth += 1,
+            ')' | '>' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        index += c.len_utf8();
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+/// A cache of the declaration sites of systems, by system name.
+///
+/// Looking up declaration sites is expensive, so this resolves each name at
+/// most once, including names which could not be found. This is added to the
+/// app by [`DeclarationSitePlugin`], so that diagnostics or editor tooling can
+/// query it.
+#[derive(Resource, Default, Debug)]
+pub struct DeclarationSiteCache {
+    sites: HashMap<String, Vec<Option<DeclarationSite>>>,
+}
+
+impl DeclarationSiteCache {
+    /// Get the declaration sites of the functions the system named
+    /// `system_name` was created from, if it has already been resolved.
+    ///
+    /// Returns `None` if the name has not been resolved. Otherwise, the result
+    /// at each index corresponds to the name at the same index of
+    /// [`system_function_names`].
+    pub fn get(&self, system_name: &str) -> Option<&[Option<DeclarationSite>]> {
+        self.sites.get(system_name).map(Vec::as_slice)
+    }
+
+    /// Get the declaration sites of the functions the system named
+    /// `system_name` was created from, resolving them if needed.
+    ///
+    /// See [`DeclarationSiteCache::get`] for the meaning of the result.
+    pub fn resolve(&mut self, system_name: &str) -> &[Option<DeclarationSite>] {
+        self.resolve_all([system_name]);
+        &self.sites[system_name]
+    }
+
+    /// Resolve the declaration sites of each of `system_names`, using a
+    /// single scan for all names which have not already been resolved.
+    pub fn resolve_all<'a>(&mut self, system_names: impl IntoIterator<Item = &'a str>) {
+        let unresolved = system_names
+            .into_iter()
+            .filter(|name| !self.sites.contains_key(*name))
+            .collect::<Vec<_>>();
+        let function_names = unresolved
+            .iter()
+            .flat_map(|name| system_function_names(name))
+            .collect::<Vec<_>>();
+        if unresolved.is_empty() {
+            return;
+        }
+        let mut sites = declarations_by_name(&function_names).into_iter();
+        for name in unresolved {
+            let count = system_function_names(name).len();
+            self.sites
+                .insert(name.to_owned(), sites.by_ref().take(count).collect());
+        }
+    }
+
+    /// Resolve the declaration sites of every system in `schedules`.
+    ///
+    /// Only systems in schedules which have been initialized (for example, by
+    /// being run) can be found.
+    pub fn resolve_schedules(&mut self, schedules: &Schedules) {
+        let names = schedules
+            .iter()
+            .filter_map(|(_, schedule)| schedule.systems().ok())
+            .flatten()
+            .map(|(_, system)| system.name().to_string())
+            .collect::<Vec<_>>();
+        self.resolve_all(names.iter().map(String::as_str));
+    }
+}
+
+/// Adds a [`DeclarationSiteCache`] resource to the app.
+#[derive(Default)]
+pub struct DeclarationSitePlugin;
+
+impl Plugin for DeclarationSitePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeclarationSiteCache>();
+    }
+}
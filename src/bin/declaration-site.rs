@@ -0,0 +1,133 @@
+//! Find the declaration sites of functions in a binary or debug file.
+//!
+//! Usage: `declaration-site [--json] [--prefix PREFIX]... [--regex REGEX]... FILE [NAME]...`
+//!
+//! Prints the declaration site of each function named `NAME`, and of every
+//! function whose name starts with any `PREFIX` or matches any `REGEX`. Exits
+//! with status 1 if any `NAME` wasn't found, or if a `PREFIX` or `REGEX` was
+//! given but no functions matched.
+//!
+//! With `--json`, prints a JSON array of
+//! [`LookupResult`](declaration_site::schema::LookupResult)s instead.
+
+use std::{env, path::PathBuf, process::ExitCode};
+
+use declaration_site::{
+    declarations_by_name_in, for_functions_in_file, schema::LookupResult, DeclarationSite,
+};
+use regex::Regex;
+
+const USAGE: &str =
+    "usage: declaration-site [--json] [--prefix PREFIX]... [--regex REGEX]... FILE [NAME]...";
+
+struct Args {
+    json: bool,
+    prefixes: Vec<String>,
+    regexes: Vec<Regex>,
+    file: PathBuf,
+    names: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut json = false;
+    let mut prefixes = Vec::new();
+    let mut regexes = Vec::new();
+    let mut positional = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--prefix" => prefixes.push(args.next().ok_or("--prefix requires a value")?),
+            "--regex" => {
+                let regex = args.next().ok_or("--regex requires a value")?;
+                regexes.push(Regex::new(&regex).map_err(|e| e.to_string())?);
+            }
+            "-h" | "--help" => return Err(USAGE.into()),
+            "--" => positional.extend(args.by_ref()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{arg}`")),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.is_empty() {
+        return Err("missing FILE".into());
+    }
+    let file = positional.remove(0).into();
+    Ok(Args {
+        json,
+        prefixes,
+        regexes,
+        file,
+        names: positional,
+    })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(it) => it,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let mut results: Vec<(String, Option<DeclarationSite>)> = Vec::new();
+    let mut success = true;
+
+    if !args.names.is_empty() {
+        let names = args.names.iter().map(String::as_str).collect::<Vec<_>>();
+        let sites = match declarations_by_name_in(&args.file, &names) {
+            Ok(it) => it,
+            Err(e) => {
+                eprintln!("could not read {}: {e}", args.file.display());
+                return ExitCode::from(2);
+            }
+        };
+        success &= sites.iter().all(Option::is_some);
+        results.extend(args.names.iter().cloned().zip(sites));
+    }
+
+    if !args.prefixes.is_empty() || !args.regexes.is_empty() {
+        let mut matched = Vec::new();
+        let scanned = for_functions_in_file(&args.file, |function| {
+            let name = function.name();
+            let is_match = args.prefixes.iter().any(|it| name.starts_with(it.as_str()))
+                || args.regexes.iter().any(|it| it.is_match(name));
+            if is_match {
+                matched.push((name.to_owned(), DeclarationSite::try_from(&function).ok()));
+            }
+        });
+        if let Err(e) = scanned {
+            eprintln!("could not read {}: {e}", args.file.display());
+            return ExitCode::from(2);
+        }
+        matched.sort_by(|(a, a_site), (b, b_site)| {
+            let a_site = a_site.as_ref().map(|it| (&it.file, it.line));
+            let b_site = b_site.as_ref().map(|it| (&it.file, it.line));
+            (a, a_site).cmp(&(b, b_site))
+        });
+        matched.dedup();
+        success &= !matched.is_empty();
+        results.extend(matched);
+    }
+
+    if args.json {
+        let results = results
+            .iter()
+            .map(|(name, site)| LookupResult::new(name, site.as_ref()))
+            .collect::<Vec<_>>();
+        let json = serde_json::to_string(&results).expect("Results are always serializable");
+        println!("{json}");
+    } else {
+        for (name, site) in &results {
+            match site {
+                Some(site) => println!("{name} {site}"),
+                None => println!("{name} <not found>"),
+            }
+        }
+    }
+
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,269 @@
+//! Caches of the declaration sites of the functions in the current process:
+//! a process-wide cache of function item types, and [`DeclarationCache`].
+
+use std::{
+    any::TypeId,
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, RwLock},
+};
+
+use crate::{
+    cancellation, declaration, is_scanning, limits, low_allocation, redaction, DeclarationIndex,
+    DeclarationSite, Redaction,
+};
+
+/// The functions found by [`cached_declaration`], by the [`TypeId`] of the
+/// function item type. Entries are leaked so that their sites can be borrowed
+/// for `'static`.
+type Cache = RwLock<HashMap<TypeId, Slot>>;
+
+/// What was found for a function item type.
+#[derive(Clone, Copy)]
+enum Slot {
+    /// The result of a scan which searched everything, which is used for
+    /// every later lookup
+    Complete(Option<&'static Entry>),
+    /// The site found by a scan which didn't search everything, which is
+    /// looked up again, but kept so that finding the same site again doesn't
+    /// leak another entry
+    Partial(&'static Entry),
+}
+
+/// A function found by [`cached_by_type_id`].
+struct Entry {
+    /// The declaration site, without redaction, so that it is still correct
+    /// if the redaction is changed
+    site: DeclarationSite,
+    /// The site redacted by each redaction it has been read with, leaked when
+    /// first read if the redaction changes it
+    redacted: Mutex<Vec<(Arc<Redaction>, &'static DeclarationSite)>>,
+}
+
+impl Entry {
+    /// The declaration site as it is reported, redacted by the current
+    /// redaction, if any.
+    fn site(&'static self) -> &'static DeclarationSite {
+        let Some(redaction) = redaction::current() else {
+            return &self.site;
+        };
+        // Only the first read with each redaction redacts the path, so later
+        // reads don't allocate
+        let mut redacted = self.redacted.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, site)) = redacted.iter().find(|(it, _)| **it == *redaction) {
+            return site;
+        }
+        let site = match redaction.redact(&self.site.file) {
+            Cow::Owned(file) => Box::leak(Box::new(DeclarationSite {
+                file,
+                line: self.site.line,
+            })),
+            Cow::Borrowed(_) => &self.site,
+        };
+        redacted.push((redaction, site));
+        site
+    }
+}
+
+fn cache() -> &'static Cache {
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Attempt to get the declaration site of the function item type `T`, as in
+/// [`declaration`], caching the result for the rest of the process.
+///
+/// The first call for each type scans for the function as [`declaration`]
+/// does. Later calls (from any thread) only look up `T`'s [`TypeId`] in the
+/// cache, so don't build or normalize the type's name, and don't allocate.
+/// This makes it suitable for use every frame, such as in debug overlays.
+///
+/// Each site found is leaked, so it can be borrowed for `'static`. This is
+/// bounded by the number of function item types looked up (and the number of
+/// redactions set by [`set_redaction`](crate::set_redaction) which they are
+/// read with, as redaction is applied when each site is read). Functions
+/// which weren't found are also cached, so a function in a library loaded
+/// after it was first looked up won't be found. Use [`declaration`] for
+/// those. Results of scans which didn't search everything, as they exceeded
+/// the [`ScanLimits`](crate::ScanLimits), were
+/// [cancelled](crate::with_cancellation), skipped files larger than the
+/// limit of [`with_low_allocation_scans`](crate::with_low_allocation_scans),
+/// or were started inside another scan, aren't cached, so the next call
+/// scans again.
+///
+/// ```rust,no_run
+/// use declaration_site::cached_declaration_of;
+///
+/// #[inline(never)]
+/// fn update_overlay() {}
+///
+/// for _frame in 0..3 {
+///     update_overlay();
+///     // Only the first frame scans for the function
+///     if let Some(site) = cached_declaration_of(&update_overlay) {
+///         println!("`update_overlay` is declared at {site}");
+///     }
+/// }
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn cached_declaration<T: 'static>() -> Option<&'static DeclarationSite> {
+    cached_by_type_id(TypeId::of::<T>(), declaration::<T>)
+}
+
+/// The cached declaration site of the type with the [`TypeId`] `id`, or
+/// `find` it and cache it if it hasn't been looked up.
+pub(crate) fn cached_by_type_id(
+    id: TypeId,
+    find: impl FnOnce() -> Option<DeclarationSite>,
+) -> Option<&'static DeclarationSite> {
+    let previous = cache()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&id)
+        .copied();
+    if let Some(Slot::Complete(entry)) = previous {
+        return entry.map(Entry::site);
+    }
+    // Nothing is searched inside another scan, so the function wouldn't
+    // really be missing
+    if is_scanning() {
+        return None;
+    }
+    // Scan without holding the lock, so other types can still be looked up
+    let (((site, skipped_files), cancelled), stats) = limits::track_stats(|| {
+        cancellation::track_interruption(|| {
+            low_allocation::track_skipped_files(|| redaction::unredacted(find))
+        })
+    });
+    let complete = !(stats.is_truncated() || cancelled || skipped_files);
+    let mut cache = cache().write().unwrap_or_else(|e| e.into_inner());
+    let slot = cache.get(&id).copied();
+    if let Some(Slot::Complete(entry)) = slot {
+        // Another thread finished a scan first
+        return entry.map(Entry::site);
+    }
+    let Some(site) = site else {
+        if complete {
+            cache.insert(id, Slot::Complete(None));
+        }
+        return None;
+    };
+    let entry = match slot {
+        Some(Slot::Partial(entry)) if entry.site == site => entry,
+        _ => Box::leak(Box::new(Entry {
+            site,
+            redacted: Mutex::default(),
+        })),
+    };
+    // A later scan may find a better site than an incomplete one did
+    let slot = if complete {
+        Slot::Complete(Some(entry))
+    } else {
+        Slot::Partial(entry)
+    };
+    cache.insert(id, slot);
+    drop(cache);
+    Some(entry.site())
+}
+
+/// Attempt to get the declaration site of the function item type of the
+/// given value, as in [`cached_declaration`].
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn cached_declaration_of<T: 'static>(_: &T) -> Option<&'static DeclarationSite> {
+    cached_declaration::<T>()
+}
+
+/// The declaration sites of the currently loaded functions, by name, read
+/// once so that many functions can be looked up without scanning again.
+///
+/// This is a [`DeclarationIndex`] of the currently loaded libraries (as
+/// [`DeclarationIndex::from_loaded_libraries`]), which can also be filtered
+/// to only keep the functions of some crates or modules with
+/// [`build_filtered`](Self::build_filtered). Building a cache scans every
+/// loaded library once, which takes about as long as a single
+/// [`declaration_by_name`](crate::declaration_by_name), and each
+/// [`lookup`](Self::lookup) then only searches the index. This suits
+/// resolving many functions at once, such as every system of an app at
+/// startup.
+///
+/// Names are matched as by [`DeclarationIndex::get`]:
+/// both are compared after [`canonical_name`](crate::canonical_name), and if
+/// several functions have the same name (such as copies of a generic
+/// function), the first one found is used. Unlike
+/// [`declaration_by_name`](crate::declaration_by_name), `Type::method`
+/// doesn't match `<Type as Trait>::method`, and a closure without a `#N`
+/// disambiguator doesn't match every closure in its parent function.
+/// Libraries loaded after the cache was built aren't included.
+///
+/// ```rust,no_run
+/// use declaration_site::DeclarationCache;
+///
+/// let cache = DeclarationCache::build_filtered("my_editor::");
+/// for system in ["my_editor::systems::move_camera", "my_editor::systems::draw_grid"] {
+///     match cache.lookup(system) {
+///         Some(site) => println!("`{system}` is declared at {site}"),
+///         None => println!("`{system}` wasn't found"),
+///     }
+/// }
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeclarationCache {
+    index: DeclarationIndex,
+}
+
+impl DeclarationCache {
+    /// Read the declaration sites of every currently loaded function.
+    pub fn build() -> Self {
+        Self::build_filtered("")
+    }
+
+    /// Read the declaration sites of the currently loaded functions whose
+    /// names start with `prefix`, such as `my_crate::`, which keeps the
+    /// cache small when only the functions of some crates are looked up.
+    ///
+    /// `prefix` is compared with the names after
+    /// [`canonical_name`](crate::canonical_name).
+    pub fn build_filtered(prefix: &str) -> Self {
+        DeclarationCache {
+            index: DeclarationIndex::from_loaded_functions(prefix),
+        }
+    }
+
+    /// The declaration site of the function with the given (unmangled)
+    /// name, if it was loaded when the cache was built.
+    pub fn lookup(&self, name: &str) -> Option<&DeclarationSite> {
+        self.index.get(name)
+    }
+
+    /// The names and declaration sites of every function in the cache, sorted
+    /// by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DeclarationSite)> {
+        self.index.iter()
+    }
+
+    /// The number of functions in the cache.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether the cache has no functions.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// The index of the functions in the cache, such as for finding the
+    /// function at a line with [`DeclarationIndex::function_at`].
+    pub fn index(&self) -> &DeclarationIndex {
+        &self.index
+    }
+}
+
+impl From<DeclarationCache> for DeclarationIndex {
+    fn from(cache: DeclarationCache) -> Self {
+        cache.index
+    }
+}
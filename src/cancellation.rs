@@ -0,0 +1,267 @@
+//! Stopping scans from another thread, and reporting how far they have got.
+
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// A flag which stops the scans run by [`with_cancellation`] once set, from
+/// any thread.
+///
+/// Clones of a token share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// A token which hasn't been cancelled.
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Stop the scans using this token at their next checkpoint.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called on this token (or a clone
+    /// of it).
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How far the scans run by [`with_scan_progress`] have got.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ScanProgress {
+    /// A file (such as a loaded library, or a file in a directory) is about
+    /// to be searched. Its path is [redacted](crate::set_redaction)
+    File {
+        /// The path of the file
+        path: PathBuf,
+    },
+    /// More functions were read. This is reported every
+    /// [`FUNCTIONS_PER_REPORT`](ScanProgress::FUNCTIONS_PER_REPORT) functions
+    Functions {
+        /// The number of functions read since the start of
+        /// [`with_scan_progress`]
+        read: usize,
+    },
+}
+
+impl ScanProgress {
+    /// The number of functions read between each [`ScanProgress::Functions`].
+    pub const FUNCTIONS_PER_REPORT: usize = 4096;
+}
+
+/// The progress reporter of [`with_scan_progress`].
+struct Reporter {
+    report: Box<dyn FnMut(ScanProgress)>,
+    functions: usize,
+}
+
+thread_local! {
+    /// The token of the current [`with_cancellation`] on this thread.
+    static TOKEN: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+    /// The reporter of the current [`with_scan_progress`] on this thread.
+    static REPORTER: RefCell<Option<Reporter>> = const { RefCell::new(None) };
+    /// Whether a scan inside the current [`track_interruption`] on this
+    /// thread stopped as it was cancelled.
+    static INTERRUPTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f`, stopping every scan on the current thread at its next checkpoint
+/// once `token` is cancelled, returning its result.
+///
+/// Scans check the token before reading each file and each function, so stop
+/// soon after it is cancelled, even part of the way through a file. What a
+/// stopped scan found is incomplete, so it reports that it was cancelled
+/// rather than returning it:
+///
+/// - Functions which return an [`io::Result`] (such as
+///   [`DeclarationIndex::from_file`](crate::DeclarationIndex::from_file))
+///   return an error of kind [`io::ErrorKind::Interrupted`].
+/// - The `try_` lookups of the current process (such as
+///   [`DeclarationSearch::try_find`](crate::DeclarationSearch::try_find))
+///   return [`DeclarationError::Cancelled`](crate::DeclarationError::Cancelled),
+///   the lookups which return an `Option` return `None`, and those which
+///   return every match return none.
+///
+/// A scan started after `token` is cancelled returns without searching
+/// anything, in the same way.
+///
+/// This is used by the futures of the `async` feature, which cancel their
+/// scans when dropped.
+///
+/// ```rust
+/// use declaration_site::{with_cancellation, CancellationToken, DeclarationIndex};
+///
+/// let path = std::env::current_exe().unwrap();
+/// let token = CancellationToken::new();
+/// token.cancel();
+/// let index = with_cancellation(&token, || DeclarationIndex::from_file(&path));
+/// assert_eq!(index.unwrap_err().kind(), std::io::ErrorKind::Interrupted);
+/// ```
+pub fn with_cancellation<R>(token: &CancellationToken, f: impl FnOnce() -> R) -> R {
+    let previous = TOKEN.with(|it| it.replace(Some(token.clone())));
+    // Restore the previous token even if `f` panics
+    let _guard = CancellationGuard { previous };
+    f()
+}
+
+struct CancellationGuard {
+    previous: Option<CancellationToken>,
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        TOKEN.with(|it| *it.borrow_mut() = previous);
+    }
+}
+
+/// Run `f`, passing how far each scan on the current thread has got to
+/// `report`, returning its result.
+///
+/// `report` is called on the scanning thread, so should return quickly, such
+/// as by sending the progress to another thread. The progress of scans
+/// started from inside `report` isn't reported.
+///
+/// ```rust
+/// use declaration_site::{with_scan_progress, DeclarationIndex, ScanProgress};
+/// use std::sync::mpsc;
+///
+/// let path = std::env::current_exe().unwrap();
+/// let (sender, receiver) = mpsc::channel();
+/// let index = with_scan_progress(
+///     move |progress| sender.send(progress).unwrap(),
+///     || DeclarationIndex::from_file(&path),
+/// );
+/// for progress in receiver.try_iter() {
+///     if let ScanProgress::Functions { read } = progress {
+///         println!("read {read} functions");
+///     }
+/// }
+/// # index.unwrap();
+/// ```
+pub fn with_scan_progress<R>(
+    report: impl FnMut(ScanProgress) + 'static,
+    f: impl FnOnce() -> R,
+) -> R {
+    let reporter = Reporter {
+        report: Box::new(report),
+        functions: 0,
+    };
+    let previous = REPORTER.with(|it| it.replace(Some(reporter)));
+    // Restore the previous reporter even if `f` panics
+    let _guard = ProgressGuard { previous };
+    f()
+}
+
+struct ProgressGuard {
+    previous: Option<Reporter>,
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        REPORTER.with(|it| *it.borrow_mut() = previous);
+    }
+}
+
+/// Whether the scans on the current thread have been cancelled.
+///
+/// This is only called by scans, which stop if it returns `true`, so the
+/// current [`track_interruption`] records that a scan stopped.
+pub(crate) fn is_cancelled() -> bool {
+    let cancelled = TOKEN.with(|it| {
+        it.borrow()
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    });
+    if cancelled {
+        INTERRUPTED.with(|it| it.set(true));
+    }
+    cancelled
+}
+
+/// Run `f`, returning its result and whether a scan inside it stopped as it
+/// was cancelled.
+pub(crate) fn track_interruption<R>(f: impl FnOnce() -> R) -> (R, bool) {
+    let previous = INTERRUPTED.with(|it| it.replace(false));
+    // Keep the stop recorded for the enclosing call even if `f` panics
+    let guard = InterruptionGuard { previous };
+    let result = f();
+    let interrupted = INTERRUPTED.with(Cell::get);
+    drop(guard);
+    (result, interrupted)
+}
+
+struct InterruptionGuard {
+    previous: bool,
+}
+
+impl Drop for InterruptionGuard {
+    fn drop(&mut self) {
+        INTERRUPTED.with(|it| it.set(it.get() || self.previous));
+    }
+}
+
+/// Run the scan `f`, returning an error of kind
+/// [`io::ErrorKind::Interrupted`] instead of its result if it stopped as it
+/// was cancelled.
+pub(crate) fn interruptible<T>(f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    match track_interruption(f) {
+        (_, true) => Err(interrupted_error()),
+        (result, false) => result,
+    }
+}
+
+/// The error returned by scans which were cancelled.
+pub(crate) fn interrupted_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "the scan was cancelled")
+}
+
+/// Pass `progress` to the reporter of the current [`with_scan_progress`], if
+/// any.
+fn report(progress: impl FnOnce(&mut Reporter) -> Option<ScanProgress>) {
+    // The reporter is taken out while it runs, so that it can't be run again
+    // from inside itself
+    let Some(mut reporter) = REPORTER.with(|it| it.borrow_mut().take()) else {
+        return;
+    };
+    if let Some(progress) = progress(&mut reporter) {
+        (reporter.report)(progress);
+    }
+    REPORTER.with(|it| *it.borrow_mut() = Some(reporter));
+}
+
+/// Report that the file at `path` is about to be searched, returning whether
+/// the scans on the current thread have been cancelled.
+pub(crate) fn file_checkpoint(path: &Path) -> bool {
+    report(|_| {
+        Some(ScanProgress::File {
+            path: crate::redaction::path_buf(path),
+        })
+    });
+    is_cancelled()
+}
+
+/// Report that a function was read, returning whether the scans on the
+/// current thread have been cancelled.
+pub(crate) fn function_checkpoint() -> bool {
+    report(|reporter| {
+        reporter.functions += 1;
+        (reporter.functions % ScanProgress::FUNCTIONS_PER_REPORT == 0).then_some(
+            ScanProgress::Functions {
+                read: reporter.functions,
+            },
+        )
+    });
+    is_cancelled()
+}
@@ -0,0 +1,277 @@
+//! Cheaply checking whether lookups are likely to work in the current
+//! process.
+
+use std::{
+    ffi::OsString,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use findshlibs::SharedLibrary;
+use symbolic_common::{Language, Name, NameMangling};
+use symbolic_debuginfo::FileFormat;
+use symbolic_demangle::{Demangle, DemangleOptions};
+
+use crate::{
+    file_access, limits, main_executable_path, object,
+    pairing::{self, PairingReport},
+    redaction,
+};
+
+/// Whether lookups are likely to work in the current process, from
+/// [`capability_probe`].
+///
+/// Checks of the main executable which couldn't run because an earlier one
+/// failed are also `false`. [`reasons`](Self::reasons) explains each failed
+/// check.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CapabilityReport {
+    /// Whether the libraries loaded into the process could be enumerated
+    pub libraries_enumerated: bool,
+    /// The file checked for the main executable, which is its debug companion
+    /// (such as a PDB) if the loader reports one
+    pub executable: Option<PathBuf>,
+    /// Whether the file of the main executable could be read, and is a
+    /// supported object file format
+    pub executable_readable: bool,
+    /// Whether the main executable (or its debug companion) has debug info
+    pub has_debug_info: bool,
+    /// Whether Rust symbols can be demangled
+    pub rust_demangler: bool,
+    /// What kind of executable the current process is running, found from
+    /// [`executable`](Self::executable)
+    pub executable_kind: ExecutableKind,
+    /// Whether the debug companion of the main executable was built with it,
+    /// if the loader reports one and the executable could be read
+    pub debug_pairing: Option<PairingReport>,
+    /// Why each of the checks which failed did so
+    pub reasons: Vec<String>,
+}
+
+impl CapabilityReport {
+    /// Whether every check passed, so lookups of functions in the main
+    /// executable are likely to work.
+    ///
+    /// Lookups can still fail for other reasons, as described in "Caveats" in
+    /// the [module level documentation](crate).
+    ///
+    /// This is `false` in doctests, as rustdoc builds the code of a doctest
+    /// without debug info by default, so functions declared in it can't be
+    /// found. See [`ExecutableKind::Doctest`].
+    pub fn is_likely_to_work(&self) -> bool {
+        self.libraries_enumerated
+            && self.executable_readable
+            && self.has_debug_info
+            && self.rust_demangler
+            && self.executable_kind != ExecutableKind::Doctest
+            && !self
+                .debug_pairing
+                .as_ref()
+                .is_some_and(PairingReport::is_mismatched)
+    }
+}
+
+/// What kind of executable the current process is running, from
+/// [`CapabilityReport::executable_kind`].
+///
+/// This is found from the path of the executable, as laid out by cargo and
+/// rustdoc.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ExecutableKind {
+    /// Any other executable, such as a bin target or an example
+    #[default]
+    Program,
+    /// A test harness built by `cargo test` (or `cargo bench`), such as for
+    /// the unit tests of a crate or an integration test. These are built with
+    /// debug info in the default `test` profile, and lookups work as in other
+    /// programs.
+    TestHarness,
+    /// A doctest built by rustdoc. Rustdoc builds the code of doctests
+    /// without debug info unless `RUSTDOCFLAGS` includes `-C debuginfo=2`,
+    /// so functions declared in a doctest usually can't be found, although
+    /// the functions of the crates it uses (which cargo builds as usual) can.
+    /// The lines found for functions declared in a doctest are those of the
+    /// program rustdoc generates for it, so may not match the doc comment.
+    Doctest,
+}
+
+impl ExecutableKind {
+    /// The kind of the executable at `path`.
+    fn of(path: &Path) -> Self {
+        let directory = path.parent().and_then(Path::file_name);
+        let directory = directory.and_then(|it| it.to_str()).unwrap_or("");
+        let stem = path.file_stem().and_then(|it| it.to_str()).unwrap_or("");
+        if directory.starts_with("rustdoctest") || stem == "rust_out" {
+            return ExecutableKind::Doctest;
+        }
+        // Test harnesses are named after their crate, with a hash of their
+        // metadata
+        let is_hash = |hash: &str| hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+        if directory == "deps" && stem.rsplit_once('-').is_some_and(|(_, hash)| is_hash(hash)) {
+            ExecutableKind::TestHarness
+        } else {
+            ExecutableKind::Program
+        }
+    }
+}
+
+impl fmt::Display for CapabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_likely_to_work() {
+            write!(f, "lookups are likely to work")?;
+        } else {
+            write!(f, "lookups will probably fail")?;
+        }
+        if let Some(executable) = &self.executable {
+            write!(f, " (checked {})", executable.display())?;
+        }
+        for reason in &self.reasons {
+            write!(f, "\n  {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Cheaply check whether lookups such as
+/// [`declaration_by_name`](crate::declaration_by_name) are likely to work in
+/// the current process, without scanning for any functions.
+///
+/// This checks that the loaded libraries can be enumerated, that the main
+/// executable (or its debug companion) can be read and reports having debug
+/// info, and that Rust symbols can be demangled. If the main executable has a
+/// debug companion, this also checks that it was built with the executable
+/// (see [`verify_debug_pairing`](crate::verify_debug_pairing)). Only the main
+/// executable and its debug companion are read (and only the start of them,
+/// if they aren't a supported format), and their debug info isn't parsed, so this takes milliseconds rather than the
+/// seconds a scan can take. This is useful for only offering features which depend on
+/// lookups (such as jumping to a definition) when they can work, so that they
+/// are hidden in stripped release builds.
+///
+/// ```rust
+/// use declaration_site::ExecutableKind;
+///
+/// let report = declaration_site::capability_probe();
+/// if !report.is_likely_to_work() {
+///     println!("{report}");
+/// }
+/// // This example is a doctest, so lookups of its functions usually fail
+/// assert_eq!(report.executable_kind, ExecutableKind::Doctest);
+/// assert!(!report.is_likely_to_work());
+/// ```
+pub fn capability_probe() -> CapabilityReport {
+    let mut report = CapabilityReport {
+        rust_demangler: has_rust_demangler(),
+        ..CapabilityReport::default()
+    };
+    if !report.rust_demangler {
+        report
+            .reasons
+            .push("Rust symbols can't be demangled by this build of `symbolic`".to_owned());
+    }
+    let mut executable = None;
+    findshlibs::TargetSharedLibrary::each(|library| {
+        // The first library is the main executable
+        executable = Some((
+            library.name().to_owned(),
+            library.debug_name().map(ToOwned::to_owned),
+        ));
+        findshlibs::IterationControl::Break
+    });
+    let Some((name, debug_name)) = executable else {
+        report
+            .reasons
+            .push("no loaded libraries could be enumerated on this platform".to_owned());
+        return report;
+    };
+    report.libraries_enumerated = true;
+    let has_companion = debug_name.is_some();
+    let path = match (debug_name, name.is_empty()) {
+        (Some(debug_name), _) => PathBuf::from(debug_name),
+        (None, false) => PathBuf::from(&name),
+        (None, true) => match main_executable_path() {
+            Ok(it) => it,
+            Err(error) => {
+                report
+                    .reasons
+                    .push(format!("the path of the executable is unknown: {error}"));
+                return report;
+            }
+        },
+    };
+    report.executable_kind = ExecutableKind::of(&path);
+    if report.executable_kind == ExecutableKind::Doctest {
+        report.reasons.push(
+            "this is a doctest, which rustdoc builds without debug info unless `RUSTDOCFLAGS` \
+             includes `-C debuginfo=2`, so its own functions probably can't be found"
+                .to_owned(),
+        );
+    }
+    // The path which leaves the process, if redaction is set
+    let shown = redaction::path_buf(&path);
+    report.executable = Some(shown.clone());
+    match read_object(&path) {
+        Ok(data) => {
+            report.executable_readable = true;
+            report.has_debug_info =
+                limits::catch_parser_panic(|| pairing::has_debug_info(&data)).unwrap_or(false);
+            if !report.has_debug_info {
+                report.reasons.push(format!(
+                    "{} has no debug info, such as when it was built with `strip` or without `debug`",
+                    shown.display()
+                ));
+            }
+            if has_companion {
+                report.debug_pairing =
+                    verify_companion(name, &path, &data).map(redaction::pairing_report);
+                if let Some(pairing) = report
+                    .debug_pairing
+                    .as_ref()
+                    .filter(|it| it.is_mismatched())
+                {
+                    report.reasons.push(pairing.to_string());
+                }
+            }
+        }
+        Err(error) => report
+            .reasons
+            .push(format!("{} can't be read: {error}", shown.display())),
+    }
+    report
+}
+
+/// Read the file at `path`, if the start of it is a supported object file
+/// format.
+fn read_object(path: &Path) -> io::Result<Vec<u8>> {
+    let header = file_access::read_range(path, 0..64)?;
+    if object::Archive::peek(&header) == FileFormat::Unknown {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "not a supported object file format",
+        ));
+    }
+    file_access::read(path)
+}
+
+/// Whether the debug companion at `debug_path` was built with the main
+/// executable, whose path is `name` as reported by the loader, if this is
+/// checked and the executable could be read.
+fn verify_companion(name: OsString, debug_path: &Path, debug: &[u8]) -> Option<PairingReport> {
+    let binary_path = if name.is_empty() {
+        main_executable_path().ok()?
+    } else {
+        PathBuf::from(name)
+    };
+    let binary = read_object(&binary_path).ok()?;
+    pairing::verify(&binary_path, &binary, debug_path, debug)
+}
+
+/// Whether `symbolic` was built with support for demangling Rust symbols.
+fn has_rust_demangler() -> bool {
+    Name::new(
+        "_ZN4core3ptr13drop_in_place17h0123456789abcdefE",
+        NameMangling::Mangled,
+        Language::Rust,
+    )
+    .demangle(DemangleOptions::name_only())
+    .is_some_and(|it| it == "core::ptr::drop_in_place")
+}
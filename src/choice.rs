@@ -0,0 +1,905 @@
+//! Choosing between several functions which match the same name.
+
+use std::{cmp::Reverse, fmt};
+
+use crate::{
+    attribute_dependency_path, confidence, macro_site,
+    names::{self, NameMatch},
+    object::{CompilationUnit, CrateTarget},
+    redaction, Confidence, ConfidenceFactor, CrateSource, DeclarationSite, DeclarationSiteError,
+    FunctionRecord, LineProvenance, MacroSite, MatchRelaxation, PairingStatus, PathComparison,
+    PathPreference,
+};
+
+/// The function chosen for a name by [`declaration_by_name`](crate::declaration_by_name),
+/// returned by [`explain_declaration_by_name`](crate::explain_declaration_by_name).
+///
+/// Several functions can match a name, such as every closure in a function
+/// for `my_crate::main::{{closure}}`, or the copies of an `#[inline]` or
+/// generic function which were compiled into the codegen units of other
+/// crates (which are common with ThinLTO). The function is chosen as follows:
+/// 1. The first function which matched exactly, and was compiled in a unit
+///    of its own crate (or whose unit or crate is unknown). The search stops
+///    at this function, unless it is in a registry dependency, as several
+///    versions of the crate could be linked. In that case, the rest of the
+///    file is searched, and the first such function from the preferred
+///    version (see [`DeclarationSearch::version`]) is chosen if there is
+///    one. See [`other_versions`](Self::other_versions). The search also
+///    continues if the function is in a bin target, as a package's bin and
+///    lib targets are separate crates with the same name, which are both
+///    linked into the bin. If the lib also has a matching function, it is
+///    chosen, as it is usually the definition which other code uses. See
+///    [`bin_and_lib`](Self::bin_and_lib)
+/// 2. Otherwise, of the functions which matched exactly (all of which are in
+///    the units of other crates), the one declared on the lowest line. Only
+///    the rest of the file containing the first of these is searched
+/// 3. Otherwise, of the functions which matched with a wildcard or another
+///    spelling (see [`names_match`](crate::names_match)), or which are the
+///    body of the future generated for an `async fn` of that name, the one
+///    declared on the lowest line
+///
+/// Functions without source locations are only chosen if no other function
+/// matched. Functions which were only found inlined into other functions
+/// (such as an `async fn` and the body of its future, which are usually
+/// inlined into the executor's `poll` in optimized builds) are only chosen
+/// if no function which wasn't inlined matched, in the same way.
+///
+/// If no function matched, the default body of the trait method is used
+/// for names spelled `<Type as Trait>::method`, as legacy demangled names of
+/// trait methods which weren't overridden don't include the type. See
+/// [`via_default_impl`](Self::via_default_impl).
+///
+/// If still no function matched, and matching is relaxed (see
+/// [`MatchRelaxation`]), the function which matched at the least relaxed
+/// level is chosen, but only if it is the only function (ignoring copies of
+/// it with the same declaration site) which matched at that level. See
+/// [`relaxation`](Self::relaxation).
+///
+/// The site of the body of an `async fn`'s future is the lowest line in its
+/// file, which is usually the line of the `async fn`. This is only used if
+/// the `async fn` itself wasn't found.
+///
+/// At each of these steps, functions whose declaration sites have a higher
+/// [`PathPreference::score`] are chosen over the rest, so a function found in
+/// several libraries is chosen from its file in the workspace by default,
+/// rather than from a copy elsewhere, whichever library is found first. The
+/// search only stops at a function (or file) whose site has the highest
+/// score. See [`outscored`](Self::outscored).
+///
+/// How far the chosen site can be trusted, from all of the above, is
+/// summarised by [`confidence`](Self::confidence).
+///
+/// [`DeclarationSearch::version`]: crate::DeclarationSearch::version
+#[derive(Clone, Debug)]
+pub struct DeclarationChoice {
+    /// The declaration site of the chosen function
+    pub site: Result<DeclarationSite, DeclarationSiteError>,
+    /// Where the line of [`site`](Self::site) came from, or `None` if the
+    /// site wasn't found
+    pub provenance: Option<LineProvenance>,
+    /// The other files which the chosen function's lines are in, in the
+    /// order they first appear, such as a file included by `include!`, or the
+    /// file of a macro defined elsewhere.
+    ///
+    /// The [`site`](Self::site) is in the file with the most of the
+    /// function's lines (see [`DeclarationSite::from_lines`]), so this is
+    /// usually empty, and otherwise lists files with fewer lines.
+    pub additional_files: Vec<String>,
+    /// The demangled name of the chosen function
+    pub function_name: String,
+    /// The name of the chosen function in the debug info, which is usually
+    /// mangled
+    pub raw_name: String,
+    /// The address of the chosen function, relative to the load address of
+    /// its object
+    pub address: u64,
+    /// The index of the object the chosen function was found in (see
+    /// [`FunctionRecord::object_index`])
+    pub object_index: usize,
+    /// The compilation unit the chosen function was compiled in, if known
+    pub compilation_unit: Option<CompilationUnit>,
+    /// Whether the chosen function's name matched exactly, rather than with a
+    /// wildcard or another spelling
+    pub exact: bool,
+    /// Whether the compilation unit belongs to the crate of the function. This
+    /// is `None` if either crate is unknown
+    pub in_own_crate: Option<bool>,
+    /// The number of functions which matched the name, including the chosen
+    /// one
+    pub candidates: usize,
+    /// The version of the crate containing the chosen function, if it is a
+    /// registry dependency (see [`attribute_dependency_path`])
+    pub version: Option<String>,
+    /// The versions of the other copies of the chosen function's crate which
+    /// contain a function matching the name exactly, when several versions
+    /// of the crate are linked through different dependency paths.
+    ///
+    /// If this is not empty, the choice between the versions was ambiguous,
+    /// unless a version was preferred.
+    pub other_versions: Vec<String>,
+    /// The package the chosen function's crate is in, to tell apart crates
+    /// with the same name from different packages, such as two `utils`
+    /// crates from unrelated workspaces which are loaded into one process.
+    ///
+    /// This is the name of the package for dependencies in cargo's download
+    /// directories and the standard library (see
+    /// [`attribute_dependency_path`]), and otherwise the directory of the
+    /// package, from the crate root of the compilation unit and the directory
+    /// it was compiled in. It is `None` if the unit is unknown, belongs to
+    /// another crate, or isn't a lib or bin target in the default layout.
+    /// See [`DeclarationSearch::package_hint`](crate::DeclarationSearch::package_hint).
+    pub package_hint: Option<String>,
+    /// The kind of target the chosen function's crate was compiled for, if
+    /// known (see [`CompilationUnit::target`])
+    pub target: Option<CrateTarget>,
+    /// Whether both the bin and lib targets of the chosen function's package
+    /// contain a function matching the name exactly. If so, the lib's
+    /// function is chosen.
+    pub bin_and_lib: bool,
+    /// Whether the chosen function is the default body of the trait method
+    /// being searched for (`my_crate::Trait::method` for
+    /// `<my_crate::Type as my_crate::Trait>::method`), so is declared in the
+    /// trait rather than the type's impl
+    pub via_default_impl: bool,
+    /// How far matching was relaxed for the chosen function's name to match.
+    /// If this isn't [`MatchRelaxation::None`], the choice is approximate
+    pub relaxation: MatchRelaxation,
+    /// The [`score`](PathPreference::score) of the path of the chosen
+    /// function's declaration site, or 0 if the site wasn't found
+    pub path_score: u32,
+    /// The declaration sites of the functions which matched as well as the
+    /// chosen function, but weren't chosen as their paths had lower scores
+    /// (see [`PathPreference`]), without duplicates. Use the `_all` lookups,
+    /// such as [`explain_declaration_by_name_all`](crate::explain_declaration_by_name_all),
+    /// to get the details of these functions.
+    pub outscored: Vec<DeclarationSite>,
+    /// The calls the chosen function was inlined at, if no copy of it which
+    /// wasn't inlined was found (so [`provenance`](Self::provenance) is
+    /// usually [`LineProvenance::Inlinee`]). This includes the calls of every
+    /// inlined copy with the same declaration site, sorted by call site.
+    ///
+    /// With optimizations, small functions are often inlined into every
+    /// caller, so these calls are the only places the function's code exists.
+    pub inlined_into: Vec<InlinedCall>,
+    /// Whether the separate debug file (such as a dSYM or PDB) the chosen
+    /// function was found in was built with its binary, as checked by
+    /// [`with_debug_pairing`](crate::with_debug_pairing). This is `None` if the
+    /// function was found in the binary itself, or in a file which isn't
+    /// loaded
+    pub debug_pairing: Option<PairingStatus>,
+    /// Whether functions declared elsewhere matched the name as well as the
+    /// chosen function, so the name is ambiguous. See
+    /// [`ambiguous_with`](Self::ambiguous_with).
+    ///
+    /// [`type_name`](core::any::type_name) isn't unique: every closure in a
+    /// function has the same name, as do functions returning different
+    /// `impl Trait`s in some cases, so a name can match several functions
+    /// which aren't copies of each other. The chosen function may not be the
+    /// one which was meant.
+    pub ambiguous: bool,
+    /// The functions which made the choice [`ambiguous`](Self::ambiguous),
+    /// one for each name in the debug info, sorted by that name. Use the
+    /// `_all` lookups, such as
+    /// [`explain_declaration_by_name_all`](crate::explain_declaration_by_name_all),
+    /// to get the details of these functions.
+    pub ambiguous_with: Vec<AmbiguousMatch>,
+    /// How far [`site`](Self::site) can be trusted, from
+    /// [`confidence_factors`](Self::confidence_factors)
+    pub confidence: Confidence,
+    /// Why the choice isn't [`Confidence::Exact`], if it isn't
+    pub confidence_factors: Vec<ConfidenceFactor>,
+}
+
+/// Another function which matched a name as well as the chosen function,
+/// from [`DeclarationChoice::ambiguous_with`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct AmbiguousMatch {
+    /// The name of the function in the debug info, which is usually mangled,
+    /// so differs from the chosen function's
+    /// [`raw_name`](DeclarationChoice::raw_name)
+    pub raw_name: String,
+    /// The declaration site of the function
+    pub site: DeclarationSite,
+}
+
+impl fmt::Display for AmbiguousMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` at {}", self.raw_name, self.site)
+    }
+}
+
+/// A call which a function was inlined at, from
+/// [`DeclarationChoice::inlined_into`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct InlinedCall {
+    /// The demangled name of the function the call is in. If that function
+    /// was itself inlined, this is the name of that function, rather than of
+    /// the function its code is in
+    pub caller: String,
+    /// The site of the call, from the line of the caller at the start of the
+    /// inlined code, if known
+    pub call_site: Option<DeclarationSite>,
+}
+
+impl InlinedCall {
+    /// The call of `inlinee`, which was inlined into `caller`.
+    fn new(caller: &FunctionRecord<'_>, inlinee: &FunctionRecord<'_>) -> Self {
+        // The lines of the inlined code are replaced with the call site in
+        // the caller's lines
+        let address = inlinee.address();
+        let call_site = caller
+            .lines()
+            .find(|line| {
+                let end = line.address.saturating_add(line.size.unwrap_or(1));
+                (line.address..end).contains(&address)
+            })
+            .filter(|line| line.line != 0)
+            .map(|line| DeclarationSite {
+                file: line.file,
+                line: line.line,
+            });
+        InlinedCall {
+            caller: caller.name().to_owned(),
+            call_site,
+        }
+    }
+}
+
+impl fmt::Display for InlinedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}`", self.caller)?;
+        match &self.call_site {
+            Some(site) => write!(f, " at {site}"),
+            None => write!(f, " (call site unknown)"),
+        }
+    }
+}
+
+/// The most calls listed when displaying a [`DeclarationChoice`].
+const DISPLAYED_INLINED_CALLS: usize = 5;
+
+impl DeclarationChoice {
+    fn new(
+        kind: NameMatch,
+        function: &FunctionRecord<'_>,
+        macro_site: MacroSite,
+        path_preference: PathPreference,
+        debug_pairing: Option<PairingStatus>,
+    ) -> Self {
+        let compilation_unit = function.compilation_unit();
+        let function_name = names::canonical_name(function.name());
+        let in_own_crate = compilation_unit
+            .and_then(CompilationUnit::crate_name)
+            .zip(names::crate_name(&function_name))
+            .map(|(unit_crate, function_crate)| unit_crate == function_crate);
+        let site = if kind == NameMatch::FutureBody {
+            future_body_site(function, macro_site)
+        } else {
+            function.declaration_site_with_provenance(macro_site)
+        };
+        let (site, provenance) = match site {
+            Ok((site, provenance)) => (Ok(site), Some(provenance)),
+            Err(error) => (Err(error), None),
+        };
+        let additional_files = site.as_ref().map_or_else(
+            |_| Vec::new(),
+            |site| macro_site::other_files(function, &site.file),
+        );
+        let path_score = site
+            .as_ref()
+            .map_or(0, |site| path_preference.score(&site.file));
+        let attribution = site
+            .as_ref()
+            .ok()
+            .and_then(|site| attribute_dependency_path(&site.file));
+        let version = attribution
+            .as_ref()
+            .filter(|attribution| attribution.source == CrateSource::Registry)
+            .and_then(|attribution| attribution.version.clone());
+        let package_hint = match attribution {
+            Some(attribution) => Some(attribution.name),
+            None => compilation_unit
+                .filter(|_| in_own_crate != Some(false))
+                .and_then(package_hint),
+        };
+        DeclarationChoice {
+            site,
+            provenance,
+            additional_files,
+            function_name: function.name().to_owned(),
+            raw_name: function.raw_name().to_owned(),
+            address: function.address(),
+            object_index: function.object_index(),
+            compilation_unit: compilation_unit.cloned(),
+            exact: kind == NameMatch::Exact,
+            in_own_crate,
+            candidates: 0,
+            version,
+            other_versions: Vec::new(),
+            package_hint,
+            target: compilation_unit.and_then(CompilationUnit::target),
+            bin_and_lib: false,
+            via_default_impl: kind == NameMatch::DefaultImpl,
+            relaxation: MatchRelaxation::None,
+            path_score,
+            outscored: Vec::new(),
+            inlined_into: Vec::new(),
+            debug_pairing,
+            ambiguous: false,
+            ambiguous_with: Vec::new(),
+            confidence: Confidence::Exact,
+            confidence_factors: Vec::new(),
+        }
+    }
+
+    /// Whether `other` matched as well as this function, but is declared
+    /// elsewhere.
+    fn is_ambiguous_with(&self, other: &DeclarationChoice) -> bool {
+        other.exact == self.exact
+            && other.relaxation == self.relaxation
+            && other.site.is_ok()
+            && !other.is_copy_of(self)
+    }
+
+    /// The functions in `others` which make this choice ambiguous, for
+    /// [`ambiguous_with`](Self::ambiguous_with).
+    fn ambiguous_matches(&self, others: &[DeclarationChoice]) -> Vec<AmbiguousMatch> {
+        let mut matches = others
+            .iter()
+            .filter(|other| self.is_ambiguous_with(other))
+            .filter_map(|other| {
+                Some(AmbiguousMatch {
+                    raw_name: other.raw_name.clone(),
+                    site: other.site.clone().ok()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| a.raw_name.cmp(&b.raw_name));
+        matches.dedup_by(|a, b| a.raw_name == b.raw_name);
+        matches
+    }
+
+    /// Set [`ambiguous_with`](Self::ambiguous_with) to `matches`, then
+    /// [`confidence`](Self::confidence), which depends on it.
+    fn set_ambiguity(&mut self, matches: Vec<AmbiguousMatch>) {
+        self.ambiguous = !matches.is_empty();
+        self.ambiguous_with = matches;
+        self.confidence_factors = confidence::factors(self);
+        self.confidence = confidence::confidence(&self.confidence_factors);
+    }
+
+    /// Whether `other` is a copy of the same function, with the same
+    /// declaration site.
+    fn is_copy_of(&self, other: &DeclarationChoice) -> bool {
+        match (&self.site, &other.site) {
+            (Ok(site), Ok(other)) => site == other,
+            _ => self.function_name == other.function_name,
+        }
+    }
+
+    /// Whether this function should be chosen over functions which aren't.
+    fn is_preferred(&self) -> bool {
+        self.exact && self.in_own_crate != Some(false)
+    }
+
+    /// Whether this function is the one to use, so the search can stop.
+    fn is_final(&self) -> bool {
+        self.is_preferred()
+            && self.version.is_none()
+            && self.target != Some(CrateTarget::Bin)
+            && self.path_score == PathPreference::TOP_SCORE
+    }
+
+    /// The package directory of a bin or lib target which could contain the
+    /// same function as another target of its package.
+    fn package(&self) -> Option<(Option<&str>, &str)> {
+        let unit = self.compilation_unit.as_ref()?;
+        let (_, package) = unit.package_target()?;
+        Some((unit.comp_dir(), package))
+    }
+}
+
+impl fmt::Display for DeclarationChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` ", self.function_name)?;
+        match &self.site {
+            Ok(site) => write!(f, "at {site}")?,
+            Err(err) => write!(f, "({err})")?,
+        }
+        let kind = if self.relaxation != MatchRelaxation::None {
+            "approximately"
+        } else if self.exact {
+            "exactly"
+        } else if self.via_default_impl {
+            "as the trait's default method"
+        } else {
+            "with a wildcard or another spelling"
+        };
+        write!(
+            f,
+            ", chosen from {} matching function(s), matched {kind}",
+            self.candidates
+        )?;
+        if let Some(provenance) = self
+            .provenance
+            .filter(|it| *it != LineProvenance::LineTableFirst)
+        {
+            write!(f, "\n  line from {provenance}")?;
+        }
+        if !self.additional_files.is_empty() {
+            write!(
+                f,
+                "\n  also has lines in {}",
+                self.additional_files.join(", ")
+            )?;
+        }
+        if let Some(unit) = self
+            .compilation_unit
+            .as_ref()
+            .and_then(CompilationUnit::name)
+        {
+            write!(f, "\n  compiled in unit `{unit}`")?;
+            if self.in_own_crate == Some(false) {
+                write!(f, " of another crate")?;
+            }
+        }
+        if let Some(version) = &self.version {
+            write!(f, "\n  from version {version} of its crate")?;
+        }
+        if let Some(package) = &self.package_hint {
+            write!(f, "\n  in package {package}")?;
+        }
+        if !self.other_versions.is_empty() {
+            write!(
+                f,
+                "\n  ambiguous: version(s) {} of the crate are also linked",
+                self.other_versions.join(", ")
+            )?;
+        }
+        if self.via_default_impl {
+            let name = names::canonical_name(&self.function_name);
+            match name.rsplit_once("::") {
+                Some((trait_, _)) => write!(f, "\n  default implementation from trait `{trait_}`")?,
+                None => write!(f, "\n  default implementation from the trait")?,
+            }
+        }
+        if self.relaxation != MatchRelaxation::None {
+            write!(
+                f,
+                "\n  approximate: only matched with {:?} relaxation",
+                self.relaxation
+            )?;
+        }
+        if !self.outscored.is_empty() {
+            let sites = self
+                .outscored
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            write!(
+                f,
+                "\n  preferred by path (score {}) over the function(s) at {}",
+                self.path_score,
+                sites.join(", ")
+            )?;
+        }
+        if !self.inlined_into.is_empty() {
+            write!(f, "\n  only found inlined into:")?;
+            for call in self.inlined_into.iter().take(DISPLAYED_INLINED_CALLS) {
+                write!(f, "\n    {call}")?;
+            }
+            if let Some(more) = self.inlined_into.len().checked_sub(DISPLAYED_INLINED_CALLS) {
+                if more > 0 {
+                    write!(f, "\n    and {more} more")?;
+                }
+            }
+        }
+        if self.bin_and_lib {
+            write!(
+                f,
+                "\n  ambiguous: both the bin and lib targets of the package contain this \
+                 function, and the {} target's was chosen",
+                match self.target {
+                    Some(CrateTarget::Bin) => "bin",
+                    _ => "lib",
+                }
+            )?;
+        }
+        if self.ambiguous {
+            write!(
+                f,
+                "\n  ambiguous: `{}` was chosen, but the name also matched:",
+                self.raw_name
+            )?;
+            for other in &self.ambiguous_with {
+                write!(f, "\n    {other}")?;
+            }
+        }
+        if self.debug_pairing == Some(PairingStatus::Mismatched) {
+            write!(f, "\n  found in a debug file from another build")?;
+        }
+        if !self.confidence_factors.is_empty() {
+            let factors = self
+                .confidence_factors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            write!(
+                f,
+                "\n  confidence: {} ({})",
+                self.confidence,
+                factors.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The [`package_hint`](DeclarationChoice::package_hint) of the functions
+/// compiled in `unit` whose sites aren't in a dependency.
+fn package_hint(unit: &CompilationUnit) -> Option<String> {
+    let dir = unit.package_dir()?;
+    // Dependencies are compiled in their package's directory, so the paths
+    // of their sites are relative to it
+    match attribute_dependency_path(&format!("{dir}/Cargo.toml")) {
+        Some(attribution) => Some(attribution.name),
+        None => Some(dir),
+    }
+}
+
+/// The lowest line of the body of an `async fn`'s future in the file of its
+/// first line, which is usually the line of the `async fn` itself.
+fn future_body_site(
+    function: &FunctionRecord<'_>,
+    macro_site: MacroSite,
+) -> Result<(DeclarationSite, LineProvenance), DeclarationSiteError> {
+    let (site, provenance) = function.declaration_site_with_provenance(macro_site)?;
+    let line = function
+        .lines()
+        .filter(|line| line.line != 0 && line.file == site.file)
+        .map(|line| line.line)
+        .min();
+    Ok(match line {
+        Some(line) => (
+            DeclarationSite { line, ..site },
+            LineProvenance::LineTableMin,
+        ),
+        None => (site, provenance),
+    })
+}
+
+/// The functions found for a name, to choose between several matches.
+#[derive(Clone, Default)]
+pub(crate) struct Candidates {
+    found: Vec<DeclarationChoice>,
+    /// The functions which were only found inlined into other functions,
+    /// used if none were found otherwise
+    inlined: Vec<DeclarationChoice>,
+    /// The default bodies of the trait method being searched for, used if no
+    /// other functions were found
+    default_impls: Vec<DeclarationChoice>,
+    /// The functions which only matched with relaxed matching, used if no
+    /// other functions were found
+    relaxed: Vec<DeclarationChoice>,
+    /// How far matching may be relaxed
+    #[cfg(feature = "process")]
+    relaxation: MatchRelaxation,
+    /// The site to report for macro-generated functions
+    macro_site: MacroSite,
+    /// The version of the function's crate to prefer
+    version: Option<String>,
+    /// The package the functions must be in, matched against their
+    /// [`package_hint`](DeclarationChoice::package_hint)
+    package: Option<String>,
+    /// How the paths of the functions' declaration sites are preferred
+    path_preference: PathPreference,
+    /// Whether the debug file being searched was built with its binary
+    debug_pairing: Option<PairingStatus>,
+    /// Whether a function which should always be chosen was found
+    is_final: bool,
+}
+
+impl Candidates {
+    #[cfg(feature = "process")]
+    pub fn new(search: &crate::DeclarationSearch<'_>) -> Self {
+        Candidates {
+            macro_site: search.macro_site,
+            version: search.version.map(str::to_owned),
+            package: search.package.map(str::to_owned),
+            relaxation: search.relaxation,
+            path_preference: search.path_preference,
+            ..Candidates::default()
+        }
+    }
+
+    fn choice(&self, kind: NameMatch, function: &FunctionRecord<'_>) -> DeclarationChoice {
+        DeclarationChoice::new(
+            kind,
+            function,
+            self.macro_site,
+            self.path_preference,
+            self.debug_pairing,
+        )
+    }
+
+    /// Whether `choice` is in the package the functions must be in, if any.
+    fn is_in_package(&self, choice: &DeclarationChoice) -> bool {
+        match (&self.package, &choice.package_hint) {
+            (None, _) => true,
+            (Some(package), Some(hint)) => PathComparison::host().matches(package, hint),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Set whether the debug file whose functions are added next was built
+    /// with its binary, or `None` if it is the binary.
+    #[cfg(feature = "process")]
+    pub fn set_debug_pairing(&mut self, debug_pairing: Option<PairingStatus>) {
+        self.debug_pairing = debug_pairing;
+    }
+
+    /// How far matching may be relaxed for functions which don't match.
+    #[cfg(feature = "process")]
+    pub fn relaxation(&self) -> MatchRelaxation {
+        self.relaxation
+    }
+
+    /// Record `function`, which only matched with `relaxation`.
+    #[cfg(feature = "process")]
+    pub fn add_relaxed(&mut self, relaxation: MatchRelaxation, function: &FunctionRecord<'_>) {
+        let mut choice = self.choice(NameMatch::Wildcard, function);
+        choice.relaxation = relaxation;
+        if self.is_in_package(&choice) {
+            self.relaxed.push(choice);
+        }
+    }
+
+    /// Record `function`, which matched as `kind`, returning whether it
+    /// should be chosen without searching any further.
+    pub fn add(&mut self, kind: NameMatch, function: &FunctionRecord<'_>) -> bool {
+        let choice = self.choice(kind, function);
+        if !self.is_in_package(&choice) {
+            return self.is_final;
+        }
+        if choice.via_default_impl {
+            self.default_impls.push(choice);
+            return false;
+        }
+        self.is_final |= choice.is_final();
+        self.found.push(choice);
+        self.is_final
+    }
+
+    /// Record `function`, which was inlined into `caller` and matched as
+    /// `kind`.
+    pub fn add_inlined(
+        &mut self,
+        kind: NameMatch,
+        function: &FunctionRecord<'_>,
+        caller: &FunctionRecord<'_>,
+    ) {
+        let mut choice = self.choice(kind, function);
+        if !self.is_in_package(&choice) {
+            return;
+        }
+        if choice.provenance == Some(LineProvenance::LineTableFirst) {
+            choice.provenance = Some(LineProvenance::Inlinee);
+        }
+        choice.inlined_into.push(InlinedCall::new(caller, function));
+        if choice.via_default_impl {
+            self.default_impls.push(choice);
+        } else {
+            self.inlined.push(choice);
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.is_final
+    }
+
+    /// Whether no function has matched, other than inlined functions and the
+    /// default bodies of a trait method.
+    pub fn is_empty(&self) -> bool {
+        self.found.is_empty()
+    }
+
+    /// Whether any function whose path has the highest score matched exactly,
+    /// in which case only the rest of the file it was found in needs to be
+    /// searched.
+    #[cfg(feature = "process")]
+    pub fn has_exact(&self) -> bool {
+        self.found
+            .iter()
+            .any(|choice| choice.exact && choice.path_score == PathPreference::TOP_SCORE)
+    }
+
+    /// The chosen function, as described in [`DeclarationChoice`].
+    pub fn best(mut self) -> Option<DeclarationChoice> {
+        self.use_fallbacks();
+        if self.found.first()?.relaxation != MatchRelaxation::None {
+            return self.unique_relaxed();
+        }
+        self.set_targets();
+        let is_wanted = |choice: &DeclarationChoice| {
+            (self.version.is_none() || choice.version == self.version)
+                && !(choice.bin_and_lib && choice.target == Some(CrateTarget::Bin))
+        };
+        // The first of the functions with the highest score
+        let best_scored = |filter: &dyn Fn(&DeclarationChoice) -> bool| {
+            let (index, _) = self
+                .found
+                .iter()
+                .enumerate()
+                .filter(|(_, choice)| filter(choice))
+                .min_by_key(|(_, choice)| Reverse(choice.path_score))?;
+            Some(index)
+        };
+        let index = best_scored(&|choice| choice.is_preferred() && is_wanted(choice))
+            .or_else(|| best_scored(&DeclarationChoice::is_preferred))
+            .or_else(|| {
+                let (index, _) = self.found.iter().enumerate().min_by_key(|(_, choice)| {
+                    let line = choice.site.as_ref().map_or(u32::MAX, |site| site.line);
+                    (
+                        !choice.exact,
+                        choice.site.is_err(),
+                        !is_wanted(choice),
+                        Reverse(choice.path_score),
+                        line,
+                    )
+                })?;
+                Some(index)
+            })?;
+        self.set_versions();
+        let candidates = self.found.len();
+        let mut chosen = self.found.swap_remove(index);
+        chosen.candidates = candidates;
+        chosen.outscored = self.outscored_by(&chosen);
+        let matches = chosen.ambiguous_matches(&self.found);
+        chosen.set_ambiguity(matches);
+        Some(redaction::choice(chosen))
+    }
+
+    /// Every function which matched, with the number of candidates and the
+    /// other versions of each.
+    #[cfg(feature = "process")]
+    pub fn all(mut self) -> Vec<DeclarationChoice> {
+        self.use_fallbacks();
+        self.set_versions();
+        self.set_targets();
+        let candidates = self.found.len();
+        let matches = self
+            .found
+            .iter()
+            .map(|choice| choice.ambiguous_matches(&self.found))
+            .collect::<Vec<_>>();
+        for (choice, matches) in self.found.iter_mut().zip(matches) {
+            choice.candidates = candidates;
+            choice.set_ambiguity(matches);
+        }
+        self.found.into_iter().map(redaction::choice).collect()
+    }
+
+    /// The declaration sites of the functions which matched as well as
+    /// `chosen`, but whose paths had lower scores.
+    fn outscored_by(&self, chosen: &DeclarationChoice) -> Vec<DeclarationSite> {
+        let mut sites = self
+            .found
+            .iter()
+            .filter(|choice| {
+                choice.exact == chosen.exact
+                    && choice.is_preferred() == chosen.is_preferred()
+                    && choice.path_score < chosen.path_score
+            })
+            .filter_map(|choice| choice.site.clone().ok())
+            .collect::<Vec<_>>();
+        sites.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        sites.dedup();
+        sites
+    }
+
+    /// Choose between the inlined functions if no others were found, then
+    /// between the default bodies of the trait method.
+    fn use_fallbacks(&mut self) {
+        if self.found.is_empty() {
+            self.found = std::mem::take(&mut self.inlined);
+            merge_inlined_calls(&mut self.found);
+        }
+        if self.found.is_empty() {
+            self.found = std::mem::take(&mut self.default_impls);
+        }
+        if self.found.is_empty() {
+            self.found = std::mem::take(&mut self.relaxed);
+            self.found.sort_by_key(|choice| choice.relaxation);
+        }
+    }
+
+    /// The function which matched at the least relaxed level, if no other
+    /// function (other than copies of it) matched at that level.
+    fn unique_relaxed(mut self) -> Option<DeclarationChoice> {
+        let level = self.found.first()?.relaxation;
+        self.found.retain(|choice| choice.relaxation == level);
+        let first = &self.found[0];
+        if !self.found.iter().all(|choice| choice.is_copy_of(first)) {
+            return None;
+        }
+        let candidates = self.found.len();
+        let mut chosen = self.found.swap_remove(0);
+        chosen.candidates = candidates;
+        let matches = chosen.ambiguous_matches(&self.found);
+        chosen.set_ambiguity(matches);
+        Some(redaction::choice(chosen))
+    }
+
+    /// Set [`bin_and_lib`](DeclarationChoice::bin_and_lib) for the functions
+    /// which matched exactly in both the bin and lib targets of a package.
+    fn set_targets(&mut self) {
+        let packages = |target| {
+            self.found
+                .iter()
+                .filter(|choice| choice.is_preferred() && choice.target == Some(target))
+                .filter_map(DeclarationChoice::package)
+                .collect::<Vec<_>>()
+        };
+        let (bins, libs) = (packages(CrateTarget::Bin), packages(CrateTarget::Lib));
+        let ambiguous = bins
+            .into_iter()
+            .filter(|package| libs.contains(package))
+            .map(|(comp_dir, package)| (comp_dir.map(str::to_owned), package.to_owned()))
+            .collect::<Vec<_>>();
+        for choice in &mut self.found {
+            choice.bin_and_lib = choice.is_preferred()
+                && choice.package().is_some_and(|(comp_dir, package)| {
+                    ambiguous
+                        .iter()
+                        .any(|it| it.0.as_deref() == comp_dir && it.1 == package)
+                });
+        }
+    }
+
+    /// Set the [`other_versions`](DeclarationChoice::other_versions) of each
+    /// function, from the versions of the functions which matched exactly.
+    fn set_versions(&mut self) {
+        let mut versions = self
+            .found
+            .iter()
+            .filter(|choice| choice.is_preferred())
+            .filter_map(|choice| choice.version.clone())
+            .collect::<Vec<_>>();
+        versions.sort();
+        versions.dedup();
+        if versions.len() < 2 {
+            return;
+        }
+        for choice in &mut self.found {
+            choice.other_versions = versions
+                .iter()
+                .filter(|version| choice.version.as_ref() != Some(*version))
+                .cloned()
+                .collect();
+        }
+    }
+}
+
+/// Give each of the inlined `choices` the calls of every copy of it.
+fn merge_inlined_calls(choices: &mut [DeclarationChoice]) {
+    let calls = choices
+        .iter()
+        .map(|choice| {
+            let mut calls = choices
+                .iter()
+                .filter(|other| other.is_copy_of(choice))
+                .flat_map(|other| other.inlined_into.iter().cloned())
+                .collect::<Vec<_>>();
+            calls.sort_by(|a, b| {
+                let a_site = a.call_site.as_ref().map(|it| (&it.file, it.line));
+                let b_site = b.call_site.as_ref().map(|it| (&it.file, it.line));
+                (a_site, &a.caller).cmp(&(b_site, &b.caller))
+            });
+            calls.dedup();
+            calls
+        })
+        .collect::<Vec<_>>();
+    for (choice, calls) in choices.iter_mut().zip(calls) {
+        choice.inlined_into = calls;
+    }
+}
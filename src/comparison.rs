@@ -0,0 +1,237 @@
+//! Comparing the declaration sites of two builds of the same code, to check
+//! that builds are reproducible.
+
+use std::fmt;
+
+use crate::{DeclarationIndex, DeclarationSite};
+
+/// How [`compare_function_maps`] decides which differences are benign.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ComparisonOptions {
+    /// Prefixes replaced in the files of both maps before they are compared,
+    /// as `(from, to)`, in the same way as rustc's
+    /// `--remap-path-prefix from=to`. The first prefix which matches a file
+    /// is used, and `/` and `\` are treated as the same separator.
+    ///
+    /// For example, `("/runner-1/work".into(), "".into())` and
+    /// `("/runner-2/work".into(), "".into())` make the checkouts of two CI
+    /// runners the same
+    pub remap_prefixes: Vec<(String, String)>,
+    /// The most lines a function can move within the same file and still be
+    /// reported as [`line_drift`](MapComparison::line_drift), rather than
+    /// [`mismatched`](MapComparison::mismatched)
+    pub max_line_drift: u32,
+}
+
+/// The differences between the declaration sites of two function maps, from
+/// [`compare_function_maps`].
+///
+/// Each list is sorted by function name. Its [`Display`](fmt::Display)
+/// implementation summarises the differences, and lists those which aren't
+/// benign.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapComparison {
+    /// The number of functions with the same declaration site in both maps
+    pub identical: usize,
+    /// The functions whose files are only different in their prefix, after
+    /// applying [`ComparisonOptions::remap_prefixes`], on the same line. A
+    /// relative file is also the same as an absolute file ending with it,
+    /// such as `src/lib.rs` and `/build/my_crate/src/lib.rs`
+    pub path_prefix: Vec<SiteDifference>,
+    /// The functions in the same file (as for
+    /// [`path_prefix`](Self::path_prefix)), which moved by at most
+    /// [`ComparisonOptions::max_line_drift`] lines
+    pub line_drift: Vec<SiteDifference>,
+    /// The functions which are declared in a different file, or moved by
+    /// more than [`ComparisonOptions::max_line_drift`] lines
+    pub mismatched: Vec<SiteDifference>,
+    /// The functions which are only in the first map, with their declaration
+    /// sites
+    pub only_in_a: Vec<(String, DeclarationSite)>,
+    /// The functions which are only in the second map, with their
+    /// declaration sites
+    pub only_in_b: Vec<(String, DeclarationSite)>,
+}
+
+/// A function whose declaration site differs between two function maps, in a
+/// [`MapComparison`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SiteDifference {
+    /// The canonical name of the function
+    pub name: String,
+    /// The declaration site in the first map
+    pub a: DeclarationSite,
+    /// The declaration site in the second map
+    pub b: DeclarationSite,
+}
+
+impl MapComparison {
+    /// Whether every function is in both maps, and only differs in ways
+    /// which are benign ([`path_prefix`](Self::path_prefix) and
+    /// [`line_drift`](Self::line_drift)).
+    ///
+    /// This is suitable for failing a CI job when two builds aren't
+    /// consistent.
+    pub fn is_consistent(&self) -> bool {
+        self.mismatched.is_empty() && self.only_in_a.is_empty() && self.only_in_b.is_empty()
+    }
+
+    /// The number of functions which were compared, including those only in
+    /// one of the maps.
+    pub fn functions(&self) -> usize {
+        self.identical
+            + self.path_prefix.len()
+            + self.line_drift.len()
+            + self.mismatched.len()
+            + self.only_in_a.len()
+            + self.only_in_b.len()
+    }
+}
+
+/// Compare the declaration sites of the functions in two function maps (such
+/// as the binaries of the same commit built on two machines), classifying
+/// each difference as benign or not using `options`.
+///
+/// Functions are matched by name. To compare the output of
+/// [`export_function_map`](crate::export_function_map), collect its rows into
+/// a [`DeclarationIndex`] (with the `serde` feature), or build the indexes
+/// directly with [`DeclarationIndex::from_file`]. The result can be written
+/// for other programs as a
+/// [`ComparisonDocument`](crate::schema::ComparisonDocument).
+///
+/// ```rust
+/// use declaration_site::{compare_function_maps, ComparisonOptions, DeclarationIndex, DeclarationSite};
+///
+/// let site = |file: &str, line| DeclarationSite { file: file.into(), line };
+/// let a: DeclarationIndex = [
+///     ("my_crate::main".to_owned(), site("/runner-1/my_crate/src/main.rs", 3)),
+///     ("my_crate::helper".to_owned(), site("/runner-1/my_crate/src/lib.rs", 10)),
+///     ("my_crate::other".to_owned(), site("/runner-1/my_crate/src/lib.rs", 20)),
+/// ]
+/// .into_iter()
+/// .collect();
+/// let b: DeclarationIndex = [
+///     ("my_crate::main".to_owned(), site("/runner-2/my_crate/src/main.rs", 3)),
+///     ("my_crate::helper".to_owned(), site("/runner-2/my_crate/src/lib.rs", 11)),
+///     ("my_crate::other".to_owned(), site("/runner-2/my_crate/src/other.rs", 20)),
+/// ]
+/// .into_iter()
+/// .collect();
+/// let options = ComparisonOptions {
+///     remap_prefixes: vec![
+///         ("/runner-1".into(), "".into()),
+///         ("/runner-2".into(), "".into()),
+///     ],
+///     max_line_drift: 2,
+/// };
+/// let comparison = compare_function_maps(&a, &b, &options);
+/// assert_eq!(comparison.path_prefix[0].name, "my_crate::main");
+/// assert_eq!(comparison.line_drift[0].name, "my_crate::helper");
+/// assert_eq!(comparison.mismatched[0].name, "my_crate::other");
+/// assert!(!comparison.is_consistent());
+/// println!("{comparison}");
+/// ```
+pub fn compare_function_maps(
+    a: &DeclarationIndex,
+    b: &DeclarationIndex,
+    options: &ComparisonOptions,
+) -> MapComparison {
+    let mut comparison = MapComparison::default();
+    for (name, a_site) in a.iter() {
+        let Some(b_site) = b.get(name) else {
+            comparison.only_in_a.push((name.to_owned(), a_site.clone()));
+            continue;
+        };
+        if a_site == b_site {
+            comparison.identical += 1;
+            continue;
+        }
+        let difference = SiteDifference {
+            name: name.to_owned(),
+            a: a_site.clone(),
+            b: b_site.clone(),
+        };
+        let same_file = is_same_file(&a_site.file, &b_site.file, &options.remap_prefixes);
+        if same_file && a_site.line == b_site.line {
+            comparison.path_prefix.push(difference);
+        } else if same_file && a_site.line.abs_diff(b_site.line) <= options.max_line_drift {
+            comparison.line_drift.push(difference);
+        } else {
+            comparison.mismatched.push(difference);
+        }
+    }
+    for (name, b_site) in b.iter() {
+        if a.get(name).is_none() {
+            comparison.only_in_b.push((name.to_owned(), b_site.clone()));
+        }
+    }
+    comparison
+}
+
+/// Whether the files `a` and `b` are the same file, after applying
+/// `remap_prefixes` to both.
+fn is_same_file(a: &str, b: &str, remap_prefixes: &[(String, String)]) -> bool {
+    let (a, b) = (remap(a, remap_prefixes), remap(b, remap_prefixes));
+    if a == b {
+        return true;
+    }
+    let (shorter, longer) = if a.len() < b.len() { (a, b) } else { (b, a) };
+    // A relative path is the same as an absolute path ending with it
+    !shorter.starts_with('/')
+        && !shorter.is_empty()
+        && longer.ends_with(&shorter)
+        && longer[..longer.len() - shorter.len()].ends_with('/')
+}
+
+/// `file` with its separators replaced by `/`, and the first matching prefix
+/// of `remap_prefixes` replaced.
+fn remap(file: &str, remap_prefixes: &[(String, String)]) -> String {
+    let file = file.replace('\\', "/");
+    for (from, to) in remap_prefixes {
+        let from = from.replace('\\', "/");
+        let from = from.trim_end_matches('/');
+        let Some(rest) = file.strip_prefix(from) else {
+            continue;
+        };
+        // Only whole components are replaced
+        if rest.is_empty() || rest.starts_with('/') {
+            let to = to.replace('\\', "/");
+            let rest = if to.is_empty() {
+                rest.trim_start_matches('/')
+            } else {
+                rest
+            };
+            return format!("{}{rest}", to.trim_end_matches('/'));
+        }
+    }
+    file
+}
+
+impl fmt::Display for MapComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "compared {} function(s): {} identical, {} differing only in path prefix, \
+             {} with line drift, {} mismatched, {} only in the first map, {} only in the second map",
+            self.functions(),
+            self.identical,
+            self.path_prefix.len(),
+            self.line_drift.len(),
+            self.mismatched.len(),
+            self.only_in_a.len(),
+            self.only_in_b.len()
+        )?;
+        for SiteDifference { name, a, b } in &self.mismatched {
+            write!(f, "\n  mismatched {name}: {a} != {b}")?;
+        }
+        for (name, site) in &self.only_in_a {
+            write!(f, "\n  only in the first map: {name} at {site}")?;
+        }
+        for (name, site) in &self.only_in_b {
+            write!(f, "\n  only in the second map: {name} at {site}")?;
+        }
+        Ok(())
+    }
+}
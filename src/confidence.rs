@@ -0,0 +1,54 @@
+//! Summarising how far the declaration site of a chosen function can be
+//! trusted, from [`DeclarationChoice::confidence`].
+
+use crate::{
+    Confidence, ConfidenceFactor, DeclarationChoice, LineProvenance, MatchRelaxation, PairingStatus,
+};
+
+/// The factors limiting the confidence of `choice`.
+pub(crate) fn factors(choice: &DeclarationChoice) -> Vec<ConfidenceFactor> {
+    let mut factors = Vec::new();
+    if choice.relaxation != MatchRelaxation::None {
+        factors.push(ConfidenceFactor::Relaxed(choice.relaxation));
+    } else if choice.via_default_impl {
+        factors.push(ConfidenceFactor::DefaultImpl);
+    } else if !choice.exact {
+        factors.push(ConfidenceFactor::Spelling);
+    }
+    if choice.in_own_crate == Some(false) {
+        factors.push(ConfidenceFactor::OtherCrate);
+    }
+    match choice.provenance {
+        Some(LineProvenance::LineTableFirst) => {}
+        Some(LineProvenance::LineTableMin) => factors.push(ConfidenceFactor::LowestLine),
+        Some(LineProvenance::Inlinee) => factors.push(ConfidenceFactor::Inlinee),
+        // `LineProvenance::Heuristic`, and any provenances added to
+        // declaration_site_core later, which can't be trusted further
+        Some(_) => factors.push(ConfidenceFactor::Heuristic),
+        None => factors.push(ConfidenceFactor::NoLines),
+    }
+    if choice.ambiguous {
+        factors.push(ConfidenceFactor::Ambiguous);
+    }
+    if !choice.other_versions.is_empty() {
+        factors.push(ConfidenceFactor::OtherVersions);
+    }
+    if choice.bin_and_lib {
+        factors.push(ConfidenceFactor::BinAndLib);
+    }
+    match choice.debug_pairing {
+        None | Some(PairingStatus::Matched) => {}
+        Some(PairingStatus::Unverifiable) => factors.push(ConfidenceFactor::UnverifiedDebugFile),
+        Some(PairingStatus::Mismatched) => factors.push(ConfidenceFactor::MismatchedDebugFile),
+    }
+    factors
+}
+
+/// The confidence of a choice with `factors`.
+pub(crate) fn confidence(factors: &[ConfidenceFactor]) -> Confidence {
+    factors
+        .iter()
+        .map(|factor| factor.limit())
+        .min()
+        .unwrap_or(Confidence::Exact)
+}
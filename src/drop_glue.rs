@@ -0,0 +1,61 @@
+//! Finding where the drop glue of a type comes from.
+
+use std::any::type_name;
+
+use crate::{declarations_by_name, redaction, DeclarationSite};
+
+/// Attempt to get the declaration site of the code which runs when a value of
+/// type `T` is dropped, for diagnosing drop-related bugs.
+///
+/// This looks up the drop glue of `T` (`core::ptr::drop_in_place::<T>`,
+/// which newer rustc versions name `core::ptr::drop_glue::<T>`) and its
+/// [`Drop`] impl (`<T as core::ops::drop::Drop>::drop`) in a single scan. The
+/// line info of drop glue always points to `drop_in_place` in the standard
+/// library, so the site of `T`'s [`Drop::drop`] is returned unless the glue's
+/// site is elsewhere. If `T` doesn't implement [`Drop`] (so it only drops
+/// its fields), or its `drop` wasn't found, the site of the drop glue in the
+/// standard library is returned, which shows that `T` has drop glue.
+///
+/// Both functions must be linked, which happens when a `T` is dropped. The
+/// declaration of `T` itself can't be found, as only functions are searched.
+///
+/// ```rust,no_run
+/// use declaration_site::drop_glue_declaration;
+///
+/// struct Connection;
+///
+/// impl Drop for Connection {
+///     fn drop(&mut self) {}
+/// }
+///
+/// drop(Connection);
+/// let site = drop_glue_declaration::<Connection>().unwrap();
+/// println!("`Connection` is dropped by {site}");
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn drop_glue_declaration<T: ?Sized>() -> Option<DeclarationSite> {
+    let type_name = type_name::<T>();
+    let names = [
+        format!("core::ptr::drop_in_place<{type_name}>"),
+        format!("core::ptr::drop_glue<{type_name}>"),
+        format!("<{type_name} as core::ops::drop::Drop>::drop"),
+    ];
+    // The files are compared before they are redacted
+    let sites =
+        redaction::unredacted(|| declarations_by_name(&names.each_ref().map(String::as_str)));
+    let [in_place, glue, drop] = sites.try_into().ok()?;
+    let glue = in_place.or(glue);
+    let site = match glue {
+        Some(site) if !is_drop_in_place(&site) => Some(site),
+        _ => drop.or(glue),
+    };
+    site.map(redaction::site)
+}
+
+/// Whether `site` is `drop_in_place` in the standard library, where the line
+/// info of drop glue points.
+fn is_drop_in_place(site: &DeclarationSite) -> bool {
+    let file = site.file.replace('\\', "/");
+    file.ends_with("core/src/ptr/mod.rs")
+}
@@ -0,0 +1,221 @@
+//! Counting the copies of generic functions, to find the causes of binary
+//! bloat.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "process")]
+use crate::{cancellation, scan_file_data, scan_loaded_files, IterationControl};
+use crate::{
+    for_functions_in_target, names, redaction, DeclarationSite, FunctionRecord, NormalizeOptions,
+    ScanTarget,
+};
+
+/// Count the functions in each object of `target`, and find the `top_n`
+/// functions in each with the most copies.
+///
+/// Copies are grouped by their path with generic arguments erased (see
+/// [`NormalizeOptions::erase_generics`]), so each monomorphization of a
+/// generic function (and each copy compiled into the codegen units of other
+/// crates) counts as a copy. The size of a group is the total size of the
+/// code of its copies. Only groups with more than one copy are reported.
+///
+/// For [`ScanTarget::CurrentProcess`], each loaded library is reported
+/// separately.
+///
+/// ```rust
+/// use declaration_site::{duplication_report, ScanTarget};
+///
+/// let report = duplication_report(ScanTarget::CurrentProcess, 5).unwrap();
+/// println!("{report}");
+/// ```
+///
+/// # Errors
+///
+/// If `target` is a file which could not be read or parsed, or the scan was
+/// cancelled (with [`io::ErrorKind::Interrupted`], see
+/// [`with_cancellation`](crate::with_cancellation))
+pub fn duplication_report(target: ScanTarget<'_>, top_n: usize) -> io::Result<DuplicationReport> {
+    let mut objects = BTreeMap::<ObjectKey, ObjectBuilder>::new();
+    let mut visit = |function: FunctionRecord<'_>| {
+        let key = ObjectKey {
+            path: function.object_path().map(Path::to_path_buf),
+            name: function.object_name().map(ToOwned::to_owned),
+            index: function.object_index(),
+        };
+        objects.entry(key).or_default().add(&function);
+    };
+    match target {
+        #[cfg(feature = "process")]
+        ScanTarget::CurrentProcess => {
+            let summary = scan_loaded_files(|path, data, _| {
+                let _ = scan_file_data(data, Some(path), &mut visit);
+                IterationControl::Continue
+            });
+            if summary.cancelled {
+                return Err(cancellation::interrupted_error());
+            }
+        }
+        target => for_functions_in_target(target, &mut visit)?,
+    }
+    Ok(DuplicationReport {
+        objects: objects
+            .into_iter()
+            .map(|(key, builder)| builder.build(key, top_n))
+            .collect(),
+    })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+struct ObjectKey {
+    path: Option<PathBuf>,
+    name: Option<String>,
+    index: usize,
+}
+
+#[derive(Default)]
+struct ObjectBuilder {
+    functions: usize,
+    names: HashSet<String>,
+    groups: HashMap<String, DuplicatedFunction>,
+}
+
+impl ObjectBuilder {
+    fn add(&mut self, function: &FunctionRecord<'_>) {
+        self.functions += 1;
+        let name = names::normalize_function_path(function.name(), NormalizeOptions::default());
+        self.names.insert(name.into_string());
+        let erased = NormalizeOptions {
+            erase_generics: true,
+            ..NormalizeOptions::default()
+        };
+        let base_path = names::normalize_function_path(function.name(), erased).into_string();
+        let group = self
+            .groups
+            .entry(base_path)
+            .or_insert_with_key(|base_path| DuplicatedFunction {
+                base_path: base_path.clone(),
+                copies: 0,
+                total_size: 0,
+                site: None,
+            });
+        group.copies += 1;
+        group.total_size += function.size();
+        if group.site.is_none() {
+            group.site = DeclarationSite::try_from(function)
+                .ok()
+                .map(redaction::site);
+        }
+    }
+
+    fn build(self, key: ObjectKey, top_n: usize) -> ObjectDuplication {
+        let mut duplicated = self
+            .groups
+            .into_values()
+            .filter(|group| group.copies > 1)
+            .collect::<Vec<_>>();
+        duplicated.sort_by(|a, b| {
+            (b.copies, b.total_size)
+                .cmp(&(a.copies, a.total_size))
+                .then_with(|| a.base_path.cmp(&b.base_path))
+        });
+        duplicated.truncate(top_n);
+        ObjectDuplication {
+            path: key.path.map(|it| it.to_string_lossy().into_owned()),
+            object_name: key.name,
+            object_index: key.index,
+            functions: self.functions,
+            distinct_names: self.names.len(),
+            duplicated,
+        }
+    }
+}
+
+/// The functions with the most copies in each object, from
+/// [`duplication_report`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DuplicationReport {
+    /// Each object which contained functions, sorted by path
+    pub objects: Vec<ObjectDuplication>,
+}
+
+/// The functions in an object, in a [`DuplicationReport`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectDuplication {
+    /// The path of the file containing the object, if several files were
+    /// searched (see [`FunctionRecord::object_path`])
+    pub path: Option<String>,
+    /// The name of the object within its file, if it is named (see
+    /// [`FunctionRecord::object_name`])
+    pub object_name: Option<String>,
+    /// The index of the object within its file (see
+    /// [`FunctionRecord::object_index`])
+    pub object_index: usize,
+    /// The number of functions in the object
+    pub functions: usize,
+    /// The number of distinct names of the functions in the object, after
+    /// [`normalize_function_path`](crate::normalize_function_path). Legacy
+    /// demangled names don't include the generic arguments of most generic
+    /// functions, so this is usually lower for legacy mangling than for v0
+    pub distinct_names: usize,
+    /// The functions with the most copies, with the most first
+    pub duplicated: Vec<DuplicatedFunction>,
+}
+
+/// The copies of a function, in an [`ObjectDuplication`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DuplicatedFunction {
+    /// The path of the function with its generic arguments erased
+    pub base_path: String,
+    /// The number of copies of the function
+    pub copies: usize,
+    /// The total size of the code of the copies, in bytes
+    pub total_size: u64,
+    /// The declaration site of the first copy found with one
+    pub site: Option<DeclarationSite>,
+}
+
+impl fmt::Display for DuplicationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, object) in self.objects.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{object}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ObjectDuplication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.path, &self.object_name) {
+            (Some(path), Some(name)) => write!(f, "{path} ({name})")?,
+            (Some(path), None) => write!(f, "{path}")?,
+            (None, Some(name)) => write!(f, "{name}")?,
+            (None, None) => write!(f, "object {}", self.object_index)?,
+        }
+        write!(
+            f,
+            ": {} function(s), {} distinct name(s)",
+            self.functions, self.distinct_names
+        )?;
+        for function in &self.duplicated {
+            write!(
+                f,
+                "\n  {} copies, {} bytes: {}",
+                function.copies, function.total_size, function.base_path
+            )?;
+            if let Some(site) = &function.site {
+                write!(f, " at {site}")?;
+            }
+        }
+        Ok(())
+    }
+}
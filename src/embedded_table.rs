@@ -0,0 +1,262 @@
+//! Tables of the declaration sites of a program's functions, which can be
+//! embedded into it so that its functions can be found once it is stripped.
+
+#[cfg(feature = "process")]
+use std::{collections::HashMap, sync::RwLock};
+use std::{error::Error, fmt, io, path::Path};
+
+use crate::{
+    index_cache::{self, SourceIdentity},
+    DeclarationIndex,
+};
+#[cfg(feature = "process")]
+use crate::{
+    names::{self, NameMatch},
+    CacheStatus, DeclarationSearch, DeclarationSite, MatchRelaxation,
+};
+
+/// The tables registered with [`register_embedded_table`], in the order they
+/// were registered.
+#[cfg(feature = "process")]
+static TABLES: RwLock<Vec<Table>> = RwLock::new(Vec::new());
+
+/// A table registered with [`register_embedded_table`], with its functions
+/// keyed by [`match_key`] so that lookups only compare a name with the
+/// functions it could match.
+#[cfg(feature = "process")]
+struct Table {
+    /// The canonical name and declaration site of each function
+    functions: Vec<(String, DeclarationSite)>,
+    /// The indices into `functions` for each [`match_key`] of their names,
+    /// and of the `async fn` whose future each function is the body of
+    by_key: HashMap<String, Vec<usize>>,
+}
+
+#[cfg(feature = "process")]
+impl Table {
+    fn new(index: &DeclarationIndex) -> Self {
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        let functions = index
+            .iter()
+            .enumerate()
+            .map(|(function, (name, site))| {
+                by_key.entry(match_key(name)).or_default().push(function);
+                if let Some(parent) = names::generated_future_parent(name) {
+                    by_key.entry(match_key(parent)).or_default().push(function);
+                }
+                (name.to_owned(), site.clone())
+            })
+            .collect();
+        Table { functions, by_key }
+    }
+
+    /// The functions whose names could match `query`.
+    fn candidates(&self, query: &str) -> impl Iterator<Item = (&str, &DeclarationSite)> {
+        self.by_key
+            .get(&match_key(query))
+            .into_iter()
+            .flatten()
+            .map(|&function| {
+                let (name, site) = &self.functions[function];
+                (name.as_str(), site)
+            })
+    }
+}
+
+/// The key shared by the names of functions and the names they match in
+/// [`find`], with or without relaxation: the last identifier of their
+/// [`relaxed_key`](names::relaxed_key), without the disambiguators of
+/// closures (as in [`wildcard_key`](names::wildcard_key)).
+#[cfg(feature = "process")]
+fn match_key(name: &str) -> String {
+    names::wildcard_key(names::last_ident(&names::relaxed_key(name))).into_owned()
+}
+
+/// Export the declaration sites of the functions in the binary or debug file
+/// at `path` as a compact table, to be embedded into a build of the same
+/// program without debug info.
+///
+/// Release builds are often stripped of their debug info, so lookups such as
+/// [`declaration_of`](crate::declaration_of) find nothing in them. Run this
+/// against a build of the program with debug info (such as from a build
+/// script, or in CI), embed the table with [`include_bytes!`] in the build
+/// which is shipped, then register it with [`register_embedded_table`].
+///
+/// The table is in the format of the cache files of
+/// [`DeclarationIndex::from_file_cached`], with only the fields which stay
+/// correct once the program is stripped: the name and declaration site of
+/// each function, without the length and debug ids of the file it was read
+/// from. Only the functions found by iterating over the file's functions are
+/// included (so not those in DWARF 5 written by LLVM, which are only found
+/// by name). Redaction (see [`set_redaction`](crate::set_redaction)) is
+/// applied to the paths in the table, so that they aren't shipped.
+///
+/// ```rust,no_run
+/// use declaration_site::export_compact_table;
+///
+/// let table = export_compact_table("target/debug/my_program").unwrap();
+/// std::fs::write("target/my_program.declarations", table).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// If the file could not be read, or is not a supported object file format.
+/// If a name or path is too long for the format (more than 4 GiB), with
+/// [`io::ErrorKind::InvalidData`]
+pub fn export_compact_table(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let index = DeclarationIndex::from_file(path)?;
+    index_cache::encode(&index, &SourceIdentity::default(), index_cache::TABLE_FLAGS)
+}
+
+/// An error returned by [`register_embedded_table`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum EmbeddedTableError {
+    /// The table was written by an incompatible version of the format or of
+    /// this crate, or isn't a table from [`export_compact_table`] (such as a
+    /// cache file of [`DeclarationIndex::from_file_cached`])
+    Incompatible,
+    /// The table is malformed, or its checksum is wrong
+    Corrupted,
+}
+
+impl fmt::Display for EmbeddedTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EmbeddedTableError::Incompatible => {
+                "the table was written by an incompatible version, or isn't an embedded table"
+            }
+            EmbeddedTableError::Corrupted => "the table is corrupted",
+        })
+    }
+}
+
+impl Error for EmbeddedTableError {}
+
+/// Register a table written by [`export_compact_table`], such as one embedded
+/// into the program with [`include_bytes!`], returning the number of
+/// functions in it.
+///
+/// Once a table is registered, [`declaration_by_name`](crate::declaration_by_name)
+/// (and the lookups which use it, such as
+/// [`declaration_of`](crate::declaration_of) and
+/// [`try_declaration_by_name`](crate::try_declaration_by_name)) look the name
+/// up in the registered tables before scanning the loaded libraries, which
+/// are only scanned if no table has the function. The names in the tables
+/// are matched as the names of loaded functions are, so closures,
+/// `Type::method` spellings of trait methods, the symbols of `#[no_mangle]`
+/// functions and [relaxed](crate::DeclarationSearch::relaxed) matching find
+/// functions in them too. Lookups which choose
+/// between several functions (such as
+/// [`DeclarationSearch::version`](crate::DeclarationSearch::version)),
+/// or which return every function or the details of the choice (such as
+/// [`explain_declaration_by_name`](crate::explain_declaration_by_name)), don't
+/// use the tables.
+///
+/// The table should be exported from a build of the same source as the
+/// program, as the sites in it aren't checked against the program.
+///
+/// ```rust,ignore
+/// use declaration_site::{declaration_of, register_embedded_table};
+///
+/// static DECLARATIONS: &[u8] = include_bytes!(env!("MY_PROGRAM_DECLARATIONS"));
+///
+/// fn main() {
+///     register_embedded_table(DECLARATIONS).expect("Should have read the table");
+///     println!("{:?}", declaration_of(&main));
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If `table` isn't a table written by [`export_compact_table`] with this
+/// version of this crate, or is corrupted
+#[cfg(feature = "process")]
+pub fn register_embedded_table(table: &[u8]) -> Result<usize, EmbeddedTableError> {
+    let (_, index) =
+        index_cache::decode(table, index_cache::TABLE_FLAGS).map_err(|status| match status {
+            CacheStatus::Incompatible => EmbeddedTableError::Incompatible,
+            _ => EmbeddedTableError::Corrupted,
+        })?;
+    let table = Table::new(&index);
+    TABLES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(table);
+    Ok(index.len())
+}
+
+/// The declaration site of the function found by `search` in the first
+/// registered table which has it.
+#[cfg(feature = "process")]
+pub(crate) fn declaration(search: &DeclarationSearch<'_>) -> Option<DeclarationSite> {
+    let tables = TABLES.read().unwrap_or_else(|e| e.into_inner());
+    tables
+        .iter()
+        .find_map(|table| find(table, search.name, search.relaxation))
+}
+
+/// The declaration site of the function named `query` in `table`, matching
+/// the names in it as the names of loaded functions are (see
+/// [`FunctionRecord::match_name`]), or `None` if no function or several
+/// different functions matched.
+///
+/// As when scanning, the closure declared on the lowest line is chosen if
+/// only closures matched, and functions are only matched with `relaxation`
+/// if nothing matched without it. Only the functions with the same
+/// [`match_key`] as `query` are compared with it.
+#[cfg(feature = "process")]
+fn find(table: &Table, query: &str, relaxation: MatchRelaxation) -> Option<DeclarationSite> {
+    let canonical_query = names::canonical_name(query);
+    if let Some((_, site)) = table
+        .candidates(query)
+        .find(|&(name, _)| name == canonical_query)
+    {
+        return Some(site.clone());
+    }
+    let mut matches = table
+        .candidates(query)
+        .filter_map(|(name, site)| {
+            let kind = names::match_names(name, query)
+                .or_else(|| names::match_future_body(name, query))
+                .or_else(|| names::match_default_method(name, query))
+                .or_else(|| names::match_unmangled(name, query, false))?;
+            Some((kind, site))
+        })
+        .collect::<Vec<_>>();
+    if matches.is_empty() && relaxation != MatchRelaxation::None {
+        let query_key = names::relaxed_key(query);
+        let relaxed = table
+            .candidates(query)
+            .filter_map(|(name, site)| {
+                let level =
+                    names::match_relaxed(&names::relaxed_key(name), &query_key, relaxation)?;
+                Some((level, site))
+            })
+            .collect::<Vec<_>>();
+        let least = relaxed.iter().map(|&(level, _)| level).min()?;
+        matches = relaxed
+            .into_iter()
+            .filter(|&(level, _)| level == least)
+            .map(|(_, site)| (NameMatch::Exact, site))
+            .collect();
+    }
+    if matches.is_empty() {
+        return None;
+    }
+    if matches.iter().any(|&(kind, _)| kind == NameMatch::Exact) {
+        matches.retain(|&(kind, _)| kind == NameMatch::Exact);
+    }
+    if matches.iter().all(|&(kind, _)| kind == NameMatch::Wildcard) {
+        return matches
+            .into_iter()
+            .map(|(_, site)| site)
+            .min_by_key(|site| (&site.file, site.line))
+            .cloned();
+    }
+    let (_, first) = matches[0];
+    matches
+        .iter()
+        .all(|&(_, site)| site == first)
+        .then(|| first.clone())
+}
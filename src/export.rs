@@ -0,0 +1,102 @@
+//! Writing every function and its declaration site to a file, enabled by the
+//! `serde` feature.
+
+use std::io;
+
+use crate::{
+    for_functions_in_target, redaction,
+    schema::{FunctionMapRow, SCHEMA_VERSION},
+    DeclarationSite, FunctionRecord, IterationControl, ScanTarget,
+};
+
+/// The format of the output of [`export_function_map`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ExportFormat {
+    /// One JSON object per line, each a [`FunctionMapRow`]. `file` and `line`
+    /// are `null` for functions without source locations
+    JsonLines,
+    /// Comma separated values, with a header row of
+    /// `name,file,line,address,size,schema_version`. `file` and `line` are
+    /// empty for functions without source locations
+    Csv,
+}
+
+/// Write the name, declaration site, address and size of every function found
+/// in `target` to `writer`.
+///
+/// Rows are written as the functions are found, so the memory used doesn't
+/// depend on the number of functions. Consider using a [`BufWriter`](io::BufWriter)
+/// for `writer`.
+///
+/// # Errors
+///
+/// If writing to `writer` fails, or if `target` is a file which could not be
+/// read or parsed.
+pub fn export_function_map(
+    target: ScanTarget<'_>,
+    mut writer: impl io::Write,
+    format: ExportFormat,
+) -> io::Result<()> {
+    if format == ExportFormat::Csv {
+        writeln!(writer, "name,file,line,address,size,schema_version")?;
+    }
+    let mut result = Ok(());
+    let mut callback = |function: FunctionRecord<'_>| {
+        let site = DeclarationSite::try_from(&function)
+            .ok()
+            .map(redaction::site);
+        let row = FunctionMapRow {
+            schema_version: SCHEMA_VERSION,
+            name: function.name().to_owned(),
+            file: site.as_ref().map(|it| it.file.clone()),
+            line: site.as_ref().map(|it| it.line),
+            address: function.address(),
+            size: function.size(),
+        };
+        result = write_row(&mut writer, &row, format);
+        if result.is_ok() {
+            IterationControl::Continue
+        } else {
+            IterationControl::Break
+        }
+    };
+    for_functions_in_target(target, &mut callback)?;
+    result?;
+    writer.flush()
+}
+
+fn write_row(
+    writer: &mut impl io::Write,
+    row: &FunctionMapRow,
+    format: ExportFormat,
+) -> io::Result<()> {
+    match format {
+        ExportFormat::JsonLines => {
+            serde_json::to_writer(&mut *writer, row)?;
+            writeln!(writer)
+        }
+        ExportFormat::Csv => {
+            write_csv_field(writer, &row.name)?;
+            write!(writer, ",")?;
+            write_csv_field(writer, row.file.as_deref().unwrap_or_default())?;
+            match row.line {
+                Some(line) => write!(writer, ",{line}")?,
+                None => write!(writer, ",")?,
+            }
+            writeln!(
+                writer,
+                ",{},{},{}",
+                row.address, row.size, row.schema_version
+            )
+        }
+    }
+}
+
+/// Write `field`, quoted if it contains characters which are special in CSV.
+fn write_csv_field(writer: &mut impl io::Write, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(writer, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        write!(writer, "{field}")
+    }
+}
@@ -0,0 +1,188 @@
+//! An [`eyre`] report handler which adds declaration sites to reports,
+//! enabled by the `eyre` feature.
+//!
+//! Call [`install_eyre_hook`] at the start of `main`, then attach functions to
+//! reports using [`WithDeclaration::with_declaration_of`]:
+//!
+//! ```rust,no_run
+//! use declaration_site::eyre::{install_eyre_hook, WithDeclaration};
+//!
+//! fn load_level() -> eyre::Result<()> {
+//!     Err(eyre::eyre!("level file missing")).with_declaration_of(&load_level)
+//! }
+//!
+//! install_eyre_hook().unwrap();
+//! if let Err(report) = load_level() {
+//!     // Prints the report, followed by "load_level defined at src/main.rs:3"
+//!     eprintln!("{report:?}");
+//! }
+//! ```
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+use ::eyre::{DefaultHandler, EyreHandler, InstallError, Report};
+
+use crate::{
+    annotate_backtrace, declarations_by_name, is_scanning, AnnotatedBacktrace, DeclarationSite,
+};
+
+/// Install [`DeclarationHandler`] as the handler for [`eyre::Report`],
+/// wrapping eyre's [`DefaultHandler`].
+///
+/// # Errors
+///
+/// If a hook has already been installed
+pub fn install_eyre_hook() -> Result<(), InstallError> {
+    ::eyre::set_hook(Box::new(|error| {
+        Box::new(DeclarationHandler {
+            inner: DefaultHandler::default_with(error),
+            declarations: Vec::new(),
+            backtrace: Backtrace::capture(),
+            annotated: OnceLock::new(),
+        })
+    }))
+}
+
+/// A report handler which prints the declaration sites of functions attached
+/// using [`WithDeclaration`] after the report from the wrapped handler.
+///
+/// If a backtrace was captured when the report was created (see
+/// [`Backtrace::capture`]), the declaration sites of the functions in its
+/// frames are also printed, found using [`annotate_backtrace`].
+///
+/// The declaration sites are only looked up when the report is printed using
+/// [`Debug`](fmt::Debug), so creating reports stays cheap. Sites which have
+/// been looked up before are reused, and the backtrace is only annotated the
+/// first time the report is printed.
+pub struct DeclarationHandler {
+    inner: Box<dyn EyreHandler>,
+    declarations: Vec<&'static str>,
+    backtrace: Backtrace,
+    /// The backtrace annotated with declaration sites, once the report has
+    /// been printed
+    annotated: OnceLock<AnnotatedBacktrace>,
+}
+
+impl DeclarationHandler {
+    /// The backtrace of the report, annotated with declaration sites.
+    fn annotated_backtrace(&self) -> Option<&AnnotatedBacktrace> {
+        if let Some(annotated) = self.annotated.get() {
+            return Some(annotated);
+        }
+        // Nothing would be found from inside a scan, so the backtrace is
+        // annotated the next time the report is printed instead
+        if is_scanning() {
+            return None;
+        }
+        Some(
+            self.annotated
+                .get_or_init(|| annotate_backtrace(&self.backtrace)),
+        )
+    }
+}
+
+/// The declaration sites previously found for reports, by function name.
+fn cached_declarations(names: &[&'static str]) -> Vec<Option<DeclarationSite>> {
+    static CACHE: OnceLock<Mutex<HashMap<&'static str, Option<DeclarationSite>>>> = OnceLock::new();
+    let cache = || {
+        CACHE
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    };
+    let missing = {
+        let cache = cache();
+        names
+            .iter()
+            .copied()
+            .filter(|name| !cache.contains_key(name))
+            .collect::<Vec<_>>()
+    };
+    // Scan without holding the lock, so a report printed from inside the scan
+    // (such as by a panic hook) doesn't deadlock
+    let found = if missing.is_empty() {
+        Vec::new()
+    } else {
+        declarations_by_name(&missing)
+    };
+    let mut cache = cache();
+    if is_scanning() {
+        // Nothing was searched, so the missing names aren't cached
+        return names
+            .iter()
+            .map(|name| cache.get(name).cloned().flatten())
+            .collect();
+    }
+    for (name, site) in missing.into_iter().zip(found) {
+        cache.entry(name).or_insert(site);
+    }
+    names.iter().map(|name| cache[name].clone()).collect()
+}
+
+impl EyreHandler for DeclarationHandler {
+    fn debug(&self, error: &(dyn Error + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.debug(error, f)?;
+        if !self.declarations.is_empty() {
+            write!(f, "\n\nDeclarations:")?;
+            let sites = cached_declarations(&self.declarations);
+            for (name, site) in self.declarations.iter().zip(sites) {
+                match site {
+                    Some(site) => write!(f, "\n    {name} defined at {site}")?,
+                    None => write!(f, "\n    {name} (declaration site not found)")?,
+                }
+            }
+        }
+        if self.backtrace.status() == BacktraceStatus::Captured {
+            let Some(backtrace) = self.annotated_backtrace() else {
+                return Ok(());
+            };
+            let mut declarations = backtrace.declarations().peekable();
+            if declarations.peek().is_some() {
+                write!(f, "\n\nBacktrace declarations:")?;
+                for (name, site) in declarations {
+                    write!(f, "\n    {name} defined at {site}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn display(&self, error: &(dyn Error + 'static), f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.display(error, f)
+    }
+
+    fn track_caller(&mut self, location: &'static std::panic::Location<'static>) {
+        self.inner.track_caller(location);
+    }
+}
+
+/// Attach functions to [`eyre::Report`]s, so that their declaration
+/// sites are printed with the report.
+///
+/// This has no effect unless [`install_eyre_hook`] has been called before the
+/// report was created.
+pub trait WithDeclaration {
+    /// Attach the function item type `function` to this report.
+    fn with_declaration_of<F>(self, function: &F) -> Self;
+}
+
+impl WithDeclaration for Report {
+    fn with_declaration_of<F>(mut self, _: &F) -> Self {
+        if let Some(handler) = self.handler_mut().downcast_mut::<DeclarationHandler>() {
+            handler.declarations.push(core::any::type_name::<F>());
+        }
+        self
+    }
+}
+
+impl<T> WithDeclaration for Result<T, Report> {
+    fn with_declaration_of<F>(self, function: &F) -> Self {
+        self.map_err(|report| report.with_declaration_of(function))
+    }
+}
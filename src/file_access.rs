@@ -0,0 +1,308 @@
+//! Reading files through a layer which can be replaced, such as by a virtual
+//! filesystem or a sandbox.
+
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    fs,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{strip_verbatim_prefix, verbatim_path};
+
+/// How this crate reads files, set for the current thread by
+/// [`with_file_access`].
+///
+/// Every file this crate reads goes through this, including the loaded
+/// libraries and their debug files, the files and directories searched by
+/// functions such as [`for_functions_in_file`](crate::for_functions_in_file),
+/// and the source files read by [`EmbeddedSources::read_source`](crate::EmbeddedSources::read_source).
+/// Paths are as this crate would pass them to [`std::fs`], such as the paths
+/// of loaded libraries reported by the loader. Absolute Windows paths of
+/// `MAX_PATH` (260) characters or more are passed in their extended-length form
+/// (`\\?\C:\...` or `\\?\UNC\server\...`, see
+/// [`verbatim_path`](crate::verbatim_path)), which Windows needs to open
+/// them, and the prefix is removed again from the paths returned by
+/// [`read_dir`](Self::read_dir).
+///
+/// The exception is the cache file of
+/// [`DeclarationIndex::from_file_cached`](crate::DeclarationIndex::from_file_cached),
+/// which is also written, so always uses [`std::fs`].
+///
+/// File accesses are `Send` and `Sync`, as the scans started on other threads
+/// on behalf of the current thread (such as by `spawn_scan`, with the `async`
+/// feature) read files through the file access of the current thread too.
+pub trait FileAccess: Send + Sync {
+    /// Read the whole contents of the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// The metadata of the file or directory at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If nothing exists at `path`, or it could not be accessed
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata>;
+
+    /// The paths of the entries directly in the directory at `path`, in any
+    /// order.
+    ///
+    /// # Errors
+    ///
+    /// If `path` is not a directory, or could not be read
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Read the bytes in `range` of the file at `path`, which are fewer if the
+    /// file ends before the end of `range`.
+    ///
+    /// This is used to check the format of a file before reading all of it,
+    /// and to read snippets of source files with [`read_snippet`](crate::read_snippet).
+    /// By default, this reads the whole file with [`read`](Self::read).
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let data = self.read(path)?;
+        let clamp =
+            |offset: u64| usize::try_from(offset).map_or(data.len(), |it| it.min(data.len()));
+        Ok(data[clamp(range.start)..clamp(range.end.max(range.start))].to_vec())
+    }
+
+    /// The canonical form of `path`, with every link resolved, as
+    /// [`fs::canonicalize`].
+    ///
+    /// This is used to find which loaded library was loaded from a path, by
+    /// [`for_functions_in_loaded_library`](crate::for_functions_in_loaded_library).
+    /// By default, this returns `path` unchanged, as for filesystems without
+    /// links.
+    ///
+    /// # Errors
+    ///
+    /// If nothing exists at `path`
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_owned())
+    }
+}
+
+/// The metadata of a file or directory, from [`FileAccess::metadata`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FileMetadata {
+    /// Whether this is a directory, rather than a file
+    pub is_dir: bool,
+    /// The length of the file in bytes
+    pub len: u64,
+}
+
+/// The [`FileAccess`] which reads files using [`std::fs`], used outside of
+/// [`with_file_access`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StdFileAccess;
+
+impl FileAccess for StdFileAccess {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let metadata = fs::metadata(path)?;
+        Ok(FileMetadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|it| it.path()))
+            .collect()
+    }
+
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let mut file = fs::File::open(path)?;
+        file.seek(SeekFrom::Start(range.start))?;
+        let mut data = Vec::new();
+        file.take(range.end.saturating_sub(range.start))
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+thread_local! {
+    /// The file access of the current [`with_file_access`] on the current
+    /// thread, if any.
+    static ACCESS: RefCell<Option<Arc<dyn FileAccess>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with every file read by this crate on the current thread going
+/// through `access`, returning its result.
+///
+/// This can be used to search programs in a virtual filesystem or a sandbox
+/// where [`std::fs`] can't be used, or to test code which uses this crate
+/// with files in memory. Outside of this function, [`StdFileAccess`] is used.
+///
+/// ```rust
+/// use std::{
+///     collections::HashMap,
+///     io,
+///     path::{Path, PathBuf},
+/// };
+///
+/// use declaration_site::{
+///     with_file_access, DeclarationSite, EmbeddedSources, FileAccess, FileMetadata,
+/// };
+///
+/// struct InMemory(HashMap<PathBuf, Vec<u8>>);
+///
+/// impl FileAccess for InMemory {
+///     fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+///         self.0.get(path).cloned().ok_or(io::ErrorKind::NotFound.into())
+///     }
+///
+///     fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+///         let data = self.0.get(path).ok_or(io::ErrorKind::NotFound)?;
+///         Ok(FileMetadata {
+///             is_dir: false,
+///             len: data.len() as u64,
+///         })
+///     }
+///
+///     fn read_dir(&self, _: &Path) -> io::Result<Vec<PathBuf>> {
+///         Err(io::ErrorKind::NotFound.into())
+///     }
+/// }
+///
+/// let files = HashMap::from([("src/greeting.rs".into(), b"fn greet() {}\n".to_vec())]);
+/// let site = DeclarationSite {
+///     file: "src/greeting.rs".into(),
+///     line: 1,
+/// };
+/// let source = with_file_access(InMemory(files), || EmbeddedSources::new().read_source(&site));
+/// assert_eq!(source.as_deref(), Some("fn greet() {}\n"));
+/// ```
+pub fn with_file_access<R>(access: impl FileAccess + 'static, f: impl FnOnce() -> R) -> R {
+    with_shared_file_access(Arc::new(access), f)
+}
+
+/// Run `f` with every file read by this crate on the current thread going
+/// through `access`, as [`with_file_access`].
+pub(crate) fn with_shared_file_access<R>(access: Arc<dyn FileAccess>, f: impl FnOnce() -> R) -> R {
+    let previous = ACCESS.with(|it| it.replace(Some(access)));
+    // Restore the previous file access even if `f` panics
+    let _guard = AccessGuard { previous };
+    f()
+}
+
+struct AccessGuard {
+    previous: Option<Arc<dyn FileAccess>>,
+}
+
+impl Drop for AccessGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        ACCESS.with(|it| *it.borrow_mut() = previous);
+    }
+}
+
+/// The file access of the current thread.
+fn current() -> Arc<dyn FileAccess> {
+    current_override().unwrap_or_else(|| Arc::new(StdFileAccess))
+}
+
+/// The file access of the current [`with_file_access`] on the current
+/// thread, if any.
+pub(crate) fn current_override() -> Option<Arc<dyn FileAccess>> {
+    ACCESS.with(|it| it.borrow().clone())
+}
+
+/// The longest Windows path which can be opened without the extended-length
+/// prefix, including its terminating nul.
+const MAX_PATH: usize = 260;
+
+/// The path `path` in the extended-length form of Windows paths, if it is an
+/// absolute Windows path too long to be opened otherwise.
+fn long_path(path: &Path) -> Cow<'_, Path> {
+    match path.to_str() {
+        Some(text) if text.len() >= MAX_PATH => match verbatim_path(text) {
+            Cow::Owned(verbatim) => Cow::Owned(verbatim.into()),
+            Cow::Borrowed(_) => Cow::Borrowed(path),
+        },
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Read the file at `path`, as [`fs::read`].
+pub(crate) fn read(path: &Path) -> io::Result<Vec<u8>> {
+    current().read(&long_path(path))
+}
+
+/// Read the file at `path` as UTF-8, as [`fs::read_to_string`].
+pub(crate) fn read_to_string(path: &Path) -> io::Result<String> {
+    String::from_utf8(read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Read `range` of the file at `path`.
+pub(crate) fn read_range(path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+    current().read_range(&long_path(path), range)
+}
+
+/// The paths of the entries directly in the directory at `path`.
+pub(crate) fn read_dir(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = current().read_dir(&long_path(path))?;
+    // The entries of a directory read by its extended-length path have the
+    // prefix too, which isn't reported
+    Ok(entries
+        .into_iter()
+        .map(|entry| match entry.to_str().map(strip_verbatim_prefix) {
+            Some(stripped) if stripped.len() != entry.as_os_str().len() => {
+                stripped.into_owned().into()
+            }
+            _ => entry,
+        })
+        .collect())
+}
+
+/// The canonical form of `path`, as [`fs::canonicalize`].
+#[cfg(feature = "libloading")]
+pub(crate) fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    let canonical = current().canonicalize(&long_path(path))?;
+    // As for `read_dir`, the extended-length prefix isn't reported
+    Ok(match canonical.to_str().map(strip_verbatim_prefix) {
+        Some(stripped) if stripped.len() != canonical.as_os_str().len() => {
+            stripped.into_owned().into()
+        }
+        _ => canonical,
+    })
+}
+
+/// Whether anything exists at `path`, as [`Path::exists`].
+#[cfg(feature = "process")]
+pub(crate) fn exists(path: &Path) -> bool {
+    metadata(path).is_ok()
+}
+
+/// The length of the file at `path` in bytes.
+#[cfg(feature = "process")]
+pub(crate) fn len(path: &Path) -> io::Result<u64> {
+    metadata(path).map(|it| it.len)
+}
+
+/// The metadata of the file or directory at `path`.
+fn metadata(path: &Path) -> io::Result<FileMetadata> {
+    current().metadata(&long_path(path))
+}
+
+/// Whether `path` is a directory, as [`Path::is_dir`].
+pub(crate) fn is_dir(path: &Path) -> bool {
+    metadata(path).is_ok_and(|it| it.is_dir)
+}
@@ -0,0 +1,80 @@
+//! Grouping functions by the source file they are declared in.
+
+use std::{collections::BTreeMap, io};
+
+use crate::{
+    for_functions_in_target, names, redaction, DeclarationIndex, DeclarationSite, ScanTarget,
+};
+
+/// Group every function in `target` by the file of its declaration site.
+///
+/// The files are sorted by path, and the functions in each file are sorted by
+/// the line of their declaration, then by name. Names are canonicalized with
+/// [`canonical_name`](crate::canonical_name), as in [`DeclarationIndex`], and
+/// a function found several times (such as in several libraries) is only
+/// listed once for each line. Each file's path is only stored once, however
+/// many functions are declared in it. With the `serde` feature, the map can
+/// be serialized directly, such as with `serde_json`.
+///
+/// Use [`DeclarationIndex::file_map`] to group the functions of an index
+/// which was already built.
+///
+/// ```rust
+/// use declaration_site::{file_map, ScanTarget};
+///
+/// let files = file_map(ScanTarget::CurrentProcess).unwrap();
+/// let mut largest = files.iter().collect::<Vec<_>>();
+/// largest.sort_by_key(|(_, functions)| std::cmp::Reverse(functions.len()));
+/// for (file, functions) in largest.iter().take(5) {
+///     println!("{file}: {} function(s)", functions.len());
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If `target` is a file which could not be read or parsed
+pub fn file_map(target: ScanTarget<'_>) -> io::Result<BTreeMap<String, Vec<(String, u32)>>> {
+    let mut files = BTreeMap::<String, Vec<(String, u32)>>::new();
+    for_functions_in_target(target, |function| {
+        let Ok(DeclarationSite { file, line }) =
+            DeclarationSite::try_from(&function).map(redaction::site)
+        else {
+            return;
+        };
+        let name = names::canonical_name(function.name()).into_owned();
+        match files.get_mut(&file) {
+            Some(functions) => functions.push((name, line)),
+            None => {
+                files.insert(file, vec![(name, line)]);
+            }
+        }
+    })?;
+    for functions in files.values_mut() {
+        functions
+            .sort_by(|(a_name, a_line), (b_name, b_line)| (a_line, a_name).cmp(&(b_line, b_name)));
+        functions.dedup();
+    }
+    Ok(files)
+}
+
+impl DeclarationIndex {
+    /// Group the functions in the index by the file of their declaration
+    /// site, as in [`file_map`], without scanning again.
+    ///
+    /// The map borrows from the index.
+    pub fn file_map(&self) -> BTreeMap<&str, Vec<(&str, u32)>> {
+        let mut files = BTreeMap::<&str, Vec<(&str, u32)>>::new();
+        for (name, site) in self.iter() {
+            files
+                .entry(site.file.as_str())
+                .or_default()
+                .push((name, site.line));
+        }
+        for functions in files.values_mut() {
+            functions.sort_by(|(a_name, a_line), (b_name, b_line)| {
+                (a_line, a_name).cmp(&(b_line, b_name))
+            });
+        }
+        files
+    }
+}
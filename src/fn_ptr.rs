@@ -0,0 +1,170 @@
+//! Finding the declaration sites of functions in the current process from
+//! their addresses, such as the addresses of `fn` pointers.
+
+use std::path::PathBuf;
+
+use findshlibs::{Avma, Segment, SharedLibrary};
+use symbolic_debuginfo::{FileFormat, Function};
+
+use crate::{
+    demangled_name, file_access, is_scanning, limits, main_executable_path, names,
+    object::{self, Object, ObjectDebugSession},
+    process::ScanGuard,
+    redaction, DeclarationSite, IterationControl,
+};
+
+/// Attempt to get the declaration site of the currently loaded function whose
+/// code starts at (or contains) `address`, such as a `fn` pointer cast with
+/// `as usize`.
+///
+/// Closures which capture nothing can be coerced to `fn` pointers, such as to
+/// pass them as callbacks. The pointer is then to a compiler generated shim
+/// (an `FnOnce::call_once` method), whose own debug info is at a synthetic
+/// location in the standard library. For these, the closure the shim calls
+/// is found instead: from its inlined body if the closure was inlined into
+/// the shim, or else from the name of the shim, and its declaration site is
+/// returned. If the closure can't be found (such as in a PDB whose closures
+/// can't be told apart), this returns `None`, rather than the location of the
+/// shim.
+///
+/// ```rust
+/// use declaration_site::declaration_of_fn_ptr;
+///
+/// let callback: fn(u32) -> u32 = |x| x + 1;
+/// # callback(1);
+/// if let Some(site) = declaration_of_fn_ptr(callback as usize) {
+///     println!("the callback is declared at {site}");
+/// }
+/// ```
+///
+/// Only the library containing `address` is read, but its debug info is
+/// searched linearly, so this takes as long as a lookup by name in the worst
+/// case. Lookups started while the current thread is already scanning return
+/// `None`, as in [`declaration_by_name`](crate::declaration_by_name).
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declaration_of_fn_ptr(address: usize) -> Option<DeclarationSite> {
+    if is_scanning() {
+        return None;
+    }
+    let _guard = ScanGuard::new();
+    let (path, svma) = loaded_library_at(address)?;
+    let data = file_access::read(&path).ok()?;
+    limits::catch_parser_panic(|| site_at(&data, svma))
+        .flatten()
+        .map(redaction::site)
+}
+
+/// The path to read the loaded library containing `address` from (or its
+/// debug companion), and the address in that file (SVMA).
+fn loaded_library_at(address: usize) -> Option<(PathBuf, u64)> {
+    let avma = Avma(address);
+    let mut library = None;
+    findshlibs::TargetSharedLibrary::each(|it| {
+        if it.segments().any(|segment| segment.contains_avma(it, avma)) {
+            library = Some((
+                it.name().to_owned(),
+                it.debug_name().map(ToOwned::to_owned),
+                it.avma_to_svma(avma),
+            ));
+            return IterationControl::Break;
+        }
+        IterationControl::Continue
+    });
+    let (name, debug_name, svma) = library?;
+    let path = match debug_name {
+        Some(debug_name) => PathBuf::from(debug_name),
+        None if name.is_empty() => main_executable_path().ok()?,
+        None => PathBuf::from(name),
+    };
+    Some((path, u64::try_from(svma.0).ok()?))
+}
+
+/// The declaration site of the function at `svma` in the file with contents
+/// `data`.
+fn site_at(data: &[u8], svma: u64) -> Option<DeclarationSite> {
+    let archive = object::Archive::parse(data).ok()?;
+    for object in archive.objects().flatten() {
+        let Some(address) = svma.checked_sub(object.load_address()) else {
+            continue;
+        };
+        let Ok(session) = object.debug_session() else {
+            continue;
+        };
+        if let Some(function) = session.function_by_address(address) {
+            return resolve_shim(&object, &session, &function);
+        }
+    }
+    None
+}
+
+/// The declaration site of `function`, or of the closure it calls if it is a
+/// shim.
+fn resolve_shim(
+    object: &Object<'_>,
+    session: &ObjectDebugSession<'_>,
+    function: &Function<'_>,
+) -> Option<DeclarationSite> {
+    let format = object.file_format();
+    let name = demangled_name(function, format)?;
+    let closure = names::shim_closure(&name).or_else(|| {
+        // Legacy symbols don't name the closure, but the debug info does
+        names::is_fn_trait_method(&name)
+            .then(|| object.subprogram_name(function.address))
+            .flatten()
+            .and_then(|name| names::shim_closure(&name))
+    });
+    let Some(closure) = closure else {
+        return DeclarationSite::try_from(function).ok();
+    };
+    if let Some(inlined) = inlined_closure(function, &closure, format) {
+        return DeclarationSite::try_from(inlined).ok();
+    }
+    closure_site(object, session, &closure)
+}
+
+/// The closure named `closure` inlined into `function`, if any.
+fn inlined_closure<'a, 'data>(
+    function: &'a Function<'data>,
+    closure: &str,
+    format: FileFormat,
+) -> Option<&'a Function<'data>> {
+    function.inlinees.iter().find_map(|inlinee| {
+        let matches =
+            demangled_name(inlinee, format).is_some_and(|name| names::names_match(&name, closure));
+        if matches {
+            Some(inlinee)
+        } else {
+            inlined_closure(inlinee, closure, format)
+        }
+    })
+}
+
+/// The declaration site of the closure named `closure` in `object`.
+///
+/// Legacy symbols name every closure in a function `{{closure}}`, so if
+/// several match, they are told apart by their DWARF names.
+fn closure_site(
+    object: &Object<'_>,
+    session: &ObjectDebugSession<'_>,
+    closure: &str,
+) -> Option<DeclarationSite> {
+    let format = object.file_format();
+    let candidates = session
+        .functions()
+        .flatten()
+        .filter(|function| {
+            demangled_name(function, format).is_some_and(|name| names::names_match(&name, closure))
+        })
+        .collect::<Vec<_>>();
+    let chosen = match candidates.as_slice() {
+        [function] => function,
+        _ => {
+            let segment = names::path_segments(closure).pop()?.to_owned();
+            candidates.iter().find(|function| {
+                object.subprogram_name(function.address).as_deref() == Some(&*segment)
+            })?
+        }
+    };
+    DeclarationSite::try_from(chosen).ok()
+}
@@ -0,0 +1,306 @@
+//! An index of the declaration sites of every function in a file.
+
+use std::{borrow::Cow, collections::BTreeMap, io, path::Path};
+
+use crate::{
+    for_functions_in_bytes, for_functions_in_file, names, redaction, scan_file_data,
+    visit_file_data, DeclarationSite, PathComparison,
+};
+
+/// The declaration sites of every function in a binary or debug file, by
+/// name.
+///
+/// Building an index reads the whole file, so this is useful when many
+/// functions will be looked up, or for listing every function. Functions
+/// without source locations are not included. If several functions have the
+/// same name (for example, instances of a generic function), the first one
+/// found is used.
+///
+/// The names are canonicalized with [`canonical_name`](crate::canonical_name),
+/// both when building the index and in [`get`](Self::get).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeclarationIndex {
+    sites: BTreeMap<String, DeclarationSite>,
+}
+
+impl DeclarationIndex {
+    /// Index the functions in the binary or debug file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read, or is not a supported object file format.
+    /// If the scan was cancelled (see
+    /// [`with_cancellation`](crate::with_cancellation)), an error of kind
+    /// [`io::ErrorKind::Interrupted`] is returned rather than the functions
+    /// found before it stopped
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut index = DeclarationIndex::default();
+        for_functions_in_file(path, |function| {
+            if let Ok(site) = DeclarationSite::try_from(&function) {
+                index.insert(function.name().to_owned(), site);
+            }
+        })?;
+        Ok(index)
+    }
+
+    /// Index the functions in the file (rather than directory) at `path` as
+    /// [`from_file`](Self::from_file), when it has already been read as
+    /// `data`.
+    pub(crate) fn from_file_data(path: &Path, data: &[u8]) -> io::Result<Self> {
+        let mut index = DeclarationIndex::default();
+        let mut callback = |function: crate::FunctionRecord<'_>| {
+            if let Ok(site) = DeclarationSite::try_from(&function) {
+                index.insert(function.name().to_owned(), site);
+            }
+        };
+        visit_file_data(path, data, &mut |data, object_path| {
+            scan_file_data(data, object_path, &mut callback)
+        })?;
+        Ok(index)
+    }
+
+    /// Index the functions in a binary or debug file with contents `data`.
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a supported object file format
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let mut index = DeclarationIndex::default();
+        for_functions_in_bytes(data, |function| {
+            if let Ok(site) = DeclarationSite::try_from(&function) {
+                index.insert(function.name().to_owned(), site);
+            }
+        })?;
+        Ok(index)
+    }
+
+    /// Index the functions in the libraries currently loaded into this
+    /// process, as found by
+    /// [`for_each_loaded_function`](crate::for_each_loaded_function).
+    ///
+    /// Enabled by the `process` feature. Libraries loaded after the index was
+    /// built aren't included. To only index the functions of some crates or
+    /// modules, use [`DeclarationCache::build_filtered`](crate::DeclarationCache::build_filtered).
+    ///
+    /// See also "Caveats" in the [module level documentation](crate).
+    #[cfg(feature = "process")]
+    pub fn from_loaded_libraries() -> Self {
+        Self::from_loaded_functions("")
+    }
+
+    /// Index the currently loaded functions whose names start with `prefix`,
+    /// after both are canonicalized.
+    #[cfg(feature = "process")]
+    pub(crate) fn from_loaded_functions(prefix: &str) -> Self {
+        let prefix = names::canonical_name(prefix);
+        let mut sites = BTreeMap::new();
+        crate::for_each_loaded_function(|function| {
+            let name = names::canonical_name(function.name());
+            if !name.starts_with(&*prefix) || sites.contains_key(&*name) {
+                return;
+            }
+            if let Ok(site) = DeclarationSite::try_from(&function) {
+                sites.insert(name.into_owned(), redaction::site(site));
+            }
+        });
+        DeclarationIndex { sites }
+    }
+
+    /// Add the function `name`, unless a function with that name is already
+    /// in the index.
+    pub(crate) fn insert(&mut self, name: String, site: DeclarationSite) {
+        let name = match names::canonical_name(&name) {
+            Cow::Borrowed(_) => name,
+            Cow::Owned(canonical) => canonical,
+        };
+        self.sites
+            .entry(name)
+            .or_insert_with(|| redaction::site(site));
+    }
+
+    /// Get the declaration site of the function with the given (unmangled)
+    /// name.
+    pub fn get(&self, name: &str) -> Option<&DeclarationSite> {
+        self.sites.get(&*names::canonical_name(name))
+    }
+
+    /// The names and declaration sites of every function in the index, sorted
+    /// by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DeclarationSite)> {
+        self.sites.iter().map(|(name, site)| (name.as_str(), site))
+    }
+
+    /// The names and declaration sites of the functions whose names start with
+    /// `prefix` (such as `my_crate::module::`), sorted by name.
+    ///
+    /// `prefix` is canonicalized as the names are, so this takes time
+    /// proportional to the number of functions returned, rather than to the
+    /// size of the index.
+    pub fn with_prefix<'a>(
+        &'a self,
+        prefix: &str,
+    ) -> impl Iterator<Item = (&'a str, &'a DeclarationSite)> + 'a {
+        let prefix = names::canonical_name(prefix).into_owned();
+        self.sites
+            .range(prefix.clone()..)
+            .take_while(move |(name, _)| name.starts_with(&prefix))
+            .map(|(name, site)| (name.as_str(), site))
+    }
+
+    /// The functions declared in the file at `path`, sorted by line.
+    ///
+    /// `path` is compared with the files of the declaration sites using
+    /// `comparison`, so may be relative, such as `src/lib.rs`.
+    pub fn functions_declared_in(
+        &self,
+        path: &str,
+        comparison: PathComparison,
+    ) -> Vec<(&str, &DeclarationSite)> {
+        let mut functions = self
+            .iter()
+            .filter(|(_, site)| comparison.matches(path, &site.file))
+            .collect::<Vec<_>>();
+        functions.sort_by_key(|&(name, site)| (site.line, name));
+        functions
+    }
+
+    /// The function containing `line` of the file at `path`, which is taken
+    /// to be the function declared nearest before (or on) it, as compared
+    /// using `comparison`.
+    ///
+    /// Only the first line of each function is known, so a line after the
+    /// end of a function is reported as part of it, and a line in a closure
+    /// or nested function can be reported as part of the function containing
+    /// it.
+    pub fn function_at(
+        &self,
+        path: &str,
+        line: u32,
+        comparison: PathComparison,
+    ) -> Option<(&str, &DeclarationSite)> {
+        self.functions_declared_in(path, comparison)
+            .into_iter()
+            .take_while(|(_, site)| site.line <= line)
+            .last()
+    }
+
+    /// The names and declaration sites of every function in the index, sorted
+    /// by name, consuming it.
+    pub(crate) fn into_sites(self) -> impl Iterator<Item = (String, DeclarationSite)> {
+        self.sites.into_iter()
+    }
+
+    /// The number of functions in the index.
+    pub fn len(&self) -> usize {
+        self.sites.len()
+    }
+
+    /// Whether the index contains no functions.
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+
+    /// Find the functions which were added, removed or moved in `other`,
+    /// compared to this index.
+    ///
+    /// This is useful for hot reloading, to compare the functions in the
+    /// previously loaded version of a library with a newly built one. Functions
+    /// are matched by name, so a renamed function is both removed and added.
+    pub fn diff(&self, other: &DeclarationIndex) -> IndexDiff {
+        let mut diff = IndexDiff::default();
+        for (name, old) in &self.sites {
+            match other.sites.get(name) {
+                None => diff.removed.push((name.clone(), old.clone())),
+                Some(new) if new != old => diff.moved.push(MovedFunction {
+                    name: name.clone(),
+                    old: old.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, new) in &other.sites {
+            if !self.sites.contains_key(name) {
+                diff.added.push((name.clone(), new.clone()));
+            }
+        }
+        diff
+    }
+
+    /// Index the binary or debug files at `old` and `new`, and
+    /// [`diff`](Self::diff) them.
+    ///
+    /// # Errors
+    ///
+    /// If either file could not be read, or is not a supported object file
+    /// format
+    pub fn diff_files(old: impl AsRef<Path>, new: impl AsRef<Path>) -> io::Result<IndexDiff> {
+        let old = DeclarationIndex::from_file(old)?;
+        let new = DeclarationIndex::from_file(new)?;
+        Ok(old.diff(&new))
+    }
+}
+
+impl FromIterator<(String, DeclarationSite)> for DeclarationIndex {
+    fn from_iter<T: IntoIterator<Item = (String, DeclarationSite)>>(iter: T) -> Self {
+        let mut index = DeclarationIndex::default();
+        for (name, site) in iter {
+            index.insert(name, site);
+        }
+        index
+    }
+}
+
+/// The differences between two [`DeclarationIndex`]es, created using
+/// [`DeclarationIndex::diff`].
+///
+/// Each list is sorted by function name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IndexDiff {
+    /// The functions which are only in the new index, with their declaration
+    /// sites
+    pub added: Vec<(String, DeclarationSite)>,
+    /// The functions which are only in the old index, with their declaration
+    /// sites in the old index
+    pub removed: Vec<(String, DeclarationSite)>,
+    /// The functions which are in both indexes, with different declaration
+    /// sites
+    pub moved: Vec<MovedFunction>,
+}
+
+impl IndexDiff {
+    /// Whether the indexes had the same functions, with the same declaration
+    /// sites.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+
+    /// Remove the functions from [`moved`](Self::moved) which are still
+    /// declared in the same file.
+    ///
+    /// Editing a file moves every function below the edit, so these are often
+    /// just noise.
+    pub fn without_line_shifts(mut self) -> Self {
+        self.moved.retain(MovedFunction::is_relocation);
+        self
+    }
+}
+
+/// A function whose declaration site differs between two
+/// [`DeclarationIndex`]es.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MovedFunction {
+    pub name: String,
+    /// The declaration site in the old index
+    pub old: DeclarationSite,
+    /// The declaration site in the new index
+    pub new: DeclarationSite,
+}
+
+impl MovedFunction {
+    /// Whether the function is now declared in a different file, rather than
+    /// just on a different line.
+    pub fn is_relocation(&self) -> bool {
+        self.old.file != self.new.file
+    }
+}
@@ -0,0 +1,399 @@
+//! Persisting a [`DeclarationIndex`] to a cache file, so that the functions
+//! in a binary only need to be scanned again when it changes.
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{file_access, limits, object, DeclarationIndex, DeclarationSite};
+
+const MAGIC: &[u8; 8] = b"DSINDEX\0";
+const MAJOR_VERSION: u16 = 1;
+const MINOR_VERSION: u16 = 0;
+const PREFIX_LEN: usize = 28;
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Names were canonicalized with `canonical_name`. Bit 1 is reserved for
+/// names with their generic arguments erased, which this version never writes
+const FLAG_CANONICAL_NAMES: u32 = 1 << 0;
+/// The file is an embedded table, without the identity of the file indexed
+const FLAG_EMBEDDED_TABLE: u32 = 1 << 2;
+const FLAGS: u32 = FLAG_CANONICAL_NAMES;
+/// The flags of the tables written by `export_compact_table`
+pub(crate) const TABLE_FLAGS: u32 = FLAGS | FLAG_EMBEDDED_TABLE;
+
+/// Whether [`DeclarationIndex::from_file_cached`] may use an existing cache
+/// file.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum CacheControl {
+    /// Load the cache file if it is valid for the file being indexed, and
+    /// otherwise rebuild the index and save it
+    #[default]
+    Use,
+    /// Rebuild the index and save it, even if the cache file is valid
+    Rebuild,
+}
+
+/// Whether [`DeclarationIndex::from_file_cached`] loaded the index from the
+/// cache file, or why it was rebuilt.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CacheStatus {
+    /// The index was loaded from the cache file
+    Loaded,
+    /// The index was rebuilt, as [`CacheControl::Rebuild`] was used
+    Forced,
+    /// The index was rebuilt, as the cache file couldn't be read
+    Missing,
+    /// The index was rebuilt, as the cache file was written by an
+    /// incompatible version of the format or of this crate
+    Incompatible,
+    /// The index was rebuilt, as the cache file is for a different version of
+    /// the file being indexed
+    Stale,
+    /// The index was rebuilt, as the cache file is malformed or its checksum
+    /// is wrong
+    Corrupted,
+}
+
+impl fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CacheStatus::Loaded => "loaded from the cache",
+            CacheStatus::Forced => "rebuilt, as requested",
+            CacheStatus::Missing => "rebuilt, as the cache couldn't be read",
+            CacheStatus::Incompatible => {
+                "rebuilt, as the cache was written by an incompatible version"
+            }
+            CacheStatus::Stale => "rebuilt, as the cache is for a different version of the file",
+            CacheStatus::Corrupted => "rebuilt, as the cache is corrupted",
+        })
+    }
+}
+
+impl DeclarationIndex {
+    /// Index the functions in the binary or debug file at `path`, using the
+    /// cache file at `cache_path` if it was written for the same version of
+    /// the file.
+    ///
+    /// If the index is rebuilt, it is saved to `cache_path` (replacing the
+    /// file atomically where the platform allows). Failing to save the cache
+    /// isn't an error, as the index is still correct, but the next call will
+    /// have to rebuild it again. An index which is missing functions, as the
+    /// scan exceeded the current [`ScanLimits`](crate::ScanLimits) or a debug
+    /// info parser panicked (see
+    /// [`ScanStats::is_truncated`](crate::ScanStats::is_truncated)), is returned
+    /// but not saved, so that it isn't loaded in place of a complete index.
+    /// `path` must be a file, rather than a directory such as a `.dSYM`
+    /// bundle.
+    ///
+    /// # Format
+    ///
+    /// Cache files start with a fixed prefix, in which integers are little
+    /// endian:
+    ///
+    /// | Offset | Size | Contents                                              |
+    /// |--------|------|-------------------------------------------------------|
+    /// | 0      | 8    | The magic bytes `DSINDEX\0`                           |
+    /// | 8      | 2    | The major format version                              |
+    /// | 10     | 2    | The minor format version                              |
+    /// | 12     | 4    | The length of the header                              |
+    /// | 16     | 8    | The length of the payload                             |
+    /// | 24     | 4    | The CRC-32 (ISO-HDLC) of the header and payload       |
+    ///
+    /// The header follows, with the version of this crate which wrote the file,
+    /// flags describing how names were normalized (bit 0 is set when names were
+    /// canonicalized with [`canonical_name`](crate::canonical_name), and bit 1
+    /// when generic arguments were erased) and whether the file is an embedded
+    /// table (bit 2, see [`export_compact_table`](crate::export_compact_table)),
+    /// and the identity of the file which was indexed: its length, and the
+    /// debug id of each object in it. Embedded tables have a length of 0 and no
+    /// debug ids, as neither is known when the table is written. Strings
+    /// are prefixed with their length as a `u16` (in the header) or `u32` (in the
+    /// payload), and lists with their length as a `u32`. Newer minor versions may
+    /// add fields to the end of the header, which older versions skip.
+    ///
+    /// The payload is the list of functions, each as its name, the file of its
+    /// declaration site, and its line as a `u32`.
+    ///
+    /// A cache file is only used if its major version, crate version, flags and
+    /// source identity match, and its checksum is correct. Otherwise, the index
+    /// is rebuilt, so a cache which is stale or corrupted never gives wrong
+    /// declaration sites.
+    ///
+    /// ```rust,no_run
+    /// use declaration_site::{CacheControl, DeclarationIndex};
+    ///
+    /// let exe = std::env::current_exe().unwrap();
+    /// let cache = exe.with_extension("declarations");
+    /// let (index, status) =
+    ///     DeclarationIndex::from_file_cached(&exe, &cache, CacheControl::Use).unwrap();
+    /// println!("{} functions, {status}", index.len());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// As for [`from_file`](Self::from_file)
+    pub fn from_file_cached(
+        path: impl AsRef<Path>,
+        cache_path: impl AsRef<Path>,
+        control: CacheControl,
+    ) -> io::Result<(Self, CacheStatus)> {
+        let (path, cache_path) = (path.as_ref(), cache_path.as_ref());
+        let data = file_access::read(path)?;
+        let source = SourceIdentity::of_bytes(&data)?;
+        let status = match control {
+            CacheControl::Rebuild => CacheStatus::Forced,
+            CacheControl::Use => match fs::read(cache_path) {
+                Ok(data) => match read_cache(&data, &source) {
+                    Ok(index) => return Ok((index, CacheStatus::Loaded)),
+                    Err(status) => status,
+                },
+                Err(_) => CacheStatus::Missing,
+            },
+        };
+        // A cancelled scan returns an error, so is never saved
+        let (index, stats) = limits::track_stats(|| DeclarationIndex::from_file_data(path, &data));
+        let index = index?;
+        if !stats.is_truncated() {
+            let _ = save_cache(&index, &source, cache_path);
+        }
+        Ok((index, status))
+    }
+}
+
+/// What identifies the version of the file which was indexed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SourceIdentity {
+    len: u64,
+    debug_ids: Vec<String>,
+}
+
+impl SourceIdentity {
+    /// The identity of the file with contents `data`.
+    pub(crate) fn of_bytes(data: &[u8]) -> io::Result<Self> {
+        let debug_ids = limits::catch_parser_panic(|| {
+            object::Archive::parse(data).map(|archive| {
+                archive
+                    .objects()
+                    .filter_map(|object| Some(object.ok()?.debug_id().to_string()))
+                    .collect()
+            })
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "the file is malformed"))?
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Ok(SourceIdentity {
+            len: data.len() as u64,
+            debug_ids,
+        })
+    }
+}
+
+fn save_cache(index: &DeclarationIndex, source: &SourceIdentity, path: &Path) -> io::Result<()> {
+    let file = encode(index, source, FLAGS)?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut writer = fs::File::create(&temporary)?;
+    writer.write_all(&file)?;
+    writer.sync_all()?;
+    fs::rename(&temporary, path)
+}
+
+/// `index`, of the file identified by `source`, in the format described in
+/// [`DeclarationIndex::from_file_cached`], with the header flags `flags`.
+///
+/// Fails with [`io::ErrorKind::InvalidData`] if a string or list is too long
+/// for the length prefix the format gives it, rather than writing a file
+/// which can't be read.
+pub(crate) fn encode(
+    index: &DeclarationIndex,
+    source: &SourceIdentity,
+    flags: u32,
+) -> io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    write_str16(&mut header, CRATE_VERSION)?;
+    header.extend_from_slice(&flags.to_le_bytes());
+    header.extend_from_slice(&source.len.to_le_bytes());
+    write_len(&mut header, source.debug_ids.len())?;
+    for debug_id in &source.debug_ids {
+        write_str16(&mut header, debug_id)?;
+    }
+    let mut payload = Vec::new();
+    write_len(&mut payload, index.len())?;
+    for (name, site) in index.iter() {
+        write_str32(&mut payload, name)?;
+        write_str32(&mut payload, &site.file)?;
+        payload.extend_from_slice(&site.line.to_le_bytes());
+    }
+    let mut file = Vec::with_capacity(PREFIX_LEN + header.len() + payload.len());
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(&MAJOR_VERSION.to_le_bytes());
+    file.extend_from_slice(&MINOR_VERSION.to_le_bytes());
+    write_len(&mut file, header.len())?;
+    file.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    file.extend_from_slice(&crc32(&[&header, &payload]).to_le_bytes());
+    file.extend_from_slice(&header);
+    file.extend_from_slice(&payload);
+    Ok(file)
+}
+
+fn read_cache(data: &[u8], source: &SourceIdentity) -> Result<DeclarationIndex, CacheStatus> {
+    let (identity, index) = decode(data, FLAGS)?;
+    if identity != *source {
+        return Err(CacheStatus::Stale);
+    }
+    Ok(index)
+}
+
+/// The index in `data`, written by [`encode`] with `flags`, and the identity
+/// of the file it was written for.
+pub(crate) fn decode(
+    data: &[u8],
+    flags: u32,
+) -> Result<(SourceIdentity, DeclarationIndex), CacheStatus> {
+    let mut prefix = Reader(data.get(..PREFIX_LEN).ok_or(CacheStatus::Corrupted)?);
+    if prefix.take(MAGIC.len())? != MAGIC {
+        return Err(CacheStatus::Corrupted);
+    }
+    // Newer minor versions can be read, as they only add header fields
+    if prefix.u16()? != MAJOR_VERSION {
+        return Err(CacheStatus::Incompatible);
+    }
+    let _minor = prefix.u16()?;
+    let header_len = prefix.u32()? as usize;
+    let payload_len = usize::try_from(prefix.u64()?).map_err(|_| CacheStatus::Corrupted)?;
+    let checksum = prefix.u32()?;
+    let rest = &data[PREFIX_LEN..];
+    if header_len.checked_add(payload_len) != Some(rest.len()) {
+        return Err(CacheStatus::Corrupted);
+    }
+    let (header, payload) = rest.split_at(header_len);
+    if crc32(&[header, payload]) != checksum {
+        return Err(CacheStatus::Corrupted);
+    }
+
+    let mut header = Reader(header);
+    if header.str16()? != CRATE_VERSION || header.u32()? != flags {
+        return Err(CacheStatus::Incompatible);
+    }
+    let len = header.u64()?;
+    let debug_ids = (0..header.u32()?)
+        .map(|_| header.str16().map(ToOwned::to_owned))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut payload = Reader(payload);
+    let count = payload.u32()?;
+    let mut sites = Vec::new();
+    for _ in 0..count {
+        let name = payload.str32()?.to_owned();
+        let file = payload.str32()?.to_owned();
+        let line = payload.u32()?;
+        sites.push((name, DeclarationSite { file, line }));
+    }
+    if !payload.0.is_empty() {
+        return Err(CacheStatus::Corrupted);
+    }
+    Ok((
+        SourceIdentity { len, debug_ids },
+        sites.into_iter().collect(),
+    ))
+}
+
+fn write_len(out: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len).map_err(|_| too_long(len))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn write_str16(out: &mut Vec<u8>, value: &str) -> io::Result<()> {
+    let len = u16::try_from(value.len()).map_err(|_| too_long(value.len()))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+fn write_str32(out: &mut Vec<u8>, value: &str) -> io::Result<()> {
+    write_len(out, value.len())?;
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// The error for a string or list of length `len`, which doesn't fit in its
+/// length prefix.
+fn too_long(len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("a field of length {len} is too long for the cache format"),
+    )
+}
+
+/// Reads the fields of a cache file, failing with [`CacheStatus::Corrupted`]
+/// if the data is too short.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CacheStatus> {
+        if self.0.len() < len {
+            return Err(CacheStatus::Corrupted);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CacheStatus> {
+        Ok(self.take(N)?.try_into().expect("Took N bytes"))
+    }
+
+    fn u16(&mut self) -> Result<u16, CacheStatus> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, CacheStatus> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, CacheStatus> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn str16(&mut self) -> Result<&'a str, CacheStatus> {
+        let len = self.u16()?.into();
+        std::str::from_utf8(self.take(len)?).map_err(|_| CacheStatus::Corrupted)
+    }
+
+    fn str32(&mut self) -> Result<&'a str, CacheStatus> {
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?).map_err(|_| CacheStatus::Corrupted)
+    }
+}
+
+/// The CRC-32 (ISO-HDLC, as used by zlib) of the concatenation of `parts`.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut index = 0;
+        while index < 256 {
+            let mut crc = index as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[index] = crc;
+            index += 1;
+        }
+        table
+    };
+    let mut crc = !0_u32;
+    for byte in parts.iter().copied().flatten() {
+        crc = (crc >> 8) ^ TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize];
+    }
+    !crc
+}
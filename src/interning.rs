@@ -0,0 +1,210 @@
+//! Declaration sites which share their file paths, for holding the sites of
+//! many functions at once.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, io,
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    for_functions_in_bytes, for_functions_in_file, names, redaction, DeclarationIndex,
+    DeclarationSite, FunctionRecord,
+};
+
+/// A [`DeclarationSite`] whose file path is shared with the other sites from
+/// the same [`SitePool`], so is cheap to clone.
+///
+/// Sites from the same pool in the same file share the same allocation for
+/// the path, so [`same_file`](Self::same_file) compares pointers rather than
+/// strings.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SharedDeclarationSite {
+    pub file: Arc<str>,
+    pub line: u32,
+}
+
+impl SharedDeclarationSite {
+    /// Whether this site is in the same file as `other`.
+    ///
+    /// This is a pointer comparison if both sites are from the same
+    /// [`SitePool`], and otherwise compares the paths.
+    pub fn same_file(&self, other: &SharedDeclarationSite) -> bool {
+        Arc::ptr_eq(&self.file, &other.file) || self.file == other.file
+    }
+
+    /// Copy this site into a [`DeclarationSite`], which owns its path.
+    pub fn to_site(&self) -> DeclarationSite {
+        DeclarationSite {
+            file: self.file.to_string(),
+            line: self.line,
+        }
+    }
+}
+
+impl fmt::Display for SharedDeclarationSite {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}:{}", self.file, self.line)
+    }
+}
+
+impl From<&SharedDeclarationSite> for DeclarationSite {
+    fn from(site: &SharedDeclarationSite) -> Self {
+        site.to_site()
+    }
+}
+
+/// A pool of file paths, which makes the [`SharedDeclarationSite`]s in each
+/// file share one allocation for its path.
+#[derive(Clone, Debug, Default)]
+pub struct SitePool {
+    files: HashSet<Arc<str>>,
+}
+
+impl SitePool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        SitePool::default()
+    }
+
+    /// The shared path of `file`, which is added to the pool if it isn't in it.
+    pub fn intern_file(&mut self, file: &str) -> Arc<str> {
+        if let Some(file) = self.files.get(file) {
+            return file.clone();
+        }
+        let file = Arc::<str>::from(file);
+        self.files.insert(file.clone());
+        file
+    }
+
+    /// The shared version of `site`.
+    pub fn intern(&mut self, site: &DeclarationSite) -> SharedDeclarationSite {
+        SharedDeclarationSite {
+            file: self.intern_file(&site.file),
+            line: site.line,
+        }
+    }
+
+    /// The number of distinct files in the pool.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether the pool contains no files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// A [`DeclarationIndex`] whose sites share their file paths, using a pool
+/// for the index.
+///
+/// A [`DeclarationIndex`] holds a copy of the path of the file for the site of
+/// each function, although a binary usually has far fewer files than
+/// functions. This holds one copy of each path instead, which for the full
+/// index of a large binary saves most of the memory used by the paths (see
+/// the `interning` example). It is otherwise the same as
+/// [`DeclarationIndex`], and names are canonicalized in the same way.
+///
+/// ```rust
+/// use declaration_site::SharedIndex;
+///
+/// let path = std::env::current_exe().unwrap();
+/// let index = SharedIndex::from_file(&path).unwrap();
+/// let mut sites = index.iter().map(|(_, site)| site);
+/// if let (Some(first), Some(second)) = (sites.next(), sites.next()) {
+///     if first.same_file(second) {
+///         println!("{first} and {second} are in the same file");
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SharedIndex {
+    sites: BTreeMap<String, SharedDeclarationSite>,
+    pool: SitePool,
+}
+
+impl SharedIndex {
+    /// Index the functions in the binary or debug file at `path`.
+    ///
+    /// The paths are interned as the functions are found, so only one copy of
+    /// each is held at once.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read, or is not a supported object file format.
+    /// If the scan was cancelled (see
+    /// [`with_cancellation`](crate::with_cancellation)), an error of kind
+    /// [`io::ErrorKind::Interrupted`] is returned
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut index = SharedIndex::default();
+        for_functions_in_file(path, |function| index.insert(&function))?;
+        Ok(index)
+    }
+
+    /// Index the functions in a binary or debug file with contents `data`, as
+    /// [`from_file`](Self::from_file).
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a supported object file format
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let mut index = SharedIndex::default();
+        for_functions_in_bytes(data, |function| index.insert(&function))?;
+        Ok(index)
+    }
+
+    /// Add `function`, unless a function with its name is already in the
+    /// index, as [`DeclarationIndex`] does.
+    fn insert(&mut self, function: &FunctionRecord<'_>) {
+        let name = names::canonical_name(function.name());
+        if self.sites.contains_key(&*name) {
+            return;
+        }
+        if let Ok(site) = DeclarationSite::try_from(function) {
+            let site = self.pool.intern(&redaction::site(site));
+            self.sites.insert(name.into_owned(), site);
+        }
+    }
+
+    /// Get the declaration site of the function with the given (unmangled)
+    /// name.
+    pub fn get(&self, name: &str) -> Option<&SharedDeclarationSite> {
+        self.sites.get(&*crate::canonical_name(name))
+    }
+
+    /// The names and declaration sites of every function in the index, sorted
+    /// by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SharedDeclarationSite)> {
+        self.sites.iter().map(|(name, site)| (name.as_str(), site))
+    }
+
+    /// The pool of the files of the sites in the index.
+    pub fn pool(&self) -> &SitePool {
+        &self.pool
+    }
+
+    /// The number of functions in the index.
+    pub fn len(&self) -> usize {
+        self.sites.len()
+    }
+
+    /// Whether the index contains no functions.
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+}
+
+impl From<DeclarationIndex> for SharedIndex {
+    /// Share the file paths of the sites of `index`, which are freed as they
+    /// are interned.
+    fn from(index: DeclarationIndex) -> Self {
+        let mut pool = SitePool::new();
+        let sites = index
+            .into_sites()
+            .map(|(name, site)| (name, pool.intern(&site)))
+            .collect();
+        SharedIndex { sites, pool }
+    }
+}
@@ -0,0 +1,80 @@
+//! Iterating over the functions of each object in the order of their
+//! addresses, for tools which show the layout of a binary.
+
+use std::{cell::Cell, io};
+
+use crate::{for_functions_in_target, FunctionRecord, IterationControl, ScanTarget};
+
+thread_local! {
+    /// Whether scans on the current thread visit the functions of each object
+    /// in address order, inside [`for_functions_in_address_order`].
+    static ADDRESS_ORDER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `callback` on each function in `target`, visiting the functions of each
+/// object in order of their [`address`](FunctionRecord::address).
+///
+/// Every function of an object is visited before any function of the next
+/// object, and objects are visited in the same order as by
+/// [`for_functions_in_file`](crate::for_functions_in_file). Functions at the
+/// same address (such as identical functions which were merged by the
+/// linker) are ordered by their size, then as in the debug info. With
+/// [`FunctionRecord::end_address`], the gaps between functions (such as
+/// padding) can be found.
+///
+/// To sort them, the functions of each object are read before any are
+/// visited, so the functions of the largest object are kept in memory at
+/// once. This is roughly the memory used by the functions and line records
+/// of its debug info, which for large binaries can be hundreds of megabytes.
+/// At most [`ScanLimits::max_functions_per_object`](crate::ScanLimits::max_functions_per_object)
+/// functions are kept.
+///
+/// ```rust
+/// use declaration_site::{for_functions_in_address_order, ScanTarget};
+///
+/// let path = std::env::current_exe().unwrap();
+/// let mut previous = None;
+/// let mut padding = 0;
+/// for_functions_in_address_order(ScanTarget::File(&path), |function| {
+///     if let Some((object, end)) = previous {
+///         if object == function.object_index() && function.address() > end {
+///             padding += function.address() - end;
+///         }
+///     }
+///     previous = Some((function.object_index(), function.end_address()));
+/// })
+/// .unwrap();
+/// println!("{padding} bytes between functions");
+/// ```
+///
+/// # Errors
+///
+/// If `target` is a file which could not be read or parsed
+pub fn for_functions_in_address_order<C>(
+    target: ScanTarget<'_>,
+    callback: impl FnMut(FunctionRecord<'_>) -> C,
+) -> io::Result<()>
+where
+    C: Into<IterationControl>,
+{
+    let previous = ADDRESS_ORDER.with(|it| it.replace(true));
+    // Restore the previous order even if `callback` panics
+    let _guard = OrderGuard { previous };
+    for_functions_in_target(target, callback)
+}
+
+struct OrderGuard {
+    previous: bool,
+}
+
+impl Drop for OrderGuard {
+    fn drop(&mut self) {
+        ADDRESS_ORDER.with(|it| it.set(self.previous));
+    }
+}
+
+/// Whether scans on the current thread visit the functions of each object in
+/// address order.
+pub(crate) fn in_address_order() -> bool {
+    ADDRESS_ORDER.with(Cell::get)
+}
@@ -1,204 +1,1143 @@
 #![forbid(unsafe_code)]
 #![doc = include_str!("../README.md")]
-use std::{env::current_exe, error::Error, fmt, fs};
+// The docs link to the items of every feature, which only all exist when
+// every feature is enabled (as CI checks)
+#![cfg_attr(
+    not(all(
+        feature = "process",
+        feature = "serde",
+        feature = "miette",
+        feature = "libloading",
+        feature = "test-fixtures"
+    )),
+    allow(rustdoc::broken_intra_doc_links)
+)]
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
 
-mod symbolic_object;
+#[cfg(feature = "async")]
+mod async_scan;
+mod attribution;
+#[cfg(feature = "process")]
+mod backtrace;
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(feature = "process")]
+mod cache;
+mod cancellation;
+#[cfg(feature = "process")]
+mod capability;
+mod choice;
+mod comparison;
+mod confidence;
+#[cfg(feature = "process")]
+mod drop_glue;
+mod duplication;
+mod embedded_table;
+#[cfg(feature = "serde")]
+mod export;
+#[cfg(feature = "eyre")]
+pub mod eyre;
+mod file_access;
+mod file_map;
+#[cfg(feature = "process")]
+mod fn_ptr;
+mod index;
+mod index_cache;
+mod interning;
+mod layout;
+mod limits;
+#[cfg(feature = "libloading")]
+mod loaded;
+#[cfg(feature = "process")]
+mod low_allocation;
+mod macro_site;
+#[cfg(feature = "miette")]
+pub mod miette;
+mod module_tree;
+mod names;
+pub mod object;
+mod pairing;
+#[cfg(feature = "process")]
+mod panic;
+mod path_syntax;
+mod paths;
+mod pdb_names;
+mod priority;
+#[cfg(feature = "process")]
+mod process;
+mod record;
+mod redaction;
+#[cfg(feature = "process")]
+mod registration;
+#[cfg(feature = "serde")]
+pub mod schema;
+#[cfg(feature = "process")]
+mod search;
+mod session;
+mod sources;
+#[cfg(feature = "process")]
+mod symbols;
+#[cfg(feature = "test-fixtures")]
+pub mod test_fixtures;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+#[cfg(feature = "process")]
+mod type_ids;
+mod unit_info;
+#[cfg(feature = "process")]
+mod verify;
 
-use findshlibs::SharedLibrary;
-use symbolic_debuginfo::Function;
+use names::{FunctionLikelihood, NameMatch};
+use symbolic_common::{Arch, Language};
+use symbolic_debuginfo::{FileFormat, Function};
 use symbolic_demangle::{Demangle, DemangleOptions};
 
+#[cfg(feature = "async")]
+pub use async_scan::{
+    declaration_by_name_async, spawn_scan, ScanCancelled, ScanProgressStream, ScanTask,
+};
+#[cfg(feature = "cargo-metadata")]
+pub use attribution::attribute_site;
+#[cfg(all(feature = "cargo-metadata", feature = "process"))]
+pub use attribution::declaration_by_name_with_metadata;
+pub use attribution::{attribute_dependency_path, CrateAttribution, CrateSource};
+#[cfg(feature = "process")]
+pub use backtrace::{
+    annotate_backtrace, current_function_declaration, declaration_from_backtrace_frame_text,
+    declarations_from_backtrace_text, AnnotatedBacktrace,
+};
+#[cfg(feature = "process")]
+pub use cache::{cached_declaration, cached_declaration_of, DeclarationCache};
+pub use cancellation::{with_cancellation, with_scan_progress, CancellationToken, ScanProgress};
+#[cfg(feature = "process")]
+pub use capability::{capability_probe, CapabilityReport, ExecutableKind};
+use choice::Candidates;
+pub use choice::{AmbiguousMatch, DeclarationChoice, InlinedCall};
+pub use comparison::{compare_function_maps, ComparisonOptions, MapComparison, SiteDifference};
+pub use declaration_site_core::{
+    function_breakpoint_spec, strip_verbatim_prefix, verbatim_path, Confidence, ConfidenceFactor,
+    Debugger, DeclarationSite, DeclarationSiteError, LineProvenance, MatchRelaxation,
+    PairingStatus,
+};
+#[cfg(feature = "process")]
+pub use drop_glue::drop_glue_declaration;
+pub use duplication::{
+    duplication_report, DuplicatedFunction, DuplicationReport, ObjectDuplication,
+};
+#[cfg(feature = "process")]
+pub use embedded_table::register_embedded_table;
+pub use embedded_table::{export_compact_table, EmbeddedTableError};
+#[cfg(feature = "serde")]
+pub use export::{export_function_map, ExportFormat};
+pub use file_access::{with_file_access, FileAccess, FileMetadata, StdFileAccess};
+pub use file_map::file_map;
+#[cfg(feature = "process")]
 pub use findshlibs::IterationControl;
+#[cfg(feature = "process")]
+pub use fn_ptr::declaration_of_fn_ptr;
+pub use index::{DeclarationIndex, IndexDiff, MovedFunction};
+pub use index_cache::{CacheControl, CacheStatus};
+pub use interning::{SharedDeclarationSite, SharedIndex, SitePool};
+pub use layout::for_functions_in_address_order;
+pub use limits::{with_scan_limits, ScanLimits, ScanStats};
+#[cfg(feature = "libloading")]
+pub use loaded::{for_functions_in_loaded_library, index_library, LoadedLibraryError};
+#[cfg(feature = "process")]
+pub use low_allocation::with_low_allocation_scans;
+pub use macro_site::MacroSite;
+pub use module_tree::{module_tree, ModuleTree};
+pub use names::{
+    canonical_name, names_match, normalize_function_path, NormalizationStage, NormalizeOptions,
+    NormalizedPath,
+};
+pub use pairing::{verify_debug_pairing, with_debug_pairing, DebugPairing, PairingReport};
+#[cfg(feature = "process")]
+pub use panic::{augment_panic_hook, install_panic_hook};
+pub use path_syntax::{
+    validate_function_path, PathSyntaxError, PathSyntaxErrorKind, ValidatedPath,
+};
+pub use paths::{PathComparison, PathPreference};
+pub use priority::scan_priority;
+#[cfg(feature = "process")]
+pub use process::{
+    declaration, declaration_by_name, declaration_by_name_all, declaration_of,
+    declarations_by_name, explain_declaration_by_name, explain_declaration_by_name_all,
+    for_each_loaded_function, try_declaration, try_declaration_by_name, try_declaration_of,
+};
+#[cfg(feature = "process")]
+#[allow(deprecated)]
+pub use process::{declaration_by_val, for_some_currently_loaded_rust_functions};
+#[cfg(feature = "process")]
+use process::{is_scanning, main_executable_path, scan_for_name, scan_loaded_files};
+pub use record::{FunctionRecord, LineRecord};
+use record::{LazyUnits, ScannedObject};
+pub use redaction::{set_redaction, Redaction, RedactionRule};
+#[cfg(feature = "process")]
+pub use registration::{declaration_of_registered, RegistrationInfo};
+#[cfg(feature = "process")]
+pub use search::DeclarationSearch;
+pub use session::IndexSession;
+pub use sources::{read_snippet, EmbeddedSources, SnippetOptions, SourceEncoding, SourceSnippet};
+#[cfg(feature = "process")]
+pub use symbols::{lookup_by_name, Lookup, SymbolOnly};
+#[cfg(feature = "process")]
+pub use type_ids::{declaration_by_type_id, register_type, register_type_of, registered_type_name};
+pub use unit_info::{for_each_compilation_unit, CuInfo};
+#[cfg(feature = "process")]
+pub use verify::{
+    verify_matching_roundtrip, verify_matching_roundtrip_of, DriftCandidate, MatchDrift,
+};
 
-/// Attempt to get the declaration site of the function item type of the
-/// given value, using its type name. This is a trivial wrapper around
-/// [`declaration`], but may be easier to use since function item types
-/// cannot be named.
+/// The state of a search for several function names at once.
+#[derive(Clone)]
+struct NameQueries<'a> {
+    /// The canonical name being searched for at each index
+    names: Vec<Option<Cow<'a, str>>>,
+    candidates: Vec<Candidates>,
+    /// The indices into `names` for each [`names::wildcard_key`]
+    by_key: HashMap<Cow<'a, str>, Vec<usize>>,
+    /// The indices into `names` for the wildcard key of the `Type::method`
+    /// spelling of each name spelled `<Type as Trait>::method`
+    by_spelling: HashMap<String, Vec<usize>>,
+    /// The indices into `names` for the identifier of the last segment of
+    /// each name, for matching the names of functions from PDBs and
+    /// unmangled names
+    by_ident: HashMap<String, Vec<usize>>,
+    /// The indices into `names` for the [`names::generic_form_key`] of each
+    /// name, for matching the generic forms of legacy demangled names
+    by_generic_key: HashMap<String, Vec<usize>>,
+    /// The indices into `names` for the identifier of the last segment of
+    /// each name containing an opaque type, which can't be found by key
+    by_opaque_ident: HashMap<String, Vec<usize>>,
+    /// The indices into `names` for the wildcard key of the default body of
+    /// each name spelled `<Type as Trait>::method` (`Trait::method`)
+    by_default: HashMap<String, Vec<usize>>,
+    /// The number of names which haven't been found exactly
+    remaining: usize,
+    /// The number of names which haven't been found at all, other than
+    /// inlined into other functions
+    unmatched: usize,
+}
+
+impl<'a> NameQueries<'a> {
+    fn new(names: &[&'a str]) -> Self {
+        let mut by_key: HashMap<Cow<str>, Vec<usize>> = HashMap::new();
+        let mut by_spelling: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_ident: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_default: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_generic_key: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_opaque_ident: HashMap<String, Vec<usize>> = HashMap::new();
+        let names = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                if names::function_likelihood(name) == FunctionLikelihood::Impossible {
+                    return None;
+                }
+                let name = names::canonical_name(name);
+                let ident = names::last_ident(&name).to_owned();
+                if name.contains(names::OPAQUE) {
+                    by_opaque_ident
+                        .entry(ident.clone())
+                        .or_default()
+                        .push(index);
+                } else {
+                    let key = names::generic_form_key(&name).into_owned();
+                    by_generic_key.entry(key).or_default().push(index);
+                }
+                by_ident.entry(ident).or_default().push(index);
+                if let Some(spelling) = names::inherent_spelling(&name) {
+                    let key = names::wildcard_key(&spelling).into_owned();
+                    by_spelling.entry(key).or_default().push(index);
+                }
+                if let Some(default_method) = names::default_method_name(&name) {
+                    let key = names::wildcard_key(&default_method).into_owned();
+                    by_default.entry(key).or_default().push(index);
+                }
+                let key = match &name {
+                    Cow::Borrowed(name) => names::wildcard_key(name),
+                    Cow::Owned(name) => Cow::Owned(names::wildcard_key(name).into_owned()),
+                };
+                by_key.entry(key).or_default().push(index);
+                Some(name)
+            })
+            .collect::<Vec<_>>();
+        NameQueries {
+            candidates: names.iter().map(|_| Candidates::default()).collect(),
+            remaining: names.iter().flatten().count(),
+            unmatched: names.iter().flatten().count(),
+            names,
+            by_key,
+            by_spelling,
+            by_ident,
+            by_generic_key,
+            by_opaque_ident,
+            by_default,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Record `function` if it matches any of the names being searched for,
+    /// breaking once a function has been chosen for every name (see
+    /// [`DeclarationChoice`]).
+    fn visit(&mut self, function: &FunctionRecord<'_>) -> IterationControl {
+        for (index, kind) in self.matches(function) {
+            let was_empty = self.candidates[index].is_empty();
+            if self.candidates[index].add(kind, function) {
+                self.remaining -= 1;
+            }
+            if was_empty && !self.candidates[index].is_empty() {
+                self.unmatched -= 1;
+            }
+        }
+        if self.unmatched > 0 {
+            for_each_inlinee(function, &mut |caller, inlinee| {
+                for (index, kind) in self.matches(inlinee) {
+                    if self.candidates[index].is_empty() {
+                        self.candidates[index].add_inlined(kind, inlinee, caller);
+                    }
+                }
+            });
+        }
+        if self.remaining == 0 {
+            IterationControl::Break
+        } else {
+            IterationControl::Continue
+        }
+    }
+
+    /// The indices of the names which `function` matches, and how.
+    fn matches(&self, function: &FunctionRecord<'_>) -> Vec<(usize, NameMatch)> {
+        let demangled_name = names::canonical_name(function.name());
+        let key = names::wildcard_key(&demangled_name);
+        // Also find queries spelled `Type::method` for a function demangled as
+        // `<Type as Trait>::method`, and the reverse
+        let spelling = names::inherent_spelling(&demangled_name)
+            .map(|spelling| names::wildcard_key(&spelling).into_owned());
+        let matches = self
+            .by_key
+            .get(&*key)
+            .into_iter()
+            .chain(spelling.and_then(|spelling| self.by_key.get(&*spelling)))
+            .chain(self.by_spelling.get(&*key))
+            .flatten()
+            .filter(|&&index| !self.candidates[index].is_final())
+            .filter_map(|&index| {
+                let name = self.names[index].as_deref()?;
+                Some((index, names::match_names(&demangled_name, name)?))
+            })
+            .collect::<Vec<_>>();
+        // Legacy demangled names print generic parameters rather than
+        // arguments, and opaque types match any type, so these can't be found
+        // by their key. Only names with an opaque type need to be compared
+        // with every function with the same last identifier
+        let ident = names::last_ident(&demangled_name);
+        let (by_generic_key, by_opaque_ident) = if demangled_name.contains(names::OPAQUE) {
+            (self.by_ident.get(ident), None)
+        } else {
+            let generic_key = names::generic_form_key(&demangled_name);
+            (
+                self.by_generic_key.get(&*generic_key),
+                self.by_opaque_ident.get(ident),
+            )
+        };
+        let generic_matches = by_generic_key
+            .into_iter()
+            .chain(by_opaque_ident)
+            .flatten()
+            .filter(|&&index| !self.candidates[index].is_final())
+            .filter(|&&index| !matches.iter().any(|&(it, _)| it == index))
+            .filter_map(|&index| {
+                let name = self.names[index].as_deref()?;
+                let kind = if name.contains(names::OPAQUE) {
+                    names::match_names(&demangled_name, name)
+                } else {
+                    names::match_generic_form(&demangled_name, name)
+                };
+                Some((index, kind?))
+            })
+            .collect::<Vec<_>>();
+        let pdb_matches = if function.is_from_pdb() {
+            self.by_ident
+                .get(names::last_ident(&demangled_name))
+                .into_iter()
+                .flatten()
+                .filter(|&&index| !self.candidates[index].is_final())
+                .filter(|&&index| !matches.iter().any(|&(it, _)| it == index))
+                .filter(|&&index| !generic_matches.iter().any(|&(it, _)| it == index))
+                .filter_map(|&index| {
+                    let name = self.names[index].as_deref()?;
+                    Some((index, pdb_names::match_pdb_names(function, name)?))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        // Unmangled names, such as of `#[no_mangle]` functions, match the last
+        // segment of a name
+        let unmangled_matches = if demangled_name.contains(':') {
+            Vec::new()
+        } else {
+            let ident = match function.object_format() {
+                FileFormat::MachO => demangled_name.strip_prefix('_'),
+                _ => None,
+            };
+            self.by_ident
+                .get(&*demangled_name)
+                .into_iter()
+                .chain(ident.and_then(|ident| self.by_ident.get(ident)))
+                .flatten()
+                .filter(|&&index| !self.candidates[index].is_final())
+                // Bare symbols being searched for already matched by key
+                .filter(|&&index| !matches.iter().any(|&(it, _)| it == index))
+                .filter_map(|&index| {
+                    let name = self.names[index].as_deref()?;
+                    Some((index, function.match_unmangled(name)?))
+                })
+                .collect()
+        };
+        // The body of an `async fn`'s future matches the `async fn`
+        let future_matches = names::generated_future_parent(&demangled_name)
+            .and_then(|parent| self.by_key.get(&*names::wildcard_key(parent)))
+            .into_iter()
+            .flatten()
+            .filter(|&&index| !self.candidates[index].is_final())
+            .filter_map(|&index| {
+                let name = self.names[index].as_deref()?;
+                Some((index, names::match_future_body(&demangled_name, name)?))
+            });
+        // Default bodies of trait methods match `<Type as Trait>::method`
+        let default_matches = self
+            .by_default
+            .get(&*key)
+            .into_iter()
+            .flatten()
+            .filter(|&&index| !self.candidates[index].is_final())
+            .filter_map(|&index| {
+                let name = self.names[index].as_deref()?;
+                Some((index, names::match_default_method(&demangled_name, name)?))
+            });
+        matches
+            .into_iter()
+            .chain(generic_matches)
+            .chain(pdb_matches)
+            .chain(unmangled_matches)
+            .chain(future_matches)
+            .chain(default_matches)
+            .collect()
+    }
+
+    /// Search the object file in `data` for the names which haven't been
+    /// found, using its DWARF accelerator tables (see [`scan_indexed`]).
+    ///
+    /// Unless this finds every name, as reading every function would have,
+    /// the queries are left unchanged and `None` is returned, so that the
+    /// file is read in full.
+    fn visit_indexed(
+        &mut self,
+        data: &[u8],
+        object_path: Option<&Path>,
+    ) -> Option<IterationControl> {
+        let mut indexed = self.clone();
+        let pending = self
+            .names
+            .iter()
+            .zip(&self.candidates)
+            .filter(|(_, candidates)| !candidates.is_final())
+            .filter_map(|(name, _)| name.as_deref());
+        for name in pending {
+            scan_indexed(data, name, &mut |object, demangled_name, function| {
+                let record = FunctionRecord::new(demangled_name, &function)
+                    .with_object_path(object_path)
+                    .with_object(object);
+                indexed.visit(&record)
+            })?;
+        }
+        if !indexed.is_empty() {
+            return None;
+        }
+        *self = indexed;
+        Some(IterationControl::Break)
+    }
+
+    fn results(self) -> Vec<Option<DeclarationSite>> {
+        self.candidates
+            .into_iter()
+            .map(|candidates| candidates.best()?.site.ok())
+            .collect()
+    }
+}
+
+/// Attempt to get the declaration site of the function with the given
+/// (unmangled) name in the binary or debug file at `path`.
+///
+/// Unlike [`declaration_by_name`], this doesn't need the file to be loaded
+/// into the current process, so can be used for inspecting other programs.
+/// The file can be for any architecture and byte order, such as a big-endian
+/// PowerPC binary inspected on an x86_64 machine.
 ///
-/// This will (probably) return `None` for non-function item types.
+/// # Errors
+///
+/// If the file could not be read, or is not a supported object file format.
+/// If the function wasn't found and the file contains relocatable ELF
+/// objects (such as a `.o` file or static library), an error of kind
+/// [`io::ErrorKind::Unsupported`] naming their architecture is returned,
+/// as the functions in these can't be told apart
+pub fn declaration_by_name_in(
+    path: impl AsRef<Path>,
+    name: &str,
+) -> io::Result<Option<DeclarationSite>> {
+    Ok(declarations_by_name_in(path, &[name])?.remove(0))
+}
+
+/// Attempt to get the declaration sites of several functions in the binary
+/// or debug file at `path`.
+///
+/// The result at each index corresponds to the name at the same index in
+/// `names`, as in [`declarations_by_name`].
 ///
-/// See also "Caveats" in the [module level documentation](crate).
+/// # Errors
 ///
-/// The `functions` example in this crate demonstrates this API.
-pub fn declaration_of<T>(_: &T) -> Option<DeclarationSite> {
-    declaration::<T>()
+/// As for [`declaration_by_name_in`], where the error for relocatable ELF
+/// objects is returned if any of the functions wasn't found
+pub fn declarations_by_name_in(
+    path: impl AsRef<Path>,
+    names: &[&str],
+) -> io::Result<Vec<Option<DeclarationSite>>> {
+    let mut queries = NameQueries::new(names);
+    let mut unrelocated = None;
+    if !queries.is_empty() {
+        visit_files(path.as_ref(), |data, object_path| {
+            if let Some(control) = queries.visit_indexed(data, object_path) {
+                return Ok(control);
+            }
+            scan_file_data(data, object_path, &mut |function| {
+                if function.is_unrelocated() {
+                    unrelocated.get_or_insert(function.object_arch());
+                }
+                queries.visit(&function)
+            })
+        })?;
+    }
+    let results = queries.results();
+    match unrelocated {
+        Some(arch) if results.contains(&None) => Err(unrelocated_error(arch)),
+        _ => Ok(results),
+    }
 }
 
-/// Attempt to get the declaration site of the function item type of the
-/// given type, using its type name. In most cases, you may want to use  
-/// [`declaration_of`], since function item types are unnameable.
+/// The error for a search of a relocatable ELF object which didn't find every
+/// name, as its functions may have been missed (see
+/// [`FunctionRecord::is_unrelocated`]).
+fn unrelocated_error(arch: Arch) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "the debug info of relocatable ELF objects (here for {}) can't be read without \
+             applying relocations, so link the object into a binary or library first",
+            arch.name()
+        ),
+    )
+}
+
+/// Run `callback` on each function in the binary or debug file at `path` which
+/// can be demangled.
 ///
-/// This function uses [`declaration_by_name`] with the [type
-/// name](core::any::type_name) of the type parameter `T`.
+/// This behaves like [`for_each_loaded_function`], but only
+/// for a single file, which does not need to be loaded into the current
+/// process.
 ///
-/// This will (probably) return `None` for non-function item types.
+/// `path` can also be a macOS `.dSYM` bundle, in which case every file in its
+/// `Contents/Resources/DWARF` directory is searched, and
+/// [`FunctionRecord::object_path`] is the file each function was found in.
+/// To search the files in any other directory, use
+/// [`for_functions_in_directory`].
 ///
-/// See also "Caveats" in the [module level documentation](crate).
+/// WASM modules whose DWARF was split into a separate file (named by their
+/// `external_debug_info` section) are searched using that file, resolved
+/// relative to `path`. Whether that file matches the module is checked as
+/// configured by [`with_debug_pairing`]. For modules which aren't in a file,
+/// use [`object::Object::external_debug_info`] to find the file, and search
+/// it using [`for_functions_in_bytes`].
 ///
-/// Note: This could be a feature provided by [`core::any`] (using a new
-/// intrinsic, returning [`&'static Location<'static>`](core::panic::Location)).
-/// However, that (currently) doesn't exist. If it did, it would be:
-/// - significantly faster
-/// - more correct for functions as it would provide a column.
-/// - support e.g. structs, unions
-/// - not able to be run dynamically as [`declaration_by_name`]
-pub fn declaration<T>() -> Option<DeclarationSite> {
-    declaration_by_name(core::any::type_name::<T>())
+/// # Errors
+///
+/// If the file could not be read, or is not a supported object file format
+/// (with [`io::ErrorKind::Unsupported`]). With [`DebugPairing::Strict`], if
+/// the external debug file of a WASM module doesn't match it (with
+/// [`io::ErrorKind::InvalidData`]). If the scan was cancelled (see
+/// [`with_cancellation`]), with [`io::ErrorKind::Interrupted`]
+pub fn for_functions_in_file<C>(
+    path: impl AsRef<Path>,
+    mut callback: impl FnMut(FunctionRecord<'_>) -> C,
+) -> io::Result<()>
+where
+    C: Into<IterationControl>,
+{
+    visit_files(path.as_ref(), |data, object_path| {
+        scan_file_data(data, object_path, &mut callback)
+    })
 }
 
-/// Attempt to get the declaration site of a currently loaded function
-/// with the given (unmangled) name.
+/// Run `visit` on the contents of each file searched for `path` by
+/// [`for_functions_in_file`], with the path to report for its functions (if
+/// it isn't `path` itself), until it returns [`IterationControl::Break`].
 ///
-/// See also "Caveats" in the [module level documentation](crate).
-pub fn declaration_by_name(name: &str) -> Option<DeclarationSite> {
-    let mut result = None;
-    for_some_currently_loaded_rust_functions(|demangled_name, function| {
-        if demangled_name == name {
-            result = (&function).try_into().ok();
-            IterationControl::Break
-        } else {
-            IterationControl::Continue
+/// If the scan is cancelled, this returns an error of kind
+/// [`io::ErrorKind::Interrupted`].
+fn visit_files(
+    path: &Path,
+    mut visit: impl FnMut(&[u8], Option<&Path>) -> io::Result<IterationControl>,
+) -> io::Result<()> {
+    cancellation::interruptible(|| visit_files_uncancelled(path, &mut visit))
+}
+
+/// [`visit_files`], without turning a cancelled scan into an error.
+fn visit_files_uncancelled(
+    path: &Path,
+    visit: &mut impl FnMut(&[u8], Option<&Path>) -> io::Result<IterationControl>,
+) -> io::Result<()> {
+    if !file_access::is_dir(path) {
+        if cancellation::file_checkpoint(path) {
+            return Ok(());
         }
-    });
-    result
+        let data = file_access::read(path)?;
+        return visit_file_data(path, &data, visit);
+    }
+    let dwarf_directory = path.join("Contents").join("Resources").join("DWARF");
+    if !file_access::is_dir(&dwarf_directory) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is a directory, but not a .dSYM bundle. Use `for_functions_in_directory` to search the files in it",
+                path.display()
+            ),
+        ));
+    }
+    // Bundles can contain several files (such as one per architecture), which
+    // are all searched
+    for file in sorted_files(&dwarf_directory)? {
+        if cancellation::file_checkpoint(&file) {
+            break;
+        }
+        let data = file_access::read(&file)?;
+        if let IterationControl::Break = visit(&data, Some(&file))? {
+            break;
+        }
+    }
+    Ok(())
 }
 
-/// Run `callback` on each currently loaded function which can be demangled in
-/// the current context, See the caveats section on the [module level
-/// documentation](crate).
+/// Run `callback` on each function in each binary or debug file directly in
+/// the directory at `path`, such as a directory of PDBs.
 ///
-/// As this crate depends on [`symbolic_demangle`] using the `"rust"` feature,
-/// this may skip non-Rust functions (e.g. from libc), despite them having debug
-/// information available. However, on platforms where mangling isn't used in
-/// debug files, such as Windows' pdb files, this may also call callback with
-/// non-Rust functions. Additionally, if [`symbolic_demangle`] is in the current
-/// dependency tree with any other demangling features enabled supported,
-/// they may also be used.
+/// Files are searched in order of their names, and
+/// [`FunctionRecord::object_path`] is the file each function was found in.
+/// Files which aren't a supported object file format are skipped, and
+/// `.dSYM` bundles in the directory are searched as in
+/// [`for_functions_in_file`]. Other subdirectories are not searched.
 ///
-/// See also "Caveats" in the [module level documentation](crate).
+/// Returns the path and error of each file which could not be searched.
 ///
-/// Note that `callback` can cause this process to end early by returning [`IterationControl::Break`].
-/// If doing so, return [IterationControl::Continue] to continue
+/// # Errors
 ///
-/// Returning [`()`](unit) will be taken as returning [IterationControl::Continue].
-pub fn for_some_currently_loaded_rust_functions<C>(mut callback: impl FnMut(String, Function) -> C)
+/// If the directory could not be read, or the scan was cancelled (see
+/// [`with_cancellation`])
+pub fn for_functions_in_directory<C>(
+    path: impl AsRef<Path>,
+    mut callback: impl FnMut(FunctionRecord<'_>) -> C,
+) -> io::Result<Vec<(PathBuf, io::Error)>>
 where
     C: Into<IterationControl>,
 {
-    let mut libraries = vec![];
-    // `each` might take locks - make this as short as possible to not block
-    // backtraces in other threads.
-    findshlibs::TargetSharedLibrary::each(|library| {
-        libraries.push((
-            library.name().to_owned(),
-            library.debug_name().map(ToOwned::to_owned),
-        ));
-    });
-    // Error handling:
-    // In these loops, if we get an error, we just try again with the next item
-    // We're not trying to be fancy here - again, this is a best effort search.
-    // If nothing works, the user should have a fallback, as explained in caveats.
-    for (library_path, debug_path) in libraries {
-        let path = if let Some(debug_path) = debug_path {
-            debug_path.into()
-        } else if library_path.len() == 0 {
-            match current_exe() {
-                Ok(it) => it,
-                Err(_) => continue,
+    let mut entries = file_access::read_dir(path.as_ref())?;
+    entries.sort();
+    let mut errors = Vec::new();
+    for entry in entries {
+        let result = if file_access::is_dir(&entry) {
+            if entry.extension().is_none_or(|it| it != "dSYM") {
+                continue;
             }
+            let mut control = IterationControl::Continue;
+            for_functions_in_file(&entry, |function| {
+                control = callback(function).into();
+                control
+            })
+            .map(|()| control)
+        } else if cancellation::file_checkpoint(&entry) {
+            return Err(cancellation::interrupted_error());
         } else {
-            library_path.into()
+            file_access::read(&entry)
+                .and_then(|data| scan_file_data(&data, Some(&entry), &mut callback))
+        };
+        match result {
+            Ok(IterationControl::Break) => break,
+            Ok(IterationControl::Continue) => {}
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+            // A cancelled scan is incomplete, rather than missing this file
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return Err(e),
+            Err(e) => errors.push((entry, e)),
+        }
+    }
+    Ok(errors)
+}
+
+/// Run `visit` as in [`visit_files`] for the file (rather than directory) at
+/// `path`, which has already been read as `data`.
+pub(crate) fn visit_file_data(
+    path: &Path,
+    data: &[u8],
+    visit: &mut impl FnMut(&[u8], Option<&Path>) -> io::Result<IterationControl>,
+) -> io::Result<()> {
+    if let Some(debug_path) = external_debug_file(path, data) {
+        let debug_data = file_access::read(&debug_path)?;
+        // The module has no debug info of its own, so a mismatched debug
+        // file is only skipped by `DebugPairing::Strict`
+        if let Some(report) = pairing::verify(path, data, &debug_path, &debug_data)
+            .filter(|report| !pairing::use_debug_file(report, false))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                report.to_string(),
+            ));
+        }
+        return visit(&debug_data, Some(&debug_path)).map(|_| ());
+    }
+    visit(data, None).map(|_| ())
+}
+
+/// The path of the external debug file of the WASM module at `path` with
+/// contents `data`, if it has no debug info of its own.
+fn external_debug_file(path: &Path, data: &[u8]) -> Option<PathBuf> {
+    let debug_info = limits::catch_parser_panic(|| {
+        let archive = object::Archive::parse(data).ok()?;
+        let module = archive.object_by_index(0).ok()??;
+        if module.has_debug_info() {
+            return None;
+        }
+        module.external_debug_info().map(ToOwned::to_owned)
+    })??;
+    // URLs (for loading in browsers) can't be resolved to a file
+    if debug_info.contains("://") {
+        return None;
+    }
+    Some(path.parent()?.join(debug_info))
+}
+
+/// The paths of the files directly in the directory at `path`, sorted by name.
+fn sorted_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = file_access::read_dir(path)?;
+    files.retain(|file| !file_access::is_dir(file));
+    files.sort();
+    Ok(files)
+}
+
+/// Run `callback` on each function in the binary or debug file with contents
+/// `data` which can be demangled.
+///
+/// See [`for_functions_in_file`] for details.
+///
+/// # Errors
+///
+/// If `data` is not a supported object file format (with
+/// [`io::ErrorKind::Unsupported`]), or could not be parsed. If the scan was
+/// cancelled (see [`with_cancellation`]), with [`io::ErrorKind::Interrupted`]
+pub fn for_functions_in_bytes<C>(
+    data: &[u8],
+    mut callback: impl FnMut(FunctionRecord<'_>) -> C,
+) -> io::Result<()>
+where
+    C: Into<IterationControl>,
+{
+    scan_file_data(data, None, &mut callback).map(|_| ())
+}
+
+/// Run `callback` on each function in the file with contents `data`, found
+/// at `object_path` if it is one of several files being searched.
+fn scan_file_data<C>(
+    data: &[u8],
+    object_path: Option<&Path>,
+    callback: &mut impl FnMut(FunctionRecord<'_>) -> C,
+) -> io::Result<IterationControl>
+where
+    C: Into<IterationControl>,
+{
+    cancellation::interruptible(|| {
+        scan_data(data, &mut |object, name, function| {
+            let record = FunctionRecord::new(name, &function)
+                .with_object_path(object_path)
+                .with_object(object);
+            callback(record).into()
+        })
+        .map_err(|e| {
+            let kind = if e.is_unsupported() {
+                io::ErrorKind::Unsupported
+            } else {
+                io::ErrorKind::InvalidData
+            };
+            io::Error::new(kind, e)
+        })
+    })
+}
+
+/// Whether to continue searching, returned by the callbacks of functions such
+/// as [`for_functions_in_file`].
+///
+/// With the `process` feature, this is `findshlibs::IterationControl`.
+#[cfg(not(feature = "process"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IterationControl {
+    /// Stop searching.
+    Break,
+    /// Continue searching.
+    Continue,
+}
+
+#[cfg(not(feature = "process"))]
+impl From<()> for IterationControl {
+    fn from(_: ()) -> Self {
+        IterationControl::Continue
+    }
+}
+
+/// Where to find functions, for functions which can search either the current
+/// process or a file, such as [`module_tree`].
+#[derive(Copy, Clone, Debug)]
+pub enum ScanTarget<'a> {
+    /// All currently loaded functions, as found by [`for_each_loaded_function`],
+    /// enabled by the `process` feature
+    #[cfg(feature = "process")]
+    CurrentProcess,
+    /// The functions in the binary or debug file at this path, as found by
+    /// [`for_functions_in_file`]
+    File(&'a Path),
+    /// The functions in a binary or debug file with these contents, as found
+    /// by [`for_functions_in_bytes`]
+    Bytes(&'a [u8]),
+}
+
+/// Run `callback` on each function in `target`.
+fn for_functions_in_target<C>(
+    target: ScanTarget<'_>,
+    callback: impl FnMut(FunctionRecord<'_>) -> C,
+) -> io::Result<()>
+where
+    C: Into<IterationControl>,
+{
+    match target {
+        #[cfg(feature = "process")]
+        ScanTarget::CurrentProcess => for_each_loaded_function(callback),
+        ScanTarget::File(path) => for_functions_in_file(path, callback)?,
+        ScanTarget::Bytes(data) => for_functions_in_bytes(data, callback)?,
+    }
+    Ok(())
+}
+
+/// Run `callback` on each function inlined into `function`, including those
+/// inlined into other inlined functions, with the function it was inlined
+/// into.
+fn for_each_inlinee(
+    function: &FunctionRecord<'_>,
+    callback: &mut impl FnMut(&FunctionRecord<'_>, &FunctionRecord<'_>),
+) {
+    for inlinee in function.inlinees() {
+        callback(function, &inlinee);
+        for_each_inlinee(&inlinee, callback);
+    }
+}
+
+/// The demangled name of `function` from an object of `format`, in the same
+/// format as [`type_name`](core::any::type_name), if it can be demangled.
+fn demangled_name(function: &Function<'_>, format: FileFormat) -> Option<String> {
+    // We only demangle the name since `type_name` doesn't return the
+    // signature
+    let demangled_name = function
+        .name
+        .demangle(DemangleOptions::name_only())
+        .or_else(|| {
+            // Keep the unmangled names of Rust functions, such as
+            // `#[no_mangle]` functions
+            let name = function.name.as_str();
+            (function.name.language() == Language::Rust && !names::is_rust_mangled(name))
+                .then(|| name.to_owned())
+        })?;
+    // Names in PDBs aren't mangled, but use their own spelling of types
+    if format == FileFormat::Pdb {
+        Some(pdb_names::normalize_owned_msvc_name(demangled_name))
+    } else {
+        Some(demangled_name)
+    }
+}
+
+/// Run `callback` on each function in the object file(s) in `data`, returning
+/// [`IterationControl::Break`] if `callback` did, or the scan was
+/// [cancelled](with_cancellation).
+///
+/// Objects are searched in the order chosen by [`priority::prioritized_objects`],
+/// and read within the current [`ScanLimits`].
+fn scan_data(
+    data: &[u8],
+    callback: &mut impl FnMut(&ScannedObject<'_>, String, Function) -> IterationControl,
+) -> Result<IterationControl, object::ObjectError> {
+    let Some(archive) = limits::catch_parser_panic(|| object::Archive::parse(data)) else {
+        return Ok(IterationControl::Continue);
+    };
+    let archive = archive?;
+    let limits = limits::current();
+    // Individual objects or functions which fail to parse are skipped, as
+    // explained in `scan_currently_loaded_rust_functions`. A panic from the
+    // parser skips the rest of its object
+    for (index, priority, object) in priority::prioritized_objects(&archive) {
+        if cancellation::is_cancelled() {
+            return Ok(IterationControl::Break);
+        }
+        let read_units =
+            || limits::catch_parser_panic(|| object.compilation_units()).unwrap_or_default();
+        let units = LazyUnits::new(&read_units);
+        let scanned = ScannedObject {
+            index,
+            name: archive.object_name(index),
+            format: object.file_format(),
+            arch: object.arch(),
+            kind: object.kind(),
+            priority,
+            units: &units,
         };
-        let file_data = match fs::read(path) {
-            Ok(it) => it,
+        let session = match limits::catch_parser_panic(|| object.debug_session()) {
+            Some(Ok(it)) => it,
             _ => continue,
         };
-        let archive = match symbolic_object::Archive::parse(&file_data) {
-            Ok(it) => it,
-            Err(_) => continue,
+        limits::record(|stats| stats.objects += 1);
+        let mut functions = session.functions();
+        let functions =
+            std::iter::from_fn(|| limits::catch_parser_panic(|| functions.next()).flatten())
+                .flatten();
+        // In address order, the functions of each object are read before any
+        // are visited, so that they can be sorted
+        let functions: Box<dyn Iterator<Item = Function>> = if layout::in_address_order() {
+            let mut sorted = functions
+                .take(limits.max_functions_per_object.saturating_add(1))
+                .collect::<Vec<_>>();
+            sorted.sort_by_key(|function| (function.address, function.size));
+            Box::new(sorted.into_iter())
+        } else {
+            Box::new(functions)
         };
-        for object in archive.objects() {
-            let object = match object {
-                Ok(it) => it,
-                Err(_) => continue,
-            };
-            let session = match object.debug_session() {
-                Ok(it) => it,
-                Err(_) => continue,
-            };
-            for function in session.functions() {
-                if let Ok(function) = function {
-                    if let Some(demangled_name) =
-                        // We only demangle the name since `type_name` doesn't return the
-                        // signature
-                        function.name.demangle(DemangleOptions::name_only())
-                    {
-                        match callback(demangled_name, function).into() {
-                            IterationControl::Break => return,
-                            IterationControl::Continue => (),
-                        }
-                    }
+        for (count, mut function) in functions.enumerate() {
+            if cancellation::function_checkpoint() {
+                return Ok(IterationControl::Break);
+            }
+            if count == limits.max_functions_per_object {
+                limits::record(|stats| stats.truncated_objects += 1);
+                break;
+            }
+            let truncated = limits::truncate_lines(&mut function, limits.max_lines_per_function);
+            limits::record(|stats| {
+                stats.functions += 1;
+                stats.truncated_functions += truncated;
+            });
+            if let Some(demangled_name) = demangled_name(&function, scanned.format) {
+                match callback(&scanned, demangled_name, function) {
+                    IterationControl::Break => return Ok(IterationControl::Break),
+                    IterationControl::Continue => (),
                 }
             }
         }
     }
+    Ok(IterationControl::Continue)
 }
 
-/// A source file location, obtained from a [`symbolic_debuginfo::Function`],
-/// using [`TryFrom`]/[`TryInto`].
+/// Run `callback` on each function in the object file in `data` which its
+/// DWARF accelerator tables say could be named `name`, as [`scan_data`]
+/// would, without reading the rest of its functions.
 ///
-/// Printing this type into a terminal will often allow it to act as a link into
-/// the source code (if the working directories line up and the terminal
-/// emulator supports this feature).
-pub struct DeclarationSite {
-    pub file: String,
-    pub line: u32,
-}
-
-impl fmt::Display for DeclarationSite {
-    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(formatter, "{}:{}", self.file, self.line)
+/// Returns `None` without running `callback` if the tables can't be used
+/// (see [`object::Object::indexed_functions`]), or `data` contains several
+/// objects. Tables can be incomplete, so unless a function matched `name`
+/// exactly, the file still has to be searched with [`scan_data`].
+fn scan_indexed(
+    data: &[u8],
+    name: &str,
+    callback: &mut impl FnMut(&ScannedObject<'_>, String, Function) -> IterationControl,
+) -> Option<IterationControl> {
+    let archive = limits::catch_parser_panic(|| object::Archive::parse(data))?.ok()?;
+    if archive.is_multi() {
+        return None;
     }
+    let (index, priority, object) = priority::prioritized_objects(&archive).pop()?;
+    let read_units =
+        || limits::catch_parser_panic(|| object.compilation_units()).unwrap_or_default();
+    let units = LazyUnits::new(&read_units);
+    let scanned = ScannedObject {
+        index,
+        name: archive.object_name(index),
+        format: object.file_format(),
+        arch: object.arch(),
+        kind: object.kind(),
+        priority,
+        units: &units,
+    };
+    let name = names::canonical_name(name);
+    // `.debug_names` names functions by the last segment of their path
+    let leaf = *names::path_segments(&name).last()?;
+    let limits = limits::current();
+    let mut control = IterationControl::Continue;
+    object.indexed_functions(&name, leaf, |mut function| {
+        if control == IterationControl::Break {
+            return;
+        }
+        if cancellation::function_checkpoint() {
+            control = IterationControl::Break;
+            return;
+        }
+        let truncated = limits::truncate_lines(&mut function, limits.max_lines_per_function);
+        limits::record(|stats| {
+            stats.functions += 1;
+            stats.truncated_functions += truncated;
+        });
+        if let Some(demangled_name) = demangled_name(&function, scanned.format) {
+            control = callback(&scanned, demangled_name, function);
+        }
+    })?;
+    limits::record(|stats| {
+        stats.objects += 1;
+        stats.indexed_objects += 1;
+    });
+    Some(control)
 }
 
-/// An error returned in the [`TryFrom`] impl for [`DeclarationSite`].
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum DeclarationSiteError {
-    /// The debug info for the function contains no source locations
+/// An error returned from the `try_` functions, such as
+/// [`try_declaration_by_name`].
+#[derive(Debug)]
+pub enum DeclarationError {
+    /// The name does not look like the name of a function item type, so no
+    /// search was performed. This crate can only find the declarations of
+    /// functions, not of other items such as structs
+    ProbablyNotAFunction,
+    /// None of the currently loaded libraries could be read, so there was
+    /// nowhere to search
+    NoSearchTargets {
+        /// The error from [`std::env::current_exe`], if it was needed to find
+        /// the main executable and failed
+        current_exe_error: Option<io::Error>,
+        /// The number of loaded libraries (including the main executable)
+        /// which were found. If this is zero, enumerating the loaded
+        /// libraries is not supported on this platform
+        libraries_enumerated: usize,
+    },
+    /// None of the currently loaded libraries which were searched has debug
+    /// info, such as when they were stripped, so no function could be found
+    NoDebugInfo {
+        /// The number of loaded libraries (or their debug files) which were
+        /// searched
+        libraries_checked: usize,
+    },
+    /// No currently loaded function with the given name was found
+    NotFound,
+    /// No currently loaded function with the given name was found, and the
+    /// debug companions of some loaded libraries weren't searched as they
+    /// don't match their libraries (see [`with_debug_pairing`]). The
+    /// function may be in one of these libraries
+    MismatchedDebugFiles(Vec<PairingReport>),
+    /// The function was found, but its debug info contains no source locations
     MissingLines,
+    /// The function was only found inlined into other functions (such as in
+    /// an optimized build), and its inlined copies have no source locations.
+    /// These are the calls it was inlined at, as in
+    /// [`DeclarationChoice::inlined_into`]
+    InlinedInto(Vec<InlinedCall>),
+    /// Nothing was searched, as the lookup was started while the current
+    /// thread was already scanning the loaded libraries, such as from a
+    /// callback of [`for_each_loaded_function`], or from a panic hook run by
+    /// a panic inside a scan
+    Reentrant,
+    /// The scan was cancelled (see [`with_cancellation`]) before it found the
+    /// function, so it may have been in what wasn't searched
+    Cancelled,
 }
 
-impl fmt::Display for DeclarationSiteError {
+impl fmt::Display for DeclarationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DeclarationSiteError::MissingLines => write!(
+            DeclarationError::ProbablyNotAFunction => write!(
+                f,
+                "name is probably not a function, and only functions can be found"
+            ),
+            DeclarationError::NoSearchTargets {
+                current_exe_error: Some(error),
+                ..
+            } => write!(f, "could not find the current executable: {error}"),
+            DeclarationError::NoSearchTargets {
+                current_exe_error: None,
+                libraries_enumerated,
+            } => write!(
+                f,
+                "none of the {libraries_enumerated} loaded libraries could be read"
+            ),
+            DeclarationError::NoDebugInfo { libraries_checked } => write!(
+                f,
+                "none of the {libraries_checked} loaded libraries which were searched has debug info"
+            ),
+            DeclarationError::NotFound => write!(f, "no loaded function with this name was found"),
+            DeclarationError::MismatchedDebugFiles(reports) => {
+                write!(
+                    f,
+                    "no loaded function with this name was found, and {} debug file(s) which \
+                     don't match their libraries were skipped",
+                    reports.len()
+                )?;
+                for report in reports {
+                    write!(f, "\n  {report}")?;
+                }
+                Ok(())
+            }
+            DeclarationError::MissingLines => DeclarationSiteError::MissingLines.fmt(f),
+            DeclarationError::InlinedInto(calls) => {
+                write!(
+                    f,
+                    "the function only exists inlined into other functions, without source \
+                     locations of its own"
+                )?;
+                for call in calls {
+                    write!(f, "\n  inlined into {call}")?;
+                }
+                Ok(())
+            }
+            DeclarationError::Reentrant => write!(
                 f,
-                "debug info contains no source locations for this function"
+                "the current thread was already scanning the loaded libraries, so nothing was \
+                 searched"
             ),
+            DeclarationError::Cancelled => write!(f, "the scan was cancelled"),
         }
     }
 }
 
-impl Error for DeclarationSiteError {}
-
-/// Get the site of the first line of the function, according to the debug info.
-///
-/// # Errors
-///
-/// If the function's debug info has no source locations
-impl<'a> TryFrom<&Function<'a>> for DeclarationSite {
-    type Error = DeclarationSiteError;
-
-    fn try_from(value: &Function<'a>) -> Result<Self, Self::Error> {
-        let line = &value
-            .lines
-            .get(0)
-            .ok_or(DeclarationSiteError::MissingLines)?;
-        let file = line.file.path_str();
+impl Error for DeclarationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeclarationError::NoSearchTargets {
+                current_exe_error: Some(error),
+                ..
+            } => Some(error),
+            _ => None,
+        }
+    }
+}
 
-        Ok(DeclarationSite {
-            file,
-            line: line.line as u32,
-        })
+impl From<DeclarationSiteError> for DeclarationError {
+    fn from(error: DeclarationSiteError) -> Self {
+        match error {
+            DeclarationSiteError::MissingLines => DeclarationError::MissingLines,
+        }
     }
 }
@@ -0,0 +1,225 @@
+//! Limits on how much debug info is processed, and containing panics from
+//! the debug info parsers, to guard against pathological or malicious files.
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use symbolic_debuginfo::Function;
+
+/// Limits on how much of the debug info of a file is processed when
+/// searching it, set for the current thread by [`with_scan_limits`].
+///
+/// A crafted (or corrupted) file can claim to contain millions of line
+/// records for a single function, or of functions in a single object, which
+/// would make searching it take unbounded time and memory. The defaults are
+/// far larger than any binary produced by rustc is expected to need, so only
+/// need to be raised for unusually large binaries.
+///
+/// Line records beyond the limit are dropped once each function has been
+/// read, so the debug info parser still reads them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ScanLimits {
+    /// The most line records of each function (and each of its inlined
+    /// functions) which are kept, in the order they appear in the debug info
+    pub max_lines_per_function: usize,
+    /// The most functions read from each object. The rest of an object's
+    /// functions are skipped
+    pub max_functions_per_object: usize,
+}
+
+impl Default for ScanLimits {
+    fn default() -> Self {
+        ScanLimits {
+            max_lines_per_function: 1 << 20,
+            max_functions_per_object: 1 << 24,
+        }
+    }
+}
+
+/// What was processed by the scans run by [`with_scan_limits`], and whether
+/// any of its [`ScanLimits`] were exceeded.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ScanStats {
+    /// The limits the scans were run with
+    pub limits: ScanLimits,
+    /// The number of objects which were searched
+    pub objects: usize,
+    /// The number of those searches which only read the functions with the
+    /// name being searched for, using the accelerator tables of the object's
+    /// DWARF debug info. If the function wasn't found this way, the object
+    /// is searched again, reading every function
+    pub indexed_objects: usize,
+    /// The number of functions which were read
+    pub functions: usize,
+    /// The number of functions (including inlined functions) which had more
+    /// line records than [`ScanLimits::max_lines_per_function`]
+    pub truncated_functions: usize,
+    /// The number of objects which had more functions than
+    /// [`ScanLimits::max_functions_per_object`]
+    pub truncated_objects: usize,
+    /// The number of times a debug info parser panicked on a malformed file,
+    /// after which the rest of the object (or file) was skipped
+    pub parser_panics: usize,
+}
+
+impl ScanStats {
+    /// Whether any limit was exceeded, or a parser panicked, so some debug
+    /// info wasn't processed.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated_functions > 0 || self.truncated_objects > 0 || self.parser_panics > 0
+    }
+}
+
+impl fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read {} function(s) in {} object(s)",
+            self.functions, self.objects
+        )?;
+        if self.truncated_functions > 0 {
+            write!(
+                f,
+                "\n  {} function(s) had more than {} line records, and only the first were kept",
+                self.truncated_functions, self.limits.max_lines_per_function
+            )?;
+        }
+        if self.truncated_objects > 0 {
+            write!(
+                f,
+                "\n  {} object(s) had more than {} functions, and only the first were read",
+                self.truncated_objects, self.limits.max_functions_per_object
+            )?;
+        }
+        if self.parser_panics > 0 {
+            write!(
+                f,
+                "\n  the debug info parser panicked {} time(s) on malformed debug info, \
+                 which was skipped",
+                self.parser_panics
+            )?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    /// The limits of scans on the current thread.
+    static LIMITS: Cell<ScanLimits> = Cell::new(ScanLimits::default());
+    /// The statistics of scans inside [`with_scan_limits`] on the current
+    /// thread, which aren't recorded otherwise.
+    static STATS: RefCell<Option<ScanStats>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `limits` applied to every search of debug info on the
+/// current thread, returning its result and what was processed.
+///
+/// This applies to every function of this crate which reads debug info, such
+/// as [`declaration_by_name_in`](crate::declaration_by_name_in) and
+/// [`DeclarationIndex::from_bytes`](crate::DeclarationIndex::from_bytes).
+/// Outside of this function, [`ScanLimits::default`] is used.
+///
+/// ```rust
+/// use declaration_site::{with_scan_limits, DeclarationIndex, ScanLimits};
+///
+/// let path = std::env::current_exe().unwrap();
+/// let limits = ScanLimits {
+///     max_functions_per_object: 10,
+///     ..ScanLimits::default()
+/// };
+/// let (index, stats) = with_scan_limits(limits, || DeclarationIndex::from_file(&path));
+/// assert!(index.unwrap().len() <= 10 * stats.objects);
+/// if stats.is_truncated() {
+///     println!("{stats}");
+/// }
+/// ```
+pub fn with_scan_limits<R>(limits: ScanLimits, f: impl FnOnce() -> R) -> (R, ScanStats) {
+    let previous_limits = LIMITS.with(|it| it.replace(limits));
+    let previous_stats = STATS.with(|it| {
+        it.replace(Some(ScanStats {
+            limits,
+            ..ScanStats::default()
+        }))
+    });
+    // Restore the previous limits even if `f` panics
+    let guard = LimitsGuard {
+        previous_limits,
+        previous_stats,
+    };
+    let result = f();
+    let stats = STATS.with(|it| it.borrow_mut().take()).unwrap_or_default();
+    drop(guard);
+    (result, stats)
+}
+
+struct LimitsGuard {
+    previous_limits: ScanLimits,
+    previous_stats: Option<ScanStats>,
+}
+
+impl Drop for LimitsGuard {
+    fn drop(&mut self) {
+        LIMITS.with(|it| it.set(self.previous_limits));
+        let previous_stats = self.previous_stats.take();
+        STATS.with(|it| *it.borrow_mut() = previous_stats);
+    }
+}
+
+/// The limits of scans on the current thread.
+pub(crate) fn current() -> ScanLimits {
+    LIMITS.with(Cell::get)
+}
+
+/// Run `f` with the current limits, returning its result and what it
+/// processed, which is also recorded in the statistics of the current
+/// [`with_scan_limits`], if any.
+pub(crate) fn track_stats<R>(f: impl FnOnce() -> R) -> (R, ScanStats) {
+    let (result, stats) = with_scan_limits(current(), f);
+    record(|outer| {
+        outer.objects += stats.objects;
+        outer.indexed_objects += stats.indexed_objects;
+        outer.functions += stats.functions;
+        outer.truncated_functions += stats.truncated_functions;
+        outer.truncated_objects += stats.truncated_objects;
+        outer.parser_panics += stats.parser_panics;
+    });
+    (result, stats)
+}
+
+/// Update the statistics of the current [`with_scan_limits`], if any.
+pub(crate) fn record(update: impl FnOnce(&mut ScanStats)) {
+    STATS.with(|it| {
+        if let Some(stats) = it.borrow_mut().as_mut() {
+            update(stats);
+        }
+    });
+}
+
+/// Run `parse`, which parses debug info using another crate, returning `None`
+/// if it panicked.
+///
+/// The parsers used by `symbolic` can panic on malformed files (rather than
+/// returning an error), such as when an offset in the file is out of bounds.
+/// The panic is still reported by the panic hook, and can't be caught if
+/// panics abort.
+pub(crate) fn catch_parser_panic<T>(parse: impl FnOnce() -> T) -> Option<T> {
+    let result = catch_unwind(AssertUnwindSafe(parse)).ok();
+    if result.is_none() {
+        record(|stats| stats.parser_panics += 1);
+    }
+    result
+}
+
+/// Drop the line records of `function` and its inlined functions beyond
+/// `max_lines`, returning how many functions had any dropped.
+pub(crate) fn truncate_lines(function: &mut Function<'_>, max_lines: usize) -> usize {
+    let mut truncated = usize::from(function.lines.len() > max_lines);
+    function.lines.truncate(max_lines);
+    for inlinee in &mut function.inlinees {
+        truncated += truncate_lines(inlinee, max_lines);
+    }
+    truncated
+}
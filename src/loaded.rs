@@ -0,0 +1,182 @@
+//! Searching a single dynamically loaded library, such as a plugin opened
+//! using [`libloading`](https://docs.rs/libloading), enabled by the
+//! `libloading` feature.
+//!
+//! `libloading::Library` doesn't provide the path it was loaded from, so these
+//! functions take the path which was passed to `Library::new`.
+
+use std::{error::Error, fmt, io, path::Path};
+
+use findshlibs::{SharedLibrary, SharedLibraryId};
+
+use crate::{
+    cancellation, file_access, main_executable_path, object, scan_data, DeclarationIndex,
+    DeclarationSite, FunctionRecord, IterationControl,
+};
+
+/// Run `callback` on each function in the currently loaded library which was
+/// loaded from `lib_path`.
+///
+/// `lib_path` can also be the path of the main executable, such as from
+/// [`current_exe`](std::env::current_exe).
+///
+/// Unlike [`for_functions_in_file`](crate::for_functions_in_file), this checks
+/// that the library is loaded into the current process, and that the file at
+/// `lib_path` is the one which was loaded, by comparing their build ids. This
+/// catches the file being replaced after it was loaded, such as when a plugin
+/// is rebuilt for hot reloading. If either the loaded library or the file has
+/// no build id, the check is skipped.
+///
+/// # Errors
+///
+/// - [`LoadedLibraryError::NotLoaded`] if no library loaded from `lib_path`
+///   was found
+/// - [`LoadedLibraryError::Mismatch`] if the file at `lib_path` is not the one
+///   which was loaded
+/// - [`LoadedLibraryError::Io`] if the file could not be read or parsed, or
+///   with an error of kind [`io::ErrorKind::Interrupted`] if the scan was
+///   cancelled (see [`with_cancellation`](crate::with_cancellation))
+pub fn for_functions_in_loaded_library<C>(
+    lib_path: impl AsRef<Path>,
+    mut callback: impl FnMut(FunctionRecord<'_>) -> C,
+) -> Result<(), LoadedLibraryError>
+where
+    C: Into<IterationControl>,
+{
+    let lib_path = file_access::canonicalize(lib_path.as_ref())?;
+    let mut libraries = Vec::new();
+    // `each` holds the loader's lock, so the paths are only compared after it
+    // returns
+    findshlibs::TargetSharedLibrary::each(|library| {
+        libraries.push((library.name().to_owned(), library.id()));
+    });
+    let loaded = libraries.into_iter().find_map(|(name, id)| {
+        // The main executable is reported without a name on some platforms
+        let path = if name.is_empty() {
+            main_executable_path().ok()?
+        } else {
+            name.into()
+        };
+        (file_access::canonicalize(&path).ok()? == lib_path).then_some(id)
+    });
+    let loaded_id = match loaded {
+        Some(it) => it.and_then(|id| expected_code_id(&id)),
+        None => return Err(LoadedLibraryError::NotLoaded),
+    };
+
+    let data = file_access::read(&lib_path)?;
+    if let Some(loaded_id) = loaded_id {
+        let archive = object::Archive::parse(&data).map_err(invalid_data)?;
+        let file_ids = archive
+            .objects()
+            .filter_map(|object| object.ok()?.code_id())
+            .collect::<Vec<_>>();
+        if !file_ids.is_empty()
+            && !file_ids
+                .iter()
+                .any(|id| id.as_str().eq_ignore_ascii_case(&loaded_id))
+        {
+            return Err(LoadedLibraryError::Mismatch {
+                loaded_id,
+                file_id: file_ids[0].to_string(),
+            });
+        }
+    }
+    let (result, cancelled) = cancellation::track_interruption(|| {
+        scan_data(&data, &mut |object, name, function| {
+            callback(FunctionRecord::new(name, &function).with_object(object)).into()
+        })
+    });
+    if cancelled {
+        return Err(LoadedLibraryError::Io(cancellation::interrupted_error()));
+    }
+    result.map_err(invalid_data)?;
+    Ok(())
+}
+
+/// Build a [`DeclarationIndex`] of the currently loaded library which was
+/// loaded from `lib_path`.
+///
+/// See [`for_functions_in_loaded_library`] for how the library is found and
+/// checked.
+///
+/// # Errors
+///
+/// As in [`for_functions_in_loaded_library`]
+pub fn index_library(lib_path: impl AsRef<Path>) -> Result<DeclarationIndex, LoadedLibraryError> {
+    let mut functions = Vec::new();
+    for_functions_in_loaded_library(lib_path, |function| {
+        if let Ok(site) = DeclarationSite::try_from(&function) {
+            functions.push((function.name().to_owned(), site));
+        }
+    })?;
+    Ok(functions.into_iter().collect())
+}
+
+/// Get the id of a loaded library in the format of
+/// [`CodeId`](symbolic_common::CodeId), if it is one which can be compared.
+fn expected_code_id(id: &SharedLibraryId) -> Option<String> {
+    match id {
+        SharedLibraryId::Uuid(bytes) => Some(hex(bytes)),
+        SharedLibraryId::GnuBuildId(bytes) => Some(hex(bytes)),
+        SharedLibraryId::PeSignature(timestamp, size) => Some(format!("{timestamp:08x}{size:x}")),
+        // This identifies the debug file, rather than the library
+        SharedLibraryId::PdbSignature(..) => None,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn invalid_data(error: object::ObjectError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// An error returned from [`for_functions_in_loaded_library`] and
+/// [`index_library`].
+#[derive(Debug)]
+pub enum LoadedLibraryError {
+    /// No currently loaded library was loaded from the given path
+    NotLoaded,
+    /// The file at the given path is not the library which was loaded from
+    /// it, most likely because it has been rebuilt since being loaded
+    Mismatch {
+        /// The build id of the loaded library
+        loaded_id: String,
+        /// The build id of the file
+        file_id: String,
+    },
+    /// The file could not be read, or is not a supported object file format
+    Io(io::Error),
+}
+
+impl fmt::Display for LoadedLibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadedLibraryError::NotLoaded => {
+                write!(f, "no library is currently loaded from this path")
+            }
+            LoadedLibraryError::Mismatch { loaded_id, file_id } => write!(
+                f,
+                "the file has changed since it was loaded (loaded build id {loaded_id}, file build id {file_id})"
+            ),
+            LoadedLibraryError::Io(error) => write!(f, "could not read library: {error}"),
+        }
+    }
+}
+
+impl Error for LoadedLibraryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadedLibraryError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadedLibraryError {
+    fn from(error: io::Error) -> Self {
+        LoadedLibraryError::Io(error)
+    }
+}
@@ -0,0 +1,99 @@
+//! Scanning the current process with bounded memory use, for lookups from
+//! hooks such as panic hooks, tracing layers and the instrumentation of a
+//! global allocator.
+
+use std::cell::Cell;
+
+thread_local! {
+    /// The largest file read by scans of the current process on the current
+    /// thread, inside [`with_low_allocation_scans`].
+    static MAX_FILE_LEN: Cell<Option<u64>> = const { Cell::new(None) };
+    /// Whether a scan inside the current [`track_skipped_files`] on this
+    /// thread skipped a file larger than [`MAX_FILE_LEN`].
+    static SKIPPED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` with every scan of the current process on the current thread
+/// using as little memory as it can, returning its result.
+///
+/// Lookups such as [`declaration_by_name`](crate::declaration_by_name) read
+/// each loaded library (or its debug companion) into memory in turn, so
+/// their largest allocation is the size of the largest file. Inside this
+/// function:
+///
+/// - Files larger than `max_file_len` bytes are skipped without being read,
+///   so functions in them aren't found.
+/// - The binaries of libraries with debug companions aren't read to check
+///   that they match (as [`DebugPairing::Unverified`](crate::DebugPairing::Unverified)),
+///   so only one file is held at a time.
+///
+/// This is suited to looking up functions from hooks, where an allocation
+/// failure or a long pause is worse than a missed lookup. Lookups started
+/// from inside another scan on the same thread (such as from a panic hook
+/// run by a panic in a callback of
+/// [`for_each_loaded_function`](crate::for_each_loaded_function)) don't
+/// search anything, with or without this function, and
+/// [`try_declaration_by_name`](crate::try_declaration_by_name) reports them
+/// as [`DeclarationError::Reentrant`](crate::DeclarationError::Reentrant).
+///
+/// ```rust
+/// use declaration_site::{try_declaration_by_name, with_low_allocation_scans, DeclarationError};
+///
+/// // No loaded library is this small, so nothing is searched
+/// let result = with_low_allocation_scans(64, || try_declaration_by_name("my_crate::my_function"));
+/// assert!(matches!(result, Err(DeclarationError::NoSearchTargets { .. })));
+/// ```
+pub fn with_low_allocation_scans<R>(max_file_len: u64, f: impl FnOnce() -> R) -> R {
+    let previous = MAX_FILE_LEN.with(|it| it.replace(Some(max_file_len)));
+    // Restore the previous limit even if `f` panics
+    let _guard = LowAllocationGuard { previous };
+    f()
+}
+
+struct LowAllocationGuard {
+    previous: Option<u64>,
+}
+
+impl Drop for LowAllocationGuard {
+    fn drop(&mut self) {
+        MAX_FILE_LEN.with(|it| it.set(self.previous));
+    }
+}
+
+/// Whether the current thread is inside [`with_low_allocation_scans`].
+pub(crate) fn is_enabled() -> bool {
+    max_file_len().is_some()
+}
+
+/// The largest file read by scans of the current process on the current
+/// thread, if limited.
+pub(crate) fn max_file_len() -> Option<u64> {
+    MAX_FILE_LEN.with(Cell::get)
+}
+
+/// Record that a scan skipped a file larger than [`max_file_len`].
+pub(crate) fn record_skipped_file() {
+    SKIPPED.with(|it| it.set(true));
+}
+
+/// Run `f`, returning its result and whether a scan inside it skipped a file
+/// larger than [`max_file_len`], so may have missed functions.
+pub(crate) fn track_skipped_files<R>(f: impl FnOnce() -> R) -> (R, bool) {
+    let previous = SKIPPED.with(|it| it.replace(false));
+    // Keep the skip recorded for the enclosing call even if `f` panics
+    let guard = SkippedGuard { previous };
+    let result = f();
+    let skipped = SKIPPED.with(Cell::get);
+    drop(guard);
+    (result, skipped)
+}
+
+struct SkippedGuard {
+    previous: bool,
+}
+
+impl Drop for SkippedGuard {
+    fn drop(&mut self) {
+        SKIPPED.with(|it| it.set(it.get() || self.previous));
+    }
+}
@@ -0,0 +1,80 @@
+//! Choosing between the macro and the macro invocation as the declaration
+//! site of macro-generated functions, and recording where the line of a
+//! declaration site came from.
+
+use std::path::Path;
+
+use crate::{DeclarationSite, DeclarationSiteError, FunctionRecord, LineProvenance};
+
+/// Which site to report for a function generated by a macro, for
+/// [`FunctionRecord::declaration_site`] and
+/// [`DeclarationSearch::macro_site`](crate::DeclarationSearch::macro_site).
+///
+/// The debug info of a function generated by a `macro_rules!` macro from the
+/// same crate points into the macro's definition, whereas the code generated
+/// by derive macros and macros from other crates is usually attributed to the
+/// invocation already.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MacroSite {
+    /// The first line of the function in the debug info, which is inside the
+    /// macro's definition for functions generated by local macros
+    #[default]
+    Expansion,
+    /// The first line of the function in the primary source file of its
+    /// compilation unit (the crate root for units compiled by rustc), falling
+    /// back to [`Expansion`](Self::Expansion) if there is none.
+    ///
+    /// This is a heuristic: the debug info doesn't record where a macro was
+    /// invoked, so this relies on part of the function's code (such as a
+    /// block passed to the macro) having come from the invocation. Invocations
+    /// in files other than the crate root are not found.
+    Invocation,
+}
+
+/// The declaration site of `function`, as chosen by `macro_site`, and where
+/// its line came from.
+pub(crate) fn declaration_site(
+    function: &FunctionRecord<'_>,
+    macro_site: MacroSite,
+) -> Result<(DeclarationSite, LineProvenance), DeclarationSiteError> {
+    if macro_site == MacroSite::Invocation {
+        if let Some(site) = invocation_site(function) {
+            return Ok((site, LineProvenance::Heuristic));
+        }
+    }
+    DeclarationSite::try_from(function).map(|site| (site, LineProvenance::LineTableFirst))
+}
+
+/// The primary source file of the compilation unit of `function`, which is
+/// the crate root for units compiled by rustc.
+pub(crate) fn primary_file<'a>(function: &FunctionRecord<'a>) -> Option<&'a str> {
+    let unit_name = function.compilation_unit()?.name()?;
+    // Units compiled by rustc are named like `src/lib.rs/@/my_crate.a1b2c3-cgu.0`
+    let primary = unit_name
+        .split_once("/@/")
+        .map_or(unit_name, |(file, _)| file);
+    (!primary.is_empty()).then_some(primary)
+}
+
+/// The files of the lines of `function` other than `file`, in the order they
+/// first appear.
+pub(crate) fn other_files(function: &FunctionRecord<'_>, file: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    for line in function.lines() {
+        if line.file != file && !files.contains(&line.file) {
+            files.push(line.file);
+        }
+    }
+    files
+}
+
+fn invocation_site(function: &FunctionRecord<'_>) -> Option<DeclarationSite> {
+    let primary = primary_file(function)?;
+    function
+        .lines()
+        .find(|line| line.line != 0 && Path::new(&line.file).ends_with(primary))
+        .map(|line| DeclarationSite {
+            file: line.file,
+            line: line.line,
+        })
+}
@@ -0,0 +1,138 @@
+//! Reporting declaration sites as [`miette`] diagnostics, enabled by
+//! the `miette` feature.
+
+use std::{error::Error, fmt, path::Path};
+
+use ::miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode, SourceSpan};
+
+use crate::{file_access, redaction, DeclarationSite, EmbeddedSources};
+
+/// Get the span of the `line`th (1-based) line of `source`, excluding the line
+/// terminator and any indentation.
+///
+/// Returns `None` if `source` has fewer than `line` lines.
+///
+/// The span is in bytes, as miette expects, so always starts and ends on a
+/// character boundary:
+///
+/// ```rust
+/// # use declaration_site::miette::line_span;
+/// let source = "// 😀 émoji\r\n\u{3000}fn 関数() {} // 名前\r\nfn main() {}";
+/// let span = line_span(source, 2).unwrap();
+/// let text = &source[span.offset()..span.offset() + span.len()];
+/// assert_eq!(text, "fn 関数() {} // 名前");
+/// assert_eq!(line_span(source, 4), None);
+/// ```
+pub fn line_span(source: &str, line: u32) -> Option<SourceSpan> {
+    let index = usize::try_from(line).ok()?.checked_sub(1)?;
+    let mut offset = 0;
+    for (i, text) in source.split_inclusive('\n').enumerate() {
+        if i == index {
+            let content = text.trim_end_matches(['\n', '\r']);
+            let indent = content.len() - content.trim_start().len();
+            return Some(SourceSpan::new(
+                (offset + indent).into(),
+                content.len() - indent,
+            ));
+        }
+        offset += text.len();
+    }
+    None
+}
+
+/// Create a diagnostic with the message `message`, labelling the line of
+/// `site` in its source file.
+///
+/// The source file is read when this is called. If it cannot be read (for
+/// example, if this is not being run on the machine the code was compiled on),
+/// or doesn't contain the line, the diagnostic has no source snippet, and
+/// instead includes the declaration site in its help text.
+pub fn declaration_diagnostic(
+    site: &DeclarationSite,
+    message: impl Into<String>,
+) -> DeclarationDiagnostic {
+    diagnostic_with_source(
+        site,
+        message,
+        file_access::read_to_string(Path::new(&site.file)).ok(),
+    )
+}
+
+/// Create a diagnostic as in [`declaration_diagnostic`], using the sources
+/// embedded in `sources` if the source file cannot be read.
+pub fn declaration_diagnostic_with_sources(
+    site: &DeclarationSite,
+    message: impl Into<String>,
+    sources: &EmbeddedSources,
+) -> DeclarationDiagnostic {
+    diagnostic_with_source(site, message, sources.read_source(site))
+}
+
+fn diagnostic_with_source(
+    site: &DeclarationSite,
+    message: impl Into<String>,
+    source: Option<String>,
+) -> DeclarationDiagnostic {
+    let snippet = source.and_then(|source| {
+        let span = line_span(&source, site.line)?;
+        Some((NamedSource::new(redaction::path(&site.file), source), span))
+    });
+    DeclarationDiagnostic {
+        message: message.into(),
+        site: redaction::site(site.clone()),
+        snippet,
+    }
+}
+
+/// A diagnostic pointing at a declaration site, created using
+/// [`declaration_diagnostic`].
+#[derive(Debug)]
+pub struct DeclarationDiagnostic {
+    message: String,
+    site: DeclarationSite,
+    snippet: Option<(NamedSource<String>, SourceSpan)>,
+}
+
+impl DeclarationDiagnostic {
+    /// The declaration site this diagnostic points at.
+    pub fn site(&self) -> &DeclarationSite {
+        &self.site
+    }
+
+    /// Whether the source file could be read, so that this diagnostic will
+    /// include a snippet of the declaration.
+    pub fn has_snippet(&self) -> bool {
+        self.snippet.is_some()
+    }
+}
+
+impl fmt::Display for DeclarationDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for DeclarationDiagnostic {}
+
+impl Diagnostic for DeclarationDiagnostic {
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        if self.snippet.is_some() {
+            None
+        } else {
+            Some(Box::new(format!("declared at {}", self.site)))
+        }
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.snippet
+            .as_ref()
+            .map(|(source, _)| source as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (_, span) = self.snippet.as_ref()?;
+        Some(Box::new(std::iter::once(
+            LabeledSpan::new_primary_with_span(Some("declared here".into()), *span),
+        )))
+    }
+}
@@ -0,0 +1,148 @@
+//! Arranging functions into a tree of crates and modules.
+
+use std::{collections::BTreeMap, io};
+
+use crate::{for_functions_in_target, names, redaction, DeclarationSite, ScanTarget};
+
+/// Arrange every function in `target` into a tree, by the segments of their
+/// paths.
+///
+/// The root of the tree is unnamed, and its children are crates. Below these
+/// are modules, types and functions. Closures are children of the function
+/// they are declared in, and methods in trait impls (such as
+/// `<my_crate::Player as core::fmt::Debug>::fmt`) are placed in the module
+/// of their type, below a node for the impl (`<my_crate::Player as
+/// core::fmt::Debug>`).
+///
+/// # Errors
+///
+/// If `target` is a file which could not be read or parsed
+pub fn module_tree(target: ScanTarget<'_>) -> io::Result<ModuleTree> {
+    let mut root = Builder::default();
+    for_functions_in_target(target, |function| {
+        let name = names::canonical_name(function.name());
+        let node = tree_path(&name)
+            .into_iter()
+            .fold(&mut root, |node, segment| {
+                node.children.entry(segment.to_owned()).or_default()
+            });
+        node.is_function = true;
+        node.own_size += function.size();
+        if node.site.is_none() {
+            node.site = DeclarationSite::try_from(&function)
+                .ok()
+                .map(redaction::site);
+        }
+    })?;
+    Ok(root.build(String::new()))
+}
+
+/// The segments of the path of the node for the function `name`.
+fn tree_path(name: &str) -> Vec<&str> {
+    let segments = names::path_segments(name);
+    let first = segments[0];
+    let self_type = first
+        .strip_prefix('<')
+        .and_then(|it| it.strip_suffix('>'))
+        .map(|it| it.split_once(" as ").map_or(it, |(self_type, _)| self_type));
+    match self_type {
+        // Place the impl in the module containing the type
+        Some(self_type) => {
+            let mut module = names::path_segments(self_type);
+            module.pop();
+            module.extend(segments);
+            module
+        }
+        None => segments,
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    children: BTreeMap<String, Builder>,
+    is_function: bool,
+    own_size: u64,
+    site: Option<DeclarationSite>,
+}
+
+impl Builder {
+    fn build(self, name: String) -> ModuleTree {
+        let children = self
+            .children
+            .into_iter()
+            .map(|(name, child)| child.build(name))
+            .collect::<Vec<_>>();
+        ModuleTree {
+            name,
+            size: self.own_size + children.iter().map(|it| it.size).sum::<u64>(),
+            is_function: self.is_function,
+            site: self.site,
+            children,
+        }
+    }
+}
+
+/// A node in the tree created by [`module_tree`], which is a crate, module,
+/// type, impl or function.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleTree {
+    /// The last segment of this node's path, such as `my_module` or
+    /// `{closure}`. This is empty for the root
+    pub name: String,
+    /// The total size of the code of this node's function (if any) and all
+    /// functions below it, in bytes
+    pub size: u64,
+    /// Whether a function has this node's path. Functions can have children,
+    /// such as closures declared in them
+    pub is_function: bool,
+    /// The declaration site of this node's function, if known
+    pub site: Option<DeclarationSite>,
+    /// The nodes below this one, sorted by name
+    pub children: Vec<ModuleTree>,
+}
+
+impl ModuleTree {
+    /// Write this tree as a [Graphviz](https://graphviz.org/) `dot` graph,
+    /// with an edge from each node to its children.
+    ///
+    /// Nodes are labelled with their name and size. Functions with a known
+    /// declaration site also include it in their label.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `writer` fails
+    pub fn write_dot(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "digraph module_tree {{")?;
+        writeln!(writer, "    node [shape=box];")?;
+        self.write_dot_node(&mut writer, &mut 0)?;
+        writeln!(writer, "}}")
+    }
+
+    /// Write this node and its children, returning the id of this node.
+    fn write_dot_node(
+        &self,
+        writer: &mut impl io::Write,
+        next_id: &mut usize,
+    ) -> io::Result<usize> {
+        let id = *next_id;
+        *next_id += 1;
+        let mut label = format!("{}\n{} bytes", self.name, self.size);
+        if let Some(site) = &self.site {
+            label.push_str(&format!("\n{site}"));
+        }
+        writeln!(writer, "    n{id} [label=\"{}\"];", escape_dot(&label))?;
+        for child in &self.children {
+            let child_id = child.write_dot_node(writer, next_id)?;
+            writeln!(writer, "    n{id} -> n{child_id};")?;
+        }
+        Ok(id)
+    }
+}
+
+/// Escape `text` for use in a quoted `dot` string.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
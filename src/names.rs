@@ -0,0 +1,1994 @@
+//! Helpers for inspecting the names of functions, as printed by
+//! [`core::any::type_name`] and by the demangler.
+
+use std::{borrow::Cow, fmt};
+
+#[cfg(feature = "process")]
+use crate::MatchRelaxation;
+
+/// Split a path into its top level `::` separated segments.
+///
+/// Separators nested inside generic arguments (`<...>`), parentheses or
+/// brackets are not split on, so `alloc::vec::Vec<alloc::string::String>`
+/// has three segments.
+pub(crate) fn path_segments(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = path.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' | b'(' | b'[' => depth += 1,
+            // `->` in a fn pointer type is not a closing bracket
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' | b')' | b']' => depth = depth.saturating_sub(1),
+            b':' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                segments.push(&path[start..i]);
+                i += 2;
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    segments.push(&path[start..]);
+    segments
+}
+
+/// The identifier of a path segment, without any generic arguments.
+pub(crate) fn segment_ident(segment: &str) -> &str {
+    match segment.find('<') {
+        // A segment which starts with `<` is a qualified path such as
+        // `<Type as Trait>`, which is its own identifier.
+        Some(0) | None => segment,
+        Some(index) => &segment[..index],
+    }
+}
+
+/// The identifier of the last segment of a path, such as `baz` for
+/// `<my_crate::Foo as my_crate::Bar>::baz<u8>`.
+pub(crate) fn last_ident(path: &str) -> &str {
+    path_segments(path)
+        .last()
+        .map_or(path, |segment| segment_ident(segment))
+}
+
+/// Remove a trailing legacy mangling hash (`::h` followed by 16 hex digits)
+/// from a demangled name.
+///
+/// The demangler normally removes this when only demangling the name, but
+/// it can be left behind, such as when demangling partially fails.
+pub(crate) fn strip_hash_suffix(name: &str) -> &str {
+    const HASH_LEN: usize = 16;
+    let index = match name.len().checked_sub(HASH_LEN + 3) {
+        Some(it) => it,
+        None => return name,
+    };
+    if !name.is_char_boundary(index) {
+        return name;
+    }
+    let (path, suffix) = name.split_at(index);
+    match suffix.strip_prefix("::h") {
+        Some(hash) if hash.bytes().all(|b| b.is_ascii_hexdigit()) => path,
+        _ => name,
+    }
+}
+
+/// How the demangled name of a function matched the name being searched for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum NameMatch {
+    /// The names are the same, once canonicalized.
+    Exact,
+    /// The names are the same, except for segments such as `{closure}` which
+    /// don't have a numeric disambiguator in one of the names. Several
+    /// functions, such as the closures in a function, can match like this.
+    Wildcard,
+    /// The names are different spellings of the same trait method, where one
+    /// is written as `<Type as Trait>::method` and the other as
+    /// `Type::method`.
+    Spelling,
+    /// The name of a function from a PDB only matches the end of the name,
+    /// as PDBs don't name the type and trait of trait impl methods. See
+    /// [`match_pdb_names`](crate::pdb_names::match_pdb_names).
+    Suffix,
+    /// The function is the body of the future generated for the `async fn`
+    /// being searched for. See [`match_future_body`].
+    FutureBody,
+    /// The function is the default body of the trait method being searched
+    /// for. See [`match_default_method`].
+    DefaultImpl,
+    /// The function's name isn't mangled, such as that of a `#[no_mangle]`
+    /// function, and is the last segment of the name being searched for. See
+    /// [`match_unmangled`].
+    Unmangled,
+    /// The function's name has generic parameters where the name being
+    /// searched for has generic arguments, such as the legacy demangled
+    /// `my_crate::Foo<T>::bar` for `my_crate::Foo<u32>::bar<u64>`. See
+    /// [`match_generic_form`].
+    GenericForm,
+}
+
+/// How the demangled name of a function matches the name being searched for,
+/// if at all. See [`names_match`].
+pub(crate) fn match_names(demangled_name: &str, query: &str) -> Option<NameMatch> {
+    let demangled_name = canonical_name(demangled_name);
+    let query = canonical_name(query);
+    if let Some(exact) = compare_paths(&demangled_name, &query) {
+        Some(if exact {
+            NameMatch::Exact
+        } else {
+            NameMatch::Wildcard
+        })
+    } else if is_inherent_spelling(&demangled_name, &query)
+        || is_inherent_spelling(&query, &demangled_name)
+    {
+        Some(NameMatch::Spelling)
+    } else {
+        match_generic_form(&demangled_name, &query)
+    }
+}
+
+/// Whether the canonical demangled name of a function is the generic form of
+/// the canonical `query`, as legacy mangled names are demangled.
+///
+/// Legacy demangled names print the generic parameters of an impl's type
+/// rather than its arguments (`my_crate::Foo<T>::bar`), and don't print the
+/// generic arguments of the function itself. A segment whose generic
+/// arguments are all parameters matches any arguments of the same number,
+/// and a last segment without arguments matches any arguments. Parameters
+/// are told apart from types by starting with an uppercase letter, as types
+/// are printed with their full paths.
+///
+/// Which of several instantiations the function is can't be told from its
+/// name, so this is never exact.
+pub(crate) fn match_generic_form(demangled_name: &str, query: &str) -> Option<NameMatch> {
+    generic_form(demangled_name, query, true)?.then_some(NameMatch::GenericForm)
+}
+
+/// A key which is the same for every name that [`match_generic_form`] could
+/// match, so that candidates can be found by hashing rather than by comparing
+/// against every name with the same last identifier.
+///
+/// Generic arguments are erased from every segment (including those of
+/// compiler generated segments such as `{closure}`), and numeric
+/// disambiguators are removed as in [`wildcard_key`].
+pub(crate) fn generic_form_key(name: &str) -> Cow<'_, str> {
+    if !name.contains('<') {
+        return wildcard_key(name);
+    }
+    let erased = erase_arguments_after(name, |c| c.is_alphanumeric() || c == '_' || c == '}');
+    Cow::Owned(wildcard_key(&erased).into_owned())
+}
+
+/// Whether `generic` matches `path` as in [`match_generic_form`], and whether
+/// it used any generic parameters to do so. `None` if it doesn't match.
+fn generic_form(generic: &str, path: &str, is_function: bool) -> Option<bool> {
+    if let (Some(generic), Some(path)) = (split_qualified(generic), split_qualified(path)) {
+        let (generic_type, generic_trait, generic_rest) = generic;
+        let (self_type, trait_, rest) = path;
+        let used = [
+            generic_form(generic_type, self_type, false)?,
+            generic_form(generic_trait, trait_, false)?,
+            generic_form(generic_rest, rest, is_function)?,
+        ];
+        return Some(used.contains(&true));
+    }
+    let generic = path_segments(generic);
+    let path = path_segments(path);
+    if generic.len() != path.len() {
+        return None;
+    }
+    let mut used = false;
+    for (index, (generic, segment)) in generic.iter().zip(&path).enumerate() {
+        if compare_paths(generic, segment).is_some() {
+            continue;
+        }
+        if generic.starts_with('<') || segment_ident(generic) != segment_ident(segment) {
+            return None;
+        }
+        fn arguments(segment: &str) -> Option<Vec<&str>> {
+            let (arguments, _) = generic_arguments(&segment[segment.find('<')? + 1..])?;
+            Some(arguments)
+        }
+        match (arguments(generic), arguments(segment)) {
+            (None, Some(_)) if is_function && index == path.len() - 1 => {}
+            (Some(parameters), Some(arguments))
+                if parameters.len() == arguments.len()
+                    && parameters.iter().all(|it| is_generic_parameter(it)) => {}
+            _ => return None,
+        }
+        used = true;
+    }
+    Some(used)
+}
+
+/// Whether a generic argument in a demangled name is a generic parameter,
+/// such as `T`, rather than a type, which would be printed with its path.
+fn is_generic_parameter(argument: &str) -> bool {
+    argument.starts_with(|c: char| c.is_ascii_uppercase())
+        && argument.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// The key of `name` for relaxed matching (see [`match_relaxed`]): its
+/// canonical name, spelled `Type::method` rather than `<Type as
+/// Trait>::method`, without generic arguments.
+#[cfg(feature = "process")]
+pub(crate) fn relaxed_key(name: &str) -> String {
+    let name = canonical_name(name);
+    let name = inherent_spelling(&name).map_or(name, Cow::Owned);
+    erase_generic_arguments(&name)
+}
+
+/// `name` without the generic arguments of any of its segments, including
+/// those of types in qualified segments such as `<Type<u8> as Trait<u16>>`.
+fn erase_generic_arguments(name: &str) -> String {
+    erase_arguments_after(name, |c| c.is_alphanumeric() || c == '_')
+}
+
+/// `name` without the generic arguments which directly follow a character
+/// matching `is_before`.
+fn erase_arguments_after(name: &str, is_before: impl Fn(char) -> bool) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' && result.ends_with(&is_before) {
+            if let Some((_, len)) = generic_arguments(&rest[1..]) {
+                rest = &rest[1 + len..];
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+/// The least relaxation with which the [`relaxed_key`]s of a demangled name
+/// and the name being searched for match, if it is at most `relaxation`.
+#[cfg(feature = "process")]
+pub(crate) fn match_relaxed(
+    demangled_key: &str,
+    query_key: &str,
+    relaxation: MatchRelaxation,
+) -> Option<MatchRelaxation> {
+    if relaxation == MatchRelaxation::None || last_ident(demangled_key) != last_ident(query_key) {
+        return None;
+    }
+    if compare_paths(demangled_key, query_key).is_some() {
+        return Some(MatchRelaxation::Conservative);
+    }
+    if relaxation < MatchRelaxation::Aggressive {
+        return None;
+    }
+    let (a, b) = (path_segments(demangled_key), path_segments(query_key));
+    let (longer, shorter) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let suffix = longer[longer.len() - shorter.len()..].join("::");
+    (shorter.len() >= 2 && compare_paths(&suffix, &shorter.join("::")).is_some())
+        .then_some(MatchRelaxation::Aggressive)
+}
+
+/// Whether the demangled name of a function is the body of the future which
+/// rustc generates for the `async fn` named `query`, such as
+/// `my_crate::fetch_data::{async_fn_env#0}`, or the `poll` method of its
+/// state machine, such as
+/// `<my_crate::fetch_data::{closure#0} as core::future::future::Future>::poll`.
+///
+/// The body of an `async fn` is usually named like a closure in the function,
+/// which can't be told apart from other closures, so isn't matched.
+pub(crate) fn match_future_body(demangled_name: &str, query: &str) -> Option<NameMatch> {
+    let demangled_name = canonical_name(demangled_name);
+    let query = canonical_name(query);
+    let parent = generated_future_parent(&demangled_name)?;
+    compare_paths(parent, &query).map(|_| NameMatch::FutureBody)
+}
+
+/// Whether the demangled name of a function is the default body of the trait
+/// method named `query`, such as `my_crate::Trait::method` for
+/// `<my_crate::Type as my_crate::Trait>::method`.
+///
+/// Legacy demangled names of default method bodies don't include the type
+/// they were instantiated for, so `type_name` of a method which wasn't
+/// overridden only matches like this.
+pub(crate) fn match_default_method(demangled_name: &str, query: &str) -> Option<NameMatch> {
+    if !query.starts_with('<') {
+        return None;
+    }
+    let default_method = default_method_name(&canonical_name(query))?;
+    let demangled_name = canonical_name(demangled_name);
+    compare_paths(&demangled_name, &default_method).map(|_| NameMatch::DefaultImpl)
+}
+
+/// The name of the default body of the trait method `<Type as Trait>::method`
+/// (`Trait::method`), if `name` is spelled like that.
+pub(crate) fn default_method_name(name: &str) -> Option<String> {
+    let (_, trait_, rest) = split_qualified(name)?;
+    Some(format!("{trait_}::{rest}"))
+}
+
+/// Whether `name`, a function name which couldn't be demangled, is the last
+/// segment of the name being searched for, as for `#[no_mangle]` functions
+/// such as `my_ffi_entry` for `my_crate::my_ffi_entry` (or for the symbol
+/// `my_ffi_entry` itself).
+///
+/// Symbols of Mach-O files have an extra leading underscore (`_my_ffi_entry`),
+/// so exactly one is also ignored if `platform_underscore` is set. This never
+/// applies to Rust mangled names (see [`is_rust_mangled`]), whose leading
+/// underscores are part of the mangling.
+pub(crate) fn match_unmangled(
+    name: &str,
+    query: &str,
+    platform_underscore: bool,
+) -> Option<NameMatch> {
+    if is_rust_mangled(name) || name.is_empty() || name.contains(':') || query.starts_with('<') {
+        return None;
+    }
+    let query = canonical_name(query);
+    let segments = path_segments(&query);
+    let last = *segments.last()?;
+    // Unmangled functions can't be generic
+    if segment_ident(last) != last {
+        return None;
+    }
+    let matches = name == last || (platform_underscore && name.strip_prefix('_') == Some(last));
+    matches.then_some(NameMatch::Unmangled)
+}
+
+/// Whether `name` is a Rust mangled symbol, in the legacy (`_ZN`) or v0
+/// (`_R`) scheme, including with the extra leading underscore of Mach-O
+/// symbols (`__ZN` or `__R`).
+pub(crate) fn is_rust_mangled(name: &str) -> bool {
+    let rest = match name.strip_prefix("__") {
+        Some(rest) => rest,
+        None => match name.strip_prefix('_') {
+            Some(rest) => rest,
+            None => return false,
+        },
+    };
+    rest.starts_with("ZN")
+        || rest.strip_prefix('R').is_some_and(|rest| {
+            rest.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit())
+        })
+}
+
+/// The function whose generated future is named `name`, as described in
+/// [`match_future_body`].
+pub(crate) fn generated_future_parent(name: &str) -> Option<&str> {
+    let (state_machine, kinds) = match split_qualified(name) {
+        Some((self_type, "core::future::future::Future", "poll")) => {
+            (self_type, &["async_fn_env", "closure"][..])
+        }
+        Some(_) => return None,
+        None => (name, &["async_fn_env"][..]),
+    };
+    let (parent, segment) = state_machine.rsplit_once("::")?;
+    match brace_segment(segment) {
+        Some((kind, _, len)) if len == segment.len() && kinds.contains(&kind) => Some(parent),
+        _ => None,
+    }
+}
+
+/// Whether the demangled name of a function matches the name being searched
+/// for, as in [`declaration_by_name_in`](crate::declaration_by_name_in).
+///
+/// Both names are compared after [`canonical_name`]. Methods of trait impls
+/// also match when one name is spelled `<Type as Trait>::method` and the
+/// other `Type::method`, as the demangler always uses the former, but
+/// [`type_name`](core::any::type_name) has used both. Segments such as
+/// `{closure}` without a numeric disambiguator match the same segment with
+/// any disambiguator, so `my_crate::main::{{closure}}` matches
+/// `my_crate::main::{closure#1}`.
+///
+/// Legacy demangled names of generic functions print the type's generic
+/// parameters and not the function's generic arguments, so
+/// `my_crate::Foo<T>::bar` matches `my_crate::Foo<u32>::bar<u64>`.
+///
+/// ```rust
+/// # use declaration_site::names_match;
+/// // `type_name` of trait impl methods and their closures, as printed by
+/// // rustc 1.95.0 and 1.97.0-nightly
+/// let recorded = [
+///     "<my_crate::Foo<alloc::vec::Vec<u8>> as my_crate::Bar>::baz",
+///     "<alloc::vec::Vec<u8> as core::clone::Clone>::clone",
+///     "<my_crate::Foo<u8> as my_crate::Bar>::baz::{{closure}}",
+/// ];
+/// // The same functions with the `Type::method` spelling
+/// let inherent = [
+///     "my_crate::Foo<alloc::vec::Vec<u8>>::baz",
+///     "alloc::vec::Vec<u8>::clone",
+///     "my_crate::Foo<u8>::baz::{{closure}}",
+/// ];
+/// for (recorded, inherent) in recorded.into_iter().zip(inherent) {
+///     // Demangled names use the `<Type as Trait>` spelling
+///     assert!(names_match(recorded, recorded));
+///     assert!(names_match(recorded, inherent));
+///     assert!(names_match(inherent, recorded));
+/// }
+///
+/// // Nested generics and qualified paths inside the angle brackets are
+/// // parsed by segment
+/// assert!(names_match(
+///     "<<my_crate::Foo as my_crate::Bar>::Output as core::ops::Fn<(u8,)>>::call",
+///     "<my_crate::Foo as my_crate::Bar>::Output::call",
+/// ));
+/// assert!(names_match(
+///     "<my_crate::Foo<fn() -> u8, [u8; 4]> as my_crate::Bar>::baz",
+///     "my_crate::Foo<fn() -> u8, [u8; 4]>::baz",
+/// ));
+///
+/// assert!(!names_match(
+///     "<my_crate::Foo as my_crate::Bar>::baz",
+///     "my_crate::Foo::qux",
+/// ));
+/// assert!(!names_match(
+///     "<my_crate::Foo as my_crate::Bar>::baz",
+///     "my_crate::Bar::baz",
+/// ));
+///
+/// // Closures match with any spelling, and with any disambiguator if one of
+/// // the names doesn't have one
+/// assert!(names_match("my_crate::main::{closure#1}", "my_crate::main::{{closure}}"));
+/// assert!(names_match("my_crate::main::{{closure}}", "my_crate::main::{closure#1}"));
+/// assert!(names_match("my_crate::main::{closure#1}", "my_crate::main::{closure#1}"));
+/// assert!(!names_match("my_crate::main::{closure#1}", "my_crate::main::{closure#0}"));
+/// assert!(!names_match("my_crate::main::{closure#0}", "my_crate::main::{shim#0}"));
+/// assert!(names_match(
+///     "<my_crate::Foo as my_crate::Bar>::baz::{closure#0}",
+///     "my_crate::Foo::baz::{{closure}}",
+/// ));
+///
+/// // `type_name` of generic inherent methods, and their legacy and v0
+/// // demangled names, as printed by rustc 1.95.0 and 1.97.0-nightly
+/// let generic_methods = [
+///     (
+///         "my_crate::Wrapper<u32>::get",
+///         "my_crate::Wrapper<T>::get",
+///         "<my_crate::Wrapper<u32>>::get",
+///     ),
+///     (
+///         "my_crate::Wrapper<u32>::map<u64>",
+///         "my_crate::Wrapper<T>::map",
+///         "<my_crate::Wrapper<u32>>::map::<u64>",
+///     ),
+///     (
+///         "my_crate::inner::Pair<u8, alloc::vec::Vec<u16>>::first",
+///         "my_crate::inner::Pair<A,B>::first",
+///         "<my_crate::inner::Pair<u8, alloc::vec::Vec<u16>>>::first",
+///     ),
+/// ];
+/// for (query, legacy, v0) in generic_methods {
+///     assert!(names_match(legacy, query));
+///     assert!(names_match(v0, query));
+/// }
+/// // Paths of primitives and std re-exports are equivalent
+/// assert!(names_match(
+///     "<my_crate::Wrapper<u32>>::map::<alloc::string::String>",
+///     "my_crate::Wrapper<core::primitive::u32>::map<std::string::String>",
+/// ));
+/// // Concrete arguments must be the same
+/// assert!(!names_match("<my_crate::Wrapper<u32>>::get", "my_crate::Wrapper<u64>::get"));
+/// assert!(!names_match("my_crate::Wrapper<u32>::get", "my_crate::Wrapper<u64>::get"));
+/// assert!(!names_match("my_crate::inner::Pair<A,B>::first", "my_crate::inner::Pair<u8>::first"));
+/// assert!(!names_match("my_crate::Wrapper<T>::get", "my_crate::Wrapper<u32>::set"));
+/// // Trait objects match with or without auto traits, in any order
+/// assert!(names_match(
+///     "<my_crate::W<dyn core::error::Error + core::marker::Sync + core::marker::Send>>::show",
+///     "my_crate::W<dyn core::error::Error + core::marker::Send + core::marker::Sync>::show",
+/// ));
+/// assert!(names_match(
+///     "<my_crate::W<dyn core::fmt::Debug>>::show",
+///     "my_crate::W<dyn core::fmt::Debug + core::marker::Send + 'static>::show",
+/// ));
+/// assert!(!names_match(
+///     "<my_crate::W<dyn core::fmt::Debug>>::show",
+///     "my_crate::W<dyn core::fmt::Display>::show",
+/// ));
+/// // Opaque types match any type
+/// assert!(names_match(
+///     "my_crate::apply::<&my_crate::make::{closure#0}>",
+///     "my_crate::apply<impl core::ops::function::Fn(u8) -> u8>",
+/// ));
+/// assert!(names_match(
+///     "my_crate::apply::<&my_crate::make::{closure#0}, u8>",
+///     "my_crate::apply<my_crate::make::{opaque#0}, u8>",
+/// ));
+/// assert!(!names_match(
+///     "my_crate::apply::<&my_crate::make::{closure#0}, u8>",
+///     "my_crate::apply<impl core::fmt::Debug, u16>",
+/// ));
+/// // Only the function's own generic arguments are left out
+/// assert!(!names_match("my_crate::Wrapper::get", "my_crate::Wrapper<u32>::get"));
+/// ```
+pub fn names_match(demangled_name: &str, query: &str) -> bool {
+    match_names(demangled_name, query).is_some()
+}
+
+/// Whether `inherent` is `qualified` with its leading `<Type as Trait>`
+/// segment replaced with `Type`.
+fn is_inherent_spelling(qualified: &str, inherent: &str) -> bool {
+    split_qualified(qualified).is_some_and(|(self_type, _, rest)| {
+        inherent
+            .strip_prefix(self_type)
+            .and_then(|it| it.strip_prefix("::"))
+            .is_some_and(|it| compare_paths(it, rest).is_some())
+    })
+}
+
+/// The crate containing the function `name`: the first segment of its path,
+/// or of its type for qualified paths such as
+/// `<my_crate::Foo as core::fmt::Debug>::fmt`. Names of a single segment,
+/// such as the symbols of `#[no_mangle]` functions, have no crate.
+pub(crate) fn crate_name(name: &str) -> Option<&str> {
+    if let Some((self_type, _, _)) = split_qualified(name) {
+        let self_type = self_type.trim_start_matches(['&', '*']);
+        let self_type = ["mut ", "const ", "dyn "]
+            .iter()
+            .fold(self_type, |it, prefix| {
+                it.strip_prefix(prefix).unwrap_or(it)
+            });
+        return crate_name(self_type);
+    }
+    let segments = path_segments(name);
+    if segments.len() < 2 {
+        return None;
+    }
+    let first = segment_ident(segments[0]);
+    (!first.is_empty() && first.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(first)
+}
+
+/// The spelling of `name` with its leading `<Type as Trait>` segment replaced
+/// with `Type`, if it has one.
+pub(crate) fn inherent_spelling(name: &str) -> Option<String> {
+    split_qualified(name).map(|(self_type, _, rest)| format!("{self_type}::{rest}"))
+}
+
+/// The methods of the `Fn` traits, through which compiler generated shims
+/// call closures.
+#[cfg(feature = "process")]
+const FN_TRAIT_METHODS: &[&str] = &["call_once", "call_mut", "call"];
+
+/// Whether the demangled name `name` is a method of the `Fn` traits without
+/// its self type, as the legacy symbols of the shims which call closures
+/// through `fn` pointers are named (such as
+/// `core::ops::function::FnOnce::call_once`).
+#[cfg(feature = "process")]
+pub(crate) fn is_fn_trait_method(name: &str) -> bool {
+    let name = strip_hash_suffix(name);
+    ["FnOnce::call_once", "FnMut::call_mut", "Fn::call"]
+        .iter()
+        .any(|method| name.strip_suffix(method) == Some("core::ops::function::"))
+}
+
+/// The name of the closure called by the compiler generated shim named
+/// `name`, such as `my_crate::main::{closure#0}`.
+///
+/// This is read from the self type of `Fn` trait methods (such as
+/// `<my_crate::main::{closure#0} as core::ops::function::FnOnce<()>>::call_once`,
+/// with `v0` mangling), including their vtable shims, or from the first
+/// generic argument of the DWARF name of the method (such as
+/// `call_once<my_crate::main::{closure_env#0}, ()>`, from
+/// [`Object::subprogram_name`](crate::object::Object::subprogram_name)).
+#[cfg(feature = "process")]
+pub(crate) fn shim_closure(name: &str) -> Option<String> {
+    let name = strip_hash_suffix(name);
+    // Vtable shims are named after the method they call
+    let name = match path_segments(name).as_slice() {
+        [.., last] if last.starts_with("{shim:vtable") || last.starts_with("{{vtable.shim}}") => {
+            name[..name.len() - last.len()].strip_suffix("::")?
+        }
+        _ => name,
+    };
+    let closure = match split_qualified(name) {
+        Some((self_type, trait_, method)) => {
+            let is_fn_trait = ["FnOnce", "FnMut", "Fn"]
+                .iter()
+                .any(|it| trait_.starts_with(&format!("core::ops::function::{it}<")));
+            (is_fn_trait && FN_TRAIT_METHODS.contains(&method)).then_some(self_type)?
+        }
+        None => {
+            let arguments = FN_TRAIT_METHODS
+                .iter()
+                .find_map(|method| name.strip_prefix(method)?.strip_prefix('<'))?;
+            let (arguments, _) = generic_arguments(arguments)?;
+            arguments.first().copied()?
+        }
+    };
+    let closure = closure
+        .trim_start_matches(['&', ' '])
+        .trim_start_matches("mut ");
+    let closure = closure.replace("{closure_env#", "{closure#");
+    let is_closure = matches!(path_segments(&closure).last(), Some(last) if last.starts_with("{closure") || *last == "{{closure}}");
+    is_closure.then_some(closure)
+}
+
+/// Split a path starting with a qualified segment, such as
+/// `<Type as Trait>::method`, into the type, the trait, and the rest of the
+/// path.
+///
+/// Returns `None` if the path doesn't start with such a segment, or has
+/// nothing after it.
+pub(crate) fn split_qualified(path: &str) -> Option<(&str, &str, &str)> {
+    let inner = path.strip_prefix('<')?;
+    let bytes = inner.as_bytes();
+    let mut depth = 0usize;
+    let mut as_index = None;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'<' | b'(' | b'[' => depth += 1,
+            // `->` in a fn pointer type is not a closing bracket
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' if depth == 0 => {
+                let (self_type, trait_) = inner[..i].split_at(as_index?);
+                let rest = inner[i + 1..].strip_prefix("::")?;
+                return Some((self_type, &trait_[" as ".len()..], rest));
+            }
+            b'>' | b')' | b']' => depth = depth.saturating_sub(1),
+            b' ' if depth == 0 && as_index.is_none() && inner[i..].starts_with(" as ") => {
+                as_index = Some(i);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Remove the parts of a function name which don't identify the function, so
+/// that names can be compared exactly.
+///
+/// This removes:
+/// - Crate disambiguators, such as the `[866a6fc06826cdd7]` in
+///   `my_crate[866a6fc06826cdd7]::foo::bar`, which some versions of the
+///   demangler print for v0 mangled names. These are square brackets
+///   containing only hex digits, directly after an identifier
+/// - Trailing legacy mangling hashes, such as `::h0123456789abcdef`
+/// - The `r#` prefix of raw identifiers, such as `my_crate::r#match`, as
+///   [`type_name`] and the demangler don't agree on whether to print it
+///
+/// Identifiers which are still punycode encoded (such as `xn--gre-6ka8l` or
+/// `_xn--gre-6ka8l`), as left by some demanglers for non-ASCII identifiers,
+/// are decoded, so that they match the identifier in [`type_name`] (`grüße`).
+///
+/// Segments for items without their own names are also spelled as in v0
+/// demangled names, so `{{closure}}` (as printed by [`type_name`] and legacy
+/// demangling) becomes `{closure}`. Numeric disambiguators, such as the `#1`
+/// in `{closure#1}`, are kept.
+///
+/// Generic arguments are spelled as by [`type_name`], which doesn't print
+/// lifetimes or the types of integer consts. Lifetime arguments are removed
+/// (`my_crate::Foo<'_, u8>` becomes `my_crate::Foo<u8>`), and integer, bool
+/// and char const arguments are spelled as literals without a type suffix
+/// or braces, so `my_crate::foo::<{1_000usize}>` becomes
+/// `my_crate::foo<1000>`.
+///
+/// Paths are spelled as by [`type_name`], without turbofishes or angle
+/// brackets around an inherent impl's type, so the v0 demangled
+/// `<my_crate::Foo<u32>>::bar::<u64>` becomes `my_crate::Foo<u32>::bar<u64>`.
+/// Types with several paths, such as `core::primitive::u32` and
+/// `std::string::String`, are spelled with the path that [`type_name`]
+/// prints.
+///
+/// Trait objects are spelled without lifetime bounds and auto traits, which
+/// rustc versions and manglings print differently, so `dyn
+/// core::error::Error+core::marker::Sync+core::marker::Send` becomes `dyn
+/// core::error::Error`. Opaque types, such as `impl core::fmt::Debug`, become
+/// `{opaque}`, which [`names_match`] matches with any type. Names which only
+/// differ in these types can't be told apart.
+///
+/// Other square brackets, such as slices and arrays in generic arguments, are
+/// kept. Canonicalizing an already canonical name returns it unchanged.
+///
+/// [`type_name`]: core::any::type_name
+///
+/// ```rust
+/// # use declaration_site::canonical_name;
+/// assert_eq!(
+///     canonical_name("my_crate::main::{{closure}}::{{closure}}"),
+///     "my_crate::main::{closure}::{closure}"
+/// );
+/// assert_eq!(
+///     canonical_name("my_crate::main::{closure#1}"),
+///     "my_crate::main::{closure#1}"
+/// );
+/// assert_eq!(
+///     canonical_name("my_crate[866a6fc06826cdd7]::foo::bar"),
+///     "my_crate::foo::bar"
+/// );
+/// assert_eq!(canonical_name("my_crate::r#mod::r#match"), "my_crate::mod::match");
+/// assert_eq!(canonical_name("my_crate::_xn--gre-6ka8l"), "my_crate::grüße");
+/// assert_eq!(canonical_name("my_crate::xn--ida::xn--gre_fn-cta7p"), "my_crate::ñ::größe_fn");
+/// assert_eq!(
+///     canonical_name("<alloc[1a2b]::string::String as core[3c4d]::fmt::Debug>::fmt::h0123456789abcdef"),
+///     "<alloc::string::String as core::fmt::Debug>::fmt"
+/// );
+///
+/// // Other square brackets and non-ASCII identifiers are kept
+/// for name in [
+///     "my_crate::foo<[u8]>",
+///     "my_crate::foo<[u8; 16]>",
+///     "<[u8] as core::fmt::Debug>::fmt",
+///     "my_crate::foo<&[alloc::string::String]>",
+///     "my_crate::foo[]",
+///     "my_crate::foo[x1]",
+///     "my_crate::grüße<for<'a> fn(&'a u8) -> u8>",
+///     "my_crate::xn-",
+///     "my_crate::axn--ida",
+/// ] {
+///     assert_eq!(canonical_name(name), name);
+/// }
+///
+/// // Const and lifetime generic arguments are spelled as by `type_name`
+/// assert_eq!(
+///     canonical_name("my_crate::foo::<3usize, true, '\\u{27}', -5_i32>"),
+///     "my_crate::foo<3, true, '\\'', -5>"
+/// );
+/// assert_eq!(canonical_name("my_crate::foo::<{1_000usize}>"), "my_crate::foo<1000>");
+/// assert_eq!(
+///     canonical_name("<my_crate::Foo<core::primitive::u32>>::bar::<std::string::String>"),
+///     "my_crate::Foo<u32>::bar<alloc::string::String>"
+/// );
+/// assert_eq!(
+///     canonical_name("<[u8] as my_crate::Bar<std::vec::Vec<u8>>>::baz"),
+///     "<[u8] as my_crate::Bar<alloc::vec::Vec<u8>>>::baz"
+/// );
+/// assert_eq!(
+///     canonical_name("core::ptr::drop_in_place<my_crate::W<dyn core::error::Error+core::marker::Sync+core::marker::Send>>"),
+///     "core::ptr::drop_in_place<my_crate::W<dyn core::error::Error>>"
+/// );
+/// assert_eq!(
+///     canonical_name("my_crate::foo<&(dyn std::error::Error + 'static)>"),
+///     "my_crate::foo<&dyn core::error::Error>"
+/// );
+/// assert_eq!(
+///     canonical_name("my_crate::foo<alloc::boxed::Box<dyn core::marker::Sync + core::marker::Send>>"),
+///     "my_crate::foo<alloc::boxed::Box<dyn core::marker::Send + core::marker::Sync>>"
+/// );
+/// assert_eq!(
+///     canonical_name("my_crate::foo::<impl core::ops::function::Fn(u8) -> u8, u8>"),
+///     "my_crate::foo<{opaque}, u8>"
+/// );
+/// assert_eq!(canonical_name("my_crate::foo<my_crate::make::{opaque#0}>"), "my_crate::foo<{opaque}>");
+/// // `impl` blocks aren't opaque types
+/// assert_eq!(canonical_name("core::slice::<impl [T]>::len"), "core::slice::<impl [T]>::len");
+/// // Only whole paths are respelled
+/// for name in ["my_crate::std::string::String", "my_crate::foo<std::string::Strings>"] {
+///     assert_eq!(canonical_name(name), name);
+/// }
+/// assert_eq!(
+///     canonical_name("<my_crate::Foo<'_, u8> as my_crate::Bar<'static>>::baz"),
+///     "<my_crate::Foo<u8> as my_crate::Bar>::baz"
+/// );
+///
+/// // Distinct const arguments are never merged, however they are spelled
+/// let spellings = |value: i64| {
+///     [
+///         format!("{value}"),
+///         format!("{value}i64"),
+///         format!("{{{value}_i64}}"),
+///     ]
+/// };
+/// for a in -20..20 {
+///     for b in -20..20 {
+///         for a_name in spellings(a) {
+///             for b_name in spellings(b) {
+///                 let a_name = format!("my_crate::foo::<{a_name}, 'a'>");
+///                 let b_name = format!("my_crate::foo::<{b_name}, 'a'>");
+///                 assert_eq!(canonical_name(&a_name) == canonical_name(&b_name), a == b);
+///             }
+///         }
+///     }
+/// }
+/// let chars = ['a', 'b', '\'', '"', '\\', '\n', '\0', '>', ',', 'ü', '\u{301}'];
+/// for a in chars {
+///     for b in chars {
+///         let a_name = format!("my_crate::foo::<{a:?}>");
+///         let escaped = format!("my_crate::foo::<'\\u{{{:x}}}'>", b as u32);
+///         assert_eq!(canonical_name(&a_name) == canonical_name(&escaped), a == b);
+///     }
+/// }
+///
+/// // Canonicalizing is idempotent
+/// for name in [
+///     "my_crate[ff]::foo::<[u8; 4]>::h0123456789abcdef",
+///     "my_crate::foo[ab][cd]",
+///     "my_crate::foo::h0123456789abcdef::h0123456789abcdef",
+///     "my_crate::foo[ab]::h0123456789abcdef[cd]",
+///     "krate_ä[0f]::ü",
+///     "my_crate::Foo::{{constructor}}",
+///     "my_crate::r#r#match::xn--xn--a-",
+///     "my_crate::_xn--gre-6ka8l::xn--",
+///     "my_crate::main::{{{closure}}}",
+///     "my_crate::foo::<'a, {0x10}, '\\x41', 00_7u8>",
+///     "my_crate::foo::<for<'a> fn(&'a u8), '>', ','>",
+/// ] {
+///     let canonical = canonical_name(name);
+///     assert_eq!(canonical_name(&canonical), canonical);
+/// }
+/// ```
+pub fn canonical_name(name: &str) -> Cow<'_, str> {
+    NormalizationStage::ALL
+        .iter()
+        .fold(Cow::Borrowed(name), |name, stage| {
+            map_cow(name, |name| stage.apply(name))
+        })
+}
+
+/// A stage of [`canonical_name`], which are applied in the order of
+/// [`NormalizationStage::ALL`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum NormalizationStage {
+    /// Removing the `r#` prefix of raw identifiers and decoding punycode
+    Identifiers,
+    /// Removing crate disambiguators, such as `my_crate[866a6fc06826cdd7]`
+    Disambiguators,
+    /// Spelling segments such as `{{closure}}` as `{closure}`
+    Braces,
+    /// Removing turbofishes and respelling paths of primitives and std types
+    TypePaths,
+    /// Removing auto traits from trait objects and collapsing opaque types
+    TraitTypes,
+    /// Removing lifetime arguments and spelling const arguments as literals
+    GenericArguments,
+    /// Removing trailing legacy symbol hashes
+    HashSuffixes,
+}
+
+impl NormalizationStage {
+    /// Every stage, in the order they are applied.
+    pub const ALL: [NormalizationStage; 7] = [
+        NormalizationStage::Identifiers,
+        NormalizationStage::Disambiguators,
+        NormalizationStage::Braces,
+        NormalizationStage::TypePaths,
+        NormalizationStage::TraitTypes,
+        NormalizationStage::GenericArguments,
+        NormalizationStage::HashSuffixes,
+    ];
+
+    /// Apply only this stage to `name`.
+    pub fn apply(self, name: &str) -> Cow<'_, str> {
+        match self {
+            NormalizationStage::Identifiers => normalize_identifiers(name),
+            NormalizationStage::Disambiguators => strip_disambiguators(name),
+            NormalizationStage::Braces => normalize_braces(name),
+            NormalizationStage::TypePaths => normalize_type_paths(name),
+            NormalizationStage::TraitTypes => normalize_trait_types(name),
+            NormalizationStage::GenericArguments => normalize_generic_arguments(name),
+            NormalizationStage::HashSuffixes => Cow::Borrowed(strip_hash_suffixes(name)),
+        }
+    }
+}
+
+/// Which parts of a name [`normalize_function_path`] removes or respells.
+///
+/// The default removes hashes and disambiguators and canonicalizes closures,
+/// but keeps generic arguments, which gives the same paths as the names
+/// matched by [`declaration_by_name_in`](crate::declaration_by_name_in).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct NormalizeOptions {
+    /// Remove trailing legacy symbol hashes, such as `::h0123456789abcdef`
+    pub strip_hashes: bool,
+    /// Remove the generic arguments of every segment, so that each
+    /// instantiation of a generic function has the same path
+    pub erase_generics: bool,
+    /// Spell closures and other compiler generated segments as `{closure}`,
+    /// rather than `{{closure}}` or `{closure#0}`
+    pub canonicalize_closures: bool,
+    /// Remove crate disambiguators, such as `my_crate[866a6fc06826cdd7]`
+    pub remove_disambiguators: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            strip_hashes: true,
+            erase_generics: false,
+            canonicalize_closures: true,
+            remove_disambiguators: true,
+        }
+    }
+}
+
+/// The path of a function normalized by [`normalize_function_path`].
+///
+/// Paths are compared, ordered and hashed by their normalized spelling, so
+/// can be used as the keys of maps, including maps which are persisted
+/// (with the `serde` feature).
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NormalizedPath(String);
+
+impl NormalizedPath {
+    /// The normalized spelling of the path.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The normalized spelling of the path.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Display for NormalizedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for NormalizedPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Normalize the path of a function, as printed by the legacy or v0
+/// demanglers or by [`type_name`](core::any::type_name), so that each
+/// spelling of the same function has the same [`NormalizedPath`].
+///
+/// This is [`canonical_name`], with the stages named by `options` enabled or
+/// disabled. Raw identifiers, punycode, type paths, trait objects, opaque
+/// types and generic arguments are always respelled as in [`canonical_name`].
+///
+/// # Grammar
+///
+/// The input is a path in the following grammar, where `ident` is any Rust
+/// identifier (including raw identifiers such as `r#match` and punycode
+/// encoded identifiers such as `_xn--gre-6ka8l`), and `type` is any Rust type
+/// (including `dyn` and `impl` types, function pointers, references and
+/// arrays):
+///
+/// ```text
+/// path          = [ qualified "::" ] segment { "::" segment } [ hash ]
+/// qualified     = "<" type [ " as " path ] ">"
+/// segment       = ident [ disambiguator ] [ [ "::" ] generics ]
+///               | "{" kind [ "#" digits ] "}"          (v0 and type_name)
+///               | "{{" kind "}}"                      (legacy)
+///               | "<impl " type [ " for " type ] ">"  (legacy impl blocks)
+/// kind          = "closure" | "constructor" | "opaque" | "constant" | ident
+/// disambiguator = "[" hex-digits "]"
+/// generics      = "<" argument { ", " argument } ">"
+/// argument      = type | lifetime | const
+/// const         = [ "-" ] digits [ int-suffix ] | "true" | "false" | char
+///               | "{" const "}"
+/// hash          = "::h" 16 * hex-digit
+/// ```
+///
+/// Input which doesn't match this grammar is returned with as much of it
+/// normalized as can be, and never causes a panic.
+///
+/// # Stability
+///
+/// The normalized spelling of an input with the same options is stable
+/// within semver compatible versions of this crate (such as each `0.x.*`),
+/// so normalized paths can be persisted and compared with paths normalized
+/// by a later compatible version. Spellings may change in versions which
+/// aren't compatible, when rustc or the demanglers change how names are
+/// printed.
+///
+/// # Examples
+///
+/// ```rust
+/// use declaration_site::{normalize_function_path, NormalizeOptions};
+///
+/// let defaults = NormalizeOptions::default();
+/// let erased = NormalizeOptions {
+///     erase_generics: true,
+///     ..NormalizeOptions::default()
+/// };
+/// let none = NormalizeOptions {
+///     strip_hashes: false,
+///     erase_generics: false,
+///     canonicalize_closures: false,
+///     remove_disambiguators: false,
+/// };
+/// let cases = [
+///     // Plain paths are unchanged
+///     ("my_crate::foo", defaults, "my_crate::foo"),
+///     ("my_crate::foo", none, "my_crate::foo"),
+///     // Legacy hashes
+///     ("my_crate::foo::h0123456789abcdef", defaults, "my_crate::foo"),
+///     ("my_crate::foo::h0123456789abcdef", none, "my_crate::foo::h0123456789abcdef"),
+///     ("my_crate::h0123456789abcdef", defaults, "my_crate"),
+///     // Identifiers which look like hashes aren't removed
+///     ("my_crate::h0123", defaults, "my_crate::h0123"),
+///     // Crate disambiguators of v0 names
+///     ("my_crate[866a6fc06826cdd7]::foo", defaults, "my_crate::foo"),
+///     ("my_crate[866a6fc06826cdd7]::foo", none, "my_crate[866a6fc06826cdd7]::foo"),
+///     (
+///         "<my_crate[ab]::Foo as core[cd]::fmt::Debug>::fmt",
+///         defaults,
+///         "<my_crate::Foo as core::fmt::Debug>::fmt",
+///     ),
+///     // Closures in each spelling
+///     ("my_crate::main::{{closure}}", defaults, "my_crate::main::{closure}"),
+///     ("my_crate::main::{closure#0}", defaults, "my_crate::main::{closure}"),
+///     ("my_crate::main::{closure#12}", defaults, "my_crate::main::{closure}"),
+///     ("my_crate::main::{{closure}}", none, "my_crate::main::{{closure}}"),
+///     ("my_crate::main::{closure#1}", none, "my_crate::main::{closure#1}"),
+///     (
+///         "my_crate::main::{{closure}}::{{closure}}",
+///         defaults,
+///         "my_crate::main::{closure}::{closure}",
+///     ),
+///     ("my_crate::Foo::{{constructor}}", defaults, "my_crate::Foo::{constructor}"),
+///     // Generic arguments, with and without turbofishes
+///     ("my_crate::foo::<u8>", defaults, "my_crate::foo<u8>"),
+///     ("my_crate::foo<u8>", defaults, "my_crate::foo<u8>"),
+///     ("my_crate::foo::<u8>", erased, "my_crate::foo"),
+///     ("my_crate::Foo<u8>::bar<u16>", erased, "my_crate::Foo::bar"),
+///     (
+///         "<my_crate::Foo<u8> as my_crate::Bar<u16>>::baz",
+///         erased,
+///         "<my_crate::Foo as my_crate::Bar>::baz",
+///     ),
+///     ("my_crate::foo::<'a, u8>", defaults, "my_crate::foo<u8>"),
+///     ("my_crate::foo::<{16_usize}>", defaults, "my_crate::foo<16>"),
+///     ("my_crate::foo::<4usize>", defaults, "my_crate::foo<4>"),
+///     ("my_crate::foo::<'\\x41'>", defaults, "my_crate::foo<'A'>"),
+///     // Paths of std types and primitives
+///     (
+///         "my_crate::foo::<alloc::string::String>",
+///         defaults,
+///         "my_crate::foo<alloc::string::String>",
+///     ),
+///     (
+///         "my_crate::foo<std::string::String>",
+///         defaults,
+///         "my_crate::foo<alloc::string::String>",
+///     ),
+///     ("my_crate::foo<core::primitive::u8>", defaults, "my_crate::foo<u8>"),
+///     // Trait objects and opaque types
+///     (
+///         "my_crate::foo<dyn core::fmt::Debug + core::marker::Send>",
+///         defaults,
+///         "my_crate::foo<dyn core::fmt::Debug>",
+///     ),
+///     (
+///         "my_crate::foo<impl core::ops::function::Fn(u8) -> u8>",
+///         defaults,
+///         "my_crate::foo<{opaque}>",
+///     ),
+///     // Raw and punycode identifiers
+///     ("my_crate::r#match", defaults, "my_crate::match"),
+///     ("my_crate::_xn--gre-6ka8l", defaults, "my_crate::grüße"),
+///     // Every option at once
+///     (
+///         "my_crate[ff]::Foo::<u8>::bar::{closure#3}::h0123456789abcdef",
+///         erased,
+///         "my_crate::Foo::bar::{closure}",
+///     ),
+///     // Input which isn't a path
+///     ("", defaults, ""),
+///     ("<<>>::{", defaults, "<<>>::{"),
+/// ];
+/// for (input, options, expected) in cases {
+///     let normalized = normalize_function_path(input, options);
+///     assert_eq!(normalized.as_str(), expected, "for `{input}` with {options:?}");
+///     // Normalizing is idempotent
+///     assert_eq!(normalize_function_path(normalized.as_str(), options), normalized);
+/// }
+///
+/// // Each spelling of a function has the same normalized path
+/// let spellings = [
+///     "my_crate::Foo<u8>::bar::{{closure}}::h0123456789abcdef",
+///     "my_crate[866a6fc06826cdd7]::Foo<u8>::bar::{closure#0}",
+///     "my_crate::Foo<core::primitive::u8>::bar::{{closure}}",
+/// ];
+/// let paths = spellings.map(|name| normalize_function_path(name, defaults));
+/// assert!(paths.iter().all(|path| *path == paths[0]));
+/// assert_eq!(paths[0].to_string(), "my_crate::Foo<u8>::bar::{closure}");
+/// ```
+pub fn normalize_function_path(input: &str, options: NormalizeOptions) -> NormalizedPath {
+    let mut name = Cow::Borrowed(input);
+    for stage in NormalizationStage::ALL {
+        let enabled = match stage {
+            NormalizationStage::Disambiguators => options.remove_disambiguators,
+            NormalizationStage::Braces => options.canonicalize_closures,
+            NormalizationStage::HashSuffixes => options.strip_hashes,
+            _ => true,
+        };
+        if enabled {
+            name = map_cow(name, |name| stage.apply(name));
+        }
+    }
+    if options.canonicalize_closures {
+        name = map_cow(name, wildcard_key);
+    }
+    if options.erase_generics {
+        name = Cow::Owned(erase_generic_arguments(&name));
+    }
+    NormalizedPath(name.into_owned())
+}
+
+/// Remove the `r#` prefix of raw identifiers in `name`, and decode any
+/// punycode encoded identifiers.
+fn normalize_identifiers(name: &str) -> Cow<'_, str> {
+    if !name.contains("r#") && !name.contains("xn--") {
+        return Cow::Borrowed(name);
+    }
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        // Only look for prefixes at the start of identifiers
+        if !result.chars().next_back().is_some_and(is_ident) {
+            if let Some(ident) = rest
+                .strip_prefix("r#")
+                .filter(|it| it.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+            {
+                rest = ident;
+                continue;
+            }
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            if let Some(decoded) = decode_punycode_ident(&rest[..len]) {
+                result.push_str(&decoded);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if result == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// Decode an identifier such as `xn--gre-6ka8l` or `_xn--gre-6ka8l`, whose
+/// non-ASCII characters are punycode encoded (RFC 3492).
+fn decode_punycode_ident(ident: &str) -> Option<String> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    let encoded = ident
+        .strip_prefix('_')
+        .unwrap_or(ident)
+        .strip_prefix("xn--")?;
+    let (basic, encoded) = match encoded.rfind('-') {
+        Some(index) => (&encoded[..index], &encoded[index + 1..]),
+        None => ("", encoded),
+    };
+    if encoded.is_empty() {
+        return None;
+    }
+    let mut output = basic.chars().collect::<Vec<_>>();
+    let (mut n, mut i, mut bias) = (128u32, 0u32, 72u32);
+    let mut digits = encoded.bytes();
+    while digits.len() > 0 {
+        let old_i = i;
+        let mut weight = 1u32;
+        for k in (BASE..).step_by(BASE as usize) {
+            let digit = match digits.next()? {
+                byte @ b'a'..=b'z' => byte - b'a',
+                byte @ b'A'..=b'Z' => byte - b'A',
+                byte @ b'0'..=b'9' => byte - b'0' + 26,
+                _ => return None,
+            } as u32;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = k.saturating_sub(bias).clamp(T_MIN, T_MAX);
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(BASE - threshold)?;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt_bias(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    let decoded = output.into_iter().collect::<String>();
+    // Keep canonicalizing idempotent
+    (!decoded.contains("xn--")).then_some(decoded)
+}
+
+/// The bias adaptation function of punycode.
+fn adapt_bias(delta: u32, len: u32, first: bool) -> u32 {
+    const BASE: u32 = 36;
+    let mut delta = if first { delta / 700 } else { delta / 2 };
+    delta += delta / len;
+    let mut k = 0;
+    while delta > ((BASE - 1) * 26) / 2 {
+        delta /= BASE - 1;
+        k += BASE;
+    }
+    k + (BASE * delta) / (delta + 38)
+}
+
+/// Apply `f` to `name`, only allocating if `name` or the result is owned.
+fn map_cow<'a>(name: Cow<'a, str>, f: impl FnOnce(&str) -> Cow<'_, str>) -> Cow<'a, str> {
+    match name {
+        Cow::Borrowed(name) => f(name),
+        Cow::Owned(name) => Cow::Owned(f(&name).into_owned()),
+    }
+}
+
+/// Spell the segments of legacy demangled names such as `{{closure}}` and
+/// `{{constructor}}` as `{closure}` and `{constructor}`, as in v0 demangled
+/// names.
+///
+/// This is repeated until nothing changes, so that `{{{closure}}}` is fully
+/// normalized.
+fn normalize_braces(name: &str) -> Cow<'_, str> {
+    let mut result = String::new();
+    // The end of the part of `name` which has been copied into `result`
+    let (mut copied, mut search) = (0, 0);
+    while let Some(index) = name[search..].find("{{") {
+        let start = search + index;
+        search = start + 1;
+        let Some((_, _, len)) = brace_segment(&name[search..]) else {
+            continue;
+        };
+        if name[search + len..].starts_with('}') {
+            result.push_str(&name[copied..start]);
+            result.push_str(&name[search..search + len]);
+            copied = search + len + 1;
+            search = copied;
+        }
+    }
+    if copied == 0 {
+        Cow::Borrowed(name)
+    } else {
+        result.push_str(&name[copied..]);
+        Cow::Owned(normalize_braces(&result).into_owned())
+    }
+}
+
+/// The paths of types which can be named by several paths, and the path
+/// printed by [`type_name`](core::any::type_name) and the demangler.
+///
+/// Paths ending in `::` are prefixes, such as of `core::primitive::u32`.
+const TYPE_PATHS: &[(&str, &str)] = &[
+    ("core::primitive::", ""),
+    ("std::primitive::", ""),
+    ("std::string::String", "alloc::string::String"),
+    ("std::vec::Vec", "alloc::vec::Vec"),
+    ("std::boxed::Box", "alloc::boxed::Box"),
+    ("std::rc::Rc", "alloc::rc::Rc"),
+    ("std::sync::Arc", "alloc::sync::Arc"),
+    ("std::borrow::Cow", "alloc::borrow::Cow"),
+    ("std::option::Option", "core::option::Option"),
+    ("std::result::Result", "core::result::Result"),
+    ("std::cell::Cell", "core::cell::Cell"),
+    ("std::cell::RefCell", "core::cell::RefCell"),
+    ("std::error::Error", "core::error::Error"),
+    ("std::fmt::Debug", "core::fmt::Debug"),
+    ("std::fmt::Display", "core::fmt::Display"),
+    ("std::future::Future", "core::future::future::Future"),
+    ("core::future::Future", "core::future::future::Future"),
+    ("std::marker::Send", "core::marker::Send"),
+    ("std::marker::Sync", "core::marker::Sync"),
+    ("std::marker::Unpin", "core::marker::Unpin"),
+    (
+        "std::collections::HashMap",
+        "std::collections::hash::map::HashMap",
+    ),
+    (
+        "std::collections::HashSet",
+        "std::collections::hash::set::HashSet",
+    ),
+    (
+        "std::collections::BTreeMap",
+        "alloc::collections::btree::map::BTreeMap",
+    ),
+];
+
+/// Spell the paths in `name` as [`type_name`] does.
+///
+/// This removes the `::` of turbofishes (`foo::<u8>` becomes `foo<u8>`), and
+/// the angle brackets of a leading qualified path without a trait
+/// (`<my_crate::Foo<u8>>::bar` becomes `my_crate::Foo<u8>::bar`). Paths of
+/// types with several paths are replaced as in [`TYPE_PATHS`].
+///
+/// [`type_name`]: core::any::type_name
+fn normalize_type_paths(name: &str) -> Cow<'_, str> {
+    let may_change = name.starts_with('<')
+        || name.contains("::<")
+        || name.contains("std::")
+        || name.contains("primitive::");
+    if !may_change {
+        return Cow::Borrowed(name);
+    }
+    let mut unwrapped = None;
+    if let Some(inner) = name.strip_prefix('<') {
+        if let Some((arguments, len)) = generic_arguments(inner) {
+            let is_path = inner.starts_with(|c: char| c.is_alphabetic() || c == '_');
+            if let ([self_type], Some(path)) = (&arguments[..], inner[len..].strip_prefix("::")) {
+                if is_path && split_qualified(name).is_none() {
+                    unwrapped = Some(format!("{self_type}::{path}"));
+                }
+            }
+        }
+    }
+    let mut result = String::with_capacity(name.len());
+    let mut rest = unwrapped.as_deref().unwrap_or(name);
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    while let Some(c) = rest.chars().next() {
+        let at_path_start = !result.ends_with(|c: char| is_ident(c) || c == ':');
+        if at_path_start {
+            let replacement = TYPE_PATHS.iter().find(|(path, _)| {
+                rest.strip_prefix(path)
+                    .is_some_and(|after| path.ends_with("::") || !after.starts_with(is_ident))
+            });
+            if let Some((path, replacement)) = replacement {
+                result.push_str(replacement);
+                rest = &rest[path.len()..];
+                continue;
+            }
+        }
+        // Turbofishes, but not segments such as `<impl [T]>`
+        let turbofish = rest.strip_prefix("::<").filter(|_| !result.is_empty());
+        if turbofish.is_some_and(|it| !it.strip_prefix("impl ").is_some_and(is_impl_block)) {
+            rest = &rest[2..];
+            continue;
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    if result == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// The auto traits which can be added to a trait object, as printed by
+/// [`type_name`](core::any::type_name) and by older rustc versions.
+const AUTO_TRAITS: &[&str] = &[
+    "core::marker::Send",
+    "core::marker::Sync",
+    "core::marker::Unpin",
+    "core::panic::unwind_safe::UnwindSafe",
+    "core::panic::unwind_safe::RefUnwindSafe",
+    "Send",
+    "Sync",
+    "Unpin",
+    "UnwindSafe",
+    "RefUnwindSafe",
+];
+
+/// The canonical spelling of an opaque type, such as `impl Trait`, which
+/// [`compare_paths`] matches with any type.
+pub(crate) const OPAQUE: &str = "{opaque}";
+
+/// Spell trait objects and opaque types in `name` so that their spellings in
+/// different rustc versions are the same.
+///
+/// Trait objects (`dyn Trait`) are spelled without lifetime bounds and auto
+/// traits such as `Send`, so `dyn core::error::Error+core::marker::Send +
+/// 'static` becomes `dyn core::error::Error`. Opaque types, whether spelled
+/// `impl Trait` or as a path such as `my_crate::make::{opaque#0}`, become
+/// [`OPAQUE`].
+fn normalize_trait_types(name: &str) -> Cow<'_, str> {
+    if !name.contains("dyn ") && !name.contains("impl ") && !name.contains("{opaque") {
+        return Cow::Borrowed(name);
+    }
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut result = String::with_capacity(name.len());
+    // The start of the type being written to `result`, for opaque type paths
+    let mut type_start = 0;
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        let at_word_start = !result.ends_with(is_ident);
+        if let Some(bounds) = rest.strip_prefix("dyn ").filter(|_| at_word_start) {
+            let len = type_len(bounds);
+            rest = &bounds[len..];
+            let bounds = trait_object_bounds(&bounds[..len]);
+            // `&(dyn Trait + Send)` needs parentheses, but `&dyn Trait` doesn't
+            if bounds.len() == 1 && result.ends_with('(') && rest.starts_with(')') {
+                result.pop();
+                rest = &rest[1..];
+            }
+            result.push_str("dyn ");
+            result.push_str(&bounds.join(" + "));
+            continue;
+        }
+        if let Some(bounds) = rest.strip_prefix("impl ") {
+            let is_segment = result == "<" || result.ends_with("::<");
+            if at_word_start && !(is_segment && is_impl_block(bounds)) {
+                result.push_str(OPAQUE);
+                rest = &bounds[type_len(bounds)..];
+                continue;
+            }
+        }
+        if rest.starts_with("{opaque") {
+            if let Some((_, _, len)) = brace_segment(rest) {
+                result.truncate(type_start);
+                result.push_str(OPAQUE);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+        if matches!(c, '<' | '(' | '[' | ',' | ' ' | '&' | '*') {
+            type_start = result.len();
+        }
+    }
+    if result == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// Whether a segment starting with `<impl ` is an impl block, such as
+/// `<impl [T]>` in `core::slice::<impl [T]>::len`, rather than generic
+/// arguments with an opaque type, given the text after `<impl `.
+///
+/// Impl blocks in paths are for types which can't be named by a path, such
+/// as primitives, or are trait impls.
+fn is_impl_block(after_impl: &str) -> bool {
+    let self_type = &after_impl[..type_len(after_impl)];
+    self_type.starts_with(['[', '*', '&', '(', '!'])
+        || self_type.starts_with("dyn ")
+        || self_type.starts_with("fn(")
+        || PRIMITIVES.contains(&self_type)
+        || split_top_level(self_type, b' ').contains(&"for")
+}
+
+/// The bounds of a trait object, without lifetimes and, unless it has no
+/// other bounds, auto traits.
+fn trait_object_bounds(bounds: &str) -> Vec<&str> {
+    let mut bounds = split_top_level(bounds, b'+')
+        .into_iter()
+        .map(str::trim)
+        .filter(|bound| !bound.is_empty() && !bound.starts_with('\''))
+        .collect::<Vec<_>>();
+    if bounds.iter().any(|bound| !AUTO_TRAITS.contains(bound)) {
+        bounds.retain(|bound| !AUTO_TRAITS.contains(bound));
+    } else {
+        // The order of auto traits differs between manglings
+        bounds.sort_unstable();
+        bounds.dedup();
+    }
+    bounds
+}
+
+/// Split `text` on `separator`, except inside brackets.
+fn split_top_level(text: &str, separator: u8) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let bytes = text.as_bytes();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'<' | b'(' | b'[' | b'{' => depth += 1,
+            // `->` in a fn pointer type is not a closing bracket
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' | b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            _ if byte == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// The length of the type (or bounds of a trait object or opaque type) at
+/// the start of `text`, which ends at the first unmatched closing bracket or
+/// top level `,` or `;`.
+pub(crate) fn type_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'<' | b'(' | b'[' | b'{' => depth += 1,
+            // `->` in a fn pointer type is not a closing bracket
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' | b')' | b']' | b'}' | b',' | b';' if depth == 0 => return i,
+            b'>' | b')' | b']' | b'}' => depth -= 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// Spell the generic arguments in `name` as [`type_name`] does, so that
+/// functions with const generic or lifetime arguments match.
+///
+/// Lifetime arguments (such as the `'_` in `my_crate::Foo<'_, u8>`) are
+/// removed, along with the `<>` if no arguments are left. Integer const
+/// arguments lose their type suffix and underscores (`3_usize` is `3`), char
+/// const arguments are escaped as by [`Debug`], and braces around literal
+/// const arguments (`{3usize}`) are removed. The binders of higher-ranked
+/// types (`for<'a>`) are kept.
+///
+/// [`type_name`]: core::any::type_name
+fn normalize_generic_arguments(name: &str) -> Cow<'_, str> {
+    // Most names can't change, so avoid allocating for them
+    let bytes = name.as_bytes();
+    let may_change = bytes.iter().enumerate().any(|(i, &byte)| match byte {
+        b'\'' => true,
+        b'<' => matches!(bytes.get(i + 1), Some(b'0'..=b'9' | b'-' | b'{')),
+        // Arguments are separated by `, `
+        b',' => match bytes.get(i + 1) {
+            Some(b' ') => matches!(bytes.get(i + 2), Some(b'0'..=b'9' | b'-' | b'{')),
+            _ => true,
+        },
+        _ => false,
+    });
+    if !may_change {
+        return Cow::Borrowed(name);
+    }
+    let mut result = String::with_capacity(name.len());
+    write_generic_arguments(name, &mut result);
+    if result == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+fn write_generic_arguments(mut name: &str, result: &mut String) {
+    while let Some(index) = name.find('<') {
+        result.push_str(&name[..index]);
+        name = &name[index + 1..];
+        let Some((arguments, len)) = generic_arguments(name) else {
+            result.push('<');
+            continue;
+        };
+        let is_binder = result.ends_with("for")
+            && !result[..result.len() - 3].ends_with(|c: char| c.is_alphanumeric() || c == '_');
+        let arguments = arguments
+            .into_iter()
+            .filter(|argument| is_binder || !is_lifetime(argument))
+            .collect::<Vec<_>>();
+        if arguments.is_empty() && !is_binder {
+            // Also remove the `::` of `foo::<'a>`
+            if result.ends_with("::") {
+                result.truncate(result.len() - 2);
+            }
+        } else {
+            result.push('<');
+            for (i, argument) in arguments.into_iter().enumerate() {
+                if i > 0 {
+                    result.push_str(", ");
+                }
+                match const_argument(argument) {
+                    Some(argument) => result.push_str(&argument),
+                    None => write_generic_arguments(argument, result),
+                }
+            }
+            result.push('>');
+        }
+        name = &name[len..];
+    }
+    result.push_str(name);
+}
+
+/// Whether a generic argument is a lifetime, such as `'a` or `'_`.
+fn is_lifetime(argument: &str) -> bool {
+    argument.strip_prefix('\'').is_some_and(|name| {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// The canonical spelling of a generic argument which is a literal integer,
+/// bool or char const, possibly in braces, or `None` for other arguments.
+fn const_argument(argument: &str) -> Option<String> {
+    let argument = match argument.strip_prefix('{') {
+        Some(inner) => inner.strip_suffix('}')?.trim(),
+        None => argument,
+    };
+    if argument == "true" || argument == "false" {
+        return Some(argument.to_owned());
+    }
+    if let Some((c, len)) = char_literal(argument) {
+        return (len == argument.len()).then(|| format!("{c:?}"));
+    }
+    const SUFFIXES: [&str; 12] = [
+        "usize", "isize", "u128", "i128", "u64", "i64", "u32", "i32", "u16", "i16", "u8", "i8",
+    ];
+    let (sign, digits) = match argument.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", argument),
+    };
+    let digits = SUFFIXES
+        .iter()
+        .find_map(|suffix| digits.strip_suffix(suffix))
+        .unwrap_or(digits);
+    let is_integer = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.bytes().all(|b| b.is_ascii_digit() || b == b'_');
+    is_integer.then(|| {
+        let digits = digits.replace('_', "");
+        // Leading zeros don't change the value
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0",
+            digits => digits,
+        };
+        format!("{sign}{digits}")
+    })
+}
+
+/// Parse the char literal at the start of `literal`, such as `'x'`, `'\''`
+/// or `'\u{301}'`, into its char and length.
+pub(crate) fn char_literal(literal: &str) -> Option<(char, usize)> {
+    let rest = literal.strip_prefix('\'')?;
+    let mut chars = rest.chars();
+    let c = match chars.next()? {
+        '\\' => match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            c @ ('\\' | '\'' | '"') => c,
+            'x' => {
+                let hex = chars.as_str().get(..2)?;
+                chars = chars.as_str()[2..].chars();
+                char::from(u8::from_str_radix(hex, 16).ok().filter(u8::is_ascii)?)
+            }
+            'u' => {
+                let hex = chars.as_str().strip_prefix('{')?;
+                let end = hex.find('}')?;
+                chars = hex[end + 1..].chars();
+                char::from_u32(u32::from_str_radix(&hex[..end], 16).ok()?)?
+            }
+            _ => return None,
+        },
+        '\'' => return None,
+        c => c,
+    };
+    chars.as_str().strip_prefix('\'')?;
+    Some((c, literal.len() - chars.as_str().len() + 1))
+}
+
+/// Split the generic arguments at the start of `arguments` (after the
+/// opening `<`) at their top level commas, returning them without
+/// surrounding spaces, and the length up to and including the closing `>`.
+///
+/// Returns `None` if the arguments aren't closed.
+pub(crate) fn generic_arguments(arguments: &str) -> Option<(Vec<&str>, usize)> {
+    let bytes = arguments.as_bytes();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' | b'(' | b'[' | b'{' => depth += 1,
+            // `->` in a fn pointer type is not a closing bracket
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b'>' if depth == 0 => {
+                let last = arguments[start..i].trim();
+                if !last.is_empty() || !result.is_empty() {
+                    result.push(last);
+                }
+                return Some((result, i + 1));
+            }
+            b'>' | b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            b',' if depth == 0 => {
+                result.push(arguments[start..i].trim());
+                start = i + 1;
+            }
+            // Brackets and commas in char consts, such as `'>'`
+            b'\'' => {
+                if let Some((_, len)) = char_literal(&arguments[i..]) {
+                    i += len;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parse the segment at the start of `segment` which names an item without
+/// its own name, such as `{closure}`, `{closure#1}` or `{shim:vtable#0}`,
+/// into its kind, numeric disambiguator and length.
+fn brace_segment(segment: &str) -> Option<(&str, Option<&str>, usize)> {
+    let end = segment.strip_prefix('{')?.find('}')? + 1;
+    let inner = &segment[1..end];
+    let is_valid = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | ':');
+    let (kind, number) = match inner.split_once('#') {
+        Some((kind, number))
+            if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            (kind, Some(number))
+        }
+        Some(_) => return None,
+        None => (inner, None),
+    };
+    (!kind.is_empty() && kind.chars().all(is_valid)).then_some((kind, number, end + 1))
+}
+
+/// Compare two canonical paths, where segments such as `{closure}` without
+/// a numeric disambiguator match the same segment with any disambiguator,
+/// and [`OPAQUE`] types match any type.
+///
+/// Returns `Some(true)` if the paths are the same and every such segment has
+/// a disambiguator, `Some(false)` if the paths only match because some didn't,
+/// or `None` if the paths don't match.
+fn compare_paths(a: &str, b: &str) -> Option<bool> {
+    let (mut a, mut b) = (a, b);
+    let mut exact = true;
+    while let Some(index) = a.find('{') {
+        if let Some(opaque) = b.find(OPAQUE).filter(|&opaque| opaque <= index) {
+            // The opaque type in `b` matches any type in `a`
+            a = a.strip_prefix(&b[..opaque])?;
+            a = &a[type_len(a)..];
+            b = &b[opaque + OPAQUE.len()..];
+            exact = false;
+            continue;
+        }
+        b = b.strip_prefix(&a[..index])?;
+        a = &a[index..];
+        if let Some(rest) = a.strip_prefix(OPAQUE) {
+            a = rest;
+            b = &b[type_len(b)..];
+            exact = false;
+            continue;
+        }
+        match (brace_segment(a), brace_segment(b)) {
+            (Some((a_kind, a_number, a_len)), Some((b_kind, b_number, b_len)))
+                if a_kind == b_kind =>
+            {
+                match (a_number, b_number) {
+                    (Some(a_number), Some(b_number)) if a_number != b_number => return None,
+                    (Some(_), Some(_)) => {}
+                    _ => exact = false,
+                }
+                a = &a[a_len..];
+                b = &b[b_len..];
+            }
+            _ => {
+                a = &a[1..];
+                b = b.strip_prefix('{')?;
+            }
+        }
+    }
+    match b.find(OPAQUE) {
+        // The opaque type in `b` matches any type in `a`
+        Some(_) => compare_paths(b, a).map(|it| it && exact),
+        None => (a == b).then_some(exact),
+    }
+}
+
+/// `name` without the numeric disambiguators of segments such as
+/// `{closure#0}`, so that names which [`compare_paths`] matches are the same.
+pub(crate) fn wildcard_key(name: &str) -> Cow<'_, str> {
+    let mut result = String::new();
+    let (mut copied, mut search) = (0, 0);
+    while let Some(index) = name[search..].find('{') {
+        let start = search + index;
+        search = start + 1;
+        if let Some((kind, Some(_), len)) = brace_segment(&name[start..]) {
+            result.push_str(&name[copied..start]);
+            result.push('{');
+            result.push_str(kind);
+            result.push('}');
+            copied = start + len;
+            search = copied;
+        }
+    }
+    if copied == 0 {
+        Cow::Borrowed(name)
+    } else {
+        result.push_str(&name[copied..]);
+        Cow::Owned(result)
+    }
+}
+
+/// Remove every crate disambiguator (such as `[866a6fc06826cdd7]`) from `name`.
+fn strip_disambiguators(name: &str) -> Cow<'_, str> {
+    let mut result = String::new();
+    // The end of the part of `name` which has been handled, and of the last
+    // disambiguator which was removed
+    let (mut copied, mut search) = (0, 0);
+    while let Some(index) = name[search..].find('[') {
+        let start = search + index;
+        search = start + 1;
+        // Compare to the output, as the disambiguators of `a[1][2]` are both
+        // directly after `a` once removed
+        let previous = match name[copied..start].chars().next_back() {
+            Some(it) => Some(it),
+            None => result.chars().next_back(),
+        };
+        let after_ident = previous.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let hex_len = name[search..]
+            .bytes()
+            .take_while(u8::is_ascii_hexdigit)
+            .count();
+        if after_ident && hex_len > 0 && name[search + hex_len..].starts_with(']') {
+            result.push_str(&name[copied..start]);
+            copied = search + hex_len + 1;
+            search = copied;
+        }
+    }
+    if copied == 0 {
+        Cow::Borrowed(name)
+    } else {
+        result.push_str(&name[copied..]);
+        Cow::Owned(result)
+    }
+}
+
+/// Remove every trailing legacy mangling hash from `name`.
+fn strip_hash_suffixes(mut name: &str) -> &str {
+    loop {
+        match strip_hash_suffix(name) {
+            stripped if stripped.len() == name.len() => return name,
+            stripped => name = stripped,
+        }
+    }
+}
+
+/// How likely it is that a [`type_name`](core::any::type_name) describes a
+/// function item type.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum FunctionLikelihood {
+    /// The name is shaped like a function path.
+    Plausible,
+    /// The name is shaped like a type path, such as `alloc::string::String`.
+    /// Functions can have names like this, but are linted against.
+    Unlikely,
+    /// The name cannot be the type name of a function item type, such as a
+    /// primitive, reference or tuple.
+    Impossible,
+}
+
+/// The prefixes of the type names of the wrappers removed by
+/// [`unwrap_fn_item_type`], whose single generic argument is the wrapped type.
+#[cfg(feature = "process")]
+const FN_ITEM_WRAPPERS: &[&str] = &[
+    "alloc::boxed::Box<",
+    "alloc::rc::Rc<",
+    "alloc::sync::Arc<",
+    "core::option::Option<",
+];
+
+/// The most wrappers removed by [`unwrap_fn_item_type`].
+#[cfg(feature = "process")]
+const MAX_FN_ITEM_WRAPPERS: usize = 8;
+
+/// The type name of the function item type wrapped in references, `Box`,
+/// `Rc`, `Arc` or `Option` in the type name `name`, such as `my_crate::run`
+/// for `&alloc::boxed::Box<my_crate::run>`.
+///
+/// `name` is returned unchanged if it isn't wrapped in one of these (such as
+/// `Box<T, A>` with a custom allocator), or if it is wrapped more than
+/// [`MAX_FN_ITEM_WRAPPERS`] times.
+#[cfg(feature = "process")]
+pub(crate) fn unwrap_fn_item_type(name: &str) -> &str {
+    let mut inner = name;
+    for _ in 0..=MAX_FN_ITEM_WRAPPERS {
+        match strip_fn_item_wrapper(inner) {
+            Some(unwrapped) => inner = unwrapped,
+            None => return inner,
+        }
+    }
+    name
+}
+
+/// The type wrapped by the outermost wrapper of the type name `name`, if it
+/// is one of those removed by [`unwrap_fn_item_type`].
+#[cfg(feature = "process")]
+fn strip_fn_item_wrapper(name: &str) -> Option<&str> {
+    if let Some(inner) = name
+        .strip_prefix("&mut ")
+        .or_else(|| name.strip_prefix('&'))
+    {
+        return Some(inner);
+    }
+    FN_ITEM_WRAPPERS.iter().find_map(|wrapper| {
+        let rest = name.strip_prefix(wrapper)?;
+        match generic_arguments(rest)? {
+            // The wrapper must be the whole name, rather than the start of a
+            // path such as `alloc::boxed::Box<T>::new`
+            (arguments, len) if arguments.len() == 1 && len == rest.len() => Some(arguments[0]),
+            _ => None,
+        }
+    })
+}
+
+/// The most wrapper closures removed by [`registered_function`].
+#[cfg(feature = "process")]
+const MAX_WRAPPER_CLOSURES: usize = 8;
+
+/// Whether the last segment of `name` is a closure without a numeric
+/// disambiguator (such as `{{closure}}`, as printed by [`type_name`]), which
+/// matches every closure in its parent function.
+///
+/// [`type_name`]: core::any::type_name
+#[cfg(feature = "process")]
+pub(crate) fn is_ambiguous_closure(name: &str) -> bool {
+    matches!(
+        path_segments(name).last(),
+        Some(&("{{closure}}" | "{closure}"))
+    )
+}
+
+/// The type name of the function which was registered as the value with
+/// type name `name`, removing the wrappers removed by
+/// [`unwrap_fn_item_type`], and closures created by generic functions to
+/// wrap their only function argument.
+///
+/// For example, a registry whose `register<F>(f: F)` method stores
+/// `move || f()` would store `framework::Registry::register<app::spawn>::{{closure}}`
+/// for `app::spawn`, which is returned.
+#[cfg(feature = "process")]
+pub(crate) fn registered_function(name: &str) -> &str {
+    let mut name = unwrap_fn_item_type(name);
+    for _ in 0..MAX_WRAPPER_CLOSURES {
+        match wrapped_function(name) {
+            Some(wrapped) => name = unwrap_fn_item_type(wrapped),
+            None => break,
+        }
+    }
+    name
+}
+
+/// The only generic argument which could be a function of the function
+/// containing the closure `name`, such as `app::spawn` for
+/// `framework::register<app::spawn>::{{closure}}`.
+#[cfg(feature = "process")]
+fn wrapped_function(name: &str) -> Option<&str> {
+    let segments = path_segments(name);
+    let is_closure = |segment: &&str| segment.starts_with("{closure") || *segment == "{{closure}}";
+    if !segments.last().is_some_and(is_closure) {
+        return None;
+    }
+    // Closures can be nested inside other closures of the same function
+    let parent = segments.iter().rev().find(|segment| !is_closure(segment))?;
+    let start = parent.find('<').filter(|&it| it > 0)?;
+    let (arguments, _) = generic_arguments(&parent[start + 1..])?;
+    let mut functions = arguments.into_iter().filter(|argument| {
+        function_likelihood(unwrap_fn_item_type(argument)) == FunctionLikelihood::Plausible
+    });
+    match (functions.next(), functions.next()) {
+        (Some(function), None) => Some(function),
+        _ => None,
+    }
+}
+
+/// The names of the primitive types.
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f16", "f32", "f64", "f128",
+];
+
+/// The prefixes of types which aren't paths, as function item types always
+/// are: references, pointers, slices, arrays, tuples, the never type, trait
+/// objects, `impl Trait` and fn pointers.
+pub(crate) const NON_PATH_PREFIXES: &[&str] = &[
+    "&", "*", "[", "(", "!", "dyn ", "impl ", "fn(", "unsafe ", "extern ",
+];
+
+/// A cheap check of whether `name` could plausibly be the type name of a
+/// function item type, used to avoid a full scan for names which cannot be
+/// found.
+pub(crate) fn function_likelihood(name: &str) -> FunctionLikelihood {
+    let name = name.trim();
+    if name.is_empty() || NON_PATH_PREFIXES.iter().any(|it| name.starts_with(it)) {
+        return FunctionLikelihood::Impossible;
+    }
+    let segments = path_segments(name);
+    // All items other than primitives are printed with at least their crate
+    // name, but `#[no_mangle]` functions can also be found by their symbol
+    if segments.len() < 2 && (PRIMITIVES.contains(&name) || !is_symbol_name(name)) {
+        return FunctionLikelihood::Impossible;
+    }
+    let last = segment_ident(segments[segments.len() - 1]);
+    if last.starts_with(|c: char| c.is_uppercase()) {
+        return FunctionLikelihood::Unlikely;
+    }
+    FunctionLikelihood::Plausible
+}
+
+/// Whether `name` could be the symbol of a `#[no_mangle]` function, which is
+/// an identifier.
+fn is_symbol_name(name: &str) -> bool {
+    !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,923 @@
+//! Finding functions by name with the accelerator tables of DWARF debug info, `.debug_names`
+//! (written by compilers for DWARF 5) and `.gdb_index` (added by `gdb-add-index`, or by linkers
+//! with `--gdb-index`), rather than by reading every function.
+//!
+//! `gimli` doesn't read either table, so they are read here. `.debug_names` maps the `DW_AT_name`
+//! of each DIE (such as `second` for `my_crate::nested::second`) to its offset, and `.gdb_index`
+//! maps qualified names (`my_crate::nested::second`) to the compilation units which define them,
+//! which are then searched for subprograms with that `DW_AT_name`.
+//!
+//! Declaration sites are chosen from the line records of functions rather than from
+//! `DW_AT_decl_line`, so the [`Function`]s of these subprograms (with their inlinees) are built in
+//! the same way as `symbolic_debuginfo`'s `DwarfDebugSession::functions` builds them, and both
+//! find the same functions with the same lines. Subprograms containing other subprograms, which
+//! it reads as separate functions, aren't built here, and instead fail the whole lookup, so that
+//! the caller falls back to reading every function.
+//!
+//! Unlike `DwarfDebugSession`, this also reads `.debug_addr`, which the DWARF 5 written by LLVM
+//! uses for the addresses of functions, so functions in DWARF 5 can only be found with the
+//! tables.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use symbolic_common::{Language, Name, NameMangling};
+use symbolic_debuginfo::dwarf::gimli::{
+    self, AttributeValue, DebugInfoOffset, DebugStrOffset, EndianSlice, Format, LittleEndian,
+    Reader as _, RunTimeEndian, SectionId, UnitOffset,
+};
+use symbolic_debuginfo::dwarf::Dwarf;
+use symbolic_debuginfo::{FileInfo, Function, LineInfo, SymbolMap};
+
+use super::units::dwarf_language;
+
+type Reader<'a> = EndianSlice<'a, RunTimeEndian>;
+
+/// A DIE, as the offset of its unit in `.debug_info` and its offset in the unit.
+type DieOffset = (usize, usize);
+
+/// Call `f` with the function of each subprogram which the accelerator tables of `dwarf` say is
+/// named `qualified_name` (in `.gdb_index`) or `name` (its `DW_AT_name`, in `.debug_names`),
+/// returning the number of functions.
+///
+/// Returns `None` without calling `f` if neither table can be read, or if one of the
+/// subprograms can't be built as it is when reading every function.
+pub(super) fn indexed_functions<'data>(
+    dwarf: &impl Dwarf<'data>,
+    symbols: &SymbolMap<'data>,
+    load_address: u64,
+    qualified_name: &str,
+    name: &str,
+    f: &mut dyn FnMut(Function<'_>),
+) -> Option<usize> {
+    let debug_names = dwarf.section("debug_names");
+    let gdb_index = dwarf.section("gdb_index");
+    if debug_names.is_none() && gdb_index.is_none() {
+        return None;
+    }
+    let endian = dwarf.endianity();
+    // Sections can be decompressed, so are loaded before being borrowed
+    let sections = gimli::Dwarf::load(|id: SectionId| {
+        let data = dwarf
+            .section(&id.name()[1..])
+            .map_or(Cow::Borrowed(&[][..]), |section| section.data);
+        Ok::<_, gimli::Error>(data)
+    })
+    .ok()?;
+    let dwarf = sections.borrow(|data| EndianSlice::new(data, endian));
+    let dies = match (debug_names, gdb_index) {
+        (Some(section), _) => {
+            debug_names_lookup(&dwarf, EndianSlice::new(&section.data, endian), name)
+        }
+        (None, Some(section)) => {
+            gdb_index_lookup(&section.data, qualified_name).and_then(|units| {
+                units.map_or(Ok(None), |units| subprograms_named(&dwarf, &units, name))
+            })
+        }
+        (None, None) => return None,
+    };
+    let functions = build_functions(&dwarf, symbols, load_address, dies.ok()??).ok()??;
+    let count = functions.len();
+    functions.into_iter().for_each(f);
+    Some(count)
+}
+
+/// The subprograms in the name indexes of the `.debug_names` section `section` named `name`, or
+/// `None` if an index can't be read.
+fn debug_names_lookup(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    mut section: Reader<'_>,
+    name: &str,
+) -> gimli::Result<Option<Vec<DieOffset>>> {
+    let hash = debug_names_hash(name);
+    let mut dies = Vec::new();
+    // Unless the linker merges them, there is an index for each compilation unit
+    while !section.is_empty() {
+        let (length, format) = section.read_initial_length()?;
+        let mut index = section.split(length)?;
+        if index.read_u16()? != 5 {
+            return Ok(None);
+        }
+        let _padding = index.read_u16()?;
+        let unit_count = index.read_u32()? as usize;
+        let local_type_unit_count = index.read_u32()? as usize;
+        let foreign_type_unit_count = index.read_u32()? as usize;
+        let bucket_count = index.read_u32()? as usize;
+        let name_count = index.read_u32()? as usize;
+        let abbreviations_size = index.read_u32()? as usize;
+        let augmentation_size = index.read_u32()? as usize;
+        index.skip(augmentation_size)?;
+        let word = usize::from(format.word_size());
+        let mut units = Vec::with_capacity(unit_count.min(index.len()));
+        for _ in 0..unit_count {
+            units.push(index.read_offset(format)?);
+        }
+        index.skip(local_type_unit_count * word + foreign_type_unit_count * 8)?;
+        let buckets = index.split(bucket_count * 4)?;
+        // Without buckets, the names aren't hashed, and have to be compared in turn
+        let hashes = index.split(if bucket_count > 0 { name_count * 4 } else { 0 })?;
+        let string_offsets = index.split(name_count * word)?;
+        let entry_offsets = index.split(name_count * word)?;
+        let Some(abbreviations) = name_abbreviations(index.split(abbreviations_size)?)? else {
+            return Ok(None);
+        };
+        let entry_pool = index;
+
+        let names = if bucket_count == 0 {
+            0..name_count
+        } else {
+            let bucket = hash as usize % bucket_count;
+            let first = at(buckets, bucket * 4)?.read_u32()? as usize;
+            // Names are numbered from 1, and 0 is an empty bucket
+            let mut end = first;
+            while first > 0 && end <= name_count {
+                let name_hash = at(hashes, (end - 1) * 4)?.read_u32()?;
+                if name_hash as usize % bucket_count != bucket {
+                    break;
+                }
+                end += 1;
+            }
+            first.saturating_sub(1)..end.saturating_sub(1)
+        };
+        for index in names {
+            if bucket_count > 0 && at(hashes, index * 4)?.read_u32()? != hash {
+                continue;
+            }
+            let string = at(string_offsets, index * word)?.read_offset(format)?;
+            if dwarf.debug_str.get_str(DebugStrOffset(string))?.slice() != name.as_bytes() {
+                continue;
+            }
+            let entries = at(entry_offsets, index * word)?.read_offset(format)?;
+            let mut entries = at(entry_pool, entries)?;
+            loop {
+                let code = entries.read_uleb128()?;
+                if code == 0 {
+                    break;
+                }
+                let Some(abbreviation) = abbreviations.get(&code) else {
+                    return Ok(None);
+                };
+                let (mut unit, mut die, mut in_type_unit) = (None, None, false);
+                for &(attribute, form) in &abbreviation.attributes {
+                    let Some(value) = read_index_value(&mut entries, form, format)? else {
+                        return Ok(None);
+                    };
+                    match attribute {
+                        gimli::DW_IDX_compile_unit => unit = Some(value as usize),
+                        gimli::DW_IDX_type_unit => in_type_unit = true,
+                        gimli::DW_IDX_die_offset => die = Some(value as usize),
+                        _ => {}
+                    }
+                }
+                if abbreviation.tag != gimli::DW_TAG_subprogram || in_type_unit {
+                    continue;
+                }
+                // The unit can be left out of indexes of a single unit
+                let unit = match (unit, units.as_slice()) {
+                    (Some(unit), _) => units.get(unit),
+                    (None, [unit]) => Some(unit),
+                    (None, _) => None,
+                };
+                let (Some(&unit), Some(die)) = (unit, die) else {
+                    return Ok(None);
+                };
+                dies.push((unit, die));
+            }
+        }
+    }
+    Ok(Some(dies))
+}
+
+/// An abbreviation of the entries of a `.debug_names` name index.
+struct NameAbbreviation {
+    tag: gimli::DwTag,
+    attributes: Vec<(gimli::DwIdx, gimli::DwForm)>,
+}
+
+/// The abbreviations of a name index, by their code, or `None` if they can't be read.
+fn name_abbreviations(
+    mut table: Reader<'_>,
+) -> gimli::Result<Option<BTreeMap<u64, NameAbbreviation>>> {
+    let mut abbreviations = BTreeMap::new();
+    loop {
+        let code = table.read_uleb128()?;
+        if code == 0 {
+            return Ok(Some(abbreviations));
+        }
+        let tag = gimli::DwTag(table.read_uleb128_u16()?);
+        let mut attributes = Vec::new();
+        loop {
+            let attribute = table.read_uleb128_u16()?;
+            let form = table.read_uleb128_u16()?;
+            if (attribute, form) == (0, 0) {
+                break;
+            }
+            attributes.push((gimli::DwIdx(attribute), gimli::DwForm(form)));
+        }
+        abbreviations.insert(code, NameAbbreviation { tag, attributes });
+    }
+}
+
+/// Read the value of an attribute of a name index entry with the form `form`, or `None` for
+/// forms which the attributes of entries don't use.
+fn read_index_value(
+    entries: &mut Reader<'_>,
+    form: gimli::DwForm,
+    format: Format,
+) -> gimli::Result<Option<u64>> {
+    Ok(Some(match form {
+        gimli::DW_FORM_flag_present => 1,
+        gimli::DW_FORM_data1 | gimli::DW_FORM_ref1 | gimli::DW_FORM_flag => {
+            entries.read_u8()?.into()
+        }
+        gimli::DW_FORM_data2 | gimli::DW_FORM_ref2 => entries.read_u16()?.into(),
+        gimli::DW_FORM_data4 | gimli::DW_FORM_ref4 => entries.read_u32()?.into(),
+        gimli::DW_FORM_data8 | gimli::DW_FORM_ref8 | gimli::DW_FORM_ref_sig8 => {
+            entries.read_u64()?
+        }
+        gimli::DW_FORM_udata | gimli::DW_FORM_ref_udata => entries.read_uleb128()?,
+        gimli::DW_FORM_sec_offset => entries.read_offset(format)? as u64,
+        _ => return Ok(None),
+    }))
+}
+
+/// The hash of `name` in `.debug_names`, the DJB hash of its case folded UTF-8.
+///
+/// Only ASCII is folded as in the DWARF standard, so lookups of other names can miss.
+fn debug_names_hash(name: &str) -> u32 {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .flat_map(|c| {
+            let mut buffer = [0; 4];
+            let len = c.encode_utf8(&mut buffer).len();
+            buffer.into_iter().take(len)
+        })
+        .fold(5381, |hash: u32, byte| {
+            hash.wrapping_mul(33).wrapping_add(byte.into())
+        })
+}
+
+/// The offsets in `.debug_info` of the compilation units which the `.gdb_index` section `data`
+/// says define `qualified_name`, or `None` if it can't be read.
+fn gdb_index_lookup(data: &[u8], qualified_name: &str) -> gimli::Result<Option<Vec<usize>>> {
+    let index = EndianSlice::new(data, LittleEndian);
+    let mut header = index;
+    // Earlier versions can be incomplete, and later ones have a different header
+    if !(7..=8).contains(&header.read_u32()?) {
+        return Ok(None);
+    }
+    let unit_list = header.read_u32()? as usize;
+    let type_unit_list = header.read_u32()? as usize;
+    let _address_area = header.read_u32()?;
+    let symbol_table = header.read_u32()? as usize;
+    let constant_pool = header.read_u32()? as usize;
+    let unit_count = type_unit_list.saturating_sub(unit_list) / 16;
+    let slots = constant_pool.saturating_sub(symbol_table) / 8;
+    if !slots.is_power_of_two() {
+        return Ok(None);
+    }
+    // The symbol table is a hash table with open addressing, as read by gdb
+    let hash = gdb_index_hash(qualified_name) as usize;
+    let mask = slots - 1;
+    let step = (hash.wrapping_mul(17) & mask) | 1;
+    let mut slot = hash & mask;
+    for _ in 0..slots {
+        let mut entry = at(index, symbol_table + slot * 8)?;
+        let (name, vector) = (entry.read_u32()? as usize, entry.read_u32()? as usize);
+        if (name, vector) == (0, 0) {
+            break;
+        }
+        let name = at(index, constant_pool + name)?.read_null_terminated_slice()?;
+        if name.slice() == qualified_name.as_bytes() {
+            let mut vector = at(index, constant_pool + vector)?;
+            let mut units = Vec::new();
+            for _ in 0..vector.read_u32()? {
+                // The other bits are the kind of symbol, and whether it's static
+                let unit = vector.read_u32()? as usize & 0xff_ffff;
+                // Later indices are type units
+                if unit < unit_count {
+                    units.push(at(index, unit_list + unit * 16)?.read_u64()? as usize);
+                }
+            }
+            return Ok(Some(units));
+        }
+        slot = (slot + step) & mask;
+    }
+    Ok(Some(Vec::new()))
+}
+
+/// The hash of `name` in `.gdb_index`, with ASCII letters lowercased.
+fn gdb_index_hash(name: &str) -> u32 {
+    name.bytes().fold(0, |hash: u32, byte| {
+        hash.wrapping_mul(67)
+            .wrapping_add(byte.to_ascii_lowercase().into())
+            .wrapping_sub(113)
+    })
+}
+
+/// `reader` from `offset` onwards.
+fn at<R: gimli::Reader<Offset = usize>>(mut reader: R, offset: usize) -> gimli::Result<R> {
+    reader.skip(offset)?;
+    Ok(reader)
+}
+
+/// The subprograms named `name` in the compilation units at the offsets `units`.
+fn subprograms_named(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    units: &[usize],
+    name: &str,
+) -> gimli::Result<Option<Vec<DieOffset>>> {
+    let mut dies = Vec::new();
+    for &offset in units {
+        let unit = dwarf.unit(
+            dwarf
+                .debug_info
+                .header_from_offset(DebugInfoOffset(offset))?,
+        )?;
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let Some(value) = entry.attr_value(gimli::DW_AT_name)? else {
+                continue;
+            };
+            if dwarf.attr_string(&unit, value)?.slice() == name.as_bytes() {
+                dies.push((offset, entry.offset().0));
+            }
+        }
+    }
+    Ok(Some(dies))
+}
+
+/// A subprogram, built as a function.
+enum Subprogram<'a> {
+    Function(Function<'a>),
+    /// The subprogram has no code (such as a declaration), so isn't a function
+    NoCode,
+    /// The subprogram can't be built as when reading every function
+    Unsupported,
+}
+
+/// Fold the functions at `depth` or deeper on `stack`, the functions inlined into each other
+/// in turn, into the inlinees of the function they were inlined into, as `symbolic_debuginfo`'s
+/// `FunctionStack::flush` does.
+fn fold_inlinees(stack: &mut Vec<(isize, Function<'_>)>, depth: isize) {
+    let cutoff = stack
+        .iter()
+        .position(|&(it, _)| it >= depth)
+        .unwrap_or(stack.len());
+    let mut inlinee = None;
+    for (_, mut function) in stack.drain(cutoff..).rev() {
+        function.inlinees.extend(inlinee.take());
+        inlinee = Some(function);
+    }
+    if let (Some(inlinee), Some((_, parent))) = (inlinee, stack.last_mut()) {
+        parent.inlinees.push(inlinee);
+    }
+}
+
+/// The functions of the subprograms `dies`, in the order they are in the debug info, or `None`
+/// if any can't be built.
+fn build_functions<'a>(
+    dwarf: &gimli::Dwarf<Reader<'a>>,
+    symbols: &SymbolMap<'_>,
+    load_address: u64,
+    dies: Vec<DieOffset>,
+) -> gimli::Result<Option<Vec<Function<'a>>>> {
+    let mut by_unit = BTreeMap::<usize, BTreeSet<usize>>::new();
+    for (unit, die) in dies {
+        by_unit.entry(unit).or_default().insert(die);
+    }
+    let mut functions = Vec::new();
+    let mut seen_ranges = BTreeSet::new();
+    for (offset, dies) in by_unit {
+        let unit = dwarf.unit(
+            dwarf
+                .debug_info
+                .header_from_offset(DebugInfoOffset(offset))?,
+        )?;
+        let mut entries = unit.entries();
+        let Some((_, root)) = entries.next_dfs()? else {
+            return Ok(None);
+        };
+        // Units which the linker removed have no functions
+        if unit.low_pc == 0 && root.attr(gimli::DW_AT_ranges)?.is_none() {
+            continue;
+        }
+        let language = match root.attr_value(gimli::DW_AT_language)? {
+            Some(AttributeValue::Language(language)) => dwarf_language(language),
+            _ => Language::Unknown,
+        };
+        // Dart's mangling is lossy, so its symbols aren't used for names
+        let use_symbols = !matches!(
+            root.attr_value(gimli::DW_AT_producer)?,
+            Some(AttributeValue::String(producer)) if producer.slice() == b"Dart VM"
+        );
+        let context = UnitContext {
+            dwarf,
+            unit: &unit,
+            language,
+            use_symbols,
+            lines: unit.line_program.clone().map(LineSequences::read),
+            load_address,
+        };
+        for die in dies {
+            match context.function(symbols, UnitOffset(die))? {
+                Subprogram::Function(function) => {
+                    // As when reading every function, copies of the same code are only kept once
+                    if seen_ranges.insert((function.address, function.size)) {
+                        functions.push(function);
+                    }
+                }
+                Subprogram::NoCode => {}
+                Subprogram::Unsupported => return Ok(None),
+            }
+        }
+    }
+    Ok(Some(functions))
+}
+
+/// A compilation unit, and what is read from it to build its functions.
+struct UnitContext<'a, 'u> {
+    dwarf: &'u gimli::Dwarf<Reader<'a>>,
+    unit: &'u gimli::Unit<Reader<'a>>,
+    language: Language,
+    use_symbols: bool,
+    lines: Option<LineSequences<'a>>,
+    load_address: u64,
+}
+
+impl<'a> UnitContext<'a, '_> {
+    /// The function of the subprogram at `offset`, with the functions inlined into it.
+    ///
+    /// This follows `symbolic_debuginfo`'s `DwarfUnit::functions`, so that the function has the
+    /// same inlinees and lines as when reading every function.
+    fn function(
+        &self,
+        symbols: &SymbolMap<'_>,
+        offset: UnitOffset,
+    ) -> gimli::Result<Subprogram<'a>> {
+        let mut entries = self.unit.entries_at_offset(offset)?;
+        let Some((_, entry)) = entries.next_dfs()? else {
+            return Ok(Subprogram::Unsupported);
+        };
+        if entry.tag() != gimli::DW_TAG_subprogram {
+            return Ok(Subprogram::NoCode);
+        }
+        let Ok(Some((ranges, _))) = self.ranges(entry) else {
+            return Ok(Subprogram::Unsupported);
+        };
+        if ranges.is_empty() {
+            return Ok(Subprogram::NoCode);
+        }
+        let address = ranges[0].begin.wrapping_sub(self.load_address);
+        // Symbols are more likely to have the full mangled name
+        let symbol_name = symbols
+            .lookup_exact(address)
+            .filter(|_| self.use_symbols)
+            .and_then(|symbol| symbol.name.clone());
+        let name = match symbol_name {
+            Some(symbol) => Name::new(
+                Cow::Owned(symbol.into_owned()),
+                NameMangling::Mangled,
+                self.language,
+            ),
+            None => match self.name(entry, 0) {
+                Ok(name) => name.unwrap_or_else(|| self.unnamed()),
+                Err(_) => return Ok(Subprogram::Unsupported),
+            },
+        };
+        // The subprogram and the functions inlined into the function on top, by their depth
+        let mut stack = vec![(0, self.new_function(name, &ranges, false))];
+        let (mut depth, mut skipped_depth) = (0, None);
+        while let Some((delta, child)) = entries.next_dfs()? {
+            depth += delta;
+            if depth <= 0 {
+                break;
+            }
+            // The functions inlined into inlined functions without code are skipped
+            match skipped_depth {
+                Some(skipped) if depth > skipped => continue,
+                _ => skipped_depth = None,
+            }
+            fold_inlinees(&mut stack, depth);
+            match child.tag() {
+                gimli::DW_TAG_inlined_subroutine => {}
+                // Nested subprograms are separate functions, in the order they are read
+                gimli::DW_TAG_subprogram => return Ok(Subprogram::Unsupported),
+                _ => continue,
+            }
+            let Ok(Some((ranges, call_site))) = self.ranges(child) else {
+                return Ok(Subprogram::Unsupported);
+            };
+            if ranges.is_empty() {
+                skipped_depth = Some(depth);
+                continue;
+            }
+            let name = match self.name(child, 0) {
+                Ok(name) => name.unwrap_or_else(|| self.unnamed()),
+                Err(_) => return Ok(Subprogram::Unsupported),
+            };
+            let Some((_, parent)) = stack.last_mut() else {
+                return Ok(Subprogram::Unsupported);
+            };
+            if let (Some(line), Some(file)) = call_site {
+                self.split_lines(&mut parent.lines, &ranges, file, line);
+            }
+            stack.push((depth, self.new_function(name, &ranges, true)));
+        }
+        fold_inlinees(&mut stack, 1);
+        match stack.pop() {
+            Some((_, function)) => Ok(Subprogram::Function(function)),
+            None => Ok(Subprogram::Unsupported),
+        }
+    }
+
+    /// The function named `name` with the code in the sorted `ranges`, without its inlinees.
+    fn new_function(&self, name: Name<'a>, ranges: &[gimli::Range], inline: bool) -> Function<'a> {
+        Function {
+            address: ranges[0].begin.wrapping_sub(self.load_address),
+            size: ranges.iter().map(|range| range.end - range.begin).sum(),
+            name,
+            compilation_dir: self.unit.comp_dir.map_or(&[][..], |dir| dir.slice()),
+            lines: self.resolve_lines(ranges),
+            inlinees: Vec::new(),
+            inline,
+        }
+    }
+
+    /// The name of functions without one.
+    fn unnamed(&self) -> Name<'a> {
+        Name::new("", NameMangling::Unmangled, self.language)
+    }
+
+    /// Point the records of `lines` (of the function which a function with the code in `ranges`
+    /// was inlined into) within `ranges` to the call site, `line` of the file at index `file`,
+    /// splitting records which are partly within them.
+    fn split_lines(
+        &self,
+        lines: &mut Vec<LineInfo<'a>>,
+        ranges: &[gimli::Range],
+        file: u64,
+        line: u64,
+    ) {
+        let file = self.file(file).unwrap_or_default();
+        let mut index = 0;
+        for range in ranges {
+            let begin = range.begin.wrapping_sub(self.load_address);
+            let end = range.end.wrapping_sub(self.load_address);
+            // The start of the range gets a record of its own, if no record covers it
+            if let Some(next) = lines.get(index) {
+                if next.address > begin {
+                    let record = LineInfo {
+                        address: begin,
+                        size: Some(end.min(next.address) - begin),
+                        file: file.clone(),
+                        line,
+                    };
+                    lines.insert(index, record);
+                    index += 1;
+                }
+            }
+            while index < lines.len() {
+                let record = &mut lines[index];
+                if record.address >= end {
+                    break;
+                }
+                index += 1;
+                // Earlier records are of the caller, or of another inlined function
+                let record_end = record.address + record.size.unwrap_or(0);
+                if record_end <= begin {
+                    continue;
+                }
+                let split = (record_end > end).then(|| {
+                    record.size = Some(end - record.address);
+                    LineInfo {
+                        address: end,
+                        size: Some(record_end - end),
+                        file: record.file.clone(),
+                        line: record.line,
+                    }
+                });
+                if record.address < begin {
+                    let max_size = begin - record.address;
+                    if record.size.is_none_or(|size| size > max_size) {
+                        record.size = Some(max_size);
+                    }
+                    let record = LineInfo {
+                        address: begin,
+                        size: Some(record_end.min(end) - begin),
+                        file: file.clone(),
+                        line,
+                    };
+                    lines.insert(index, record);
+                    index += 1;
+                } else {
+                    record.file = file.clone();
+                    record.line = line;
+                }
+                // The rest of a split record may need to be split again
+                if let Some(split) = split {
+                    lines.insert(index, split);
+                }
+            }
+            // The end of the range gets a record of its own, if no record covers it
+            if let Some(previous) = index.checked_sub(1).and_then(|index| lines.get(index)) {
+                let record_end = previous.address + previous.size.unwrap_or(0);
+                if record_end < end {
+                    let record = LineInfo {
+                        address: record_end,
+                        size: Some(end - record_end),
+                        file: file.clone(),
+                        line,
+                    };
+                    lines.insert(index, record);
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    /// The sorted address ranges of the code of `entry`, and the line and index of the file of
+    /// its call site if it was inlined, or `None` if it uses unsupported forms.
+    #[allow(clippy::type_complexity)]
+    fn ranges(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, Reader<'a>>,
+    ) -> gimli::Result<Option<(Vec<gimli::Range>, (Option<u64>, Option<u64>))>> {
+        let (mut low_pc, mut high_pc, mut size) = (None, None, None);
+        let mut call_site = (None, None);
+        let mut ranges = Vec::new();
+        let mut attributes = entry.attrs();
+        while let Some(attribute) = attributes.next()? {
+            match (attribute.name(), attribute.value()) {
+                (gimli::DW_AT_low_pc, AttributeValue::Addr(address)) => low_pc = Some(address),
+                (gimli::DW_AT_low_pc, AttributeValue::DebugAddrIndex(index)) => {
+                    low_pc = Some(self.dwarf.address(self.unit, index)?);
+                }
+                (gimli::DW_AT_high_pc, AttributeValue::Addr(address)) => high_pc = Some(address),
+                (gimli::DW_AT_high_pc, AttributeValue::Udata(udata)) => size = Some(udata),
+                (gimli::DW_AT_call_line, AttributeValue::Udata(line)) => call_site.0 = Some(line),
+                (gimli::DW_AT_call_file, AttributeValue::FileIndex(file)) => {
+                    call_site.1 = Some(file);
+                }
+                (
+                    gimli::DW_AT_low_pc
+                    | gimli::DW_AT_high_pc
+                    | gimli::DW_AT_call_line
+                    | gimli::DW_AT_call_file,
+                    _,
+                ) => return Ok(None),
+                (
+                    gimli::DW_AT_ranges | gimli::DW_AT_rnglists_base | gimli::DW_AT_start_scope,
+                    value,
+                ) => {
+                    let Some(mut list) = self.dwarf.attr_ranges(self.unit, value)? else {
+                        continue;
+                    };
+                    loop {
+                        match list.next() {
+                            // A range which begins at 0 is code removed by the linker
+                            Ok(Some(range)) if range.begin > 0 => ranges.push(range),
+                            Ok(Some(_)) => {}
+                            // Inverted ranges end the list, as in `symbolic_debuginfo`
+                            Ok(None) | Err(gimli::Error::InvalidAddressRange) => break,
+                            Err(error) => return Err(error),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if ranges.is_empty() {
+            // A `low_pc` of 0 is a function removed by the linker, and `u64::MAX` (or one less)
+            // are used for removed code too
+            let begin = low_pc.filter(|&it| it != 0 && it < u64::MAX - 1);
+            let end = match (high_pc, size) {
+                (Some(high_pc), _) => Some(high_pc),
+                (None, Some(size)) => begin.map(|begin| begin.wrapping_add(size)),
+                (None, None) => None,
+            };
+            if let (Some(begin), Some(end)) = (begin, end) {
+                if begin > end {
+                    return Ok(None);
+                }
+                if begin < end {
+                    ranges.push(gimli::Range { begin, end });
+                }
+            }
+        }
+        ranges.sort_by_key(|range| range.begin);
+        Ok(Some((ranges, call_site)))
+    }
+
+    /// The name of `entry` in the debug info, following its specification or abstract origin if
+    /// it has no name of its own. References to other units aren't followed, and are an error.
+    fn name(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<'_, '_, Reader<'a>>,
+        depth: usize,
+    ) -> gimli::Result<Option<Name<'a>>> {
+        let mut plain_name = None;
+        let mut reference = None;
+        let mut attributes = entry.attrs();
+        while let Some(attribute) = attributes.next()? {
+            match attribute.name() {
+                gimli::DW_AT_linkage_name | gimli::DW_AT_MIPS_linkage_name => {
+                    return Ok(self
+                        .string(attribute.value())
+                        .map(|name| Name::new(name, NameMangling::Mangled, self.language)));
+                }
+                gimli::DW_AT_name => plain_name = Some(attribute.value()),
+                gimli::DW_AT_specification | gimli::DW_AT_abstract_origin => {
+                    reference = Some(attribute.value());
+                }
+                _ => {}
+            }
+        }
+        if let Some(value) = plain_name {
+            return Ok(self
+                .string(value)
+                .map(|name| Name::new(name, NameMangling::Unmangled, self.language)));
+        }
+        match reference {
+            Some(AttributeValue::UnitRef(offset)) if offset != entry.offset() && depth < 8 => {
+                self.name(&self.unit.entry(offset)?, depth + 1)
+            }
+            Some(AttributeValue::UnitRef(_)) | None => Ok(None),
+            Some(_) => Err(gimli::Error::UnsupportedAttributeForm),
+        }
+    }
+
+    /// The string `value`, if it is one.
+    fn string(&self, value: AttributeValue<Reader<'a>>) -> Option<Cow<'a, str>> {
+        let string = self.dwarf.attr_string(self.unit, value).ok()?;
+        Some(String::from_utf8_lossy(string.slice()))
+    }
+
+    /// The line records of the code in `ranges`, split and merged as by `symbolic_debuginfo`.
+    fn resolve_lines(&self, ranges: &[gimli::Range]) -> Vec<LineInfo<'a>> {
+        let mut records = Vec::new();
+        let Some(lines) = &self.lines else {
+            return records;
+        };
+        for range in ranges {
+            let Some((first, rows)) = lines.rows(range).split_first() else {
+                continue;
+            };
+            let mut file_index = first.file_index;
+            let mut record = LineInfo {
+                address: range.begin.wrapping_sub(self.load_address),
+                size: first.size.map(|size| size + first.address - range.begin),
+                file: self.file(first.file_index).unwrap_or_default(),
+                line: first.line.unwrap_or(0),
+            };
+            for row in rows {
+                let line = row.line.unwrap_or(0);
+                // Rows of the same line are merged
+                if (file_index, record.line) == (row.file_index, line) {
+                    if let Some(size) = record.size.as_mut() {
+                        *size += row.size.unwrap_or(0);
+                    }
+                    continue;
+                }
+                records.push(record);
+                file_index = row.file_index;
+                record = LineInfo {
+                    address: row.address.wrapping_sub(self.load_address),
+                    size: row.size,
+                    file: self.file(row.file_index).unwrap_or_default(),
+                    line,
+                };
+            }
+            if let Some(size) = record.size.as_mut() {
+                *size = range.end.wrapping_sub(self.load_address) - record.address;
+            }
+            records.push(record);
+        }
+        records
+    }
+
+    /// The file at `index` in the line program of the unit.
+    fn file(&self, index: u64) -> Option<FileInfo<'a>> {
+        let lines = self.lines.as_ref()?;
+        let file = lines.header.file(index)?;
+        let slice = |value| Some(self.dwarf.attr_string(self.unit, value).ok()?.slice());
+        Some(FileInfo {
+            dir: file
+                .directory(&lines.header)
+                .and_then(slice)
+                .unwrap_or_default(),
+            name: slice(file.path_name()).unwrap_or_default(),
+        })
+    }
+}
+
+/// A row of a line program, as kept by `symbolic_debuginfo`.
+struct LineRow {
+    address: u64,
+    file_index: u64,
+    line: Option<u64>,
+    size: Option<u64>,
+}
+
+/// A sequence of rows of a line program.
+struct LineSequence {
+    start: u64,
+    end: u64,
+    rows: Vec<LineRow>,
+}
+
+/// The sequences of the line program of a unit, read in the same way as `symbolic_debuginfo`'s
+/// `DwarfLineProgram`, so that functions have the same lines as when reading every function.
+struct LineSequences<'a> {
+    header: gimli::LineProgramHeader<Reader<'a>>,
+    sequences: Vec<LineSequence>,
+}
+
+impl<'a> LineSequences<'a> {
+    fn read(program: gimli::IncompleteLineProgram<Reader<'a>>) -> Self {
+        let mut sequences = Vec::new();
+        let mut rows = Vec::<LineRow>::new();
+        let mut previous_address = 0;
+        let mut state_machine = program.rows();
+        while let Ok(Some((_, row))) = state_machine.next_row() {
+            let address = row.address();
+            // rustc has written sequences spanning from 0 for WASM, but code can't be at 0
+            if address == 0 {
+                continue;
+            }
+            if let Some(last) = rows.last_mut() {
+                if address >= last.address {
+                    last.size = Some(address - last.address);
+                }
+            }
+            if row.end_sequence() {
+                if !rows.is_empty() {
+                    sequences.push(LineSequence {
+                        start: rows[0].address,
+                        end: if address < previous_address {
+                            previous_address + 1
+                        } else {
+                            address
+                        },
+                        rows: std::mem::take(&mut rows),
+                    });
+                }
+                previous_address = 0;
+            } else if address >= previous_address {
+                let (file_index, line) = (row.file_index(), row.line().map(|it| it.get()));
+                match rows.last_mut() {
+                    // Later rows at the same address replace earlier ones
+                    Some(last) if last.address == address => {
+                        last.file_index = file_index;
+                        last.line = line;
+                    }
+                    _ => rows.push(LineRow {
+                        address,
+                        file_index,
+                        line,
+                        size: None,
+                    }),
+                }
+                previous_address = address;
+            }
+        }
+        if !rows.is_empty() {
+            // A sequence without an end is assumed to end after one more byte
+            sequences.push(LineSequence {
+                start: rows[0].address,
+                end: previous_address + 1,
+                rows,
+            });
+        }
+        sequences.sort_by_key(|sequence| sequence.start);
+        LineSequences {
+            header: state_machine.header().clone(),
+            sequences,
+        }
+    }
+
+    /// The rows covering `range`, from the first sequence which overlaps it.
+    fn rows(&self, range: &gimli::Range) -> &[LineRow] {
+        for sequence in &self.sequences {
+            if sequence.end <= range.begin || sequence.start > range.end {
+                continue;
+            }
+            let from = match sequence
+                .rows
+                .binary_search_by_key(&range.begin, |row| row.address)
+            {
+                Ok(index) => index,
+                Err(0) => continue,
+                Err(next) => next - 1,
+            };
+            let len = sequence.rows[from..]
+                .binary_search_by_key(&range.end, |row| row.address)
+                .unwrap_or_else(|it| it);
+            return &sequence.rows[from..from + len];
+        }
+        &[]
+    }
+}
@@ -0,0 +1,86 @@
+use std::iter::FusedIterator;
+
+use symbolic_debuginfo::FileFormat;
+
+use super::{peek, Object, ObjectError};
+
+/// A Unix `ar` archive, such as a static library (`.a`) or an rlib, which
+/// contains a named object file for each member.
+#[derive(Debug)]
+pub(super) struct ArArchive<'d> {
+    /// The name and data of each member which is an object file
+    members: Vec<(String, &'d [u8])>,
+}
+
+impl<'d> ArArchive<'d> {
+    /// Whether `data` starts with the `ar` magic.
+    pub fn test(data: &[u8]) -> bool {
+        data.starts_with(b"!<arch>\n")
+    }
+
+    pub fn parse(data: &'d [u8]) -> Result<Self, ObjectError> {
+        let archive = goblin::archive::Archive::parse(data).map_err(ObjectError::transparent)?;
+        let members = (0..archive.len())
+            .filter_map(|index| archive.get_at(index))
+            .filter_map(|member| {
+                let start = usize::try_from(member.offset).ok()?;
+                let member_data = data.get(start..)?.get(..member.size())?;
+                // Skip members which aren't objects, such as an rlib's metadata
+                (peek(member_data, false) != FileFormat::Unknown)
+                    .then(|| (member.extended_name().to_owned(), member_data))
+            })
+            .collect();
+        Ok(ArArchive { members })
+    }
+
+    /// The format of the first object, as `ar` isn't a [`FileFormat`].
+    pub fn file_format(&self) -> FileFormat {
+        self.members
+            .first()
+            .map_or(FileFormat::Unknown, |(_, data)| peek(data, false))
+    }
+
+    pub fn objects(&self) -> ArObjects<'d, '_> {
+        ArObjects {
+            members: self.members.iter(),
+        }
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn object_by_index(&self, index: usize) -> Result<Option<Object<'d>>, ObjectError> {
+        self.members
+            .get(index)
+            .map(|(_, data)| Object::parse(data))
+            .transpose()
+    }
+
+    pub fn object_name(&self, index: usize) -> Option<&str> {
+        self.members.get(index).map(|(name, _)| name.as_str())
+    }
+
+    pub fn is_multi(&self) -> bool {
+        true
+    }
+}
+
+pub(super) struct ArObjects<'d, 'a> {
+    members: std::slice::Iter<'a, (String, &'d [u8])>,
+}
+
+impl<'d> Iterator for ArObjects<'d, '_> {
+    type Item = Result<Object<'d>, ObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.members.next().map(|(_, data)| Object::parse(data))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.members.size_hint()
+    }
+}
+
+impl FusedIterator for ArObjects<'_, '_> {}
+impl ExactSizeIterator for ArObjects<'_, '_> {}
@@ -0,0 +1,1107 @@
+// Taken from https://github.com/getsentry/symbolic/blob/master/symbolic-debuginfo/src/object.rs but without breakpad,
+// due the breakpad support using a MPL-2.0 dependency, which is forbidden by bevy
+//! Generic wrappers over various object file formats.
+//!
+//! This is the format detection and dispatch used by this crate to read
+//! binary and debug files. It is adapted from [`symbolic_debuginfo`]'s
+//! `Object` and `Archive` types, which have the same API. However, those also
+//! support Breakpad symbol files, which requires a dependency licensed under
+//! the MPL-2.0 license. As this crate is used by bevy, which doesn't allow
+//! that license, Breakpad files are not supported here, and
+//! [`Archive::parse`] returns an error for them. Breakpad can also be
+//! disabled in `symbolic_debuginfo`, but its `Object` type is then not
+//! available.
+//!
+//! Portable PDB files (used by .NET) are also not supported, as the version of
+//! `symbolic_debuginfo` used here cannot parse them. [`Archive::peek`] reports
+//! them as [`FileFormat::Unknown`], so they are skipped when scanning. When
+//! upgrading to a version of `symbolic_debuginfo` which has
+//! `FileFormat::PortablePdb`, its variant should be added to each of the
+//! `match_inner!` based types in this module, as upstream does.
+//!
+//! Use this to inspect files directly, such as to walk their functions using
+//! [`ObjectLike::debug_session`]
+//! (which [`Object`] also provides as an inherent method).
+//!
+//! # Stability
+//!
+//! The types in this module are kept in line with `symbolic_debuginfo`, and
+//! expose its types (such as [`ElfObject`]
+//! and [`Function`]). Upgrading that
+//! dependency to a new major version will therefore be a breaking change for
+//! this module, even when the rest of this crate's API is unchanged. Within a
+//! version of `symbolic_debuginfo`, the API of this module will only be
+//! extended.
+//!
+//! This module is licensed under the MIT license only (with modifications
+//! also available under Apache-2.0), as described in the README.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+
+use symbolic_common::{clean_path, Arch, CodeId, DebugId};
+
+use symbolic_debuginfo::dwarf::*;
+use symbolic_debuginfo::elf::*;
+use symbolic_debuginfo::macho::*;
+use symbolic_debuginfo::pdb::*;
+use symbolic_debuginfo::pe::*;
+use symbolic_debuginfo::sourcebundle::*;
+use symbolic_debuginfo::wasm::*;
+use symbolic_debuginfo::*;
+mod accelerator;
+mod ar_archive;
+mod mono_archive;
+mod owned;
+mod pe_dwarf;
+mod subprograms;
+mod units;
+mod wasm_external;
+use ar_archive::{ArArchive, ArObjects};
+use mono_archive::{MonoArchive, MonoArchiveObjects};
+pub use owned::{OwnedDebugSession, OwnedObject};
+use pe_dwarf::PeDwarf;
+pub use units::{CompilationUnit, CompilationUnits, CrateTarget};
+
+macro_rules! match_inner {
+    ($value:expr, $ty:tt ($pat:pat) => $expr:expr) => {
+        match $value {
+            $ty::Elf($pat) => $expr,
+            $ty::MachO($pat) => $expr,
+            $ty::Pdb($pat) => $expr,
+            $ty::Pe($pat) => $expr,
+            $ty::SourceBundle($pat) => $expr,
+            $ty::Wasm($pat) => $expr,
+        }
+    };
+}
+
+macro_rules! map_inner {
+    ($value:expr, $from:tt($pat:pat) => $to:tt($expr:expr)) => {
+        match $value {
+            $from::Elf($pat) => $to::Elf($expr),
+            $from::MachO($pat) => $to::MachO($expr),
+            $from::Pdb($pat) => $to::Pdb($expr),
+            $from::Pe($pat) => $to::Pe($expr),
+            $from::SourceBundle($pat) => $to::SourceBundle($expr),
+            $from::Wasm($pat) => $to::Wasm($expr),
+        }
+    };
+}
+
+/// Internal representation of the object error type.
+#[derive(Debug)]
+enum ObjectErrorRepr {
+    /// The object file format is not supported.
+    UnsupportedObject,
+
+    /// The archive contains no objects.
+    EmptyArchive,
+
+    /// A transparent error from the inner object file type.
+    Transparent(Box<dyn Error + Send + Sync + 'static>),
+}
+
+/// An error when dealing with any kind of [`Object`](enum.Object.html).
+pub struct ObjectError {
+    repr: ObjectErrorRepr,
+}
+
+impl ObjectError {
+    /// Creates a new object error with the given representation.
+    fn new(repr: ObjectErrorRepr) -> Self {
+        Self { repr }
+    }
+
+    /// Creates a new object error for an archive which contains no objects.
+    fn empty_archive() -> Self {
+        Self::new(ObjectErrorRepr::EmptyArchive)
+    }
+
+    /// Whether this error is because the data isn't a supported object file format.
+    pub(crate) fn is_unsupported(&self) -> bool {
+        matches!(self.repr, ObjectErrorRepr::UnsupportedObject)
+    }
+
+    /// Creates a new object error from an arbitrary error payload.
+    fn transparent<E>(source: E) -> Self
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let repr = ObjectErrorRepr::Transparent(source.into());
+        Self { repr }
+    }
+}
+
+impl fmt::Debug for ObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repr {
+            ObjectErrorRepr::Transparent(ref inner) => fmt::Debug::fmt(inner, f),
+            _ => fmt::Debug::fmt(&self.repr, f),
+        }
+    }
+}
+
+impl fmt::Display for ObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repr {
+            ObjectErrorRepr::UnsupportedObject => write!(f, "unsupported object file format"),
+            ObjectErrorRepr::EmptyArchive => write!(f, "archive contains no objects"),
+            ObjectErrorRepr::Transparent(ref inner) => fmt::Display::fmt(inner, f),
+        }
+    }
+}
+
+impl Error for ObjectError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.repr {
+            ObjectErrorRepr::UnsupportedObject | ObjectErrorRepr::EmptyArchive => None,
+            ObjectErrorRepr::Transparent(ref inner) => inner.source(),
+        }
+    }
+}
+
+/// Tries to infer the object type from the start of the given buffer.
+///
+/// If `archive` is set to `true`, multi architecture objects will be allowed. Otherwise, only
+/// single-arch objects are checked.
+pub fn peek(data: &[u8], archive: bool) -> FileFormat {
+    if data.len() < 16 {
+        return FileFormat::Unknown;
+    }
+
+    if ElfObject::test(data) {
+        FileFormat::Elf
+    } else if PeObject::test(data) {
+        FileFormat::Pe
+    } else if PdbObject::test(data) {
+        FileFormat::Pdb
+    } else if SourceBundle::test(data) {
+        FileFormat::SourceBundle
+    } else if WasmObject::test(data) {
+        FileFormat::Wasm
+    } else {
+        let magic = goblin::mach::parse_magic_and_ctx(data, 0).map(|(magic, _)| magic);
+
+        match magic {
+            Ok(goblin::mach::fat::FAT_MAGIC) => {
+                use scroll::Pread;
+                if data.pread_with::<u32>(4, scroll::BE).is_ok()
+                    && archive
+                    && MachArchive::test(data)
+                {
+                    FileFormat::MachO
+                } else {
+                    FileFormat::Unknown
+                }
+            }
+            Ok(
+                goblin::mach::header::MH_CIGAM_64
+                | goblin::mach::header::MH_CIGAM
+                | goblin::mach::header::MH_MAGIC_64
+                | goblin::mach::header::MH_MAGIC,
+            ) => FileFormat::MachO,
+            _ => FileFormat::Unknown,
+        }
+    }
+}
+
+/// A generic object file providing uniform access to various file formats.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum Object<'data> {
+    /// Executable and Linkable Format, used on Linux.
+    Elf(ElfObject<'data>),
+    /// Mach Objects, used on macOS and iOS derivatives.
+    MachO(MachObject<'data>),
+    /// Program Database, the debug companion format on Windows.
+    Pdb(PdbObject<'data>),
+    /// Portable Executable, an extension of COFF used on Windows.
+    Pe(PeObject<'data>),
+    /// A source bundle.
+    SourceBundle(SourceBundle<'data>),
+    /// A WASM file.
+    Wasm(WasmObject<'data>),
+}
+
+impl<'data> Object<'data> {
+    /// Tries to infer the object type from the start of the given buffer.
+    ///
+    /// Unlike [`Archive::peek`], this returns [`FileFormat::Unknown`] for Mach-O fat files.
+    pub fn peek(data: &[u8]) -> FileFormat {
+        peek(data, false)
+    }
+
+    /// Tries to parse a single object from the given slice.
+    pub fn parse(data: &'data [u8]) -> Result<Self, ObjectError> {
+        macro_rules! parse_object {
+            ($kind:ident, $file:ident) => {
+                Object::$kind($file::parse(data).map_err(ObjectError::transparent)?)
+            };
+        }
+
+        let object = match Self::peek(data) {
+            FileFormat::Elf => parse_object!(Elf, ElfObject),
+            FileFormat::MachO => parse_object!(MachO, MachObject),
+            FileFormat::Pdb => parse_object!(Pdb, PdbObject),
+            FileFormat::Pe => parse_object!(Pe, PeObject),
+            FileFormat::SourceBundle => parse_object!(SourceBundle, SourceBundle),
+            FileFormat::Wasm => parse_object!(Wasm, WasmObject),
+            FileFormat::Unknown | FileFormat::Breakpad => {
+                return Err(ObjectError::new(ObjectErrorRepr::UnsupportedObject))
+            }
+        };
+
+        Ok(object)
+    }
+
+    /// The container format of this file, corresponding to the variant of this instance.
+    pub fn file_format(&self) -> FileFormat {
+        match *self {
+            Object::Elf(_) => FileFormat::Elf,
+            Object::MachO(_) => FileFormat::MachO,
+            Object::Pdb(_) => FileFormat::Pdb,
+            Object::Pe(_) => FileFormat::Pe,
+            Object::SourceBundle(_) => FileFormat::SourceBundle,
+            Object::Wasm(_) => FileFormat::Wasm,
+        }
+    }
+
+    /// The code identifier of this object.
+    ///
+    /// This is a platform-dependent string of variable length that _always_ refers to the code file
+    /// (e.g. executable or library), even if this object is a debug file. See the variants for the
+    /// semantics of this code identifier.
+    pub fn code_id(&self) -> Option<CodeId> {
+        match_inner!(self, Object(ref o) => o.code_id())
+    }
+
+    /// The debug information identifier of this object.
+    ///
+    /// For platforms that use different identifiers for their code and debug files, this _always_
+    /// refers to the debug file, regardless whether this object is a debug file or not.
+    pub fn debug_id(&self) -> DebugId {
+        match_inner!(self, Object(ref o) => o.debug_id())
+    }
+
+    /// The CPU architecture of this object.
+    pub fn arch(&self) -> Arch {
+        match_inner!(self, Object(ref o) => o.arch())
+    }
+
+    /// The kind of this object.
+    pub fn kind(&self) -> ObjectKind {
+        match_inner!(self, Object(ref o) => o.kind())
+    }
+
+    /// The address at which the image prefers to be loaded into memory.
+    pub fn load_address(&self) -> u64 {
+        match_inner!(self, Object(ref o) => o.load_address())
+    }
+
+    /// Determines whether this object exposes a public symbol table.
+    pub fn has_symbols(&self) -> bool {
+        match_inner!(self, Object(ref o) => o.has_symbols())
+    }
+
+    /// Returns an iterator over symbols in the public symbol table.
+    pub fn symbols(&self) -> SymbolIterator<'data, '_> {
+        map_inner!(self, Object(ref o) => SymbolIterator(o.symbols()))
+    }
+
+    /// Returns an ordered map of symbols in the symbol table.
+    pub fn symbol_map(&self) -> SymbolMap<'data> {
+        match_inner!(self, Object(ref o) => o.symbol_map())
+    }
+
+    /// Returns the symbol containing the given address, if any.
+    ///
+    /// This builds the [`symbol_map`](Self::symbol_map) on every call, so for repeated lookups,
+    /// build the map once and use [`SymbolMap::lookup`] instead.
+    pub fn symbol_by_address(&self, address: u64) -> Option<Symbol<'data>> {
+        self.symbol_map().lookup(address).cloned()
+    }
+
+    /// Determines whether this object contains debug information.
+    ///
+    /// For PE files, this includes DWARF debug info embedded by MinGW toolchains, when there is no
+    /// PDB reference.
+    pub fn has_debug_info(&self) -> bool {
+        if let Object::Pe(ref o) = *self {
+            if PeDwarf::new(o).is_some() {
+                return true;
+            }
+        }
+        match_inner!(self, Object(ref o) => o.has_debug_info())
+    }
+
+    /// Constructs a debugging session.
+    ///
+    /// A debugging session loads certain information from the object file and creates caches for
+    /// efficient access to various records in the debug information. Since this can be quite a
+    /// costly process, try to reuse the debugging session as long as possible.
+    ///
+    /// Objects that do not support debugging or do not contain debugging information return an
+    /// empty debug session. This only returns an error if constructing the debug session fails due
+    /// to invalid debug data in the object.
+    ///
+    /// Constructing this session will also work if the object does not contain debugging
+    /// information, in which case the session will be a no-op. This can be checked via
+    /// [`has_debug_info`](enum.Object.html#method.has_debug_info).
+    pub fn debug_session(&self) -> Result<ObjectDebugSession<'data>, ObjectError> {
+        match *self {
+            Object::Elf(ref o) => o
+                .debug_session()
+                .map(ObjectDebugSession::Dwarf)
+                .map_err(ObjectError::transparent),
+            Object::MachO(ref o) => o
+                .debug_session()
+                .map(ObjectDebugSession::Dwarf)
+                .map_err(ObjectError::transparent),
+            Object::Pdb(ref o) => o
+                .debug_session()
+                .map(ObjectDebugSession::Pdb)
+                .map_err(ObjectError::transparent),
+            Object::Pe(ref o) => match PeDwarf::new(o) {
+                Some(dwarf) => dwarf
+                    .debug_session()
+                    .map(ObjectDebugSession::Dwarf)
+                    .map_err(ObjectError::transparent),
+                None => o
+                    .debug_session()
+                    .map(ObjectDebugSession::Pe)
+                    .map_err(ObjectError::transparent),
+            },
+            Object::SourceBundle(ref o) => o
+                .debug_session()
+                .map(ObjectDebugSession::SourceBundle)
+                .map_err(ObjectError::transparent),
+            Object::Wasm(ref o) => o
+                .debug_session()
+                .map(ObjectDebugSession::Dwarf)
+                .map_err(ObjectError::transparent),
+        }
+    }
+
+    /// Determines whether this object contains stack unwinding information.
+    pub fn has_unwind_info(&self) -> bool {
+        match_inner!(self, Object(ref o) => o.has_unwind_info())
+    }
+
+    /// Determines whether this object contains embedded source
+    pub fn has_sources(&self) -> bool {
+        match_inner!(self, Object(ref o) => o.has_sources())
+    }
+
+    /// The path or URL of the file containing the debug info of a WASM module whose DWARF was split
+    /// into a separate file, from its `external_debug_info` section.
+    ///
+    /// This is `None` for all other objects. Relative paths are relative to the module's file.
+    pub fn external_debug_info(&self) -> Option<&'data str> {
+        match *self {
+            Object::Wasm(ref o) => wasm_external::external_debug_info(o.data()),
+            _ => None,
+        }
+    }
+
+    /// The DWARF compilation units of the object, or the modules of a PDB, which can be used to
+    /// find which unit a [`Function`] was compiled in.
+    ///
+    /// This reads the debug info on every call. It is empty for objects without DWARF debug info
+    /// which aren't PDBs.
+    pub fn compilation_units(&self) -> CompilationUnits {
+        match *self {
+            Object::Elf(ref o) => CompilationUnits::read(o, o.load_address()),
+            Object::MachO(ref o) => CompilationUnits::read(o, o.load_address()),
+            Object::Wasm(ref o) => CompilationUnits::read(o, o.load_address()),
+            Object::Pe(ref o) => PeDwarf::new(o).map_or_else(CompilationUnits::default, |dwarf| {
+                CompilationUnits::read(&dwarf, o.load_address())
+            }),
+            Object::Pdb(ref o) => CompilationUnits::read_pdb(o),
+            _ => CompilationUnits::default(),
+        }
+    }
+
+    /// The name of the DWARF subprogram whose code starts at `address`, relative to the object's
+    /// load address (as in [`Function::address`]), as written by the compiler (`DW_AT_name`).
+    ///
+    /// Unlike the names of [`Function`]s, which are read from symbols where possible, this includes
+    /// the generic arguments and closure disambiguators of Rust functions, without their path, such
+    /// as `call_once<my_crate::main::{closure_env#0}, ()>` or `{closure#0}`. This reads the debug
+    /// info on every call, and is `None` for objects without DWARF debug info.
+    pub fn subprogram_name(&self, address: u64) -> Option<String> {
+        match *self {
+            Object::Elf(ref o) => subprograms::subprogram_name(o, o.load_address(), address),
+            Object::MachO(ref o) => subprograms::subprogram_name(o, o.load_address(), address),
+            Object::Wasm(ref o) => subprograms::subprogram_name(o, o.load_address(), address),
+            Object::Pe(ref o) => PeDwarf::new(o)
+                .and_then(|dwarf| subprograms::subprogram_name(&dwarf, o.load_address(), address)),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` with each function which the DWARF accelerator tables of the object say is
+    /// named `qualified_name` or `name`, returning the number of functions, to find functions
+    /// without reading all of them.
+    ///
+    /// `.debug_names` (written by compilers for DWARF 5) names functions by their `DW_AT_name`,
+    /// which is `name`, such as `second` or `{closure#0}`. `.gdb_index` (added by
+    /// `gdb-add-index`, or by linkers with `--gdb-index`) names them by their path, which is
+    /// `qualified_name`, such as `my_crate::nested::second`. The functions are the same as those
+    /// returned by [`ObjectDebugSession::functions`], but other functions with the same name can
+    /// be returned too, so their names still need to be compared.
+    ///
+    /// Returns `None` without calling `f` if the object has neither table, or some of the
+    /// functions can't be read from them (such as those containing nested subprograms). Only
+    /// the tables of linked ELF files are read. The tables can also be incomplete, so if a
+    /// function isn't found, it may still be found by reading every function.
+    ///
+    /// This reads the addresses of functions in the DWARF 5 written by LLVM (such as by rustc
+    /// with `-C dwarf-version=5`), which [`ObjectDebugSession::functions`] can't, so the
+    /// functions of these objects can only be found by name.
+    pub fn indexed_functions(
+        &self,
+        qualified_name: &str,
+        name: &str,
+        mut f: impl FnMut(Function<'_>),
+    ) -> Option<usize> {
+        match *self {
+            // The offsets in the tables of relocatable objects haven't been relocated
+            Object::Elf(ref o) if o.kind() != ObjectKind::Relocatable => {
+                accelerator::indexed_functions(
+                    o,
+                    &o.symbol_map(),
+                    o.load_address(),
+                    qualified_name,
+                    name,
+                    &mut f,
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Looks up the embedded source contents of the file at `path`.
+    ///
+    /// This creates a debug session on every call, so for repeated lookups, create the session
+    /// once and use [`ObjectDebugSession::extract_source`] instead.
+    pub fn extract_source(&self, path: &str) -> Option<String> {
+        if !self.has_sources() {
+            return None;
+        }
+        self.debug_session().ok()?.extract_source(path)
+    }
+
+    /// Determines whether this object is malformed and was only partially parsed
+    pub fn is_malformed(&self) -> bool {
+        match_inner!(self, Object(ref o) => o.is_malformed())
+    }
+}
+
+// impl<'slf, 'data: 'slf> AsSelf<'slf> for Object<'data> {
+//     type Ref = Object<'slf>;
+
+//     fn as_self(&'slf self) -> &Self::Ref {
+//         unsafe { std::mem::transmute(self) }
+//     }
+// }
+
+impl<'data: 'object, 'object> ObjectLike<'data, 'object> for Object<'data> {
+    type Error = ObjectError;
+    type Session = ObjectDebugSession<'data>;
+    type SymbolIterator = SymbolIterator<'data, 'object>;
+
+    fn file_format(&self) -> FileFormat {
+        self.file_format()
+    }
+
+    fn code_id(&self) -> Option<CodeId> {
+        self.code_id()
+    }
+
+    fn debug_id(&self) -> DebugId {
+        self.debug_id()
+    }
+
+    fn arch(&self) -> Arch {
+        self.arch()
+    }
+
+    fn kind(&self) -> ObjectKind {
+        self.kind()
+    }
+
+    fn load_address(&self) -> u64 {
+        self.load_address()
+    }
+
+    fn has_symbols(&self) -> bool {
+        self.has_symbols()
+    }
+
+    fn symbol_map(&self) -> SymbolMap<'data> {
+        self.symbol_map()
+    }
+
+    fn symbols(&'object self) -> Self::SymbolIterator {
+        self.symbols()
+    }
+
+    fn has_debug_info(&self) -> bool {
+        self.has_debug_info()
+    }
+
+    fn debug_session(&self) -> Result<Self::Session, Self::Error> {
+        self.debug_session()
+    }
+
+    fn has_unwind_info(&self) -> bool {
+        self.has_unwind_info()
+    }
+
+    fn has_sources(&self) -> bool {
+        self.has_sources()
+    }
+
+    fn is_malformed(&self) -> bool {
+        self.is_malformed()
+    }
+}
+
+/// A generic debugging session.
+#[allow(clippy::large_enum_variant)]
+#[allow(missing_docs)]
+pub enum ObjectDebugSession<'d> {
+    Dwarf(DwarfDebugSession<'d>),
+    Pdb(PdbDebugSession<'d>),
+    Pe(PeDebugSession<'d>),
+    SourceBundle(SourceBundleDebugSession<'d>),
+}
+
+impl<'d> ObjectDebugSession<'d> {
+    /// Returns an iterator over all functions in this debug file.
+    ///
+    /// Functions are iterated in the order they are declared in their compilation units. The
+    /// functions yielded by this iterator include all inlinees and line records resolved.
+    ///
+    /// Note that the iterator holds a mutable borrow on the debug session, which allows it to use
+    /// caches and optimize resources while resolving function and line information.
+    pub fn functions(&self) -> ObjectFunctionIterator<'_> {
+        match *self {
+            ObjectDebugSession::Dwarf(ref s) => ObjectFunctionIterator::Dwarf(s.functions()),
+            ObjectDebugSession::Pdb(ref s) => ObjectFunctionIterator::Pdb(s.functions()),
+            ObjectDebugSession::Pe(ref s) => ObjectFunctionIterator::Pe(s.functions()),
+            ObjectDebugSession::SourceBundle(ref s) => {
+                ObjectFunctionIterator::SourceBundle(s.functions())
+            }
+        }
+    }
+
+    /// Returns the function whose code contains the given address, relative to the object's
+    /// load address (SVMA).
+    ///
+    /// Functions whose code is split into several ranges (such as into hot and cold parts) are
+    /// found if any of their ranges contains the address. Inlined functions are not returned
+    /// directly, but are found in the `inlinees` of the returned function.
+    ///
+    /// `symbolic_debuginfo` does not provide an index of functions by address for any of the
+    /// supported formats, so this searches every function. For repeated lookups, iterate over
+    /// [`functions`](Self::functions) once instead.
+    pub fn function_by_address(&self, address: u64) -> Option<Function<'_>> {
+        let mut fallback = None;
+        // Functions which fail to parse are skipped, as in the rest of this crate
+        for function in self.functions().flatten() {
+            let in_lines = function.lines.iter().any(|line| {
+                let end = line.address + line.size.unwrap_or(1);
+                (line.address..end).contains(&address)
+            });
+            if in_lines {
+                return Some(function);
+            }
+            // The size of a function is the total size of its ranges, so this range can overlap
+            // other functions if it was split. Only use it if no line records match
+            let end = function.address + function.size;
+            if fallback.is_none() && (function.address..end).contains(&address) {
+                fallback = Some(function);
+            }
+        }
+        fallback
+    }
+
+    /// Returns an iterator over all source files referenced by this debug file.
+    pub fn files(&self) -> ObjectFileIterator<'_> {
+        match *self {
+            ObjectDebugSession::Dwarf(ref s) => ObjectFileIterator::Dwarf(s.files()),
+            ObjectDebugSession::Pdb(ref s) => ObjectFileIterator::Pdb(s.files()),
+            ObjectDebugSession::Pe(ref s) => ObjectFileIterator::Pe(s.files()),
+            ObjectDebugSession::SourceBundle(ref s) => ObjectFileIterator::SourceBundle(s.files()),
+        }
+    }
+
+    /// Looks up a file's source contents by its full canonicalized path.
+    ///
+    /// The given path must be canonicalized.
+    pub fn source_by_path(&self, path: &str) -> Result<Option<Cow<'_, str>>, ObjectError> {
+        match *self {
+            ObjectDebugSession::Dwarf(ref s) => {
+                s.source_by_path(path).map_err(ObjectError::transparent)
+            }
+            ObjectDebugSession::Pdb(ref s) => {
+                s.source_by_path(path).map_err(ObjectError::transparent)
+            }
+            ObjectDebugSession::Pe(ref s) => {
+                s.source_by_path(path).map_err(ObjectError::transparent)
+            }
+            ObjectDebugSession::SourceBundle(ref s) => {
+                s.source_by_path(path).map_err(ObjectError::transparent)
+            }
+        }
+    }
+
+    /// Looks up the embedded source contents of the file at `path`, such as from a
+    /// [`SourceBundle`].
+    ///
+    /// Unlike [`source_by_path`](Self::source_by_path), `path` is canonicalized before looking it
+    /// up. Relative paths (such as those in a [`DeclarationSite`](crate::DeclarationSite) for a
+    /// crate in the current workspace) match any file whose absolute path ends with them.
+    pub fn extract_source(&self, path: &str) -> Option<String> {
+        let path = clean_path(path);
+        if let Ok(Some(source)) = self.source_by_path(&path) {
+            return Some(source.into_owned());
+        }
+        if is_absolute_path(&path) {
+            return None;
+        }
+        let path = path.replace('\\', "/");
+        let full_path = self
+            .files()
+            .flatten()
+            .map(|file| file.abs_path_str())
+            .find(|it| {
+                it.replace('\\', "/")
+                    .strip_suffix(path.as_str())
+                    .is_some_and(|it| it.ends_with('/'))
+            })?;
+        Some(self.source_by_path(&full_path).ok()??.into_owned())
+    }
+}
+
+/// Whether `path` is absolute on either Unix or Windows, as debug files can be from either.
+fn is_absolute_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with(['/', '\\'])
+        || (bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+impl<'session> DebugSession<'session> for ObjectDebugSession<'_> {
+    type Error = ObjectError;
+    type FunctionIterator = ObjectFunctionIterator<'session>;
+    type FileIterator = ObjectFileIterator<'session>;
+
+    fn functions(&'session self) -> Self::FunctionIterator {
+        self.functions()
+    }
+
+    fn files(&'session self) -> Self::FileIterator {
+        self.files()
+    }
+
+    fn source_by_path(&self, path: &str) -> Result<Option<Cow<'_, str>>, Self::Error> {
+        self.source_by_path(path)
+    }
+}
+
+/// An iterator over functions in an [`Object`](enum.Object.html).
+#[allow(missing_docs)]
+pub enum ObjectFunctionIterator<'s> {
+    Dwarf(DwarfFunctionIterator<'s>),
+    Pdb(PdbFunctionIterator<'s>),
+    Pe(PeFunctionIterator<'s>),
+    SourceBundle(SourceBundleFunctionIterator<'s>),
+}
+
+impl<'s> Iterator for ObjectFunctionIterator<'s> {
+    type Item = Result<Function<'s>, ObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            ObjectFunctionIterator::Dwarf(ref mut i) => {
+                Some(i.next()?.map_err(ObjectError::transparent))
+            }
+            ObjectFunctionIterator::Pdb(ref mut i) => {
+                Some(i.next()?.map_err(ObjectError::transparent))
+            }
+            ObjectFunctionIterator::Pe(ref mut i) => {
+                Some(i.next()?.map_err(ObjectError::transparent))
+            }
+            ObjectFunctionIterator::SourceBundle(ref mut i) => {
+                Some(i.next()?.map_err(ObjectError::transparent))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            ObjectFunctionIterator::Dwarf(ref i) => i.size_hint(),
+            ObjectFunctionIterator::Pdb(ref i) => i.size_hint(),
+            ObjectFunctionIterator::Pe(ref i) => i.size_hint(),
+            ObjectFunctionIterator::SourceBundle(ref i) => i.size_hint(),
+        }
+    }
+}
+
+// This and the other session and symbol iterators are not `FusedIterator`s, as the iterators of
+// some formats may continue after returning `None` (such as after an error).
+
+/// An iterator over source files in an [`Object`](enum.Object.html).
+#[allow(missing_docs)]
+#[allow(clippy::large_enum_variant)]
+pub enum ObjectFileIterator<'s> {
+    Dwarf(DwarfFileIterator<'s>),
+    Pdb(PdbFileIterator<'s>),
+    Pe(PeFileIterator<'s>),
+    SourceBundle(SourceBundleFileIterator<'s>),
+}
+
+impl<'s> Iterator for ObjectFileIterator<'s> {
+    type Item = Result<FileEntry<'s>, ObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            ObjectFileIterator::Dwarf(ref mut i) => {
+                Some(i.next()?.map_err(ObjectError::transparent))
+            }
+            ObjectFileIterator::Pdb(ref mut i) => Some(i.next()?.map_err(ObjectError::transparent)),
+            ObjectFileIterator::Pe(ref mut i) => Some(i.next()?.map_err(ObjectError::transparent)),
+            ObjectFileIterator::SourceBundle(ref mut i) => {
+                Some(i.next()?.map_err(ObjectError::transparent))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            ObjectFileIterator::Dwarf(ref i) => i.size_hint(),
+            ObjectFileIterator::Pdb(ref i) => i.size_hint(),
+            ObjectFileIterator::Pe(ref i) => i.size_hint(),
+            ObjectFileIterator::SourceBundle(ref i) => i.size_hint(),
+        }
+    }
+}
+
+/// A generic symbol iterator
+#[allow(missing_docs)]
+pub enum SymbolIterator<'data, 'object> {
+    Elf(ElfSymbolIterator<'data, 'object>),
+    MachO(MachOSymbolIterator<'data>),
+    Pdb(PdbSymbolIterator<'data, 'object>),
+    Pe(PeSymbolIterator<'data, 'object>),
+    SourceBundle(SourceBundleSymbolIterator<'data>),
+    Wasm(WasmSymbolIterator<'data, 'object>),
+}
+
+impl<'data, 'object> Iterator for SymbolIterator<'data, 'object> {
+    type Item = Symbol<'data>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match_inner!(self, SymbolIterator(ref mut iter) => iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match_inner!(self, SymbolIterator(ref iter) => iter.size_hint())
+    }
+}
+
+#[derive(Debug)]
+enum ArchiveInner<'d> {
+    Ar(ArArchive<'d>),
+    Elf(MonoArchive<'d, ElfObject<'d>>),
+    MachO(MachArchive<'d>),
+    Pdb(MonoArchive<'d, PdbObject<'d>>),
+    Pe(MonoArchive<'d, PeObject<'d>>),
+    SourceBundle(MonoArchive<'d, SourceBundle<'d>>),
+    Wasm(MonoArchive<'d, WasmObject<'d>>),
+}
+
+/// A generic archive that can contain one or more object files.
+///
+/// Effectively, this will only contain a single object for all file types other than `MachO` and
+/// `ar`. Mach objects can either be single object files or so-called _fat_ files that contain
+/// multiple objects per architecture. Unix `ar` archives (static libraries and rlibs) contain an
+/// object for each member which is an object file, whose names are available from
+/// [`object_name`](Self::object_name).
+///
+/// The members of `ar` archives are usually relocatable objects, whose debug info hasn't been
+/// relocated. Their functions can be found, but often without any line records.
+#[derive(Debug)]
+pub struct Archive<'d>(ArchiveInner<'d>);
+
+impl<'d> Archive<'d> {
+    /// Tries to infer the object archive type from the start of the given buffer.
+    pub fn peek(data: &[u8]) -> FileFormat {
+        peek(data, true)
+    }
+
+    /// Tries to parse a generic archive from the given slice.
+    pub fn parse(data: &'d [u8]) -> Result<Self, ObjectError> {
+        if ArArchive::test(data) {
+            return ArArchive::parse(data).map(|it| Archive(ArchiveInner::Ar(it)));
+        }
+
+        let archive = match Self::peek(data) {
+            FileFormat::Elf => Archive(ArchiveInner::Elf(MonoArchive::new(data))),
+            FileFormat::MachO => {
+                let inner = MachArchive::parse(data)
+                    .map(ArchiveInner::MachO)
+                    .map_err(ObjectError::transparent)?;
+                Archive(inner)
+            }
+            FileFormat::Pdb => Archive(ArchiveInner::Pdb(MonoArchive::new(data))),
+            FileFormat::Pe => Archive(ArchiveInner::Pe(MonoArchive::new(data))),
+            FileFormat::SourceBundle => Archive(ArchiveInner::SourceBundle(MonoArchive::new(data))),
+            FileFormat::Wasm => Archive(ArchiveInner::Wasm(MonoArchive::new(data))),
+            FileFormat::Unknown | FileFormat::Breakpad => {
+                return Err(ObjectError::new(ObjectErrorRepr::UnsupportedObject))
+            }
+        };
+
+        Ok(archive)
+    }
+
+    /// The container format of this file.
+    ///
+    /// For `ar` archives, this is the format of their first object, or [`FileFormat::Unknown`] if
+    /// they contain no objects.
+    pub fn file_format(&self) -> FileFormat {
+        match self.0 {
+            ArchiveInner::Ar(ref a) => a.file_format(),
+            ArchiveInner::Elf(_) => FileFormat::Elf,
+            ArchiveInner::MachO(_) => FileFormat::MachO,
+            ArchiveInner::Pdb(_) => FileFormat::Pdb,
+            ArchiveInner::Pe(_) => FileFormat::Pe,
+            ArchiveInner::SourceBundle(_) => FileFormat::SourceBundle,
+            ArchiveInner::Wasm(_) => FileFormat::Wasm,
+        }
+    }
+
+    /// Returns an iterator over all objects contained in this archive.
+    pub fn objects(&self) -> ObjectIterator<'d, '_> {
+        ObjectIterator(match self.0 {
+            ArchiveInner::Ar(ref a) => ObjectIteratorInner::Ar(a.objects()),
+            ArchiveInner::Elf(ref a) => ObjectIteratorInner::Elf(a.objects()),
+            ArchiveInner::MachO(ref a) => ObjectIteratorInner::MachO(a.objects()),
+            ArchiveInner::Pdb(ref a) => ObjectIteratorInner::Pdb(a.objects()),
+            ArchiveInner::Pe(ref a) => ObjectIteratorInner::Pe(a.objects()),
+            ArchiveInner::SourceBundle(ref a) => ObjectIteratorInner::SourceBundle(a.objects()),
+            ArchiveInner::Wasm(ref a) => ObjectIteratorInner::Wasm(a.objects()),
+        })
+    }
+
+    /// Returns the number of objects in this archive.
+    pub fn object_count(&self) -> usize {
+        match self.0 {
+            ArchiveInner::Ar(ref a) => a.object_count(),
+            ArchiveInner::Elf(ref a) => a.object_count(),
+            ArchiveInner::MachO(ref a) => a.object_count(),
+            ArchiveInner::Pdb(ref a) => a.object_count(),
+            ArchiveInner::Pe(ref a) => a.object_count(),
+            ArchiveInner::SourceBundle(ref a) => a.object_count(),
+            ArchiveInner::Wasm(ref a) => a.object_count(),
+        }
+    }
+
+    /// Resolves the object at the given index.
+    ///
+    /// Returns `Ok(None)` if the index is out of bounds, or `Err` if the object exists but cannot
+    /// be parsed.
+    pub fn object_by_index(&self, index: usize) -> Result<Option<Object<'d>>, ObjectError> {
+        match self.0 {
+            ArchiveInner::Ar(ref a) => a.object_by_index(index),
+            ArchiveInner::Elf(ref a) => a
+                .object_by_index(index)
+                .map(|opt| opt.map(Object::Elf))
+                .map_err(ObjectError::transparent),
+            ArchiveInner::MachO(ref a) => a
+                .object_by_index(index)
+                .map(|opt| opt.map(Object::MachO))
+                .map_err(ObjectError::transparent),
+            ArchiveInner::Pdb(ref a) => a
+                .object_by_index(index)
+                .map(|opt| opt.map(Object::Pdb))
+                .map_err(ObjectError::transparent),
+            ArchiveInner::Pe(ref a) => a
+                .object_by_index(index)
+                .map(|opt| opt.map(Object::Pe))
+                .map_err(ObjectError::transparent),
+            ArchiveInner::SourceBundle(ref a) => a
+                .object_by_index(index)
+                .map(|opt| opt.map(Object::SourceBundle))
+                .map_err(ObjectError::transparent),
+            ArchiveInner::Wasm(ref a) => a
+                .object_by_index(index)
+                .map(|opt| opt.map(Object::Wasm))
+                .map_err(ObjectError::transparent),
+        }
+    }
+
+    /// Returns whether this is a multi-object archive.
+    ///
+    /// This may also return true if there is only a single object inside the archive.
+    pub fn is_multi(&self) -> bool {
+        match self.0 {
+            ArchiveInner::Ar(ref a) => a.is_multi(),
+            ArchiveInner::Elf(ref a) => a.is_multi(),
+            ArchiveInner::MachO(ref a) => a.is_multi(),
+            ArchiveInner::Pdb(ref a) => a.is_multi(),
+            ArchiveInner::Pe(ref a) => a.is_multi(),
+            ArchiveInner::SourceBundle(ref a) => a.is_multi(),
+            ArchiveInner::Wasm(ref a) => a.is_multi(),
+        }
+    }
+
+    /// The name of the object at the given index, for archives whose objects are named.
+    ///
+    /// This is the member name for `ar` archives, and `None` for all other archives. The objects
+    /// from [`objects`](Self::objects) are in index order, so can be named using
+    /// [`Iterator::enumerate`].
+    pub fn object_name(&self, index: usize) -> Option<&str> {
+        match self.0 {
+            ArchiveInner::Ar(ref a) => a.object_name(index),
+            _ => None,
+        }
+    }
+}
+
+impl<'d, 'a> IntoIterator for &'a Archive<'d> {
+    type Item = Result<Object<'d>, ObjectError>;
+    type IntoIter = ObjectIterator<'d, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.objects()
+    }
+}
+
+impl<'d> IntoIterator for Archive<'d> {
+    type Item = Result<Object<'d>, ObjectError>;
+    type IntoIter = IntoObjects<'d>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoObjects {
+            count: self.object_count(),
+            archive: self,
+            index: 0,
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum ObjectIteratorInner<'d, 'a> {
+    Ar(ArObjects<'d, 'a>),
+    Elf(MonoArchiveObjects<'d, ElfObject<'d>>),
+    MachO(MachObjectIterator<'d, 'a>),
+    Pdb(MonoArchiveObjects<'d, PdbObject<'d>>),
+    Pe(MonoArchiveObjects<'d, PeObject<'d>>),
+    SourceBundle(MonoArchiveObjects<'d, SourceBundle<'d>>),
+    Wasm(MonoArchiveObjects<'d, WasmObject<'d>>),
+}
+
+/// An iterator over [`Object`](enum.Object.html)s in an [`Archive`](struct.Archive.html).
+///
+/// Its length is the [`object_count`](Archive::object_count) of the archive, including objects
+/// which fail to parse:
+///
+/// ```rust
+/// # use declaration_site::object::Archive;
+/// let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+/// let archive = Archive::parse(&data).unwrap();
+/// assert_eq!(archive.objects().len(), archive.object_count());
+/// assert_eq!(archive.objects().len(), archive.objects().count());
+/// ```
+pub struct ObjectIterator<'d, 'a>(ObjectIteratorInner<'d, 'a>);
+
+impl<'d, 'a> Iterator for ObjectIterator<'d, 'a> {
+    type Item = Result<Object<'d>, ObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.0 {
+            ObjectIteratorInner::Ar(ref mut iter) => iter.next()?,
+            ObjectIteratorInner::Elf(ref mut iter) => iter
+                .next()?
+                .map(Object::Elf)
+                .map_err(ObjectError::transparent),
+            ObjectIteratorInner::MachO(ref mut iter) => iter
+                .next()?
+                .map(Object::MachO)
+                .map_err(ObjectError::transparent),
+            ObjectIteratorInner::Pdb(ref mut iter) => iter
+                .next()?
+                .map(Object::Pdb)
+                .map_err(ObjectError::transparent),
+            ObjectIteratorInner::Pe(ref mut iter) => iter
+                .next()?
+                .map(Object::Pe)
+                .map_err(ObjectError::transparent),
+            ObjectIteratorInner::SourceBundle(ref mut iter) => iter
+                .next()?
+                .map(Object::SourceBundle)
+                .map_err(ObjectError::transparent),
+            ObjectIteratorInner::Wasm(ref mut iter) => iter
+                .next()?
+                .map(Object::Wasm)
+                .map_err(ObjectError::transparent),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            ObjectIteratorInner::Ar(ref iter) => iter.size_hint(),
+            ObjectIteratorInner::Elf(ref iter) => iter.size_hint(),
+            ObjectIteratorInner::MachO(ref iter) => iter.size_hint(),
+            ObjectIteratorInner::Pdb(ref iter) => iter.size_hint(),
+            ObjectIteratorInner::Pe(ref iter) => iter.size_hint(),
+            ObjectIteratorInner::SourceBundle(ref iter) => iter.size_hint(),
+            ObjectIteratorInner::Wasm(ref iter) => iter.size_hint(),
+        }
+    }
+}
+
+// The iterators of every archive format are fused and have an exact size
+impl std::iter::FusedIterator for ObjectIterator<'_, '_> {}
+impl ExactSizeIterator for ObjectIterator<'_, '_> {}
+
+/// An iterator over [`Object`](enum.Object.html)s in an [`Archive`](struct.Archive.html), which
+/// owns the archive.
+///
+/// Objects only borrow the archive's data, so this can be used where the archive itself would
+/// not live long enough, such as when returning the iterator from a function.
+pub struct IntoObjects<'d> {
+    archive: Archive<'d>,
+    index: usize,
+    count: usize,
+}
+
+impl<'d> Iterator for IntoObjects<'d> {
+    type Item = Result<Object<'d>, ObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+        let object = self.archive.object_by_index(self.index).transpose();
+        self.index = if object.is_some() {
+            self.index + 1
+        } else {
+            self.count
+        };
+        object
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl std::iter::FusedIterator for IntoObjects<'_> {}
+impl ExactSizeIterator for IntoObjects<'_> {}
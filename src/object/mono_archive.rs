@@ -16,6 +16,7 @@ pub trait Parse<'data>: Sized {
 
     fn parse(data: &'data [u8]) -> Result<Self, Self::Error>;
 
+    #[allow(dead_code)]
     fn test(data: &'data [u8]) -> bool {
         Self::parse(data).is_ok()
     }
@@ -102,11 +103,23 @@ where
     }
 
     pub fn objects(&self) -> MonoArchiveObjects<'d, P> {
-        // TODO(ja): Consider parsing this lazily instead.
-        MonoArchiveObjects(Some(self.object()))
+        MonoArchiveObjects {
+            data: Some(self.data),
+            _ph: PhantomData,
+        }
+    }
+
+    pub fn object_count(&self) -> usize {
+        1
+    }
+
+    pub fn object_by_index(&self, index: usize) -> Result<Option<P>, P::Error> {
+        match index {
+            0 => self.object().map(Some),
+            _ => Ok(None),
+        }
     }
 
-    #[allow(dead_code)]
     pub fn is_multi(&self) -> bool {
         false
     }
@@ -127,10 +140,13 @@ where
     }
 }
 
+/// An iterator over the single object in a [`MonoArchive`], which is only
+/// parsed when the iterator is advanced.
 #[derive(Debug)]
-pub struct MonoArchiveObjects<'d, P>(Option<Result<P, P::Error>>)
-where
-    P: Parse<'d>;
+pub struct MonoArchiveObjects<'d, P> {
+    data: Option<&'d [u8]>,
+    _ph: PhantomData<&'d P>,
+}
 
 impl<'d, P> Iterator for MonoArchiveObjects<'d, P>
 where
@@ -139,11 +155,11 @@ where
     type Item = Result<P, P::Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.take()
+        Some(P::parse(self.data.take()?))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        if self.0.is_some() {
+        if self.data.is_some() {
             (1, Some(1))
         } else {
             (0, Some(0))
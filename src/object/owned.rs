@@ -0,0 +1,172 @@
+//! Objects and debug sessions which own the data they were parsed from.
+
+use std::sync::Arc;
+
+use self_cell::self_cell;
+use symbolic_common::{Arch, CodeId, DebugId};
+use symbolic_debuginfo::{FileFormat, Function, ObjectKind};
+
+use super::{Archive, Object, ObjectDebugSession, ObjectError};
+
+self_cell!(
+    struct ObjectCell {
+        owner: Arc<[u8]>,
+
+        #[not_covariant]
+        dependent: Object,
+    }
+);
+
+self_cell!(
+    struct SessionCell {
+        owner: Arc<[u8]>,
+
+        #[not_covariant]
+        dependent: ObjectDebugSession,
+    }
+);
+
+/// Parse the object at `index` in the archive in `data`.
+fn parse_object(data: &[u8], index: usize) -> Result<Object<'_>, Option<ObjectError>> {
+    Archive::parse(data)?.object_by_index(index)?.ok_or(None)
+}
+
+/// An [`Object`] which owns the data it was parsed from, so isn't limited to
+/// the lifetime of a borrow.
+///
+/// The object borrows from its data, so is accessed within a closure using
+/// [`with_object`](Self::with_object). Commonly used properties are also
+/// available directly.
+pub struct OwnedObject {
+    cell: ObjectCell,
+    index: usize,
+}
+
+impl OwnedObject {
+    /// Parse the first object in the archive in `data`.
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a supported object file format, or contains no objects
+    pub fn parse(data: impl Into<Arc<[u8]>>) -> Result<Self, ObjectError> {
+        Self::parse_at(data, 0)?.ok_or_else(ObjectError::empty_archive)
+    }
+
+    /// Parse the object at `index` in the archive in `data`, which is only
+    /// not `0` for Mach-O fat files.
+    ///
+    /// Returns `Ok(None)` if `index` is out of bounds.
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a supported object file format, or the object cannot
+    /// be parsed
+    pub fn parse_at(data: impl Into<Arc<[u8]>>, index: usize) -> Result<Option<Self>, ObjectError> {
+        match ObjectCell::try_new(data.into(), |data| parse_object(data, index)) {
+            Ok(cell) => Ok(Some(OwnedObject { cell, index })),
+            Err(None) => Ok(None),
+            Err(Some(error)) => Err(error),
+        }
+    }
+
+    /// The data this object was parsed from.
+    pub fn data(&self) -> &[u8] {
+        self.cell.borrow_owner()
+    }
+
+    /// Run `f` with the parsed object.
+    pub fn with_object<R>(&self, f: impl for<'d> FnOnce(&Object<'d>) -> R) -> R {
+        self.cell.with_dependent(|_, object| f(object))
+    }
+
+    /// Create a debug session for this object, which shares its data.
+    ///
+    /// # Errors
+    ///
+    /// If the object's debug info cannot be read
+    pub fn debug_session(&self) -> Result<OwnedDebugSession, ObjectError> {
+        let data = Arc::clone(self.cell.borrow_owner());
+        let index = self.index;
+        SessionCell::try_new(data, |data| match parse_object(data, index) {
+            Ok(object) => object.debug_session(),
+            // The object was parsed successfully before, so this is unreachable
+            Err(error) => Err(error.unwrap_or_else(ObjectError::empty_archive)),
+        })
+        .map(|cell| OwnedDebugSession { cell })
+    }
+
+    /// See [`Object::file_format`].
+    pub fn file_format(&self) -> FileFormat {
+        self.with_object(|object| object.file_format())
+    }
+
+    /// See [`Object::code_id`].
+    pub fn code_id(&self) -> Option<CodeId> {
+        self.with_object(|object| object.code_id())
+    }
+
+    /// See [`Object::debug_id`].
+    pub fn debug_id(&self) -> DebugId {
+        self.with_object(|object| object.debug_id())
+    }
+
+    /// See [`Object::arch`].
+    pub fn arch(&self) -> Arch {
+        self.with_object(|object| object.arch())
+    }
+
+    /// See [`Object::kind`].
+    pub fn kind(&self) -> ObjectKind {
+        self.with_object(|object| object.kind())
+    }
+
+    /// See [`Object::load_address`].
+    pub fn load_address(&self) -> u64 {
+        self.with_object(|object| object.load_address())
+    }
+
+    /// See [`Object::has_symbols`].
+    pub fn has_symbols(&self) -> bool {
+        self.with_object(|object| object.has_symbols())
+    }
+
+    /// See [`Object::has_debug_info`].
+    pub fn has_debug_info(&self) -> bool {
+        self.with_object(|object| object.has_debug_info())
+    }
+}
+
+/// An [`ObjectDebugSession`] which owns the data it was created from, created
+/// using [`OwnedObject::debug_session`].
+///
+/// The session borrows from its data, so is accessed within a closure using
+/// [`with_session`](Self::with_session). The functions it finds also borrow
+/// from the session, so can't be returned from the closure:
+///
+/// ```rust,compile_fail
+/// # use declaration_site::object::OwnedObject;
+/// # let object = OwnedObject::parse(Vec::new()).unwrap();
+/// let session = object.debug_session().unwrap();
+/// let function = session.with_session(|session| session.function_by_address(0));
+/// ```
+pub struct OwnedDebugSession {
+    cell: SessionCell,
+}
+
+impl OwnedDebugSession {
+    /// Run `f` with the debug session.
+    pub fn with_session<R>(&self, f: impl for<'d> FnOnce(&ObjectDebugSession<'d>) -> R) -> R {
+        self.cell.with_dependent(|_, session| f(session))
+    }
+
+    /// Run `f` on the function whose code contains `address`, if any.
+    ///
+    /// See [`ObjectDebugSession::function_by_address`].
+    pub fn with_function_by_address<R>(
+        &self,
+        address: u64,
+        f: impl for<'s> FnOnce(&Function<'s>) -> R,
+    ) -> Option<R> {
+        self.with_session(|session| session.function_by_address(address).map(|it| f(&it)))
+    }
+}
@@ -0,0 +1,64 @@
+//! DWARF debug info embedded in PE files, as emitted by MinGW toolchains (such as Rust's
+//! `*-pc-windows-gnu` targets).
+//!
+//! The version of `symbolic_debuginfo` used here only reads debug info from PDBs for PE files, so
+//! this provides the sections to its DWARF implementation directly.
+
+use std::borrow::Cow;
+
+use symbolic_debuginfo::dwarf::{Dwarf, DwarfDebugSession, DwarfError, DwarfSection, Endian};
+use symbolic_debuginfo::pe::PeObject;
+
+/// The DWARF sections of a [`PeObject`].
+pub(super) struct PeDwarf<'a, 'data> {
+    object: &'a PeObject<'data>,
+}
+
+impl<'a, 'data> PeDwarf<'a, 'data> {
+    /// The DWARF sections of `object`, if it has DWARF debug info and no PDB reference.
+    pub fn new(object: &'a PeObject<'data>) -> Option<Self> {
+        let dwarf = PeDwarf { object };
+        // Linkers which only emit a build id still write a CodeView record, with an empty PDB name
+        let has_pdb = object
+            .debug_file_name()
+            .is_some_and(|name| !name.is_empty());
+        (!has_pdb && dwarf.has_section("debug_info")).then_some(dwarf)
+    }
+
+    pub fn debug_session(&self) -> Result<DwarfDebugSession<'data>, DwarfError> {
+        // DWARF addresses include the image base, but the session's addresses are relative to it
+        DwarfDebugSession::parse(
+            self,
+            self.object.symbol_map(),
+            self.object.load_address() as i64,
+            self.object.kind(),
+        )
+    }
+}
+
+impl<'data> Dwarf<'data> for PeDwarf<'_, 'data> {
+    fn endianity(&self) -> Endian {
+        // PE is only used on little-endian architectures
+        Endian::Little
+    }
+
+    fn raw_section(&self, name: &str) -> Option<DwarfSection<'data>> {
+        let section = self.object.sections().iter().find(|section| {
+            section.name().ok().and_then(|it| it.strip_prefix('.')) == Some(name)
+        })?;
+        // The raw data is padded to the file alignment, whereas the virtual size is that of the
+        // section's contents
+        let size = match section.virtual_size {
+            0 => section.size_of_raw_data,
+            size => size.min(section.size_of_raw_data),
+        };
+        let start = section.pointer_to_raw_data as usize;
+        let data = self.object.data().get(start..start + size as usize)?;
+        Some(DwarfSection {
+            address: self.object.load_address() + u64::from(section.virtual_address),
+            offset: u64::from(section.pointer_to_raw_data),
+            align: 1,
+            data: Cow::Borrowed(data),
+        })
+    }
+}
@@ -0,0 +1,109 @@
+//! The names of DWARF subprograms as written by the compiler, used to tell apart functions whose
+//! symbol names are the same.
+//!
+//! `symbolic_debuginfo` names functions after their symbols where it can. The legacy symbols of
+//! Rust functions don't include generic arguments or closure disambiguators, so the shim which
+//! calls a closure through a `fn` pointer is only named `core::ops::function::FnOnce::call_once`,
+//! and the closures in a function are all named `{{closure}}`. The `DW_AT_name` of their
+//! subprograms, such as `call_once<my_crate::main::{closure_env#0}, ()>` or `{closure#0}`, is read
+//! with `gimli` directly.
+
+use std::borrow::Cow;
+
+use symbolic_debuginfo::dwarf::gimli::{self, EndianSlice, RunTimeEndian, SectionId};
+use symbolic_debuginfo::dwarf::Dwarf;
+
+type Reader<'a> = EndianSlice<'a, RunTimeEndian>;
+
+/// The `DW_AT_name` of the subprogram whose code starts at `address`, relative to
+/// `load_address`, in the DWARF sections of `dwarf`.
+pub(super) fn subprogram_name<'data>(
+    dwarf: &impl Dwarf<'data>,
+    load_address: u64,
+    address: u64,
+) -> Option<String> {
+    let endian = dwarf.endianity();
+    // Sections can be decompressed, so are loaded before being borrowed
+    let sections = gimli::Dwarf::load(|id: SectionId| {
+        let data = dwarf
+            .section(&id.name()[1..])
+            .map_or(Cow::Borrowed(&[][..]), |section| section.data);
+        Ok::<_, gimli::Error>(data)
+    })
+    .ok()?;
+    let dwarf = sections.borrow(|data| EndianSlice::new(data, endian));
+    find_subprogram_name(&dwarf, load_address.checked_add(address)?)
+        .ok()
+        .flatten()
+}
+
+/// The name of the subprogram whose code starts at `address`, searching the units containing it.
+fn find_subprogram_name(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    address: u64,
+) -> gimli::Result<Option<String>> {
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        // Skip units which can't be read, as when scanning functions
+        let Ok(unit) = dwarf.unit(header) else {
+            continue;
+        };
+        if !unit_contains(dwarf, &unit, address) {
+            continue;
+        }
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let low_pc = match entry.attr_value(gimli::DW_AT_low_pc)? {
+                Some(value) => dwarf.attr_address(&unit, value)?,
+                None => None,
+            };
+            if low_pc == Some(address) {
+                return entry_name(dwarf, &unit, entry);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Whether any of the address ranges of `unit` contain `address`.
+fn unit_contains(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    unit: &gimli::Unit<Reader<'_>>,
+    address: u64,
+) -> bool {
+    let Ok(mut ranges) = dwarf.unit_ranges(unit) else {
+        return false;
+    };
+    while let Ok(Some(range)) = ranges.next() {
+        if (range.begin..range.end).contains(&address) {
+            return true;
+        }
+    }
+    false
+}
+
+/// The `DW_AT_name` of `entry`, or of the declaration it is the definition of.
+fn entry_name(
+    dwarf: &gimli::Dwarf<Reader<'_>>,
+    unit: &gimli::Unit<Reader<'_>>,
+    entry: &gimli::DebuggingInformationEntry<'_, '_, Reader<'_>>,
+) -> gimli::Result<Option<String>> {
+    if let Some(name) = entry.attr_value(gimli::DW_AT_name)? {
+        let name = dwarf.attr_string(unit, name)?;
+        return Ok(Some(String::from_utf8_lossy(name.slice()).into_owned()));
+    }
+    for origin in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+        // Only declarations in the same unit are followed
+        if let Some(gimli::AttributeValue::UnitRef(offset)) = entry.attr_value(origin)? {
+            let declaration = unit.entry(offset)?;
+            if let Some(name) = declaration.attr_value(gimli::DW_AT_name)? {
+                let name = dwarf.attr_string(unit, name)?;
+                return Ok(Some(String::from_utf8_lossy(name.slice()).into_owned()));
+            }
+        }
+    }
+    Ok(None)
+}
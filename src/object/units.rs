@@ -0,0 +1,394 @@
+//! The compilation units of DWARF debug info, used to tell which unit a function was compiled in.
+//!
+//! `symbolic_debuginfo`'s debug sessions don't expose the unit of each function, so this reads
+//! the name, producer, language and address ranges of each unit with `gimli` directly. The
+//! modules (compilands) of PDBs are read with `pdb` in the same way.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use symbolic_common::Language;
+use symbolic_debuginfo::dwarf::gimli::{
+    self, AttributeValue, EndianSlice, RunTimeEndian, SectionId,
+};
+use symbolic_debuginfo::dwarf::Dwarf;
+use symbolic_debuginfo::pdb::pdb::{self, FallibleIterator, SymbolData};
+use symbolic_debuginfo::pdb::PdbObject;
+
+/// A DWARF compilation unit.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompilationUnit {
+    name: Option<String>,
+    comp_dir: Option<String>,
+    producer: Option<String>,
+    language: Language,
+}
+
+impl CompilationUnit {
+    /// The name of the unit (`DW_AT_name`).
+    ///
+    /// For units compiled by rustc, this is the path of the crate root and the
+    /// name of the codegen unit, such as `src/lib.rs/@/my_crate.a1b2c3-cgu.0`.
+    /// For the modules of PDBs, this is the path of the object file, such as
+    /// `C:\target\debug\deps\my_crate.my_crate.a1b2c3-cgu.0.rcgu.o`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The working directory of the compiler (`DW_AT_comp_dir`).
+    pub fn comp_dir(&self) -> Option<&str> {
+        self.comp_dir.as_deref()
+    }
+
+    /// The compiler which produced the unit (`DW_AT_producer`), such as
+    /// `clang LLVM (rustc version 1.80.0 (051478957 2024-07-21))`.
+    ///
+    /// rustc doesn't record its flags (such as the opt-level) here, but C and
+    /// C++ compilers may, such as clang with `-grecord-command-line`. For the
+    /// modules of PDBs, this is the version string of the compiler, such as
+    /// `rustc version 1.80.0 (051478957 2024-07-21)` or
+    /// `Microsoft (R) Optimizing Compiler`.
+    pub fn producer(&self) -> Option<&str> {
+        self.producer.as_deref()
+    }
+
+    /// The source language of the unit (`DW_AT_language`), or
+    /// [`Language::Unknown`] if it isn't recorded or isn't one known to
+    /// `symbolic`.
+    ///
+    /// PDBs don't have a language for Rust, so the modules of PDBs are
+    /// [`Language::Rust`] if their [`producer`](Self::producer) is rustc.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// The name of the crate the unit was compiled for, if it can be
+    /// determined.
+    ///
+    /// This is read from the codegen unit in the [name](Self::name) of units
+    /// compiled by rustc, or from the file name of PDB modules compiled from
+    /// object files (without any `-a1b2c3` hash). Newer versions of rustc
+    /// hash the names of codegen units, in which case this is only known for
+    /// the targets which cargo names after their root file, such as
+    /// `tests/my_test.rs` (including the test harnesses of integration tests),
+    /// `examples/my_example/main.rs` and `src/bin/my_tool.rs`. Otherwise, the last
+    /// component of the [`comp_dir`](Self::comp_dir) is used, without any
+    /// version (such as `my-crate-1.2.3` for a crate from crates.io), with `-`
+    /// replaced by `_`.
+    pub fn crate_name(&self) -> Option<Cow<'_, str>> {
+        if let Some((root, cgu)) = self.name().and_then(|name| name.rsplit_once("/@/")) {
+            return match cgu.split_once('.') {
+                Some((krate, _)) => Some(Cow::Borrowed(krate)),
+                None => target_crate_name(root).map(|krate| Cow::Owned(krate.replace('-', "_"))),
+            };
+        }
+        if let Some(name) = self
+            .name()
+            .filter(|name| name.ends_with(".o") || name.ends_with(".obj"))
+        {
+            let file = name.rsplit(['/', '\\']).next()?;
+            let stem = file.split('.').next()?;
+            // Remove a hash suffix, such as the `-a1b2c3` of `my_crate-a1b2c3.my_crate.cgu.o`
+            let stem = stem
+                .rsplit_once('-')
+                .filter(|(_, hash)| !hash.is_empty() && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+                .map_or(stem, |(stem, _)| stem);
+            return (!stem.is_empty()).then(|| Cow::Owned(stem.replace('-', "_")));
+        }
+        let dir = self.comp_dir()?.trim_end_matches(['/', '\\']);
+        let dir = dir.rsplit(['/', '\\']).next()?;
+        // Remove a version suffix, such as `-1.2.3`
+        let dir = dir
+            .match_indices('-')
+            .find(|(index, _)| dir[index + 1..].starts_with(|c: char| c.is_ascii_digit()))
+            .map_or(dir, |(index, _)| &dir[..index]);
+        (!dir.is_empty()).then(|| Cow::Owned(dir.replace('-', "_")))
+    }
+
+    /// Replace the paths of the unit (its [`comp_dir`](Self::comp_dir), and the
+    /// crate root in its [name](Self::name), or the whole name if it isn't
+    /// one of rustc's) with `redact` of them.
+    pub(crate) fn redact_paths(&mut self, redact: impl Fn(&str) -> String) {
+        if let Some(comp_dir) = &mut self.comp_dir {
+            *comp_dir = redact(comp_dir);
+        }
+        if let Some(name) = &mut self.name {
+            *name = match name.rsplit_once("/@/") {
+                Some((root, cgu)) => format!("{}/@/{cgu}", redact(root)),
+                None => redact(name),
+            };
+        }
+    }
+
+    /// The path of the root file of the crate the unit was compiled for, such
+    /// as `src/lib.rs`, read from the [name](Self::name) of units compiled by
+    /// rustc.
+    pub fn crate_root(&self) -> Option<&str> {
+        let (root, _) = self.name()?.rsplit_once("/@/")?;
+        (!root.is_empty()).then_some(root)
+    }
+
+    /// The kind of cargo target the unit's crate was compiled for, if its
+    /// [`crate_root`](Self::crate_root) is in the default location for a lib
+    /// (`src/lib.rs`) or bin (`src/main.rs` or `src/bin/`).
+    pub fn target(&self) -> Option<CrateTarget> {
+        self.package_target().map(|(target, _)| target)
+    }
+
+    /// The kind of target of the unit's crate, and the directory of its
+    /// package relative to the [`comp_dir`](Self::comp_dir), as in
+    /// [`target`](Self::target).
+    pub(crate) fn package_target(&self) -> Option<(CrateTarget, &str)> {
+        let root = self.crate_root()?;
+        // The package of a `src` directory, with its trailing separator
+        fn package(src: &str) -> Option<&str> {
+            let package = src.strip_suffix("src")?;
+            (package.is_empty() || package.ends_with(['/', '\\'])).then_some(package)
+        }
+        let (parent, file) = root.rsplit_once(['/', '\\'])?;
+        match file {
+            "lib.rs" => Some((CrateTarget::Lib, package(parent)?)),
+            "main.rs" => Some((CrateTarget::Bin, package(parent)?)),
+            _ => match parent.rsplit_once(['/', '\\'])? {
+                (src, "bin") => Some((CrateTarget::Bin, package(src)?)),
+                _ => None,
+            },
+        }
+    }
+
+    /// The directory of the package of a bin or lib target (see
+    /// [`package_target`](Self::package_target)), resolved against the
+    /// [`comp_dir`](Self::comp_dir) if it is relative, without a trailing
+    /// separator.
+    pub(crate) fn package_dir(&self) -> Option<String> {
+        let (_, package) = self.package_target()?;
+        let package = package.trim_end_matches(['/', '\\']);
+        let is_absolute = package.starts_with(['/', '\\']) || package.get(1..2) == Some(":");
+        let dir = match self.comp_dir().map(|dir| dir.trim_end_matches(['/', '\\'])) {
+            Some(comp_dir) if !is_absolute && !comp_dir.is_empty() => {
+                if package.is_empty() {
+                    comp_dir.to_owned()
+                } else {
+                    let separator = if comp_dir.contains('/') { '/' } else { '\\' };
+                    format!("{comp_dir}{separator}{package}")
+                }
+            }
+            _ => package.to_owned(),
+        };
+        (!dir.is_empty()).then_some(dir)
+    }
+}
+
+/// The name of the crate whose root file is `root`, for the targets which cargo
+/// names after their root file.
+fn target_crate_name(root: &str) -> Option<&str> {
+    let components = root.split(['/', '\\']).collect::<Vec<_>>();
+    match components.as_slice() {
+        [.., "tests" | "examples" | "benches", file] | [.., "src", "bin", file] => {
+            file.strip_suffix(".rs")
+        }
+        [.., "tests" | "examples" | "benches" | "bin", directory, "main.rs"] => Some(directory),
+        _ => None,
+    }
+}
+
+/// The [`Language`] of the DWARF language code `language`.
+pub(super) fn dwarf_language(language: gimli::DwLang) -> Language {
+    match language {
+        gimli::DW_LANG_C | gimli::DW_LANG_C89 | gimli::DW_LANG_C99 | gimli::DW_LANG_C11 => {
+            Language::C
+        }
+        gimli::DW_LANG_C_plus_plus
+        | gimli::DW_LANG_C_plus_plus_03
+        | gimli::DW_LANG_C_plus_plus_11
+        | gimli::DW_LANG_C_plus_plus_14 => Language::Cpp,
+        gimli::DW_LANG_D => Language::D,
+        gimli::DW_LANG_Go => Language::Go,
+        gimli::DW_LANG_ObjC => Language::ObjC,
+        gimli::DW_LANG_ObjC_plus_plus => Language::ObjCpp,
+        gimli::DW_LANG_Rust => Language::Rust,
+        gimli::DW_LANG_Swift => Language::Swift,
+        _ => Language::Unknown,
+    }
+}
+
+/// The [`Language`] of a PDB module compiled from `language` by `producer`.
+///
+/// `pdb` reads the languages it doesn't know (including Rust) as
+/// [`Masm`](pdb::SourceLanguage::Masm), so Rust is recognised by its
+/// producer instead.
+fn pdb_language(language: pdb::SourceLanguage, producer: &str) -> Language {
+    if producer.starts_with("rustc") {
+        return Language::Rust;
+    }
+    match language {
+        pdb::SourceLanguage::C => Language::C,
+        pdb::SourceLanguage::Cpp => Language::Cpp,
+        pdb::SourceLanguage::D => Language::D,
+        _ => Language::Unknown,
+    }
+}
+
+/// The kind of cargo target a crate was compiled for. See
+/// [`CompilationUnit::target`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CrateTarget {
+    /// A library, such as `src/lib.rs`
+    Lib,
+    /// A binary, such as `src/main.rs` or `src/bin/tool.rs`
+    Bin,
+}
+
+/// The compilation units of an object, by address.
+///
+/// See [`Object::compilation_units`](super::Object::compilation_units).
+#[derive(Clone, Debug, Default)]
+pub struct CompilationUnits {
+    units: Vec<CompilationUnit>,
+    /// The address ranges of each unit, as indices into `units`, sorted by
+    /// start address
+    ranges: Vec<(Range<u64>, usize)>,
+}
+
+impl CompilationUnits {
+    /// Read the units of the DWARF sections in `dwarf`, with addresses
+    /// relative to `load_address`.
+    pub(super) fn read<'data>(dwarf: &impl Dwarf<'data>, load_address: u64) -> Self {
+        let endian = dwarf.endianity();
+        // Sections can be decompressed, so are loaded before being borrowed
+        let sections = gimli::Dwarf::load(|id: SectionId| {
+            let data = dwarf
+                .section(&id.name()[1..])
+                .map_or(Cow::Borrowed(&[][..]), |section| section.data);
+            Ok::<_, gimli::Error>(data)
+        });
+        let Ok(sections) = sections else {
+            return Self::default();
+        };
+        let dwarf = sections.borrow(|data| EndianSlice::new(data, endian));
+        Self::read_units(&dwarf, load_address).unwrap_or_default()
+    }
+
+    /// Read the modules of `object` as units.
+    pub(super) fn read_pdb(object: &PdbObject<'_>) -> Self {
+        Self::read_modules(&mut object.inner().write()).unwrap_or_default()
+    }
+
+    fn read_modules<'s>(pdb: &mut pdb::PDB<'s, impl pdb::Source<'s> + 's>) -> pdb::Result<Self> {
+        let mut result = Self::default();
+        let address_map = pdb.address_map()?;
+        let debug_info = pdb.debug_information()?;
+        let mut modules = debug_info.modules()?;
+        while let Some(module) = modules.next()? {
+            let index = result.units.len();
+            result.units.push(CompilationUnit {
+                name: Some(module.module_name().into_owned()),
+                ..CompilationUnit::default()
+            });
+            // Skip modules which can't be read, as when scanning functions
+            let Ok(Some(info)) = pdb.module_info(&module) else {
+                continue;
+            };
+            let Ok(mut symbols) = info.symbols() else {
+                continue;
+            };
+            while let Ok(Some(symbol)) = symbols.next() {
+                match symbol.parse() {
+                    Ok(SymbolData::Procedure(procedure)) => {
+                        if let Some(rva) = procedure.offset.to_rva(&address_map) {
+                            let start = u64::from(rva.0);
+                            let range = start..start + u64::from(procedure.len);
+                            result.ranges.push((range, index));
+                        }
+                    }
+                    Ok(SymbolData::CompileFlags(flags)) => {
+                        let unit = &mut result.units[index];
+                        let producer = flags.version_string.to_string().into_owned();
+                        unit.language = pdb_language(flags.language, &producer);
+                        unit.producer = Some(producer);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        result.ranges.sort_by_key(|(range, _)| range.start);
+        Ok(result)
+    }
+
+    fn read_units(
+        dwarf: &gimli::Dwarf<EndianSlice<'_, RunTimeEndian>>,
+        load_address: u64,
+    ) -> gimli::Result<Self> {
+        let mut result = Self::default();
+        let mut headers = dwarf.units();
+        while let Some(header) = headers.next()? {
+            // Skip units which can't be read, as when scanning functions
+            let Ok(unit) = dwarf.unit(header) else {
+                continue;
+            };
+            let string = |it: Option<EndianSlice<'_, RunTimeEndian>>| {
+                it.map(|it| String::from_utf8_lossy(it.slice()).into_owned())
+            };
+            // The producer and language are attributes of the unit's root entry
+            let mut entries = unit.entries();
+            let root = entries.next_dfs().ok().flatten().map(|(_, entry)| entry);
+            let attribute = |name| root.and_then(|entry| entry.attr_value(name).ok().flatten());
+            let producer = attribute(gimli::DW_AT_producer)
+                .and_then(|value| dwarf.attr_string(&unit, value).ok());
+            let language = match attribute(gimli::DW_AT_language) {
+                Some(AttributeValue::Language(language)) => dwarf_language(language),
+                _ => Language::Unknown,
+            };
+            let index = result.units.len();
+            result.units.push(CompilationUnit {
+                name: string(unit.name),
+                comp_dir: string(unit.comp_dir),
+                producer: string(producer),
+                language,
+            });
+            let Ok(mut ranges) = dwarf.unit_ranges(&unit) else {
+                continue;
+            };
+            while let Ok(Some(range)) = ranges.next() {
+                if range.begin < range.end && range.begin >= load_address {
+                    let range = range.begin - load_address..range.end - load_address;
+                    result.ranges.push((range, index));
+                }
+            }
+        }
+        result.ranges.sort_by_key(|(range, _)| range.start);
+        Ok(result)
+    }
+
+    /// The unit containing the code at `address`, relative to the object's
+    /// load address (as in [`Function::address`](symbolic_debuginfo::Function::address)).
+    pub fn containing(&self, address: u64) -> Option<&CompilationUnit> {
+        self.containing_index(address).map(|unit| &self.units[unit])
+    }
+
+    /// The index of the unit containing the code at `address`, as in
+    /// [`containing`](Self::containing).
+    pub(crate) fn containing_index(&self, address: u64) -> Option<usize> {
+        let index = self
+            .ranges
+            .partition_point(|(range, _)| range.start <= address);
+        // Ranges can overlap in unusual debug info, so check the closest few
+        self.ranges[..index]
+            .iter()
+            .rev()
+            .take(4)
+            .find(|(range, _)| range.contains(&address))
+            .map(|(_, unit)| *unit)
+    }
+
+    /// Whether no units were found, such as for objects without DWARF debug
+    /// info.
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// The units, in the order they appear in the debug info.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &CompilationUnit> {
+        self.units.iter()
+    }
+}
@@ -0,0 +1,42 @@
+//! Finding the external debug file of a WASM module, whose DWARF was split out of the module.
+//!
+//! Such modules name the file containing their debug info in an `external_debug_info` custom
+//! section, which the version of `symbolic_debuginfo` used here doesn't read.
+
+/// The contents of the `external_debug_info` custom section of the WASM module in `data`.
+pub(super) fn external_debug_info(data: &[u8]) -> Option<&str> {
+    // Skip the magic and version
+    let mut rest = data.get(8..)?;
+    while !rest.is_empty() {
+        let id = rest[0];
+        rest = &rest[1..];
+        let size = read_u32(&mut rest)? as usize;
+        let mut section = rest.get(..size)?;
+        rest = &rest[size..];
+        if id == 0 && read_str(&mut section)? == "external_debug_info" {
+            return read_str(&mut section);
+        }
+    }
+    None
+}
+
+/// Read an unsigned LEB128 encoded integer from the start of `data`.
+fn read_u32(data: &mut &[u8]) -> Option<u32> {
+    let mut result = 0u32;
+    for (index, byte) in data.iter().enumerate().take(5) {
+        result |= u32::from(byte & 0x7f) << (index * 7);
+        if byte & 0x80 == 0 {
+            *data = &data[index + 1..];
+            return Some(result);
+        }
+    }
+    None
+}
+
+/// Read a length-prefixed UTF-8 string from the start of `data`.
+fn read_str<'d>(data: &mut &'d [u8]) -> Option<&'d str> {
+    let len = read_u32(data)? as usize;
+    let bytes = data.get(..len)?;
+    *data = &data[len..];
+    std::str::from_utf8(bytes).ok()
+}
@@ -0,0 +1,235 @@
+//! Checking that a separate debug file (such as a dSYM, PDB or split WASM
+//! debug file) was built with the binary it is used for.
+
+use std::{
+    cell::Cell,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use symbolic_debuginfo::FileFormat;
+
+use crate::{file_access, limits, object, PairingStatus};
+
+/// Whether a debug file was built with its binary, from
+/// [`verify_debug_pairing`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PairingReport {
+    /// The path of the binary
+    pub binary_path: PathBuf,
+    /// The path of the debug file
+    pub debug_path: PathBuf,
+    /// The debug ids of the objects in the binary which can be compared
+    pub binary_ids: Vec<String>,
+    /// The debug ids of the objects in the debug file which can be compared
+    pub debug_ids: Vec<String>,
+    /// Whether the debug file was built with the binary
+    pub status: PairingStatus,
+}
+
+impl PairingReport {
+    /// Whether the debug file is known to be from a different build to the
+    /// binary.
+    pub fn is_mismatched(&self) -> bool {
+        self.status == PairingStatus::Mismatched
+    }
+}
+
+impl fmt::Display for PairingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (binary, debug) = (self.binary_path.display(), self.debug_path.display());
+        match self.status {
+            PairingStatus::Matched => write!(
+                f,
+                "{debug} matches {binary} (debug id {})",
+                self.debug_ids.join(", ")
+            ),
+            PairingStatus::Mismatched => write!(
+                f,
+                "{debug} doesn't match {binary}: its debug id is {}, but the binary's is {}",
+                self.debug_ids.join(", "),
+                self.binary_ids.join(", ")
+            ),
+            PairingStatus::Unverifiable => {
+                let missing = if self.binary_ids.is_empty() {
+                    &binary
+                } else {
+                    &debug
+                };
+                write!(
+                    f,
+                    "can't check whether {debug} matches {binary}, as {missing} has no debug id"
+                )
+            }
+        }
+    }
+}
+
+/// Check whether the debug file at `debug_path` was built with the binary at
+/// `binary_path`, by comparing their debug ids.
+///
+/// Searches which use a separate debug file (the debug companions of loaded
+/// libraries, and the external debug files of WASM modules) check this as
+/// configured by [`with_debug_pairing`], as a debug file from a different
+/// build gives wrong declaration sites.
+///
+/// ```rust,no_run
+/// use declaration_site::verify_debug_pairing;
+///
+/// let report = verify_debug_pairing("target/app.wasm", "target/app.debug.wasm").unwrap();
+/// if report.is_mismatched() {
+///     eprintln!("{report}");
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If either file could not be read
+pub fn verify_debug_pairing(
+    binary_path: impl AsRef<Path>,
+    debug_path: impl AsRef<Path>,
+) -> io::Result<PairingReport> {
+    let (binary_path, debug_path) = (binary_path.as_ref(), debug_path.as_ref());
+    let binary = file_access::read(binary_path)?;
+    let debug = file_access::read(debug_path)?;
+    Ok(pairing(binary_path, &binary, debug_path, &debug))
+}
+
+/// Whether the debug file with contents `debug` was built with the binary with
+/// contents `binary`.
+pub(crate) fn pairing(
+    binary_path: &Path,
+    binary: &[u8],
+    debug_path: &Path,
+    debug: &[u8],
+) -> PairingReport {
+    let binary_ids = debug_ids(binary);
+    let debug_ids = debug_ids(debug);
+    let status = if binary_ids.is_empty() || debug_ids.is_empty() {
+        PairingStatus::Unverifiable
+    } else if debug_ids.iter().any(|id| binary_ids.contains(id)) {
+        PairingStatus::Matched
+    } else {
+        PairingStatus::Mismatched
+    };
+    PairingReport {
+        binary_path: binary_path.to_owned(),
+        debug_path: debug_path.to_owned(),
+        binary_ids,
+        debug_ids,
+        status,
+    }
+}
+
+/// The debug ids of the objects in the file with contents `data` which
+/// identify their build.
+fn debug_ids(data: &[u8]) -> Vec<String> {
+    limits::catch_parser_panic(|| {
+        let Ok(archive) = object::Archive::parse(data) else {
+            return Vec::new();
+        };
+        archive
+            .objects()
+            .flatten()
+            // The debug id of an ELF file without a build id is a hash of its
+            // code, which a separate debug file doesn't contain
+            .filter(|object| object.file_format() != FileFormat::Elf || object.code_id().is_some())
+            .map(|object| object.debug_id())
+            .filter(|id| !id.is_nil())
+            .map(|id| id.to_string())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Whether any object in the file with contents `data` has debug info.
+#[cfg(feature = "process")]
+pub(crate) fn has_debug_info(data: &[u8]) -> bool {
+    object::Archive::parse(data).is_ok_and(|archive| {
+        archive
+            .objects()
+            .any(|object| object.is_ok_and(|it| it.has_debug_info()))
+    })
+}
+
+/// What searches do with a separate debug file which doesn't match its binary
+/// (see [`PairingStatus::Mismatched`]), set for the current thread by
+/// [`with_debug_pairing`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum DebugPairing {
+    /// Search the binary instead if it has debug info of its own, and
+    /// otherwise search the mismatched debug file anyway
+    #[default]
+    FallBack,
+    /// Never search a mismatched debug file. Searching a file whose debug
+    /// file doesn't match it fails, and the loaded libraries whose debug
+    /// companion doesn't match are only searched if they have debug info of
+    /// their own
+    Strict,
+    /// Don't check whether debug files match their binaries, which avoids
+    /// reading the binaries of loaded libraries with debug companions
+    Unverified,
+}
+
+thread_local! {
+    /// The pairing of scans on the current thread.
+    static PAIRING: Cell<DebugPairing> = const { Cell::new(DebugPairing::FallBack) };
+}
+
+/// Run `f` with `pairing` applied to every search on the current thread which
+/// uses a separate debug file, returning its result.
+///
+/// Outside of this function, [`DebugPairing::FallBack`] is used. The debug
+/// files which were skipped by a search of the loaded libraries are reported
+/// by [`DeclarationError::MismatchedDebugFiles`](crate::DeclarationError::MismatchedDebugFiles)
+/// if the function wasn't found.
+///
+/// ```rust
+/// use declaration_site::{with_debug_pairing, DebugPairing};
+///
+/// let site = with_debug_pairing(DebugPairing::Strict, || {
+///     declaration_site::declaration_by_name("my_crate::my_function")
+/// });
+/// assert!(site.is_none());
+/// ```
+pub fn with_debug_pairing<R>(pairing: DebugPairing, f: impl FnOnce() -> R) -> R {
+    let previous = PAIRING.with(|it| it.replace(pairing));
+    // Restore the previous pairing even if `f` panics
+    let _guard = PairingGuard { previous };
+    f()
+}
+
+/// The pairing of scans on the current thread.
+#[cfg(feature = "async")]
+pub(crate) fn current() -> DebugPairing {
+    PAIRING.with(Cell::get)
+}
+
+struct PairingGuard {
+    previous: DebugPairing,
+}
+
+impl Drop for PairingGuard {
+    fn drop(&mut self) {
+        PAIRING.with(|it| it.set(self.previous));
+    }
+}
+
+/// Check whether the debug file with contents `debug` matches its binary, if
+/// the current [`DebugPairing`] checks this.
+pub(crate) fn verify(
+    binary_path: &Path,
+    binary: &[u8],
+    debug_path: &Path,
+    debug: &[u8],
+) -> Option<PairingReport> {
+    (PAIRING.with(Cell::get) != DebugPairing::Unverified)
+        .then(|| pairing(binary_path, binary, debug_path, debug))
+}
+
+/// Whether the debug file of `report` should be searched rather than its
+/// binary, which has debug info of its own if `binary_has_debug_info`.
+pub(crate) fn use_debug_file(report: &PairingReport, binary_has_debug_info: bool) -> bool {
+    !report.is_mismatched()
+        || (PAIRING.with(Cell::get) == DebugPairing::FallBack && !binary_has_debug_info)
+}
@@ -0,0 +1,179 @@
+//! A panic hook which prints the declaration site of the panicking function.
+
+use std::{
+    backtrace::Backtrace,
+    fmt::{self, Write},
+    panic::PanicHookInfo,
+};
+
+use crate::{
+    backtrace::frame_function_name, declaration_by_name, is_scanning, limits, low_allocation,
+    names, with_low_allocation_scans, with_scan_limits, ScanLimits,
+};
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// The largest file read by the lookup in the hook.
+const MAX_FILE_LEN: u64 = 256 << 20;
+
+/// The limits of the lookup in the hook.
+const LIMITS: ScanLimits = ScanLimits {
+    max_lines_per_function: 1 << 16,
+    max_functions_per_object: 1 << 22,
+};
+
+/// The longest line of the backtrace which is checked for the function which
+/// panicked. Longer lines are skipped.
+const MAX_LINE_LEN: usize = 4096;
+
+/// Replace the current panic hook with one which runs it, then prints where the
+/// function which panicked was declared.
+///
+/// See [`augment_panic_hook`] for details.
+pub fn install_panic_hook(crates: &[&str]) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(augment_panic_hook(previous, crates));
+}
+
+/// Create a panic hook which runs `previous`, then prints where the function
+/// which panicked was declared.
+///
+/// The function which panicked is the function in the first frame of a
+/// backtrace captured in the hook whose crate is in `crates`. If `crates` is
+/// empty, the first frame not from the standard library (or this crate) is
+/// used instead. For example, this could print:
+///
+/// ```text
+/// thread 'main' panicked at src/player.rs:40:13:
+/// player has no health
+/// note: panicked in `my_game::player::update_player`, declared at src/player.rs:35
+/// ```
+///
+/// Capturing the backtrace and finding the declaration site are expensive,
+/// but this only happens when a panic occurs. To keep the memory used by the
+/// hook bounded:
+///
+/// - The backtrace is read a line at a time, and only up to the frame of the
+///   function which panicked, rather than being formatted into a string.
+/// - The function is looked up as in [`with_low_allocation_scans`], skipping
+///   files larger than 256 MiB, and within [`ScanLimits`] lower than the
+///   defaults. Lower limits set on the panicking thread are kept.
+///
+/// If the panic came from inside this crate's own search for functions,
+/// nothing extra is printed, to avoid searching again whilst in a potentially
+/// broken state.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn augment_panic_hook(previous: PanicHook, crates: &[&str]) -> PanicHook {
+    let crates = crates.iter().map(|&it| it.to_owned()).collect::<Vec<_>>();
+    Box::new(move |info| {
+        previous(info);
+        if is_scanning() {
+            return;
+        }
+        let Some(function) = first_selected_frame(&Backtrace::force_capture(), &crates) else {
+            return;
+        };
+        let current = limits::current();
+        let limits = ScanLimits {
+            max_lines_per_function: LIMITS
+                .max_lines_per_function
+                .min(current.max_lines_per_function),
+            max_functions_per_object: LIMITS
+                .max_functions_per_object
+                .min(current.max_functions_per_object),
+        };
+        let max_file_len =
+            low_allocation::max_file_len().map_or(MAX_FILE_LEN, |it| it.min(MAX_FILE_LEN));
+        let (site, _) = with_scan_limits(limits, || {
+            with_low_allocation_scans(max_file_len, || declaration_by_name(&function))
+        });
+        match site {
+            Some(site) => {
+                eprintln!("note: panicked in `{function}`, declared at {site}")
+            }
+            None => eprintln!("note: panicked in `{function}`"),
+        }
+    })
+}
+
+/// The name of the function in the first frame of `backtrace` whose crate is
+/// in `crates`, as in [`is_selected_crate`].
+fn first_selected_frame(backtrace: &Backtrace, crates: &[String]) -> Option<String> {
+    let mut frames = SelectedFrame {
+        crates,
+        line: String::new(),
+        skip_line: false,
+        function: None,
+    };
+    // Formatting stops with an error once the frame is found
+    if write!(frames, "{backtrace}").is_ok() {
+        // The last line doesn't end with a newline
+        let _ = frames.check_line();
+    }
+    frames.function
+}
+
+/// Finds the first frame of a backtrace from the selected crates as it is
+/// formatted, holding only the current line.
+struct SelectedFrame<'a> {
+    crates: &'a [String],
+    line: String,
+    /// Whether the current line is longer than [`MAX_LINE_LEN`]
+    skip_line: bool,
+    function: Option<String>,
+}
+
+impl SelectedFrame<'_> {
+    fn push(&mut self, text: &str) {
+        if self.line.len() + text.len() > MAX_LINE_LEN {
+            self.skip_line = true;
+        }
+        if !self.skip_line {
+            self.line.push_str(text);
+        }
+    }
+
+    /// Check whether the current line is the frame being searched for, then
+    /// start the next line.
+    fn check_line(&mut self) -> fmt::Result {
+        if !self.skip_line {
+            let function =
+                frame_function_name(&self.line).filter(|name| is_selected_crate(name, self.crates));
+            if let Some(function) = function {
+                self.function = Some(function.to_owned());
+                return Err(fmt::Error);
+            }
+        }
+        self.line.clear();
+        self.skip_line = false;
+        Ok(())
+    }
+}
+
+impl Write for SelectedFrame<'_> {
+    fn write_str(&mut self, mut text: &str) -> fmt::Result {
+        while let Some((line, rest)) = text.split_once('\n') {
+            self.push(line);
+            self.check_line()?;
+            text = rest;
+        }
+        self.push(text);
+        Ok(())
+    }
+}
+
+/// Whether the function with name `function` is from one of `crates`, or from
+/// outside the standard library if `crates` is empty.
+fn is_selected_crate(function: &str, crates: &[String]) -> bool {
+    const EXCLUDED: &[&str] = &["std", "core", "alloc", "declaration_site"];
+    let segments = names::path_segments(function);
+    // For trait methods, use the crate of the implementing type
+    let first = segments[0].trim_start_matches('<');
+    let krate = first.split("::").next().unwrap_or(first);
+    if crates.is_empty() {
+        !EXCLUDED.contains(&krate) && !krate.starts_with('_') && krate != "main"
+    } else {
+        crates.iter().any(|it| it == krate)
+    }
+}
@@ -0,0 +1,306 @@
+//! Checking that a function path typed by a user is well formed before it is
+//! searched for.
+
+use std::{error::Error, fmt, ops::Deref, str::FromStr};
+
+use crate::names::{self, NON_PATH_PREFIXES};
+
+/// A function path which [`validate_function_path`] found to be well formed.
+///
+/// This derefs to `str`, so can be passed to the lookups which take a name,
+/// such as [`declaration_by_name`](crate::declaration_by_name), as `&path`.
+/// The lookups don't check the syntax of names, so a malformed name is only
+/// found not to match once every function has been read.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ValidatedPath(String);
+
+impl ValidatedPath {
+    /// The path, exactly as it was validated.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The path, exactly as it was validated.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for ValidatedPath {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ValidatedPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ValidatedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for ValidatedPath {
+    type Err = PathSyntaxError;
+
+    fn from_str(input: &str) -> Result<Self, PathSyntaxError> {
+        validate_function_path(input)
+    }
+}
+
+/// Why [`validate_function_path`] rejected a path, and where.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PathSyntaxError {
+    /// The byte offset in the path of the problem, such as the position of
+    /// an unclosed bracket
+    pub offset: usize,
+    /// What is wrong with the path
+    pub kind: PathSyntaxErrorKind,
+}
+
+/// What is wrong with a path rejected by [`validate_function_path`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum PathSyntaxErrorKind {
+    /// The path is empty, or only whitespace
+    Empty,
+    /// The path is a type which isn't a path, such as `&str` or `(u8, u8)`,
+    /// so can't be the path of a function
+    NotAPath,
+    /// The path has a single segment, such as `main`, but the paths of
+    /// functions always start with their crate
+    SingleSegment,
+    /// A `::` with no segment before or after it, such as a trailing `::`
+    EmptySegment,
+    /// Whitespace at the start or end of the path, several spaces in a row,
+    /// a space next to `::`, or whitespace other than a space
+    UnexpectedWhitespace,
+    /// A character which can't appear in a path, or where it is (such as a
+    /// single `:` outside of braces)
+    DisallowedCharacter(char),
+    /// A `<`, `(`, `[`, `{` or `"` which is never closed
+    UnclosedDelimiter(char),
+    /// A closing `>`, `)`, `]` or `}` without an opening one
+    UnopenedDelimiter(char),
+    /// A closing delimiter which doesn't close the innermost open one, such
+    /// as the `)` of `foo<u8)`
+    MismatchedDelimiter {
+        /// The delimiter which would close the innermost open one
+        expected: char,
+        /// The delimiter in the path
+        found: char,
+    },
+}
+
+impl fmt::Display for PathSyntaxErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSyntaxErrorKind::Empty => f.write_str("the path is empty"),
+            PathSyntaxErrorKind::NotAPath => f.write_str("expected a path, found another type"),
+            PathSyntaxErrorKind::SingleSegment => {
+                f.write_str("the path has a single segment, rather than starting with a crate")
+            }
+            PathSyntaxErrorKind::EmptySegment => f.write_str("empty path segment"),
+            PathSyntaxErrorKind::UnexpectedWhitespace => f.write_str("unexpected whitespace"),
+            PathSyntaxErrorKind::DisallowedCharacter(c) => write!(f, "unexpected `{c}`"),
+            PathSyntaxErrorKind::UnclosedDelimiter(c) => write!(f, "unclosed `{c}`"),
+            PathSyntaxErrorKind::UnopenedDelimiter(c) => write!(f, "unexpected closing `{c}`"),
+            PathSyntaxErrorKind::MismatchedDelimiter { expected, found } => {
+                write!(f, "expected `{expected}`, found `{found}`")
+            }
+        }
+    }
+}
+
+impl fmt::Display for PathSyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)
+    }
+}
+
+impl Error for PathSyntaxError {}
+
+/// The punctuation which can appear in paths, other than delimiters, `:`,
+/// quotes and spaces, which are checked separately.
+const PUNCTUATION: &[char] = &['&', '*', ',', ';', '#', '!', '=', '.', '+', '-'];
+
+/// Check that `input` is a well formed function path, in the grammar of
+/// [`normalize_function_path`](crate::normalize_function_path), such as
+/// `my_crate::Foo<u8>::bar::{{closure}}`.
+///
+/// This checks that the path is split into segments by `::` with none
+/// empty, that its brackets are balanced, and that it has no characters
+/// which can't appear in a path, which is much cheaper than searching for a
+/// malformed name. Names printed by [`type_name`](core::any::type_name) and
+/// by the demanglers are always well formed. The error has the byte offset
+/// of the problem, such as for underlining it in a user interface.
+///
+/// A path which is well formed doesn't necessarily name a function which
+/// exists, or even a function: `alloc::string::String` is well formed.
+///
+/// # Examples
+///
+/// ```rust
+/// use declaration_site::{validate_function_path, PathSyntaxErrorKind};
+///
+/// let valid = [
+///     "my_crate::foo",
+///     "my_crate::Foo<u8>::bar::{{closure}}",
+///     "my_crate[866a6fc06826cdd7]::main::{closure#0}",
+///     "<my_crate::Foo as core::fmt::Debug>::fmt",
+///     "my_crate::foo::<alloc::boxed::Box<dyn core::ops::function::Fn(u8) -> u8 + Send>>",
+///     "my_crate::foo::<[u8; 4], &'static str, {-1_i8}, '>'>",
+///     "core::fmt::num::<impl core::fmt::Debug for usize>::fmt",
+///     "my_crate::foo::h0123456789abcdef",
+///     "my_crate::grüße",
+/// ];
+/// for input in valid {
+///     let path = validate_function_path(input).unwrap();
+///     assert_eq!(path.as_str(), input);
+/// }
+///
+/// let invalid = [
+///     ("", 0, PathSyntaxErrorKind::Empty),
+///     ("&str", 0, PathSyntaxErrorKind::NotAPath),
+///     ("main", 0, PathSyntaxErrorKind::SingleSegment),
+///     ("my_crate::", 8, PathSyntaxErrorKind::EmptySegment),
+///     ("my_crate::::foo", 10, PathSyntaxErrorKind::EmptySegment),
+///     (" my_crate::foo", 0, PathSyntaxErrorKind::UnexpectedWhitespace),
+///     ("my_crate:: foo", 10, PathSyntaxErrorKind::UnexpectedWhitespace),
+///     ("my_crate::foo\n", 13, PathSyntaxErrorKind::UnexpectedWhitespace),
+///     ("my_crate:foo", 8, PathSyntaxErrorKind::DisallowedCharacter(':')),
+///     ("my_crate::foo@", 13, PathSyntaxErrorKind::DisallowedCharacter('@')),
+///     ("my_crate::foo<u8", 13, PathSyntaxErrorKind::UnclosedDelimiter('<')),
+///     ("my_crate::foo>", 13, PathSyntaxErrorKind::UnopenedDelimiter('>')),
+///     (
+///         "my_crate::foo<u8)",
+///         16,
+///         PathSyntaxErrorKind::MismatchedDelimiter { expected: '>', found: ')' },
+///     ),
+/// ];
+/// for (input, offset, kind) in invalid {
+///     let error = validate_function_path(input).unwrap_err();
+///     assert_eq!((error.offset, error.kind), (offset, kind), "for `{input}`");
+/// }
+/// ```
+pub fn validate_function_path(input: &str) -> Result<ValidatedPath, PathSyntaxError> {
+    check_syntax(input).map_err(|(offset, kind)| PathSyntaxError { offset, kind })?;
+    Ok(ValidatedPath(input.to_owned()))
+}
+
+fn check_syntax(input: &str) -> Result<(), (usize, PathSyntaxErrorKind)> {
+    if input.trim().is_empty() {
+        return Err((0, PathSyntaxErrorKind::Empty));
+    }
+    // A path starts with an identifier, a qualified path or a braced segment
+    let first = input.chars().next().unwrap_or_default();
+    if first.is_whitespace() {
+        return Err((0, PathSyntaxErrorKind::UnexpectedWhitespace));
+    }
+    let is_path_start = is_ident_char(first) || first == '<' || first == '{';
+    if !is_path_start || NON_PATH_PREFIXES.iter().any(|it| input.starts_with(it)) {
+        return Err((0, PathSyntaxErrorKind::NotAPath));
+    }
+    // The open delimiters, innermost last
+    let mut open = Vec::new();
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        let before = input[..i].chars().next_back();
+        match c {
+            '<' | '(' | '[' | '{' => open.push((i, c)),
+            // `->` in a fn pointer type is not a closing delimiter
+            '>' if before == Some('-') => {}
+            '>' | ')' | ']' | '}' => match open.pop() {
+                None => return Err((i, PathSyntaxErrorKind::UnopenedDelimiter(c))),
+                Some((_, opening)) if closing_delimiter(opening) != c => {
+                    let expected = closing_delimiter(opening);
+                    let kind = PathSyntaxErrorKind::MismatchedDelimiter { expected, found: c };
+                    return Err((i, kind));
+                }
+                Some(_) => {}
+            },
+            ':' if input[i + 1..].starts_with(':') => {
+                let after = input[i + 2..].chars().next();
+                if after.is_some_and(char::is_whitespace) {
+                    return Err((i + 2, PathSyntaxErrorKind::UnexpectedWhitespace));
+                }
+                // The segment after a `::` followed by another is empty, which
+                // is found at the second `::`
+                if !before.is_some_and(ends_segment)
+                    || !after.is_some_and(|c| c == ':' || starts_segment(c))
+                {
+                    return Err((i, PathSyntaxErrorKind::EmptySegment));
+                }
+                i += 2;
+                continue;
+            }
+            // Such as `{shim:vtable#0}`
+            ':' if open.last().is_some_and(|&(_, opening)| opening == '{') => {}
+            '\'' => {
+                // Otherwise, this is a lifetime
+                if let Some((_, len)) = names::char_literal(&input[i..]) {
+                    i += len;
+                    continue;
+                }
+            }
+            // Such as the ABI of `extern "C" fn()`
+            '"' => match input[i + 1..].find('"') {
+                Some(len) => {
+                    i += len + 2;
+                    continue;
+                }
+                None => return Err((i, PathSyntaxErrorKind::UnclosedDelimiter('"'))),
+            },
+            ' ' => {
+                let after = input[i + 1..].chars().next();
+                if before == Some(':') || matches!(after, None | Some(' ' | ':')) {
+                    let offset = if after == Some(' ') { i + 1 } else { i };
+                    return Err((offset, PathSyntaxErrorKind::UnexpectedWhitespace));
+                }
+            }
+            c if c.is_whitespace() => {
+                return Err((i, PathSyntaxErrorKind::UnexpectedWhitespace));
+            }
+            c if is_ident_char(c) || PUNCTUATION.contains(&c) => {}
+            c => return Err((i, PathSyntaxErrorKind::DisallowedCharacter(c))),
+        }
+        i += c.len_utf8();
+    }
+    if let Some(&(offset, c)) = open.last() {
+        return Err((offset, PathSyntaxErrorKind::UnclosedDelimiter(c)));
+    }
+    if names::path_segments(input).len() < 2 {
+        return Err((0, PathSyntaxErrorKind::SingleSegment));
+    }
+    Ok(())
+}
+
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+fn closing_delimiter(opening: char) -> char {
+    match opening {
+        '<' => '>',
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Whether a `::` can follow `c`, which is the end of the segment before it.
+fn ends_segment(c: char) -> bool {
+    is_ident_char(c) || matches!(c, '>' | '}' | ']')
+}
+
+/// Whether a `::` can be followed by `c`, which is the start of the segment
+/// after it.
+fn starts_segment(c: char) -> bool {
+    is_ident_char(c) || matches!(c, '<' | '{')
+}
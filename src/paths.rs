@@ -0,0 +1,162 @@
+//! Comparing the paths of source files given by users with those in debug
+//! info, and preferring some paths over others.
+
+use crate::attribute_dependency_path;
+
+/// How the paths of source files are compared, such as by
+/// [`DeclarationIndex::functions_declared_in`](crate::DeclarationIndex::functions_declared_in).
+///
+/// Both paths have their separators normalized (so `\` and `/` are the same),
+/// and are compared by component, ignoring `.` components. A relative path
+/// matches any path which ends with the same components, so `src/lib.rs`
+/// matches `/home/me/my_crate/src/lib.rs`. This applies in both directions,
+/// as the paths in debug info are often relative to the directory they were
+/// compiled in.
+///
+/// The default is [`PathComparison::host`], which matches how the file
+/// systems of the current platform compare names. Use an explicit comparison
+/// when reading binaries built for a different platform, such as when
+/// symbolicating a Windows binary on Linux.
+///
+/// ```rust
+/// use declaration_site::PathComparison;
+///
+/// let windows = PathComparison::CaseInsensitive;
+/// assert!(windows.matches(r"SRC\Lib.rs", "src/lib.rs"));
+/// assert!(windows.matches("src/lib.rs", r"C:\Users\me\my_crate\src\lib.rs"));
+/// assert!(windows.matches("./src/lib.rs", "/home/me/my_crate/src/lib.rs"));
+/// assert!(windows.matches(r"C:\work\my_crate\src\lib.rs", "src/lib.rs"));
+/// assert!(!windows.matches("lib.rs", "src/mylib.rs"));
+/// assert!(!windows.matches("my_crate/src/lib.rs", "/src/lib.rs"));
+///
+/// let linux = PathComparison::CaseSensitive;
+/// assert!(!linux.matches(r"SRC\Lib.rs", "src/lib.rs"));
+/// assert!(!linux.matches("src/Lib.rs", "/home/me/my_crate/src/lib.rs"));
+/// assert!(linux.matches(r"src\lib.rs", "/home/me/my_crate/src/lib.rs"));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PathComparison {
+    /// Components must be equal, as on Linux
+    CaseSensitive,
+    /// Components must be equal ignoring case, as on Windows and the default
+    /// file systems of macOS
+    CaseInsensitive,
+}
+
+impl PathComparison {
+    /// The comparison used by the default file systems of the current
+    /// platform, which is [`CaseInsensitive`](Self::CaseInsensitive) on
+    /// Windows and macOS, and [`CaseSensitive`](Self::CaseSensitive)
+    /// elsewhere.
+    pub fn host() -> Self {
+        if cfg!(any(windows, target_os = "macos", target_os = "ios")) {
+            PathComparison::CaseInsensitive
+        } else {
+            PathComparison::CaseSensitive
+        }
+    }
+
+    /// Whether `path` (such as the file of a
+    /// [`DeclarationSite`](crate::DeclarationSite)) is the file at `query`,
+    /// where either may be relative.
+    pub fn matches(self, query: &str, path: &str) -> bool {
+        let query_components = components(query).collect::<Vec<_>>();
+        let path_components = components(path).collect::<Vec<_>>();
+        let len = query_components.len().min(path_components.len());
+        if len == 0 || query_components.len() > len && is_absolute(path) {
+            return false;
+        }
+        query_components[query_components.len() - len..]
+            .iter()
+            .zip(&path_components[path_components.len() - len..])
+            .all(|(a, b)| match self {
+                PathComparison::CaseSensitive => a == b,
+                PathComparison::CaseInsensitive => a
+                    .chars()
+                    .flat_map(char::to_lowercase)
+                    .eq(b.chars().flat_map(char::to_lowercase)),
+            })
+    }
+}
+
+impl Default for PathComparison {
+    fn default() -> Self {
+        PathComparison::host()
+    }
+}
+
+/// Which of several functions matching the same name is preferred by the
+/// path of its declaration site, as chosen by
+/// [`DeclarationSearch::path_preference`](crate::DeclarationSearch::path_preference).
+///
+/// The same function can be found in several libraries with different
+/// files, such as when one library was built from the crate in the
+/// workspace, and another from a copy of the crate vendored under `target/`.
+/// Each function is given a [`score`](Self::score) from the path of its
+/// declaration site, and of the functions which would otherwise be chosen
+/// equally, the one with the highest score is chosen. The search continues
+/// through every loaded library until a function with the highest possible
+/// score is found, so lookups of functions declared outside the workspace
+/// (such as in the standard library) search every library.
+///
+/// The default is [`Workspace`](Self::Workspace).
+///
+/// ```rust
+/// use declaration_site::PathPreference;
+///
+/// let preference = PathPreference::Workspace;
+/// let workspace = preference.score("/home/me/my_crate/src/lib.rs");
+/// assert!(workspace > preference.score("/home/me/my_crate/target/vendor/my_crate/src/lib.rs"));
+/// assert!(
+///     workspace
+///         > preference.score(
+///             "/home/me/.cargo/registry/src/index.crates.io-6f17d22bba15001f/my_crate-0.1.0/src/lib.rs"
+///         )
+/// );
+/// assert_eq!(workspace, preference.score("src/lib.rs"));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum PathPreference {
+    /// Prefer paths which look like they are in a workspace, rather than in
+    /// a `target` directory or in cargo's download directories (see
+    /// [`attribute_dependency_path`])
+    #[default]
+    Workspace,
+    /// Don't prefer any path, so the first function found is chosen, as in
+    /// the order described in [`DeclarationChoice`](crate::DeclarationChoice)
+    FirstFound,
+}
+
+impl PathPreference {
+    /// The highest score of any path.
+    pub(crate) const TOP_SCORE: u32 = 1;
+
+    /// The score of the declaration site in the file at `path`, where higher
+    /// scores are preferred.
+    ///
+    /// With [`Workspace`](Self::Workspace), this is 1 for paths which look
+    /// like they are in a workspace, and 0 for the others. With
+    /// [`FirstFound`](Self::FirstFound), every path scores 1.
+    pub fn score(self, path: &str) -> u32 {
+        match self {
+            PathPreference::Workspace => {
+                let in_target = components(path).any(|component| component == "target");
+                u32::from(!in_target && attribute_dependency_path(path).is_none())
+            }
+            PathPreference::FirstFound => Self::TOP_SCORE,
+        }
+    }
+}
+
+/// Whether `path` is absolute on any platform, such as `/src/lib.rs` or
+/// `C:\src\lib.rs`.
+fn is_absolute(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || path.get(1..2) == Some(":")
+}
+
+/// The components of `path`, separated by either `/` or `\`, without empty
+/// and `.` components.
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+}
@@ -0,0 +1,264 @@
+//! Helpers for matching the names of functions in PDBs, which rustc spells
+//! differently to [`core::any::type_name`].
+//!
+//! The names of generic arguments in PDBs are spelled like C++ types, so
+//! `&str` is `ref$<str$>` and `(u8, u16)` is `tuple$<u8,u16>`. Methods of
+//! trait impls are named after their impl block (`my_crate::impl$3::method`),
+//! rather than the type and trait, so can only be found by the crate and
+//! name of the method.
+
+use std::borrow::Cow;
+
+use crate::{
+    names::{self, NameMatch},
+    object::CompilationUnit,
+    FunctionRecord,
+};
+
+/// Respell the C++ style names of types in the PDB function name `name` as
+/// in [`type_name`](core::any::type_name), so that names of generic
+/// functions match.
+///
+/// This also spells `closure$N` segments as `{closure#N}`, and `impl$<T,
+/// Trait>` (which is used for vtables) as `<T as Trait>`. The `impl$N`
+/// segments of methods in impl blocks are kept.
+pub(crate) fn normalize_msvc_name(name: &str) -> Cow<'_, str> {
+    if !name.contains('$') && !name.contains(',') && !name.contains(" >") {
+        return Cow::Borrowed(name);
+    }
+    let mut result = String::with_capacity(name.len());
+    write_normalized(name, &mut result);
+    if result == name {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+/// [`normalize_msvc_name`], keeping `name` if it doesn't change.
+pub(crate) fn normalize_owned_msvc_name<T>(name: T) -> T
+where
+    T: AsRef<str> + From<String>,
+{
+    match normalize_msvc_name(name.as_ref()) {
+        Cow::Owned(normalized) => T::from(normalized),
+        Cow::Borrowed(_) => name,
+    }
+}
+
+fn write_normalized(mut name: &str, result: &mut String) {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    while let Some(c) = name.chars().next() {
+        if c == '<' {
+            let Some((arguments, len)) = names::generic_arguments(&name[1..]) else {
+                result.push_str(name);
+                return;
+            };
+            result.push('<');
+            write_list(&arguments, ", ", result);
+            result.push('>');
+            name = &name[1 + len..];
+            continue;
+        }
+        if !is_ident(c) {
+            result.push(c);
+            name = &name[c.len_utf8()..];
+            continue;
+        }
+        let len = name.find(|c| !is_ident(c)).unwrap_or(name.len());
+        let ident = &name[..len];
+        name = &name[len..];
+        let Some(kind) = ident.strip_suffix('$') else {
+            match ident.strip_prefix("closure$") {
+                Some(number)
+                    if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    result.push_str("{closure#");
+                    result.push_str(number);
+                    result.push('}');
+                }
+                _ => result.push_str(ident),
+            }
+            continue;
+        };
+        let Some((rest, (arguments, len))) = name
+            .strip_prefix('<')
+            .and_then(|rest| Some((rest, names::generic_arguments(rest)?)))
+        else {
+            result.push_str(match kind {
+                "str" => "str",
+                "never" => "!",
+                _ => ident,
+            });
+            continue;
+        };
+        name = &rest[len..];
+        match (kind, &arguments[..]) {
+            ("ref", [pointee]) => write_prefixed("&", pointee, result),
+            ("ref_mut", [pointee]) => write_prefixed("&mut ", pointee, result),
+            ("ptr_const", [pointee]) => write_prefixed("*const ", pointee, result),
+            ("ptr_mut", [pointee]) => write_prefixed("*mut ", pointee, result),
+            ("slice" | "slice2", [element]) => {
+                result.push('[');
+                write_normalized(element, result);
+                result.push(']');
+            }
+            ("array", [element, len]) => {
+                result.push('[');
+                write_normalized(element, result);
+                result.push_str("; ");
+                result.push_str(len);
+                result.push(']');
+            }
+            ("tuple", elements) => {
+                result.push('(');
+                write_list(elements, ", ", result);
+                if elements.len() == 1 {
+                    result.push(',');
+                }
+                result.push(')');
+            }
+            ("dyn", traits) => {
+                result.push_str("dyn ");
+                write_list(traits, " + ", result);
+            }
+            ("enum2", [inner]) => write_normalized(inner, result),
+            ("impl", [self_type, trait_]) => {
+                result.push('<');
+                write_normalized(self_type, result);
+                result.push_str(" as ");
+                write_normalized(trait_, result);
+                result.push('>');
+            }
+            _ => {
+                result.push_str(ident);
+                result.push('<');
+                write_list(&arguments, ",", result);
+                result.push('>');
+            }
+        }
+    }
+}
+
+fn write_prefixed(prefix: &str, name: &str, result: &mut String) {
+    result.push_str(prefix);
+    write_normalized(name, result);
+}
+
+fn write_list(names: &[&str], separator: &str, result: &mut String) {
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            result.push_str(separator);
+        }
+        write_normalized(name, result);
+    }
+}
+
+/// Whether the name of `function`, from a PDB, matches the name being
+/// searched for as a [`NameMatch::Suffix`].
+///
+/// The names match if they have the same final identifier (ignoring its
+/// generic arguments, which include those of the impl block in PDBs) and:
+/// - The PDB name is a method in an impl block (`my_crate::impl$3::method`)
+///   of the crate of the self type or trait being searched for, whose generic
+///   arguments could be for the self type (see [`impl_matches`])
+/// - Or the PDB name is unqualified (`method`), and the crate being searched
+///   for is the crate of the module the function was compiled in (see
+///   [`CompilationUnit::crate_name`]), or the module's crate is unknown
+/// - Or the PDB name is the name being searched for, with different generic
+///   arguments for its final identifier
+///
+/// Several methods with the same name in different impl blocks of a crate
+/// can match like this.
+pub(crate) fn match_pdb_names(function: &FunctionRecord<'_>, query: &str) -> Option<NameMatch> {
+    let pdb_name = names::canonical_name(function.name());
+    let query = names::canonical_name(query);
+    let pdb_segments = names::path_segments(&pdb_name);
+    let query_segments = names::path_segments(&query);
+    let (pdb_last, pdb_path) = pdb_segments.split_last()?;
+    let (query_last, query_path) = query_segments.split_last()?;
+    if names::segment_ident(pdb_last) != names::segment_ident(query_last) {
+        return None;
+    }
+    let crates = query_crates(&query);
+    let matches = match pdb_path {
+        [] => {
+            let unit_crate = function
+                .compilation_unit()
+                .and_then(CompilationUnit::crate_name);
+            unit_crate.is_none_or(|unit_crate| crates.contains(&&*unit_crate))
+        }
+        [.., impl_block] if is_impl_block(impl_block) => {
+            let self_type = match names::split_qualified(&query) {
+                Some((self_type, _, _)) => self_type,
+                // The `Type::method` spelling
+                None => query[..query.len() - query_last.len()].trim_end_matches(':'),
+            };
+            let pdb_arguments = segment_arguments(pdb_last);
+            let method_arguments = segment_arguments(query_last);
+            // The method's own generic arguments come after the impl block's
+            let impl_arguments = pdb_arguments
+                .strip_suffix(&method_arguments[..])
+                .unwrap_or(&pdb_arguments);
+            crates.contains(&pdb_path[0]) && impl_matches(impl_arguments, self_type)
+        }
+        _ => pdb_path == query_path,
+    };
+    matches.then_some(NameMatch::Suffix)
+}
+
+/// Whether an impl block with the generic arguments `impl_arguments` could
+/// be for `self_type`.
+///
+/// If the self type is generic, such as `Wrapper<u8>`, the impl block must
+/// be generic too, with arguments which appear in the self type. Otherwise,
+/// the impl block must not be generic, or must be a blanket impl for the
+/// self type. This means that impls for instances of generic types, such as
+/// `impl Shape for Wrapper<u8>`, are not found.
+fn impl_matches(impl_arguments: &[&str], self_type: &str) -> bool {
+    let self_type = self_type.trim_start_matches(['&', '*']);
+    let self_type = ["mut ", "const ", "dyn "]
+        .iter()
+        .fold(self_type, |it, prefix| {
+            it.strip_prefix(prefix).unwrap_or(it)
+        });
+    let is_generic = names::path_segments(self_type)
+        .last()
+        .is_some_and(|segment| segment.len() > names::segment_ident(segment).len());
+    if is_generic {
+        !impl_arguments.is_empty()
+            && impl_arguments
+                .iter()
+                .all(|argument| self_type.contains(argument))
+    } else {
+        impl_arguments.is_empty() || impl_arguments == [self_type]
+    }
+}
+
+/// The generic arguments of a path segment, such as `u8` and `u16` for
+/// `foo<u8, u16>`.
+fn segment_arguments(segment: &str) -> Vec<&str> {
+    match segment[names::segment_ident(segment).len()..].strip_prefix('<') {
+        Some(arguments) => names::generic_arguments(arguments).map_or_else(Vec::new, |it| it.0),
+        None => Vec::new(),
+    }
+}
+
+/// The crates of the self type and trait of a qualified path such as
+/// `<my_crate::Foo as other_crate::Bar>::baz`, or the crate of another path.
+fn query_crates(query: &str) -> Vec<&str> {
+    let mut crates = Vec::new();
+    crates.extend(names::crate_name(query));
+    if let Some((_, trait_, _)) = names::split_qualified(query) {
+        crates.extend(names::crate_name(trait_));
+    }
+    crates
+}
+
+/// Whether `segment` is the name rustc gives to an impl block in PDBs, such
+/// as `impl$3`.
+fn is_impl_block(segment: &str) -> bool {
+    segment
+        .strip_prefix("impl$")
+        .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
@@ -0,0 +1,50 @@
+//! Choosing which objects in a file to search, and in which order.
+
+use symbolic_debuginfo::ObjectKind;
+
+use crate::{
+    limits,
+    object::{Archive, Object},
+};
+
+/// How useful `object` is likely to be when searching for functions, used to
+/// order the objects in a file with several (such as a Mach-O fat file or a
+/// static library).
+///
+/// Objects with higher priorities are searched first, and objects with a
+/// priority of `0` aren't searched at all. From highest to lowest:
+/// - `4`: Debug companion files (such as dSYMs, PDBs or split DWARF), which
+///   have the most complete debug info
+/// - `3`: Executables, libraries and dumps with debug info
+/// - `2`: Relocatable objects with debug info, whose addresses haven't been
+///   relocated
+/// - `1`: Objects without any debug info
+/// - `0`: Objects which only contain sources, such as source bundles
+pub fn scan_priority(object: &Object<'_>) -> u8 {
+    match object.kind() {
+        ObjectKind::Sources => 0,
+        _ if !object.has_debug_info() => 1,
+        ObjectKind::Relocatable => 2,
+        ObjectKind::Debug => 4,
+        _ => 3,
+    }
+}
+
+/// The objects in `archive` which should be searched, with their indices and
+/// priorities, in the order they should be searched.
+///
+/// Objects which fail to parse (or whose parser panics) are skipped. Objects
+/// with the same priority are kept in the order they appear in the archive.
+pub(crate) fn prioritized_objects<'d>(archive: &Archive<'d>) -> Vec<(usize, u8, Object<'d>)> {
+    let mut objects = archive
+        .objects()
+        .enumerate()
+        .filter_map(|(index, object)| {
+            let object = object.ok()?;
+            let priority = limits::catch_parser_panic(|| scan_priority(&object))?;
+            (priority > 0).then_some((index, priority, object))
+        })
+        .collect::<Vec<_>>();
+    objects.sort_by_key(|(_, priority, _)| std::cmp::Reverse(*priority));
+    objects
+}
@@ -0,0 +1,737 @@
+//! Searching the libraries loaded into the current process, enabled by the
+//! `process` feature.
+
+use std::{
+    cell::Cell,
+    env::current_exe,
+    io,
+    path::{Path, PathBuf},
+};
+
+use findshlibs::SharedLibrary;
+use symbolic_debuginfo::Function;
+
+use crate::{
+    cancellation,
+    choice::Candidates,
+    embedded_table, file_access, for_each_inlinee, limits, low_allocation,
+    names::{self, FunctionLikelihood, NameMatch},
+    pairing::{self, PairingReport},
+    record::ScannedObject,
+    redaction, scan_data, scan_indexed, DeclarationChoice, DeclarationError, DeclarationSearch,
+    DeclarationSite, FunctionRecord, IterationControl, MacroSite, MatchRelaxation, NameQueries,
+    PairingStatus,
+};
+
+/// Attempt to get the declaration site of the function item type of the
+/// given value, using its type name. This is a trivial wrapper around
+/// [`declaration`], but may be easier to use since function item types
+/// cannot be named.
+///
+/// This will (probably) return `None` for non-function item types.
+///
+/// The function item can be wrapped in references (`&my_fn` or `&mut
+/// my_fn`), [`Box`], [`Rc`](std::rc::Rc), [`Arc`](std::sync::Arc) or
+/// [`Option`], up to 8 times, such as `&Some(Box::new(my_fn))`, as described
+/// in [`declaration`]. Trait objects (such as `&dyn Fn()`) and function
+/// pointers (`fn()`) don't name the function, so can't be found.
+///
+/// Each call scans for the function. Use
+/// [`cached_declaration_of`](crate::cached_declaration_of) to look up the
+/// same function repeatedly.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declaration_of<T>(_: &T) -> Option<DeclarationSite> {
+    declaration::<T>()
+}
+
+/// The former name of [`declaration_of`], which behaves the same way.
+#[deprecated(since = "0.3.0", note = "renamed to `declaration_of`")]
+pub fn declaration_by_val<T>(value: &T) -> Option<DeclarationSite> {
+    declaration_of(value)
+}
+
+/// Attempt to get the declaration site of the function item type of the
+/// given type, using its type name. In most cases, you may want to use  
+/// [`declaration_of`], since function item types are unnameable.
+///
+/// This function uses [`declaration_by_name`] with the [type
+/// name](core::any::type_name) of the type parameter `T`. If `T` is a
+/// reference to, or a [`Box`], [`Rc`](std::rc::Rc), [`Arc`](std::sync::Arc)
+/// or [`Option`] of a function item type (or a closure), nested up to 8
+/// times, the function is searched for instead. Other wrappers (including
+/// `Box` with a custom allocator), and names wrapped more times, are
+/// searched for as they are.
+///
+/// This will (probably) return `None` for non-function item types.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+///
+/// Note: This could be a feature provided by [`core::any`] (using a new
+/// intrinsic, returning [`&'static Location<'static>`](core::panic::Location)).
+/// However, that (currently) doesn't exist. If it did, it would be:
+/// - significantly faster
+/// - more correct for functions as it would provide a column.
+/// - support e.g. structs, unions
+/// - not able to be run dynamically as [`declaration_by_name`]
+pub fn declaration<T>() -> Option<DeclarationSite> {
+    declaration_by_name(names::unwrap_fn_item_type(core::any::type_name::<T>()))
+}
+
+/// Attempt to get the declaration site of a currently loaded function
+/// with the given (unmangled) name.
+///
+/// Names which cannot possibly be the name of a function (such as `u32` or
+/// `&str`) return `None` without scanning. Both names are compared after
+/// [`canonical_name`](crate::canonical_name), so crate disambiguators (such as
+/// `my_crate[866a6fc06826cdd7]`) and trailing legacy symbol hashes (such as
+/// `::h0123456789abcdef`) are ignored. Methods of trait impls can be found
+/// using either `<Type as Trait>::method` or `Type::method`, as described in
+/// [`names_match`](crate::names_match).
+///
+/// Closures can be found using `{{closure}}`, `{closure}` or `{closure#N}`
+/// segments. Without the `#N` disambiguator, every closure in the parent
+/// function matches, and the one declared on the lowest line is returned. Use
+/// [`declaration_by_name_all`] to get all of them.
+///
+/// If several functions match exactly, such as copies of an `#[inline]` or
+/// generic function compiled into the codegen units of several crates, the
+/// one compiled in a unit of its own crate is preferred. See
+/// [`DeclarationChoice`] for the details, and [`explain_declaration_by_name`]
+/// for finding which function was chosen.
+///
+/// `#[no_mangle]` functions can be found by their symbol (such as
+/// `my_ffi_entry`), as well as by their path (`my_crate::my_ffi_entry`).
+/// There is no lookup by mangled name: the symbols of other functions are
+/// demangled before being compared, so the demangled name (from
+/// [`rustc-demangle`](https://docs.rs/rustc-demangle), formatted with `{:#}`
+/// to omit the hash) finds the same function.
+///
+/// For functions generated by `macro_rules!` macros, this returns the site
+/// inside the macro. Use [`DeclarationSearch::macro_site`] to find the
+/// invocation instead, and [`DeclarationSearch`] for the other options of a
+/// lookup, such as the version or package to prefer.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declaration_by_name(name: &str) -> Option<DeclarationSite> {
+    DeclarationSearch::new(name).find()
+}
+
+/// Find which currently loaded function [`declaration_by_name`] chooses for
+/// the given (unmangled) name, and why.
+///
+/// This is useful for debugging why [`declaration_by_name`] returned an
+/// unexpected declaration site, such as when several functions match the
+/// name. Returns `None` if no function matched. This is
+/// [`DeclarationSearch::explain`] without any options.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn explain_declaration_by_name(name: &str) -> Option<DeclarationChoice> {
+    DeclarationSearch::new(name).explain()
+}
+
+/// Find every currently loaded function which matches the given (unmangled)
+/// name, as in [`declaration_by_name_all`], with the details of each as in
+/// [`explain_declaration_by_name`].
+///
+/// This includes every copy of a function, such as those compiled into the
+/// units of several crates, and the functions of each version of a crate
+/// when several are linked (see [`DeclarationChoice::version`]). The functions
+/// are sorted by the file and line of their declaration sites. If the
+/// function was only found inlined into other functions, its inlined copies
+/// are returned, with the calls they were inlined at (see
+/// [`DeclarationChoice::inlined_into`]). This is
+/// [`DeclarationSearch::explain_all`] without any options.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn explain_declaration_by_name_all(name: &str) -> Vec<DeclarationChoice> {
+    DeclarationSearch::new(name).explain_all()
+}
+
+/// Every currently loaded function which matches the name of `search`, for
+/// [`DeclarationSearch::explain_all`].
+pub(crate) fn explain_all(search: &DeclarationSearch<'_>) -> Vec<DeclarationChoice> {
+    let name = search.name;
+    let mut candidates = Candidates::new(search);
+    let relaxed_key = names::relaxed_key(name);
+    let summary = scan_loaded_files(|_, file_data, pairing| {
+        candidates.set_debug_pairing(pairing);
+        let _ = scan_data(file_data, &mut |object, demangled_name, function| {
+            let function = FunctionRecord::new(demangled_name, &function).with_object(object);
+            match function.match_name(name) {
+                Some(kind) => {
+                    candidates.add(kind, &function);
+                }
+                None if candidates.is_empty() => {
+                    for_each_inlinee(&function, &mut |caller, inlinee| {
+                        if let Some(kind) = inlinee.match_name(name) {
+                            candidates.add_inlined(kind, inlinee, caller);
+                        }
+                    });
+                    add_relaxed_match(&mut candidates, &function, &relaxed_key);
+                }
+                None => {}
+            }
+            IterationControl::Continue
+        });
+        IterationControl::Continue
+    });
+    if summary.cancelled {
+        return Vec::new();
+    }
+    let mut choices = candidates.all();
+    choices.sort_by(|a, b| {
+        a.relaxation
+            .cmp(&b.relaxation)
+            .then_with(|| match (&a.site, &b.site) {
+                (Ok(a), Ok(b)) => a.file.cmp(&b.file).then(a.line.cmp(&b.line)),
+                (a, b) => a.is_err().cmp(&b.is_err()),
+            })
+    });
+    choices
+}
+
+/// Get the declaration sites of every currently loaded function which
+/// matches the given (unmangled) name, as in [`declaration_by_name`].
+///
+/// This is mostly useful for names which match several functions, such as
+/// `my_crate::main::{{closure}}`, which matches every closure directly inside
+/// `my_crate::main`. The sites are sorted by file and line, without
+/// duplicates. If the function was only found inlined into other functions,
+/// the sites of its inlined copies are returned.
+///
+/// When several versions of a crate are linked, this includes the sites of
+/// the function in each version. Use [`explain_declaration_by_name_all`] to
+/// get the version of each.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declaration_by_name_all(name: &str) -> Vec<DeclarationSite> {
+    let mut sites = Vec::new();
+    if names::function_likelihood(name) == FunctionLikelihood::Impossible {
+        return sites;
+    }
+    // Inlined copies, then the default bodies of a trait method, are only
+    // used if nothing else matched
+    let mut inlined = Vec::new();
+    let mut default_impls = Vec::new();
+    let summary = scan_currently_loaded_rust_functions(|object, demangled_name, function| {
+        let function = FunctionRecord::new(demangled_name, &function).with_object(object);
+        match function.match_name(name) {
+            Some(NameMatch::DefaultImpl) => {
+                default_impls.extend(DeclarationSite::try_from(&function));
+            }
+            Some(_) => sites.extend(DeclarationSite::try_from(&function)),
+            None if sites.is_empty() => for_each_inlinee(&function, &mut |_, inlinee| {
+                if inlinee.match_name(name).is_some() {
+                    inlined.extend(DeclarationSite::try_from(inlinee));
+                }
+            }),
+            None => {}
+        }
+        IterationControl::Continue
+    });
+    if summary.cancelled {
+        return Vec::new();
+    }
+    if sites.is_empty() {
+        sites = inlined;
+    }
+    if sites.is_empty() {
+        sites = default_impls;
+    }
+    sites.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    sites.dedup();
+    sites
+}
+
+/// Attempt to get the declaration sites of several currently loaded
+/// functions, using a single scan.
+///
+/// The result at each index is the declaration site of the function with the
+/// name at the same index in `names`, found as in [`declaration_by_name`].
+/// This is much faster than calling [`declaration_by_name`] repeatedly, as the
+/// loaded libraries only need to be read once. Each loaded function is looked
+/// up among the names by hashing, so the time taken to check it depends on
+/// the length of its name rather than on the number of names, even when many
+/// names share prefixes or last segments (such as the names of a call tree).
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declarations_by_name(names: &[&str]) -> Vec<Option<DeclarationSite>> {
+    let mut queries = NameQueries::new(names);
+    if !queries.is_empty() {
+        let summary = scan_loaded_files(|_, file_data, _| {
+            if let Some(control) = queries.visit_indexed(file_data, None) {
+                return control;
+            }
+            match scan_data(file_data, &mut |object, name, function| {
+                queries.visit(&FunctionRecord::new(name, &function).with_object(object))
+            }) {
+                Ok(IterationControl::Break) => IterationControl::Break,
+                _ => IterationControl::Continue,
+            }
+        });
+        if summary.cancelled {
+            return vec![None; names.len()];
+        }
+    }
+    queries.results()
+}
+
+/// Like [`declaration_of`], but reports why the declaration site could not be
+/// found.
+///
+/// The `functions` example in this crate demonstrates this API.
+pub fn try_declaration_of<T>(_: &T) -> Result<DeclarationSite, DeclarationError> {
+    try_declaration::<T>()
+}
+
+/// Like [`declaration`], but reports why the declaration site could not be
+/// found.
+pub fn try_declaration<T>() -> Result<DeclarationSite, DeclarationError> {
+    try_declaration_by_name(names::unwrap_fn_item_type(core::any::type_name::<T>()))
+}
+
+/// Like [`declaration_by_name`], but reports why the declaration site could
+/// not be found.
+///
+/// This is [`DeclarationSearch::try_find`] without any options.
+///
+/// # Errors
+///
+/// - [`DeclarationError::ProbablyNotAFunction`] if `name` doesn't look like
+///   the name of a function. In this case, no scan is performed. Note that
+///   this is a heuristic, and so functions with unconventional names (such as
+///   `my_crate::MyFunction`) may be reported as not being functions. Use
+///   [`declaration_by_name`] to always search for these
+/// - [`DeclarationError::NoDebugInfo`] if none of the loaded libraries
+///   which could be read has debug info, such as in a stripped release
+///   build, so the function can't be found whatever its name (unless it is in
+///   a table registered with
+///   [`register_embedded_table`](crate::register_embedded_table))
+/// - [`DeclarationError::NotFound`] if no function with this name was found
+/// - [`DeclarationError::MissingLines`] if the function was found, but its
+///   debug info has no source locations
+/// - [`DeclarationError::InlinedInto`] if the function was only found inlined
+///   into other functions, and its inlined copies have no source locations.
+///   If they do, their site is returned, and
+///   [`explain_declaration_by_name`] reports where they were inlined
+/// - [`DeclarationError::Cancelled`] if the scan was cancelled (see
+///   [`with_cancellation`](crate::with_cancellation)) before it finished, even
+///   if a function had already been found, as a better match may not have
+///   been searched yet
+pub fn try_declaration_by_name(name: &str) -> Result<DeclarationSite, DeclarationError> {
+    DeclarationSearch::new(name).try_find()
+}
+
+/// The declaration site of the function chosen by `search`, or why it could
+/// not be found.
+pub(crate) fn scan_for_name(
+    search: &DeclarationSearch<'_>,
+) -> Result<DeclarationSite, DeclarationError> {
+    if search.version.is_none()
+        && search.package.is_none()
+        && search.macro_site == MacroSite::Expansion
+    {
+        if let Some(site) = embedded_table::declaration(search) {
+            return Ok(site);
+        }
+    }
+    let (choice, summary) = choose_by_name(search);
+    if summary.cancelled {
+        return Err(DeclarationError::Cancelled);
+    }
+    match choice {
+        Some(choice) => match choice.site {
+            Ok(site) => Ok(site),
+            Err(_) if !choice.inlined_into.is_empty() => {
+                Err(DeclarationError::InlinedInto(choice.inlined_into))
+            }
+            Err(error) => Err(error.into()),
+        },
+        None if summary.reentrant => Err(DeclarationError::Reentrant),
+        None if summary.files_searched == 0 => Err(DeclarationError::NoSearchTargets {
+            current_exe_error: summary.current_exe_error,
+            libraries_enumerated: summary.libraries_enumerated,
+        }),
+        None if !summary.mismatched_debug_files.is_empty() => {
+            Err(DeclarationError::MismatchedDebugFiles(
+                summary
+                    .mismatched_debug_files
+                    .into_iter()
+                    .map(redaction::pairing_report)
+                    .collect(),
+            ))
+        }
+        None if !summary.any_has_debug_info() => Err(DeclarationError::NoDebugInfo {
+            libraries_checked: summary.files_searched,
+        }),
+        None => Err(DeclarationError::NotFound),
+    }
+}
+
+/// Choose the currently loaded function for `search`, as described in
+/// [`DeclarationChoice`].
+pub(crate) fn choose_by_name(
+    search: &DeclarationSearch<'_>,
+) -> (Option<DeclarationChoice>, ScanSummary) {
+    let name = search.name;
+    let mut candidates = Candidates::new(search);
+    let relaxed_key = names::relaxed_key(name);
+    let summary = scan_loaded_files(|_, file_data, pairing| {
+        candidates.set_debug_pairing(pairing);
+        // Copies of a function compiled into the units of other crates are in
+        // the same file as the function itself, and have the same name, so are
+        // in the accelerator tables too
+        let mut indexed = candidates.clone();
+        let found = scan_indexed(file_data, name, &mut |object, demangled_name, function| {
+            let function = FunctionRecord::new(demangled_name, &function).with_object(object);
+            visit_candidate(&mut indexed, &function, name, &relaxed_key)
+        });
+        if found.is_some() && indexed.has_exact() {
+            candidates = indexed;
+            return IterationControl::Break;
+        }
+        let _ = scan_data(file_data, &mut |object, demangled_name, function| {
+            let function = FunctionRecord::new(demangled_name, &function).with_object(object);
+            visit_candidate(&mut candidates, &function, name, &relaxed_key)
+        });
+        if candidates.has_exact() {
+            IterationControl::Break
+        } else {
+            IterationControl::Continue
+        }
+    });
+    // A function found before the scan was cancelled might not be the one
+    // which would have been chosen
+    let choice = candidates.best().filter(|_| !summary.cancelled);
+    (choice, summary)
+}
+
+/// Record `function` in `candidates` if it matches `name`, or if it contains
+/// an inlined function or relaxed match for `name` and nothing else has
+/// matched, returning whether it should be chosen without searching further.
+fn visit_candidate(
+    candidates: &mut Candidates,
+    function: &FunctionRecord<'_>,
+    name: &str,
+    relaxed_key: &str,
+) -> IterationControl {
+    let Some(kind) = function.match_name(name) else {
+        if candidates.is_empty() {
+            for_each_inlinee(function, &mut |caller, inlinee| {
+                if let Some(kind) = inlinee.match_name(name) {
+                    candidates.add_inlined(kind, inlinee, caller);
+                }
+            });
+            add_relaxed_match(candidates, function, relaxed_key);
+        }
+        return IterationControl::Continue;
+    };
+    if candidates.add(kind, function) {
+        IterationControl::Break
+    } else {
+        IterationControl::Continue
+    }
+}
+
+/// Record `function` in `candidates` if its name matches the name with the
+/// [`relaxed_key`](names::relaxed_key) `query_key` with the candidates'
+/// relaxation.
+fn add_relaxed_match(candidates: &mut Candidates, function: &FunctionRecord<'_>, query_key: &str) {
+    if candidates.relaxation() == MatchRelaxation::None {
+        return;
+    }
+    let key = names::relaxed_key(function.name());
+    if let Some(level) = names::match_relaxed(&key, query_key, candidates.relaxation()) {
+        candidates.add_relaxed(level, function);
+    }
+}
+
+/// Run `callback` on each currently loaded function which can be demangled in
+/// the current context, See the caveats section on the [module level
+/// documentation](crate).
+///
+/// See [`FunctionRecord`] for the information available about each function.
+///
+/// As this crate depends on [`symbolic_demangle`] using the `"rust"` feature,
+/// this may skip non-Rust functions (e.g. from libc), despite them having debug
+/// information available. Functions compiled by rustc whose names aren't
+/// mangled, such as `#[no_mangle]` functions, are included with their
+/// unmangled names. However, on platforms where mangling isn't used in
+/// debug files, such as Windows' pdb files, this may also call callback with
+/// non-Rust functions. Additionally, if [`symbolic_demangle`] is in the current
+/// dependency tree with any other demangling features enabled supported,
+/// they may also be used.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+///
+/// Note that `callback` can cause this process to end early by returning [`IterationControl::Break`].
+/// If doing so, return [IterationControl::Continue] to continue
+///
+/// Returning [`()`](unit) will be taken as returning [IterationControl::Continue].
+pub fn for_each_loaded_function<C>(mut callback: impl FnMut(FunctionRecord<'_>) -> C)
+where
+    C: Into<IterationControl>,
+{
+    scan_currently_loaded_rust_functions(|object, name, function| {
+        callback(FunctionRecord::new(name, &function).with_object(object)).into()
+    });
+}
+
+/// Run `callback` on the demangled name and debug info of each currently
+/// loaded function which can be demangled.
+///
+/// This exposes types from [`symbolic_debuginfo`], so upgrading that
+/// dependency would be a breaking change for users of this function. Use
+/// [`for_each_loaded_function`] instead, which behaves the same way.
+#[deprecated(
+    since = "0.3.0",
+    note = "use `for_each_loaded_function`, which doesn't expose `symbolic_debuginfo` types"
+)]
+pub fn for_some_currently_loaded_rust_functions<C>(mut callback: impl FnMut(String, Function) -> C)
+where
+    C: Into<IterationControl>,
+{
+    scan_currently_loaded_rust_functions(|_, name, function| callback(name, function).into());
+}
+
+/// What was searched by [`scan_currently_loaded_rust_functions`], used to
+/// explain why a search found nothing.
+pub(crate) struct ScanSummary {
+    pub libraries_enumerated: usize,
+    /// The number of library or debug files which could be read
+    pub files_searched: usize,
+    /// The paths of the files searched, to find whether any have debug info
+    /// if nothing was found (see [`any_has_debug_info`](Self::any_has_debug_info))
+    pub searched_files: Vec<PathBuf>,
+    pub current_exe_error: Option<io::Error>,
+    /// Whether nothing was searched, as the current thread was already
+    /// scanning
+    pub reentrant: bool,
+    /// The debug companions which don't match their libraries, and so weren't
+    /// searched (or were only searched as their libraries have no debug info)
+    pub mismatched_debug_files: Vec<PairingReport>,
+    /// Whether the scan stopped before finishing, as it was cancelled (see
+    /// [`with_cancellation`](crate::with_cancellation)), so what it found is
+    /// incomplete
+    pub cancelled: bool,
+}
+
+impl ScanSummary {
+    /// Whether any of the files searched has debug info.
+    ///
+    /// The files are read again, so this should only be used once the search
+    /// has failed, rather than checking every file during every scan.
+    pub fn any_has_debug_info(&self) -> bool {
+        self.searched_files.iter().any(|path| {
+            read_loaded_file(path).is_ok_and(|data| {
+                limits::catch_parser_panic(|| pairing::has_debug_info(&data)).unwrap_or(false)
+            })
+        })
+    }
+}
+
+thread_local! {
+    /// Whether the current thread is inside [`scan_currently_loaded_rust_functions`].
+    static SCANNING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the current thread is currently scanning the loaded libraries.
+///
+/// Another scan can't be started until it finishes, such as from a panic
+/// hook, if the panic came from inside a scan.
+pub(crate) fn is_scanning() -> bool {
+    SCANNING.with(Cell::get)
+}
+
+/// Marks the current thread as scanning until dropped.
+pub(crate) struct ScanGuard {
+    was_scanning: bool,
+}
+
+impl ScanGuard {
+    pub(crate) fn new() -> Self {
+        ScanGuard {
+            was_scanning: SCANNING.with(|it| it.replace(true)),
+        }
+    }
+}
+
+impl Drop for ScanGuard {
+    fn drop(&mut self) {
+        SCANNING.with(|it| it.set(self.was_scanning));
+    }
+}
+
+pub(crate) fn scan_currently_loaded_rust_functions(
+    mut callback: impl FnMut(&ScannedObject<'_>, String, Function) -> IterationControl,
+) -> ScanSummary {
+    scan_loaded_files(
+        |_, file_data, _| match scan_data(file_data, &mut callback) {
+            Ok(IterationControl::Break) => IterationControl::Break,
+            _ => IterationControl::Continue,
+        },
+    )
+}
+
+/// The path to read the main executable from, which the loader reports without
+/// a name on some platforms.
+///
+/// This is usually [`current_exe`]. On Linux, if the executable has been
+/// deleted since it was started (such as when `cargo test` rebuilds a test
+/// harness while it runs, which replaces the file in `target/debug/deps/`),
+/// this is `/proc/self/exe`, which can still be read.
+pub(crate) fn main_executable_path() -> io::Result<PathBuf> {
+    let path = current_exe()?;
+    if cfg!(any(target_os = "linux", target_os = "android")) && !file_access::exists(&path) {
+        let proc_exe = Path::new("/proc/self/exe");
+        if file_access::exists(proc_exe) {
+            return Ok(proc_exe.to_owned());
+        }
+    }
+    Ok(path)
+}
+
+/// Run `visit` on the path and contents of the library (or its debug file)
+/// of each currently loaded library which can be read.
+///
+/// If the current thread is already scanning, such as when `visit` (or a
+/// [`FileAccess`](crate::FileAccess), or a panic hook) starts another
+/// lookup, this returns immediately without searching anything. A nested scan
+/// would otherwise enumerate the loaded libraries from inside the loader's
+/// lock on some platforms, and could recurse without bound from hooks which
+/// are run by the scan itself, such as the instrumentation of a global
+/// allocator.
+pub(crate) fn scan_loaded_files(
+    visit: impl FnMut(&Path, &[u8], Option<PairingStatus>) -> IterationControl,
+) -> ScanSummary {
+    let mut summary = ScanSummary {
+        libraries_enumerated: 0,
+        files_searched: 0,
+        searched_files: Vec::new(),
+        current_exe_error: None,
+        reentrant: is_scanning(),
+        mismatched_debug_files: Vec::new(),
+        cancelled: false,
+    };
+    if summary.reentrant {
+        return summary;
+    }
+    let ((), cancelled) =
+        cancellation::track_interruption(|| visit_loaded_files(visit, &mut summary));
+    summary.cancelled = cancelled;
+    summary
+}
+
+/// The scan of [`scan_loaded_files`], once it is known not to be reentrant.
+fn visit_loaded_files(
+    mut visit: impl FnMut(&Path, &[u8], Option<PairingStatus>) -> IterationControl,
+    summary: &mut ScanSummary,
+) {
+    let _guard = ScanGuard::new();
+    let mut libraries = vec![];
+    // `each` might take locks - make this as short as possible to not block
+    // backtraces in other threads.
+    findshlibs::TargetSharedLibrary::each(|library| {
+        libraries.push((
+            library.name().to_owned(),
+            library.debug_name().map(ToOwned::to_owned),
+        ));
+    });
+    // Error handling:
+    // In these loops, if we get an error, we just try again with the next item
+    // We're not trying to be fancy here - again, this is a best effort search.
+    // If nothing works, the user should have a fallback, as explained in caveats.
+    summary.libraries_enumerated = libraries.len();
+    for (library_path, debug_path) in libraries {
+        if cancellation::is_cancelled() {
+            return;
+        }
+        let binary_path = if library_path.is_empty() {
+            main_executable_path()
+        } else {
+            Ok(library_path.into())
+        };
+        let (path, file_data, pairing) = match (debug_path, binary_path) {
+            (Some(debug_path), binary_path) => {
+                let debug_path = PathBuf::from(debug_path);
+                let Ok(debug_data) = read_loaded_file(&debug_path) else {
+                    continue;
+                };
+                match binary_path {
+                    // The binary is read as well to verify the companion,
+                    // unless allocations are being kept low
+                    Ok(binary_path) if !low_allocation::is_enabled() => {
+                        match choose_debug_file(binary_path, debug_path, debug_data, summary) {
+                            Some(it) => it,
+                            None => continue,
+                        }
+                    }
+                    _ => (debug_path, debug_data, Some(PairingStatus::Unverifiable)),
+                }
+            }
+            (None, Ok(path)) => match read_loaded_file(&path) {
+                Ok(data) => (path, data, None),
+                _ => continue,
+            },
+            (None, Err(e)) => {
+                summary.current_exe_error = Some(e);
+                continue;
+            }
+        };
+        if cancellation::file_checkpoint(&path) {
+            return;
+        }
+        summary.files_searched += 1;
+        summary.searched_files.push(path.clone());
+        if let IterationControl::Break = visit(&path, &file_data, pairing) {
+            return;
+        }
+    }
+}
+
+/// Read the loaded library (or debug file) at `path`, unless it is larger than
+/// the current [`with_low_allocation_scans`](crate::with_low_allocation_scans)
+/// allows.
+fn read_loaded_file(path: &Path) -> io::Result<Vec<u8>> {
+    if let Some(max_file_len) = low_allocation::max_file_len() {
+        if file_access::len(path)? > max_file_len {
+            low_allocation::record_skipped_file();
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                "file is larger than the low allocation limit",
+            ));
+        }
+    }
+    file_access::read(path)
+}
+
+/// The path and contents of the file to search for a loaded library with the
+/// debug companion at `debug_path`, and whether it is the companion and was
+/// built with the library, or `None` if neither should be searched, as
+/// configured by [`with_debug_pairing`](crate::with_debug_pairing).
+fn choose_debug_file(
+    binary_path: PathBuf,
+    debug_path: PathBuf,
+    debug_data: Vec<u8>,
+    summary: &mut ScanSummary,
+) -> Option<(PathBuf, Vec<u8>, Option<PairingStatus>)> {
+    let Ok(binary_data) = file_access::read(&binary_path) else {
+        return Some((debug_path, debug_data, Some(PairingStatus::Unverifiable)));
+    };
+    let Some(report) = pairing::verify(&binary_path, &binary_data, &debug_path, &debug_data) else {
+        return Some((debug_path, debug_data, Some(PairingStatus::Unverifiable)));
+    };
+    if !report.is_mismatched() {
+        return Some((debug_path, debug_data, Some(report.status)));
+    }
+    let binary_has_debug_info =
+        limits::catch_parser_panic(|| pairing::has_debug_info(&binary_data)).unwrap_or(false);
+    let use_debug_file = pairing::use_debug_file(&report, binary_has_debug_info);
+    summary.mismatched_debug_files.push(report);
+    if use_debug_file {
+        Some((debug_path, debug_data, Some(PairingStatus::Mismatched)))
+    } else {
+        binary_has_debug_info.then_some((binary_path, binary_data, None))
+    }
+}
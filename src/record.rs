@@ -0,0 +1,337 @@
+//! Crate-owned views of the functions found in debug info.
+
+use std::{borrow::Cow, cell::OnceCell, fmt, path::Path};
+
+use symbolic_common::Arch;
+use symbolic_debuginfo::{FileFormat, Function, ObjectKind};
+use symbolic_demangle::{Demangle, DemangleOptions};
+
+use crate::{
+    macro_site,
+    names::{self, NameMatch},
+    object::{CompilationUnit, CompilationUnits},
+    pdb_names, DeclarationSite, DeclarationSiteError, LineProvenance, MacroSite,
+};
+
+/// A function found in debug info, passed to callbacks such as in
+/// [`for_each_loaded_function`](crate::for_each_loaded_function).
+///
+/// This exposes the parts of the debug info which are useful for finding
+/// declaration sites, without making the types of the underlying debug info
+/// parser part of this crate's API.
+#[derive(Clone, Debug)]
+pub struct FunctionRecord<'a> {
+    name: Cow<'a, str>,
+    function: &'a Function<'a>,
+    object_path: Option<&'a Path>,
+    object: Option<ScannedObject<'a>>,
+}
+
+/// The object in a file which a function was found in.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ScannedObject<'a> {
+    /// The index of the object in its archive
+    pub index: usize,
+    /// The name of the object, for archives with named objects
+    pub name: Option<&'a str>,
+    /// The format of the object
+    pub format: FileFormat,
+    /// The CPU architecture of the object
+    pub arch: Arch,
+    /// The kind of the object
+    pub kind: ObjectKind,
+    /// The [`scan_priority`](crate::scan_priority) of the object
+    pub priority: u8,
+    /// The compilation units of the object
+    pub units: &'a LazyUnits<'a>,
+}
+
+/// The compilation units of an object, read when first needed.
+pub(crate) struct LazyUnits<'a> {
+    units: OnceCell<CompilationUnits>,
+    read: &'a dyn Fn() -> CompilationUnits,
+}
+
+impl<'a> LazyUnits<'a> {
+    pub fn new(read: &'a dyn Fn() -> CompilationUnits) -> Self {
+        LazyUnits {
+            units: OnceCell::new(),
+            read,
+        }
+    }
+
+    pub fn get(&self) -> &CompilationUnits {
+        self.units.get_or_init(self.read)
+    }
+}
+
+impl fmt::Debug for LazyUnits<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyUnits")
+            .field("units", &self.units.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> FunctionRecord<'a> {
+    pub(crate) fn new(name: impl Into<Cow<'a, str>>, function: &'a Function<'a>) -> Self {
+        FunctionRecord {
+            name: name.into(),
+            function,
+            object_path: None,
+            object: None,
+        }
+    }
+
+    pub(crate) fn with_object(mut self, object: &ScannedObject<'a>) -> Self {
+        self.object = Some(*object);
+        self
+    }
+
+    pub(crate) fn with_object_path(mut self, object_path: Option<&'a Path>) -> Self {
+        self.object_path = object_path;
+        self
+    }
+
+    /// The demangled name of the function, without its signature, in the
+    /// same format as [`type_name`](core::any::type_name).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the function as it appears in the debug info, which is
+    /// usually mangled.
+    pub fn raw_name(&self) -> &'a str {
+        self.function.name.as_str()
+    }
+
+    /// The address of the start of the function, relative to the start of the
+    /// file it was found in.
+    pub fn address(&self) -> u64 {
+        self.function.address
+    }
+
+    /// The size of the function's code, in bytes.
+    pub fn size(&self) -> u64 {
+        self.function.size
+    }
+
+    /// The address of the end of the function, which is one past its last
+    /// byte (its [`address`](Self::address) plus its [`size`](Self::size)).
+    pub fn end_address(&self) -> u64 {
+        self.function.address.saturating_add(self.function.size)
+    }
+
+    /// The path of the file the function was found in, when it isn't the
+    /// file which was searched. This is set for the files in `.dSYM` bundles,
+    /// the external debug files of WASM modules, and the files in
+    /// [`for_functions_in_directory`](crate::for_functions_in_directory).
+    pub fn object_path(&self) -> Option<&'a Path> {
+        self.object_path
+    }
+
+    /// The index of the object the function was found in, within its file
+    /// (see [`Archive::object_by_index`](crate::object::Archive::object_by_index)).
+    ///
+    /// This is only not `0` for files containing several objects, such as
+    /// Mach-O fat files and static libraries. These objects are searched in
+    /// order of their [`object_priority`](Self::object_priority), rather than
+    /// their index.
+    pub fn object_index(&self) -> usize {
+        self.object.map_or(0, |it| it.index)
+    }
+
+    /// The name of the object the function was found in, for files whose
+    /// objects are named, such as the member name in a static library.
+    pub fn object_name(&self) -> Option<&'a str> {
+        self.object.and_then(|it| it.name)
+    }
+
+    /// The [`scan_priority`](crate::scan_priority) of the object the function
+    /// was found in.
+    pub fn object_priority(&self) -> u8 {
+        self.object.map_or(0, |it| it.priority)
+    }
+
+    /// The CPU architecture of the object the function was found in, if
+    /// known.
+    ///
+    /// Objects of any architecture and byte order can be searched, such as a
+    /// big-endian PowerPC binary searched on an x86_64 machine.
+    pub fn object_arch(&self) -> Arch {
+        self.object.map_or(Arch::Unknown, |it| it.arch)
+    }
+
+    /// Whether the function was found in a relocatable ELF object (such as
+    /// a member of a static library). The addresses in the debug info of
+    /// these are usually stored in relocations, which aren't applied, so
+    /// their functions appear to overlap and are missing lines.
+    pub(crate) fn is_unrelocated(&self) -> bool {
+        self.object_format() == FileFormat::Elf
+            && self
+                .object
+                .is_some_and(|it| it.kind == ObjectKind::Relocatable)
+    }
+
+    /// The DWARF compilation unit the function was compiled in.
+    ///
+    /// With link-time optimization or `#[inline]` and generic functions, a
+    /// function can be compiled into the units of several crates, so this can
+    /// be used to tell the copies apart. The units are read from the object
+    /// the first time this is called for one of its functions. This is `None`
+    /// for objects without DWARF debug info.
+    pub fn compilation_unit(&self) -> Option<&'a CompilationUnit> {
+        let object = self.object?;
+        object.units.get().containing(self.address())
+    }
+
+    /// How the name of the function matches `query`, if at all.
+    ///
+    /// This is [`names::match_names`], falling back to
+    /// [`pdb_names::match_pdb_names`] for functions from PDBs, then to
+    /// [`names::match_future_body`], [`names::match_default_method`] and
+    /// [`match_unmangled`](Self::match_unmangled).
+    #[cfg(feature = "process")]
+    pub(crate) fn match_name(&self, query: &str) -> Option<NameMatch> {
+        names::match_names(self.name(), query)
+            .or_else(|| {
+                self.is_from_pdb()
+                    .then(|| pdb_names::match_pdb_names(self, query))?
+            })
+            .or_else(|| names::match_future_body(self.name(), query))
+            .or_else(|| names::match_default_method(self.name(), query))
+            .or_else(|| self.match_unmangled(query))
+    }
+
+    /// [`names::match_unmangled`], if the function was compiled in a unit of
+    /// the crate being searched for (or either crate is unknown, such as when
+    /// searching for a bare symbol).
+    pub(crate) fn match_unmangled(&self, query: &str) -> Option<NameMatch> {
+        let kind = names::match_unmangled(
+            self.name(),
+            query,
+            self.object_format() == FileFormat::MachO,
+        )?;
+        let unit_crate = self
+            .compilation_unit()
+            .and_then(CompilationUnit::crate_name);
+        match (unit_crate, names::crate_name(&names::canonical_name(query))) {
+            (Some(unit_crate), Some(query_crate)) if unit_crate != query_crate => None,
+            _ => Some(kind),
+        }
+    }
+
+    /// Whether the function was found in a PDB.
+    pub(crate) fn is_from_pdb(&self) -> bool {
+        self.object_format() == FileFormat::Pdb
+    }
+
+    /// The format of the object the function was found in, if known.
+    pub(crate) fn object_format(&self) -> FileFormat {
+        self.object.map_or(FileFormat::Unknown, |it| it.format)
+    }
+
+    /// The declaration site of the function, choosing between the macro and
+    /// its invocation for macro-generated functions as described in
+    /// [`MacroSite`].
+    ///
+    /// With [`MacroSite::Expansion`], this is the same as the [`TryFrom`] impl
+    /// of [`DeclarationSite`].
+    pub fn declaration_site(
+        &self,
+        macro_site: MacroSite,
+    ) -> Result<DeclarationSite, DeclarationSiteError> {
+        crate::macro_site::declaration_site(self, macro_site).map(|(site, _)| site)
+    }
+
+    /// The declaration site of the function as in
+    /// [`declaration_site`](Self::declaration_site), and where its line came
+    /// from.
+    ///
+    /// This is [`LineProvenance::Heuristic`] if the site was found by
+    /// [`MacroSite::Invocation`], and otherwise
+    /// [`LineProvenance::LineTableFirst`], as inlined functions are
+    /// [`FunctionRecord`]s of their own.
+    pub fn declaration_site_with_provenance(
+        &self,
+        macro_site: MacroSite,
+    ) -> Result<(DeclarationSite, LineProvenance), DeclarationSiteError> {
+        crate::macro_site::declaration_site(self, macro_site)
+    }
+
+    /// The source locations of the function's code, in the order they appear
+    /// in the debug info.
+    ///
+    /// This doesn't include the code of inlined functions, which is instead
+    /// found in [`inlinees`](Self::inlinees).
+    pub fn lines(&self) -> impl ExactSizeIterator<Item = LineRecord> + 'a {
+        self.function.lines.iter().map(|line| LineRecord {
+            address: line.address,
+            size: line.size,
+            file: line.file.path_str(),
+            line: line.line as u32,
+        })
+    }
+
+    /// The functions which were inlined into this function.
+    ///
+    /// Inlined functions which cannot be demangled use their raw name.
+    pub fn inlinees(&self) -> impl ExactSizeIterator<Item = FunctionRecord<'a>> + 'a {
+        let object_path = self.object_path;
+        let object = self.object;
+        let from_pdb = object.is_some_and(|it| it.format == FileFormat::Pdb);
+        self.function.inlinees.iter().map(move |function| {
+            let name = function
+                // As in the scan, `type_name` doesn't return the signature
+                .name
+                .demangle(DemangleOptions::name_only())
+                .map_or(Cow::Borrowed(function.name.as_str()), Cow::Owned);
+            let name = if from_pdb {
+                pdb_names::normalize_owned_msvc_name(name)
+            } else {
+                name
+            };
+            FunctionRecord {
+                object_path,
+                object,
+                ..FunctionRecord::new(name, function)
+            }
+        })
+    }
+}
+
+/// A source location of part of a function's code, from
+/// [`FunctionRecord::lines`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LineRecord {
+    /// The address of the start of this code, relative to the start of the
+    /// file it was found in
+    pub address: u64,
+    /// The size of this code in bytes, if known
+    pub size: Option<u64>,
+    pub file: String,
+    pub line: u32,
+}
+
+/// Get the site of the function, according to the debug info, as in
+/// [`DeclarationSite::from_lines`] with the primary file of its compilation
+/// unit.
+///
+/// # Errors
+///
+/// If the function's debug info has no source locations
+impl<'a> TryFrom<&FunctionRecord<'a>> for DeclarationSite {
+    type Error = DeclarationSiteError;
+
+    fn try_from(value: &FunctionRecord<'a>) -> Result<Self, Self::Error> {
+        let lines = &value.function.lines;
+        // The units are only read for functions whose lines are in several files
+        let spans_files = lines
+            .first()
+            .is_some_and(|first| lines.iter().any(|line| line.file != first.file));
+        let primary_file = spans_files
+            .then(|| macro_site::primary_file(value))
+            .flatten();
+        DeclarationSite::from_lines(lines, primary_file)
+    }
+}
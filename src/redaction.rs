@@ -0,0 +1,372 @@
+//! Redacting the paths in results, so that they can be sent elsewhere (such as
+//! in telemetry) without revealing the layout of the machine they were built
+//! on.
+//!
+//! Every path in results goes through this module on its way out, so the
+//! prefix of extended-length Windows paths is removed here too, whether or not
+//! redaction is set.
+
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    env,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+#[cfg(feature = "process")]
+use crate::PairingReport;
+use crate::{
+    strip_verbatim_prefix, AmbiguousMatch, DeclarationChoice, DeclarationSite, InlinedCall,
+};
+
+/// The redaction set by [`set_redaction`], if any.
+static REDACTION: RwLock<Option<Arc<Redaction>>> = RwLock::new(None);
+
+thread_local! {
+    /// Whether redaction is suspended on this thread by [`unredacted`]
+    static SUSPENDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A way of changing a path in a [`Redaction`].
+///
+/// Paths are split into components at either separator, so each rule applies
+/// in the same way to the paths of binaries built on any platform.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RedactionRule {
+    /// Replace the home directory at the start of a path with `~`, such as
+    /// `/home/me/my_crate/src/lib.rs` with `~/my_crate/src/lib.rs`.
+    ///
+    /// Home directories are the current user's (from `HOME` or `USERPROFILE`),
+    /// `/root`, and any directory of `/home`, `/Users` or `<drive>:\Users`, so
+    /// the home directories of the machine a binary was built on are
+    /// replaced, even if it isn't the machine running it.
+    HomeDirectory,
+    /// Remove the components of a path before the first component named
+    /// this, such as with `KeepFrom("src".into())`, replacing
+    /// `/home/me/my_crate/src/lib.rs` with `src/lib.rs`.
+    ///
+    /// Paths which don't have the component are unchanged, so this is
+    /// usually followed by another rule, such as
+    /// [`HashDirectories`](Self::HashDirectories), for the paths of the
+    /// standard library and the directories of compilation units.
+    KeepFrom(String),
+    /// Replace the directory of a path with a hash of it, keeping the file
+    /// name, such as `/home/me/my_crate/src/lib.rs` with
+    /// `#3e1f4b0a9c2d/lib.rs`.
+    ///
+    /// Files in the same directory have the same hash, so results can still
+    /// be grouped by directory. The hash isn't cryptographic: a directory
+    /// from a small set of guesses (such as a user name in a well-known
+    /// layout) can be recovered by hashing each guess, so use
+    /// [`HomeDirectory`](Self::HomeDirectory) or
+    /// [`KeepFrom`](Self::KeepFrom) first to remove those.
+    HashDirectories,
+}
+
+/// The rules which [`set_redaction`] applies to the paths in results, in
+/// order.
+///
+/// Redacting a path which was already redacted by the same rules leaves it
+/// unchanged, so results can be passed through several redacting functions
+/// (such as a redacted [`DeclarationSite`] given to
+/// [`miette::declaration_diagnostic`](crate::miette::declaration_diagnostic)).
+///
+/// ```rust
+/// use declaration_site::{Redaction, RedactionRule};
+///
+/// let home = Redaction::new(vec![RedactionRule::HomeDirectory]);
+/// assert_eq!(home.redact("/home/me/my_crate/src/lib.rs"), "~/my_crate/src/lib.rs");
+/// assert_eq!(home.redact(r"C:\Users\me\my_crate\src\lib.rs"), r"~\my_crate\src\lib.rs");
+/// assert_eq!(home.redact("src/lib.rs"), "src/lib.rs");
+///
+/// let redaction = Redaction::new(vec![
+///     RedactionRule::KeepFrom("src".into()),
+///     RedactionRule::HashDirectories,
+/// ]);
+/// let redacted = redaction.redact("/home/me/my_crate/src/lib.rs");
+/// assert!(redacted.starts_with('#') && redacted.ends_with("/lib.rs"));
+/// assert_eq!(redaction.redact("src/lib.rs"), redacted);
+/// assert_eq!(redaction.redact(&redacted), redacted);
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Redaction {
+    /// The rules applied to each path, in order
+    pub rules: Vec<RedactionRule>,
+}
+
+impl Redaction {
+    /// A redaction applying `rules` in order.
+    pub fn new(rules: Vec<RedactionRule>) -> Self {
+        Redaction { rules }
+    }
+
+    /// The path `path`, with each rule applied in order.
+    pub fn redact<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        let mut path = Cow::Borrowed(path);
+        for rule in &self.rules {
+            let redacted = match rule {
+                RedactionRule::HomeDirectory => replace_home(&path),
+                RedactionRule::KeepFrom(anchor) => keep_from(&path, anchor),
+                RedactionRule::HashDirectories => hash_directory(&path),
+            };
+            if let Some(redacted) = redacted {
+                path = Cow::Owned(redacted);
+            }
+        }
+        path
+    }
+}
+
+/// Redact the paths in every result which leaves this crate, for the rest of
+/// the process, or stop redacting them with `None`.
+///
+/// Redaction is off by default. When set, it applies (from any thread) to
+/// the files of [`DeclarationSite`]s returned by every lookup, the other
+/// paths of [`DeclarationChoice`]s (such as their
+/// [`compilation_unit`](DeclarationChoice::compilation_unit)), the files
+/// paired in [`DeclarationError::MismatchedDebugFiles`](crate::DeclarationError::MismatchedDebugFiles),
+/// [`DeclarationIndex`](crate::DeclarationIndex)es (and so their
+/// [cache files](crate::DeclarationIndex::from_file_cached) and
+/// [`schema`](crate::schema) documents),
+/// [`export_function_map`](crate::export_function_map),
+/// [`file_map`](crate::file_map), [`module_tree`](crate::module_tree),
+/// [`duplication_report`](crate::duplication_report), the `at` lines of
+/// [`AnnotatedBacktrace`](crate::AnnotatedBacktrace)s, and diagnostics. The
+/// raw debug info given to the callbacks of scans (such as
+/// [`FunctionRecord`](crate::FunctionRecord)) isn't redacted.
+///
+/// The paths are redacted last, after any remapping by the compiler (with
+/// `--remap-path-prefix`) when the binary was built, after the `\\?\`
+/// prefix of extended-length Windows paths is removed (see
+/// [`strip_verbatim_prefix`]), and after the paths
+/// were used to choose between functions (see
+/// [`PathPreference`](crate::PathPreference)), so redaction doesn't change
+/// which function is found. Redacted paths may not exist, so source snippets
+/// (such as from [`miette`](crate::miette)) are only found for relative paths
+/// which still exist from the working directory, or from
+/// [`EmbeddedSources`](crate::EmbeddedSources).
+///
+/// This should be set at startup, before the first lookup, as some results
+/// (such as those of [`cached_declaration`](crate::cached_declaration)) are
+/// kept for the rest of the process.
+///
+/// ```rust,no_run
+/// use declaration_site::{declaration_of, set_redaction, Redaction, RedactionRule};
+///
+/// set_redaction(Some(Redaction::new(vec![
+///     RedactionRule::HomeDirectory,
+///     RedactionRule::KeepFrom("src".into()),
+/// ])));
+///
+/// fn update_player() {}
+///
+/// if let Some(site) = declaration_of(&update_player) {
+///     // Such as `src/player.rs:9`
+///     println!("{site}");
+/// }
+/// ```
+pub fn set_redaction(redaction: Option<Redaction>) {
+    *REDACTION.write().unwrap_or_else(|e| e.into_inner()) = redaction.map(Arc::new);
+}
+
+/// The redaction set by [`set_redaction`], if any, and it isn't suspended on
+/// this thread.
+pub(crate) fn current() -> Option<Arc<Redaction>> {
+    if SUSPENDED.get() {
+        return None;
+    }
+    REDACTION.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Run `f` without redaction on this thread, for lookups whose paths are
+/// compared with others before the result is redacted.
+#[cfg(feature = "process")]
+pub(crate) fn unredacted<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SUSPENDED.set(self.0);
+        }
+    }
+    let _restore = Restore(SUSPENDED.replace(true));
+    f()
+}
+
+/// The path `path` as it is reported: without the prefix of extended-length
+/// Windows paths (see [`strip_verbatim_prefix`]), and then redacted with
+/// `redaction`, if any.
+fn reported<'a>(redaction: Option<&Redaction>, path: &'a str) -> Cow<'a, str> {
+    let path = strip_verbatim_prefix(path);
+    let Some(redaction) = redaction else {
+        return path;
+    };
+    match redaction.redact(&path) {
+        Cow::Owned(redacted) => Cow::Owned(redacted),
+        Cow::Borrowed(_) => path,
+    }
+}
+
+/// Replace `path` with how it is [`reported`].
+fn report_string(redaction: Option<&Redaction>, path: &mut String) {
+    match reported(redaction, path) {
+        Cow::Borrowed(reported) if reported.len() == path.len() => {}
+        reported => *path = reported.into_owned(),
+    }
+}
+
+/// The path `path` as it is reported, redacted if redaction is set.
+#[cfg(any(feature = "process", feature = "serde", feature = "miette"))]
+pub(crate) fn path(path: &str) -> Cow<'_, str> {
+    reported(current().as_deref(), path)
+}
+
+/// Replace the file of `site` with how it is reported, redacted if
+/// redaction is set.
+pub(crate) fn site(mut site: DeclarationSite) -> DeclarationSite {
+    report_string(current().as_deref(), &mut site.file);
+    site
+}
+
+/// Replace every path of `choice` with how it is reported, redacted if
+/// redaction is set.
+pub(crate) fn choice(mut choice: DeclarationChoice) -> DeclarationChoice {
+    let redaction = current();
+    let redaction = redaction.as_deref();
+    let sites = choice
+        .site
+        .iter_mut()
+        .chain(&mut choice.outscored)
+        .chain(
+            choice
+                .inlined_into
+                .iter_mut()
+                .filter_map(|InlinedCall { call_site, .. }| call_site.as_mut()),
+        )
+        .chain(
+            choice
+                .ambiguous_with
+                .iter_mut()
+                .map(|AmbiguousMatch { site, .. }| site),
+        );
+    for site in sites {
+        report_string(redaction, &mut site.file);
+    }
+    for file in choice
+        .additional_files
+        .iter_mut()
+        .chain(&mut choice.package_hint)
+    {
+        report_string(redaction, file);
+    }
+    if let Some(unit) = &mut choice.compilation_unit {
+        unit.redact_paths(|path| reported(redaction, path).into_owned());
+    }
+    choice
+}
+
+/// The path `path` as it is reported, redacted if redaction is set.
+pub(crate) fn path_buf(path: &Path) -> PathBuf {
+    let redaction = current();
+    match (path.to_str(), redaction) {
+        (Some(text), redaction) => PathBuf::from(reported(redaction.as_deref(), text).into_owned()),
+        (None, Some(redaction)) => {
+            PathBuf::from(redaction.redact(&path.to_string_lossy()).into_owned())
+        }
+        (None, None) => path.to_owned(),
+    }
+}
+
+/// Replace the paths of the files paired in `report` with how they are
+/// reported, redacted if redaction is set.
+#[cfg(feature = "process")]
+pub(crate) fn pairing_report(mut report: PairingReport) -> PairingReport {
+    report.binary_path = path_buf(&report.binary_path);
+    report.debug_path = path_buf(&report.debug_path);
+    report
+}
+
+/// The byte ranges of the non-empty components of `path`, split at either
+/// separator.
+fn components(path: &str) -> Vec<(usize, usize)> {
+    let mut components = Vec::new();
+    let mut start = 0;
+    for (index, c) in path.char_indices().chain([(path.len(), '/')]) {
+        if c == '/' || c == '\\' {
+            if index > start {
+                components.push((start, index));
+            }
+            start = index + c.len_utf8();
+        }
+    }
+    components
+}
+
+/// `path` with its home directory replaced by `~`, if it has one.
+fn replace_home(path: &str) -> Option<String> {
+    let components = components(path);
+    let component = |index: usize| components.get(index).map(|&(start, end)| &path[start..end]);
+    let is_absolute = path.starts_with(['/', '\\']);
+    let current_home = ["HOME", "USERPROFILE"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .find_map(|home| {
+            let home = home.into_string().ok()?;
+            let home_components = self::components(&home);
+            let is_prefix = !home_components.is_empty()
+                && home_components.len() <= components.len()
+                && home_components
+                    .iter()
+                    .enumerate()
+                    .all(|(index, &(start, end))| component(index) == Some(&home[start..end]))
+                && home.starts_with(['/', '\\']) == is_absolute;
+            is_prefix.then_some(home_components.len())
+        });
+    let is_drive = |component: &str| component.len() == 2 && component.ends_with(':');
+    let home_len = current_home.or_else(|| match (component(0)?, component(1), component(2)) {
+        ("home" | "Users", Some(_), _) if is_absolute => Some(2),
+        ("root", _, _) if is_absolute => Some(1),
+        (drive, Some(users), Some(_)) if is_drive(drive) && users.eq_ignore_ascii_case("users") => {
+            Some(3)
+        }
+        _ => None,
+    })?;
+    let (_, end) = components[home_len - 1];
+    Some(format!("~{}", &path[end..]))
+}
+
+/// `path` from the first component named `anchor`, if it isn't already the
+/// first component.
+fn keep_from(path: &str, anchor: &str) -> Option<String> {
+    let (start, _) = components(path)
+        .into_iter()
+        .find(|&(start, end)| &path[start..end] == anchor)?;
+    (start > 0).then(|| path[start..].to_owned())
+}
+
+/// The length of the hex digits of a hashed directory.
+const HASH_DIGITS: usize = 12;
+
+/// `path` with its directory replaced by a hash, unless it has no directory
+/// or it was already hashed.
+fn hash_directory(path: &str) -> Option<String> {
+    let separator = path.rfind(['/', '\\'])?;
+    let (directory, file) = (&path[..separator], &path[separator + 1..]);
+    let is_hashed = directory.len() == HASH_DIGITS + 1
+        && directory.starts_with('#')
+        && directory[1..].bytes().all(|byte| byte.is_ascii_hexdigit());
+    if is_hashed {
+        return None;
+    }
+    // FNV-1a, which is stable across platforms and versions of Rust
+    let hash = directory
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    Some(format!(
+        "#{:0width$x}/{file}",
+        hash >> (64 - 4 * HASH_DIGITS),
+        width = HASH_DIGITS
+    ))
+}
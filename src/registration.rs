@@ -0,0 +1,154 @@
+//! Finding the declaration sites of functions registered with a framework,
+//! such as the systems of an ECS or the handlers of a plugin registry.
+
+use std::{fmt, panic::Location, path::Path};
+
+use crate::{declaration_by_name, declaration_by_name_all, names, redaction, DeclarationSite};
+
+/// Attempt to get the declaration site of a function registered with a
+/// framework, from inside the method it was registered with.
+///
+/// This is like [`declaration_of`](crate::declaration_of), except that:
+/// - Closures created by generic functions to wrap their only function
+///   argument are resolved to that argument, up to 8 times. For example,
+///   `framework::register<app::spawn>::{{closure}}` is resolved to
+///   `app::spawn`.
+/// - The [caller](Location::caller) of this function is taken to be where the
+///   function was registered. If the function is a closure, so that every
+///   closure in its parent function has the same type name, the closure
+///   declared in the caller's file on the nearest line to the caller (or the
+///   earlier of two equally near) is returned. Closures should therefore be
+///   declared in, or just before, the call which registers them.
+///
+/// Frameworks usually register functions through generic methods, such as
+/// `fn register<F: Fn()>(&mut self, f: F)`. Call this from such a method
+/// marked `#[track_caller]`, so that the caller is the registration in the
+/// user's code. Otherwise, closures are resolved as in
+/// [`declaration_by_name`]. [`RegistrationInfo::of`] also records where the
+/// function was registered:
+///
+/// ```rust
+/// use declaration_site::RegistrationInfo;
+///
+/// #[derive(Default)]
+/// struct Registry {
+///     handlers: Vec<(Box<dyn Fn()>, RegistrationInfo)>,
+/// }
+///
+/// impl Registry {
+///     #[track_caller]
+///     fn register<F: Fn() + 'static>(&mut self, handler: F) {
+///         let info = RegistrationInfo::of(&handler);
+///         self.handlers.push((Box::new(handler), info));
+///     }
+/// }
+///
+/// fn on_start() {}
+///
+/// let mut registry = Registry::default();
+/// registry.register(on_start);
+/// registry.register(|| println!("started"));
+/// for (_, info) in &registry.handlers {
+///     println!("{info}");
+/// }
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+#[track_caller]
+pub fn declaration_of_registered<F>(_: &F) -> Option<DeclarationSite> {
+    let name = names::registered_function(core::any::type_name::<F>());
+    registered_declaration(name, Location::caller())
+}
+
+/// A function registered with a framework, as captured by
+/// [`RegistrationInfo::of`] or [`register_with_site!`](crate::register_with_site).
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RegistrationInfo {
+    /// The type name of the function, after resolving the wrappers described
+    /// in [`declaration_of_registered`]
+    pub name: &'static str,
+    /// Where the function was registered
+    pub registered_at: &'static Location<'static>,
+    /// The declaration site of the function, if it was found
+    pub declaration: Option<DeclarationSite>,
+}
+
+impl RegistrationInfo {
+    /// Capture the registration of `function` at the [caller](Location::caller),
+    /// and find its declaration site as in [`declaration_of_registered`].
+    #[track_caller]
+    pub fn of<F>(_: &F) -> Self {
+        let name = names::registered_function(core::any::type_name::<F>());
+        let registered_at = Location::caller();
+        Self {
+            name,
+            registered_at,
+            declaration: registered_declaration(name, registered_at),
+        }
+    }
+}
+
+impl fmt::Display for RegistrationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` registered at {}", self.name, self.registered_at)?;
+        match &self.declaration {
+            Some(site) => write!(f, ", declared at {site}"),
+            None => write!(f, ", declaration not found"),
+        }
+    }
+}
+
+/// The declaration site of the function named `name`, registered at
+/// `registered_at`.
+fn registered_declaration(name: &str, registered_at: &Location<'_>) -> Option<DeclarationSite> {
+    if !names::is_ambiguous_closure(name) {
+        return declaration_by_name(name);
+    }
+    // The files are compared before they are redacted
+    let sites = redaction::unredacted(|| declaration_by_name_all(name));
+    let nearest = sites
+        .iter()
+        .filter(|site| Path::new(&site.file).ends_with(registered_at.file()))
+        // The earlier of two equally near sites is first, as they are sorted
+        .min_by_key(|site| site.line.abs_diff(registered_at.line()));
+    nearest.or(sites.first()).cloned().map(redaction::site)
+}
+
+/// Register `function` with `registry` by calling `registry.register(function)`,
+/// evaluating to the [`RegistrationInfo`] of `function`, registered where this
+/// is invoked.
+///
+/// Unlike calling [`RegistrationInfo::of`] inside the registry, this doesn't
+/// need the registry's cooperation. The value returned by `register` is
+/// discarded.
+///
+/// ```rust
+/// use declaration_site::register_with_site;
+///
+/// #[derive(Default)]
+/// struct Registry {
+///     handlers: Vec<fn()>,
+/// }
+///
+/// impl Registry {
+///     fn register(&mut self, handler: fn()) {
+///         self.handlers.push(handler);
+///     }
+/// }
+///
+/// fn on_start() {}
+///
+/// let mut registry = Registry::default();
+/// let info = register_with_site!(registry, on_start);
+/// assert_eq!(info.registered_at.line(), line!() - 1);
+/// println!("{info}");
+/// ```
+#[macro_export]
+macro_rules! register_with_site {
+    ($registry:expr, $function:expr $(,)?) => {{
+        let function = $function;
+        let info = $crate::RegistrationInfo::of(&function);
+        $registry.register(function);
+        info
+    }};
+}
@@ -0,0 +1,405 @@
+//! The formats of the output this crate writes for other programs to read,
+//! enabled by the `serde` feature.
+//!
+//! These types are kept separate from the types they are built from, so that
+//! changing those (such as adding a field to [`DeclarationSite`]) doesn't
+//! change the output. Every artifact includes [`SCHEMA_VERSION`]:
+//!
+//! - Each row written by [`export_function_map`](crate::export_function_map)
+//!   is a [`FunctionMapRow`], with the version in its `schema_version` field
+//!   (or column, for CSV).
+//! - Each result printed by `declaration-site --json` is a [`LookupResult`].
+//! - An index can be persisted as JSON as an [`IndexDocument`].
+//! - Refreshing an [`IndexSession`](crate::IndexSession) is summarised as a
+//!   [`RefreshResult`].
+//! - A [`duplication_report`](crate::duplication_report) can be dumped as a
+//!   [`DuplicationDocument`].
+//! - A [`compare_function_maps`](crate::compare_function_maps) of two builds
+//!   can be written for a CI gate as a [`ComparisonDocument`].
+//!
+//! The version is incremented when a field is removed or renamed, or its
+//! meaning changes. Fields can be added without incrementing it, so readers
+//! should ignore fields they don't know about (which `serde` does unless
+//! `deny_unknown_fields` is used).
+//!
+//! The types elsewhere in the crate which implement `Serialize` (such as
+//! [`DeclarationSite`] and [`DuplicationReport`]) serialize their current
+//! fields, so aren't covered by the version. Neither is the binary format of
+//! the [index cache](crate::DeclarationIndex::from_file_cached), which has a
+//! version of its own.
+//!
+//! ```rust
+//! use declaration_site::schema::{LookupResult, SCHEMA_VERSION};
+//!
+//! let line = r#"{"schema_version":1,"name":"my_crate::main","file":"src/main.rs","line":3}"#;
+//! let result: LookupResult = serde_json::from_str(line).unwrap();
+//! assert_eq!(result.schema_version, SCHEMA_VERSION);
+//! assert_eq!(result.line, Some(3));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    redaction, DeclarationIndex, DeclarationSite, DuplicatedFunction, DuplicationReport, IndexDiff,
+    MapComparison, ObjectDuplication, SiteDifference,
+};
+
+/// The version of the formats in this module.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A function found by [`export_function_map`](crate::export_function_map).
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct FunctionMapRow {
+    /// [`SCHEMA_VERSION`], when written
+    pub schema_version: u32,
+    /// The demangled name of the function
+    pub name: String,
+    /// The file of the declaration site of the function, if it has one
+    pub file: Option<String>,
+    /// The line of the declaration site of the function, if it has one
+    pub line: Option<u32>,
+    /// The address of the function in the file, which is not necessarily its
+    /// address in memory
+    pub address: u64,
+    /// The size of the code of the function, in bytes
+    pub size: u64,
+}
+
+impl FromIterator<FunctionMapRow> for DeclarationIndex {
+    /// Rebuild an index from the rows written by
+    /// [`export_function_map`](crate::export_function_map), such as to
+    /// [compare](crate::compare_function_maps) two builds. Rows without a
+    /// declaration site are skipped, and if several rows have the same name,
+    /// the first with a declaration site is used.
+    fn from_iter<T: IntoIterator<Item = FunctionMapRow>>(rows: T) -> Self {
+        rows.into_iter()
+            .filter_map(|row| {
+                let (file, line) = row.file.zip(row.line)?;
+                Some((row.name, DeclarationSite { file, line }))
+            })
+            .collect()
+    }
+}
+
+/// The declaration site of a function looked up by the `declaration-site`
+/// command line tool.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct LookupResult {
+    /// [`SCHEMA_VERSION`], when written
+    pub schema_version: u32,
+    /// The name which was looked up, or which matched a prefix or regex
+    pub name: String,
+    /// The file of the declaration site, if the function was found
+    pub file: Option<String>,
+    /// The line of the declaration site, if the function was found
+    pub line: Option<u32>,
+}
+
+impl LookupResult {
+    /// The result of looking up `name`, which was found at `site`.
+    pub fn new(name: impl Into<String>, site: Option<&DeclarationSite>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            name: name.into(),
+            file: site.map(|it| redaction::path(&it.file).into_owned()),
+            line: site.map(|it| it.line),
+        }
+    }
+}
+
+/// The outcome of [refreshing](crate::IndexSession::refresh) or
+/// [reloading](crate::IndexSession::reload) an [`IndexSession`](crate::IndexSession),
+/// as written by the `daemon` example.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct RefreshResult {
+    /// [`SCHEMA_VERSION`], when written
+    pub schema_version: u32,
+    /// Whether the file was indexed again
+    pub changed: bool,
+    /// The number of functions in the index afterwards
+    pub functions: usize,
+    /// The number of functions added to the index
+    pub added: usize,
+    /// The number of functions removed from the index
+    pub removed: usize,
+    /// The number of functions whose declaration sites changed
+    pub moved: usize,
+}
+
+impl RefreshResult {
+    /// The result of a refresh which left `index`, having made the changes in
+    /// `diff` (or none, if the file wasn't indexed again).
+    pub fn new(index: &DeclarationIndex, diff: Option<&IndexDiff>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            changed: diff.is_some(),
+            functions: index.len(),
+            added: diff.map_or(0, |it| it.added.len()),
+            removed: diff.map_or(0, |it| it.removed.len()),
+            moved: diff.map_or(0, |it| it.moved.len()),
+        }
+    }
+}
+
+/// A [`DeclarationIndex`], to be persisted as JSON.
+///
+/// ```rust
+/// use declaration_site::{schema::IndexDocument, DeclarationIndex};
+///
+/// # let index = DeclarationIndex::default();
+/// let json = serde_json::to_string(&IndexDocument::from(&index)).unwrap();
+/// let document: IndexDocument = serde_json::from_str(&json).unwrap();
+/// assert_eq!(DeclarationIndex::from(document), index);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct IndexDocument {
+    /// [`SCHEMA_VERSION`], when written
+    pub schema_version: u32,
+    /// Every function in the index, sorted by name
+    pub functions: Vec<IndexEntry>,
+}
+
+/// A function in an [`IndexDocument`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// The canonical name of the function
+    pub name: String,
+    /// The file of the declaration site of the function
+    pub file: String,
+    /// The line of the declaration site of the function
+    pub line: u32,
+}
+
+impl From<&DeclarationIndex> for IndexDocument {
+    fn from(index: &DeclarationIndex) -> Self {
+        let functions = index
+            .iter()
+            .map(|(name, site)| IndexEntry {
+                name: name.to_owned(),
+                file: site.file.clone(),
+                line: site.line,
+            })
+            .collect();
+        Self {
+            schema_version: SCHEMA_VERSION,
+            functions,
+        }
+    }
+}
+
+impl From<IndexDocument> for DeclarationIndex {
+    /// Rebuild an index, ignoring the version of `document`. If several
+    /// entries have the same name, the first is used.
+    fn from(document: IndexDocument) -> Self {
+        let mut index = DeclarationIndex::default();
+        for entry in document.functions {
+            let site = DeclarationSite {
+                file: entry.file,
+                line: entry.line,
+            };
+            index.insert(entry.name, site);
+        }
+        index
+    }
+}
+
+/// A [`DuplicationReport`], to be dumped as JSON.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DuplicationDocument {
+    /// [`SCHEMA_VERSION`], when written
+    pub schema_version: u32,
+    /// Each object which contained functions, sorted by path
+    pub objects: Vec<ObjectEntry>,
+}
+
+/// The functions in an object, in a [`DuplicationDocument`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ObjectEntry {
+    /// The path of the file containing the object, if several files were
+    /// searched
+    pub path: Option<String>,
+    /// The name of the object within its file, if it is named
+    pub object_name: Option<String>,
+    /// The index of the object within its file
+    pub object_index: usize,
+    /// The number of functions in the object
+    pub functions: usize,
+    /// The number of distinct names of the functions in the object
+    pub distinct_names: usize,
+    /// The functions with the most copies, with the most first
+    pub duplicated: Vec<DuplicatedEntry>,
+}
+
+/// The copies of a function, in an [`ObjectEntry`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DuplicatedEntry {
+    /// The path of the function with its generic arguments erased
+    pub base_path: String,
+    /// The number of copies of the function
+    pub copies: usize,
+    /// The total size of the code of the copies, in bytes
+    pub total_size: u64,
+    /// The file of the declaration site of the first copy found with one
+    pub file: Option<String>,
+    /// The line of the declaration site of the first copy found with one
+    pub line: Option<u32>,
+}
+
+impl From<&DuplicationReport> for DuplicationDocument {
+    fn from(report: &DuplicationReport) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            objects: report.objects.iter().map(ObjectEntry::from).collect(),
+        }
+    }
+}
+
+impl From<&ObjectDuplication> for ObjectEntry {
+    fn from(object: &ObjectDuplication) -> Self {
+        Self {
+            path: object.path.clone(),
+            object_name: object.object_name.clone(),
+            object_index: object.object_index,
+            functions: object.functions,
+            distinct_names: object.distinct_names,
+            duplicated: object
+                .duplicated
+                .iter()
+                .map(DuplicatedEntry::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&DuplicatedFunction> for DuplicatedEntry {
+    fn from(function: &DuplicatedFunction) -> Self {
+        Self {
+            base_path: function.base_path.clone(),
+            copies: function.copies,
+            total_size: function.total_size,
+            file: function.site.as_ref().map(|it| it.file.clone()),
+            line: function.site.as_ref().map(|it| it.line),
+        }
+    }
+}
+
+impl From<DuplicationDocument> for DuplicationReport {
+    fn from(document: DuplicationDocument) -> Self {
+        let objects = document
+            .objects
+            .into_iter()
+            .map(|object| ObjectDuplication {
+                path: object.path,
+                object_name: object.object_name,
+                object_index: object.object_index,
+                functions: object.functions,
+                distinct_names: object.distinct_names,
+                duplicated: object
+                    .duplicated
+                    .into_iter()
+                    .map(|function| DuplicatedFunction {
+                        base_path: function.base_path,
+                        copies: function.copies,
+                        total_size: function.total_size,
+                        site: function
+                            .file
+                            .zip(function.line)
+                            .map(|(file, line)| DeclarationSite { file, line }),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self { objects }
+    }
+}
+
+/// A [`MapComparison`], to be written for a CI gate as JSON.
+///
+/// ```rust
+/// use declaration_site::{
+///     compare_function_maps, schema::ComparisonDocument, ComparisonOptions, DeclarationIndex,
+/// };
+///
+/// # let (a, b) = (DeclarationIndex::default(), DeclarationIndex::default());
+/// let comparison = compare_function_maps(&a, &b, &ComparisonOptions::default());
+/// let document = ComparisonDocument::from(&comparison);
+/// println!("{}", serde_json::to_string_pretty(&document).unwrap());
+/// if !document.consistent {
+///     std::process::exit(1);
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct ComparisonDocument {
+    /// [`SCHEMA_VERSION`], when written
+    pub schema_version: u32,
+    /// Whether the maps were [consistent](MapComparison::is_consistent)
+    pub consistent: bool,
+    /// The number of functions with the same declaration site in both maps
+    pub identical: usize,
+    /// The functions whose files only differ in their prefix
+    pub path_prefix: Vec<DifferenceEntry>,
+    /// The functions which moved by a few lines in the same file
+    pub line_drift: Vec<DifferenceEntry>,
+    /// The functions whose declaration sites don't match
+    pub mismatched: Vec<DifferenceEntry>,
+    /// The functions which are only in the first map
+    pub only_in_a: Vec<IndexEntry>,
+    /// The functions which are only in the second map
+    pub only_in_b: Vec<IndexEntry>,
+}
+
+/// A function whose declaration site differs between two function maps, in
+/// a [`ComparisonDocument`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct DifferenceEntry {
+    /// The canonical name of the function
+    pub name: String,
+    /// The file of the declaration site in the first map
+    pub a_file: String,
+    /// The line of the declaration site in the first map
+    pub a_line: u32,
+    /// The file of the declaration site in the second map
+    pub b_file: String,
+    /// The line of the declaration site in the second map
+    pub b_line: u32,
+}
+
+impl From<&MapComparison> for ComparisonDocument {
+    fn from(comparison: &MapComparison) -> Self {
+        let differences = |differences: &[SiteDifference]| {
+            differences.iter().map(DifferenceEntry::from).collect()
+        };
+        let entries = |functions: &[(String, DeclarationSite)]| {
+            functions
+                .iter()
+                .map(|(name, site)| IndexEntry {
+                    name: name.clone(),
+                    file: redaction::path(&site.file).into_owned(),
+                    line: site.line,
+                })
+                .collect()
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            consistent: comparison.is_consistent(),
+            identical: comparison.identical,
+            path_prefix: differences(&comparison.path_prefix),
+            line_drift: differences(&comparison.line_drift),
+            mismatched: differences(&comparison.mismatched),
+            only_in_a: entries(&comparison.only_in_a),
+            only_in_b: entries(&comparison.only_in_b),
+        }
+    }
+}
+
+impl From<&SiteDifference> for DifferenceEntry {
+    fn from(difference: &SiteDifference) -> Self {
+        Self {
+            name: difference.name.clone(),
+            a_file: redaction::path(&difference.a.file).into_owned(),
+            a_line: difference.a.line,
+            b_file: redaction::path(&difference.b.file).into_owned(),
+            b_line: difference.b.line,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Looking up currently loaded functions by name, with the options for
+//! choosing between the functions which match.
+
+use std::{fmt, sync::Arc};
+
+use crate::{
+    file_access,
+    names::{self, FunctionLikelihood},
+    process, DeclarationChoice, DeclarationError, DeclarationSite, FileAccess, MacroSite,
+    MatchRelaxation, PathPreference,
+};
+
+/// A lookup of a currently loaded function by its (unmangled) name, with
+/// options for choosing between the functions which match it.
+///
+/// Without any options, this finds the same function as
+/// [`declaration_by_name`](crate::declaration_by_name), which is
+/// `DeclarationSearch::new(name).find()`. Each option changes one step of
+/// the choice described in [`DeclarationChoice`], so they can be combined,
+/// such as to only consider one package's functions while relaxing matching:
+///
+/// ```rust
+/// use declaration_site::{DeclarationSearch, MatchRelaxation, PathPreference};
+///
+/// #[inline(never)]
+/// fn my_function() {}
+///
+/// let name = std::any::type_name_of_val(&my_function);
+/// let search = DeclarationSearch::new(name)
+///     .path_preference(PathPreference::FirstFound)
+///     .relaxed(MatchRelaxation::Conservative);
+/// if let Some(site) = search.find() {
+///     println!("`{name}` is declared at {site}");
+/// }
+/// // Every function which matched, and why each was or wasn't chosen
+/// for choice in search.explain_all() {
+///     println!("{choice}");
+/// }
+/// my_function();
+/// ```
+///
+/// Names which cannot possibly be the name of a function (such as `u32` or
+/// `&str`) aren't searched for.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+#[derive(Clone)]
+pub struct DeclarationSearch<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) macro_site: MacroSite,
+    pub(crate) version: Option<&'a str>,
+    pub(crate) package: Option<&'a str>,
+    pub(crate) relaxation: MatchRelaxation,
+    pub(crate) path_preference: PathPreference,
+    file_access: Option<Arc<dyn FileAccess>>,
+}
+
+impl<'a> DeclarationSearch<'a> {
+    /// A search for the function with the given (unmangled) name, as
+    /// described in [`declaration_by_name`](crate::declaration_by_name).
+    pub fn new(name: &'a str) -> Self {
+        DeclarationSearch {
+            name,
+            macro_site: MacroSite::Expansion,
+            version: None,
+            package: None,
+            relaxation: MatchRelaxation::None,
+            path_preference: PathPreference::default(),
+            file_access: None,
+        }
+    }
+
+    /// Report the site of macro-generated functions as chosen by
+    /// `macro_site`, rather than [`MacroSite::Expansion`].
+    ///
+    /// See [`MacroSite`] for the limitations of finding the invocation of a
+    /// macro.
+    pub fn macro_site(mut self, macro_site: MacroSite) -> Self {
+        self.macro_site = macro_site;
+        self
+    }
+
+    /// Prefer the function from `version` of its crate.
+    ///
+    /// Several versions of a crate can be linked into the same program
+    /// through different dependency paths, in which case the functions of each
+    /// version have the same name. Without a preferred version, the function
+    /// from the version which is found first is chosen. The version of a
+    /// function is found from the path of its declaration site, so only
+    /// registry dependencies have versions (see
+    /// [`attribute_dependency_path`](crate::attribute_dependency_path)). Use
+    /// [`explain`](Self::explain) to find whether several versions matched,
+    /// or `declaration_by_name_with_metadata` (with the `cargo-metadata`
+    /// feature) to prefer the version used by the current package.
+    ///
+    /// If no function from `version` matched, the search finds the same
+    /// function as without a version.
+    pub fn version(mut self, version: &'a str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Only consider functions from the package `package`.
+    ///
+    /// Unrelated packages can have crates with the same name, such as a
+    /// `utils` crate in each of two workspaces, whose functions have the same
+    /// names when both are loaded into one process (such as through different
+    /// dynamic libraries). A function is in `package` if its
+    /// [`DeclarationChoice::package_hint`] matches it as a path (see
+    /// [`PathComparison::matches`](crate::PathComparison::matches)), so
+    /// `package` can be the name of the package's directory (such as
+    /// `game_utils`), a longer path to it, or the name of a dependency in
+    /// cargo's download directories. Functions whose package isn't known are
+    /// skipped. Use [`explain_all`](Self::explain_all) without a package hint
+    /// to find the packages of every function which matched.
+    pub fn package_hint(mut self, package: &'a str) -> Self {
+        self.package = Some(package);
+        self
+    }
+
+    /// Choose between functions found in different files as chosen by
+    /// `preference`, rather than [`PathPreference::Workspace`].
+    ///
+    /// The same function can be found in several libraries with different
+    /// declaration sites, such as in the workspace and in a copy vendored
+    /// under `target/`. Use [`PathPreference::FirstFound`] to choose the first
+    /// function found, which avoids searching every loaded library for
+    /// functions declared outside the workspace. Use
+    /// [`explain`](Self::explain) to find which functions lost to the
+    /// preference (see [`DeclarationChoice::outscored`]).
+    pub fn path_preference(mut self, preference: PathPreference) -> Self {
+        self.path_preference = preference;
+        self
+    }
+
+    /// Relax matching as chosen by `relaxation` if no function matched.
+    ///
+    /// This finds functions whose names are spelled differently by the rustc
+    /// or demangler which built the program than by the one which produced
+    /// the name, such as with different generic arguments. A function which
+    /// only matched with relaxed matching is only found if it is the only
+    /// function (other than copies of it) which matched at the least relaxed
+    /// level which matched any. Use [`explain`](Self::explain) to find
+    /// whether the result is approximate (see
+    /// [`DeclarationChoice::relaxation`]), and
+    /// [`explain_all`](Self::explain_all) to get every function which
+    /// matched, including when the relaxed match was ambiguous.
+    pub fn relaxed(mut self, relaxation: MatchRelaxation) -> Self {
+        self.relaxation = relaxation;
+        self
+    }
+
+    /// Read every file through `access` during the search, as
+    /// [`with_file_access`](crate::with_file_access) would.
+    pub fn file_access(mut self, access: impl FileAccess + 'static) -> Self {
+        self.file_access = Some(Arc::new(access));
+        self
+    }
+
+    /// The declaration site of the chosen function, if any matched.
+    pub fn find(&self) -> Option<DeclarationSite> {
+        if !self.is_possible() {
+            return None;
+        }
+        self.run(|| process::scan_for_name(self)).ok()
+    }
+
+    /// The declaration site of the chosen function, or why it could not be
+    /// found.
+    ///
+    /// # Errors
+    ///
+    /// As for [`try_declaration_by_name`](crate::try_declaration_by_name)
+    pub fn try_find(&self) -> Result<DeclarationSite, DeclarationError> {
+        if names::function_likelihood(self.name) != FunctionLikelihood::Plausible {
+            return Err(DeclarationError::ProbablyNotAFunction);
+        }
+        self.run(|| process::scan_for_name(self))
+    }
+
+    /// Which function is chosen, and why, or `None` if no function matched.
+    ///
+    /// This is useful for debugging why a search found an unexpected
+    /// declaration site, such as when several functions match the name.
+    pub fn explain(&self) -> Option<DeclarationChoice> {
+        if !self.is_possible() {
+            return None;
+        }
+        self.run(|| process::choose_by_name(self).0)
+    }
+
+    /// Every function which matched, with the details of each as in
+    /// [`explain`](Self::explain).
+    ///
+    /// This includes every copy of a function, such as those compiled into
+    /// the units of several crates, and the functions of each version of a
+    /// crate when several are linked (see [`DeclarationChoice::version`]), so
+    /// a preferred [`version`](Self::version) doesn't filter them. The
+    /// functions are sorted by the file and line of their declaration sites.
+    /// If the function was only found inlined into other functions, its
+    /// inlined copies are returned, with the calls they were inlined at (see
+    /// [`DeclarationChoice::inlined_into`]).
+    ///
+    /// With [relaxed](Self::relaxed) matching, this includes every function
+    /// which matched at every level up to the relaxation, even if several
+    /// different functions matched. The functions which matched at the least
+    /// relaxed level are first.
+    pub fn explain_all(&self) -> Vec<DeclarationChoice> {
+        if !self.is_possible() {
+            return Vec::new();
+        }
+        self.run(|| process::explain_all(self))
+    }
+
+    /// Whether the name could be the name of a function, so should be
+    /// searched for.
+    fn is_possible(&self) -> bool {
+        names::function_likelihood(self.name) != FunctionLikelihood::Impossible
+    }
+
+    /// Run `f` with the search's file access, if it has one.
+    fn run<R>(&self, f: impl FnOnce() -> R) -> R {
+        match &self.file_access {
+            Some(access) => file_access::with_shared_file_access(access.clone(), f),
+            None => f(),
+        }
+    }
+}
+
+impl fmt::Debug for DeclarationSearch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeclarationSearch")
+            .field("name", &self.name)
+            .field("macro_site", &self.macro_site)
+            .field("version", &self.version)
+            .field("package", &self.package)
+            .field("relaxation", &self.relaxation)
+            .field("path_preference", &self.path_preference)
+            .field("file_access", &self.file_access.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,124 @@
+//! Keeping the index of a file in memory to answer many lookups, such as in a
+//! long running tool, and refreshing it when the file is rebuilt.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{file_access, index_cache::SourceIdentity, DeclarationIndex, IndexDiff};
+
+/// The [`DeclarationIndex`] of a binary or debug file, kept in memory, which
+/// can be refreshed when the file changes.
+///
+/// Lookups in the index don't read the file, so they take microseconds.
+/// [`refresh`](Self::refresh) checks whether the file has changed (by its
+/// length and the debug ids of its objects, as for the
+/// [index cache](DeclarationIndex::from_file_cached)), and only indexes it
+/// again if it has.
+///
+/// ```rust,no_run
+/// use declaration_site::IndexSession;
+///
+/// let mut session = IndexSession::open(std::env::current_exe().unwrap()).unwrap();
+/// for name in ["my_crate::main", "my_crate::run"] {
+///     println!("{name}: {:?}", session.index().get(name));
+/// }
+/// // After the file is rebuilt
+/// if let Some(diff) = session.refresh().unwrap() {
+///     println!("{} functions moved", diff.moved.len());
+/// }
+/// ```
+///
+/// The `daemon` example in this crate answers queries from a session.
+#[derive(Debug)]
+pub struct IndexSession {
+    path: PathBuf,
+    index: DeclarationIndex,
+    source: SourceIdentity,
+    scans: usize,
+}
+
+impl IndexSession {
+    /// Index the binary or debug file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read, or is not a supported object file format
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let (index, source) = read_index(&path)?;
+        Ok(IndexSession {
+            path,
+            index,
+            source,
+            scans: 1,
+        })
+    }
+
+    /// The path of the indexed file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The index of the file, as of the last time it was indexed.
+    pub fn index(&self) -> &DeclarationIndex {
+        &self.index
+    }
+
+    /// The number of times the file has been indexed, including when the
+    /// session was opened.
+    pub fn scans(&self) -> usize {
+        self.scans
+    }
+
+    /// Index the file again if it has changed since it was last indexed,
+    /// returning how the index changed, or `None` if the file hadn't changed.
+    ///
+    /// The file is read to check whether it has changed, but its functions
+    /// are only scanned if it has.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read, or is not a supported object file
+    /// format. The index is unchanged in this case.
+    pub fn refresh(&mut self) -> io::Result<Option<IndexDiff>> {
+        let data = file_access::read(&self.path)?;
+        if SourceIdentity::of_bytes(&data)? == self.source {
+            return Ok(None);
+        }
+        self.replace(&data).map(Some)
+    }
+
+    /// Index the file again, even if it hasn't changed, returning how the
+    /// index changed.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read, or is not a supported object file
+    /// format. The index is unchanged in this case.
+    pub fn reload(&mut self) -> io::Result<IndexDiff> {
+        let data = file_access::read(&self.path)?;
+        self.replace(&data)
+    }
+
+    /// Replace the index with that of the file with contents `data`.
+    fn replace(&mut self, data: &[u8]) -> io::Result<IndexDiff> {
+        let source = SourceIdentity::of_bytes(data)?;
+        let index = DeclarationIndex::from_bytes(data)?;
+        let diff = self.index.diff(&index);
+        self.index = index;
+        self.source = source;
+        self.scans += 1;
+        Ok(diff)
+    }
+}
+
+/// The index and identity of the file at `path`, reading it once.
+fn read_index(path: &Path) -> io::Result<(DeclarationIndex, SourceIdentity)> {
+    let data = file_access::read(path)?;
+    Ok((
+        DeclarationIndex::from_bytes(&data)?,
+        SourceIdentity::of_bytes(&data)?,
+    ))
+}
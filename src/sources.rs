@@ -0,0 +1,470 @@
+//! Reading source files embedded in debug files, such as source bundles,
+//! and reading snippets of source files.
+
+use std::{
+    borrow::Cow,
+    io,
+    ops::{Range, RangeInclusive},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    file_access,
+    object::{ObjectError, OwnedDebugSession, OwnedObject},
+    DeclarationSite,
+};
+#[cfg(feature = "process")]
+use crate::{scan_loaded_files, IterationControl};
+
+/// A set of debug files which contain embedded sources, such as source bundles
+/// created with `symbolic_debuginfo`'s `SourceBundleWriter`.
+///
+/// This can be used to read the source of a [`DeclarationSite`] when its file
+/// isn't available locally, such as when the program is running on a
+/// different machine to the one it was compiled on.
+///
+/// ```rust
+/// # use std::io::Cursor;
+/// # use declaration_site::{DeclarationSite, EmbeddedSources};
+/// # use symbolic_debuginfo::sourcebundle::{SourceBundleWriter, SourceFileInfo};
+/// let mut bundle = Vec::new();
+/// let mut writer = SourceBundleWriter::start(Cursor::new(&mut bundle)).unwrap();
+/// let mut info = SourceFileInfo::new();
+/// info.set_path("/home/me/project/src/greeting.rs".into());
+/// writer
+///     .add_file("src/greeting.rs", "fn greet() {}\n".as_bytes(), info)
+///     .unwrap();
+/// writer.finish().unwrap();
+///
+/// let mut sources = EmbeddedSources::new();
+/// sources.add_bytes(bundle).unwrap();
+/// let site = DeclarationSite {
+///     file: "src/greeting.rs".into(),
+///     line: 1,
+/// };
+/// assert_eq!(sources.read_source(&site).as_deref(), Some("fn greet() {}\n"));
+/// ```
+#[derive(Default)]
+pub struct EmbeddedSources {
+    sessions: Vec<OwnedDebugSession>,
+}
+
+impl EmbeddedSources {
+    /// An empty set of sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Find the currently loaded libraries (or their debug files) which
+    /// contain embedded sources.
+    ///
+    /// Enabled by the `process` feature.
+    ///
+    /// See also "Caveats" in the [module level documentation](crate).
+    #[cfg(feature = "process")]
+    pub fn from_loaded_libraries() -> Self {
+        let mut sources = Self::new();
+        scan_loaded_files(|_, data, _| {
+            // Only copy the libraries which will be kept
+            if has_sources(data) {
+                let _ = sources.add_bytes(data);
+            }
+            IterationControl::Continue
+        });
+        sources
+    }
+
+    /// Add the objects with embedded sources in the file at `path`, returning
+    /// the number of objects added.
+    ///
+    /// # Errors
+    ///
+    /// If the file could not be read, or is not a supported object file format
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> io::Result<usize> {
+        self.add_bytes(file_access::read(path.as_ref())?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Add the objects with embedded sources in the file with contents
+    /// `data`, returning the number of objects added.
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a supported object file format
+    pub fn add_bytes(&mut self, data: impl Into<Arc<[u8]>>) -> Result<usize, ObjectError> {
+        let data = data.into();
+        let mut added = 0;
+        for index in 0.. {
+            let Some(object) = OwnedObject::parse_at(Arc::clone(&data), index)? else {
+                break;
+            };
+            if !object.with_object(|object| object.has_sources()) {
+                continue;
+            }
+            if let Ok(session) = object.debug_session() {
+                self.sessions.push(session);
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Whether no objects with embedded sources have been added.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// The embedded source of the file at `path`, from the first object
+    /// which contains it.
+    ///
+    /// See [`ObjectDebugSession::extract_source`](crate::object::ObjectDebugSession::extract_source)
+    /// for how `path` is matched.
+    pub fn source(&self, path: &str) -> Option<String> {
+        self.sessions
+            .iter()
+            .find_map(|session| session.with_session(|session| session.extract_source(path)))
+    }
+
+    /// The source of the file containing `site`, read from the local file if
+    /// it exists, or otherwise from the embedded sources.
+    pub fn read_source(&self, site: &DeclarationSite) -> Option<String> {
+        file_access::read_to_string(Path::new(&site.file))
+            .ok()
+            .or_else(|| self.source(&site.file))
+    }
+
+    /// The lines around `site`, as in [`read_snippet`], read from the local
+    /// file if it exists, or otherwise from the embedded sources.
+    ///
+    /// Embedded sources are extracted whole, but only `options.max_bytes` of
+    /// them are searched for the lines.
+    pub fn read_snippet(
+        &self,
+        site: &DeclarationSite,
+        options: SnippetOptions,
+    ) -> Option<SourceSnippet> {
+        read_snippet(site, options).ok().or_else(|| {
+            let source = self.source(&site.file)?;
+            let source = source.as_bytes();
+            let read = |range: Range<u64>| {
+                let clamp = |offset: u64| {
+                    usize::try_from(offset).map_or(source.len(), |it| it.min(source.len()))
+                };
+                Ok(source[clamp(range.start)..clamp(range.end)].to_vec())
+            };
+            snippet(read, options.lines(site.line), options.max_bytes).ok()
+        })
+    }
+}
+
+/// Which lines of a source file [`read_snippet`] reads, and how much of the
+/// file it reads to find them.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SnippetOptions {
+    /// The number of lines before and after the line of the site to read
+    pub context_lines: u32,
+    /// The most bytes of the file which are read, including the lines
+    /// before the snippet. Lines after this many bytes aren't read, and the
+    /// snippet is marked as [truncated](SourceSnippet::truncated)
+    pub max_bytes: u64,
+}
+
+impl SnippetOptions {
+    /// The lines of the snippet around `line`.
+    fn lines(self, line: u32) -> RangeInclusive<u32> {
+        line.saturating_sub(self.context_lines).max(1)..=line.saturating_add(self.context_lines)
+    }
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        SnippetOptions {
+            context_lines: 2,
+            max_bytes: 1 << 20,
+        }
+    }
+}
+
+/// How the text of a [`SourceSnippet`] was decoded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SourceEncoding {
+    /// UTF-8, with or without a byte order mark
+    Utf8,
+    /// UTF-16 little endian, detected by its byte order mark
+    Utf16Le,
+    /// UTF-16 big endian, detected by its byte order mark
+    Utf16Be,
+    /// Latin-1 (ISO-8859-1), where each byte is a character. Text which
+    /// isn't UTF-8 and has no byte order mark is decoded as this, which
+    /// gives the right characters for Latin-1 files, but not for other
+    /// encodings
+    Latin1,
+}
+
+/// The lines of a source file around a declaration site, read by
+/// [`read_snippet`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SourceSnippet {
+    /// The number of the first line in [`lines`](Self::lines), starting at 1
+    pub first_line: u32,
+    /// The text of each line, without its `\n` or `\r\n` line ending. There
+    /// are fewer lines than were asked for if the file ends first, or if
+    /// the rest weren't read within [`SnippetOptions::max_bytes`]
+    pub lines: Vec<String>,
+    /// How the text was decoded
+    pub encoding: SourceEncoding,
+    /// Whether the text may not be what was written, as it wasn't valid in
+    /// its encoding, so was decoded as [`Latin1`](SourceEncoding::Latin1), or
+    /// had characters replaced with `U+FFFD`
+    pub lossy: bool,
+    /// Whether [`SnippetOptions::max_bytes`] were read before reaching the
+    /// end of the snippet, so some of its lines are missing
+    pub truncated: bool,
+}
+
+impl SourceSnippet {
+    /// The text of line `line` (starting at 1), if it is in the snippet.
+    pub fn line(&self, line: u32) -> Option<&str> {
+        let index = line.checked_sub(self.first_line)?;
+        self.lines
+            .get(usize::try_from(index).ok()?)
+            .map(String::as_str)
+    }
+}
+
+/// Read the lines around `site` in its source file, reading as little of the
+/// file as possible.
+///
+/// The file is read in chunks through [`FileAccess::read_range`](crate::FileAccess::read_range),
+/// up to the end of the snippet, and at most `options.max_bytes` of it are
+/// read, so this can be used with huge generated files. Files starting with
+/// a UTF-8 or UTF-16 byte order mark are decoded as that encoding, and
+/// others as UTF-8 unless the snippet isn't valid UTF-8, when it is decoded
+/// as Latin-1 and marked as [lossy](SourceSnippet::lossy). Lines may end with
+/// `\n` or `\r\n`.
+///
+/// ```rust
+/// use std::{collections::HashMap, io, path::{Path, PathBuf}};
+///
+/// use declaration_site::{
+///     read_snippet, with_file_access, DeclarationSite, FileAccess, FileMetadata,
+///     SnippetOptions, SourceEncoding,
+/// };
+///
+/// struct InMemory(HashMap<PathBuf, Vec<u8>>);
+///
+/// impl FileAccess for InMemory {
+///     fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+///         self.0.get(path).cloned().ok_or(io::ErrorKind::NotFound.into())
+///     }
+///
+///     fn metadata(&self, _: &Path) -> io::Result<FileMetadata> {
+///         Err(io::ErrorKind::Unsupported.into())
+///     }
+///
+///     fn read_dir(&self, _: &Path) -> io::Result<Vec<PathBuf>> {
+///         Err(io::ErrorKind::Unsupported.into())
+///     }
+/// }
+///
+/// let source = b"// Caf\xe9\r\nfn greet() {}\r\n\r\nfn main() {}\r\n".to_vec();
+/// let files = HashMap::from([("src/greeting.rs".into(), source)]);
+/// let site = DeclarationSite {
+///     file: "src/greeting.rs".into(),
+///     line: 2,
+/// };
+/// let options = SnippetOptions {
+///     context_lines: 1,
+///     ..SnippetOptions::default()
+/// };
+/// let snippet = with_file_access(InMemory(files), || read_snippet(&site, options)).unwrap();
+/// assert_eq!(snippet.first_line, 1);
+/// assert_eq!(snippet.lines, ["// Café", "fn greet() {}", ""]);
+/// assert_eq!(snippet.line(2), Some("fn greet() {}"));
+/// assert_eq!(snippet.encoding, SourceEncoding::Latin1);
+/// assert!(snippet.lossy);
+/// assert!(!snippet.truncated);
+/// ```
+///
+/// # Errors
+///
+/// If the file could not be read
+pub fn read_snippet(site: &DeclarationSite, options: SnippetOptions) -> io::Result<SourceSnippet> {
+    let path = Path::new(&site.file);
+    snippet(
+        |range| file_access::read_range(path, range),
+        options.lines(site.line),
+        options.max_bytes,
+    )
+}
+
+/// The size of the first chunk read by [`snippet`], which doubles with each
+/// chunk, so that short files are read at once, and long files in a few
+/// reads.
+const FIRST_CHUNK: u64 = 4096;
+
+/// Read `lines` of a file, reading at most `max_bytes` of it with `read`,
+/// which reads a range of the file (fewer bytes at its end).
+fn snippet(
+    mut read: impl FnMut(Range<u64>) -> io::Result<Vec<u8>>,
+    lines: RangeInclusive<u32>,
+    max_bytes: u64,
+) -> io::Result<SourceSnippet> {
+    let mut offset = 0u64;
+    let mut chunk = FIRST_CHUNK;
+    // The bytes which haven't been split into lines yet
+    let mut pending = Vec::new();
+    let mut encoding = None;
+    let mut line = 1;
+    let mut found = Vec::new();
+    let mut at_end = false;
+    while line <= *lines.end() && !at_end {
+        let end = offset.saturating_add(chunk).min(max_bytes);
+        if end <= offset {
+            break;
+        }
+        let data = read(offset..end)?;
+        at_end = (data.len() as u64) < end - offset;
+        offset += data.len() as u64;
+        chunk = chunk.saturating_mul(2);
+        pending.extend_from_slice(&data);
+        let encoding = *encoding.get_or_insert_with(|| {
+            let (encoding, bom) = sniff_encoding(&pending);
+            pending.drain(..bom);
+            encoding
+        });
+        let newline = newline(encoding.unwrap_or(SourceEncoding::Utf8));
+        let mut start = 0;
+        while let Some(len) = (start..pending.len())
+            .step_by(newline.len())
+            .find(|&i| pending[i..].starts_with(newline))
+            .map(|i| i - start)
+        {
+            if lines.contains(&line) {
+                found.push(pending[start..start + len].to_vec());
+            }
+            line += 1;
+            start += len + newline.len();
+            if line > *lines.end() {
+                break;
+            }
+        }
+        pending.drain(..start);
+        // The last line of a file may not end with a newline
+        if at_end && !pending.is_empty() && lines.contains(&line) {
+            found.push(std::mem::take(&mut pending));
+            line += 1;
+        }
+    }
+    let truncated = line <= *lines.end() && !at_end;
+    let (encoding, text, lossy) = decode(encoding.flatten(), found);
+    Ok(SourceSnippet {
+        first_line: *lines.start(),
+        lines: text,
+        encoding,
+        lossy,
+        truncated,
+    })
+}
+
+/// The encoding of a file starting with `data` given by its byte order mark,
+/// if it has one, and the length of the mark.
+fn sniff_encoding(data: &[u8]) -> (Option<SourceEncoding>, usize) {
+    match data {
+        [0xEF, 0xBB, 0xBF, ..] => (Some(SourceEncoding::Utf8), 3),
+        [0xFF, 0xFE, ..] => (Some(SourceEncoding::Utf16Le), 2),
+        [0xFE, 0xFF, ..] => (Some(SourceEncoding::Utf16Be), 2),
+        _ => (None, 0),
+    }
+}
+
+/// The code unit of `\n` in `encoding`.
+fn newline(encoding: SourceEncoding) -> &'static [u8] {
+    match encoding {
+        SourceEncoding::Utf8 | SourceEncoding::Latin1 => b"\n",
+        SourceEncoding::Utf16Le => b"\n\0",
+        SourceEncoding::Utf16Be => b"\0\n",
+    }
+}
+
+/// Decode `lines` from `encoding` (as UTF-8 or Latin-1 if the file has no
+/// byte order mark), removing the `\r` of `\r\n` line endings, returning the
+/// encoding they were decoded from and whether it was lossy.
+fn decode(
+    encoding: Option<SourceEncoding>,
+    lines: Vec<Vec<u8>>,
+) -> (SourceEncoding, Vec<String>, bool) {
+    let mut lossy = false;
+    let (encoding, decoded) = match encoding {
+        Some(encoding @ (SourceEncoding::Utf16Le | SourceEncoding::Utf16Be)) => {
+            let decoded = lines
+                .iter()
+                .map(|line| {
+                    lossy |= line.len() % 2 != 0;
+                    let units = line.chunks_exact(2).map(|unit| match encoding {
+                        SourceEncoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]),
+                        _ => u16::from_be_bytes([unit[0], unit[1]]),
+                    });
+                    char::decode_utf16(units)
+                        .map(|c| {
+                            c.unwrap_or_else(|_| {
+                                lossy = true;
+                                char::REPLACEMENT_CHARACTER
+                            })
+                        })
+                        .collect()
+                })
+                .collect();
+            (encoding, decoded)
+        }
+        Some(encoding) => {
+            let decoded = lines
+                .iter()
+                .map(|line| {
+                    let text = String::from_utf8_lossy(line);
+                    lossy |= matches!(text, Cow::Owned(_));
+                    text.into_owned()
+                })
+                .collect();
+            (encoding, decoded)
+        }
+        None if lines.iter().all(|line| str::from_utf8(line).is_ok()) => {
+            let decoded = lines
+                .into_iter()
+                .map(|line| String::from_utf8(line).unwrap_or_default())
+                .collect();
+            (SourceEncoding::Utf8, decoded)
+        }
+        // Files without a byte order mark which aren't UTF-8 are most often
+        // Latin-1, such as comments in older C sources
+        None => {
+            lossy = true;
+            let decoded = lines
+                .iter()
+                .map(|line| line.iter().copied().map(char::from).collect())
+                .collect();
+            (SourceEncoding::Latin1, decoded)
+        }
+    };
+    (encoding, strip_carriage_returns(decoded), lossy)
+}
+
+fn strip_carriage_returns(mut lines: Vec<String>) -> Vec<String> {
+    for line in &mut lines {
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    lines
+}
+
+/// Whether any object in `data` has embedded sources.
+#[cfg(feature = "process")]
+fn has_sources(data: &[u8]) -> bool {
+    crate::object::Archive::parse(data).is_ok_and(|archive| {
+        archive
+            .objects()
+            .any(|object| object.is_ok_and(|object| object.has_sources()))
+    })
+}
@@ -0,0 +1,127 @@
+//! Falling back to the symbol table for libraries without debug info.
+
+use std::fmt;
+
+use symbolic_common::Name;
+use symbolic_demangle::{Demangle, DemangleOptions};
+
+use crate::{
+    names, object, redaction, scan_for_name, scan_loaded_files, DeclarationSearch, DeclarationSite,
+    IterationControl,
+};
+
+/// A function found in the symbol table of a library which has no debug info,
+/// so whose declaration site is unknown.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolOnly {
+    /// The demangled name of the function
+    pub name: String,
+    /// The path of the library containing the function
+    pub library: String,
+    /// The address of the function as stated in the library (its SVMA),
+    /// which can be used to resolve it later, such as with a symbol server
+    pub svma: u64,
+}
+
+impl fmt::Display for SymbolOnly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {} at {:#x}", self.name, self.library, self.svma)
+    }
+}
+
+/// The result of [`lookup_by_name`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Lookup {
+    /// The function was found in debug info
+    Declaration(DeclarationSite),
+    /// The function was only found in the symbol table of a library without
+    /// debug info
+    SymbolOnly(SymbolOnly),
+}
+
+impl Lookup {
+    /// The declaration site, if it was found.
+    pub fn declaration(&self) -> Option<&DeclarationSite> {
+        match self {
+            Lookup::Declaration(site) => Some(site),
+            Lookup::SymbolOnly(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Lookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lookup::Declaration(site) => site.fmt(f),
+            Lookup::SymbolOnly(symbol) => symbol.fmt(f),
+        }
+    }
+}
+
+/// Attempt to find a currently loaded function with the given (unmangled)
+/// name, as in [`declaration_by_name`](crate::declaration_by_name).
+///
+/// If `symbol_fallback` is set and the function isn't found in any debug
+/// info, the symbol tables of loaded libraries without debug info (such as
+/// those built with `-C strip=debuginfo`) are also searched. This finds the
+/// library containing the function, but not its declaration site.
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn lookup_by_name(name: &str, symbol_fallback: bool) -> Option<Lookup> {
+    if names::function_likelihood(name) == names::FunctionLikelihood::Impossible {
+        return None;
+    }
+    if let Ok(site) = scan_for_name(&DeclarationSearch::new(name)) {
+        return Some(Lookup::Declaration(site));
+    }
+    if !symbol_fallback {
+        return None;
+    }
+    let mut result = None;
+    scan_loaded_files(|path, data, _| {
+        result = scan_symbols(data, name).map(|(name, svma)| SymbolOnly {
+            name,
+            library: redaction::path(&path.display().to_string()).into_owned(),
+            svma,
+        });
+        if result.is_some() {
+            IterationControl::Break
+        } else {
+            IterationControl::Continue
+        }
+    });
+    result.map(Lookup::SymbolOnly)
+}
+
+/// Find the symbol for the function `name` in the objects in `data` which
+/// have symbols but no debug info, returning its demangled name and SVMA.
+fn scan_symbols(data: &[u8], name: &str) -> Option<(String, u64)> {
+    let archive = object::Archive::parse(data).ok()?;
+    for object in archive.objects().flatten() {
+        // Objects with debug info were already searched
+        if object.has_debug_info() || !object.has_symbols() {
+            continue;
+        }
+        for symbol in object.symbols() {
+            let Some(raw_name) = symbol.name() else {
+                continue;
+            };
+            let address = symbol.address + object.load_address();
+            let Some(demangled_name) = Name::from(raw_name).demangle(DemangleOptions::name_only())
+            else {
+                // `symbolic` already removes the extra leading underscore of
+                // Mach-O symbols, so it isn't ignored again
+                if names::match_unmangled(raw_name, name, false).is_some() {
+                    return Some((raw_name.to_owned(), address));
+                }
+                continue;
+            };
+            if names::names_match(&demangled_name, name) {
+                return Some((demangled_name, address));
+            }
+        }
+    }
+    None
+}
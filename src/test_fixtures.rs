@@ -0,0 +1,107 @@
+//! Functions of several shapes, for checking that functions can be found by
+//! their [`type_name`](core::any::type_name) with the current toolchain,
+//! enabled by the `test-fixtures` feature.
+//!
+//! A change in how rustc or the demangler spells names can stop functions
+//! from being found without any other failure. Run [`verify_fixtures`] in a
+//! test (or example) built with debug info in CI, to find this out when the
+//! toolchain is updated:
+//!
+//! ```rust,no_run
+//! if let Err(drift) = declaration_site::test_fixtures::verify_fixtures() {
+//!     panic!("{drift}");
+//! }
+//! ```
+
+use std::{
+    future::Future,
+    hint::black_box,
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use crate::{verify_matching_roundtrip_of, MatchDrift};
+
+/// A free function.
+#[inline(never)]
+pub fn free_function(x: u32) -> u32 {
+    x.wrapping_mul(3)
+}
+
+/// A type with an inherent method and a trait method.
+pub struct Fixture(pub u32);
+
+impl Fixture {
+    /// An inherent method.
+    #[inline(never)]
+    pub fn method(&self) -> u32 {
+        self.0.wrapping_add(1)
+    }
+}
+
+/// A trait implemented by [`Fixture`].
+pub trait FixtureTrait {
+    /// A trait method.
+    fn trait_method(&self) -> u32;
+}
+
+impl FixtureTrait for Fixture {
+    #[inline(never)]
+    fn trait_method(&self) -> u32 {
+        self.0.wrapping_mul(2)
+    }
+}
+
+/// A generic function.
+#[inline(never)]
+pub fn generic_function<T: Into<u64>>(x: T) -> u64 {
+    x.into().wrapping_add(1)
+}
+
+/// A function returning a closure.
+#[inline(never)]
+pub fn make_closure(offset: u32) -> impl Fn(u32) -> u32 {
+    move |x| x.wrapping_add(offset)
+}
+
+/// An `async fn`.
+#[inline(never)]
+pub async fn async_function(x: u32) -> u32 {
+    black_box(x).wrapping_add(2)
+}
+
+/// Call each of the fixtures in this module, then check that each can be
+/// found by its `type_name` using [`verify_matching_roundtrip_of`].
+///
+/// # Errors
+///
+/// The failure for the first fixture which wasn't found.
+pub fn verify_fixtures() -> Result<(), MatchDrift> {
+    // Ensure that the fixtures are linked
+    black_box(free_function(black_box(1)));
+    let fixture = Fixture(black_box(2));
+    black_box(fixture.method());
+    black_box(fixture.trait_method());
+    black_box(generic_function(black_box(3u8)));
+    let closure = make_closure(black_box(4));
+    black_box(black_box(&closure as &dyn Fn(u32) -> u32)(5));
+    black_box(block_on(async_function(black_box(6))));
+
+    verify_matching_roundtrip_of(&free_function)?;
+    verify_matching_roundtrip_of(&Fixture::method)?;
+    verify_matching_roundtrip_of(&<Fixture as FixtureTrait>::trait_method)?;
+    verify_matching_roundtrip_of(&generic_function::<u8>)?;
+    verify_matching_roundtrip_of(&closure)?;
+    verify_matching_roundtrip_of(&async_function)?;
+    Ok(())
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Helpers for checking that functions are found at their true declaration
+//! sites, enabled by the `test-support` feature.
+//!
+//! A probe is a purpose-built function whose body is [`probe!`](crate::probe),
+//! which returns a [`Probe`] recording the function's name, [`file!`] and
+//! [`line!`] at compile time. [`assert_declaration_matches!`](crate::assert_declaration_matches)
+//! then looks the function up as [`declaration_by_name`] would, and checks
+//! that it was found at the recorded site. Declare probes of the shapes of
+//! function you rely on, and check them in a test (or example) built with
+//! debug info in CI, to find out when they stop being found, such as when the
+//! toolchain is updated:
+//!
+//! ```rust,no_run
+//! use declaration_site::{assert_declaration_matches, probe, test_support::Probe};
+//!
+//! #[inline(never)]
+//! fn free_probe() -> Probe {
+//!     probe!(free_probe)
+//! }
+//!
+//! struct Widget;
+//!
+//! impl Widget {
+//!     #[inline(never)]
+//!     fn method_probe(&self) -> Probe {
+//!         probe!(Self::method_probe)
+//!     }
+//! }
+//!
+//! assert_declaration_matches!(free_probe());
+//! assert_declaration_matches!(Widget.method_probe(), tolerance = 2);
+//! ```
+//!
+//! Probes are written out rather than declared by a macro, as the debug info
+//! of a function generated by a macro points into the macro's definition.
+//! Probes should be `#[inline(never)]`, as inlined functions are only found
+//! through their inlined copies, if at all.
+
+use std::{error::Error, fmt, path::Path};
+
+use crate::{declaration_by_name, redaction, DeclarationSite};
+
+/// A function whose body is [`probe!`](crate::probe), with the site it was
+/// declared at.
+///
+/// Calling the function returns its probe, which also ensures that the
+/// function is linked.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Probe {
+    /// The [`type_name`](core::any::type_name) of the function
+    pub name: &'static str,
+    /// The file the function was declared in, from [`file!`]
+    pub file: &'static str,
+    /// The line of the function's [`probe!`](crate::probe), from [`line!`]
+    pub line: u32,
+}
+
+impl Probe {
+    /// Look up the function of this probe as
+    /// [`declaration_by_name`] would, and check that it was found in
+    /// [`file`](Self::file), within `tolerance` lines of
+    /// [`line`](Self::line).
+    ///
+    /// # Errors
+    ///
+    /// If the function wasn't found, or was found at another site.
+    pub fn check(&self, tolerance: u32) -> Result<DeclarationSite, ProbeMismatch> {
+        // The file is compared before it is redacted
+        let Some(site) = redaction::unredacted(|| declaration_by_name(self.name)) else {
+            return Err(ProbeMismatch {
+                probe: *self,
+                tolerance,
+                found: None,
+            });
+        };
+        if Path::new(&site.file).ends_with(self.file) && site.line.abs_diff(self.line) <= tolerance
+        {
+            Ok(redaction::site(site))
+        } else {
+            Err(ProbeMismatch {
+                probe: *self,
+                tolerance,
+                found: Some(redaction::site(site)),
+            })
+        }
+    }
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` at {}:{}", self.name, self.file, self.line)
+    }
+}
+
+/// Why the function of a [`Probe`] wasn't found at the site it was declared
+/// at, from [`Probe::check`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProbeMismatch {
+    /// The probe which was checked
+    pub probe: Probe,
+    /// The number of lines the site found could differ from the probe's by
+    pub tolerance: u32,
+    /// The site the function was found at, or `None` if it wasn't found
+    pub found: Option<DeclarationSite>,
+}
+
+impl fmt::Display for ProbeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.found {
+            Some(site) => write!(
+                f,
+                "{} was found at {site}, not within {} line(s) of its declaration",
+                self.probe, self.tolerance
+            ),
+            None => write!(f, "{} wasn't found", self.probe),
+        }
+    }
+}
+
+impl Error for ProbeMismatch {}
+
+/// The [`Probe`] of the function `$function`, for use as its body.
+///
+/// `$function` is the path of the function containing this, with any generic
+/// arguments, such as `Self::method` or `generic::<T>`. The probe's line is
+/// the line this is invoked on, whereas the function is found at the line of
+/// its signature, which is the line before as formatted by rustfmt. The
+/// default tolerance of
+/// [`assert_declaration_matches!`](crate::assert_declaration_matches) allows
+/// for this.
+///
+/// ```rust,no_run
+/// use declaration_site::{probe, test_support::Probe};
+///
+/// #[inline(never)]
+/// fn generic_probe<T>() -> Probe {
+///     probe!(generic_probe::<T>)
+/// }
+///
+/// let probe = generic_probe::<u8>();
+/// assert!(probe.name.ends_with("generic_probe<u8>"));
+/// ```
+#[macro_export]
+macro_rules! probe {
+    ($function:expr $(,)?) => {
+        $crate::test_support::Probe {
+            name: ::core::any::type_name_of_val(&$function),
+            file: ::core::file!(),
+            line: ::core::line!(),
+        }
+    };
+}
+
+/// Check that the function of a [`Probe`] is found at the site it was
+/// declared at, panicking with a [`ProbeMismatch`] otherwise.
+///
+/// The line of the site found may differ from the probe's line by
+/// `tolerance` lines. This is 1 if not given, as the function's signature is
+/// usually on the line before its `probe!`. Evaluates to the
+/// [`DeclarationSite`](crate::DeclarationSite) found.
+///
+/// ```rust,no_run
+/// use declaration_site::{assert_declaration_matches, probe, test_support::Probe};
+///
+/// #[inline(never)]
+/// fn my_probe() -> Probe {
+///     probe!(my_probe)
+/// }
+///
+/// let site = assert_declaration_matches!(my_probe());
+/// println!("`my_probe` is declared at {site}");
+/// ```
+#[macro_export]
+macro_rules! assert_declaration_matches {
+    ($probe:expr $(,)?) => {
+        $crate::assert_declaration_matches!($probe, tolerance = 1)
+    };
+    ($probe:expr, tolerance = $tolerance:expr $(,)?) => {
+        match $crate::test_support::Probe::check(&$probe, $tolerance) {
+            ::core::result::Result::Ok(site) => site,
+            ::core::result::Result::Err(mismatch) => ::core::panic!("{}", mismatch),
+        }
+    };
+}
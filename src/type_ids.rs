@@ -0,0 +1,92 @@
+//! Finding declaration sites from a [`TypeId`], for types whose names were
+//! registered beforehand.
+
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+
+use crate::{cache::cached_by_type_id, declaration_by_name, names, DeclarationSite};
+
+/// The names of the types registered with [`register_type`], by [`TypeId`].
+type Registry = RwLock<HashMap<TypeId, &'static str>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Record the name of `T` by its [`TypeId`], so that its declaration site can
+/// later be found by [`declaration_by_type_id`].
+///
+/// This only inserts into a process-wide map, so is cheap enough to call
+/// from startup code or from every registration of a type with a reflection
+/// system or plugin registry. Registering a type more than once has no
+/// further effect. Use [`register_type_of`] for function item types, which
+/// can't be named.
+pub fn register_type<T: ?Sized + 'static>() {
+    let id = TypeId::of::<T>();
+    let registered = registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains_key(&id);
+    if !registered {
+        registry()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id, type_name::<T>());
+    }
+}
+
+/// Record the name of the type of the given value, as in [`register_type`].
+pub fn register_type_of<T: ?Sized + 'static>(_: &T) {
+    register_type::<T>();
+}
+
+/// The name of the type with the [`TypeId`] `id`, if it was registered with
+/// [`register_type`].
+pub fn registered_type_name(id: TypeId) -> Option<&'static str> {
+    registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&id)
+        .copied()
+}
+
+/// Attempt to get the declaration site of the function item type with the
+/// [`TypeId`] `id`, which must have been registered with [`register_type`].
+///
+/// This is for code which only has a `TypeId`, such as a reflection system
+/// which stores them long after the types themselves are known. The
+/// registered name is looked up as in [`declaration`](crate::declaration),
+/// and the result is cached with those of
+/// [`cached_declaration`](crate::cached_declaration), so only the first
+/// lookup of each type (by either function) scans for it.
+///
+/// Returns `None` without scanning if the type wasn't registered.
+///
+/// ```rust,no_run
+/// use std::any::Any;
+///
+/// use declaration_site::{declaration_by_type_id, register_type_of};
+///
+/// #[inline(never)]
+/// fn on_spawn() {}
+///
+/// // When the handler is registered
+/// register_type_of(&on_spawn);
+/// let id = on_spawn.type_id();
+///
+/// // Later, with only the `TypeId`
+/// on_spawn();
+/// if let Some(site) = declaration_by_type_id(id) {
+///     println!("The handler is declared at {site}");
+/// }
+/// ```
+///
+/// See also "Caveats" in the [module level documentation](crate).
+pub fn declaration_by_type_id(id: TypeId) -> Option<DeclarationSite> {
+    let name = registered_type_name(id)?;
+    cached_by_type_id(id, || declaration_by_name(names::unwrap_fn_item_type(name))).cloned()
+}
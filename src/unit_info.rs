@@ -0,0 +1,160 @@
+//! Listing the compilation units of a binary, for auditing which compilers
+//! contributed code to it.
+
+use std::{io, path::Path};
+
+use symbolic_common::Language;
+
+use crate::{
+    limits,
+    object::{Archive, CompilationUnit},
+    priority, visit_files, IterationControl, ScanTarget,
+};
+
+/// A compilation unit of an object, from [`for_each_compilation_unit`].
+#[derive(Clone, Copy, Debug)]
+pub struct CuInfo<'a> {
+    /// The name of the unit, as in [`CompilationUnit::name`]
+    pub name: Option<&'a str>,
+    /// The working directory of the compiler, as in
+    /// [`CompilationUnit::comp_dir`]
+    pub comp_dir: Option<&'a str>,
+    /// The compiler which produced the unit, as in
+    /// [`CompilationUnit::producer`]
+    pub producer: Option<&'a str>,
+    /// The source language of the unit, as in [`CompilationUnit::language`]
+    pub language: Language,
+    /// The number of functions in the debug info whose code is in the unit,
+    /// including those which can't be demangled (such as C functions)
+    pub function_count: usize,
+    /// The file the unit was found in, as in
+    /// [`FunctionRecord::object_path`](crate::FunctionRecord::object_path).
+    /// For [`ScanTarget::CurrentProcess`], this is the library (or its debug
+    /// file) being searched
+    pub object_path: Option<&'a Path>,
+    /// The index of the object the unit was found in, as in
+    /// [`FunctionRecord::object_index`](crate::FunctionRecord::object_index)
+    pub object_index: usize,
+    /// The unit itself, such as for its
+    /// [`crate_name`](CompilationUnit::crate_name)
+    pub unit: &'a CompilationUnit,
+}
+
+/// Run `callback` on each compilation unit of each object in `target`, with
+/// the number of functions compiled in it.
+///
+/// This is for auditing the compilers which contributed code to a binary,
+/// such as to find code built by an older rustc, or by a C compiler with
+/// different flags. Units are read from DWARF debug info, and from the
+/// modules (compilands) of PDBs. Objects without either have no units.
+///
+/// Every function of each object is read to count the functions in each
+/// unit, within the current [`ScanLimits`](crate::ScanLimits).
+///
+/// ```rust,no_run
+/// use std::collections::BTreeMap;
+///
+/// use declaration_site::{for_each_compilation_unit, ScanTarget};
+///
+/// let mut functions_by_producer = BTreeMap::new();
+/// for_each_compilation_unit(ScanTarget::CurrentProcess, |unit| {
+///     let producer = unit.producer.unwrap_or("unknown").to_owned();
+///     *functions_by_producer.entry(producer).or_insert(0) += unit.function_count;
+/// })
+/// .unwrap();
+/// for (producer, functions) in functions_by_producer {
+///     println!("{functions} functions from {producer}");
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If `target` is a file which could not be read or parsed, or the scan was
+/// cancelled (with [`io::ErrorKind::Interrupted`], see
+/// [`with_cancellation`](crate::with_cancellation))
+pub fn for_each_compilation_unit<C>(
+    target: ScanTarget<'_>,
+    mut callback: impl FnMut(CuInfo<'_>) -> C,
+) -> io::Result<()>
+where
+    C: Into<IterationControl>,
+{
+    let mut visit =
+        |data: &[u8], object_path: Option<&Path>| visit_units(data, object_path, &mut callback);
+    match target {
+        #[cfg(feature = "process")]
+        ScanTarget::CurrentProcess => {
+            let summary = crate::process::scan_loaded_files(|path, data, _| {
+                visit(data, Some(path)).unwrap_or(IterationControl::Continue)
+            });
+            if summary.cancelled {
+                return Err(crate::cancellation::interrupted_error());
+            }
+            Ok(())
+        }
+        ScanTarget::File(path) => visit_files(path, visit),
+        ScanTarget::Bytes(data) => visit(data, None).map(|_| ()),
+    }
+}
+
+/// Run `callback` on each unit of each object in the file with contents
+/// `data`.
+fn visit_units<C>(
+    data: &[u8],
+    object_path: Option<&Path>,
+    callback: &mut impl FnMut(CuInfo<'_>) -> C,
+) -> io::Result<IterationControl>
+where
+    C: Into<IterationControl>,
+{
+    let archive = match limits::catch_parser_panic(|| Archive::parse(data)) {
+        Some(Ok(archive)) => archive,
+        Some(Err(e)) => {
+            let kind = if e.is_unsupported() {
+                io::ErrorKind::Unsupported
+            } else {
+                io::ErrorKind::InvalidData
+            };
+            return Err(io::Error::new(kind, e));
+        }
+        None => return Ok(IterationControl::Continue),
+    };
+    let limits = limits::current();
+    for (object_index, _, object) in priority::prioritized_objects(&archive) {
+        let Some(units) = limits::catch_parser_panic(|| object.compilation_units()) else {
+            continue;
+        };
+        if units.is_empty() {
+            continue;
+        }
+        let mut counts = vec![0; units.iter().len()];
+        if let Some(Ok(session)) = limits::catch_parser_panic(|| object.debug_session()) {
+            let mut functions = session.functions();
+            let functions =
+                std::iter::from_fn(|| limits::catch_parser_panic(|| functions.next()).flatten())
+                    .flatten()
+                    .take(limits.max_functions_per_object);
+            for function in functions {
+                if let Some(unit) = units.containing_index(function.address) {
+                    counts[unit] += 1;
+                }
+            }
+        }
+        for (unit, function_count) in units.iter().zip(counts) {
+            let info = CuInfo {
+                name: unit.name(),
+                comp_dir: unit.comp_dir(),
+                producer: unit.producer(),
+                language: unit.language(),
+                function_count,
+                object_path,
+                object_index,
+                unit,
+            };
+            if let IterationControl::Break = callback(info).into() {
+                return Ok(IterationControl::Break);
+            }
+        }
+    }
+    Ok(IterationControl::Continue)
+}
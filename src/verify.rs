@@ -0,0 +1,247 @@
+//! Checking that functions can be found by their [`type_name`], to detect
+//! changes in how rustc or the demangler spells names.
+
+use std::{any::type_name, error::Error, fmt};
+
+use crate::{
+    explain_declaration_by_name, for_each_loaded_function,
+    names::{self, NormalizationStage},
+    DeclarationSiteError,
+};
+
+/// Check that the function item type `T` can be found by its
+/// [`type_name`], as by [`declaration_by_name`](crate::declaration_by_name).
+///
+/// The function must be linked into the current program, so should be called
+/// before this is. If it isn't found, the error describes the functions which
+/// it could have matched, and the [`NormalizationStage`] which last changed
+/// the part of their names which differs from the `type_name`. This makes a
+/// change in the spelling of names by a new version of rustc or of the
+/// demangler into a failure which says what needs to change.
+///
+/// The `test-fixtures` feature adds [`test_fixtures`](crate::test_fixtures),
+/// which checks functions of several shapes using this.
+///
+/// The type of a function item can't be written, so
+/// [`verify_matching_roundtrip_of`] is usually easier to use:
+///
+/// ```rust,no_run
+/// use declaration_site::verify_matching_roundtrip_of;
+///
+/// #[inline(never)]
+/// fn my_function() {}
+///
+/// my_function();
+/// if let Err(drift) = verify_matching_roundtrip_of(&my_function) {
+///     panic!("{drift}");
+/// }
+/// ```
+///
+/// # Errors
+///
+/// If the function wasn't found, or its declaration site couldn't be read.
+pub fn verify_matching_roundtrip<T>() -> Result<(), MatchDrift> {
+    verify_matching_name(type_name::<T>())
+}
+
+/// Check that the function item type of `_function` can be found by its
+/// [`type_name`], as in [`verify_matching_roundtrip`].
+///
+/// # Errors
+///
+/// If the function wasn't found, or its declaration site couldn't be read.
+pub fn verify_matching_roundtrip_of<T>(_function: &T) -> Result<(), MatchDrift> {
+    verify_matching_roundtrip::<T>()
+}
+
+fn verify_matching_name(name: &str) -> Result<(), MatchDrift> {
+    match explain_declaration_by_name(name).map(|choice| choice.site) {
+        Some(Ok(_)) => return Ok(()),
+        Some(Err(error)) => {
+            return Err(MatchDrift::NoSite {
+                name: name.to_owned(),
+                error,
+            })
+        }
+        None => {}
+    }
+    // Find the functions which the name was probably meant to match
+    let query = names::canonical_name(name);
+    let ident = names::last_ident(&query);
+    let krate = names::crate_name(&query);
+    let mut candidates = Vec::new();
+    for_each_loaded_function(|function| {
+        let demangled_name = names::canonical_name(function.name());
+        let same_crate = match (names::crate_name(&demangled_name), krate) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        let is_candidate = names::last_ident(&demangled_name) == ident && same_crate;
+        if is_candidate
+            && !candidates
+                .iter()
+                .any(|it: &DriftCandidate| it.demangled_name == function.name())
+        {
+            candidates.push(DriftCandidate::new(function.name(), name));
+        }
+    });
+    if candidates.is_empty() {
+        Err(MatchDrift::NotFound {
+            name: name.to_owned(),
+        })
+    } else {
+        Err(MatchDrift::Mismatch {
+            name: name.to_owned(),
+            candidates,
+        })
+    }
+}
+
+/// Why a function couldn't be found by its [`type_name`], returned by
+/// [`verify_matching_roundtrip`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MatchDrift {
+    /// No function with the same crate and last identifier as the name was
+    /// found, so the function probably isn't linked, or has no debug info
+    NotFound {
+        /// The `type_name` of the function
+        name: String,
+    },
+    /// The function matched, but its declaration site couldn't be read
+    NoSite {
+        /// The `type_name` of the function
+        name: String,
+        /// Why the declaration site couldn't be read
+        error: DeclarationSiteError,
+    },
+    /// Functions with the same crate and last identifier as the name were
+    /// found, but didn't match it
+    Mismatch {
+        /// The `type_name` of the function
+        name: String,
+        /// The functions which could have matched
+        candidates: Vec<DriftCandidate>,
+    },
+}
+
+impl fmt::Display for MatchDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchDrift::NotFound { name } => write!(
+                f,
+                "`{name}` wasn't found, and no function with its crate and identifier is linked"
+            ),
+            MatchDrift::NoSite { name, error } => {
+                write!(
+                    f,
+                    "`{name}` was found, but its declaration site wasn't: {error}"
+                )
+            }
+            MatchDrift::Mismatch { name, candidates } => {
+                write!(
+                    f,
+                    "`{name}` didn't match any of {} function(s)",
+                    candidates.len()
+                )?;
+                for candidate in candidates {
+                    write!(f, "\n{candidate}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for MatchDrift {}
+
+/// A function which could have matched a name in [`MatchDrift::Mismatch`],
+/// with the spelling of both names after each [`NormalizationStage`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DriftCandidate {
+    /// The demangled name of the function
+    pub demangled_name: String,
+    /// The spellings of the demangled name and of the name being searched
+    /// for after each stage of [`canonical_name`](crate::canonical_name)
+    pub stages: Vec<(NormalizationStage, String, String)>,
+    /// The last stage which changed the first path segment where the
+    /// canonical names differ, or `None` if no stage changed it (or the
+    /// canonical names are the same)
+    pub failed_stage: Option<NormalizationStage>,
+}
+
+impl DriftCandidate {
+    fn new(demangled_name: &str, query: &str) -> Self {
+        let mut stages = Vec::new();
+        let (mut demangled, mut spelled) = (demangled_name.to_owned(), query.to_owned());
+        for stage in NormalizationStage::ALL {
+            demangled = stage.apply(&demangled).into_owned();
+            spelled = stage.apply(&spelled).into_owned();
+            stages.push((stage, demangled.clone(), spelled.clone()));
+        }
+        let failed_stage = differing_segment(&demangled, &spelled).and_then(|index| {
+            let segment = |name: &str| {
+                names::path_segments(name)
+                    .get(index)
+                    .map(|it| it.to_string())
+            };
+            let mut previous = (segment(demangled_name), segment(query));
+            let mut failed_stage = None;
+            for (stage, demangled, spelled) in &stages {
+                let current = (segment(demangled), segment(spelled));
+                if current != previous {
+                    failed_stage = Some(*stage);
+                }
+                previous = current;
+            }
+            failed_stage
+        });
+        DriftCandidate {
+            demangled_name: demangled_name.to_owned(),
+            stages,
+            failed_stage,
+        }
+    }
+}
+
+/// The index of the first path segment which differs between `a` and `b`.
+fn differing_segment(a: &str, b: &str) -> Option<usize> {
+    let (a, b) = (names::path_segments(a), names::path_segments(b));
+    (0..a.len().max(b.len())).find(|&index| a.get(index) != b.get(index))
+}
+
+impl fmt::Display for DriftCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "  `{}`", self.demangled_name)?;
+        let Some((_, demangled, spelled)) = self.stages.last() else {
+            return Ok(());
+        };
+        match differing_segment(demangled, spelled) {
+            Some(index) => {
+                let (a, b) = (
+                    names::path_segments(demangled),
+                    names::path_segments(spelled),
+                );
+                write!(
+                    f,
+                    "\n    canonical names differ at `{}` and `{}`",
+                    a.get(index).unwrap_or(&""),
+                    b.get(index).unwrap_or(&"")
+                )?;
+                match self.failed_stage {
+                    Some(stage) => write!(f, ", last changed by the {stage:?} stage")?,
+                    None => write!(f, ", which no stage changed")?,
+                }
+            }
+            None => write!(f, "\n    canonical names are the same: `{demangled}`")?,
+        }
+        // The first stage, then only the stages which changed either name
+        let mut previous = None;
+        for (stage, demangled, spelled) in &self.stages {
+            if previous != Some((demangled, spelled)) {
+                write!(f, "\n    after {stage:?}: `{demangled}` and `{spelled}`")?;
+            }
+            previous = Some((demangled, spelled));
+        }
+        Ok(())
+    }
+}
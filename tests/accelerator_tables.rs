@@ -0,0 +1,193 @@
+//! Checks that functions are found at the same declaration sites whether they
+//! are looked up in the DWARF accelerator tables of a library or by reading
+//! every function, using libraries built from the same source with a
+//! `.debug_names`, with a `.gdb_index` and without either table (see
+//! `fixtures/accelerator/build.sh`).
+
+use std::{fs, path::PathBuf};
+
+use declaration_site::{
+    declaration_by_name_in, declarations_by_name_in, object::Object, with_scan_limits,
+    DeclarationIndex, DeclarationSite, ScanLimits,
+};
+use symbolic_debuginfo::Function;
+
+/// DWARF 5, with a `.debug_names`
+const DEBUG_NAMES: &str = "fixtures/accelerator/libaccelerator_fixture.so";
+/// DWARF 4, with a `.gdb_index`
+const GDB_INDEX: &str = "fixtures/accelerator/libaccelerator_fixture_gdb_index.so";
+/// DWARF 4, without either table
+const UNINDEXED: &str = "fixtures/accelerator/libaccelerator_fixture_unindexed.so";
+
+fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+/// What is read from a function and its inlinees to find its declaration
+/// site, with addresses relative to `base` so that functions from libraries
+/// with different layouts can be compared.
+#[derive(Debug, PartialEq)]
+struct Summary {
+    name: String,
+    address: u64,
+    size: u64,
+    lines: Vec<(String, u64, u64, Option<u64>)>,
+    inlinees: Vec<Summary>,
+}
+
+impl Summary {
+    fn new(function: &Function<'_>, base: u64) -> Self {
+        Summary {
+            name: function.name.to_string(),
+            address: function.address - base,
+            size: function.size,
+            lines: function
+                .lines
+                .iter()
+                .map(|line| {
+                    let path = line.file.path_str();
+                    (path, line.line, line.address - base, line.size)
+                })
+                .collect(),
+            inlinees: function
+                .inlinees
+                .iter()
+                .map(|inlinee| Summary::new(inlinee, base))
+                .collect(),
+        }
+    }
+}
+
+/// Every function in the library at `path`, by reading every function.
+fn read_every_function(path: &str) -> Vec<Summary> {
+    let data = fs::read(fixture(path)).unwrap();
+    let object = Object::parse(&data).unwrap();
+    let session = object.debug_session().unwrap();
+    let functions = session
+        .functions()
+        .map(|function| {
+            let function = function.unwrap();
+            Summary::new(&function, function.address)
+        })
+        .collect();
+    functions
+}
+
+/// The names of the functions in the fixtures, and the sites found for them
+/// without the tables.
+fn expected_sites() -> (Vec<String>, Vec<Option<DeclarationSite>>) {
+    let index = DeclarationIndex::from_file(fixture(UNINDEXED)).unwrap();
+    assert!(index.len() > 384, "{}", index.len());
+    let names = index
+        .iter()
+        .map(|(name, _)| name.to_owned())
+        .collect::<Vec<_>>();
+    let names_ref = names.iter().map(String::as_str).collect::<Vec<_>>();
+    let sites = declarations_by_name_in(fixture(UNINDEXED), &names_ref).unwrap();
+    (names, sites)
+}
+
+#[test]
+fn every_function_is_found_at_the_same_site() {
+    let (names, expected) = expected_sites();
+    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+    for path in [DEBUG_NAMES, GDB_INDEX] {
+        let sites = declarations_by_name_in(fixture(path), &names).unwrap();
+        assert_eq!(sites, expected, "in {path}");
+        for (name, expected) in names.iter().zip(&expected) {
+            let site = declaration_by_name_in(fixture(path), name).unwrap();
+            assert_eq!(site, *expected, "for `{name}` in {path}");
+        }
+    }
+}
+
+#[test]
+fn tables_are_used_when_they_name_the_function() {
+    for name in ["accelerator_fixture::last", "accelerator_fixture::m47::f7"] {
+        let (expected, stats) = with_scan_limits(ScanLimits::default(), || {
+            declaration_by_name_in(fixture(UNINDEXED), name).unwrap()
+        });
+        assert!(expected.is_some());
+        assert_eq!(stats.indexed_objects, 0);
+        assert!(stats.functions > 384, "{stats}");
+        for path in [DEBUG_NAMES, GDB_INDEX] {
+            let (site, indexed_stats) = with_scan_limits(ScanLimits::default(), || {
+                declaration_by_name_in(fixture(path), name).unwrap()
+            });
+            assert_eq!(site, expected, "for `{name}` in {path}");
+            assert_eq!(indexed_stats.indexed_objects, 1, "in {path}");
+            // Every module has an `f7`, but that's still far fewer functions
+            assert!(
+                indexed_stats.functions * 4 < stats.functions,
+                "{indexed_stats}"
+            );
+        }
+    }
+}
+
+#[test]
+fn functions_from_tables_match_reading_every_function() {
+    let names = [
+        ("accelerator_fixture::first", "first"),
+        ("accelerator_fixture::nested::second", "second"),
+        ("accelerator_fixture::m03::f4", "f4"),
+        ("accelerator_fixture::with_inlinee", "with_inlinee"),
+        ("accelerator_fixture::last", "last"),
+        (
+            "accelerator_fixture::accelerator_fixture_entry",
+            "accelerator_fixture_entry",
+        ),
+    ];
+    // The functions in DWARF 5 can't be read without the tables (as their
+    // addresses are in `.debug_addr`), so they are compared with the same
+    // functions in DWARF 4
+    for (path, compared) in [(GDB_INDEX, GDB_INDEX), (DEBUG_NAMES, UNINDEXED)] {
+        let every_function = read_every_function(compared);
+        let data = fs::read(fixture(path)).unwrap();
+        let object = Object::parse(&data).unwrap();
+        for (qualified_name, name) in names {
+            let mut indexed = Vec::new();
+            let count = object
+                .indexed_functions(qualified_name, name, |function| {
+                    indexed.push(Summary::new(&function, function.address));
+                })
+                .unwrap_or_else(|| panic!("Should have looked up `{qualified_name}` in {path}"));
+            assert_eq!(count, indexed.len());
+            assert!(!indexed.is_empty(), "`{qualified_name}` in {path}");
+            for function in indexed {
+                assert!(every_function.contains(&function), "{function:?} in {path}");
+            }
+        }
+    }
+}
+
+#[test]
+fn functions_which_arent_in_the_tables_are_found_by_reading_every_function() {
+    // `.gdb_index` names the methods of impls by their impl (as
+    // `accelerator_fixture::{impl#0}::bump`)
+    let name = "accelerator_fixture::Counter::bump";
+    let data = fs::read(fixture(GDB_INDEX)).unwrap();
+    let object = Object::parse(&data).unwrap();
+    let found = object.indexed_functions(name, "bump", |_| {});
+    assert_eq!(found, Some(0));
+    let (site, stats) = with_scan_limits(ScanLimits::default(), || {
+        declaration_by_name_in(fixture(GDB_INDEX), name).unwrap()
+    });
+    assert!(site.is_some());
+    assert_eq!(
+        site,
+        declaration_by_name_in(fixture(UNINDEXED), name).unwrap()
+    );
+    assert!(stats.functions > 384, "{stats}");
+
+    for path in [DEBUG_NAMES, GDB_INDEX] {
+        let data = fs::read(fixture(path)).unwrap();
+        let object = Object::parse(&data).unwrap();
+        let found = object.indexed_functions("accelerator_fixture::missing", "missing", |_| {
+            panic!("Shouldn't have found a missing function")
+        });
+        assert_eq!(found, Some(0), "in {path}");
+        let site = declaration_by_name_in(fixture(path), "accelerator_fixture::missing").unwrap();
+        assert_eq!(site, None);
+    }
+}
@@ -0,0 +1,160 @@
+//! Checks that the futures of the `async` feature find the same results as
+//! the blocking functions, and that cancelling or dropping them stops their
+//! scans.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use declaration_site::{
+    declaration_by_name, declaration_by_name_async, for_functions_in_directory, spawn_scan,
+    with_file_access, with_low_allocation_scans, DeclarationIndex, FileAccess, FileMetadata,
+    ScanCancelled, ScanProgress, ScanTask,
+};
+
+#[inline(never)]
+fn async_function() -> u32 {
+    std::hint::black_box(1)
+}
+
+/// A directory of copies of this test's executable, each of which takes a
+/// while to read.
+struct SlowDirectory {
+    data: Arc<Vec<u8>>,
+    reads: Arc<AtomicUsize>,
+}
+
+impl SlowDirectory {
+    const FILES: usize = 10;
+    const READ_TIME: Duration = Duration::from_millis(200);
+}
+
+impl FileAccess for SlowDirectory {
+    fn read(&self, _: &Path) -> io::Result<Vec<u8>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(Self::READ_TIME);
+        Ok(self.data.to_vec())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        Ok(FileMetadata {
+            is_dir: path == Path::new("slow"),
+            len: self.data.len() as u64,
+        })
+    }
+
+    fn read_dir(&self, _: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok((0..Self::FILES)
+            .map(|index| Path::new("slow").join(format!("copy{index}")))
+            .collect())
+    }
+}
+
+/// Start counting the functions in a [`SlowDirectory`], which counts its reads
+/// in `reads`.
+fn count_slowly(reads: &Arc<AtomicUsize>) -> ScanTask<io::Result<usize>> {
+    let access = SlowDirectory {
+        data: Arc::new(fs::read(env::current_exe().unwrap()).unwrap()),
+        reads: reads.clone(),
+    };
+    spawn_scan(move || {
+        with_file_access(access, || {
+            let mut functions = 0;
+            for_functions_in_directory("slow", |_| functions += 1).map(|_| functions)
+        })
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dropping_the_task_stops_the_scan() {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let mut task = count_slowly(&reads);
+    let mut progress = task.progress();
+    assert!(matches!(
+        progress.next().await,
+        Some(ScanProgress::File { .. })
+    ));
+    // The task is dropped by the timeout while the first file is being read
+    assert!(tokio::time::timeout(Duration::from_millis(10), task)
+        .await
+        .is_err());
+    // The stream ends once the scan has stopped
+    while progress.next().await.is_some() {}
+    let reads = reads.load(Ordering::SeqCst);
+    assert!(
+        reads < SlowDirectory::FILES,
+        "the scan read {reads} files after it was cancelled"
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cancelled_tasks_resolve_to_an_error() {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let mut task = count_slowly(&reads);
+    let mut progress = task.progress();
+    assert!(matches!(
+        progress.next().await,
+        Some(ScanProgress::File { .. })
+    ));
+    // The functions counted before the scan stopped aren't returned
+    task.cancel();
+    assert_eq!(task.await.unwrap_err(), ScanCancelled);
+    assert!(reads.load(Ordering::SeqCst) < SlowDirectory::FILES);
+
+    // Only scans which stop as they were cancelled are incomplete, so a task
+    // which doesn't search anything keeps its result
+    let task = spawn_scan(|| 1);
+    task.cancel();
+    assert_eq!(task.await, Ok(1));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn async_scans_match_blocking_scans() {
+    let name = format!("{}::async_function", module_path!());
+    let expected = declaration_by_name(&name);
+    assert!(expected.is_some());
+    assert_eq!(declaration_by_name_async(&name).await.unwrap(), expected);
+
+    let exe = env::current_exe().unwrap();
+    let mut task = DeclarationIndex::build_async(&exe);
+    let mut progress = task.progress();
+    let index = task.await.unwrap().unwrap();
+    assert_eq!(index, DeclarationIndex::from_file(&exe).unwrap());
+    assert!(index.get(&name).is_some());
+    let mut functions_read = 0;
+    while let Some(progress) = progress.next().await {
+        if let ScanProgress::Functions { read } = progress {
+            assert!(read > functions_read);
+            functions_read = read;
+        }
+    }
+    assert!(functions_read >= index.len() / ScanProgress::FUNCTIONS_PER_REPORT);
+    async_function();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn async_scans_use_the_settings_of_the_calling_thread() {
+    let name = format!("{}::async_function", module_path!());
+    assert!(declaration_by_name_async(&name).await.unwrap().is_some());
+
+    // Every loaded library is larger than this, so is skipped
+    let task = with_low_allocation_scans(64, || declaration_by_name_async(&name));
+    assert_eq!(task.await, Ok(None));
+
+    let reads = Arc::new(AtomicUsize::new(0));
+    let access = SlowDirectory {
+        data: Arc::new(Vec::new()),
+        reads: reads.clone(),
+    };
+    let task = with_file_access(access, || declaration_by_name_async(&name));
+    assert_eq!(task.await, Ok(None));
+    assert!(reads.load(Ordering::SeqCst) > 0);
+    async_function();
+}
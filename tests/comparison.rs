@@ -0,0 +1,141 @@
+//! Checks how `compare_function_maps` classifies the differences between two
+//! builds.
+
+use std::path::Path;
+
+use declaration_site::{
+    compare_function_maps, ComparisonOptions, DeclarationIndex, DeclarationSite, MapComparison,
+};
+
+fn site(file: &str, line: u32) -> DeclarationSite {
+    DeclarationSite {
+        file: file.into(),
+        line,
+    }
+}
+
+fn index(functions: &[(&str, &str, u32)]) -> DeclarationIndex {
+    functions
+        .iter()
+        .map(|&(name, file, line)| (name.to_owned(), site(file, line)))
+        .collect()
+}
+
+fn names(differences: &[declaration_site::SiteDifference]) -> Vec<&str> {
+    differences.iter().map(|it| it.name.as_str()).collect()
+}
+
+#[test]
+fn identical_builds_are_consistent() {
+    let fixture =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/big_endian/libbig_endian_fixture.so");
+    let a = DeclarationIndex::from_file(&fixture).unwrap();
+    let b = DeclarationIndex::from_file(&fixture).unwrap();
+    let comparison = compare_function_maps(&a, &b, &ComparisonOptions::default());
+    assert!(comparison.is_consistent(), "{comparison}");
+    assert_eq!(comparison.identical, a.len());
+    assert_eq!(comparison.functions(), a.len());
+    assert_eq!(
+        comparison,
+        MapComparison {
+            identical: a.len(),
+            ..MapComparison::default()
+        }
+    );
+}
+
+#[test]
+fn remapped_prefixes_are_benign() {
+    let a = index(&[
+        ("c::remapped", "/runner-1/work/src/lib.rs", 1),
+        ("c::windows", r"C:\runner-1\work\src\lib.rs", 2),
+        ("c::relative", "src/main.rs", 3),
+        ("c::partial_component", "/runner-10/work/src/lib.rs", 4),
+        ("c::other_crate", "/runner-1/work/src/lib.rs", 5),
+    ]);
+    let b = index(&[
+        ("c::remapped", "/runner-2/work/src/lib.rs", 1),
+        ("c::windows", "/runner-2/work/src/lib.rs", 2),
+        ("c::relative", "/build/work/src/main.rs", 3),
+        ("c::partial_component", "/runner-2/work/src/lib.rs", 4),
+        ("c::other_crate", "/runner-2/work/other/src/lib.rs", 5),
+    ]);
+    let options = ComparisonOptions {
+        remap_prefixes: vec![
+            ("/runner-1/".into(), "/build".into()),
+            (r"C:\runner-1".into(), "/build".into()),
+            ("/runner-2".into(), "/build".into()),
+        ],
+        max_line_drift: 0,
+    };
+    let comparison = compare_function_maps(&a, &b, &options);
+    assert_eq!(
+        names(&comparison.path_prefix),
+        ["c::relative", "c::remapped", "c::windows"]
+    );
+    // Prefixes only replace whole components
+    assert_eq!(
+        names(&comparison.mismatched),
+        ["c::other_crate", "c::partial_component"]
+    );
+    assert!(!comparison.is_consistent());
+
+    // Without remapping, only the relative path is benign
+    let comparison = compare_function_maps(&a, &b, &ComparisonOptions::default());
+    assert_eq!(names(&comparison.path_prefix), ["c::relative"]);
+    assert_eq!(comparison.mismatched.len(), 4);
+}
+
+#[test]
+fn line_drift_is_within_the_threshold() {
+    let a = index(&[
+        ("c::up", "/a/src/lib.rs", 10),
+        ("c::down", "/a/src/lib.rs", 20),
+        ("c::far", "/a/src/lib.rs", 30),
+        ("c::moved_file", "/a/src/lib.rs", 40),
+        ("c::prefix_and_line", "/a/src/lib.rs", 50),
+    ]);
+    let b = index(&[
+        ("c::up", "/a/src/lib.rs", 7),
+        ("c::down", "/a/src/lib.rs", 23),
+        ("c::far", "/a/src/lib.rs", 34),
+        ("c::moved_file", "/a/src/other.rs", 40),
+        ("c::prefix_and_line", "/b/src/lib.rs", 51),
+    ]);
+    let options = ComparisonOptions {
+        remap_prefixes: vec![("/b".into(), "/a".into())],
+        max_line_drift: 3,
+    };
+    let comparison = compare_function_maps(&a, &b, &options);
+    assert_eq!(
+        names(&comparison.line_drift),
+        ["c::down", "c::prefix_and_line", "c::up"]
+    );
+    assert_eq!(names(&comparison.mismatched), ["c::far", "c::moved_file"]);
+    assert!(comparison.path_prefix.is_empty());
+}
+
+#[test]
+fn missing_functions_are_inconsistent() {
+    let a = index(&[("c::both", "src/lib.rs", 1), ("c::only_a", "src/lib.rs", 2)]);
+    let b = index(&[("c::both", "src/lib.rs", 1), ("c::only_b", "src/lib.rs", 3)]);
+    let comparison = compare_function_maps(&a, &b, &ComparisonOptions::default());
+    assert_eq!(comparison.identical, 1);
+    assert_eq!(
+        comparison.only_in_a,
+        [("c::only_a".into(), site("src/lib.rs", 2))]
+    );
+    assert_eq!(
+        comparison.only_in_b,
+        [("c::only_b".into(), site("src/lib.rs", 3))]
+    );
+    assert!(!comparison.is_consistent());
+    assert_eq!(comparison.functions(), 3);
+    assert_eq!(
+        comparison.to_string(),
+        "compared 3 function(s): 1 identical, 0 differing only in path prefix, \
+         0 with line drift, 0 mismatched, 1 only in the first map, 1 only in the second map\n  \
+         only in the first map: c::only_a at src/lib.rs:2\n  \
+         only in the second map: c::only_b at src/lib.rs:3"
+    );
+}
@@ -0,0 +1,66 @@
+//! Checks that `DeclarationCache` finds functions at the same sites as
+//! `declaration_by_name`, and only keeps the functions it was filtered to.
+
+use declaration_site::{declaration_by_name, DeclarationCache, DeclarationIndex};
+
+fn name_of<T>(_: &T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+#[inline(never)]
+fn cached_function() -> u32 {
+    line!()
+}
+
+mod systems {
+    #[inline(never)]
+    pub fn first_system() -> u32 {
+        line!()
+    }
+
+    #[inline(never)]
+    pub fn second_system() -> u32 {
+        line!()
+    }
+}
+
+#[test]
+fn functions_are_found_at_the_same_sites() {
+    let lines = [
+        cached_function(),
+        systems::first_system(),
+        systems::second_system(),
+    ];
+    assert!(std::hint::black_box(lines)[0] > 0);
+    let names = [
+        name_of(&cached_function),
+        name_of(&systems::first_system),
+        name_of(&systems::second_system),
+    ];
+
+    let cache = DeclarationCache::build();
+    assert!(cache.len() > 100, "{}", cache.len());
+    for name in names {
+        let site = cache.lookup(name).unwrap_or_else(|| panic!("`{name}`"));
+        assert_eq!(Some(site), declaration_by_name(name).as_ref());
+        assert!(site.file.ends_with("declaration_cache.rs"), "{site}");
+    }
+    assert_eq!(cache.lookup("declaration_cache::missing"), None);
+
+    // The cache is an index of the loaded libraries
+    assert_eq!(cache.index(), &DeclarationIndex::from_loaded_libraries());
+}
+
+#[test]
+fn filtered_caches_only_keep_the_functions_with_the_prefix() {
+    assert!(std::hint::black_box(systems::first_system()) > 0);
+    let prefix = "declaration_cache::systems::";
+    let cache = DeclarationCache::build_filtered(prefix);
+    assert!(!cache.is_empty());
+    assert!(cache.iter().all(|(name, _)| name.starts_with(prefix)));
+    assert!(cache.lookup(name_of(&systems::first_system)).is_some());
+    assert_eq!(cache.lookup(name_of(&cached_function)), None);
+    let index = DeclarationIndex::from_loaded_libraries();
+    assert!(cache.len() < index.len());
+    assert!(cache.iter().eq(index.with_prefix(prefix)));
+}
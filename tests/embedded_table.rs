@@ -0,0 +1,204 @@
+//! Checks that functions are found in a table from `export_compact_table` once
+//! it is registered, without scanning, including by the `functions` example
+//! when it is stripped with the table embedded.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use declaration_site::{
+    declaration_of, export_compact_table, register_embedded_table, with_scan_limits, CacheControl,
+    DeclarationIndex, DeclarationSearch, EmbeddedTableError, MatchRelaxation, ScanLimits,
+};
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[inline(never)]
+fn function_in_table() -> u32 {
+    std::hint::black_box(7)
+}
+
+#[test]
+fn registered_tables_are_used_before_scanning() {
+    let exe = env::current_exe().unwrap();
+    let table = export_compact_table(&exe).unwrap();
+    let index = DeclarationIndex::from_file(&exe).unwrap();
+    let expected = declaration_of(&function_in_table).expect("Should have found the function");
+
+    // Tables which weren't written by `export_compact_table` are rejected
+    let mut corrupted = table.clone();
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert_eq!(
+        register_embedded_table(&corrupted),
+        Err(EmbeddedTableError::Corrupted)
+    );
+    assert_eq!(
+        register_embedded_table(b"not a table"),
+        Err(EmbeddedTableError::Corrupted)
+    );
+    let cache = env::temp_dir().join(format!("embedded_table-{}.cache", std::process::id()));
+    DeclarationIndex::from_file_cached(&exe, &cache, CacheControl::Rebuild).unwrap();
+    let cache_file = fs::read(&cache).unwrap();
+    let _ = fs::remove_file(&cache);
+    assert_eq!(
+        register_embedded_table(&cache_file),
+        Err(EmbeddedTableError::Incompatible)
+    );
+
+    assert_eq!(register_embedded_table(&table), Ok(index.len()));
+    let (site, stats) =
+        with_scan_limits(ScanLimits::default(), || declaration_of(&function_in_table));
+    assert_eq!(site, Some(expected));
+    assert_eq!(stats.functions, 0, "{stats}");
+    function_in_table();
+}
+
+#[inline(never)]
+fn closure_in_table() -> u32 {
+    let closure = |x: u32| std::hint::black_box(x) + 1;
+    closure(8)
+}
+
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn table_entry() -> u32 {
+    std::hint::black_box(9)
+}
+
+struct TableTarget(u32);
+
+impl Default for TableTarget {
+    #[inline(never)]
+    fn default() -> Self {
+        TableTarget(std::hint::black_box(10))
+    }
+}
+
+struct Wrapper<T>(T);
+
+impl<T: Copy> Wrapper<T> {
+    #[inline(never)]
+    fn get(&self) -> T {
+        std::hint::black_box(self.0)
+    }
+}
+
+#[test]
+fn tables_match_names_as_scans_do() {
+    let exe = env::current_exe().unwrap();
+    let module = module_path!();
+    let closure = format!("{module}::closure_in_table::{{{{closure}}}}");
+    // `#[no_mangle]` functions by their path
+    let entry = format!("{module}::table_entry");
+    // Trait methods spelled `Type::method`
+    let method = format!("{module}::TableTarget::default");
+    // Generic arguments which weren't instantiated, with relaxed matching
+    let relaxed = format!("{module}::Wrapper<u64>::get");
+    let searches = [
+        DeclarationSearch::new(&closure),
+        DeclarationSearch::new(&entry),
+        DeclarationSearch::new(&method),
+        DeclarationSearch::new(&relaxed).relaxed(MatchRelaxation::Conservative),
+    ];
+    // Explaining a lookup doesn't use the tables
+    let expected = searches
+        .iter()
+        .map(|search| search.explain().and_then(|choice| choice.site.ok()))
+        .collect::<Vec<_>>();
+    assert!(expected.iter().all(Option::is_some), "{expected:?}");
+
+    register_embedded_table(&export_compact_table(&exe).unwrap()).unwrap();
+    for (search, expected) in searches.iter().zip(expected) {
+        let (site, stats) = with_scan_limits(ScanLimits::default(), || search.find());
+        assert_eq!(site, expected, "for {search:?}");
+        assert_eq!(stats.functions, 0, "for {search:?}: {stats}");
+    }
+
+    closure_in_table();
+    table_entry();
+    assert_eq!(TableTarget::default().0, 10);
+    assert_eq!(Wrapper(11_u32).get(), 11);
+}
+
+/// Build the `functions` example into `target_dir`, embedding the table at
+/// `table` if there is one, returning its path.
+fn build_example(target_dir: &Path, table: Option<&Path>) -> PathBuf {
+    let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command
+        .args([
+            "rustc",
+            "--quiet",
+            "--example",
+            "functions",
+            "--manifest-path",
+        ])
+        .arg(root().join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target_dir);
+    if let Some(table) = table {
+        command
+            .args(["--", "--cfg", "declaration_site_table"])
+            .env("DECLARATION_SITE_TABLE", table);
+    }
+    let status = command.status().expect("Should have run cargo");
+    assert!(status.success(), "building the example failed");
+    target_dir.join("debug/examples/functions")
+}
+
+/// Copy `binary` to `stripped`, and strip it.
+fn strip(binary: &Path, stripped: &Path) {
+    fs::copy(binary, stripped).unwrap();
+    let status = Command::new("strip")
+        .arg("--strip-all")
+        .arg(stripped)
+        .status()
+        .expect("Should have run strip");
+    assert!(status.success());
+    let index = DeclarationIndex::from_file(stripped).unwrap();
+    assert!(index.is_empty(), "{} functions", index.len());
+}
+
+fn run(binary: &Path) -> Output {
+    Command::new(binary)
+        .current_dir(root())
+        .output()
+        .unwrap_or_else(|e| panic!("running {} failed: {e}", binary.display()))
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn stripped_example_finds_functions_in_its_table() {
+    let target_dir = root().join("target/embedded-table");
+    let binary = build_example(&target_dir, None);
+    let table = target_dir.join("functions.declarations");
+    fs::write(&table, export_compact_table(&binary).unwrap()).unwrap();
+
+    // Without the table, the stripped example doesn't find its function, as
+    // there is no debug info
+    let stripped = target_dir.join("functions-stripped");
+    strip(&binary, &stripped);
+    let output = run(&stripped);
+    let printed = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(printed.contains("has debug info"), "{printed}");
+
+    let binary = build_example(&target_dir, Some(&table));
+    strip(&binary, &stripped);
+    let output = run(&stripped);
+    let printed = String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{printed}");
+    let source = fs::read_to_string(root().join("examples/functions.rs")).unwrap();
+    let line = source
+        .lines()
+        .position(|line| line.ends_with("// Found by this example"))
+        .unwrap()
+        + 1;
+    assert!(
+        printed.contains(&format!("functions.rs:{line}")),
+        "{printed}"
+    );
+}
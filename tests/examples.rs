@@ -0,0 +1,126 @@
+//! Builds and runs every example, checking that each succeeds and prints the
+//! declaration sites marked in its source.
+//!
+//! A line of an example ending with [`MARKER`] must be printed (as
+//! `file.rs:line`) by that example. This is how most examples show that they
+//! found a function at its declaration site, so a broken example fails here
+//! rather than after it is shipped.
+//!
+//! The examples are built with every feature, into `target/examples-harness`.
+//! Dependencies are optimized, as some examples scan large binaries many times,
+//! but the examples keep the debug info they look themselves up in.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// The comment marking a line whose declaration site an example prints.
+const MARKER: &str = "// Found by this example";
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// An example, as discovered by cargo.
+struct Example {
+    name: String,
+    /// The files of the example: either a single file, or every file in its
+    /// directory
+    sources: Vec<PathBuf>,
+}
+
+fn examples() -> Vec<Example> {
+    let mut examples = Vec::new();
+    for entry in fs::read_dir(root().join("examples")).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|it| it == "rs") {
+            examples.push(Example {
+                name: path.file_stem().unwrap().to_string_lossy().into_owned(),
+                sources: vec![path],
+            });
+        } else if path.join("main.rs").is_file() {
+            examples.push(Example {
+                name: path.file_name().unwrap().to_string_lossy().into_owned(),
+                sources: fs::read_dir(&path)
+                    .unwrap()
+                    .map(|it| it.unwrap().path())
+                    .collect(),
+            });
+        }
+    }
+    examples.sort_by(|a, b| a.name.cmp(&b.name));
+    examples
+}
+
+/// The sites marked in `sources`, as `file.rs:line`.
+fn marked_sites(sources: &[PathBuf]) -> Vec<String> {
+    let mut sites = Vec::new();
+    for source in sources {
+        let file_name = source.file_name().unwrap().to_string_lossy();
+        let text = fs::read_to_string(source).unwrap();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim_end().ends_with(MARKER) {
+                sites.push(format!("{file_name}:{}", index + 1));
+            }
+        }
+    }
+    sites
+}
+
+/// Build every example, returning the directory containing them.
+fn build() -> PathBuf {
+    let target_dir = root().join("target/examples-harness");
+    let status = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(["build", "--quiet", "--examples", "--all-features"])
+        .arg("--manifest-path")
+        .arg(root().join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .args(["--config", "profile.dev.package.\"*\".opt-level=2"])
+        .status()
+        .expect("Should have run cargo");
+    assert!(status.success(), "building the examples failed");
+    target_dir.join("debug/examples")
+}
+
+/// Run `example`, returning why it failed, if it did.
+fn check(directory: &Path, example: &Example) -> Vec<String> {
+    let executable = directory.join(format!("{}{}", example.name, env::consts::EXE_SUFFIX));
+    let output = Command::new(&executable)
+        .current_dir(root())
+        .stdin(Stdio::null())
+        .output()
+        .unwrap_or_else(|e| panic!("running {} failed: {e}", executable.display()));
+    let printed = String::from_utf8_lossy(&output.stdout) + String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return vec![format!(
+            "`{}` exited with {}:\n{printed}",
+            example.name, output.status
+        )];
+    }
+    marked_sites(&example.sources)
+        .into_iter()
+        .filter(|site| !printed.contains(site))
+        .map(|site| format!("`{}` didn't print {site}:\n{printed}", example.name))
+        .collect()
+}
+
+#[test]
+fn examples_run_and_print_their_sites() {
+    let directory = build();
+    let examples = examples();
+    // Some examples take tens of seconds, so they are run in parallel
+    let failures = std::thread::scope(|scope| {
+        let checks = examples
+            .iter()
+            .map(|example| scope.spawn(|| check(&directory, example)))
+            .collect::<Vec<_>>();
+        checks
+            .into_iter()
+            .flat_map(|it| it.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
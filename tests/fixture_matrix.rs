@@ -0,0 +1,142 @@
+//! Builds `fixtures/matrix` with several profiles, and checks that each of
+//! its functions is found in each build at the site it recorded.
+//!
+//! The fixture is built with the `cargo` running this test, into a target
+//! directory for each profile inside `fixtures/matrix/target`, so the first
+//! run takes a few seconds per profile.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use declaration_site::declarations_by_name_in;
+
+/// The lines a function may be found at before or after the line it recorded,
+/// as its signature is usually the line before its first statement.
+const TOLERANCE: u32 = 1;
+
+/// A profile to build the fixture with, as `CARGO_PROFILE_DEV_*` variables.
+struct Profile {
+    name: &'static str,
+    settings: &'static [(&'static str, &'static str)],
+    /// Whether the debug info produced can be read on this platform
+    supported: bool,
+}
+
+const PROFILES: &[Profile] = &[
+    Profile {
+        name: "debug",
+        settings: &[],
+        supported: true,
+    },
+    Profile {
+        name: "opt-level-1",
+        settings: &[("OPT_LEVEL", "1")],
+        supported: true,
+    },
+    Profile {
+        name: "codegen-units-16",
+        settings: &[("CODEGEN_UNITS", "16"), ("INCREMENTAL", "false")],
+        supported: true,
+    },
+    // DWARF packages (`.dwp`), as produced on Linux, can't be read
+    Profile {
+        name: "split-debuginfo",
+        settings: &[("SPLIT_DEBUGINFO", "packed")],
+        supported: cfg!(any(target_os = "macos", windows)),
+    },
+];
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/matrix")
+}
+
+/// Build the fixture with `profile`, returning the path of the executable.
+fn build(profile: &Profile) -> PathBuf {
+    let target_dir = fixture_dir().join("target").join(profile.name);
+    let mut command = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command
+        .arg("build")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(fixture_dir().join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .env("CARGO_PROFILE_DEV_DEBUG", "true");
+    // Without this, the debug info of macOS builds stays in the object files
+    if cfg!(target_os = "macos") {
+        command.env("CARGO_PROFILE_DEV_SPLIT_DEBUGINFO", "packed");
+    }
+    for (key, value) in profile.settings {
+        command.env(format!("CARGO_PROFILE_DEV_{key}"), value);
+    }
+    let status = command.status().expect("Should have run cargo");
+    assert!(status.success(), "building with {} failed", profile.name);
+    target_dir
+        .join("debug")
+        .join(format!("matrix_fixture{}", env::consts::EXE_SUFFIX))
+}
+
+/// The file containing the debug info of `executable`.
+fn debug_file(executable: &Path) -> PathBuf {
+    if cfg!(windows) {
+        executable.with_extension("pdb")
+    } else if cfg!(target_os = "macos") {
+        let mut dsym = executable.as_os_str().to_owned();
+        dsym.push(".dSYM/Contents/Resources/DWARF/matrix_fixture");
+        dsym.into()
+    } else {
+        executable.to_owned()
+    }
+}
+
+/// The name, file and line of each function recorded by the fixture.
+fn recorded_sites(executable: &Path) -> Vec<(String, String, u32)> {
+    let output = Command::new(executable)
+        .output()
+        .expect("Should have run the fixture");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let mut parts = line.split('\t');
+            let mut next = || parts.next().expect("Should have three fields").to_owned();
+            (next(), next(), next().parse().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn functions_are_found_in_each_profile() {
+    let mut failures = Vec::new();
+    for profile in PROFILES {
+        if !profile.supported {
+            eprintln!("skipping {}, as it isn't supported here", profile.name);
+            continue;
+        }
+        let executable = build(profile);
+        let recorded = recorded_sites(&executable);
+        assert!(!recorded.is_empty());
+        let names = recorded
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let found = declarations_by_name_in(debug_file(&executable), &names)
+            .unwrap_or_else(|e| panic!("reading the {} build failed: {e}", profile.name));
+        for ((name, file, line), site) in recorded.iter().zip(found) {
+            let matches = site.as_ref().is_some_and(|site| {
+                Path::new(&site.file).ends_with(file) && site.line.abs_diff(*line) <= TOLERANCE
+            });
+            if !matches {
+                failures.push(format!(
+                    "{}: `{name}` recorded at {file}:{line}, found at {site:?}",
+                    profile.name
+                ));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
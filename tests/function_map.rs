@@ -0,0 +1,108 @@
+//! Checks the fields of each row written by `export_function_map`, in both
+//! formats, for a library with known functions.
+
+use std::{collections::BTreeSet, fs};
+
+use declaration_site::{export_function_map, ExportFormat, ScanTarget};
+use serde_json::Value;
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/fixtures/big_endian/libbig_endian_fixture.so"
+);
+
+fn export(target: ScanTarget<'_>, format: ExportFormat) -> String {
+    let mut output = Vec::new();
+    export_function_map(target, &mut output, format).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+/// Split a CSV line into its fields, unquoting quoted fields.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(char),
+        }
+    }
+    assert!(!quoted, "Unterminated quote in {line}");
+    fields
+}
+
+#[test]
+fn json_lines_rows() {
+    let output = export(ScanTarget::File(FIXTURE.as_ref()), ExportFormat::JsonLines);
+    assert!(output.ends_with('\n'));
+    let rows = output
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(!rows.is_empty());
+    let expected_keys = ["address", "file", "line", "name", "schema_version", "size"];
+    for row in &rows {
+        let object = row.as_object().unwrap();
+        let keys = object.keys().map(String::as_str).collect::<BTreeSet<_>>();
+        assert_eq!(keys, BTreeSet::from(expected_keys), "{row}");
+        assert!(row["name"].is_string(), "{row}");
+        assert!(row["address"].is_u64() && row["size"].is_u64(), "{row}");
+        assert_eq!(row["schema_version"], 1, "{row}");
+        // A function has both a file and a line, or neither
+        assert!(
+            (row["file"].is_string() && row["line"].is_u64())
+                || (row["file"].is_null() && row["line"].is_null()),
+            "{row}"
+        );
+    }
+
+    let first = rows
+        .iter()
+        .find(|row| row["name"] == "big_endian_fixture::first")
+        .expect("Should have exported `first`");
+    assert!(first["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("big_endian_fixture.rs"));
+    assert_eq!(first["line"], 27);
+    assert!(first["size"].as_u64().unwrap() > 0);
+
+    // The same rows are written for the contents of the file
+    let data = fs::read(FIXTURE).unwrap();
+    assert_eq!(
+        export(ScanTarget::Bytes(&data), ExportFormat::JsonLines),
+        output
+    );
+}
+
+#[test]
+fn csv_rows_match_json_lines() {
+    let csv = export(ScanTarget::File(FIXTURE.as_ref()), ExportFormat::Csv);
+    let json = export(ScanTarget::File(FIXTURE.as_ref()), ExportFormat::JsonLines);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("name,file,line,address,size,schema_version")
+    );
+    let mut count = 0;
+    for (line, row) in lines.zip(json.lines()) {
+        let row = serde_json::from_str::<Value>(row).unwrap();
+        let fields = csv_fields(line);
+        assert_eq!(fields.len(), 6, "{line}");
+        assert_eq!(fields[0], row["name"].as_str().unwrap());
+        assert_eq!(fields[1], row["file"].as_str().unwrap_or_default());
+        let line_number = row["line"].as_u64().map(|it| it.to_string());
+        assert_eq!(fields[2], line_number.unwrap_or_default());
+        assert_eq!(fields[3], row["address"].to_string());
+        assert_eq!(fields[4], row["size"].to_string());
+        assert_eq!(fields[5], "1");
+        count += 1;
+    }
+    assert_eq!(count, json.lines().count());
+}
@@ -0,0 +1,70 @@
+//! Looking up a function of this test's binary by its clean name and by its
+//! name with the legacy `::h<hash>` suffix, as left by the demangler when
+//! demangling partially fails, finds the same declaration site.
+
+use std::env;
+
+use declaration_site::{
+    declaration_by_name, declaration_by_name_in, declarations_by_name, declarations_by_name_in,
+    for_functions_in_file, DeclarationIndex,
+};
+
+const NAME: &str = "hash_suffix::hashed_function";
+
+#[inline(never)]
+fn hashed_function() -> u32 {
+    std::hint::black_box(1)
+}
+
+/// The name of `hashed_function` with the hash of its legacy mangled symbol,
+/// such as `hash_suffix::hashed_function::h0123456789abcdef`.
+fn suffixed_name() -> String {
+    let exe = env::current_exe().unwrap();
+    let mut raw_names = Vec::new();
+    for_functions_in_file(&exe, |function| {
+        if function.name() == NAME {
+            raw_names.push(function.raw_name().to_owned());
+        }
+    })
+    .unwrap();
+    let hash = raw_names
+        .iter()
+        .find_map(|raw| {
+            let hash = raw.strip_suffix('E')?.rsplit_once("17h")?.1;
+            (hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+        })
+        .unwrap_or_else(|| panic!("Should have found a legacy symbol in {raw_names:?}"));
+    format!("{NAME}::h{hash}")
+}
+
+#[test]
+fn suffixed_names_find_the_same_site() {
+    assert_eq!(hashed_function(), 1);
+    let exe = env::current_exe().unwrap();
+    let suffixed = suffixed_name();
+
+    let clean = declaration_by_name_in(&exe, NAME)
+        .unwrap()
+        .expect("Should have found `hashed_function`");
+    assert!(clean.file.ends_with("hash_suffix.rs"), "{clean}");
+    assert_eq!(clean.line, 15);
+    assert_eq!(
+        declaration_by_name_in(&exe, &suffixed).unwrap().as_ref(),
+        Some(&clean)
+    );
+    assert_eq!(
+        declarations_by_name_in(&exe, &[NAME, &suffixed]).unwrap(),
+        [Some(clean.clone()), Some(clean.clone())]
+    );
+
+    let index = DeclarationIndex::from_file(&exe).unwrap();
+    assert_eq!(index.get(NAME), Some(&clean));
+    assert_eq!(index.get(&suffixed), Some(&clean));
+
+    assert_eq!(declaration_by_name(NAME).as_ref(), Some(&clean));
+    assert_eq!(declaration_by_name(&suffixed).as_ref(), Some(&clean));
+    assert_eq!(
+        declarations_by_name(&[NAME, &suffixed]),
+        [Some(clean.clone()), Some(clean)]
+    );
+}
@@ -0,0 +1,88 @@
+//! Checks that the library of this test's own executable can be searched as a
+//! loaded library, and that other files can't.
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use declaration_site::{
+    for_functions_in_loaded_library, index_library, with_file_access, FileAccess, FileMetadata,
+    LoadedLibraryError, StdFileAccess,
+};
+
+#[inline(never)]
+fn loaded_function() -> u32 {
+    std::hint::black_box(1)
+}
+
+/// Reads files from disk, recording the paths it was asked to canonicalize.
+struct RecordingAccess {
+    canonicalized: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl FileAccess for RecordingAccess {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        StdFileAccess.read(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        StdFileAccess.metadata(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        StdFileAccess.read_dir(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.canonicalized.lock().unwrap().push(path.to_owned());
+        StdFileAccess.canonicalize(path)
+    }
+}
+
+#[test]
+fn the_executable_is_indexed_as_a_loaded_library() {
+    let exe = env::current_exe().unwrap();
+    let index = index_library(&exe).unwrap();
+    let name = format!("{}::loaded_function", module_path!());
+    let site = index
+        .get(&name)
+        .expect("Should have found `loaded_function`");
+    assert!(site.file.ends_with("loaded_library.rs"), "{site}");
+    assert_eq!(site.line, 16);
+
+    let mut functions = 0;
+    for_functions_in_loaded_library(&exe, |_| functions += 1).unwrap();
+    // The index only has one site for each name
+    assert!(functions >= index.len(), "{functions} < {}", index.len());
+    loaded_function();
+}
+
+#[test]
+fn paths_are_canonicalized_through_the_file_access() {
+    let exe = env::current_exe().unwrap();
+    let canonicalized = Arc::new(Mutex::new(Vec::new()));
+    let access = RecordingAccess {
+        canonicalized: canonicalized.clone(),
+    };
+    with_file_access(access, || index_library(&exe)).unwrap();
+    let canonicalized = canonicalized.lock().unwrap();
+    assert!(canonicalized.contains(&exe), "{canonicalized:?}");
+    // The loaded libraries are compared until the executable is found
+    assert!(canonicalized.len() > 1, "{canonicalized:?}");
+}
+
+#[test]
+fn files_which_arent_loaded_are_rejected() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures/accelerator/libaccelerator_fixture.so");
+    assert!(matches!(
+        index_library(fixture),
+        Err(LoadedLibraryError::NotLoaded)
+    ));
+    assert!(matches!(
+        index_library("does/not/exist"),
+        Err(LoadedLibraryError::Io(error)) if error.kind() == io::ErrorKind::NotFound
+    ));
+}
@@ -0,0 +1,102 @@
+//! Builds `declaration_site_core` without its default features, checking that
+//! each `no_std` configuration builds and doesn't enable `std` in its
+//! dependencies.
+//!
+//! The crate is built with the `cargo` running this test, into
+//! `target/no-std`. If a target without `std` is installed (such as with
+//! `rustup target add thumbv7em-none-eabihf`), the crate is also built for it,
+//! which fails if anything it uses needs `std`.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// The feature sets `declaration_site_core` is built with, after
+/// `--no-default-features`.
+const FEATURES: &[&str] = &["", "alloc", "serde", "alloc,serde"];
+
+/// Targets without `std`, which the crate is built for if installed.
+const NO_STD_TARGETS: &[&str] = &[
+    "thumbv7em-none-eabihf",
+    "thumbv6m-none-eabi",
+    "riscv32imac-unknown-none-elf",
+];
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn cargo(args: &[&str], features: &str) -> Output {
+    let output = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(args)
+        .args([
+            "--package",
+            "declaration_site_core",
+            "--no-default-features",
+        ])
+        .args(["--features", features])
+        .arg("--manifest-path")
+        .arg(root().join("Cargo.toml"))
+        .output()
+        .expect("Should have run cargo");
+    assert!(
+        output.status.success(),
+        "`cargo {}` with features `{features}` failed:\n{}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// The installed target without `std`, if any.
+fn installed_no_std_target() -> Option<&'static str> {
+    NO_STD_TARGETS.iter().copied().find(|target| {
+        let Ok(output) = Command::new("rustc")
+            .args(["--print", "target-libdir", "--target", target])
+            .output()
+        else {
+            return false;
+        };
+        let directory = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        output.status.success() && directory.is_dir()
+    })
+}
+
+#[test]
+fn core_builds_without_std() {
+    let target_dir = root().join("target/no-std");
+    let target_dir = target_dir.to_str().unwrap();
+    for features in FEATURES {
+        cargo(&["build", "--quiet", "--target-dir", target_dir], features);
+        // Nothing else in the build should pull in `std`
+        let tree = cargo(&["tree", "--edges", "normal,features"], features);
+        let tree = String::from_utf8_lossy(&tree.stdout);
+        assert!(
+            !tree.contains("feature \"std\""),
+            "`std` is enabled with features `{features}`:\n{tree}"
+        );
+    }
+
+    let Some(target) = installed_no_std_target() else {
+        eprintln!(
+            "No target without std is installed, so declaration_site_core was only built for \
+             the host. Install one of {NO_STD_TARGETS:?} to build for it"
+        );
+        return;
+    };
+    for features in FEATURES {
+        cargo(
+            &[
+                "build",
+                "--quiet",
+                "--target-dir",
+                target_dir,
+                "--target",
+                target,
+            ],
+            features,
+        );
+    }
+}
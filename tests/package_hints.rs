@@ -0,0 +1,57 @@
+//! Checks that the functions of this crate are found in its package, and
+//! only when looking them up in it. See `fixtures/packages` for crates with
+//! the same name from different packages.
+
+use std::{any::type_name_of_val, path::Path};
+
+use declaration_site::{
+    declaration_by_name, explain_declaration_by_name, DeclarationSearch, PathPreference,
+};
+
+#[test]
+fn library_functions_are_in_their_package() {
+    let root = env!("CARGO_MANIFEST_DIR");
+    let name = type_name_of_val(&declaration_by_name);
+    let choice = explain_declaration_by_name(name).expect("Should have found the function");
+    let hint = choice
+        .package_hint
+        .as_deref()
+        .expect("Should have a package");
+    assert_eq!(Path::new(hint), Path::new(root), "{choice}");
+
+    let site = declaration_by_name(name);
+    assert!(site.is_some());
+    let package = Path::new(root).file_name().unwrap().to_str().unwrap();
+    for package in [root, package] {
+        let search = DeclarationSearch::new(name).package_hint(package);
+        assert_eq!(search.find(), site, "in {package}");
+        // Package hints combine with the other options
+        let search = search.path_preference(PathPreference::FirstFound);
+        assert_eq!(search.find(), site, "in {package}");
+    }
+    assert_eq!(
+        DeclarationSearch::new(name)
+            .package_hint("not_a_package")
+            .find(),
+        None
+    );
+}
+
+#[test]
+fn dependencies_are_in_their_package() {
+    // A function of a registry dependency, which is named by its package
+    let name = "rustc_demangle::demangle";
+    let choice = explain_declaration_by_name(name).expect("Should have found the function");
+    assert_eq!(
+        choice.package_hint.as_deref(),
+        Some("rustc-demangle"),
+        "{choice}"
+    );
+    let search = DeclarationSearch::new(name);
+    assert!(search
+        .clone()
+        .package_hint("rustc-demangle")
+        .find()
+        .is_some());
+    assert_eq!(search.package_hint("rustc_demangle").find(), None);
+}
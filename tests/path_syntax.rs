@@ -0,0 +1,50 @@
+//! Checks that the names of real functions, as printed by `type_name` and by
+//! the demanglers, are accepted by `validate_function_path`, and that
+//! validated paths can be looked up.
+
+use declaration_site::{declaration_by_name, validate_function_path, DeclarationIndex};
+
+fn name_of<T>(_: &T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+fn generic<T: ?Sized>() -> &'static str {
+    std::any::type_name::<T>()
+}
+
+#[test]
+fn every_indexed_name_is_valid() {
+    let index = DeclarationIndex::from_file(std::env::current_exe().unwrap()).unwrap();
+    assert!(index.len() > 100, "{}", index.len());
+    let invalid = index
+        .iter()
+        // Unmangled functions, such as those from C, have no crate
+        .filter(|(name, _)| name.contains("::"))
+        .filter_map(|(name, _)| {
+            let error = validate_function_path(name).err()?;
+            Some(format!("`{name}`: {error}"))
+        })
+        .collect::<Vec<_>>();
+    assert!(invalid.is_empty(), "{invalid:#?}");
+}
+
+#[test]
+fn type_names_are_valid_and_found() {
+    let closure = |x: u8| x;
+    let names = [
+        name_of(&generic::<dyn Fn(&str) -> Option<u8> + Send>),
+        name_of(&generic::<[(u8, &'static str); 4]>),
+        name_of(&every_indexed_name_is_valid),
+        name_of(&closure),
+    ];
+    assert_eq!(closure(1), 1);
+    let arguments = [
+        generic::<dyn Fn(&str) -> Option<u8> + Send>(),
+        generic::<[(u8, &'static str); 4]>(),
+    ];
+    assert!(!std::hint::black_box(arguments)[0].is_empty());
+    for name in names {
+        let path = validate_function_path(name).unwrap_or_else(|error| panic!("`{name}`: {error}"));
+        assert!(declaration_by_name(&path).is_some(), "`{path}`");
+    }
+}
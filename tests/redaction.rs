@@ -0,0 +1,139 @@
+//! Checks that no path of the build machine is left in any output once
+//! redaction is set, by looking up this test's own functions and writing each
+//! kind of output which has paths.
+//!
+//! Redaction is set for the whole process, so this is a single test in its own
+//! binary.
+
+use std::{any::type_name_of_val, backtrace::Backtrace, env, path::Path};
+
+use declaration_site::{
+    annotate_backtrace, capability_probe, declaration_of, declaration_of_fn_ptr,
+    duplication_report, explain_declaration_by_name, explain_declaration_by_name_all,
+    export_function_map, file_map, module_tree,
+    schema::{DuplicationDocument, IndexDocument, LookupResult},
+    set_redaction, DeclarationIndex, ExportFormat, Redaction, RedactionRule, ScanTarget,
+};
+
+#[inline(never)]
+fn redacted_function() -> u32 {
+    std::hint::black_box(1)
+}
+
+/// Each `(what, output)` pair, with `output` checked for unredacted paths.
+fn outputs() -> Vec<(&'static str, String)> {
+    let exe = env::current_exe().unwrap();
+    let name = format!("{}::redacted_function", module_path!());
+    let site = declaration_of(&redacted_function).expect("Should have found this test's function");
+    let index = DeclarationIndex::from_file(&exe).unwrap();
+    let mut json_lines = Vec::new();
+    export_function_map(
+        ScanTarget::File(&exe),
+        &mut json_lines,
+        ExportFormat::JsonLines,
+    )
+    .unwrap();
+    let mut csv = Vec::new();
+    export_function_map(ScanTarget::File(&exe), &mut csv, ExportFormat::Csv).unwrap();
+    let json = |value: &dyn erased::Serialize| value.to_json();
+    vec![
+        ("declaration_of", site.to_string()),
+        (
+            "explain_declaration_by_name",
+            format!("{:?}", explain_declaration_by_name(&name)),
+        ),
+        (
+            "explain_declaration_by_name_all",
+            format!("{:?}", explain_declaration_by_name_all(&name)),
+        ),
+        (
+            "explain_declaration_by_name (library)",
+            format!(
+                "{:?}",
+                explain_declaration_by_name(type_name_of_val(&set_redaction))
+            ),
+        ),
+        (
+            "declaration_of_fn_ptr",
+            format!(
+                "{:?}",
+                declaration_of_fn_ptr(redacted_function as fn() -> u32 as usize)
+            ),
+        ),
+        ("LookupResult", json(&LookupResult::new(&name, Some(&site)))),
+        ("IndexDocument", json(&IndexDocument::from(&index))),
+        (
+            "export_function_map (JSON lines)",
+            String::from_utf8(json_lines).unwrap(),
+        ),
+        ("export_function_map (CSV)", String::from_utf8(csv).unwrap()),
+        (
+            "file_map",
+            format!("{:?}", file_map(ScanTarget::File(&exe)).unwrap()),
+        ),
+        (
+            "module_tree",
+            json(&module_tree(ScanTarget::File(&exe)).unwrap()),
+        ),
+        (
+            "duplication_report",
+            json(&DuplicationDocument::from(
+                &duplication_report(ScanTarget::File(&exe), 5).unwrap(),
+            )),
+        ),
+        (
+            "annotate_backtrace",
+            annotate_backtrace(&Backtrace::force_capture()).to_string(),
+        ),
+        ("capability_probe", format!("{:?}", capability_probe())),
+    ]
+}
+
+/// Serializing the outputs of different types in one list.
+mod erased {
+    pub trait Serialize {
+        fn to_json(&self) -> String;
+    }
+
+    impl<T: serde::Serialize> Serialize for T {
+        fn to_json(&self) -> String {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+#[test]
+fn no_unredacted_paths() {
+    set_redaction(Some(Redaction::new(vec![
+        RedactionRule::HomeDirectory,
+        RedactionRule::KeepFrom("tests".into()),
+        RedactionRule::HashDirectories,
+    ])));
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut leaks = vec![root.to_string_lossy().into_owned()];
+    // The home directory is only a leak if it is more than the root
+    leaks.extend(
+        env::var("HOME")
+            .ok()
+            .filter(|home| Path::new(home).components().count() > 1),
+    );
+
+    for (what, output) in outputs() {
+        assert!(!output.is_empty(), "{what} has no output");
+        for leak in &leaks {
+            assert!(
+                !output.contains(leak.as_str()),
+                "{what} contains the unredacted path {leak}:\n{output}"
+            );
+        }
+    }
+    // This file is kept from `tests` onwards, then its directory is hashed
+    let site = declaration_of(&redacted_function).unwrap();
+    let (directory, file) = site.file.split_once('/').unwrap();
+    assert_eq!(file, "redaction.rs");
+    assert!(directory.starts_with('#'), "{site}");
+    assert_eq!(
+        Redaction::new(vec![RedactionRule::HashDirectories]).redact("tests/redaction.rs"),
+        site.file
+    );
+}
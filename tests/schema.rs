@@ -0,0 +1,163 @@
+//! Checks that each kind of output in `declaration_site::schema` is written
+//! as in the golden files in `fixtures/schema/`, and can be read back.
+//!
+//! If a format is changed on purpose, set `BLESS=1` to rewrite the golden
+//! files, and increment `SCHEMA_VERSION` if the change isn't only additions.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use declaration_site::{
+    compare_function_maps, duplication_report, export_function_map,
+    schema::{
+        ComparisonDocument, DuplicationDocument, FunctionMapRow, IndexDocument, SCHEMA_VERSION,
+    },
+    ComparisonOptions, DeclarationIndex, DeclarationSite, DuplicationReport, ExportFormat,
+    ScanTarget,
+};
+
+const FIXTURE: &str = "fixtures/big_endian/libbig_endian_fixture.so";
+
+/// Compare `actual` to the golden file `name`, or rewrite it if blessing.
+fn check_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures/schema")
+        .join(name);
+    if env::var_os("BLESS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        actual, expected,
+        "{name} has changed, set `BLESS=1` to update it"
+    );
+}
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE)
+}
+
+fn export(format: ExportFormat) -> String {
+    let mut output = Vec::new();
+    export_function_map(ScanTarget::File(&fixture()), &mut output, format).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn function_map_json_lines() {
+    let output = export(ExportFormat::JsonLines);
+    check_golden("function_map.jsonl", &output);
+    for line in output.lines() {
+        let row: FunctionMapRow = serde_json::from_str(line).unwrap();
+        assert_eq!(row.schema_version, SCHEMA_VERSION);
+        assert_eq!(serde_json::to_string(&row).unwrap(), line);
+    }
+}
+
+#[test]
+fn function_map_csv() {
+    let output = export(ExportFormat::Csv);
+    check_golden("function_map.csv", &output);
+    let mut lines = output.lines();
+    assert_eq!(
+        lines.next(),
+        Some("name,file,line,address,size,schema_version")
+    );
+    for line in lines {
+        assert!(line.ends_with(&format!(",{SCHEMA_VERSION}")), "{line}");
+    }
+}
+
+#[test]
+fn index_document() {
+    let index = DeclarationIndex::from_file(fixture()).unwrap();
+    assert!(!index.is_empty());
+    let json = serde_json::to_string_pretty(&IndexDocument::from(&index)).unwrap() + "\n";
+    check_golden("index.json", &json);
+    let document: IndexDocument = serde_json::from_str(&json).unwrap();
+    assert_eq!(document.schema_version, SCHEMA_VERSION);
+    assert_eq!(DeclarationIndex::from(document), index);
+}
+
+#[test]
+fn duplication_document() {
+    let report = duplication_report(ScanTarget::File(&fixture()), 5).unwrap();
+    let json = serde_json::to_string_pretty(&DuplicationDocument::from(&report)).unwrap() + "\n";
+    check_golden("duplication.json", &json);
+    let document: DuplicationDocument = serde_json::from_str(&json).unwrap();
+    assert_eq!(document.schema_version, SCHEMA_VERSION);
+    assert_eq!(DuplicationReport::from(document), report);
+}
+
+#[test]
+fn comparison_document() {
+    let index = DeclarationIndex::from_file(fixture()).unwrap();
+    // The exported function map has the same declaration sites as the index
+    let rows = export(ExportFormat::JsonLines)
+        .lines()
+        .map(|line| serde_json::from_str::<FunctionMapRow>(line).unwrap())
+        .collect::<DeclarationIndex>();
+    let comparison = compare_function_maps(&index, &rows, &ComparisonOptions::default());
+    assert!(comparison.is_consistent(), "{comparison}");
+
+    // Add functions to both maps, so there is one of each kind of difference
+    let file = "fixtures/big_endian/big_endian_fixture.rs";
+    let site = |line| DeclarationSite {
+        file: file.into(),
+        line,
+    };
+    let mut functions = index
+        .iter()
+        .map(|(name, site)| (name.to_owned(), site.clone()))
+        .collect::<Vec<_>>();
+    functions.push(("big_endian_fixture::drifted".into(), site(40)));
+    functions.push(("big_endian_fixture::removed".into(), site(50)));
+    let original = functions.iter().cloned().collect::<DeclarationIndex>();
+    functions[0].1.file = format!("/other/{file}");
+    functions[1].1.line += 10;
+    functions[2].1.line += 1;
+    functions[3] = ("big_endian_fixture::added".into(), site(60));
+    let changed = functions.into_iter().collect::<DeclarationIndex>();
+    let options = ComparisonOptions {
+        remap_prefixes: vec![("/other".into(), "".into())],
+        max_line_drift: 1,
+    };
+    let comparison = compare_function_maps(&original, &changed, &options);
+    let json = serde_json::to_string_pretty(&ComparisonDocument::from(&comparison)).unwrap() + "\n";
+    check_golden("comparison.json", &json);
+    let document: ComparisonDocument = serde_json::from_str(&json).unwrap();
+    assert_eq!(document.schema_version, SCHEMA_VERSION);
+    assert!(!document.consistent);
+    assert_eq!(
+        (
+            document.path_prefix.len(),
+            document.line_drift.len(),
+            document.mismatched.len(),
+            document.only_in_a.len(),
+            document.only_in_b.len(),
+        ),
+        (1, 1, 1, 1, 1)
+    );
+}
+
+#[test]
+#[cfg(feature = "cli")]
+fn cli_json() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_declaration-site"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--json", "--prefix", "big_endian_fixture::", FIXTURE])
+        .args(["big_endian_fixture::missing"])
+        .output()
+        .unwrap();
+    // Exits with 1, as a name wasn't found
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    check_golden("cli.json", &stdout);
+    let results: Vec<declaration_site::schema::LookupResult> =
+        serde_json::from_str(&stdout).unwrap();
+    assert!(results.iter().all(|it| it.schema_version == SCHEMA_VERSION));
+    assert_eq!(serde_json::to_string(&results).unwrap(), stdout.trim_end());
+}
@@ -0,0 +1,243 @@
+//! Checks that snippets of source files are read with the right line
+//! numbers whatever their line endings and encoding, and that only as much
+//! of a file as is needed (within the budget) is read.
+
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use declaration_site::{
+    read_snippet, with_file_access, DeclarationSite, EmbeddedSources, FileAccess, FileMetadata,
+    SnippetOptions, SourceEncoding, SourceSnippet,
+};
+use symbolic_debuginfo::sourcebundle::{SourceBundleWriter, SourceFileInfo};
+
+/// Files in memory, which records the ranges read from them, and can only be
+/// read in ranges.
+struct RangeFiles {
+    files: HashMap<PathBuf, Vec<u8>>,
+    ranges: Arc<Mutex<Vec<Range<u64>>>>,
+}
+
+impl FileAccess for RangeFiles {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        panic!("Should have only read ranges of {}", path.display())
+    }
+
+    fn metadata(&self, _: &Path) -> io::Result<FileMetadata> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn read_dir(&self, _: &Path) -> io::Result<Vec<PathBuf>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn read_range(&self, path: &Path, range: Range<u64>) -> io::Result<Vec<u8>> {
+        let data = self.files.get(path).ok_or(io::ErrorKind::NotFound)?;
+        self.ranges.lock().unwrap().push(range.clone());
+        let clamp = |offset: u64| (offset as usize).min(data.len());
+        Ok(data[clamp(range.start)..clamp(range.end)].to_vec())
+    }
+}
+
+/// The snippet around `line` of a file with contents `source`, and the
+/// ranges of it which were read.
+fn snippet_of(
+    source: &[u8],
+    line: u32,
+    options: SnippetOptions,
+) -> (io::Result<SourceSnippet>, Vec<Range<u64>>) {
+    let ranges = Arc::new(Mutex::new(Vec::new()));
+    let access = RangeFiles {
+        files: HashMap::from([("src/lib.rs".into(), source.to_vec())]),
+        ranges: ranges.clone(),
+    };
+    let site = DeclarationSite {
+        file: "src/lib.rs".into(),
+        line,
+    };
+    let snippet = with_file_access(access, || read_snippet(&site, options));
+    let ranges = ranges.lock().unwrap().clone();
+    (snippet, ranges)
+}
+
+fn context(context_lines: u32) -> SnippetOptions {
+    SnippetOptions {
+        context_lines,
+        ..SnippetOptions::default()
+    }
+}
+
+#[test]
+fn crlf_line_endings_are_removed() {
+    let source = b"//! A crate\r\n\r\nfn first() {}\r\nfn second() {}\r\nfn third() {}";
+    let (snippet, _) = snippet_of(source, 4, context(1));
+    let snippet = snippet.unwrap();
+    assert_eq!(snippet.first_line, 3);
+    assert_eq!(
+        snippet.lines,
+        ["fn first() {}", "fn second() {}", "fn third() {}"]
+    );
+    assert_eq!(snippet.encoding, SourceEncoding::Utf8);
+    assert!(!snippet.lossy);
+    assert!(!snippet.truncated);
+
+    // The same lines with `\n` line endings
+    let unix = String::from_utf8(source.to_vec())
+        .unwrap()
+        .replace("\r\n", "\n");
+    let (unix_snippet, _) = snippet_of(unix.as_bytes(), 4, context(1));
+    assert_eq!(unix_snippet.unwrap(), snippet);
+}
+
+#[test]
+fn latin_1_is_decoded_and_marked_lossy() {
+    let source = b"/* Fran\xe7ois's d\xe9coder */\nint decode(void);\n";
+    let (snippet, _) = snippet_of(source, 2, context(1));
+    let snippet = snippet.unwrap();
+    assert_eq!(
+        snippet.lines,
+        ["/* François's décoder */", "int decode(void);"]
+    );
+    assert_eq!(snippet.encoding, SourceEncoding::Latin1);
+    assert!(snippet.lossy);
+
+    // A line which isn't UTF-8 outside of the snippet doesn't matter
+    let (snippet, _) = snippet_of(source, 2, context(0));
+    let snippet = snippet.unwrap();
+    assert_eq!(snippet.lines, ["int decode(void);"]);
+    assert_eq!(snippet.encoding, SourceEncoding::Utf8);
+    assert!(!snippet.lossy);
+}
+
+#[test]
+fn files_with_byte_order_marks_are_decoded() {
+    let text = "fn café() {}\r\nfn main() {}\r\n";
+    let mut utf8 = b"\xef\xbb\xbf".to_vec();
+    utf8.extend_from_slice(text.as_bytes());
+    let mut utf16_le = b"\xff\xfe".to_vec();
+    utf16_le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let mut utf16_be = b"\xfe\xff".to_vec();
+    utf16_be.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+    for (source, encoding) in [
+        (utf8, SourceEncoding::Utf8),
+        (utf16_le, SourceEncoding::Utf16Le),
+        (utf16_be, SourceEncoding::Utf16Be),
+    ] {
+        let (snippet, _) = snippet_of(&source, 1, context(1));
+        let snippet = snippet.unwrap();
+        assert_eq!(
+            snippet.lines,
+            ["fn café() {}", "fn main() {}"],
+            "{encoding:?}"
+        );
+        assert_eq!(snippet.encoding, encoding);
+        assert!(!snippet.lossy, "{encoding:?}");
+    }
+
+    // An unpaired surrogate is replaced
+    let mut source = b"\xff\xfe".to_vec();
+    source.extend(
+        [0xD800u16, u16::from(b'x')]
+            .into_iter()
+            .flat_map(u16::to_le_bytes),
+    );
+    let (snippet, _) = snippet_of(&source, 1, context(0));
+    let snippet = snippet.unwrap();
+    assert_eq!(snippet.lines, ["\u{FFFD}x"]);
+    assert!(snippet.lossy);
+}
+
+#[test]
+fn files_shorter_than_the_line_have_fewer_lines() {
+    let source = b"fn first() {}\nfn second() {}\n";
+    let (snippet, _) = snippet_of(source, 2, context(2));
+    let snippet = snippet.unwrap();
+    assert_eq!(snippet.first_line, 1);
+    assert_eq!(snippet.lines, ["fn first() {}", "fn second() {}"]);
+    assert!(!snippet.truncated);
+
+    let (snippet, _) = snippet_of(source, 10, context(1));
+    let snippet = snippet.unwrap();
+    assert_eq!(snippet.first_line, 9);
+    assert!(snippet.lines.is_empty());
+    assert_eq!(snippet.line(10), None);
+    assert!(!snippet.truncated);
+
+    let (snippet, _) = snippet_of(b"", 1, context(0));
+    assert!(snippet.unwrap().lines.is_empty());
+}
+
+#[test]
+fn only_the_lines_up_to_the_snippet_are_read() {
+    let source = (1..=100_000)
+        .map(|line| format!("const LINE_{line}: u32 = {line};\n"))
+        .collect::<String>();
+    let (snippet, ranges) = snippet_of(source.as_bytes(), 100, context(2));
+    let snippet = snippet.unwrap();
+    assert_eq!(snippet.line(100), Some("const LINE_100: u32 = 100;"));
+    assert_eq!(snippet.lines.len(), 5);
+    // The ranges are read in order, and stop well before the end
+    assert_eq!(ranges[0].start, 0);
+    assert!(
+        ranges.windows(2).all(|it| it[0].end == it[1].start),
+        "{ranges:?}"
+    );
+    let read = ranges.last().unwrap().end;
+    assert!(read < source.len() as u64 / 100, "{read}");
+
+    // Lines beyond the budget aren't read
+    let options = SnippetOptions {
+        context_lines: 2,
+        max_bytes: 64 * 1024,
+    };
+    let (snippet, ranges) = snippet_of(source.as_bytes(), 50_000, options);
+    let snippet = snippet.unwrap();
+    assert!(snippet.lines.is_empty());
+    assert!(snippet.truncated);
+    assert_eq!(ranges.last().unwrap().end, 64 * 1024);
+
+    // Lines which end within the budget are kept, but not the line it ends in
+    let budget = options.max_bytes as usize;
+    let partial = source[..budget].matches('\n').count() as u32 + 1;
+    let (snippet, _) = snippet_of(source.as_bytes(), partial, options);
+    let snippet = snippet.unwrap();
+    assert!(snippet.truncated);
+    assert_eq!(snippet.first_line, partial - 2);
+    assert_eq!(snippet.lines.len(), 2, "{snippet:?}");
+    assert_eq!(
+        snippet.line(partial - 1),
+        Some(format!("const LINE_{0}: u32 = {0};", partial - 1).as_str())
+    );
+}
+
+#[test]
+fn embedded_sources_are_read_when_the_file_isnt() {
+    let mut bundle = Vec::new();
+    let mut writer = SourceBundleWriter::start(Cursor::new(&mut bundle)).unwrap();
+    let mut info = SourceFileInfo::new();
+    info.set_path("/home/me/project/src/greeting.rs".into());
+    let source = "fn greet() {}\r\nfn main() {}\r\n";
+    writer
+        .add_file("src/greeting.rs", source.as_bytes(), info)
+        .unwrap();
+    writer.finish().unwrap();
+    let mut sources = EmbeddedSources::new();
+    sources.add_bytes(bundle).unwrap();
+
+    let site = DeclarationSite {
+        file: "src/greeting.rs".into(),
+        line: 2,
+    };
+    let snippet = sources.read_snippet(&site, context(0)).unwrap();
+    assert_eq!(snippet.lines, ["fn main() {}"]);
+    let missing = DeclarationSite {
+        file: "src/missing.rs".into(),
+        line: 1,
+    };
+    assert_eq!(sources.read_snippet(&missing, context(0)), None);
+}
@@ -0,0 +1,60 @@
+//! Lookups from inside a test harness built by `cargo test`, whose executable
+//! is in `target/debug/deps/` with a hashed name.
+
+use declaration_site::{
+    capability_probe, declaration_by_name, declaration_of, explain_declaration_by_name,
+    ExecutableKind,
+};
+
+#[inline(never)]
+fn helper(x: u32) -> u32 {
+    std::hint::black_box(x).wrapping_mul(3)
+}
+
+mod nested {
+    #[inline(never)]
+    pub fn nested_helper(x: u32) -> u32 {
+        std::hint::black_box(x) ^ 5
+    }
+}
+
+#[test]
+fn capability_probe_reports_test_harness() {
+    let report = capability_probe();
+    assert_eq!(report.executable_kind, ExecutableKind::TestHarness);
+    let executable = report
+        .executable
+        .as_ref()
+        .expect("Should have found the executable");
+    assert!(executable.exists(), "{report}");
+    assert!(report.is_likely_to_work(), "{report}");
+}
+
+#[test]
+fn finds_functions_in_test_harness() {
+    helper(1);
+    let site = declaration_of(&helper).expect("Should have found `helper`");
+    assert!(site.file.ends_with("test_harness.rs"), "{site}");
+    assert_eq!(site.line, 10);
+}
+
+#[test]
+fn finds_functions_by_name_in_test_harness() {
+    nested::nested_helper(1);
+    let name = std::any::type_name_of_val(&nested::nested_helper);
+    assert_eq!(name, "test_harness::nested::nested_helper");
+    let site = declaration_by_name(name).expect("Should have found `nested_helper`");
+    assert!(site.file.ends_with("test_harness.rs"), "{site}");
+    assert_eq!(site.line, 16);
+
+    let choice = explain_declaration_by_name(name).expect("Should have found `nested_helper`");
+    assert_eq!(choice.in_own_crate, Some(true), "{choice}");
+}
+
+#[test]
+fn finds_test_functions() {
+    // Test functions are called through the harness, so are always linked
+    let site = declaration_by_name("test_harness::finds_test_functions")
+        .expect("Should have found this test");
+    assert_eq!(site.line, 55);
+}
@@ -0,0 +1,94 @@
+//! Registering types and looking them up by `TypeId` from several threads at
+//! once.
+
+use std::{
+    any::{Any, TypeId},
+    sync::Barrier,
+    thread,
+};
+
+use declaration_site::{
+    cached_declaration_of, declaration_by_type_id, register_type, register_type_of,
+    registered_type_name,
+};
+
+#[inline(never)]
+fn first() -> u32 {
+    std::hint::black_box(1)
+}
+
+#[inline(never)]
+fn second() -> u32 {
+    std::hint::black_box(2)
+}
+
+#[inline(never)]
+fn third() -> u32 {
+    std::hint::black_box(3)
+}
+
+const THREADS: usize = 8;
+
+#[test]
+fn concurrent_registration_and_lookup() {
+    let functions: [fn() -> u32; 3] = [first, second, third];
+    let ids = [first.type_id(), second.type_id(), third.type_id()];
+    assert_eq!(functions.iter().map(|it| it()).sum::<u32>(), 6);
+
+    let barrier = Barrier::new(THREADS);
+    let sites = thread::scope(|scope| {
+        let threads = (0..THREADS)
+            .map(|index| {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    barrier.wait();
+                    // Each thread registers every function, in a different order
+                    for offset in 0..ids.len() {
+                        match (index + offset) % 3 {
+                            0 => register_type_of(&first),
+                            1 => register_type_of(&second),
+                            _ => register_type_of(&third),
+                        }
+                    }
+                    ids.map(declaration_by_type_id)
+                })
+            })
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|it| it.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    for thread_sites in &sites {
+        assert_eq!(thread_sites, &sites[0]);
+    }
+    let lines = sites[0]
+        .iter()
+        .map(|site| {
+            let site = site.as_ref().expect("Should have found every function");
+            assert!(site.file.ends_with("type_ids.rs"), "{site}");
+            site.line
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [16, 21, 26]);
+
+    // The lookups share the cache of `cached_declaration`
+    let cached = cached_declaration_of(&first).unwrap();
+    assert_eq!(Some(cached), sites[0][0].as_ref());
+    assert_eq!(
+        registered_type_name(ids[1]),
+        Some(std::any::type_name_of_val(&second))
+    );
+}
+
+#[test]
+fn unregistered_types_are_not_found() {
+    struct Unregistered;
+    assert_eq!(registered_type_name(TypeId::of::<Unregistered>()), None);
+    assert_eq!(declaration_by_type_id(TypeId::of::<Unregistered>()), None);
+    register_type::<Unregistered>();
+    assert!(registered_type_name(TypeId::of::<Unregistered>())
+        .is_some_and(|name| name.ends_with("Unregistered")));
+    // Registered, but not a function
+    assert_eq!(declaration_by_type_id(TypeId::of::<Unregistered>()), None);
+}
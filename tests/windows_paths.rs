@@ -0,0 +1,188 @@
+//! Checks that files in deep Windows directories and on network shares are
+//! read using their extended-length paths, and that the `\\?\` prefix isn't
+//! reported, by reading a fixture through a `FileAccess` which only opens
+//! long paths as Windows does.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use declaration_site::{
+    for_functions_in_directory, for_functions_in_file, strip_verbatim_prefix, verbatim_path,
+    with_file_access, DeclarationIndex, DeclarationSite, FileAccess, FileMetadata,
+};
+
+const FIXTURE: &str = "fixtures/big_endian/libbig_endian_fixture.so";
+
+/// Files in memory, which can only be opened by paths shorter than
+/// `MAX_PATH` (260 characters) unless they are in the extended-length form,
+/// and whose directories list their entries with the same prefix as the
+/// directory.
+struct WindowsFiles {
+    files: HashMap<String, Vec<u8>>,
+    opened: Arc<Mutex<Vec<String>>>,
+}
+
+impl WindowsFiles {
+    /// The files `paths`, each a copy of the fixture.
+    fn new(paths: &[&str]) -> (Self, Arc<Mutex<Vec<String>>>) {
+        let data = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE)).unwrap();
+        let files = paths
+            .iter()
+            .map(|path| (verbatim_path(path).into_owned(), data.clone()))
+            .collect();
+        let opened = Arc::new(Mutex::new(Vec::new()));
+        let access = WindowsFiles {
+            files,
+            opened: opened.clone(),
+        };
+        (access, opened)
+    }
+
+    /// The key of the file at `path`, if Windows could open it.
+    fn open(&self, path: &Path) -> io::Result<String> {
+        let path = path.to_str().unwrap();
+        if path.len() >= 260 && !path.starts_with(r"\\?\") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidFilename,
+                "the path is too long",
+            ));
+        }
+        self.opened.lock().unwrap().push(path.to_owned());
+        Ok(verbatim_path(path).into_owned())
+    }
+}
+
+impl FileAccess for WindowsFiles {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let key = self.open(path)?;
+        self.files
+            .get(&key)
+            .cloned()
+            .ok_or(io::ErrorKind::NotFound.into())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let key = self.open(path)?;
+        if let Some(data) = self.files.get(&key) {
+            return Ok(FileMetadata {
+                is_dir: false,
+                len: data.len() as u64,
+            });
+        }
+        let prefix = format!("{key}\\");
+        if self.files.keys().any(|file| file.starts_with(&prefix)) {
+            return Ok(FileMetadata {
+                is_dir: true,
+                len: 0,
+            });
+        }
+        Err(io::ErrorKind::NotFound.into())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let key = self.open(path)?;
+        let verbatim = path.to_str().unwrap().starts_with(r"\\?\");
+        let prefix = format!("{key}\\");
+        Ok(self
+            .files
+            .keys()
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .map(|name| {
+                let entry = format!("{key}\\{name}");
+                match verbatim {
+                    true => entry.into(),
+                    false => strip_verbatim_prefix(&entry).into_owned().into(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// A directory on `root` which is nested deeper than `MAX_PATH`.
+fn deep_directory(root: &str) -> String {
+    let directory = format!(r"{root}\{}", ["nested_directory"; 16].join(r"\"));
+    assert!(directory.len() > 260);
+    directory
+}
+
+fn functions_in_file(path: &str) -> io::Result<usize> {
+    let mut functions = 0;
+    for_functions_in_file(path, |_| functions += 1)?;
+    Ok(functions)
+}
+
+#[test]
+fn long_paths_are_read_with_their_extended_length_form() {
+    let drive = format!(r"{}\libbig_endian_fixture.so", deep_directory(r"C:\build"));
+    let share = format!(
+        r"{}\libbig_endian_fixture.so",
+        deep_directory(r"\\server\share")
+    );
+    let short = r"C:\build\libbig_endian_fixture.so";
+    let (access, opened) = WindowsFiles::new(&[&drive, &share, short]);
+    let functions = with_file_access(access, || {
+        [drive.as_str(), &share, short].map(|path| functions_in_file(path).unwrap())
+    });
+    assert!(functions[0] > 0);
+    assert_eq!(functions, [functions[0]; 3]);
+
+    let opened = opened.lock().unwrap();
+    assert!(opened.contains(&format!(r"\\?\{drive}")));
+    assert!(opened.contains(&format!(r"\\?\UNC\{}", &share[2..])));
+    // Short paths are passed as they are
+    assert!(opened.iter().any(|it| it == short));
+    assert!(opened
+        .iter()
+        .all(|it| it.len() < 260 || it.starts_with(r"\\?\")));
+}
+
+#[test]
+fn directory_entries_are_reported_without_the_prefix() {
+    for root in [r"C:\build", r"\\server\share"] {
+        let directory = deep_directory(root);
+        let file = format!(r"{directory}\libbig_endian_fixture.so");
+        let (access, _) = WindowsFiles::new(&[&file]);
+        let mut object_paths = Vec::new();
+        let errors = with_file_access(access, || {
+            for_functions_in_directory(&directory, |function| {
+                object_paths.extend(function.object_path().map(Path::to_owned));
+            })
+        })
+        .unwrap();
+        assert!(errors.is_empty(), "{errors:?}");
+        assert!(!object_paths.is_empty());
+        assert!(object_paths.iter().all(|it| *it == Path::new(&file)));
+    }
+}
+
+#[test]
+fn sites_are_reported_without_the_prefix() {
+    let sites = [
+        (r"\\?\C:\build\src\lib.rs", r"C:\build\src\lib.rs"),
+        (
+            r"\\?\UNC\server\share\src\lib.rs",
+            r"\\server\share\src\lib.rs",
+        ),
+        ("/build/src/lib.rs", "/build/src/lib.rs"),
+    ];
+    let index = sites
+        .iter()
+        .enumerate()
+        .map(|(line, (file, _))| {
+            let site = DeclarationSite {
+                file: (*file).into(),
+                line: line as u32 + 1,
+            };
+            (format!("my_crate::function_{line}"), site)
+        })
+        .collect::<DeclarationIndex>();
+    for (line, (verbatim, reported)) in sites.iter().enumerate() {
+        let site = index.get(&format!("my_crate::function_{line}")).unwrap();
+        assert_eq!(site.file, *reported);
+        assert_eq!(site.verbatim_file(), *verbatim);
+    }
+}